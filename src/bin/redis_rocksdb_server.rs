@@ -0,0 +1,51 @@
+use std::{net::TcpListener, sync::Arc, time::Duration};
+
+use redis_rocksdb::{server::Server, ExpireSweeper, NotifyEvents, RedisRocksdbBuilder};
+
+const USAGE: &str = "usage: redis_rocksdb_server [--bind 127.0.0.1] [--port 6379] [--dir redis_rocksdb.db] [--column-families] [--notify-keyspace-events KEA]";
+
+/// 兼容redis的服务，可以使用redis-cli等客户端访问
+fn main() -> anyhow::Result<()> {
+    let mut bind = "127.0.0.1".to_owned();
+    let mut port = 6379u16;
+    let mut dir = "redis_rocksdb.db".to_owned();
+    let mut notify_events = NotifyEvents::default();
+    let mut column_families = false;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let value = match arg.as_str() {
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return Ok(());
+            }
+            //只能在新建数据库时使用，参见[redis_rocksdb::ColumnFamilies]
+            "--column-families" => {
+                column_families = true;
+                continue;
+            }
+            _ => args.next().ok_or_else(|| anyhow::anyhow!("missing value for {}\n{}", arg, USAGE))?,
+        };
+        match arg.as_str() {
+            "--bind" => bind = value,
+            "--port" => port = value.parse()?,
+            "--dir" => dir = value,
+            "--notify-keyspace-events" => notify_events = NotifyEvents::parse(value.as_bytes())?,
+            _ => anyhow::bail!("unknown option {}\n{}", arg, USAGE),
+        }
+    }
+
+    //旧版本写入的数据库，先转换key的格式，没有类型记录的数据库不能自动转换，返回错误
+    let db = Arc::new(
+        RedisRocksdbBuilder::new(&dir)
+            .column_families(column_families)
+            .migrate_key_layout(true)
+            .build()?,
+    );
+    db.set_notify_events(notify_events);
+    let _sweeper = ExpireSweeper::start(&db, Duration::from_millis(100));
+
+    let listener = TcpListener::bind((bind.as_str(), port))?;
+    println!("redis_rocksdb server listening on {}, data in {}", listener.local_addr()?, dir);
+    Arc::new(Server::new(db)).serve(listener)?;
+    Ok(())
+}
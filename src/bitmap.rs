@@ -0,0 +1,79 @@
+use crate::{LenType, RrError};
+
+/// bitcount与bitpos的范围单位
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BitUnit {
+    #[default]
+    Byte,
+    Bit,
+}
+
+/// bitcount与bitpos的范围，与redis一样包含start与end，负数从尾部开始计算
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct BitRange {
+    pub start: i64,
+    /// None表示到最后，bitpos查找0时，没有指定end才会返回字符串之后的第一个位置
+    pub end: Option<i64>,
+    pub unit: BitUnit,
+}
+
+/// bitop的运算
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BitOp {
+    And,
+    Or,
+    Xor,
+    /// 只能有一个key
+    Not,
+}
+
+/// bitfield的 OVERFLOW WRAP|SAT|FAIL，只影响之后的SET与INCRBY
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BitOverflow {
+    #[default]
+    Wrap,
+    /// 饱和，取最大或最小值
+    Sat,
+    /// 不修改，返回None
+    Fail,
+}
+
+/// bitfield的类型，如i8、u16，有符号时bits最大为64，无符号时最大为63
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BitFieldType {
+    pub signed: bool,
+    pub bits: u8,
+}
+
+/// bitfield的子命令，offset的单位是bit
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BitFieldOp {
+    Get(BitFieldType, u64),
+    /// 返回原来的值
+    Set(BitFieldType, u64, i64),
+    /// 返回新的值
+    IncrBy(BitFieldType, u64, i64),
+    Overflow(BitOverflow),
+}
+
+/// 对应redis的位图，位图就是字符串，get等字符串的方法也可以读取
+/// 修改时把字符串分成多个块存放，修改一个bit只需要重写它所在的块
+pub trait Bitmap<T> {
+    /// 返回原来的值，超出字符串长度时用0填充
+    /// 对应redis的setbit
+    fn set_bit(&self, t: &T, key: &[u8], offset: u64, value: bool) -> Result<bool, RrError>;
+    /// 对应redis的getbit
+    fn get_bit(&self, t: &T, key: &[u8], offset: u64) -> Result<bool, RrError>;
+    /// range为None时统计整个字符串
+    /// 对应redis的bitcount
+    fn bit_count(&self, t: &T, key: &[u8], range: Option<&BitRange>) -> Result<LenType, RrError>;
+    /// 返回第一个值为bit的位置，没有时返回-1
+    /// 对应redis的bitpos
+    fn bit_pos(&self, t: &T, key: &[u8], bit: bool, range: Option<&BitRange>) -> Result<i64, RrError>;
+    /// 结果存入dst（覆盖原来的key），短的字符串用0补齐，返回结果的长度，结果为空时删除dst
+    /// 对应redis的bitop
+    fn bit_op(&self, t: &T, op: BitOp, dst: &[u8], keys: &[&[u8]]) -> Result<LenType, RrError>;
+    /// 按顺序执行，每一个GET/SET/INCRBY返回一个值，OVERFLOW FAIL时返回None
+    /// 对应redis的bitfield
+    fn bit_field(&self, t: &T, key: &[u8], ops: &[BitFieldOp]) -> Result<Vec<Option<i64>>, RrError>;
+}
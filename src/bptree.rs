@@ -1,3 +1,5 @@
+use std::ops::Bound;
+
 use crate::{LenType, RrError};
 
 /// b+ tree(binary plus tree)
@@ -22,6 +24,10 @@ pub trait BPTree<T> {
     fn get_last(&self, t: &T, key: &[u8]) -> Result<Option<(Vec<u8>, Vec<u8>)>, RrError>;
     fn get(&self, t: &T, key: &[u8], field: &[u8]) -> Result<Option<Vec<u8>>, RrError>;
     fn len(&self, t: &T, key: &[u8]) -> Result<Option<LenType>, RrError>;
+    /// 按字段的顺序返回 start 到 end 范围内的字段与值，rev为true时从end开始从大到小返回
+    /// limit为None时返回所有。分页读取时，把上一页的最后一个字段作为 Bound::Excluded 传入（rev时为end）就可以接着读取
+    /// 返回值 0: field, 1: field value
+    fn range(&self, t: &T, key: &[u8], start: Bound<&[u8]>, end: Bound<&[u8]>, rev: bool, limit: Option<LenType>) -> Result<Vec<(Vec<u8>, Vec<u8>)>, RrError>;
 
     /// 删除指定的key，及所有字段
     fn del_key(&self, t: &T, key: &[u8]) -> Result<(), RrError>;
@@ -1,4 +1,4 @@
 pub use vec_bytes::*;
 
-/// length of data is variable, zero copy, insert, binary search, persistence
+/// length of data is variable, insert, binary search, persistence
 mod vec_bytes;
@@ -1,298 +1,159 @@
-use std::{
-    marker::PhantomData,
-    mem,
-    mem::{size_of, ManuallyDrop},
-    ptr,
-};
-
-use crate::{read_int_ptr, write_int_ptr, BytesType, LenType, MetaKey};
-
-#[derive(Debug, Clone)]
-pub struct BytesMeta {
-    offset: i64, //这个值由于要持久化，所以不能使用isize
+use std::mem::size_of;
+
+use crate::{read_int, write_int, BytesType, LenType};
+
+/// 有序的变长bytes数组，持久化的格式如下
+/// number_keys(LenType) bytes_data(BytesType) metas_capacity(u64) metas(i64 * number_keys) keys
+/// 每个key为 len(BytesType) key len(BytesType)，前后都有长度，方便从后向前遍历
+/// meta为key相对于第一个key的偏移量，不用解析所有的key就可以二分查找
+/// 在内存中直接使用 Vec<Vec<u8>>，只在读写数据库时转换，这样修改时不需要移动整块内存
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct VecBytes {
+    keys: Vec<Vec<u8>>,
 }
 
-impl BytesMeta {
-    pub fn new(offset: isize) -> Self {
-        Self { offset: offset as i64 }
-    }
-    pub fn to_isize(&self) -> isize {
-        self.offset as isize
-    }
-    pub fn set_offset(&mut self, offset: isize) {
-        self.offset = offset as i64;
-    }
-}
+impl VecBytes {
+    pub const OFFSET_DATA: usize = size_of::<LenType>() + size_of::<BytesType>();
+    pub const OFFSET_META: usize = Self::OFFSET_DATA + size_of::<u64>();
+    const LEN_META: usize = size_of::<i64>();
+    const LEN_BYTES: usize = size_of::<BytesType>();
 
-impl From<isize> for BytesMeta {
-    fn from(value: isize) -> Self {
-        BytesMeta::new(value)
+    pub fn new() -> Self {
+        Self { keys: Vec::new() }
     }
-}
 
-impl From<usize> for BytesMeta {
-    fn from(value: usize) -> Self {
-        BytesMeta::new(value as isize)
+    pub fn len(&self) -> usize {
+        self.keys.len()
     }
-}
-
-/// 前面存放meta信息，所key的offset(相对于第一个key的位置，这样当增加meta时，原meta中的offset不用修改)，key
-/// 这样设计是为了使用二分查找，因为key长度不固定
-#[derive(Clone, Debug)]
-pub struct VecBytes<T: Metas> {
-    pub number_keys: LenType,
-    /// 单位是 byte, 所有keys合起来的，包含前后的 BytesType
-    pub bytes_data: BytesType,
-    /// 为meta数据分配的容量
-    pub metas_capacity: u64,
-
-    //在整个数据中的offset
-    pub offset: isize,
 
-    _mark: PhantomData<T>,
-}
-
-impl<T: Metas> Default for VecBytes<T> {
-    fn default() -> Self {
-        Self::new()
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
     }
-}
 
-impl<T: Metas> VecBytes<T> {
-    pub const OFFSET_DATA: isize = (size_of::<LenType>() + size_of::<BytesType>()) as isize;
-    pub const OFFSET_META: isize = Self::OFFSET_DATA + size_of::<u64>() as isize;
-    ///一次分配大小，这样不用每增加一个元素就分配一次
-    pub const ONE_EXPAND: isize = 64 * (size_of::<BytesMeta>() as isize);
-
-    pub fn new() -> Self {
-        // Vec::new()
-        // core::marker::PhantomData
-        Self {
-            number_keys: 0,
-            bytes_data: 0,
-            metas_capacity: 0,
-            offset: 0,
-            _mark: PhantomData,
-        }
+    pub fn get(&self, index: usize) -> Option<&[u8]> {
+        self.keys.get(index).map(|k| k.as_slice())
     }
 
-    /// keys已排序
-    pub fn add(data: &mut Vec<u8>, offset: isize, keys_: &[&[u8]]) -> Self {
-        let mut keys = VecBytes::new();
-        keys.number_keys = keys_.len() as LenType;
-        keys.offset = offset;
-        if keys.number_keys as usize * size_of::<BytesMeta>() >= keys.metas_capacity as usize {
-            keys.expand(data);
-        }
-
-        for index in 0..keys_.len() {
-            keys.insert_with_index(data, keys_[index], Some(index));
-        }
-        keys
+    pub fn first(&self) -> Option<&[u8]> {
+        self.keys.first().map(|k| k.as_slice())
     }
 
-    pub fn read_from(&mut self, data: &[u8], offset: isize) {
-        self.offset = offset;
-        unsafe {
-            self.number_keys = read_int_ptr(data.as_ptr().offset(self.offset));
-            self.bytes_data = read_int_ptr(data.as_ptr().offset(self.offset + size_of::<LenType>() as isize));
-            self.metas_capacity = read_int_ptr(data.as_ptr().offset(Self::OFFSET_DATA));
-        }
+    pub fn last(&self) -> Option<&[u8]> {
+        self.keys.last().map(|k| k.as_slice())
     }
 
-    /// 在index处插入key,
-    pub fn insert_with_index(&mut self, data: &mut Vec<u8>, key: &[u8], index: Option<usize>) {
-        if self.is_expand() {
-            self.expand(data);
-        }
-        let add_bytes = Self::compute_new_bytes(key);
-        data.reserve_exact(add_bytes as usize);
-
-        let start = self.offset + Self::OFFSET_DATA + self.metas_capacity as isize + self.bytes_data as isize;
-        unsafe {
-            write_int_ptr(data.as_mut_ptr().offset(start), key.len() as BytesType);
-            write_int_ptr(
-                data.as_mut_ptr().offset(start + size_of::<BytesType>() as isize + key.len() as isize),
-                key.len() as BytesType,
-            );
-            ptr::copy(key.as_ptr(), data.as_mut_ptr().offset(start + size_of::<BytesType>() as isize), key.len());
-        }
-
-        {
-            let mut meta = T::new(
-                data,
-                Self::OFFSET_META,
-                self.number_keys,
-                (self.metas_capacity as usize / size_of::<MetaKey>()) as i64,
-            );
-            let index = match index {
-                Some(i) => i,
-                None => match meta.search(data.as_slice(), self.offset + Self::OFFSET_META + self.metas_capacity as isize, key) {
-                    Ok(i) => i,
-                    Err(i) => i,
-                },
-            };
-            meta.insert(index, BytesMeta::from(start))
-        }
-        unsafe {
-            data.set_len(data.len() + add_bytes as usize);
-        }
-        self.set_number_keys(self.number_keys + 1 as LenType, data);
-        self.set_bytes_data(self.bytes_data + add_bytes as BytesType, data);
-    }
-    /// offset keys中node中的偏移量，
-    pub fn inserts(&mut self, data: &mut Vec<u8>, keys: &[&[u8]]) {
-        for key in keys {
-            self.insert_with_index(data, key, None);
-        }
+    pub fn binary_search(&self, key: &[u8]) -> Result<usize, usize> {
+        self.keys.binary_search_by(|k| k.as_slice().cmp(key))
     }
 
-    pub fn set_number_keys(&mut self, number_keys: LenType, data: &mut [u8]) {
-        self.number_keys = number_keys;
-        unsafe {
-            write_int_ptr(data.as_mut_ptr().offset(self.offset), self.number_keys);
-        }
-    }
-    pub fn set_bytes_data(&mut self, bytes_number: BytesType, data: &mut [u8]) {
-        self.bytes_data = bytes_number;
-        unsafe {
-            write_int_ptr(data.as_mut_ptr().offset(self.offset + size_of::<LenType>() as isize), self.bytes_data);
-        }
+    /// 小于key的个数
+    pub fn lower_bound(&self, key: &[u8]) -> usize {
+        self.keys.partition_point(|k| k.as_slice() < key)
     }
 
-    pub fn binary_search(&self, data: &[u8], key: &[u8]) -> Result<usize, usize> {
-        let meta = T::new(
-            data,
-            Self::OFFSET_META,
-            self.number_keys,
-            (self.metas_capacity as usize / size_of::<MetaKey>()) as i64,
-        );
-        meta.search(data, self.offset + Self::OFFSET_META + self.metas_capacity as isize, key)
+    /// 小于等于key的个数，也就是b+ tree中key所在子节点的下标
+    pub fn upper_bound(&self, key: &[u8]) -> usize {
+        self.keys.partition_point(|k| k.as_slice() <= key)
     }
 
-    fn compute_new_bytes(key: &[u8]) -> BytesType {
-        size_of::<BytesType>() as BytesType * 2 + key.len() as BytesType
+    pub fn insert(&mut self, index: usize, key: Vec<u8>) {
+        self.keys.insert(index, key);
     }
 
-    /// 是否需要扩展meta的空间
-    fn is_expand(&self) -> bool {
-        if self.number_keys as usize * size_of::<BytesMeta>() >= self.metas_capacity as usize {
-            return true;
-        }
-        false
-    }
-    fn expand(&mut self, data: &mut Vec<u8>) {
-        let expand_size = Self::ONE_EXPAND;
-        data.reserve_exact(expand_size as usize);
-        unsafe {
-            data.set_len(data.len() + expand_size as usize);
-        }
-        let old_metas_capacity = self.metas_capacity as isize;
-        unsafe {
-            let p_data = data.as_mut_ptr().offset(Self::OFFSET_META + old_metas_capacity);
-            ptr::copy(
-                p_data,
-                p_data.offset(expand_size),
-                data.len() - expand_size as usize - Self::OFFSET_META as usize - old_metas_capacity as usize,
-            );
-            write_int_ptr(
-                data.as_mut_ptr().add(size_of::<LenType>()),
-                old_metas_capacity as LenType + expand_size as LenType,
-            );
-        };
-        self.metas_capacity = old_metas_capacity as u64 + expand_size as u64;
+    pub fn set(&mut self, index: usize, key: Vec<u8>) {
+        self.keys[index] = key;
     }
 
-    fn reduce(&mut self, data: &mut Vec<u8>) {
-        let reduce_size = Self::ONE_EXPAND;
-        let mut temp_fields = Vec::<u8>::with_capacity(data.len() - self.metas_capacity as usize - Self::OFFSET_META as usize);
+    pub fn remove(&mut self, index: usize) -> Vec<u8> {
+        self.keys.remove(index)
+    }
 
-        let mut head_array = unsafe {
-            Vec::from_raw_parts(
-                data.as_mut_ptr().offset(Self::OFFSET_META) as *mut BytesMeta,
-                self.number_keys as usize,
-                self.metas_capacity as usize / mem::size_of::<BytesMeta>(),
-            )
-        };
-        let mut offset = 0;
-        let p_data = unsafe { data.as_ptr().offset(Self::OFFSET_META + self.metas_capacity as isize) };
-        for field_meta in &mut head_array {
-            let start = field_meta.offset as isize;
-            let field_size = unsafe { read_int_ptr::<BytesType>(p_data.offset(start)) };
-            unsafe {
-                ptr::copy_nonoverlapping(
-                    p_data.offset(start),
-                    temp_fields.as_mut_ptr().offset(offset),
-                    field_size as usize + size_of::<BytesType>(),
-                );
-            }
-            field_meta.set_offset(offset);
-            offset += field_size as isize + size_of::<BytesType>() as isize;
-            unsafe {
-                temp_fields.set_len(offset as usize);
-            }
-        }
-        let _ = ManuallyDrop::new(head_array);
-        self.metas_capacity -= reduce_size as u64;
-        unsafe {
-            temp_fields.set_len(offset as usize);
-            write_int_ptr(data.as_mut_ptr().add(size_of::<LenType>()), self.metas_capacity as LenType);
-            ptr::copy_nonoverlapping(
-                temp_fields.as_ptr(),
-                data.as_mut_ptr().offset(Self::OFFSET_META + self.metas_capacity as isize),
-                temp_fields.len(),
-            );
-            data.set_len(Self::OFFSET_META as usize + self.metas_capacity as usize + temp_fields.len());
-        }
+    pub fn push(&mut self, key: Vec<u8>) {
+        self.keys.push(key);
     }
-}
 
-pub trait Metas {
-    fn new(data: &[u8], offset: isize, len: LenType, cap: i64) -> Self;
-    fn insert(&mut self, index: usize, key_meta: BytesMeta);
-    fn search(&self, data: &[u8], offset: isize, key: &[u8]) -> Result<usize, usize>;
-}
+    pub fn pop(&mut self) -> Option<Vec<u8>> {
+        self.keys.pop()
+    }
 
-#[derive(Debug, Clone)]
-pub struct KeyMetas {
-    //这个数据是一个引用
-    data: Vec<BytesMeta>,
-}
+    /// 返回 [at, len) 的部分，自己保留 [0, at)
+    pub fn split_off(&mut self, at: usize) -> Self {
+        Self { keys: self.keys.split_off(at) }
+    }
 
-impl Drop for KeyMetas {
-    fn drop(&mut self) {
-        let data = std::mem::take(&mut self.data);
-        mem::forget(data);
+    pub fn append(&mut self, other: &mut Self) {
+        self.keys.append(&mut other.keys);
     }
-}
 
-impl Metas for KeyMetas {
-    fn new(data: &[u8], offset: isize, len: LenType, cap: i64) -> Self {
-        unsafe {
-            Self {
-                data: Vec::from_raw_parts(data.as_ptr().offset(offset) as *mut _, len as usize, cap as usize),
+    /// 从data的offset处读取，返回值 0: 数据，1: 读取的字节数。如果数据不完整返回None
+    pub fn read_from(data: &[u8], offset: usize) -> Option<(Self, usize)> {
+        let data = data.get(offset..)?;
+        if data.len() < Self::OFFSET_META {
+            return None;
+        }
+        let number_keys: LenType = read_int(data);
+        let bytes_data: BytesType = read_int(&data[size_of::<LenType>()..]);
+        let metas_capacity: u64 = read_int(&data[Self::OFFSET_DATA..]);
+        let start = Self::OFFSET_META.checked_add(usize::try_from(metas_capacity).ok()?)?;
+        let end = start.checked_add(usize::try_from(bytes_data).ok()?)?;
+        let mut bytes = data.get(start..end)?;
+
+        let mut keys = Vec::with_capacity(number_keys as usize);
+        for _ in 0..number_keys {
+            if bytes.len() < Self::LEN_BYTES * 2 {
+                return None;
             }
+            let len = usize::try_from(read_int::<BytesType>(bytes)).ok()?;
+            let key = bytes.get(Self::LEN_BYTES..Self::LEN_BYTES + len)?;
+            keys.push(key.to_vec());
+            bytes = bytes.get(Self::LEN_BYTES * 2 + len..)?;
         }
-    }
-    fn insert(&mut self, index: usize, key_meta: BytesMeta) {
-        self.data.insert(index, key_meta);
+        Some((Self { keys }, end))
     }
 
-    fn search(&self, data: &[u8], offset: isize, key: &[u8]) -> Result<usize, usize> {
-        unsafe {
-            self.data.binary_search_by(|a| {
-                let mut start = offset + a.to_isize();
-                let bytes_ = read_int_ptr::<BytesType>(data.as_ptr().offset(start));
-                start += size_of::<BytesType>() as isize;
-                let o_key = &data[start as usize..start as usize + bytes_ as usize];
-                key.cmp(o_key)
-            })
+    /// 追加到data的尾部
+    pub fn write_to(&self, data: &mut Vec<u8>) {
+        let bytes_data: usize = self.keys.iter().map(|k| k.len() + Self::LEN_BYTES * 2).sum();
+        let metas_capacity = self.keys.len() * Self::LEN_META;
+        let start = data.len();
+        data.resize(start + Self::OFFSET_META + metas_capacity, 0);
+        write_int(&mut data[start..], self.keys.len() as LenType);
+        write_int(&mut data[start + size_of::<LenType>()..], bytes_data as BytesType);
+        write_int(&mut data[start + Self::OFFSET_DATA..], metas_capacity as u64);
+
+        data.reserve(bytes_data);
+        let mut offset = 0;
+        let mut len = [0u8; Self::LEN_BYTES];
+        for (i, key) in self.keys.iter().enumerate() {
+            write_int(&mut data[start + Self::OFFSET_META + i * Self::LEN_META..], offset as i64);
+            write_int(&mut len, key.len() as BytesType);
+            data.extend_from_slice(&len);
+            data.extend_from_slice(key);
+            data.extend_from_slice(&len);
+            offset += key.len() + Self::LEN_BYTES * 2;
         }
     }
 }
 
 #[cfg(test)]
 mod test {
+    use crate::datas::VecBytes;
+
     #[test]
-    fn test_vec() {}
+    fn test_vec() {
+        let mut keys = VecBytes::new();
+        keys.insert(0, b"b".to_vec());
+        keys.insert(0, b"a".to_vec());
+        keys.push(b"ccc".to_vec());
+        assert_eq!(Ok(1), keys.binary_search(b"b"));
+        assert_eq!(Err(3), keys.binary_search(b"d"));
+        assert_eq!(2, keys.upper_bound(b"b"));
+
+        let mut data = vec![9u8; 3];
+        keys.write_to(&mut data);
+        let (read, end) = VecBytes::read_from(&data, 3).expect("");
+        assert_eq!(keys, read);
+        assert_eq!(data.len() - 3, end);
+        assert!(VecBytes::read_from(&data[..data.len() - 1], 3).is_none());
+    }
 }
@@ -3,11 +3,32 @@ use std::{
     fmt::{Display, Formatter},
 };
 
+use rocksdb::ErrorKind;
+
 use crate::RrError::Other;
 
+/// 调用者可以按变体区分错误，不需要匹配字符串
 #[derive(Debug)]
 pub enum RrError {
+    /// 参数等调用者的错误，信息与redis的一样（没有大写前缀时，服务端回复时加上 "ERR "）
     Message(String),
+    /// 不存在，参数为不存在的是什么，如 "key"
+    NotFound(String),
+    /// key的类型与操作不一致
+    WrongType,
+    /// list、stack等的下标超出范围
+    IndexOutOfRange(i64),
+    /// 存放的数据不正确，key为数据所在的rocksdb key（不确定时为空），reason为哪一部分数据
+    Corruption {
+        key: Vec<u8>,
+        reason: String,
+    },
+    /// 事务冲突或死锁（rocksdb的Busy、TryAgain），重试整个事务就可以
+    TransactionConflict,
+    /// 等待锁超时（rocksdb的TimedOut）
+    Timeout,
+    /// rocksdb的其它错误
+    Storage(rocksdb::Error),
     Other(anyhow::Error),
 }
 
@@ -16,23 +37,38 @@ impl RrError {
         RrError::Message(message)
     }
     pub(crate) fn not_find(name: &str) -> RrError {
-        RrError::Message(name.to_owned())
+        RrError::NotFound(name.to_owned())
+    }
+    pub(crate) fn index_out_of_range<I: Into<i64>>(index: I) -> RrError {
+        RrError::IndexOutOfRange(index.into())
     }
-    pub(crate) fn none_error(name: &str) -> RrError {
-        RrError::Message(name.to_owned())
+    pub(crate) fn corruption(key: &[u8], reason: &str) -> RrError {
+        RrError::Corruption {
+            key: key.to_vec(),
+            reason: reason.to_owned(),
+        }
     }
-    pub(crate) fn data_error(name: &str) -> RrError {
-        RrError::Message(name.to_owned())
+    /// 与redis的错误信息一样
+    pub(crate) fn wrong_type() -> RrError {
+        RrError::WrongType
     }
 }
 
 impl Display for RrError {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        // write!(f,"{}", self.0)
         match &self {
             RrError::Message(s) => {
                 write!(f, "RrError: {}", s)
             }
+            RrError::NotFound(name) => write!(f, "no such {}", name),
+            RrError::WrongType => write!(f, "WRONGTYPE Operation against a key holding the wrong kind of value"),
+            RrError::IndexOutOfRange(_) => write!(f, "index out of range"),
+            RrError::Corruption { key, reason } => {
+                write!(f, "corrupted data: {}, key: {:?}", reason, String::from_utf8_lossy(key))
+            }
+            RrError::TransactionConflict => write!(f, "transaction conflict, retry it"),
+            RrError::Timeout => write!(f, "timed out waiting for a lock"),
+            RrError::Storage(e) => write!(f, "{}", e),
             RrError::Other(e) => {
                 write!(f, "{}", e)
             }
@@ -42,10 +78,10 @@ impl Display for RrError {
 
 impl std::error::Error for RrError {
     fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
-        if let RrError::Other(e) = self {
-            e.source()
-        } else {
-            None
+        match self {
+            RrError::Storage(e) => Some(e),
+            RrError::Other(e) => Some(e.as_ref()),
+            _ => None,
         }
     }
 }
@@ -56,22 +92,19 @@ impl From<anyhow::Error> for RrError {
     }
 }
 
-// impl<T> From<T> for RrError where
-//     T: std::error::Error
-// {
-//     fn from(r: T) -> Self {
-//         Other(r)
-//     }
-// }
-
 impl From<rocksdb::Error> for RrError {
     fn from(e: rocksdb::Error) -> Self {
-        RrError::Other(anyhow::Error::from(e))
+        match e.kind() {
+            ErrorKind::Busy | ErrorKind::TryAgain => RrError::TransactionConflict,
+            ErrorKind::TimedOut => RrError::Timeout,
+            _ => RrError::Storage(e),
+        }
     }
 }
 
+/// 从存放的数据中读取定长的字段时出错
 impl From<TryFromSliceError> for RrError {
     fn from(e: TryFromSliceError) -> Self {
-        RrError::Other(anyhow::Error::from(e))
+        RrError::corruption(&[], &e.to_string())
     }
 }
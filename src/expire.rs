@@ -0,0 +1,24 @@
+use crate::{Bytes, RrError};
+
+/// key的过期时间，对所有类型的key都有效（KeyValue, RedisList, Stack, Object, Heap, SortedSet, BPTree）
+/// 过期的key在访问时被删除（惰性删除），没有被访问的由[crate::ExpireSweeper]在后台删除
+/// key与它所有的子key（list的node, zip list, 字段等）在同一个事务中删除
+pub trait KeyExpire {
+    /// 对应redis的expire，seconds <= 0时直接删除key
+    /// 返回false表示key不存在
+    fn expire<K: Bytes>(&self, key: &K, seconds: i64) -> Result<bool, RrError>;
+    /// 对应redis的pexpire，单位为毫秒，参见[KeyExpire::expire]
+    fn pexpire<K: Bytes>(&self, key: &K, milliseconds: i64) -> Result<bool, RrError>;
+    /// 对应redis的expireat，timestamp为unix时间（秒），是过去的时间时直接删除key
+    fn expire_at<K: Bytes>(&self, key: &K, timestamp: i64) -> Result<bool, RrError>;
+    /// 对应redis的pexpireat，timestamp为unix时间（毫秒），参见[KeyExpire::expire_at]
+    fn pexpire_at<K: Bytes>(&self, key: &K, timestamp: i64) -> Result<bool, RrError>;
+    /// 对应redis的ttl，返回剩余的秒数
+    /// key不存在时返回-2，没有过期时间时返回-1
+    fn ttl<K: Bytes>(&self, key: &K) -> Result<i64, RrError>;
+    /// 对应redis的pttl，返回剩余的毫秒数，参见[KeyExpire::ttl]
+    fn pttl<K: Bytes>(&self, key: &K) -> Result<i64, RrError>;
+    /// 对应redis的persist，移除过期时间
+    /// 返回false表示key不存在或没有过期时间
+    fn persist<K: Bytes>(&self, key: &K) -> Result<bool, RrError>;
+}
@@ -0,0 +1,102 @@
+use crate::{LenType, RrError, ZAddOptions};
+
+/// 经度与纬度（度）
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct GeoCoord {
+    pub longitude: f64,
+    pub latitude: f64,
+}
+
+impl GeoCoord {
+    pub fn new(longitude: f64, latitude: f64) -> Self {
+        GeoCoord { longitude, latitude }
+    }
+}
+
+/// 距离的单位，对应redis中的 m | km | mi | ft
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum GeoUnit {
+    #[default]
+    M,
+    Km,
+    Mi,
+    Ft,
+}
+
+impl GeoUnit {
+    /// 一个单位是多少米
+    pub fn meters(&self) -> f64 {
+        match self {
+            GeoUnit::M => 1.0,
+            GeoUnit::Km => 1000.0,
+            GeoUnit::Mi => 1609.34,
+            GeoUnit::Ft => 0.3048,
+        }
+    }
+}
+
+/// geosearch的中心，对应redis中的 FROMMEMBER | FROMLONLAT
+#[derive(Clone, Debug, PartialEq)]
+pub enum GeoFrom {
+    Member(Vec<u8>),
+    Coord(GeoCoord),
+}
+
+/// geosearch的范围（米），对应redis中的 BYRADIUS | BYBOX
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GeoShape {
+    Radius(f64),
+    Box { width: f64, height: f64 },
+}
+
+/// 按距离排序，对应redis中的 ASC | DESC
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GeoOrder {
+    Asc,
+    Desc,
+}
+
+/// geosearch 的选项
+#[derive(Clone, Debug, PartialEq)]
+pub struct GeoSearch {
+    pub from: GeoFrom,
+    pub shape: GeoShape,
+    /// 与redis一样，有count且不是any时默认为Asc
+    pub order: Option<GeoOrder>,
+    pub count: Option<LenType>,
+    /// ANY: 找到count个后就停止，所以不一定是最近的
+    pub any: bool,
+}
+
+/// geosearch的结果
+#[derive(Clone, Debug, PartialEq)]
+pub struct GeoMatch {
+    pub member: Vec<u8>,
+    /// 到中心的距离（米）
+    pub dist: f64,
+    /// 52 bits的geohash，也是sorted set中的score
+    pub hash: u64,
+    pub coord: GeoCoord,
+}
+
+/// 与redis一样，使用[crate::KeyType::SortedSet]存放，score为经纬度编码后的52 bits geohash
+/// 所以geo的key也可以使用sorted set的方法，比如zrem
+/// 经纬度使用geohash格子的中心，与原来的值会有很小的误差
+pub trait Geo<T> {
+    /// 经度为[-180, 180]，纬度为[-85.05112878, 85.05112878]，返回值与[crate::SortedSet::add]一样
+    /// opts中不能有compare
+    /// 对应redis的geoadd
+    fn add(&self, t: &T, key: &[u8], members: &[(GeoCoord, &[u8])], opts: &ZAddOptions) -> Result<LenType, RrError>;
+    /// 返回值与请求的顺序一样
+    /// 对应redis的geopos
+    fn pos(&self, t: &T, key: &[u8], members: &[&[u8]]) -> Result<Vec<Option<GeoCoord>>, RrError>;
+    /// 两个成员的距离（米），有一个不存在时返回None
+    /// 对应redis的geodist
+    fn dist(&self, t: &T, key: &[u8], member1: &[u8], member2: &[u8]) -> Result<Option<f64>, RrError>;
+    /// 11个字符的标准geohash，返回值与请求的顺序一样
+    /// 对应redis的geohash
+    fn hash(&self, t: &T, key: &[u8], members: &[&[u8]]) -> Result<Vec<Option<String>>, RrError>;
+    /// 只遍历中心所在的格子与它周围的8个格子，格子的大小由范围决定
+    /// 对应redis的geosearch
+    fn search(&self, t: &T, key: &[u8], opts: &GeoSearch) -> Result<Vec<GeoMatch>, RrError>;
+}
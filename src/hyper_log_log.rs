@@ -0,0 +1,15 @@
+use crate::{LenType, RrError};
+
+/// 对应redis的HyperLogLog，与redis一样存放为字符串，格式与redis的sparse/dense编码一样，
+/// 所以get得到的值可以set到redis中使用，反之亦然
+pub trait HyperLogLog<T> {
+    /// 有寄存器被修改或创建了key时返回true
+    /// 对应redis的pfadd
+    fn pf_add(&self, t: &T, key: &[u8], elements: &[&[u8]]) -> Result<bool, RrError>;
+    /// 多个key时返回并集的基数，不存在的key被忽略
+    /// 对应redis的pfcount
+    fn pf_count(&self, t: &T, keys: &[&[u8]]) -> Result<LenType, RrError>;
+    /// 把keys与dst（如果存在）合并后存入dst
+    /// 对应redis的pfmerge
+    fn pf_merge(&self, t: &T, dst: &[u8], keys: &[&[u8]]) -> Result<(), RrError>;
+}
@@ -0,0 +1,119 @@
+use crate::{LenType, RrError, ScanBatch, ScanCursor, ScanOptions, WrapDb};
+
+/// 顶层key的类型，每一个key在第一次写入时登记类型
+/// 使用与登记不一样类型的方法访问key时，返回WRONGTYPE错误
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KeyType {
+    /// [crate::KeyValue]
+    String = 1,
+    /// [crate::RedisList]
+    List = 2,
+    /// [crate::Stack]
+    Stack = 3,
+    /// [crate::ObjectImp]
+    Hash = 4,
+    /// [crate::BitObject]
+    BitHash = 5,
+    /// [crate::MaxHeap]
+    MaxHeap = 6,
+    /// [crate::MinHeap]
+    MinHeap = 7,
+    /// [crate::SortedSetImp]
+    SortedSet = 8,
+    /// [crate::BPTreeImpl]
+    BPTree = 9,
+    /// [crate::SetImpl]
+    Set = 10,
+    /// [crate::BitmapImpl]，与[KeyType::String]一样是字符串，按块存放
+    Bitmap = 11,
+    /// [crate::StreamImpl]
+    Stream = 12,
+}
+
+const ALL_KEY_TYPES: [KeyType; 12] = [
+    KeyType::String,
+    KeyType::List,
+    KeyType::Stack,
+    KeyType::Hash,
+    KeyType::BitHash,
+    KeyType::MaxHeap,
+    KeyType::MinHeap,
+    KeyType::SortedSet,
+    KeyType::BPTree,
+    KeyType::Set,
+    KeyType::Bitmap,
+    KeyType::Stream,
+];
+
+impl KeyType {
+    /// 对应redis的type命令返回的名字
+    pub fn name(&self) -> &'static str {
+        match self {
+            KeyType::String => "string",
+            KeyType::List => "list",
+            KeyType::Stack => "stack",
+            KeyType::Hash => "hash",
+            KeyType::BitHash => "bit_hash",
+            KeyType::MaxHeap => "max_heap",
+            KeyType::MinHeap => "min_heap",
+            KeyType::SortedSet => "zset",
+            KeyType::BPTree => "bptree",
+            KeyType::Set => "set",
+            KeyType::Bitmap => "string",
+            KeyType::Stream => "stream",
+        }
+    }
+
+    /// [KeyType::name]的反向，不区分大小写，"string"返回[KeyType::String]
+    pub fn from_name(name: &[u8]) -> Option<KeyType> {
+        ALL_KEY_TYPES.iter().copied().find(|k| k.name().as_bytes().eq_ignore_ascii_case(name))
+    }
+}
+
+impl TryFrom<u8> for KeyType {
+    type Error = RrError;
+
+    fn try_from(v: u8) -> Result<Self, Self::Error> {
+        Ok(match v {
+            1 => KeyType::String,
+            2 => KeyType::List,
+            3 => KeyType::Stack,
+            4 => KeyType::Hash,
+            5 => KeyType::BitHash,
+            6 => KeyType::MaxHeap,
+            7 => KeyType::MinHeap,
+            8 => KeyType::SortedSet,
+            9 => KeyType::BPTree,
+            10 => KeyType::Set,
+            11 => KeyType::Bitmap,
+            12 => KeyType::Stream,
+            _ => return Err(RrError::corruption(&[], "key type")),
+        })
+    }
+}
+
+/// 对所有类型的key都有效的命令，key的类型见[KeyType]
+/// 与redis不同，list等变为空时，key仍然存在，直到被删除
+pub trait KeySpace<T: WrapDb> {
+    /// 对应redis的del，删除key及它所有的子key，返回false表示key不存在
+    fn del(&self, t: &T, key: &[u8]) -> Result<bool, RrError>;
+    /// 删除多个key，返回删除的个数
+    fn dels(&self, t: &T, keys: &[&[u8]]) -> Result<LenType, RrError>;
+    /// 对应redis的unlink，rocksdb的删除只是写入tombstone，数据在compaction时才真正删除，所以与[KeySpace::dels]一样
+    fn unlink(&self, t: &T, keys: &[&[u8]]) -> Result<LenType, RrError>;
+    /// 对应redis的exists，返回存在的key的个数，重复的key会重复计数
+    fn exists(&self, t: &T, keys: &[&[u8]]) -> Result<LenType, RrError>;
+    /// 对应redis的type，key不存在时返回None
+    fn key_type(&self, t: &T, key: &[u8]) -> Result<Option<KeyType>, RrError>;
+    /// 对应redis的rename，new_key存在时先删除它，过期时间也一起移动
+    /// key不存在时返回错误
+    fn rename(&self, t: &T, key: &[u8], new_key: &[u8]) -> Result<(), RrError>;
+    /// 对应redis的renamenx，new_key存在时返回false
+    fn rename_nx(&self, t: &T, key: &[u8], new_key: &[u8]) -> Result<bool, RrError>;
+    /// 对应redis的copy，过期时间也一起复制
+    /// src不存在，或dst存在且replace为false时返回false
+    fn copy(&self, t: &T, src: &[u8], dst: &[u8], replace: bool) -> Result<bool, RrError>;
+    /// 对应redis的scan，从cursor之后开始遍历所有的key，返回下一批的游标，按key的bytes排序
+    /// 可以使用opts.key_type只返回指定类型的key，已过期的key不会返回
+    fn scan(&self, t: &T, cursor: &ScanCursor, opts: &ScanOptions) -> Result<ScanBatch<Vec<u8>>, RrError>;
+}
@@ -1,6 +1,73 @@
-use crate::{Bytes, RrError};
+use crate::{Bytes, LenType, RrError};
 
+/// set与getex的过期时间
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Expiration {
+    /// unix时间（毫秒），EX/PX/EXAT/PXAT都转换为它，是过去的时间时key被删除
+    At(i64),
+    /// KEEPTTL: 保留原来的过期时间
+    KeepTtl,
+    /// PERSIST: 移除过期时间，只用于getex，set没有指定过期时间时总是移除
+    Persist,
+}
+
+/// set 的 NX | XX
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SetCondition {
+    /// 只在key不存在时写入
+    NotExists,
+    /// 只在key存在时写入
+    Exists,
+}
+
+/// set 的选项
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SetOptions {
+    pub condition: Option<SetCondition>,
+    /// None时与redis一样移除原来的过期时间
+    pub expiration: Option<Expiration>,
+    /// GET: 返回原来的值，原来的值不是字符串时返回WRONGTYPE错误
+    pub get: bool,
+}
+
+/// 与[crate::RedisList]一样所有的方法都使用 &self，以便在多个线程中共享同一个实例
+///
+/// 修改的方法先锁定key再读取，所以并发的incr等不会丢失更新，也能像redis一样返回新的值与溢出等错误，
+/// 这是没有使用merge operator的原因
 pub trait KeyValue {
-    fn get<K: Bytes, V: Bytes>(&self, key: &K) -> Result<Option<Vec<u8>>, RrError>;
-    fn put<K: Bytes, V: Bytes>(&mut self, key: &K, value: &V) -> Result<(), RrError>;
+    fn get<K: Bytes>(&self, key: &K) -> Result<Option<Vec<u8>>, RrError>;
+    /// 与redis的set一样，key为其它类型时覆盖它，并清除key的过期时间
+    fn put<K: Bytes, V: Bytes>(&self, key: &K, value: &V) -> Result<(), RrError>;
+    /// 带选项的set，返回是否写入与原来的值（只在options.get为true时返回）
+    fn put_with<K: Bytes, V: Bytes>(&self, key: &K, value: &V, options: &SetOptions) -> Result<(bool, Option<Vec<u8>>), RrError>;
+    /// 对应redis的setnx，返回false表示key已存在
+    fn put_not_exist<K: Bytes, V: Bytes>(&self, key: &K, value: &V) -> Result<bool, RrError>;
+    /// 对应redis的getset，返回原来的值
+    fn get_set<K: Bytes, V: Bytes>(&self, key: &K, value: &V) -> Result<Option<Vec<u8>>, RrError>;
+    /// 对应redis的getdel，返回删除的值
+    fn get_del<K: Bytes>(&self, key: &K) -> Result<Option<Vec<u8>>, RrError>;
+    /// 对应redis的getex，key存在时按expiration修改过期时间，None时与get一样
+    fn get_ex<K: Bytes>(&self, key: &K, expiration: Option<Expiration>) -> Result<Option<Vec<u8>>, RrError>;
+    /// 对应redis的incrby，key不存在时当作0，返回新的值，保留原来的过期时间
+    /// 值不是整数或溢出时返回错误
+    fn incr_by<K: Bytes>(&self, key: &K, increment: i64) -> Result<i64, RrError>;
+    /// 对应redis的decrby，参见[KeyValue::incr_by]
+    fn decr_by<K: Bytes>(&self, key: &K, decrement: i64) -> Result<i64, RrError>;
+    /// 对应redis的incrbyfloat，结果为NaN或Infinity时返回错误
+    fn incr_by_float<K: Bytes>(&self, key: &K, increment: f64) -> Result<f64, RrError>;
+    /// 对应redis的append，返回新的长度
+    fn append<K: Bytes, V: Bytes>(&self, key: &K, value: &V) -> Result<LenType, RrError>;
+    /// 对应redis的strlen，key不存在时返回0
+    fn str_len<K: Bytes>(&self, key: &K) -> Result<LenType, RrError>;
+    /// 对应redis的getrange，start与end都包含，负数从尾部开始计算
+    fn get_range<K: Bytes>(&self, key: &K, start: i64, end: i64) -> Result<Vec<u8>, RrError>;
+    /// 对应redis的setrange，从offset开始覆盖，不够长时用0填充，返回新的长度
+    /// 与redis一样，结果最大为512MB
+    fn set_range<K: Bytes, V: Bytes>(&self, key: &K, offset: LenType, value: &V) -> Result<LenType, RrError>;
+    /// 对应redis的mget，不存在或不是字符串的key返回None
+    fn mget<K: Bytes>(&self, keys: &[K]) -> Result<Vec<Option<Vec<u8>>>, RrError>;
+    /// 对应redis的mset，在一个事务中写入
+    fn mset<K: Bytes, V: Bytes>(&self, items: &[(K, V)]) -> Result<(), RrError>;
+    /// 对应redis的msetnx，只要有一个key存在就都不写入，返回false
+    fn mset_not_exist<K: Bytes, V: Bytes>(&self, items: &[(K, V)]) -> Result<bool, RrError>;
 }
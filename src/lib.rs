@@ -1,26 +1,46 @@
 pub extern crate rust_rocksdb as rocksdb;
 
+pub use bitmap::*;
 pub use bptree::*;
 pub use error::RrError;
+pub use expire::*;
+pub use geo::*;
 pub use heap::*;
+pub use hyper_log_log::*;
+pub use key_space::*;
 pub use key_value::*;
 pub use list::*;
 pub use object::*;
+pub use pub_sub::*;
 pub use rocksdb_impl::*;
+pub use scan::*;
+pub use set::*;
 pub use sorted_set::*;
 pub use stack::*;
+pub use stream::*;
 pub use types::*;
 pub use wrap_db::*;
 
+mod bitmap;
 mod bptree;
 mod datas;
 mod error;
+mod expire;
+mod geo;
 mod heap;
+mod hyper_log_log;
+mod key_space;
 mod key_value;
 mod list;
 mod object;
+mod pub_sub;
+pub mod resp;
 mod rocksdb_impl;
+mod scan;
+pub mod server;
+mod set;
 mod sorted_set;
 mod stack;
+mod stream;
 mod types;
 mod wrap_db;
@@ -1,35 +1,42 @@
 use crate::{Bytes, LenType, RrError};
 
+/// 所有的方法都使用 &self，以便在多个线程中共享同一个实例，比如把list作为线程间的工作队列
+/// 每一个方法在自己的事务中执行，要与其它的修改一起提交时使用[List]
 pub trait RedisList {
-    fn list_blpop<K: Bytes, V: Bytes>(&mut self, key: &K, timeout: i64) -> Result<V, RrError>;
-    fn list_brpop<K: Bytes, V: Bytes>(&mut self, key: &K, timeout: i64) -> Result<V, RrError>;
-    fn list_brpoplpush<K: Bytes, V: Bytes>(&mut self, srckey: &K, dstkey: &K, timeout: i64) -> Result<V, RrError>;
+    /// 对应redis的blpop，list为空时阻塞，直到有其它调用push数据或超时
+    /// timeout的单位为秒，0或者大到超出时间范围时表示一直等待，超时返回None
+    /// 多个等待者阻塞在同一个key上时，按到达的先后顺序取得数据
+    fn list_blpop<K: Bytes>(&self, key: &K, timeout: i64) -> Result<Option<Vec<u8>>, RrError>;
+    /// 对应redis的brpop，参数及返回值参见[RedisList::list_blpop]
+    fn list_brpop<K: Bytes>(&self, key: &K, timeout: i64) -> Result<Option<Vec<u8>>, RrError>;
+    /// 对应redis的brpoplpush，阻塞版本的[RedisList::list_replace_back]，参数及返回值参见[RedisList::list_blpop]
+    fn list_brpoplpush<K: Bytes>(&self, srckey: &K, dstkey: &K, timeout: i64) -> Result<Option<Vec<u8>>, RrError>;
     fn list_index<K: Bytes>(&self, key: &K, index: i32) -> Result<Vec<u8>, RrError>;
 
     /// 如果命令执行成功，返回插入操作完成之后，列表的长度。
     /// 如果没有找到指定元素 ，返回 -1 。
     /// 如果 key 不存在或为空列表，返回 0
-    fn list_insert_before<K: Bytes, V: Bytes>(&mut self, key: &K, pivot: &V, value: &V) -> Result<i32, RrError>;
+    fn list_insert_before<K: Bytes, V: Bytes>(&self, key: &K, pivot: &V, value: &V) -> Result<i32, RrError>;
     /// 如果命令执行成功，返回插入操作完成之后，列表的长度。
     /// 如果没有找到指定元素 ，返回 -1 。
     /// 如果 key 不存在或为空列表，返回 0
-    fn list_insert_after<K: Bytes, V: Bytes>(&mut self, key: &K, pivot: &V, value: &V) -> Result<i32, RrError>;
+    fn list_insert_after<K: Bytes, V: Bytes>(&self, key: &K, pivot: &V, value: &V) -> Result<i32, RrError>;
 
     // 返回值为-1表示还没有这个list
     fn list_len<K: Bytes>(&self, key: &K) -> Result<i32, RrError>;
 
     /// 对应redis的 lpop，由于lpop命名，不是很明确，所以改名
-    fn list_pop_front<K: Bytes>(&mut self, key: &K) -> Result<Option<Vec<u8>>, RrError>;
+    fn list_pop_front<K: Bytes>(&self, key: &K) -> Result<Option<Vec<u8>>, RrError>;
     /// 返回len of list
     /// 对应redis的lpush，由于lpush命名，不是很明确，所以改名
-    fn list_push_front<K: Bytes, V: Bytes>(&mut self, key: &K, value: &V) -> Result<i32, RrError>;
+    fn list_push_front<K: Bytes, V: Bytes>(&self, key: &K, value: &V) -> Result<i32, RrError>;
 
     // /// 返回len of list
-    // fn lpushs<K: Bytes, V: Bytes>(&mut self, key: &K, values: &[&V]) -> Result<i32, RrError>;
+    // fn lpushs<K: Bytes, V: Bytes>(&self, key: &K, values: &[&V]) -> Result<i32, RrError>;
 
     /// 返回len of list，如果list不存在返回值为 0
     /// 对应redis的lpushx
-    fn list_push_front_exists<K: Bytes, V: Bytes>(&mut self, key: &K, value: &V) -> Result<i32, RrError>;
+    fn list_push_front_exists<K: Bytes, V: Bytes>(&self, key: &K, value: &V) -> Result<i32, RrError>;
     /// 返回在range范围内的元素，所以start与stop可能会在list的下标之外。range是包含stop的
     /// 如果一个都没有找到，返回为len为0的Vec
     /// 0表示第一个元素
@@ -41,26 +48,51 @@ pub trait RedisList {
     /// count > 0 : 从表头开始向表尾搜索，移除与 VALUE 相等的元素，数量为 COUNT。
     /// count < 0 : 从表尾开始向表头搜索，移除与 VALUE 相等的元素，数量为 COUNT 的绝对值。
     /// count = 0 : 移除表中所有与 VALUE 相等的值
-    fn list_rem<K: Bytes, V: Bytes>(&mut self, key: &K, count: i32, value: &V) -> Result<LenType, RrError>;
+    fn list_rem<K: Bytes, V: Bytes>(&self, key: &K, count: i32, value: &V) -> Result<LenType, RrError>;
     /// 保留指定区间内的元素，不在指定区间之内的元素都将被删除, 反回删除的元素数量
-    fn list_trim<K: Bytes>(&mut self, key: K, start: i32, stop: i32) -> Result<i32, RrError>;
+    /// start与stop的含义与[RedisList::list_range]一样，区间为空时删除整个list
+    fn list_trim<K: Bytes>(&self, key: &K, start: i32, stop: i32) -> Result<i32, RrError>;
 
     /// index无效或list为空时，返回错误。其余返回原来的值
-    fn list_set<K: Bytes, V: Bytes>(&mut self, key: &K, index: i32, value: &V) -> Result<Vec<u8>, RrError>;
+    fn list_set<K: Bytes, V: Bytes>(&self, key: &K, index: i32, value: &V) -> Result<Vec<u8>, RrError>;
     /// 移除列表的最后一个元素
-    fn list_pop_back<K: Bytes>(&mut self, key: &K) -> Result<Option<Vec<u8>>, RrError>;
-    /// 移除列表的最后一个元素，并将该元素添加到另一个列表并返回
+    fn list_pop_back<K: Bytes>(&self, key: &K) -> Result<Option<Vec<u8>>, RrError>;
+    /// 移除列表的最后一个元素，并将该元素添加到另一个列表的头部并返回，如果list为空返回None
     /// 对应redis的rpoplpush
-    fn list_replace_back<K: Bytes, V: Bytes>(&mut self, key: &K, dstkey: &K) -> Result<V, RrError>;
+    fn list_replace_back<K: Bytes>(&self, key: &K, dstkey: &K) -> Result<Option<Vec<u8>>, RrError>;
     /// 返回len of list
     /// 对应redis的rpush
-    fn list_push_back<K: Bytes, V: Bytes>(&mut self, key: &K, value: &V) -> Result<i32, RrError>;
+    fn list_push_back<K: Bytes, V: Bytes>(&self, key: &K, value: &V) -> Result<i32, RrError>;
     // /// 返回len of list
-    // fn rpushs<K: Bytes, V: Bytes>(&mut self, key: &K, value: &[&V]) -> Result<i32, RrError>;
+    // fn rpushs<K: Bytes, V: Bytes>(&self, key: &K, value: &[&V]) -> Result<i32, RrError>;
     /// 为已经存在的列表添加值， 添加到尾部
     /// 对应redis的rpushx
-    fn list_push_back_exists<K: Bytes, V: Bytes>(&mut self, key: &K, value: &V) -> Result<i32, RrError>;
+    fn list_push_back_exists<K: Bytes, V: Bytes>(&self, key: &K, value: &V) -> Result<i32, RrError>;
 
     /// 返回len of list
-    fn list_clear<K: Bytes>(&mut self, key: &K) -> Result<i32, RrError>;
+    fn list_clear<K: Bytes>(&self, key: &K) -> Result<i32, RrError>;
+}
+
+/// 与[RedisList]一样的list，但是在调用者的事务（或db）中执行，所以可以与Object等的修改一起提交
+/// 参数与返回值参见[RedisList]中对应的方法，没有阻塞的方法，push也不会唤醒阻塞在[RedisList::list_blpop]上的等待者
+///
+/// 使用[crate::WrapRocksDb]或[crate::WrapTransactionDB]时，一个方法中的多次写入不是原子的
+pub trait List<T> {
+    fn index(&self, t: &T, key: &[u8], index: i32) -> Result<Vec<u8>, RrError>;
+    fn insert_before(&self, t: &T, key: &[u8], pivot: &[u8], value: &[u8]) -> Result<i32, RrError>;
+    fn insert_after(&self, t: &T, key: &[u8], pivot: &[u8], value: &[u8]) -> Result<i32, RrError>;
+    /// 返回值为-1表示还没有这个list
+    fn len(&self, t: &T, key: &[u8]) -> Result<i32, RrError>;
+    fn pop_front(&self, t: &T, key: &[u8]) -> Result<Option<Vec<u8>>, RrError>;
+    fn push_front(&self, t: &T, key: &[u8], value: &[u8]) -> Result<i32, RrError>;
+    fn push_front_exists(&self, t: &T, key: &[u8], value: &[u8]) -> Result<i32, RrError>;
+    fn range(&self, t: &T, key: &[u8], start: i32, stop: i32) -> Result<Vec<Vec<u8>>, RrError>;
+    fn rem(&self, t: &T, key: &[u8], count: i32, value: &[u8]) -> Result<LenType, RrError>;
+    fn trim(&self, t: &T, key: &[u8], start: i32, stop: i32) -> Result<i32, RrError>;
+    fn set(&self, t: &T, key: &[u8], index: i32, value: &[u8]) -> Result<Vec<u8>, RrError>;
+    fn pop_back(&self, t: &T, key: &[u8]) -> Result<Option<Vec<u8>>, RrError>;
+    fn replace_back(&self, t: &T, key: &[u8], dstkey: &[u8]) -> Result<Option<Vec<u8>>, RrError>;
+    fn push_back(&self, t: &T, key: &[u8], value: &[u8]) -> Result<i32, RrError>;
+    fn push_back_exists(&self, t: &T, key: &[u8], value: &[u8]) -> Result<i32, RrError>;
+    fn clear(&self, t: &T, key: &[u8]) -> Result<i32, RrError>;
 }
@@ -1,4 +1,7 @@
-use crate::{LenType, RrError};
+use crate::{LenType, RrError, ScanBatch, ScanCursor, ScanOptions};
+
+/// 字段与值
+pub type FieldValue = (Vec<u8>, Vec<u8>);
 
 /// 可以存储大量的数据，在遍历数据时，性能不如redis hash
 pub trait Object<T> {
@@ -28,6 +31,10 @@ pub trait Object<T> {
     /// 一个包含哈希表中所有值的列表。 当 key 不存在时，返回一个空表
     fn vals(&self, t: &T, key: &[u8]) -> Result<Vec<Vec<u8>>, RrError>;
 
+    /// 从cursor之后开始遍历字段与值，返回下一批的游标，按字段的bytes排序
+    /// 对应redis的hscan，不会一次读取所有的字段
+    fn scan(&self, t: &T, key: &[u8], cursor: &ScanCursor, opts: &ScanOptions) -> Result<ScanBatch<FieldValue>, RrError>;
+
     /// 删除指定的key，及所有字段
     fn del_key(&self, t: &T, key: &[u8]) -> Result<(), RrError>;
 }
@@ -0,0 +1,103 @@
+use std::{
+    fmt::{Display, Formatter},
+    ops::BitOr,
+};
+
+use crate::RrError;
+
+/// 收到的消息，pattern为Some时是psubscribe收到的（对应redis的pmessage）
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PubSubMessage {
+    pub pattern: Option<Vec<u8>>,
+    pub channel: Vec<u8>,
+    pub payload: Vec<u8>,
+}
+
+/// 发送哪些keyspace/keyevent通知，对应redis的notify-keyspace-events，默认为空，不发送任何通知
+///
+/// K: keyspace通知，channel为 `__keyspace@<db>__:<key>`，消息为事件名
+/// E: keyevent通知，channel为 `__keyevent@<db>__:<event>`，消息为key
+/// g: del、expire、persist等与类型无关的事件，$: string，l: list，h: hash，x: 过期删除（expired）
+/// p: heap（redis中没有），A: g$lhxp 的别名
+///
+/// K与E至少要有一个，并且至少有一个事件的类别，才会发送通知
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct NotifyEvents(u32);
+
+impl NotifyEvents {
+    pub const KEYSPACE: NotifyEvents = NotifyEvents(1);
+    pub const KEYEVENT: NotifyEvents = NotifyEvents(1 << 1);
+    pub const GENERIC: NotifyEvents = NotifyEvents(1 << 2);
+    pub const STRING: NotifyEvents = NotifyEvents(1 << 3);
+    pub const LIST: NotifyEvents = NotifyEvents(1 << 4);
+    pub const HASH: NotifyEvents = NotifyEvents(1 << 5);
+    pub const EXPIRED: NotifyEvents = NotifyEvents(1 << 6);
+    pub const HEAP: NotifyEvents = NotifyEvents(1 << 7);
+    /// 所有事件的类别，不包含K与E
+    pub const ALL: NotifyEvents = NotifyEvents((1 << 2) | (1 << 3) | (1 << 4) | (1 << 5) | (1 << 6) | (1 << 7));
+
+    const FLAGS: [(u8, NotifyEvents); 8] = [
+        (b'K', NotifyEvents::KEYSPACE),
+        (b'E', NotifyEvents::KEYEVENT),
+        (b'g', NotifyEvents::GENERIC),
+        (b'$', NotifyEvents::STRING),
+        (b'l', NotifyEvents::LIST),
+        (b'h', NotifyEvents::HASH),
+        (b'x', NotifyEvents::EXPIRED),
+        (b'p', NotifyEvents::HEAP),
+    ];
+
+    /// 与redis一样的字符，如 "KEA"、"Kl"，空字符串表示不发送通知
+    pub fn parse(flags: &[u8]) -> Result<Self, RrError> {
+        let mut events = NotifyEvents::default();
+        for c in flags {
+            events = events
+                | match *c {
+                    b'A' => NotifyEvents::ALL,
+                    c => NotifyEvents::FLAGS
+                        .iter()
+                        .find(|(f, _)| *f == c)
+                        .map(|(_, e)| *e)
+                        .ok_or_else(|| RrError::message(format!("Invalid event class character '{}'", c as char)))?,
+                };
+        }
+        Ok(events)
+    }
+
+    pub fn contains(&self, other: NotifyEvents) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// 是否会发送class类别的通知
+    pub fn is_enabled(&self, class: NotifyEvents) -> bool {
+        self.0 & class.0 & NotifyEvents::ALL.0 != 0 && self.0 & (NotifyEvents::KEYSPACE.0 | NotifyEvents::KEYEVENT.0) != 0
+    }
+
+    pub fn bits(&self) -> u32 {
+        self.0
+    }
+
+    pub fn from_bits(bits: u32) -> Self {
+        NotifyEvents(bits)
+    }
+}
+
+impl BitOr for NotifyEvents {
+    type Output = NotifyEvents;
+
+    fn bitor(self, rhs: Self) -> Self::Output {
+        NotifyEvents(self.0 | rhs.0)
+    }
+}
+
+/// 与[NotifyEvents::parse]相反
+impl Display for NotifyEvents {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for (c, e) in NotifyEvents::FLAGS {
+            if self.contains(e) {
+                write!(f, "{}", c as char)?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,358 @@
+use std::borrow::Cow;
+
+use crate::{resp::Frame, RrError};
+
+/// 最大的bulk长度，与redis的proto-max-bulk-len默认值一样
+const MAX_BULK_LEN: i64 = 512 * 1024 * 1024;
+/// 最大的元素个数，与redis的multibulk限制一样
+const MAX_ELEMENTS: i64 = 1024 * 1024 * 1024;
+/// 最大的嵌套层数，防止恶意的输入使栈溢出
+const MAX_DEPTH: usize = 128;
+/// 没有CRLF的单行最大长度，超过时认为是错误的输入，而不是没有读完
+const MAX_LINE_LEN: usize = 64 * 1024;
+/// 预分配元素的最大个数，长度是由客户端发送的，不能直接使用
+const MAX_PREALLOCATE: usize = 1024;
+
+enum ParseError {
+    /// 数据不完整，需要读取更多的数据
+    Incomplete,
+    Invalid(String),
+}
+
+type ParseResult<T> = Result<T, ParseError>;
+
+/// 从buf的开始解析一个frame，返回frame与使用的bytes数
+/// 数据不完整时返回None，读取更多数据后要从头再解析，分多次读取时使用[RespReader]
+/// 返回的frame借用buf，不会复制数据
+pub fn decode(buf: &[u8]) -> Result<Option<(Frame<'_>, usize)>, RrError> {
+    let mut parser = Parser { buf, pos: 0 };
+    match parser.frame(0) {
+        Ok(frame) => Ok(Some((frame, parser.pos))),
+        Err(ParseError::Incomplete) => Ok(None),
+        Err(ParseError::Invalid(message)) => Err(RrError::message(format!("Protocol error: {}", message))),
+    }
+}
+
+/// 带缓存的增量解析，把每次从网络读取的数据使用[RespReader::feed]加入，然后读取完整的frame
+/// 保存扫描的状态，每次只扫描新加入的数据，frame完整后才解析一次，所以大的frame分多次读取时不会反复解析
+#[derive(Default)]
+pub struct RespReader {
+    buf: Vec<u8>,
+    /// buf中已经解析完的位置
+    pos: usize,
+    /// 从pos开始的下一个frame的扫描状态
+    scanner: Scanner,
+}
+
+impl RespReader {
+    pub fn new() -> Self {
+        RespReader::default()
+    }
+
+    pub fn feed(&mut self, data: &[u8]) {
+        //解析完的数据超过一半时才移动，减少复制
+        if self.pos > 0 && self.pos * 2 >= self.buf.len() {
+            self.buf.drain(..self.pos);
+            self.pos = 0;
+        }
+        self.buf.extend_from_slice(data);
+        self.scanner.scan(&self.buf[self.pos..]);
+    }
+
+    /// 返回借用缓存的frame与它的长度，处理完后调用[RespReader::consume]
+    pub fn peek(&self) -> Result<Option<(Frame<'_>, usize)>, RrError> {
+        match self.scanner.state {
+            Scanned::Incomplete => Ok(None),
+            Scanned::Frame(len) => decode(&self.buf[self.pos..self.pos + len]),
+            //从头解析得到具体的错误信息
+            Scanned::Invalid => decode(&self.buf[self.pos..]),
+        }
+    }
+
+    pub fn consume(&mut self, len: usize) {
+        self.pos = (self.pos + len).min(self.buf.len());
+        self.scanner = Scanner::default();
+        self.scanner.scan(&self.buf[self.pos..]);
+    }
+
+    /// 返回一个完整的frame，数据不完整时返回None，返回的frame复制了数据
+    pub fn next_frame(&mut self) -> Result<Option<Frame<'static>>, RrError> {
+        match self.peek()? {
+            None => Ok(None),
+            Some((frame, len)) => {
+                let frame = frame.into_owned();
+                self.consume(len);
+                Ok(Some(frame))
+            }
+        }
+    }
+
+    /// 还没有解析的数据，可以自己解析后调用[RespReader::consume]
+    pub fn data(&self) -> &[u8] {
+        &self.buf[self.pos..]
+    }
+
+    /// 还没有解析的数据长度
+    pub fn pending(&self) -> usize {
+        self.buf.len() - self.pos
+    }
+}
+
+#[derive(Default, Clone, Copy)]
+enum Scanned {
+    #[default]
+    Incomplete,
+    /// 完整的frame的长度
+    Frame(usize),
+    /// 数据不正确，不再扫描
+    Invalid,
+}
+
+/// 只检查frame的结构，找到frame的结束位置，不检查内容也不创建frame
+#[derive(Default)]
+struct Scanner {
+    /// 已经扫描过的位置，相对于[RespReader::pos]
+    offset: usize,
+    /// 每一层聚合类型还没有扫描的元素个数
+    remaining: Vec<usize>,
+    /// 已经读取了bulk的长度，等待数据与CRLF
+    bulk: Option<usize>,
+    state: Scanned,
+}
+
+impl Scanner {
+    /// 从上次的位置继续扫描，data为从[RespReader::pos]开始的所有数据
+    fn scan(&mut self, data: &[u8]) {
+        while let Scanned::Incomplete = self.state {
+            match self.step(data) {
+                Ok(()) => {}
+                Err(ParseError::Incomplete) => return,
+                Err(ParseError::Invalid(_)) => self.state = Scanned::Invalid,
+            }
+        }
+    }
+
+    /// 扫描一个元素或者聚合类型的头
+    fn step(&mut self, data: &[u8]) -> ParseResult<()> {
+        if let Some(len) = self.bulk {
+            if data.len() < self.offset + len + 2 {
+                return Err(ParseError::Incomplete);
+            }
+            self.offset += len + 2;
+            self.bulk = None;
+            self.element_done();
+            return Ok(());
+        }
+        if self.remaining.len() > MAX_DEPTH {
+            return Err(invalid("too deep nesting"));
+        }
+        let tag = *data.get(self.offset).ok_or(ParseError::Incomplete)?;
+        let mut parser = Parser {
+            buf: data,
+            pos: self.offset + 1,
+        };
+        match tag {
+            b'$' | b'!' | b'=' => {
+                let len = parser.len(MAX_BULK_LEN)?;
+                self.offset = parser.pos;
+                match len {
+                    None => self.element_done(),
+                    Some(len) => self.bulk = Some(len),
+                }
+            }
+            b'*' => {
+                let len = parser.len(MAX_ELEMENTS)?;
+                self.offset = parser.pos;
+                self.aggregate(len.unwrap_or(0));
+            }
+            b'~' | b'>' | b'%' | b'|' => {
+                let len = parser.len(MAX_ELEMENTS)?.ok_or_else(|| invalid("invalid length"))?;
+                self.offset = parser.pos;
+                self.aggregate(if tag == b'%' || tag == b'|' { len * 2 } else { len });
+            }
+            b'+' | b'-' | b':' | b'_' | b',' | b'#' | b'(' => {
+                parser.line()?;
+                self.offset = parser.pos;
+                self.element_done();
+            }
+            _ => return Err(invalid("unexpected type")),
+        }
+        Ok(())
+    }
+
+    fn aggregate(&mut self, len: usize) {
+        if len == 0 {
+            self.element_done();
+        } else {
+            self.remaining.push(len);
+        }
+    }
+
+    /// 一个元素扫描完了，所在的聚合类型也完整时向上一层
+    fn element_done(&mut self) {
+        loop {
+            match self.remaining.last_mut() {
+                None => {
+                    self.state = Scanned::Frame(self.offset);
+                    return;
+                }
+                Some(left) => {
+                    *left -= 1;
+                    if *left > 0 {
+                        return;
+                    }
+                    self.remaining.pop();
+                }
+            }
+        }
+    }
+}
+
+struct Parser<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn frame(&mut self, depth: usize) -> ParseResult<Frame<'a>> {
+        if depth > MAX_DEPTH {
+            return Err(invalid("too deep nesting"));
+        }
+        let tag = *self.buf.get(self.pos).ok_or(ParseError::Incomplete)?;
+        self.pos += 1;
+        let frame = match tag {
+            b'+' => Frame::Simple(Cow::Borrowed(self.line()?)),
+            b'-' => Frame::Error(Cow::Borrowed(self.line()?)),
+            b':' => Frame::Integer(parse_int(self.line()?)?),
+            b'$' => match self.bulk()? {
+                None => Frame::Null,
+                Some(data) => Frame::Bulk(Cow::Borrowed(data)),
+            },
+            b'*' => match self.len(MAX_ELEMENTS)? {
+                None => Frame::Null,
+                Some(len) => Frame::Array(self.frames(len, depth)?),
+            },
+            b'%' => Frame::Map(self.pairs(depth)?),
+            b'~' => Frame::Set(self.aggregate(depth)?),
+            b'>' => Frame::Push(self.aggregate(depth)?),
+            b'_' => {
+                if !self.line()?.is_empty() {
+                    return Err(invalid("invalid null"));
+                }
+                Frame::Null
+            }
+            b',' => Frame::Double(parse_double(self.line()?)?),
+            b'#' => match self.line()? {
+                b"t" => Frame::Boolean(true),
+                b"f" => Frame::Boolean(false),
+                _ => return Err(invalid("invalid boolean")),
+            },
+            b'(' => {
+                let line = self.line()?;
+                let digits = line.strip_prefix(b"-").or_else(|| line.strip_prefix(b"+")).unwrap_or(line);
+                if digits.is_empty() || !digits.iter().all(u8::is_ascii_digit) {
+                    return Err(invalid("invalid big number"));
+                }
+                Frame::BigNumber(Cow::Borrowed(line))
+            }
+            b'!' => Frame::BulkError(Cow::Borrowed(self.bulk()?.ok_or_else(|| invalid("invalid bulk error"))?)),
+            b'=' => {
+                let data = self.bulk()?.ok_or_else(|| invalid("invalid verbatim string"))?;
+                if data.len() < 4 || data[3] != b':' {
+                    return Err(invalid("invalid verbatim string"));
+                }
+                Frame::Verbatim(Cow::Borrowed(&data[..3]), Cow::Borrowed(&data[4..]))
+            }
+            b'|' => Frame::Attribute(self.pairs(depth)?),
+            _ => return Err(invalid(&format!("unexpected type '{}'", tag as char))),
+        };
+        Ok(frame)
+    }
+
+    /// 返回CRLF之前的内容，并移到CRLF之后
+    fn line(&mut self) -> ParseResult<&'a [u8]> {
+        let rest = &self.buf[self.pos..];
+        match rest.windows(2).position(|w| w == b"\r\n") {
+            None if rest.len() > MAX_LINE_LEN => Err(invalid("too big line")),
+            None => Err(ParseError::Incomplete),
+            Some(end) => {
+                self.pos += end + 2;
+                Ok(&rest[..end])
+            }
+        }
+    }
+
+    /// 长度为-1时返回None
+    fn len(&mut self, max: i64) -> ParseResult<Option<usize>> {
+        match parse_int(self.line()?)? {
+            -1 => Ok(None),
+            len if (0..=max).contains(&len) => Ok(Some(len as usize)),
+            _ => Err(invalid("invalid length")),
+        }
+    }
+
+    fn bulk(&mut self) -> ParseResult<Option<&'a [u8]>> {
+        let len = match self.len(MAX_BULK_LEN)? {
+            None => return Ok(None),
+            Some(len) => len,
+        };
+        let rest = &self.buf[self.pos..];
+        if rest.len() < len + 2 {
+            return Err(ParseError::Incomplete);
+        }
+        if &rest[len..len + 2] != b"\r\n" {
+            return Err(invalid("invalid bulk length"));
+        }
+        self.pos += len + 2;
+        Ok(Some(&rest[..len]))
+    }
+
+    fn frames(&mut self, len: usize, depth: usize) -> ParseResult<Vec<Frame<'a>>> {
+        let mut frames = Vec::with_capacity(len.min(MAX_PREALLOCATE));
+        for _ in 0..len {
+            frames.push(self.frame(depth + 1)?);
+        }
+        Ok(frames)
+    }
+
+    /// RESP3的集合类型没有-1的长度
+    fn aggregate(&mut self, depth: usize) -> ParseResult<Vec<Frame<'a>>> {
+        let len = self.len(MAX_ELEMENTS)?.ok_or_else(|| invalid("invalid length"))?;
+        self.frames(len, depth)
+    }
+
+    fn pairs(&mut self, depth: usize) -> ParseResult<Vec<(Frame<'a>, Frame<'a>)>> {
+        let len = self.len(MAX_ELEMENTS)?.ok_or_else(|| invalid("invalid length"))?;
+        let mut pairs = Vec::with_capacity(len.min(MAX_PREALLOCATE));
+        for _ in 0..len {
+            let k = self.frame(depth + 1)?;
+            let v = self.frame(depth + 1)?;
+            pairs.push((k, v));
+        }
+        Ok(pairs)
+    }
+}
+
+fn invalid(message: &str) -> ParseError {
+    ParseError::Invalid(message.to_owned())
+}
+
+fn parse_int(data: &[u8]) -> ParseResult<i64> {
+    std::str::from_utf8(data)
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .ok_or_else(|| invalid("invalid integer"))
+}
+
+fn parse_double(data: &[u8]) -> ParseResult<f64> {
+    match data {
+        b"inf" | b"+inf" => Ok(f64::INFINITY),
+        b"-inf" => Ok(f64::NEG_INFINITY),
+        b"nan" => Ok(f64::NAN),
+        _ => std::str::from_utf8(data)
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .filter(|d: &f64| d.is_finite())
+            .ok_or_else(|| invalid("invalid double")),
+    }
+}
@@ -0,0 +1,150 @@
+use std::io::Write;
+
+use crate::resp::{Frame, RespVersion};
+
+impl Frame<'_> {
+    /// 编码后加入到out的后面，RESP2时RESP3特有的类型转换为相近的RESP2类型，见[Frame]中各类型的说明
+    pub fn encode(&self, version: RespVersion, out: &mut Vec<u8>) {
+        let resp3 = version == RespVersion::Resp3;
+        match self {
+            Frame::Simple(s) => {
+                //simple string中不能有CRLF，这时使用bulk
+                if has_line_break(s) {
+                    write_bulk(b'$', s, out);
+                } else {
+                    write_line(b'+', s, out);
+                }
+            }
+            Frame::Error(s) => write_error(s, out),
+            Frame::Integer(i) => write_int(b':', *i, out),
+            Frame::Bulk(s) => write_bulk(b'$', s, out),
+            Frame::Null => {
+                if resp3 {
+                    out.extend_from_slice(b"_\r\n");
+                } else {
+                    out.extend_from_slice(b"$-1\r\n");
+                }
+            }
+            Frame::Array(v) => write_frames(b'*', v, version, out),
+            Frame::Map(pairs) => {
+                if resp3 {
+                    write_len(b'%', pairs.len(), out);
+                } else {
+                    write_len(b'*', pairs.len() * 2, out);
+                }
+                for (k, v) in pairs {
+                    k.encode(version, out);
+                    v.encode(version, out);
+                }
+            }
+            Frame::Set(v) => write_frames(if resp3 { b'~' } else { b'*' }, v, version, out),
+            Frame::Push(v) => write_frames(if resp3 { b'>' } else { b'*' }, v, version, out),
+            Frame::Double(d) => {
+                let s = format_double(*d);
+                if resp3 {
+                    write_line(b',', s.as_bytes(), out);
+                } else {
+                    write_bulk(b'$', s.as_bytes(), out);
+                }
+            }
+            Frame::Boolean(b) => {
+                if resp3 {
+                    out.extend_from_slice(if *b { b"#t\r\n" } else { b"#f\r\n" });
+                } else {
+                    write_int(b':', *b as i64, out);
+                }
+            }
+            Frame::BigNumber(s) => {
+                if resp3 {
+                    write_line(b'(', s, out);
+                } else {
+                    write_bulk(b'$', s, out);
+                }
+            }
+            Frame::BulkError(s) => {
+                if resp3 {
+                    write_bulk(b'!', s, out);
+                } else {
+                    write_error(s, out);
+                }
+            }
+            Frame::Verbatim(format, s) => {
+                if resp3 {
+                    write_len(b'=', s.len() + 4, out);
+                    out.extend_from_slice(format);
+                    out.push(b':');
+                    out.extend_from_slice(s);
+                    out.extend_from_slice(b"\r\n");
+                } else {
+                    write_bulk(b'$', s, out);
+                }
+            }
+            Frame::Attribute(pairs) => {
+                if resp3 {
+                    write_len(b'|', pairs.len(), out);
+                    for (k, v) in pairs {
+                        k.encode(version, out);
+                        v.encode(version, out);
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn to_bytes(&self, version: RespVersion) -> Vec<u8> {
+        let mut out = Vec::new();
+        self.encode(version, &mut out);
+        out
+    }
+}
+
+fn has_line_break(s: &[u8]) -> bool {
+    s.iter().any(|b| *b == b'\r' || *b == b'\n')
+}
+
+fn write_line(tag: u8, s: &[u8], out: &mut Vec<u8>) {
+    out.push(tag);
+    out.extend_from_slice(s);
+    out.extend_from_slice(b"\r\n");
+}
+
+/// 错误中的CRLF替换为空格
+fn write_error(s: &[u8], out: &mut Vec<u8>) {
+    out.push(b'-');
+    out.extend(s.iter().map(|b| if *b == b'\r' || *b == b'\n' { b' ' } else { *b }));
+    out.extend_from_slice(b"\r\n");
+}
+
+fn write_int(tag: u8, i: i64, out: &mut Vec<u8>) {
+    out.push(tag);
+    let _ = write!(out, "{}\r\n", i);
+}
+
+fn write_len(tag: u8, len: usize, out: &mut Vec<u8>) {
+    out.push(tag);
+    let _ = write!(out, "{}\r\n", len);
+}
+
+fn write_bulk(tag: u8, s: &[u8], out: &mut Vec<u8>) {
+    write_len(tag, s.len(), out);
+    out.extend_from_slice(s);
+    out.extend_from_slice(b"\r\n");
+}
+
+fn write_frames(tag: u8, frames: &[Frame<'_>], version: RespVersion, out: &mut Vec<u8>) {
+    write_len(tag, frames.len(), out);
+    for f in frames {
+        f.encode(version, out);
+    }
+}
+
+/// 与redis一样使用 inf、-inf与nan
+fn format_double(d: f64) -> String {
+    if d.is_nan() {
+        "nan".to_owned()
+    } else if d.is_infinite() {
+        if d > 0.0 { "inf" } else { "-inf" }.to_owned()
+    } else {
+        format!("{}", d)
+    }
+}
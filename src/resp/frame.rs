@@ -0,0 +1,194 @@
+use std::borrow::Cow;
+
+use crate::RrError;
+
+/// 协议的版本，客户端使用 HELLO 3 切换到RESP3
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RespVersion {
+    #[default]
+    Resp2,
+    Resp3,
+}
+
+/// RESP2/RESP3的一个frame
+/// 解析时所有的bytes都借用输入的buffer（零拷贝），回复时可以直接使用数据库读出的Vec<u8>，见[Frame::into_owned]
+#[derive(Clone, Debug, PartialEq)]
+pub enum Frame<'a> {
+    /// +OK
+    Simple(Cow<'a, [u8]>),
+    /// -ERR message，内容包含错误的前缀，如 "ERR"、"WRONGTYPE"
+    Error(Cow<'a, [u8]>),
+    /// :100
+    Integer(i64),
+    /// $5 hello
+    Bulk(Cow<'a, [u8]>),
+    /// RESP3的 _ ，RESP2的 $-1 与 *-1 都解析为Null
+    Null,
+    /// *2 ...
+    Array(Vec<Frame<'a>>),
+    /// RESP3 %2 ...，RESP2时编码为长度是两倍的Array
+    Map(Vec<(Frame<'a>, Frame<'a>)>),
+    /// RESP3 ~2 ...，RESP2时编码为Array
+    Set(Vec<Frame<'a>>),
+    /// RESP3 >2 ...，pub/sub的消息，RESP2时编码为Array
+    Push(Vec<Frame<'a>>),
+    /// RESP3 ,1.23，RESP2时编码为Bulk
+    Double(f64),
+    /// RESP3 #t，RESP2时编码为Integer 1/0
+    Boolean(bool),
+    /// RESP3 (12345678901234567890，RESP2时编码为Bulk
+    BigNumber(Cow<'a, [u8]>),
+    /// RESP3 !21 SYNTAX invalid syntax，RESP2时编码为Error
+    BulkError(Cow<'a, [u8]>),
+    /// RESP3 =15 txt:Some string，(格式, 内容)，格式为3个byte，RESP2时编码为Bulk(内容)
+    Verbatim(Cow<'a, [u8]>, Cow<'a, [u8]>),
+    /// RESP3 |1 ...，回复之前的附加信息，作为单独的一个frame返回，RESP2时不编码
+    Attribute(Vec<(Frame<'a>, Frame<'a>)>),
+}
+
+impl<'a> Frame<'a> {
+    pub fn ok() -> Frame<'static> {
+        Frame::Simple(Cow::Borrowed(b"OK"))
+    }
+
+    pub fn simple<S: Into<Cow<'a, [u8]>>>(s: S) -> Self {
+        Frame::Simple(s.into())
+    }
+
+    pub fn bulk<S: Into<Cow<'a, [u8]>>>(s: S) -> Self {
+        Frame::Bulk(s.into())
+    }
+
+    /// message需要包含错误的前缀，如 "ERR syntax error"
+    pub fn error<S: Into<Cow<'a, [u8]>>>(message: S) -> Self {
+        Frame::Error(message.into())
+    }
+
+    /// 复制借用的bytes，这样frame可以脱离输入的buffer
+    pub fn into_owned(self) -> Frame<'static> {
+        fn owned(c: Cow<'_, [u8]>) -> Cow<'static, [u8]> {
+            Cow::Owned(c.into_owned())
+        }
+        fn owned_vec(v: Vec<Frame<'_>>) -> Vec<Frame<'static>> {
+            v.into_iter().map(Frame::into_owned).collect()
+        }
+        fn owned_pairs(v: Vec<(Frame<'_>, Frame<'_>)>) -> Vec<(Frame<'static>, Frame<'static>)> {
+            v.into_iter().map(|(k, v)| (k.into_owned(), v.into_owned())).collect()
+        }
+        match self {
+            Frame::Simple(s) => Frame::Simple(owned(s)),
+            Frame::Error(s) => Frame::Error(owned(s)),
+            Frame::Integer(i) => Frame::Integer(i),
+            Frame::Bulk(s) => Frame::Bulk(owned(s)),
+            Frame::Null => Frame::Null,
+            Frame::Array(v) => Frame::Array(owned_vec(v)),
+            Frame::Map(v) => Frame::Map(owned_pairs(v)),
+            Frame::Set(v) => Frame::Set(owned_vec(v)),
+            Frame::Push(v) => Frame::Push(owned_vec(v)),
+            Frame::Double(d) => Frame::Double(d),
+            Frame::Boolean(b) => Frame::Boolean(b),
+            Frame::BigNumber(s) => Frame::BigNumber(owned(s)),
+            Frame::BulkError(s) => Frame::BulkError(owned(s)),
+            Frame::Verbatim(f, s) => Frame::Verbatim(owned(f), owned(s)),
+            Frame::Attribute(v) => Frame::Attribute(owned_pairs(v)),
+        }
+    }
+
+    /// Simple、Bulk、BigNumber与Verbatim返回内容，其它返回None
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        match self {
+            Frame::Simple(s) | Frame::Bulk(s) | Frame::BigNumber(s) | Frame::Verbatim(_, s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// Integer直接返回，Simple与Bulk按十进制解析
+    pub fn as_integer(&self) -> Option<i64> {
+        match self {
+            Frame::Integer(i) => Some(*i),
+            Frame::Simple(s) | Frame::Bulk(s) => std::str::from_utf8(s).ok()?.parse().ok(),
+            _ => None,
+        }
+    }
+
+    pub fn is_error(&self) -> bool {
+        matches!(self, Frame::Error(_) | Frame::BulkError(_))
+    }
+
+    /// Error与BulkError转换为[RrError]，其它返回None
+    pub fn to_error(&self) -> Option<RrError> {
+        match self {
+            Frame::Error(s) | Frame::BulkError(s) => Some(RrError::message(String::from_utf8_lossy(s).into_owned())),
+            _ => None,
+        }
+    }
+}
+
+impl From<&RrError> for Frame<'static> {
+    /// 错误回复，没有大写前缀(如 WRONGTYPE，至少3个字母)的错误加上 "ERR "，换行替换为空格
+    fn from(e: &RrError) -> Self {
+        let message = match e {
+            RrError::Message(s) => s.clone(),
+            e => e.to_string(),
+        };
+        let message = message.replace(['\r', '\n'], " ");
+        let prefix = message.split(' ').next().unwrap_or_default();
+        let has_prefix = message.len() > prefix.len() && prefix.len() >= 3 && prefix.bytes().all(|b| b.is_ascii_uppercase());
+        if has_prefix {
+            Frame::Error(Cow::Owned(message.into_bytes()))
+        } else {
+            Frame::Error(Cow::Owned(format!("ERR {}", message).into_bytes()))
+        }
+    }
+}
+
+impl From<Vec<u8>> for Frame<'static> {
+    fn from(v: Vec<u8>) -> Self {
+        Frame::Bulk(Cow::Owned(v))
+    }
+}
+
+impl<'a> From<&'a [u8]> for Frame<'a> {
+    fn from(v: &'a [u8]) -> Self {
+        Frame::Bulk(Cow::Borrowed(v))
+    }
+}
+
+impl<'a, T: Into<Frame<'a>>> From<Option<T>> for Frame<'a> {
+    fn from(v: Option<T>) -> Self {
+        match v {
+            None => Frame::Null,
+            Some(v) => v.into(),
+        }
+    }
+}
+
+impl From<i64> for Frame<'static> {
+    fn from(v: i64) -> Self {
+        Frame::Integer(v)
+    }
+}
+
+impl From<bool> for Frame<'static> {
+    fn from(v: bool) -> Self {
+        Frame::Boolean(v)
+    }
+}
+
+impl From<f64> for Frame<'static> {
+    fn from(v: f64) -> Self {
+        Frame::Double(v)
+    }
+}
+
+impl<'a> From<Vec<Frame<'a>>> for Frame<'a> {
+    fn from(v: Vec<Frame<'a>>) -> Self {
+        Frame::Array(v)
+    }
+}
+
+impl From<Vec<Vec<u8>>> for Frame<'static> {
+    fn from(v: Vec<Vec<u8>>) -> Self {
+        Frame::Array(v.into_iter().map(Frame::from).collect())
+    }
+}
@@ -0,0 +1,8 @@
+pub use decode::*;
+pub use frame::*;
+
+/// RESP2/RESP3协议的解析与编码，[see](https://github.com/redis/redis-specifications/tree/master/protocol)
+/// 不支持RESP3中长度未知的流式string与集合（$?、*?），redis的服务端也不发送它们
+mod decode;
+mod encode;
+mod frame;
@@ -0,0 +1,493 @@
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, BTreeSet},
+};
+
+use crate::{
+    read_len_type,
+    rocksdb_impl::{
+        expire_impl::expire_if_needed,
+        key_space_impl::{delete_key, get_type, make_type_key, set_type},
+        key_value_impl::{clamp_range, MAX_STRING_LEN},
+        shared::{make_field_key, make_head_key},
+    },
+    write_len_type, BitFieldOp, BitFieldType, BitOp, BitOverflow, BitRange, BitUnit, Bitmap, KeyType, LenType, RrError, WrapDb, BYTES_LEN_TYPE,
+};
+
+/// 位图使用[KeyType::Bitmap]登记，使用两种key存放数据
+/// head key                            : 字符串的长度（bytes）
+/// head key + "c" + 块的序号（u64 BE） : 块的内容，不存在或比块短的部分都是0，全为0的块不存放
+/// 没有按位修改过的字符串（[KeyType::String]）也可以读取，第一次按位修改时转换为块
+pub struct BitmapImpl {}
+
+/// 每一个块的bytes，修改一个bit时只重写一个块
+const CHUNK_SIZE: u64 = 4096;
+const CHUNK_TAG: &[u8] = b"c";
+
+impl<T: WrapDb> Bitmap<T> for BitmapImpl {
+    fn set_bit(&self, t: &T, key: &[u8], offset: u64, value: bool) -> Result<bool, RrError> {
+        check_offset(offset, 1)?;
+        let mut chunks = Chunks::open_for_update(t, key)?;
+        chunks.grow(offset / 8 + 1);
+        let old = chunks.set(offset, value)?;
+        chunks.flush()?;
+        Ok(old)
+    }
+
+    fn get_bit(&self, t: &T, key: &[u8], offset: u64) -> Result<bool, RrError> {
+        check_offset(offset, 1)?;
+        match Chunks::open(t, key)? {
+            None => Ok(false),
+            Some(chunks) => chunks.get(offset),
+        }
+    }
+
+    fn bit_count(&self, t: &T, key: &[u8], range: Option<&BitRange>) -> Result<LenType, RrError> {
+        let chunks = match Chunks::open(t, key)? {
+            None => return Ok(0),
+            Some(c) => c,
+        };
+        let (start, end) = match chunks.bit_range(range) {
+            None => return Ok(0),
+            Some(r) => r,
+        };
+        let mut count = 0;
+        chunks.for_each_byte(start / 8, end / 8, |i, b| {
+            count += (b & byte_mask(i, start, end)).count_ones() as LenType;
+            true
+        })?;
+        Ok(count)
+    }
+
+    fn bit_pos(&self, t: &T, key: &[u8], bit: bool, range: Option<&BitRange>) -> Result<i64, RrError> {
+        let chunks = match Chunks::open(t, key)? {
+            None => return Ok(if bit { -1 } else { 0 }),
+            Some(c) => c,
+        };
+        let (start, end) = match chunks.bit_range(range) {
+            None => return Ok(-1),
+            Some(r) => r,
+        };
+        let mut pos = None;
+        chunks.for_each_byte(start / 8, end / 8, |i, b| {
+            let found = (if bit { b } else { !b }) & byte_mask(i, start, end);
+            if found != 0 {
+                pos = Some(i * 8 + found.leading_zeros() as u64);
+            }
+            found == 0
+        })?;
+        match pos {
+            Some(p) => Ok(p as i64),
+            //与redis一样，没有指定end时把字符串的右边当作0
+            None if !bit && range.is_none_or(|r| r.end.is_none()) => Ok(end as i64 + 1),
+            None => Ok(-1),
+        }
+    }
+
+    fn bit_op(&self, t: &T, op: BitOp, dst: &[u8], keys: &[&[u8]]) -> Result<LenType, RrError> {
+        if keys.is_empty() {
+            return Err(RrError::message("BITOP requires at least one source key".to_owned()));
+        }
+        if op == BitOp::Not && keys.len() != 1 {
+            return Err(RrError::message("BITOP NOT must be called with a single source key.".to_owned()));
+        }
+        let mut sources = Vec::with_capacity(keys.len());
+        for key in keys {
+            sources.push(Chunks::open(t, key)?);
+        }
+        let len = sources.iter().flatten().map(|c| c.len).max().unwrap_or(0);
+        //dst可以是参与运算的key，所以先计算出结果，全为0的块不用保存
+        let mut result = BTreeMap::new();
+        for index in 0..len.div_ceil(CHUNK_SIZE) {
+            let chunk_len = (len - index * CHUNK_SIZE).min(CHUNK_SIZE) as usize;
+            let mut re = read_chunk(&sources[0], index, chunk_len)?;
+            for source in &sources[1..] {
+                let c = read_chunk(source, index, chunk_len)?;
+                for (a, b) in re.iter_mut().zip(c.iter()) {
+                    match op {
+                        BitOp::And => *a &= *b,
+                        BitOp::Or => *a |= *b,
+                        BitOp::Xor => *a ^= *b,
+                        //只有一个key
+                        BitOp::Not => {}
+                    }
+                }
+            }
+            if op == BitOp::Not {
+                re.iter_mut().for_each(|b| *b = !*b);
+            }
+            if re.iter().any(|b| *b != 0) {
+                result.insert(index, re);
+            }
+        }
+        expire_if_needed(t, dst)?;
+        delete_key(t, dst)?;
+        if len == 0 {
+            return Ok(0);
+        }
+        let mut chunks = Chunks::empty(t, dst);
+        chunks.len = len;
+        chunks.dirty = result.keys().copied().collect();
+        chunks.cache = result;
+        chunks.flush()?;
+        Ok(len)
+    }
+
+    fn bit_field(&self, t: &T, key: &[u8], ops: &[BitFieldOp]) -> Result<Vec<Option<i64>>, RrError> {
+        let mut write_end = None;
+        for op in ops {
+            match *op {
+                BitFieldOp::Get(ty, offset) => {
+                    ty.check()?;
+                    check_offset(offset, ty.bits)?;
+                }
+                BitFieldOp::Set(ty, offset, _) | BitFieldOp::IncrBy(ty, offset, _) => {
+                    ty.check()?;
+                    check_offset(offset, ty.bits)?;
+                    write_end = write_end.max(Some(offset + ty.bits as u64));
+                }
+                BitFieldOp::Overflow(_) => {}
+            }
+        }
+        let mut chunks = match write_end {
+            //与redis一样，先把字符串扩展到所有写入的位置，即使写入因为FAIL没有执行
+            Some(end) => {
+                let mut c = Chunks::open_for_update(t, key)?;
+                c.grow(end.div_ceil(8));
+                c
+            }
+            None => Chunks::open(t, key)?.unwrap_or_else(|| Chunks::empty(t, key)),
+        };
+        let mut overflow = BitOverflow::default();
+        let mut re = Vec::with_capacity(ops.len());
+        for op in ops {
+            match *op {
+                BitFieldOp::Overflow(o) => overflow = o,
+                BitFieldOp::Get(ty, offset) => re.push(Some(chunks.get_field(ty, offset)?)),
+                BitFieldOp::Set(ty, offset, value) => {
+                    let old = chunks.get_field(ty, offset)?;
+                    //与redis一样，无符号时把value当作u64
+                    let value = if ty.signed { value as i128 } else { value as u64 as i128 };
+                    match ty.limit(value, overflow) {
+                        None => re.push(None),
+                        Some(new) => {
+                            chunks.set_field(ty, offset, new)?;
+                            re.push(Some(old));
+                        }
+                    }
+                }
+                BitFieldOp::IncrBy(ty, offset, increment) => {
+                    let old = chunks.get_field(ty, offset)?;
+                    match ty.limit(old as i128 + increment as i128, overflow) {
+                        None => re.push(None),
+                        Some(new) => {
+                            chunks.set_field(ty, offset, new)?;
+                            re.push(Some(new));
+                        }
+                    }
+                }
+            }
+        }
+        if write_end.is_some() {
+            chunks.flush()?;
+        }
+        Ok(re)
+    }
+}
+
+impl BitFieldType {
+    fn check(&self) -> Result<(), RrError> {
+        let max = if self.signed { 64 } else { 63 };
+        if self.bits == 0 || self.bits > max {
+            return Err(RrError::message(
+                "Invalid bitfield type. Use something like i16 u8. Note that u64 is not supported but i64 is.".to_owned(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// 按溢出的方式把value限制在类型的范围内，FAIL时返回None
+    fn limit(&self, value: i128, overflow: BitOverflow) -> Option<i64> {
+        let (min, max) = if self.signed {
+            (-(1i128 << (self.bits - 1)), (1i128 << (self.bits - 1)) - 1)
+        } else {
+            (0, (1i128 << self.bits) - 1)
+        };
+        if (min..=max).contains(&value) {
+            return Some(value as i64);
+        }
+        match overflow {
+            BitOverflow::Wrap => {
+                let m = 1i128 << self.bits;
+                let w = value.rem_euclid(m);
+                Some((if w > max { w - m } else { w }) as i64)
+            }
+            BitOverflow::Sat => Some(value.clamp(min, max) as i64),
+            BitOverflow::Fail => None,
+        }
+    }
+}
+
+/// 按块读写一个字符串
+struct Chunks<'a, T: WrapDb> {
+    t: &'a T,
+    key: &'a [u8],
+    /// 字符串的长度（bytes）
+    len: u64,
+    /// 读入或修改过的块
+    cache: BTreeMap<u64, Vec<u8>>,
+    /// 修改过的块
+    dirty: BTreeSet<u64>,
+    /// 原来是[KeyType::String]，所有的块都在cache中，写入时删除原来的值
+    plain: bool,
+}
+
+impl<'a, T: WrapDb> Chunks<'a, T> {
+    fn empty(t: &'a T, key: &'a [u8]) -> Self {
+        Chunks {
+            t,
+            key,
+            len: 0,
+            cache: BTreeMap::new(),
+            dirty: BTreeSet::new(),
+            plain: false,
+        }
+    }
+
+    /// 类型为[KeyType::Bitmap]的key
+    fn bitmap(t: &'a T, key: &'a [u8]) -> Result<Self, RrError> {
+        let mut chunks = Chunks::empty(t, key);
+        chunks.len = t.get(&make_head_key(KeyType::Bitmap, key))?.map(|v| read_len_type(&v)).unwrap_or(0);
+        Ok(chunks)
+    }
+
+    /// key不存在时返回None，不是字符串时返回WRONGTYPE错误
+    fn open(t: &'a T, key: &'a [u8]) -> Result<Option<Self>, RrError> {
+        expire_if_needed(t, key)?;
+        match get_type(t, key)? {
+            None => Ok(None),
+            Some(KeyType::String) => {
+                let v = t.get(&make_head_key(KeyType::String, key))?.unwrap_or_default();
+                let mut chunks = Chunks::empty(t, key);
+                chunks.len = v.len() as u64;
+                chunks.plain = true;
+                for (i, c) in v.chunks(CHUNK_SIZE as usize).enumerate() {
+                    chunks.cache.insert(i as u64, c.to_vec());
+                }
+                Ok(Some(chunks))
+            }
+            Some(KeyType::Bitmap) => Ok(Some(Chunks::bitmap(t, key)?)),
+            Some(_) => Err(RrError::wrong_type()),
+        }
+    }
+
+    /// 与字符串的修改一样先锁定key，所以并发的修改不会丢失更新，key不存在时为空的字符串
+    fn open_for_update(t: &'a T, key: &'a [u8]) -> Result<Self, RrError> {
+        t.get_for_update(&make_type_key(key))?;
+        Ok(Chunks::open(t, key)?.unwrap_or_else(|| Chunks::empty(t, key)))
+    }
+
+    /// 块的长度，最后一个块可能比CHUNK_SIZE短
+    fn chunk_len(&self, index: u64) -> usize {
+        self.len.saturating_sub(index * CHUNK_SIZE).min(CHUNK_SIZE) as usize
+    }
+
+    /// 读取一个块，长度为[Chunks::chunk_len]，读入的块不放入cache，用于遍历
+    fn read(&self, index: u64) -> Result<Cow<'_, [u8]>, RrError> {
+        let len = self.chunk_len(index);
+        let mut c = match self.cache.get(&index) {
+            Some(c) if c.len() == len => return Ok(Cow::Borrowed(c)),
+            Some(c) => c.clone(),
+            None if self.plain => Vec::new(),
+            None => self.t.get(&chunk_key(self.key, index))?.unwrap_or_default(),
+        };
+        c.resize(len, 0);
+        Ok(Cow::Owned(c))
+    }
+
+    /// 修改一个块之前调用，读入cache并标记为已修改
+    fn chunk_mut(&mut self, index: u64) -> Result<&mut Vec<u8>, RrError> {
+        let len = self.chunk_len(index);
+        if !self.cache.contains_key(&index) {
+            let c = self.read(index)?.into_owned();
+            self.cache.insert(index, c);
+        }
+        self.dirty.insert(index);
+        let c = self.cache.entry(index).or_default();
+        c.resize(len, 0);
+        Ok(c)
+    }
+
+    /// 字符串的长度至少为len，新增的部分为0
+    fn grow(&mut self, len: u64) {
+        self.len = self.len.max(len);
+    }
+
+    fn get(&self, offset: u64) -> Result<bool, RrError> {
+        let byte = offset / 8;
+        if byte >= self.len {
+            return Ok(false);
+        }
+        let c = self.read(byte / CHUNK_SIZE)?;
+        Ok(c[(byte % CHUNK_SIZE) as usize] & bit_mask(offset) != 0)
+    }
+
+    /// 返回原来的值，调用之前要使用[Chunks::grow]保证offset在字符串中
+    fn set(&mut self, offset: u64, value: bool) -> Result<bool, RrError> {
+        let byte = offset / 8;
+        let c = self.chunk_mut(byte / CHUNK_SIZE)?;
+        let b = &mut c[(byte % CHUNK_SIZE) as usize];
+        let old = *b & bit_mask(offset) != 0;
+        if value {
+            *b |= bit_mask(offset);
+        } else {
+            *b &= !bit_mask(offset);
+        }
+        Ok(old)
+    }
+
+    /// 读取offset开始的bits个bit，有符号时扩展符号位
+    fn get_field(&self, ty: BitFieldType, offset: u64) -> Result<i64, RrError> {
+        let bits = ty.bits as u64;
+        let (first, last) = (offset / 8, (offset + bits - 1) / 8);
+        let mut bytes = vec![0u8; (last - first + 1) as usize];
+        self.for_each_byte(first, last, |i, b| {
+            bytes[(i - first) as usize] = b;
+            true
+        })?;
+        let mut v = 0u64;
+        for pos in offset..offset + bits {
+            let bit = bytes[(pos / 8 - first) as usize] & bit_mask(pos) != 0;
+            v = (v << 1) | bit as u64;
+        }
+        if ty.signed && bits < 64 && (v >> (bits - 1)) & 1 == 1 {
+            v |= u64::MAX << bits;
+        }
+        Ok(v as i64)
+    }
+
+    /// 写入value的低bits个bit，调用之前要使用[Chunks::grow]保证范围在字符串中
+    fn set_field(&mut self, ty: BitFieldType, offset: u64, value: i64) -> Result<(), RrError> {
+        let bits = ty.bits as u64;
+        for i in 0..bits {
+            self.set(offset + i, (value >> (bits - 1 - i)) & 1 == 1)?;
+        }
+        Ok(())
+    }
+
+    /// 依次访问[start, end]中的byte，超出字符串的部分不访问，f返回false时停止
+    fn for_each_byte(&self, start: u64, end: u64, mut f: impl FnMut(u64, u8) -> bool) -> Result<(), RrError> {
+        if self.len == 0 {
+            return Ok(());
+        }
+        let end = end.min(self.len - 1);
+        let mut i = start;
+        while i <= end {
+            let index = i / CHUNK_SIZE;
+            let c = self.read(index)?;
+            let chunk_end = ((index + 1) * CHUNK_SIZE - 1).min(end);
+            for j in i..=chunk_end {
+                if !f(j, c[(j - index * CHUNK_SIZE) as usize]) {
+                    return Ok(());
+                }
+            }
+            i = chunk_end + 1;
+        }
+        Ok(())
+    }
+
+    /// 把范围转换为bit的位置[start, end]，范围为空时返回None
+    fn bit_range(&self, range: Option<&BitRange>) -> Option<(u64, u64)> {
+        let range = match range {
+            None => return (self.len > 0).then(|| (0, self.len * 8 - 1)),
+            Some(r) => r,
+        };
+        let (len, scale) = match range.unit {
+            BitUnit::Byte => (self.len, 8),
+            BitUnit::Bit => (self.len * 8, 1),
+        };
+        let (start, end) = clamp_range(len, range.start, range.end.unwrap_or(-1))?;
+        Some((start * scale, end * scale + scale - 1))
+    }
+
+    /// 写入修改过的块、长度与类型，原来是[KeyType::String]时删除原来的值并写入所有的块
+    fn flush(&mut self) -> Result<(), RrError> {
+        if self.plain {
+            self.t.delete(&make_head_key(KeyType::String, self.key))?;
+            self.dirty.extend(self.cache.keys().copied());
+            self.plain = false;
+        }
+        for index in &self.dirty {
+            let k = chunk_key(self.key, *index);
+            match self.cache.get(index) {
+                Some(c) if c.iter().any(|b| *b != 0) => self.t.put(&k, c)?,
+                _ => self.t.delete(&k)?,
+            }
+        }
+        self.dirty.clear();
+        let mut v = [0u8; BYTES_LEN_TYPE];
+        write_len_type(&mut v, self.len);
+        self.t.put(&make_head_key(KeyType::Bitmap, self.key), &v)?;
+        set_type(self.t, self.key, KeyType::Bitmap)
+    }
+}
+
+/// 合并所有的块，用于get等字符串的方法
+pub(crate) fn read_bitmap<T: WrapDb>(t: &T, key: &[u8]) -> Result<Vec<u8>, RrError> {
+    let chunks = Chunks::bitmap(t, key)?;
+    let mut v = Vec::with_capacity(chunks.len as usize);
+    for index in 0..chunks.len.div_ceil(CHUNK_SIZE) {
+        v.extend_from_slice(&chunks.read(index)?);
+    }
+    Ok(v)
+}
+
+/// 字符串的方法修改位图之前调用，删除长度与所有的块，保留类型与过期时间
+pub(crate) fn delete_bitmap<T: WrapDb>(t: &T, key: &[u8]) -> Result<(), RrError> {
+    let head_key = make_head_key(KeyType::Bitmap, key);
+    for item in t.prefix_iterator(&head_key) {
+        let (k, _) = item?;
+        if !k.starts_with(&head_key) {
+            break;
+        }
+        t.delete(&k)?;
+    }
+    Ok(())
+}
+
+/// 块的内容，长度为len，key不存在时全为0
+fn read_chunk<T: WrapDb>(source: &Option<Chunks<'_, T>>, index: u64, len: usize) -> Result<Vec<u8>, RrError> {
+    let mut c = match source {
+        None => Vec::new(),
+        Some(s) => s.read(index)?.into_owned(),
+    };
+    c.resize(len, 0);
+    Ok(c)
+}
+
+/// 与redis一样，bit的位置加上长度不能超出字符串的最大长度
+fn check_offset(offset: u64, bits: u8) -> Result<(), RrError> {
+    match offset.checked_add(bits as u64) {
+        Some(end) if end <= MAX_STRING_LEN as u64 * 8 => Ok(()),
+        _ => Err(RrError::message("bit offset is not an integer or out of range".to_owned())),
+    }
+}
+
+/// 与redis一样，offset为0的bit是第一个byte的最高位
+fn bit_mask(offset: u64) -> u8 {
+    0x80 >> (offset % 8)
+}
+
+/// byte i中位于[start, end]（bit的位置）的bit
+fn byte_mask(i: u64, start: u64, end: u64) -> u8 {
+    let first = i * 8;
+    let lo = start.saturating_sub(first).min(7);
+    let hi = (end - first).min(7);
+    (0xFF >> lo) & (0xFF << (7 - hi))
+}
+
+fn chunk_key(key: &[u8], index: u64) -> Vec<u8> {
+    let mut k = make_field_key(KeyType::Bitmap, key, CHUNK_TAG);
+    k.extend_from_slice(&index.to_be_bytes());
+    k
+}
@@ -1,67 +1,193 @@
-use crate::{rocksdb_impl::shared::make_field_key, BPTree, LenType, RrError, WrapDb};
+use std::ops::Bound;
+
+use crate::{
+    rocksdb_impl::{
+        bptree::btree::{BTree, BTreeIter, MAX_BRANCHING_FACTOR},
+        key_space_impl::{check_type, ensure_type, remove_if_empty, remove_key_meta, set_type},
+        shared::make_head_key,
+    },
+    BPTree, KeyType, LenType, RrError, WrapDb,
+};
 
 /// see
 /// [Writing a storage engine in Rust: Writing a persistent BTree (Part 1)] (https://nimrodshn.medium.com/writing-a-storage-engine-in-rust-writing-a-persistent-btree-part-1-916b6f3e2934)
 /// [A persistent copy-on-write B+Tree implementation, designed as an index for a key-value store, inspired by SQLite](https://github.com/nimrodshn/btree)
-pub struct BPTreeImpl {}
+/// 树中只有字段名，字段的值单独存放，所以 get/set 一个已存在的字段不需要访问树
+pub struct BPTreeImpl {
+    /// 最小的度数，每个节点中最多有 2 * degree - 1 个字段
+    degree: usize,
+}
+
+impl Default for BPTreeImpl {
+    fn default() -> Self {
+        BPTreeImpl {
+            degree: MAX_BRANCHING_FACTOR / 2,
+        }
+    }
+}
+
+impl BPTreeImpl {
+    /// degree 最小为2，同一个key要使用同样的degree，不然节点的大小会不一致（不影响正确性）
+    pub fn new(degree: usize) -> Self {
+        BPTreeImpl { degree }
+    }
+
+    /// 按字段的顺序遍历，一次只读取一个leaf节点，适合遍历大量的字段，参数参见[BPTree::range]
+    pub fn iter<'a, T: WrapDb>(&self, t: &'a T, key: &[u8], start: Bound<&[u8]>, end: Bound<&[u8]>, rev: bool) -> Result<BPTreeIter<'a, T>, RrError> {
+        check_type(t, key, KeyType::BPTree)?;
+        let it = self.tree(t, key).iter(start.map(<[u8]>::to_vec), end.map(<[u8]>::to_vec), rev)?;
+        Ok(BPTreeIter { t, key: key.to_vec(), it })
+    }
+
+    fn tree<'a, T: WrapDb>(&self, t: &'a T, key: &[u8]) -> BTree<'a, T> {
+        BTree::new(key, t, self.degree)
+    }
+}
 
 impl<T: WrapDb> BPTree<T> for BPTreeImpl {
-    fn set_exist(&self, t: &T, key: &[u8], field: &[u8], _value: &[u8]) -> Result<(), RrError> {
-        let field_key = make_field_key(key, field);
-        if t.exist(&field_key)? {
-            // let head_key = make_head_key(key);
-            // let mut heap = {
-            //     match t.get(&head_key)? {
-            //         None => FieldHeap::new(vec![]),
-            //         Some(v) => FieldHeap::new(v)
-            //     }
-            // };
-            // let p = &mut heap as *mut _;
-            // heap.init(MaxHeapCompare { heap: p });
-            // heap.push(field);
-            // t.put(&head_key, &heap.data)?;
+    fn set_exist(&self, t: &T, key: &[u8], field: &[u8], value: &[u8]) -> Result<(), RrError> {
+        //没有写入时不写入类型的版本，不然会使watch这个key的事务失败
+        check_type(t, key, KeyType::BPTree)?;
+        let value_key = BTree::<T>::value_key(key, field);
+        if t.get(&value_key)?.is_some() {
+            set_type(t, key, KeyType::BPTree)?;
+            t.put(&value_key, value)?;
         }
-        // t.put(&field_key, value)?;
         Ok(())
     }
 
-    fn set_not_exist(&self, _t: &T, _key: &[u8], _field: &[u8], _value: &[u8]) -> Result<(), RrError> {
-        todo!()
+    fn set_not_exist(&self, t: &T, key: &[u8], field: &[u8], value: &[u8]) -> Result<(), RrError> {
+        check_type(t, key, KeyType::BPTree)?;
+        let value_key = BTree::<T>::value_key(key, field);
+        if t.get(&value_key)?.is_none() {
+            set_type(t, key, KeyType::BPTree)?;
+            self.tree(t, key).insert(field)?;
+            t.put(&value_key, value)?;
+        }
+        Ok(())
     }
 
-    fn set(&self, _t: &T, _key: &[u8], _field: &[u8], _value: &[u8]) -> Result<(), RrError> {
-        todo!()
+    fn set(&self, t: &T, key: &[u8], field: &[u8], value: &[u8]) -> Result<(), RrError> {
+        ensure_type(t, key, KeyType::BPTree)?;
+        let value_key = BTree::<T>::value_key(key, field);
+        if t.get(&value_key)?.is_none() {
+            self.tree(t, key).insert(field)?;
+        }
+        t.put(&value_key, value)?;
+        Ok(())
     }
 
-    fn del_first(&self, _t: &T, _key: &[u8]) -> Result<Option<(Vec<u8>, Vec<u8>)>, RrError> {
-        todo!()
+    fn del_first(&self, t: &T, key: &[u8]) -> Result<Option<(Vec<u8>, Vec<u8>)>, RrError> {
+        check_type(t, key, KeyType::BPTree)?;
+        let re = self.get_first(t, key)?;
+        if let Some((field, _)) = &re {
+            self.del(t, key, field)?;
+        }
+        Ok(re)
+    }
+
+    fn del_last(&self, t: &T, key: &[u8]) -> Result<Option<(Vec<u8>, Vec<u8>)>, RrError> {
+        check_type(t, key, KeyType::BPTree)?;
+        let re = self.get_last(t, key)?;
+        if let Some((field, _)) = &re {
+            self.del(t, key, field)?;
+        }
+        Ok(re)
     }
 
-    fn del_last(&self, _t: &T, _key: &[u8]) -> Result<Option<(Vec<u8>, Vec<u8>)>, RrError> {
-        todo!()
+    fn del(&self, t: &T, key: &[u8], field: &[u8]) -> Result<Option<Vec<u8>>, RrError> {
+        check_type(t, key, KeyType::BPTree)?;
+        let value_key = BTree::<T>::value_key(key, field);
+        let old = t.get(&value_key)?;
+        if old.is_some() {
+            set_type(t, key, KeyType::BPTree)?;
+            self.tree(t, key).delete(field)?;
+            t.delete(&value_key)?;
+            //最后一个字段删除时树的head与root已经删除了
+            remove_if_empty(t, key, KeyType::BPTree)?;
+        }
+        Ok(old)
+    }
+
+    fn get_first(&self, t: &T, key: &[u8]) -> Result<Option<(Vec<u8>, Vec<u8>)>, RrError> {
+        check_type(t, key, KeyType::BPTree)?;
+        match self.tree(t, key).first()? {
+            None => Ok(None),
+            Some(field) => {
+                let value_key = BTree::<T>::value_key(key, &field);
+                let value = t.get(&value_key)?.ok_or_else(|| RrError::corruption(&value_key, "value of bptree"))?;
+                Ok(Some((field, value)))
+            }
+        }
     }
 
-    fn del(&self, _t: &T, _key: &[u8], _field: &[u8]) -> Result<Option<Vec<u8>>, RrError> {
-        todo!()
+    fn get_last(&self, t: &T, key: &[u8]) -> Result<Option<(Vec<u8>, Vec<u8>)>, RrError> {
+        check_type(t, key, KeyType::BPTree)?;
+        match self.tree(t, key).last()? {
+            None => Ok(None),
+            Some(field) => {
+                let value_key = BTree::<T>::value_key(key, &field);
+                let value = t.get(&value_key)?.ok_or_else(|| RrError::corruption(&value_key, "value of bptree"))?;
+                Ok(Some((field, value)))
+            }
+        }
     }
 
-    fn get_first(&self, _t: &T, _key: &[u8]) -> Result<Option<(Vec<u8>, Vec<u8>)>, RrError> {
-        todo!()
+    fn get(&self, t: &T, key: &[u8], field: &[u8]) -> Result<Option<Vec<u8>>, RrError> {
+        check_type(t, key, KeyType::BPTree)?;
+        t.get(&BTree::<T>::value_key(key, field))
     }
 
-    fn get_last(&self, _t: &T, _key: &[u8]) -> Result<Option<(Vec<u8>, Vec<u8>)>, RrError> {
-        todo!()
+    fn len(&self, t: &T, key: &[u8]) -> Result<Option<LenType>, RrError> {
+        check_type(t, key, KeyType::BPTree)?;
+        Ok(self.tree(t, key).len()?)
     }
 
-    fn get(&self, _t: &T, _key: &[u8], _field: &[u8]) -> Result<Option<Vec<u8>>, RrError> {
-        todo!()
+    fn range(&self, t: &T, key: &[u8], start: Bound<&[u8]>, end: Bound<&[u8]>, rev: bool, limit: Option<LenType>) -> Result<Vec<(Vec<u8>, Vec<u8>)>, RrError> {
+        check_type(t, key, KeyType::BPTree)?;
+        let it = self.iter(t, key, start, end, rev)?;
+        match limit {
+            None => it.collect(),
+            Some(limit) => it.take(limit as usize).collect(),
+        }
     }
 
-    fn len(&self, _t: &T, _key: &[u8]) -> Result<Option<LenType>, RrError> {
-        todo!()
+    fn del_key(&self, t: &T, key: &[u8]) -> Result<(), RrError> {
+        check_type(t, key, KeyType::BPTree)?;
+        remove_key_meta(t, key)?;
+        let head_key = make_head_key(KeyType::BPTree, key);
+        for item in t.prefix_iterator(&head_key) {
+            let (k, _) = item?;
+            if !k.starts_with(&head_key) {
+                break;
+            }
+            t.delete(&k)?;
+        }
+        Ok(())
     }
+}
+
+/// [BPTreeImpl::iter]的返回值，Item为 (field, field value)
+pub struct BPTreeIter<'a, T: WrapDb> {
+    t: &'a T,
+    key: Vec<u8>,
+    it: BTreeIter<'a, T>,
+}
+
+impl<'a, T: WrapDb> Iterator for BPTreeIter<'a, T> {
+    type Item = Result<(Vec<u8>, Vec<u8>), RrError>;
 
-    fn del_key(&self, _t: &T, _key: &[u8]) -> Result<(), RrError> {
-        todo!()
+    fn next(&mut self) -> Option<Self::Item> {
+        let field = match self.it.next()? {
+            Ok(f) => f,
+            Err(e) => return Some(Err(e.into())),
+        };
+        let value_key = BTree::<T>::value_key(&self.key, &field);
+        let value = match self.t.get(&value_key) {
+            Ok(Some(v)) => v,
+            Ok(None) => return Some(Err(RrError::corruption(&value_key, "value of bptree"))),
+            Err(e) => return Some(Err(e)),
+        };
+        Some(Ok((field, value)))
     }
 }
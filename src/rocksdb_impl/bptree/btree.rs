@@ -1,372 +1,541 @@
-use super::{error::Error, node::Node, node_type::NodeType};
-use crate::WrapDb;
+use std::{mem::size_of, ops::Bound};
+
+use super::{
+    children::Children,
+    db_key::DbKey,
+    error::Error,
+    leaf_data::LeafData,
+    node::Node,
+    node_type::{Keys, NodeType},
+};
+use crate::{
+    read_len_type,
+    rocksdb_impl::shared::{make_field_key, make_head_key},
+    write_len_type, KeyType, LenType, WrapDb,
+};
 
 /// [see](https://github.com/nimrodshn/btree)
 /// B+Tree properties.
 pub const MAX_BRANCHING_FACTOR: usize = 200;
 pub const NODE_KEYS_LIMIT: usize = MAX_BRANCHING_FACTOR - 1;
+/// 最小的度数，小于2时不能分裂
+pub const MIN_DEGREE: usize = 2;
+
+const NODE_TAG: &[u8] = b"n";
+const VALUE_TAG: &[u8] = b"v";
+
+/// 存放在 head key 中: root的db key(12) + 字段的数量(LenType)
+struct TreeHead {
+    root: DbKey,
+    len: LenType,
+}
+
+impl TreeHead {
+    const LEN: usize = DbKey::LEN_DB_KEY + size_of::<LenType>();
+
+    fn read(data: &[u8]) -> Result<Self, Error> {
+        if data.len() < TreeHead::LEN {
+            return Err(Error::TryFromSlice("head of bptree"));
+        }
+        Ok(TreeHead {
+            root: DbKey::from(&data[..DbKey::LEN_DB_KEY]),
+            len: read_len_type(&data[DbKey::LEN_DB_KEY..]),
+        })
+    }
+
+    fn to_bytes(&self) -> [u8; TreeHead::LEN] {
+        let mut data = [0u8; TreeHead::LEN];
+        data[..DbKey::LEN_DB_KEY].copy_from_slice(self.root.key());
+        write_len_type(&mut data[DbKey::LEN_DB_KEY..], self.len);
+        data
+    }
+}
+
+/// insert_node的返回值
+enum Inserted {
+    Exists,
+    Done,
+    /// 节点分裂了，0: 上移到父节点的key，1: 新节点的db key
+    Split(Vec<u8>, DbKey),
+}
 
 /// BTree struct represents an on-disk B+tree.
-/// Each node is persisted in the table file, the leaf nodes contain the values.
+/// 节点存放在 head key + "n" + db key 中，字段的值存放在 head key + "v" + field 中，leaf节点中只有字段名
+/// 每个节点(除了root)中key的个数在 [b - 1, 2b - 1] 之间
 pub struct BTree<'a, T: WrapDb> {
-    b: u64,
+    b: usize,
     t: &'a T,
     key: Vec<u8>,
 }
 
 impl<'a, T: WrapDb> BTree<'a, T> {
-    pub fn new(key: &[u8], t: &'a T) -> Self {
-        BTree { b: 3, t, key: key.to_vec() }
+    pub fn new(key: &[u8], t: &'a T, b: usize) -> Self {
+        BTree {
+            b: b.max(MIN_DEGREE),
+            t,
+            key: key.to_vec(),
+        }
+    }
+
+    /// 字段值存放的key
+    pub fn value_key(key: &[u8], field: &[u8]) -> Vec<u8> {
+        let mut k = make_field_key(KeyType::BPTree, key, VALUE_TAG);
+        k.extend_from_slice(field);
+        k
+    }
+
+    pub fn len(&self) -> Result<Option<LenType>, Error> {
+        Ok(self.read_head()?.map(|h| h.len))
+    }
+
+    /// 返回最小的字段
+    pub fn first(&self) -> Result<Option<Vec<u8>>, Error> {
+        self.edge(false)
+    }
+
+    /// 返回最大的字段
+    pub fn last(&self) -> Result<Option<Vec<u8>>, Error> {
+        self.edge(true)
+    }
+
+    /// insert a key possibly splitting nodes along the way. 如果已存在返回false
+    pub fn insert(&self, field: &[u8]) -> Result<bool, Error> {
+        let head = match self.read_head()? {
+            None => {
+                let mut leaf = LeafData::new();
+                leaf.push(field.to_vec());
+                let root = Node::new(NodeType::Leaf(leaf));
+                self.put_node(&root)?;
+                self.write_head(&TreeHead { root: root.db_key, len: 1 })?;
+                return Ok(true);
+            }
+            Some(h) => h,
+        };
+        let mut root = self.get_node(&head.root)?;
+        match self.insert_node(&mut root, field)? {
+            Inserted::Exists => Ok(false),
+            Inserted::Done => {
+                self.write_head(&TreeHead {
+                    root: head.root,
+                    len: head.len + 1,
+                })?;
+                Ok(true)
+            }
+            Inserted::Split(median, sibling) => {
+                // split the root creating a new root
+                let mut children = Children::new();
+                children.push(root.db_key);
+                children.push(sibling);
+                let mut keys = Keys::new();
+                keys.push(median);
+                let new_root = Node::new(NodeType::Internal(children, keys));
+                self.put_node(&new_root)?;
+                self.write_head(&TreeHead {
+                    root: new_root.db_key,
+                    len: head.len + 1,
+                })?;
+                Ok(true)
+            }
+        }
+    }
+
+    /// delete a given key from the tree. 如果不存在返回false
+    pub fn delete(&self, field: &[u8]) -> Result<bool, Error> {
+        let head = match self.read_head()? {
+            None => return Ok(false),
+            Some(h) => h,
+        };
+        let mut root = self.get_node(&head.root)?;
+        if !self.delete_node(&mut root, field)? {
+            return Ok(false);
+        }
+        match &root.node_type {
+            NodeType::Leaf(leaf) if leaf.is_empty() => {
+                self.delete_node_data(&root.db_key)?;
+                self.t.delete(&make_head_key(KeyType::BPTree, &self.key))?;
+            }
+            //root中只有一个子节点时，子节点成为新的root，树的高度减一
+            NodeType::Internal(children, keys) if keys.is_empty() => {
+                let child = children.first().ok_or(Error::Unexpected)?.clone();
+                self.delete_node_data(&root.db_key)?;
+                self.write_head(&TreeHead {
+                    root: child,
+                    len: head.len - 1,
+                })?;
+            }
+            _ => self.write_head(&TreeHead {
+                root: head.root,
+                len: head.len - 1,
+            })?,
+        }
+        Ok(true)
+    }
+
+    fn max_keys(&self) -> usize {
+        2 * self.b - 1
+    }
+
+    fn min_keys(&self) -> usize {
+        self.b - 1
+    }
+
+    /// insert_node (recursively) finds the leaf to insert the key, 如果有修改会保存节点
+    fn insert_node(&self, node: &mut Node, field: &[u8]) -> Result<Inserted, Error> {
+        match &mut node.node_type {
+            NodeType::Leaf(leaf) => match leaf.binary_search(field) {
+                Ok(_) => return Ok(Inserted::Exists),
+                Err(idx) => leaf.insert(idx, field.to_vec()),
+            },
+            NodeType::Internal(children, keys) => {
+                let idx = keys.upper_bound(field);
+                let mut child = self.get_node(children.get(idx).ok_or(Error::Unexpected)?)?;
+                match self.insert_node(&mut child, field)? {
+                    Inserted::Split(median, sibling) => {
+                        keys.insert(idx, median);
+                        children.insert(idx + 1, sibling);
+                    }
+                    other => return Ok(other),
+                }
+            }
+            NodeType::None => return Err(Error::Unexpected),
+        }
+
+        if node.keys_len() > self.max_keys() {
+            let (median, mut sibling) = node.split(node.keys_len() / 2)?;
+            if let NodeType::Leaf(_) = &node.node_type {
+                self.link_after(node, &mut sibling)?;
+            }
+            self.put_node(&sibling)?;
+            self.put_node(node)?;
+            Ok(Inserted::Split(median, sibling.db_key))
+        } else {
+            self.put_node(node)?;
+            Ok(Inserted::Done)
+        }
+    }
+
+    /// delete_node recursively traverses the tree until it finds the given key and delete it.
+    /// 子节点underflow时，从兄弟节点借一个key或与兄弟节点合并，如果有修改会保存节点
+    fn delete_node(&self, node: &mut Node, field: &[u8]) -> Result<bool, Error> {
+        let (idx, child) = match &mut node.node_type {
+            NodeType::Leaf(leaf) => {
+                return match leaf.binary_search(field) {
+                    Ok(idx) => {
+                        leaf.remove(idx);
+                        self.put_node(node)?;
+                        Ok(true)
+                    }
+                    Err(_) => Ok(false),
+                };
+            }
+            NodeType::Internal(children, keys) => {
+                let idx = keys.upper_bound(field);
+                let mut child = self.get_node(children.get(idx).ok_or(Error::Unexpected)?)?;
+                if !self.delete_node(&mut child, field)? {
+                    return Ok(false);
+                }
+                (idx, child)
+            }
+            NodeType::None => return Err(Error::Unexpected),
+        };
+        if child.keys_len() < self.min_keys() {
+            self.rebalance(node, idx, child)?;
+            self.put_node(node)?;
+        }
+        Ok(true)
+    }
+
+    /// 处理parent中第idx个子节点的underflow
+    fn rebalance(&self, parent: &mut Node, idx: usize, mut child: Node) -> Result<(), Error> {
+        let (children, keys) = match &mut parent.node_type {
+            NodeType::Internal(children, keys) => (children, keys),
+            _ => return Err(Error::Unexpected),
+        };
+        let left = match idx {
+            0 => None,
+            _ => Some(self.get_node(children.get(idx - 1).ok_or(Error::Unexpected)?)?),
+        };
+        if let Some(mut left) = left.filter(|l| l.keys_len() > self.min_keys()) {
+            // borrow from the left sibling
+            match (&mut left.node_type, &mut child.node_type) {
+                (NodeType::Leaf(l), NodeType::Leaf(c)) => {
+                    c.insert(0, l.pop().ok_or(Error::Unexpected)?);
+                    keys.set(idx - 1, c.first().ok_or(Error::Unexpected)?.to_vec());
+                }
+                (NodeType::Internal(lc, lk), NodeType::Internal(cc, ck)) => {
+                    ck.insert(0, keys.remove(idx - 1));
+                    keys.insert(idx - 1, lk.pop().ok_or(Error::Unexpected)?);
+                    cc.insert(0, lc.pop().ok_or(Error::Unexpected)?);
+                }
+                _ => return Err(Error::Unexpected),
+            }
+            self.put_node(&left)?;
+            self.put_node(&child)?;
+            return Ok(());
+        }
+
+        let right = match children.get(idx + 1) {
+            None => None,
+            Some(k) => Some(self.get_node(k)?),
+        };
+        if let Some(mut right) = right.filter(|r| r.keys_len() > self.min_keys()) {
+            // borrow from the right sibling
+            match (&mut child.node_type, &mut right.node_type) {
+                (NodeType::Leaf(c), NodeType::Leaf(r)) => {
+                    c.push(r.remove(0));
+                    keys.set(idx, r.first().ok_or(Error::Unexpected)?.to_vec());
+                }
+                (NodeType::Internal(cc, ck), NodeType::Internal(rc, rk)) => {
+                    ck.push(keys.remove(idx));
+                    keys.insert(idx, rk.remove(0));
+                    cc.push(rc.remove(0));
+                }
+                _ => return Err(Error::Unexpected),
+            }
+            self.put_node(&child)?;
+            self.put_node(&right)?;
+            return Ok(());
+        }
+
+        // merge with a sibling, 兄弟节点都只有 b - 1 个key，合并后不会超出
+        let (sep, mut left, mut right) = if idx > 0 {
+            let left = self.get_node(children.get(idx - 1).ok_or(Error::Unexpected)?)?;
+            (idx - 1, left, child)
+        } else {
+            let right = self.get_node(children.get(idx + 1).ok_or(Error::Unexpected)?)?;
+            (idx, child, right)
+        };
+        let sep_key = keys.remove(sep);
+        children.remove(sep + 1);
+        match (&mut left.node_type, &mut right.node_type) {
+            (NodeType::Leaf(l), NodeType::Leaf(r)) => {
+                l.append(r);
+                left.next = right.next.clone();
+                if let Some(next) = &right.next {
+                    let mut next = self.get_node(next)?;
+                    next.prev = Some(left.db_key.clone());
+                    self.put_node(&next)?;
+                }
+            }
+            (NodeType::Internal(lc, lk), NodeType::Internal(rc, rk)) => {
+                lk.push(sep_key);
+                lk.append(rk);
+                lc.append(rc);
+            }
+            _ => return Err(Error::Unexpected),
+        }
+        self.put_node(&left)?;
+        self.delete_node_data(&right.db_key)
+    }
+
+    /// 把新的leaf节点right链接到left之后
+    fn link_after(&self, left: &mut Node, right: &mut Node) -> Result<(), Error> {
+        right.prev = Some(left.db_key.clone());
+        right.next = left.next.replace(right.db_key.clone());
+        if let Some(next) = &right.next {
+            let mut next = self.get_node(next)?;
+            next.prev = Some(right.db_key.clone());
+            self.put_node(&next)?;
+        }
+        Ok(())
+    }
+
+    /// 找到开始的leaf节点与位置，rev为false时从start开始向后，rev为true时从end开始向前
+    pub fn iter(self, start: Bound<Vec<u8>>, end: Bound<Vec<u8>>, rev: bool) -> Result<BTreeIter<'a, T>, Error> {
+        let head = match self.read_head()? {
+            None => {
+                return Ok(BTreeIter {
+                    tree: self,
+                    leaf: None,
+                    index: 0,
+                    start,
+                    end,
+                    rev,
+                })
+            }
+            Some(h) => h,
+        };
+        let seek = if rev { &end } else { &start };
+        let mut node = self.get_node(&head.root)?;
+        let index = loop {
+            let next = match &node.node_type {
+                NodeType::Internal(children, keys) => {
+                    let idx = match seek {
+                        Bound::Included(f) | Bound::Excluded(f) => keys.upper_bound(f),
+                        Bound::Unbounded if rev => keys.len(),
+                        Bound::Unbounded => 0,
+                    };
+                    children.get(idx).ok_or(Error::Unexpected)?.clone()
+                }
+                // 向后时index为下一个要返回的位置，向前时index为还没有返回的个数
+                NodeType::Leaf(leaf) => {
+                    break match (seek, rev) {
+                        (Bound::Included(f), false) => leaf.lower_bound(f),
+                        (Bound::Excluded(f), false) => leaf.upper_bound(f),
+                        (Bound::Unbounded, false) => 0,
+                        (Bound::Included(f), true) => leaf.upper_bound(f),
+                        (Bound::Excluded(f), true) => leaf.lower_bound(f),
+                        (Bound::Unbounded, true) => leaf.len(),
+                    };
+                }
+                NodeType::None => return Err(Error::Unexpected),
+            };
+            node = self.get_node(&next)?;
+        };
+        Ok(BTreeIter {
+            tree: self,
+            leaf: Some(node),
+            index,
+            start,
+            end,
+            rev,
+        })
+    }
+
+    fn edge(&self, last: bool) -> Result<Option<Vec<u8>>, Error> {
+        let head = match self.read_head()? {
+            None => return Ok(None),
+            Some(h) => h,
+        };
+        let mut node = self.get_node(&head.root)?;
+        loop {
+            let next = match &node.node_type {
+                NodeType::Leaf(leaf) => {
+                    let field = if last { leaf.last() } else { leaf.first() };
+                    return Ok(field.map(|f| f.to_vec()));
+                }
+                NodeType::Internal(children, _) => {
+                    let child = if last { children.last() } else { children.first() };
+                    child.ok_or(Error::Unexpected)?.clone()
+                }
+                NodeType::None => return Err(Error::Unexpected),
+            };
+            node = self.get_node(&next)?;
+        }
     }
-    fn is_node_full(&self, node: &Node) -> Result<bool, Error> {
-        match &node.node_type {
-            NodeType::Leaf(pairs) => Ok(pairs.number_keys == (2 * self.b - 1)),
-            NodeType::Internal(_, keys) => Ok(keys.number_keys == (2 * self.b - 1)),
-            NodeType::None => Err(Error::Unexpected),
+
+    fn read_head(&self) -> Result<Option<TreeHead>, Error> {
+        match self.t.get(&make_head_key(KeyType::BPTree, &self.key))? {
+            None => Ok(None),
+            Some(v) => Ok(Some(TreeHead::read(&v)?)),
         }
     }
 
-    fn is_node_underflow(&self, node: &Node) -> Result<bool, Error> {
-        match &node.node_type {
-            // A root cannot really be "underflowing" as it can contain less than b-1 keys / pointers.
-            NodeType::Leaf(pairs) => Ok(pairs.number_keys < self.b - 1 && !node.is_root()),
-            NodeType::Internal(_, keys) => Ok(keys.number_keys < self.b - 1 && !node.is_root()),
-            NodeType::None => Err(Error::Unexpected),
+    fn write_head(&self, head: &TreeHead) -> Result<(), Error> {
+        self.t.put(&make_head_key(KeyType::BPTree, &self.key), &head.to_bytes())?;
+        Ok(())
+    }
+
+    fn node_key(&self, db_key: &DbKey) -> Vec<u8> {
+        let mut k = make_field_key(KeyType::BPTree, &self.key, NODE_TAG);
+        k.extend_from_slice(db_key.key());
+        k
+    }
+
+    fn get_node(&self, db_key: &DbKey) -> Result<Node, Error> {
+        let data = self.t.get(&self.node_key(db_key))?.ok_or(Error::KeyNotFound)?;
+        Node::try_from(data.as_slice())
+    }
+
+    fn put_node(&self, node: &Node) -> Result<(), Error> {
+        self.t.put(&self.node_key(&node.db_key), &node.to_bytes())?;
+        Ok(())
+    }
+
+    fn delete_node_data(&self, db_key: &DbKey) -> Result<(), Error> {
+        self.t.delete(&self.node_key(db_key))?;
+        Ok(())
+    }
+}
+
+/// 按字段的顺序遍历，通过leaf节点之间的链接移动，不用每次都从root开始
+pub struct BTreeIter<'a, T: WrapDb> {
+    tree: BTree<'a, T>,
+    /// 为None时遍历结束
+    leaf: Option<Node>,
+    index: usize,
+    start: Bound<Vec<u8>>,
+    end: Bound<Vec<u8>>,
+    rev: bool,
+}
+
+impl<'a, T: WrapDb> BTreeIter<'a, T> {
+    fn in_range(&self, field: &[u8]) -> bool {
+        if self.rev {
+            match &self.start {
+                Bound::Included(s) => field >= s.as_slice(),
+                Bound::Excluded(s) => field > s.as_slice(),
+                Bound::Unbounded => true,
+            }
+        } else {
+            match &self.end {
+                Bound::Included(e) => field <= e.as_slice(),
+                Bound::Excluded(e) => field < e.as_slice(),
+                Bound::Unbounded => true,
+            }
+        }
+    }
+
+    /// 移到下一个(rev时为前一个)leaf节点
+    fn move_leaf(&mut self) -> Result<(), Error> {
+        let leaf = match self.leaf.take() {
+            None => return Ok(()),
+            Some(l) => l,
+        };
+        let link = if self.rev { leaf.prev } else { leaf.next };
+        if let Some(k) = link {
+            let node = self.tree.get_node(&k)?;
+            self.index = if self.rev { node.keys_len() } else { 0 };
+            self.leaf = Some(node);
+        }
+        Ok(())
+    }
+}
+
+impl<'a, T: WrapDb> Iterator for BTreeIter<'a, T> {
+    type Item = Result<Vec<u8>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let leaf = match &self.leaf {
+                None => return None,
+                Some(Node {
+                    node_type: NodeType::Leaf(leaf),
+                    ..
+                }) => leaf,
+                Some(_) => {
+                    self.leaf = None;
+                    return Some(Err(Error::Unexpected));
+                }
+            };
+            let field = if self.rev {
+                match self.index {
+                    0 => None,
+                    i => leaf.get(i - 1),
+                }
+            } else {
+                leaf.get(self.index)
+            };
+            match field {
+                Some(f) => {
+                    let f = f.to_vec();
+                    if !self.in_range(&f) {
+                        self.leaf = None;
+                        return None;
+                    }
+                    if self.rev {
+                        self.index -= 1;
+                    } else {
+                        self.index += 1;
+                    }
+                    return Some(Ok(f));
+                }
+                None => {
+                    if let Err(e) = self.move_leaf() {
+                        return Some(Err(e));
+                    }
+                }
+            }
         }
     }
-    //
-    //     fn set_parent_of_children(&self, node: &mut Node) -> Result<(), Error> {
-    //         match &node.node_type {
-    //             NodeType::Internal(children, _) => {
-    //                 let mut offset = children.offset + Children::OFFSET_DATA;
-    //                 let mut child_db_key = DbKey::ZERO_KEY.clone();
-    //                 let parent_db_key = node.parent_db_key().key();
-    //
-    //                 for _ in 0..children.number_children as isize {
-    //                     unsafe {
-    //                         std::ptr::copy_nonoverlapping(node.data.as_ptr().offset(offset), child_db_key.as_mut_ptr(), DbKey::LEN_DB_KEY);
-    //                     }
-    //
-    //                     let mut data = self.t.get(&child_db_key)?.ok_or(Error::KeyNotFound)?;
-    //
-    //                     Node::set_parent_db_key_data(data.as_mut_slice(), parent_db_key);
-    //                     self.t.put(&child_db_key, data.as_slice())?;
-    //
-    //                     offset += DbKey::LEN_DB_KEY as isize;
-    //                 }
-    //             }
-    //             NodeType::Leaf(leaf) => {
-    //                 //todo
-    //             }
-    //             NodeType::None => {}
-    //         }
-    //         Ok(())
-    //     }
-    //
-    //     /// insert a key value pair possibly splitting nodes along the way.
-    //     pub fn insert(&mut self, k: &[u8], v: &[u8]) -> Result<(), Error> {
-    //         // let new_root_offset: Offset;
-    //         let mut new_root: Node;
-    //         let head_key = make_head_key(self.key);
-    //         let mut root = {
-    //             match self.t.get(&head_key)? {
-    //                 Some(data) => Node::try_from(data)?,
-    //                 None => Node::new(NodeType::Internal(Children::new(), VecBytes::new()))
-    //             }
-    //         };
-    //
-    //         if self.is_node_full(&root)? {
-    //             // split the root creating a new root and child nodes along the way.
-    //             let (median, mut sibling) = root.split(self.b)?;
-    //             new_root = Node::new(NodeType::Internal(Children::new(), VecBytes::new()));
-    //             new_root.set_parent_none();
-    //
-    //             root.set_parent_db_key(new_root.db_key().key());
-    //             sibling.set_parent_db_key(new_root.db_key().key());
-    //
-    //             let children = Children::add(&mut new_root, &vec![root.db_key().key(), sibling.db_key().key()]);
-    //
-    //             VecBytes::<KeyMetas>::add(&mut new_root.data, children.offset_keys(), &vec![median.as_slice()]);
-    //
-    //             self.t.put(sibling.db_key().key(), &sibling.data)?;
-    //             self.t.put(root.db_key().key(), &root.data)?;
-    //             self.t.put(new_root.db_key().key(), &new_root.data)?;
-    //             //sibling的所有中的child的每一个 parent_key已变化，所以需要更新
-    //             self.set_parent_of_children(&mut sibling)?;
-    //
-    //             // continue recursively.
-    //             self.insert_non_full(&mut new_root, k, v)?;
-    //             self.t.put(&head_key, &new_root.data)?;
-    //         } else {
-    //             // continue recursively.
-    //             self.insert_non_full(&mut root, k, v)?;
-    //             // finish by setting the root to its new copy.
-    //             self.t.put(&head_key, &root.data)?;
-    //         }
-    //
-    //         Ok(())
-    //     }
-    //
-    //     /// insert_non_full (recursively) finds a node rooted at a given non-full node.
-    //     /// to insert a given key-value pair. Here we assume the node is
-    //     /// already a copy of an existing node in a copy-on-write root to node traversal.
-    //     fn insert_non_full(
-    //         &mut self,
-    //         node: &mut Node,
-    //         k: &[u8], v: &[u8],
-    //     ) -> Result<(), Error> {
-    //         match &mut node.node_type {
-    //             NodeType::Leaf(ref mut pairs) => {
-    //                 let kv = KeyValue::to_vec(k, v);
-    //                 pairs.insert_with_index(&mut node.data, kv.as_slice(), None);
-    //                 self.t.put(node.db_key().key(), &node.data)?;
-    //                 Ok(())
-    //             }
-    //             NodeType::Internal(ref mut children, ref mut keys) => {
-    //                 let idx = keys.binary_search(&mut node.data, k).unwrap_or_else(|x| x);
-    //                 let child_db_key = children.get(&node.data, idx);
-    //                 let bytes = self.t.get(child_db_key.key())?.ok_or(Error::KeyNotFound)?;
-    //                 let mut child = Node::try_from(bytes)?;
-    //                 if self.is_node_full(&child)? {
-    //                     let (median, mut sibling) = child.split(self.b)?;
-    //                     sibling.set_parent_db_key(node.db_key().key());
-    //                     children.insert(idx + 1, sibling.db_key().key());
-    //                     keys.insert(idx, median.clone());
-    //
-    //                     self.t.put(sibling.db_key().key(), &sibling.data)?;
-    //                     self.t.put(child.db_key().key(), &child.data)?;
-    //                     //sibling的所有中的child的每一个 parent_key已变化，所以需要更新
-    //                     self.set_parent_of_children(&mut sibling)?;
-    //
-    //
-    //                     if k <= median.0 {
-    //                         self.insert_non_full(&mut child, k, v)
-    //                     } else {
-    //                         self.insert_non_full(&mut sibling, k, v)
-    //                     }
-    //                 } else {
-    //                     // self.pager.write_page_at_offset(Page::try_from(&*node)?, &node_offset)?;
-    //                     self.insert_non_full(&mut child, k, v)
-    //                 }
-    //
-    //                 self.t.put(node.db_key().key(), &node.data)?;
-    //                 Ok(())
-    //             }
-    //             NodeType::None => Err(Error::UnexpectedError),
-    //         }
-    //     }
-    //
-    //     /// search searches for a specific key in the BTree.
-    //     pub fn search(&mut self, key: &[u8]) -> Result<KeyValue, Error> {
-    //         let root_offset = self.wal.get_root()?;
-    //         let root_page = self.pager.get_page(&root_offset)?;
-    //         let root = Node::try_from(root_page)?;
-    //         self.search_node(root, &key)
-    //     }
-    //
-    //     /// search_node recursively searches a sub tree rooted at node for a key.
-    //     fn search_node(&mut self, node: Node, search: &[u8]) -> Result<KeyValue, Error> {
-    //         match node.node_type {
-    //             NodeType::Internal(children, keys) => {
-    //                 let idx = keys.binary_search(&node.data, search)
-    //                     .unwrap_or_else(|x| x);
-    //                 // Retrieve child page from disk and deserialize.
-    //                 let child_offset = children.get(idx).ok_or(Error::UnexpectedError)?;
-    //                 let page = self.pager.get_page(child_offset)?;
-    //                 let child_node = Node::try_from(page)?;
-    //                 self.search_node(child_node, search)
-    //             }
-    //             NodeType::Leaf(pairs) => {
-    //                 if let Ok(idx) =
-    //                     pairs.binary_search_by_key(&search.to_string(), |pair| pair.key.clone())
-    //                 {
-    //                     return Ok(pairs[idx].clone());
-    //                 }
-    //                 Err(Error::KeyNotFound)
-    //             }
-    //             NodeType::None => Err(Error::UnexpectedError),
-    //         }
-    //     }
-    //
-    //     /// delete deletes a given key from the tree.
-    //     pub fn delete(&mut self, key: &[u8]) -> Result<(), Error> {
-    //         let root_offset = self.wal.get_root()?;
-    //         let root_page = self.pager.get_page(&root_offset)?;
-    //         // Shadow the new root and rewrite it.
-    //         let mut new_root = Node::try_from(root_page)?;
-    //         let new_root_page = Page::try_from(&new_root)?;
-    //         let new_root_offset = self.pager.write_page(new_root_page)?;
-    //         self.delete_key_from_subtree(key, &mut new_root)?;
-    //         self.wal.set_root(new_root_offset)
-    //     }
-    //
-    //     /// delete key from subtree recursively traverses a tree rooted at a node in certain offset
-    //     /// until it finds the given key and delete the key-value pair. Here we assume the node is
-    //     /// already a copy of an existing node in a copy-on-write root to node traversal.
-    //     fn delete_key_from_subtree(
-    //         &mut self,
-    //         key: &[u8],
-    //         node: &mut Node,
-    //     ) -> Result<(), Error> {
-    //         match &mut node.node_type {
-    //             NodeType::Leaf(ref mut pairs) => {
-    //                 let key_idx = pairs
-    //                     .binary_search_by_key(&key, |kv| Key(kv.key.clone()))
-    //                     .map_err(|_| Error::KeyNotFound)?;
-    //                 pairs.remove(key_idx);
-    //                 // self.pager
-    //                 //     .write_page_at_offset(Page::try_from(&*node)?, node_offset)?;
-    //                 // Check for underflow - if it occures,
-    //                 // we need to merge with a sibling.
-    //                 // this can only occur if node is not the root (as it cannot "underflow").
-    //                 // continue recoursively up the tree.
-    //                 self.borrow_if_needed(node.to_owned(), &key)?;
-    //             }
-    //             NodeType::Internal(children, keys) => {
-    //                 let node_idx = keys.binary_search(&key).unwrap_or_else(|x| x);
-    //                 // Retrieve child page from disk and deserialize,
-    //                 // copy over the child page and continue recursively.
-    //                 let child_offset = children.get(node_idx).ok_or(Error::UnexpectedError)?;
-    //                 let child_page = self.pager.get_page(child_offset)?;
-    //                 let mut child_node = Node::try_from(child_page)?;
-    //                 // Fix the parent_offset as the child node is a child of a copied parent
-    //                 // in a copy-on-write root to leaf traversal.
-    //                 // This is important for the case of a node underflow which might require a leaf to root traversal.
-    //                 // child_node.parent_key = Some(node_offset.to_owned());
-    //                 let new_child_page = Page::try_from(&child_node)?;
-    //                 let new_child_offset = self.pager.write_page(new_child_page)?;
-    //                 // Assign the new pointer in the parent and continue reccoursively.
-    //                 children[node_idx] = new_child_offset.to_owned();
-    //                 // self.pager
-    //                 //     .write_page_at_offset(Page::try_from(&*node)?, node_offset)?;
-    //                 return self.delete_key_from_subtree(key, &mut child_node, &new_child_offset);
-    //             }
-    //             NodeType::None => return Err(Error::UnexpectedError),
-    //         }
-    //         Ok(())
-    //     }
-    //
-    //     /// borrow_if_needed checks the node for underflow (following a removal of a key),
-    //     /// if it underflows it is merged with a sibling node, and than called recoursively
-    //     /// up the tree. Since the downward root-to-leaf traversal was done using the copy-on-write
-    //     /// technique we are ensured that any merges will only be reflected in the copied parent in the path.
-    //     fn borrow_if_needed(&mut self, node: Node, key: &[u8]) -> Result<(), Error> {
-    //         if self.is_node_underflow(&node)? {
-    //             // Fetch the sibling from the parent -
-    //             // This could be quicker if we implement sibling pointers.
-    //             let parent_offset = node.parent_key.clone().ok_or(Error::UnexpectedError)?;
-    //             let parent_page = self.pager.get_page(&parent_offset)?;
-    //             let mut parent_node = Node::try_from(parent_page)?;
-    //             // The parent has to be an "internal" node.
-    //             match parent_node.node_type {
-    //                 NodeType::Internal(ref mut children, ref mut keys) => {
-    //                     let idx = keys.binary_search(key).unwrap_or_else(|x| x);
-    //                     // The sibling is in idx +- 1 as the above index led
-    //                     // the downward search to node.
-    //                     let sibling_idx;
-    //                     match idx > 0 {
-    //                         false => sibling_idx = idx + 1,
-    //                         true => sibling_idx = idx - 1,
-    //                     }
-    //
-    //                     let sibling_offset = children.get(sibling_idx).ok_or(Error::UnexpectedError)?;
-    //                     let sibling_page = self.pager.get_page(sibling_offset)?;
-    //                     let sibling = Node::try_from(sibling_page)?;
-    //                     let merged_node = self.merge(node, sibling)?;
-    //                     let merged_node_offset =
-    //                         self.pager.write_page(Page::try_from(&merged_node)?)?;
-    //                     let merged_node_idx = cmp::min(idx, sibling_idx);
-    //                     // remove the old nodes.
-    //                     children.remove(merged_node_idx);
-    //                     // remove shifts nodes to the left.
-    //                     children.remove(merged_node_idx);
-    //                     // if the parent is the root, and there is a single child - the merged node -
-    //                     // we can safely replace the root with the child.
-    //                     if parent_node.is_root && children.is_empty() {
-    //                         self.wal.set_root(merged_node_offset)?;
-    //                         return Ok(());
-    //                     }
-    //                     // remove the keys that separated the two nodes from each other:
-    //                     keys.remove(idx);
-    //                     // write the new node in place.
-    //                     children.insert(merged_node_idx, merged_node_offset);
-    //                     // write the updated parent back to disk and continue up the tree.
-    //                     self.pager
-    //                         .write_page_at_offset(Page::try_from(&parent_node)?, &parent_offset)?;
-    //                     return self.borrow_if_needed(parent_node, key);
-    //                 }
-    //                 _ => return Err(Error::UnexpectedError),
-    //             }
-    //         }
-    //         Ok(())
-    //     }
-    //
-    //     // merges two *sibling* nodes, it assumes the following:
-    //     // 1. the two nodes are of the same type.
-    //     // 2. the two nodes do not accumulate to an overflow,
-    //     // i.e. |first.keys| + |second.keys| <= [2*(b-1) for keys or 2*b for offsets].
-    //     fn merge(&self, first: Node, second: Node) -> Result<Node, Error> {
-    //         match first.node_type {
-    //             NodeType::Leaf(first_pairs) => {
-    //                 if let NodeType::Leaf(second_pairs) = second.node_type {
-    //                     let merged_pairs: LeafData = first_pairs
-    //                         .into_iter()
-    //                         .chain(second_pairs.into_iter())
-    //                         .collect();
-    //                     let node_type = NodeType::Leaf(merged_pairs);
-    //                     Ok(Node::new(node_type))
-    //                 } else {
-    //                     Err(Error::UnexpectedError)
-    //                 }
-    //             }
-    //             NodeType::Internal(first_offsets, first_keys) => {
-    //                 if let NodeType::Internal(second_offsets, second_keys) = second.node_type {
-    //                     let merged_keys = first_keys
-    //                         .into_iter()
-    //                         .chain(second_keys.into_iter())
-    //                         .collect();
-    //                     let merged_offsets = first_offsets
-    //                         .into_iter()
-    //                         .chain(second_offsets.into_iter())
-    //                         .collect();
-    //                     let node_type = NodeType::Internal(merged_offsets, merged_keys);
-    //                     Ok(Node::new(node_type))
-    //                 } else {
-    //                     Err(Error::UnexpectedError)
-    //                 }
-    //             }
-    //             NodeType::None => Err(Error::UnexpectedError),
-    //         }
-    //     }
-    //
-    //     /// print_sub_tree is a helper function for recursively printing the nodes rooted at a node given by its offset.
-    //     fn print_sub_tree(&mut self, prefix: String) -> Result<(), Error> {
-    //         // println!("{}Node at offset: {}", prefix, offset.0);
-    //         let curr_prefix = format!("{}|->", prefix);
-    //         // let page = self.pager.get_page(&offset)?;
-    //         let node = Node::try_from(vec![])?;
-    //         match node.node_type {
-    //             NodeType::Internal(children, keys) => {
-    //                 println!("{}Keys: {:?}", curr_prefix, keys);
-    //                 println!("{}Children: {:?}", curr_prefix, children);
-    //                 let child_prefix = format!("{}   |  ", prefix);
-    //                 for child_offset in children {
-    //                     self.print_sub_tree(child_prefix.clone())?;
-    //                 }
-    //                 Ok(())
-    //             }
-    //             NodeType::Leaf(pairs) => {
-    //                 println!("{}Key value pairs: {:?}", curr_prefix, pairs);
-    //                 Ok(())
-    //             }
-    //             NodeType::None => Err(Error::UnexpectedError),
-    //         }
-    //     }
-    //
-    //     /// print is a helper for recursively printing the tree.
-    //     pub fn print(&mut self) -> Result<(), Error> {
-    //         println!();
-    //         let root_offset = self.wal.get_root()?;
-    //         self.print_sub_tree("".to_string())
-    //     }
 }
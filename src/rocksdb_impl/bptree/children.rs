@@ -1,99 +1,85 @@
 use std::mem::size_of;
 
-use crate::{
-    read_int_ptr,
-    rocksdb_impl::bptree::{db_key::DbKey, node::Node, node_type::NodeType},
-    write_int_ptr, BytesType, LenType,
-};
+use crate::{read_int, rocksdb_impl::bptree::db_key::DbKey, write_int, BytesType, LenType};
 
-#[derive(Clone, Debug)]
+/// 子节点的db key，持久化的格式如下
+/// number_children(LenType) bytes_number(BytesType) db_key * number_children
+#[derive(Clone, Debug, Default)]
 pub struct Children {
-    pub number_children: LenType,
-    // //单位是 byte, 不是key的个数
-    pub bytes_number: BytesType,
-    pub offset: isize,
+    db_keys: Vec<DbKey>,
 }
 
-impl From<&[u8]> for Children {
-    fn from(data: &[u8]) -> Self {
-        let offset = Node::OFFSET_NODE_DATA;
-        let number_children = read_int_ptr(unsafe { data.as_ptr().offset(offset) });
-        let bytes_number = read_int_ptr(unsafe { data.as_ptr().offset(offset + size_of::<LenType>() as isize) });
-        Self {
-            offset,
-            number_children,
-            bytes_number,
-        }
+impl Children {
+    pub const OFFSET_DATA: usize = size_of::<LenType>() + size_of::<BytesType>();
+
+    pub fn new() -> Self {
+        Children { db_keys: Vec::new() }
     }
-}
 
-impl Default for Children {
-    fn default() -> Self {
-        Self::new()
+    pub fn len(&self) -> usize {
+        self.db_keys.len()
     }
-}
 
-impl Children {
-    pub const OFFSET_DATA: isize = (size_of::<LenType>() + size_of::<BytesType>()) as isize;
-    pub fn new() -> Self {
-        Children {
-            number_children: 0,
-            offset: Node::OFFSET_NODE_DATA,
-            bytes_number: 0,
-        }
+    pub fn get(&self, index: usize) -> Option<&DbKey> {
+        self.db_keys.get(index)
     }
-    pub fn read_from(&mut self, data: &[u8]) {
-        self.offset = Node::OFFSET_NODE_DATA;
-        unsafe {
-            self.number_children = read_int_ptr(data.as_ptr().offset(self.offset));
-            self.bytes_number = read_int_ptr(data.as_ptr().offset(self.offset + size_of::<LenType>() as isize));
-        }
+
+    pub fn first(&self) -> Option<&DbKey> {
+        self.db_keys.first()
     }
 
-    pub fn get(&self, data: &[u8], index: usize) -> DbKey {
-        let start = self.offset as usize + Children::OFFSET_DATA as usize + index * DbKey::LEN_DB_KEY;
-        DbKey::from(&data[start..])
+    pub fn last(&self) -> Option<&DbKey> {
+        self.db_keys.last()
     }
 
-    pub fn set_number_children(&mut self, number_children: LenType, data: &mut [u8]) {
-        self.number_children = number_children;
-        self.bytes_number = self.number_children as BytesType * DbKey::LEN_DB_KEY as BytesType;
-        unsafe {
-            write_int_ptr(data.as_mut_ptr().offset(self.offset), self.number_children);
-            write_int_ptr(data.as_mut_ptr().offset(self.offset + size_of::<LenType>() as isize), self.bytes_number);
+    pub fn insert(&mut self, index: usize, db_key: DbKey) {
+        self.db_keys.insert(index, db_key);
+    }
+
+    pub fn remove(&mut self, index: usize) -> DbKey {
+        self.db_keys.remove(index)
+    }
+
+    pub fn push(&mut self, db_key: DbKey) {
+        self.db_keys.push(db_key);
+    }
+
+    pub fn pop(&mut self) -> Option<DbKey> {
+        self.db_keys.pop()
+    }
+
+    /// 返回 [at, len) 的部分，自己保留 [0, at)
+    pub fn split_off(&mut self, at: usize) -> Self {
+        Children {
+            db_keys: self.db_keys.split_off(at),
         }
     }
 
-    pub fn offset_keys(&self) -> isize {
-        self.offset + self.bytes_number as isize + Children::OFFSET_DATA
+    pub fn append(&mut self, other: &mut Self) {
+        self.db_keys.append(&mut other.db_keys);
+    }
+
+    /// 从data的offset处读取，返回值 0: 数据，1: 读取的字节数。如果数据不完整返回None
+    pub fn read_from(data: &[u8], offset: usize) -> Option<(Self, usize)> {
+        let data = data.get(offset..)?;
+        if data.len() < Children::OFFSET_DATA {
+            return None;
+        }
+        let number_children = usize::try_from(read_int::<LenType>(data)).ok()?;
+        let end = Children::OFFSET_DATA.checked_add(number_children.checked_mul(DbKey::LEN_DB_KEY)?)?;
+        let bytes = data.get(Children::OFFSET_DATA..end)?;
+        let db_keys = bytes.chunks_exact(DbKey::LEN_DB_KEY).map(DbKey::from).collect();
+        Some((Children { db_keys }, end))
     }
 
-    pub fn add(node: &mut Node, children: &[&[u8]]) -> Children {
-        if let NodeType::Internal(old_children, _) = &mut node.node_type {
-            let new_bytes = DbKey::LEN_DB_KEY * children.len();
-            node.data.reserve_exact(new_bytes);
-            unsafe {
-                //移动keys的数据
-                let _old = old_children.number_children;
-                let offset_keys = old_children.offset + Children::OFFSET_DATA + old_children.bytes_number as isize;
-                std::ptr::copy(
-                    node.data.as_mut_ptr().offset(offset_keys),
-                    node.data.as_mut_ptr().offset(offset_keys + new_bytes as isize),
-                    node.data.len() - offset_keys as usize - new_bytes,
-                );
-                node.data.set_len(node.data.len() + new_bytes);
-            }
-            let mut offset_keys = old_children.offset + Children::OFFSET_DATA + old_children.bytes_number as isize;
-            for k in children {
-                unsafe {
-                    std::ptr::copy_nonoverlapping(k.as_ptr(), node.data.as_mut_ptr().offset(offset_keys), k.len());
-                }
-                offset_keys += k.len() as isize;
-            }
-            old_children.set_number_children(old_children.number_children + children.len() as LenType, &mut node.data);
-            old_children.clone()
-        } else {
-            panic!("the node type is not Internal");
+    /// 追加到data的尾部
+    pub fn write_to(&self, data: &mut Vec<u8>) {
+        let start = data.len();
+        data.resize(start + Children::OFFSET_DATA, 0);
+        write_int(&mut data[start..], self.db_keys.len() as LenType);
+        write_int(&mut data[start + size_of::<LenType>()..], (self.db_keys.len() * DbKey::LEN_DB_KEY) as BytesType);
+        for k in &self.db_keys {
+            data.extend_from_slice(k.key());
         }
     }
 }
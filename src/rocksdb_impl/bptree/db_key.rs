@@ -1,3 +1,5 @@
+use crate::rocksdb_impl::bptree::kits::new_db_key;
+
 #[derive(Clone, Eq, PartialEq, Debug)]
 pub struct DbKey {
     data: [u8; DbKey::LEN_DB_KEY],
@@ -22,6 +24,12 @@ impl From<&[u8]> for DbKey {
 impl DbKey {
     pub const LEN_DB_KEY: usize = 12;
     pub const ZERO_KEY: [u8; DbKey::LEN_DB_KEY] = [0; DbKey::LEN_DB_KEY];
+
+    /// 生成一个新的唯一的key
+    pub fn new() -> Self {
+        DbKey { data: new_db_key() }
+    }
+
     pub fn key(&self) -> &[u8] {
         &self.data
     }
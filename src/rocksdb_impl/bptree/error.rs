@@ -23,3 +23,15 @@ impl From<RrError> for Error {
         Error::RrError(e)
     }
 }
+
+impl From<Error> for RrError {
+    fn from(e: Error) -> RrError {
+        match e {
+            Error::RrError(e) => e,
+            Error::KeyNotFound => RrError::not_find("key of bptree"),
+            Error::TryFromSlice(name) => RrError::corruption(&[], &format!("bptree: {}", name)),
+            Error::UTF8 => RrError::corruption(&[], "bptree: utf8"),
+            _ => RrError::message(format!("bptree: {:?}", e)),
+        }
+    }
+}
@@ -1,3 +1,6 @@
-pub fn new_db_key() -> Vec<u8> {
-    xid::new().to_string().as_bytes().to_vec()
+use crate::rocksdb_impl::bptree::db_key::DbKey;
+
+/// xid的12个bytes，不使用字符串形式（20个字符），否则截断为12个bytes时会重复
+pub fn new_db_key() -> [u8; DbKey::LEN_DB_KEY] {
+    *xid::new().as_bytes()
 }
@@ -4,6 +4,7 @@
 // pub use page::*;
 // pub use page_layout::*;
 // pub use wal::*;
+pub use bptree_impl::{BPTreeImpl, BPTreeIter};
 
 mod bptree_impl;
 mod btree;
@@ -1,236 +1,135 @@
 use std::{convert::TryFrom, mem::size_of};
 
 use super::{error::Error, node_type::NodeType};
-use crate::{
-    datas::VecBytes,
-    read_int_ptr,
-    rocksdb_impl::bptree::{children::Children, db_key::DbKey, kits::new_db_key, leaf_data::LeafData, node_type::Keys},
-    BytesType, LenType,
-};
+use crate::rocksdb_impl::bptree::{children::Children, db_key::DbKey, node_type::Keys};
 
 /// Node represents a node in the BTree occupied by a single page in memory.
 #[derive(Clone, Debug)]
 pub struct Node {
     pub node_type: NodeType,
-    pub data: Vec<u8>,
+    pub db_key: DbKey,
+    /// 只有leaf节点使用，前一个与后一个leaf节点，遍历时不用每次都从root开始
+    pub prev: Option<DbKey>,
+    pub next: Option<DbKey>,
 }
 
 // Node represents a node in the B-Tree.
 impl Node {
-    /// Common Node header layout (Ten bytes in total)
-    pub const OFFSET_NODE_TYPE: isize = 0;
-    pub const OFFSET_DB_KEY: isize = Node::OFFSET_NODE_TYPE + size_of::<u8>() as isize;
-    pub const OFFSET_PARENT_DB_KEY: isize = Node::OFFSET_DB_KEY + DbKey::LEN_DB_KEY as isize;
-    pub const OFFSET_NODE_DATA: isize = Node::OFFSET_PARENT_DB_KEY + DbKey::LEN_DB_KEY as isize;
+    /// Common Node header layout
+    /// node type(u8) db key(12) parent db key(12)
+    /// parent db key只是保留了位置，并没有使用，总是写入0。因为分裂与合并时要更新所有被移动的子节点，代价太大，
+    /// 所有的操作都是从root开始向下进行的，不需要它
+    /// leaf节点在header之后是 prev db key(12) next db key(12)，全0表示没有，然后是LeafData
+    pub const OFFSET_NODE_TYPE: usize = 0;
+    pub const OFFSET_DB_KEY: usize = Node::OFFSET_NODE_TYPE + size_of::<u8>();
+    pub const OFFSET_PARENT_DB_KEY: usize = Node::OFFSET_DB_KEY + DbKey::LEN_DB_KEY;
+    pub const OFFSET_NODE_DATA: usize = Node::OFFSET_PARENT_DB_KEY + DbKey::LEN_DB_KEY;
+    const LEN_LINKS: usize = DbKey::LEN_DB_KEY * 2;
 
     pub fn new(node_type: NodeType) -> Node {
-        let mut data = Vec::with_capacity(Node::OFFSET_NODE_DATA as usize);
-        // data.resize(data.capacity(), 0);
-        data[0] = u8::from(&node_type);
-        let mut node = Node { node_type, data };
-        node.set_node_type(&[]);
-        node.make_db_key();
-        node
-    }
-
-    pub fn set_node_type(&mut self, data: &[u8]) {
-        let node_type = &self.node_type;
-        match node_type {
-            NodeType::None => {
-                unsafe {
-                    self.data.set_len(Node::OFFSET_NODE_DATA as usize);
-                }
-                let t = [0_u8; Node::OFFSET_NODE_DATA as usize];
-                self.data.extend_from_slice(&t);
-            }
-            NodeType::Internal(_children, _keys) => {
-                if data.is_empty() {
-                    unsafe {
-                        self.data.set_len(Node::OFFSET_NODE_DATA as usize);
-                    }
-                    let t = [0_u8; Node::OFFSET_NODE_DATA as usize];
-                    self.data.extend_from_slice(&t);
-                } else {
-                    self.data.reserve(data.len() - self.data.len());
-                    unsafe {
-                        self.data.set_len(data.len());
-                    }
-                    self.data.copy_from_slice(data);
-                }
-            }
-            NodeType::Leaf(_leaf) => {
-                if data.is_empty() {
-                    unsafe {
-                        self.data.set_len(Node::OFFSET_NODE_DATA as usize);
-                    }
-                    let t = [0_u8; Node::OFFSET_NODE_DATA as usize];
-                    self.data.extend_from_slice(&t);
-                } else {
-                    self.data.reserve(data.len() - self.data.len());
-                    unsafe {
-                        self.data.set_len(data.len());
-                    }
-                    self.data.copy_from_slice(data);
-                }
-            }
+        Node {
+            node_type,
+            db_key: DbKey::new(),
+            prev: None,
+            next: None,
         }
     }
 
-    pub fn is_root(&self) -> bool {
-        let parent = self.parent_db_key();
-        parent.key().eq(&DbKey::ZERO_KEY)
-    }
-
-    pub fn make_db_key(&mut self) {
-        let n = new_db_key();
-        unsafe { std::ptr::copy(n.as_ptr(), self.data.as_mut_ptr().offset(Node::OFFSET_DB_KEY), n.len()) }
-    }
-
-    pub fn db_key(&self) -> DbKey {
-        DbKey::from(&self.data[Node::OFFSET_DB_KEY as usize..Node::OFFSET_DB_KEY as usize + DbKey::LEN_DB_KEY])
-    }
-
-    pub fn set_db_key(&mut self, key: &[u8]) {
-        unsafe {
-            std::ptr::copy(key.as_ptr(), self.data.as_mut_ptr().offset(Node::OFFSET_DB_KEY), key.len());
+    /// key的个数
+    pub fn keys_len(&self) -> usize {
+        match &self.node_type {
+            NodeType::Internal(_, keys) => keys.len(),
+            NodeType::Leaf(leaf) => leaf.len(),
+            NodeType::None => 0,
         }
     }
 
-    pub fn parent_db_key(&self) -> DbKey {
-        DbKey::from(&self.data[Node::OFFSET_PARENT_DB_KEY as usize..Node::OFFSET_PARENT_DB_KEY as usize + DbKey::LEN_DB_KEY])
-    }
-    pub fn set_parent_db_key(&mut self, key: &[u8]) {
-        Node::set_parent_db_key_data(&mut self.data, key);
-    }
-
-    pub fn set_parent_none(&mut self) {
-        Node::set_parent_db_key_data(&mut self.data, &DbKey::ZERO_KEY);
+    fn read_link(data: &[u8]) -> Option<DbKey> {
+        let key = &data[..DbKey::LEN_DB_KEY];
+        if key == DbKey::ZERO_KEY {
+            None
+        } else {
+            Some(DbKey::from(key))
+        }
     }
 
-    pub fn set_parent_db_key_data(data: &mut [u8], key: &[u8]) {
-        unsafe {
-            std::ptr::copy(key.as_ptr(), data.as_mut_ptr().offset(Node::OFFSET_PARENT_DB_KEY), key.len());
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut data = Vec::with_capacity(Node::OFFSET_NODE_DATA);
+        data.push(u8::from(&self.node_type));
+        data.extend_from_slice(self.db_key.key());
+        data.extend_from_slice(&DbKey::ZERO_KEY);
+        match &self.node_type {
+            NodeType::Internal(children, keys) => {
+                children.write_to(&mut data);
+                keys.write_to(&mut data);
+            }
+            NodeType::Leaf(leaf) => {
+                for link in [&self.prev, &self.next] {
+                    match link {
+                        None => data.extend_from_slice(&DbKey::ZERO_KEY),
+                        Some(k) => data.extend_from_slice(k.key()),
+                    }
+                }
+                leaf.write_to(&mut data);
+            }
+            NodeType::None => {}
         }
+        data
     }
 
     /// split creates a sibling node from a given node by splitting the node in two around a median.
-    /// split will split the child at b leaving the [0, b-1] keys
-    /// while moving the set of [b, 2b-1] keys to the sibling.
-    pub fn split(&mut self, at: u64) -> Result<(Vec<u8>, Node), Error> {
-        let node = self;
-        let at = at as usize;
-        match &mut node.node_type {
+    /// leaf: 保留 [0, at) 的keys，[at, len) 移到sibling中，median为sibling的第一个key
+    /// internal: 保留 [0, at) 的keys，median为第at个key（上移到父节点中），[at + 1, len) 移到sibling中
+    pub fn split(&mut self, at: usize) -> Result<(Vec<u8>, Node), Error> {
+        match &mut self.node_type {
             NodeType::Internal(children, keys) => {
-                let mut new_children = Children::new();
-                let mut new_keys = VecBytes::new();
-                let mut mid_key = vec![];
-
-                let mut new_data = Vec::with_capacity(Node::OFFSET_NODE_DATA as usize + node.data.len() / 2);
-                // let mut re = Vec::with_capacity(keys.bytes_number as usize);
-                let new_offset = Node::OFFSET_NODE_DATA;
-                unsafe {
-                    new_children.set_number_children(children.number_children - at as LenType, &mut new_data);
-                    children.set_number_children(at as LenType, &mut node.data);
-
-                    let start = new_offset + Children::OFFSET_DATA + children.number_children as isize * DbKey::LEN_DB_KEY as isize;
-                    let count = new_children.number_children as usize * DbKey::LEN_DB_KEY;
-                    std::ptr::copy_nonoverlapping(
-                        node.data.as_ptr().offset(start),
-                        new_data.as_mut_ptr().offset(Node::OFFSET_NODE_DATA + Children::OFFSET_DATA),
-                        count,
-                    );
-
-                    std::ptr::copy(
-                        node.data.as_ptr().offset(start + count as isize),
-                        node.data.as_mut_ptr().offset(start),
-                        node.data.len() - start as usize - count,
-                    );
-                    node.data.set_len(node.data.len() - count);
-                }
-                new_keys.offset = new_children.offset_keys();
-                new_keys.set_number_keys(at as LenType - 1, &mut new_data);
-
-                let mut offset_original = keys.offset + Keys::OFFSET_DATA;
-                unsafe {
-                    for i in 0..keys.number_keys {
-                        if i as usize == at {
-                            let b = read_int_ptr::<BytesType>(node.data.as_ptr().offset(offset_original));
-                            mid_key.reserve_exact(b as usize);
-                            mid_key.set_len(b as usize);
-                            std::ptr::copy_nonoverlapping(
-                                node.data.as_ptr().offset(offset_original + size_of::<BytesType>() as isize),
-                                new_data.as_mut_ptr(),
-                                mid_key.len(),
-                            );
-                            let temp_offset = offset_original + b as isize + size_of::<BytesType>() as isize;
-
-                            let new_offset = new_keys.offset + Keys::OFFSET_DATA;
-                            new_keys.set_bytes_data(node.data.len() as BytesType - temp_offset as BytesType, &mut new_data);
-                            std::ptr::copy_nonoverlapping(
-                                node.data.as_ptr().offset(temp_offset),
-                                new_data.as_mut_ptr().offset(new_offset),
-                                new_keys.bytes_data as usize,
-                            );
-                            break;
-                        }
-                        let b = read_int_ptr::<BytesType>(node.data.as_ptr().offset(offset_original));
-                        offset_original += b as isize + size_of::<BytesType>() as isize;
-                    }
-                    keys.set_number_keys(at as LenType - 1, &mut node.data);
-                    keys.set_bytes_data((offset_original - keys.offset - Keys::OFFSET_DATA) as BytesType, &mut node.data);
-                    node.data.set_len(offset_original as usize);
-                }
-                Ok((
-                    mid_key,
-                    Node {
-                        node_type: NodeType::Internal(new_children, new_keys),
-                        data: new_data,
-                    },
-                ))
+                let mut new_keys = keys.split_off(at);
+                let median = new_keys.remove(0);
+                let new_children = children.split_off(at + 1);
+                Ok((median, Node::new(NodeType::Internal(new_children, new_keys))))
             }
-            NodeType::Leaf(_leaf) => {
-                //todo
-                let new_leaf = LeafData::new();
-                let new_data = Vec::with_capacity(Node::OFFSET_NODE_DATA as usize);
-                Ok((
-                    vec![],
-                    Node {
-                        data: new_data,
-                        node_type: NodeType::Leaf(new_leaf),
-                    },
-                ))
+            NodeType::Leaf(leaf) => {
+                let new_leaf = leaf.split_off(at);
+                let median = new_leaf.first().ok_or(Error::Unexpected)?.to_vec();
+                Ok((median, Node::new(NodeType::Leaf(new_leaf))))
             }
             NodeType::None => Err(Error::Unexpected),
         }
     }
 }
 
-/// Implement TryFrom<Page> for Node allowing for easier
+/// Implement TryFrom<&[u8]> for Node allowing for easier
 /// deserialization of data from a Page.
-impl TryFrom<Vec<u8>> for Node {
+impl TryFrom<&[u8]> for Node {
     type Error = Error;
-    fn try_from(data: Vec<u8>) -> Result<Node, Error> {
-        let raw = data.as_slice();
-        let node_type = NodeType::from(raw[Node::OFFSET_NODE_TYPE as usize]);
-
-        match node_type {
-            NodeType::Internal(mut children, mut keys) => {
-                children.read_from(raw);
-                keys.read_from(raw, children.offset_keys());
-                Ok(Node {
-                    node_type: NodeType::Internal(children, keys),
-                    data,
-                })
+    fn try_from(raw: &[u8]) -> Result<Node, Error> {
+        if raw.len() < Node::OFFSET_NODE_DATA {
+            return Err(Error::TryFromSlice("node header"));
+        }
+        let db_key = DbKey::from(&raw[Node::OFFSET_DB_KEY..Node::OFFSET_PARENT_DB_KEY]);
+        let mut prev = None;
+        let mut next = None;
+        let node_type = match NodeType::from(raw[Node::OFFSET_NODE_TYPE]) {
+            NodeType::Internal(_, _) => {
+                let (children, offset) = Children::read_from(raw, Node::OFFSET_NODE_DATA).ok_or(Error::TryFromSlice("children"))?;
+                let (keys, _) = Keys::read_from(raw, Node::OFFSET_NODE_DATA + offset).ok_or(Error::TryFromSlice("keys"))?;
+                if children.len() != keys.len() + 1 {
+                    return Err(Error::TryFromSlice("children and keys"));
+                }
+                NodeType::Internal(children, keys)
             }
-
-            NodeType::Leaf(mut leaf) => {
-                leaf.read_from(raw, Node::OFFSET_NODE_DATA);
-                Ok(Node {
-                    node_type: NodeType::Leaf(leaf),
-                    data,
-                })
+            NodeType::Leaf(_) => {
+                let offset = Node::OFFSET_NODE_DATA + Node::LEN_LINKS;
+                if raw.len() < offset {
+                    return Err(Error::TryFromSlice("links of leaf"));
+                }
+                prev = Node::read_link(&raw[Node::OFFSET_NODE_DATA..]);
+                next = Node::read_link(&raw[Node::OFFSET_NODE_DATA + DbKey::LEN_DB_KEY..]);
+                let (leaf, _) = Keys::read_from(raw, offset).ok_or(Error::TryFromSlice("leaf"))?;
+                NodeType::Leaf(leaf)
             }
-            NodeType::None => Err(Error::Unexpected),
-        }
+            NodeType::None => return Err(Error::Unexpected),
+        };
+        Ok(Node { node_type, db_key, prev, next })
     }
 }
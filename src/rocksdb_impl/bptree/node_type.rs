@@ -1,11 +1,11 @@
 use std::convert::From;
 
 use crate::{
-    datas::{KeyMetas, VecBytes},
+    datas::VecBytes,
     rocksdb_impl::bptree::{children::Children, leaf_data::LeafData},
 };
 
-pub type Keys = VecBytes<KeyMetas>;
+pub type Keys = VecBytes;
 
 // NodeType Represents different node types in the BTree.
 #[derive(Clone, Debug)]
@@ -0,0 +1,143 @@
+use std::path::Path;
+
+use rocksdb::{BlockBasedOptions, ColumnFamily, ColumnFamilyDescriptor, Options, SliceTransform, TransactionDB, TransactionDBOptions};
+
+use crate::{
+    rocksdb_impl::shared::{data_key_type, head_key_of, split_namespace, TAG_LIST_NODE},
+    ExpireIndex, KeyType, RrError, TransactionalDb,
+};
+
+/// 按类型分开存放数据的column family，可以单独压缩、备份与调整参数
+/// 类型登记、过期时间与没有列出的类型（set, sorted set, stream, stack）仍然存放在default中
+/// 只能在新建数据库时选择，使用[ColumnFamilies::open]打开，然后使用[crate::RedisRocksdb::new]
+/// 数据库中没有这些column family时，所有的数据都存放在default中
+#[derive(Clone, Copy, Default)]
+pub struct ColumnFamilies<'a> {
+    string: Option<&'a ColumnFamily>,
+    list: Option<&'a ColumnFamily>,
+    hash: Option<&'a ColumnFamily>,
+    heap: Option<&'a ColumnFamily>,
+    bp_tree: Option<&'a ColumnFamily>,
+}
+
+impl<'a> ColumnFamilies<'a> {
+    /// 字符串与位图
+    pub const STRING: &'static str = "string";
+    /// list的head、node与zip list
+    pub const LIST: &'static str = "list";
+    /// hash与bit hash的字段
+    pub const HASH: &'static str = "hash";
+    /// max heap与min heap
+    pub const HEAP: &'static str = "heap";
+    /// b+ tree的节点
+    pub const BP_TREE: &'static str = "bp_tree";
+    pub const NAMES: [&'static str; 5] = [Self::STRING, Self::LIST, Self::HASH, Self::HEAP, Self::BP_TREE];
+
+    pub fn new<D: TransactionalDb>(db: &'a D) -> Self {
+        ColumnFamilies {
+            string: db.cf_handle(Self::STRING),
+            list: db.cf_handle(Self::LIST),
+            hash: db.cf_handle(Self::HASH),
+            heap: db.cf_handle(Self::HEAP),
+            bp_tree: db.cf_handle(Self::BP_TREE),
+        }
+    }
+
+    /// 打开或新建使用column family的数据库，opts用于default，其它的column family使用[ColumnFamilies::options]
+    /// expire_index的compaction filter设置到每一个column family上，之后使用[crate::RedisRocksdb::with_expire_index]
+    /// 没有使用column family新建的数据库返回错误，不然default中的数据就访问不到了
+    pub fn open<P: AsRef<Path>>(opts: Options, txn_db_opts: &TransactionDBOptions, path: P, expire_index: &ExpireIndex) -> Result<TransactionDB, RrError> {
+        Self::open_with(opts, txn_db_opts, path, expire_index, Self::options)
+    }
+
+    /// 与[ColumnFamilies::open]一样，cf_options返回每一个column family的参数
+    pub(crate) fn open_with<P, F>(
+        mut opts: Options,
+        txn_db_opts: &TransactionDBOptions,
+        path: P,
+        expire_index: &ExpireIndex,
+        cf_options: F,
+    ) -> Result<TransactionDB, RrError>
+    where
+        P: AsRef<Path>,
+        F: Fn(&str) -> Options,
+    {
+        let descriptors = Self::descriptors(&mut opts, path.as_ref(), expire_index, cf_options)?;
+        Ok(TransactionDB::open_cf_descriptors(&opts, txn_db_opts, path, descriptors)?)
+    }
+
+    /// 打开数据库时所有column family的描述，也用于[rocksdb::OptimisticTransactionDB]
+    pub(crate) fn descriptors<F>(opts: &mut Options, path: &Path, expire_index: &ExpireIndex, cf_options: F) -> Result<Vec<ColumnFamilyDescriptor>, RrError>
+    where
+        F: Fn(&str) -> Options,
+    {
+        //数据库还不存在时list_cf出错
+        if let Ok(names) = rocksdb::DB::list_cf(opts, path) {
+            if !names.iter().any(|name| name == Self::STRING) {
+                return Err(RrError::message("the database was created without column families".to_owned()));
+            }
+        }
+        opts.create_missing_column_families(true);
+        let descriptors = Self::NAMES.iter().map(|name| {
+            let mut cf_opts = cf_options(name);
+            expire_index.set_compaction_filter(&mut cf_opts);
+            ColumnFamilyDescriptor::new(*name, cf_opts)
+        });
+        Ok(descriptors.collect())
+    }
+
+    /// 每一个column family调整过的参数
+    /// hash的字段使用head key作为前缀，同一个key的字段共用bloom filter；list的zip list与heap比较大，使用大一些的block
+    pub fn options(name: &str) -> Options {
+        let mut opts = Options::default();
+        let mut block_opts = BlockBasedOptions::default();
+        block_opts.set_bloom_filter(10.0, false);
+        Self::tune(name, &mut opts, &mut block_opts);
+        opts.set_block_based_table_factory(&block_opts);
+        opts
+    }
+
+    /// [ColumnFamilies::options]中与column family相关的部分，调用之后再设置block_opts
+    pub(crate) fn tune(name: &str, opts: &mut Options, block_opts: &mut BlockBasedOptions) {
+        match name {
+            Self::HASH => opts.set_prefix_extractor(prefix_extractor()),
+            Self::LIST | Self::HEAP => block_opts.set_block_size(64 * 1024),
+            _ => {}
+        }
+    }
+
+    /// 数据库中存在的column family，不包含default
+    pub(crate) fn handles(&self) -> impl Iterator<Item = &'a ColumnFamily> {
+        [self.string, self.list, self.hash, self.heap, self.bp_tree].into_iter().flatten()
+    }
+
+    /// key所在的column family，None时为default
+    pub(crate) fn get(&self, key: &[u8]) -> Option<&'a ColumnFamily> {
+        if key.first() == Some(&TAG_LIST_NODE) {
+            return self.list;
+        }
+        match data_key_type(key)? {
+            KeyType::String | KeyType::Bitmap => self.string,
+            KeyType::List => self.list,
+            KeyType::Hash | KeyType::BitHash => self.hash,
+            KeyType::MaxHeap | KeyType::MinHeap => self.heap,
+            KeyType::BPTree => self.bp_tree,
+            KeyType::Stack | KeyType::SortedSet | KeyType::Set | KeyType::Stream => None,
+        }
+    }
+}
+
+/// 数据的key使用head key作为前缀，同一个key的数据共用prefix bloom filter；其它的记录使用tag作为前缀，如所有的类型登记
+/// 前缀包含namespace的前缀，所以[crate::WrapDb::prefix_iterator]的prefix至少要包含上面的前缀
+pub(crate) fn prefix_extractor() -> SliceTransform {
+    SliceTransform::create("redis_rocksdb_head_key", key_prefix, None)
+}
+
+fn key_prefix(key: &[u8]) -> &[u8] {
+    let (_, rest) = split_namespace(key);
+    let len = match head_key_of(rest) {
+        Some(head) => head.len(),
+        None => rest.len().min(1),
+    };
+    &key[..key.len() - rest.len() + len]
+}
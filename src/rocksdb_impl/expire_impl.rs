@@ -0,0 +1,372 @@
+use std::{
+    collections::HashMap,
+    mem,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex, RwLock, Weak,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use rocksdb::{CompactionDecision, Options};
+
+use crate::{
+    rocksdb_impl::{
+        key_space_impl::{delete_key, get_type},
+        shared::{namespace_key, split_field_key, split_namespace, TAG_EXPIRE, TAG_EXPIRE_TIME},
+    },
+    Bytes, KeyExpire, KeyType, NotifyEvents, RedisRocksdb, RrError, TransactionalDb, WrapDb,
+};
+
+/// key的过期时间，value为过期的unix时间（毫秒, i64 LE）
+const EXPIRE_KEY_PREFIX: &[u8] = &[TAG_EXPIRE];
+/// 按过期时间排序的索引，key为 prefix + 过期时间（u64 BE） + key，value为空，清理时从头开始遍历
+const EXPIRE_TIME_PREFIX: &[u8] = &[TAG_EXPIRE_TIME];
+const LEN_TIME: usize = mem::size_of::<u64>();
+
+type ExpireMap = RwLock<HashMap<Vec<u8>, i64>>;
+type Expires = Arc<ExpireMap>;
+
+/// 所有的[ExpireIndex]，删除过期时间时，从所有的索引中移除，这样compaction filter不会删除新写入的数据
+static INDEXES: Mutex<Vec<Weak<ExpireMap>>> = Mutex::new(Vec::new());
+
+/// 在内存中保存key的过期时间，compaction filter不用读取数据库就可以判断字段所在的key是否过期
+/// 只有在本进程中设置的过期时间，重启之前设置的由[ExpireSweeper]删除
+#[derive(Clone)]
+pub struct ExpireIndex {
+    expires: Expires,
+}
+
+impl Default for ExpireIndex {
+    fn default() -> Self {
+        ExpireIndex::new()
+    }
+}
+
+impl ExpireIndex {
+    pub fn new() -> Self {
+        let expires = Expires::default();
+        lock(&INDEXES).push(Arc::downgrade(&expires));
+        ExpireIndex { expires }
+    }
+
+    /// 设置compaction filter，删除已过期的key的数据
+    /// 要在打开数据库之前调用，然后使用[RedisRocksdb::with_expire_index]
+    pub fn set_compaction_filter(&self, opts: &mut Options) {
+        let expires = self.expires.clone();
+        opts.set_compaction_filter("redis_rocksdb_expire", move |_level, key, _value| {
+            if ExpireIndex::is_field_expired(&expires, key, now_millis()) {
+                CompactionDecision::Remove
+            } else {
+                CompactionDecision::Keep
+            }
+        });
+    }
+
+    /// 数据的key中有key的长度，可以直接取出key，索引中的key带有namespace的前缀
+    /// list的head中有node的位置，删除了head就找不到node，所以list由[ExpireSweeper]删除
+    fn is_field_expired(expires: &Expires, field_key: &[u8], now: i64) -> bool {
+        let expires = expires.read().unwrap_or_else(|e| e.into_inner());
+        if expires.is_empty() {
+            return false;
+        }
+        let (namespace, field_key) = split_namespace(field_key);
+        match split_field_key(field_key) {
+            None | Some((KeyType::List, _, _)) => false,
+            Some((_, key, _)) => matches!(expires.get(namespace_key(namespace, key).as_ref()), Some(deadline) if *deadline <= now),
+        }
+    }
+
+    /// key为存放在数据库中的key，即带有namespace的前缀
+    pub(crate) fn insert(&self, key: &[u8], deadline: i64) {
+        self.expires.write().unwrap_or_else(|e| e.into_inner()).insert(key.to_vec(), deadline);
+    }
+
+    /// 不知道key是哪一个数据库的，所以从所有的索引中移除，多移除只是让compaction filter少删除一些数据
+    pub(crate) fn remove_all(key: &[u8]) {
+        lock(&INDEXES).retain(|w| match w.upgrade() {
+            None => false,
+            Some(expires) => {
+                expires.write().unwrap_or_else(|e| e.into_inner()).remove(key);
+                true
+            }
+        });
+    }
+}
+
+/// 后台定时删除过期的key，drop时停止线程
+pub struct ExpireSweeper {
+    stop: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl ExpireSweeper {
+    /// 每次最多删除的key的个数，删除完一批后如果还有过期的key，马上开始下一批
+    const BATCH: usize = 128;
+
+    /// 每隔interval清理一次，db被drop后线程自动结束
+    pub fn start<D: TransactionalDb>(db: &Arc<RedisRocksdb<D>>, interval: Duration) -> Self {
+        let stop = Arc::new(AtomicBool::new(false));
+        let weak = Arc::downgrade(db);
+        let thread_stop = stop.clone();
+        let handle = thread::spawn(move || {
+            while !thread_stop.load(Ordering::Acquire) {
+                thread::park_timeout(interval);
+                if thread_stop.load(Ordering::Acquire) {
+                    break;
+                }
+                let db = match weak.upgrade() {
+                    None => break,
+                    Some(db) => db,
+                };
+                loop {
+                    match db.sweep_expired(ExpireSweeper::BATCH) {
+                        Ok(n) if n >= ExpireSweeper::BATCH => continue,
+                        Ok(_) => break,
+                        Err(e) => {
+                            log::error!("sweep expired keys: {}", e);
+                            break;
+                        }
+                    }
+                }
+            }
+        });
+        ExpireSweeper { stop, handle: Some(handle) }
+    }
+}
+
+impl Drop for ExpireSweeper {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Release);
+        if let Some(handle) = self.handle.take() {
+            handle.thread().unpark();
+            let _ = handle.join();
+        }
+    }
+}
+
+impl<D: TransactionalDb> KeyExpire for RedisRocksdb<D> {
+    fn expire<K: Bytes>(&self, key: &K, seconds: i64) -> Result<bool, RrError> {
+        self.pexpire_at(key, now_millis().saturating_add(seconds.saturating_mul(1000)))
+    }
+
+    fn pexpire<K: Bytes>(&self, key: &K, milliseconds: i64) -> Result<bool, RrError> {
+        self.pexpire_at(key, now_millis().saturating_add(milliseconds))
+    }
+
+    fn expire_at<K: Bytes>(&self, key: &K, timestamp: i64) -> Result<bool, RrError> {
+        self.pexpire_at(key, timestamp.saturating_mul(1000))
+    }
+
+    fn pexpire_at<K: Bytes>(&self, key: &K, timestamp: i64) -> Result<bool, RrError> {
+        let key = key.as_ref();
+        let re = self.commit_once(|t| pexpire_at_key(t, key, timestamp))?;
+        if re {
+            if timestamp > now_millis() {
+                self.index_expire(key, timestamp);
+                self.notify(NotifyEvents::GENERIC, "expire", key);
+            } else {
+                self.notify(NotifyEvents::GENERIC, "del", key);
+            }
+        }
+        Ok(re)
+    }
+
+    fn ttl<K: Bytes>(&self, key: &K) -> Result<i64, RrError> {
+        Ok(ttl_from_pttl(self.pttl(key)?))
+    }
+
+    fn pttl<K: Bytes>(&self, key: &K) -> Result<i64, RrError> {
+        let key = key.as_ref();
+        self.check_expired(key)?;
+        pttl_key(&self.wrap_db(), key)
+    }
+
+    fn persist<K: Bytes>(&self, key: &K) -> Result<bool, RrError> {
+        let re = self.commit_once(|t| persist_key(t, key.as_ref()))?;
+        if re {
+            self.notify(NotifyEvents::GENERIC, "persist", key.as_ref());
+        }
+        Ok(re)
+    }
+}
+
+/// timestamp为过去的时间时删除key，返回false表示key不存在
+/// 提交之后，如果timestamp是将来的时间，要加入到[ExpireIndex]中
+pub(crate) fn pexpire_at_key<T: WrapDb>(t: &T, key: &[u8], timestamp: i64) -> Result<bool, RrError> {
+    expire_if_needed(t, key)?;
+    if get_type(t, key)?.is_none() {
+        return Ok(false);
+    }
+    if timestamp <= now_millis() {
+        delete_key(t, key)?;
+    } else {
+        set_expire(t, key, timestamp)?;
+    }
+    Ok(true)
+}
+
+/// key不存在时返回-2，没有过期时间时返回-1，调用之前要删除已过期的key
+pub(crate) fn pttl_key<T: WrapDb>(t: &T, key: &[u8]) -> Result<i64, RrError> {
+    if get_type(t, key)?.is_none() {
+        return Ok(-2);
+    }
+    match get_expire(t, key)? {
+        None => Ok(-1),
+        Some(deadline) => Ok((deadline - now_millis()).max(0)),
+    }
+}
+
+/// 与redis一样四舍五入到秒
+pub(crate) fn ttl_from_pttl(ms: i64) -> i64 {
+    if ms < 0 {
+        ms
+    } else {
+        (ms + 500) / 1000
+    }
+}
+
+pub(crate) fn persist_key<T: WrapDb>(t: &T, key: &[u8]) -> Result<bool, RrError> {
+    if expire_if_needed(t, key)? {
+        return Ok(false);
+    }
+    Ok(clear_expire(t, key)? && get_type(t, key)?.is_some())
+}
+
+impl<D: TransactionalDb> RedisRocksdb<D> {
+    /// 删除所有数据库中已过期的key，一次最多处理limit个，返回处理的个数，等于limit时可能还有过期的key
+    pub fn sweep_expired(&self, limit: usize) -> Result<usize, RrError> {
+        let mut swept = 0;
+        for namespace in self.physical_namespaces()? {
+            if swept >= limit {
+                break;
+            }
+            swept += self.select(self.namespaces.logical(namespace)).sweep_selected(limit - swept)?;
+        }
+        Ok(swept)
+    }
+
+    /// 只删除选择的数据库中已过期的key
+    fn sweep_selected(&self, limit: usize) -> Result<usize, RrError> {
+        let now = now_millis();
+        let mut time_keys = Vec::new();
+        for item in self.wrap_db().prefix_iterator(EXPIRE_TIME_PREFIX) {
+            let (k, _) = item?;
+            if !k.starts_with(EXPIRE_TIME_PREFIX) || time_keys.len() >= limit {
+                break;
+            }
+            if read_time(&k[EXPIRE_TIME_PREFIX.len()..]) > now {
+                break;
+            }
+            time_keys.push(k.to_vec());
+        }
+        for time_key in &time_keys {
+            let key = &time_key[EXPIRE_TIME_PREFIX.len() + LEN_TIME..];
+            let tr = self.db.transaction();
+            let t = self.wrap_transaction(&tr);
+            //在读取与删除之间，过期时间可能被修改了，所以在事务中再检查一次
+            let expired = expire_if_needed(&t, key)?;
+            if !expired {
+                t.delete(time_key)?;
+            }
+            tr.commit()?;
+            if expired {
+                self.notify(NotifyEvents::EXPIRED, "expired", key);
+            }
+        }
+        Ok(time_keys.len())
+    }
+
+    /// 提交之后把key的过期时间加入到[ExpireIndex]中
+    pub(crate) fn index_expire(&self, key: &[u8], deadline: i64) {
+        let namespace = self.namespaces.physical(self.namespace);
+        self.expire_index.insert(&namespace_key(namespace, key), deadline);
+    }
+
+    /// 如果key已过期，在一个事务中删除它及所有的子key，返回true表示已删除
+    pub(crate) fn check_expired(&self, key: &[u8]) -> Result<bool, RrError> {
+        //大多数的key没有过期时间，先不使用事务读取一次
+        if get_expire(&self.wrap_db(), key)?.is_none() {
+            return Ok(false);
+        }
+        let tr = self.db.transaction();
+        let re = expire_if_needed(&self.wrap_transaction(&tr), key)?;
+        tr.commit()?;
+        if re {
+            self.notify(NotifyEvents::EXPIRED, "expired", key);
+        }
+        Ok(re)
+    }
+}
+
+/// unix时间，毫秒
+pub(crate) fn now_millis() -> i64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis() as i64).unwrap_or(0)
+}
+
+pub(crate) fn make_expire_key(key: &[u8]) -> Vec<u8> {
+    [EXPIRE_KEY_PREFIX, key].concat()
+}
+
+fn make_expire_time_key(deadline: i64, key: &[u8]) -> Vec<u8> {
+    [EXPIRE_TIME_PREFIX, &(deadline.max(0) as u64).to_be_bytes(), key].concat()
+}
+
+fn read_time(data: &[u8]) -> i64 {
+    let mut bytes = [0u8; LEN_TIME];
+    bytes.copy_from_slice(&data[..LEN_TIME]);
+    u64::from_be_bytes(bytes) as i64
+}
+
+/// 返回key的过期时间，没有设置时返回None
+pub(crate) fn get_expire<T: WrapDb>(t: &T, key: &[u8]) -> Result<Option<i64>, RrError> {
+    let expire_key = make_expire_key(key);
+    match t.get(&expire_key)? {
+        None => Ok(None),
+        Some(v) => {
+            let bytes: [u8; 8] = v.as_slice().try_into().map_err(|_| RrError::corruption(&expire_key, "expire time"))?;
+            Ok(Some(i64::from_le_bytes(bytes)))
+        }
+    }
+}
+
+pub(crate) fn set_expire<T: WrapDb>(t: &T, key: &[u8], deadline: i64) -> Result<(), RrError> {
+    if let Some(old) = get_expire(t, key)? {
+        t.delete(&make_expire_time_key(old, key))?;
+    }
+    t.put(&make_expire_key(key), &deadline.to_le_bytes())?;
+    t.put(&make_expire_time_key(deadline, key), &[])
+}
+
+/// 删除key的过期时间，返回false表示没有设置过期时间
+/// 删除key时也要调用，不然新建的同名key会继承原来的过期时间
+pub(crate) fn clear_expire<T: WrapDb>(t: &T, key: &[u8]) -> Result<bool, RrError> {
+    match get_expire(t, key)? {
+        None => Ok(false),
+        Some(old) => {
+            t.delete(&make_expire_time_key(old, key))?;
+            t.delete(&make_expire_key(key))?;
+            ExpireIndex::remove_all(&namespace_key(t.namespace(), key));
+            Ok(true)
+        }
+    }
+}
+
+/// 如果key已过期，删除它及所有的子key，返回true表示已删除
+/// 所有类型的key在访问之前都要调用，t为[crate::WrapTransaction]时，删除是原子的
+/// 删除后由[WrapDb::expired]发送expired通知，在事务中时提交之后才发送
+pub(crate) fn expire_if_needed<T: WrapDb>(t: &T, key: &[u8]) -> Result<bool, RrError> {
+    match get_expire(t, key)? {
+        Some(deadline) if deadline <= now_millis() => {
+            delete_key(t, key)?;
+            t.expired(key);
+            Ok(true)
+        }
+        _ => Ok(false),
+    }
+}
+
+fn lock<T>(m: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    m.lock().unwrap_or_else(|e| e.into_inner())
+}
@@ -0,0 +1,313 @@
+use crate::{Geo, GeoCoord, GeoFrom, GeoMatch, GeoOrder, GeoSearch, GeoShape, LenType, RrError, ScoreBound, SortedSet, SortedSetImp, WrapDb, ZAddOptions};
+
+/// 使用[SortedSetImp]存放，与redis的geohash编码一样，所以score可以在两者之间复制
+/// 纬度的范围与web mercator一样，是[LAT_MIN, LAT_MAX]
+pub struct GeoImpl {}
+
+const LON_MIN: f64 = -180.0;
+const LON_MAX: f64 = 180.0;
+const LAT_MIN: f64 = -85.05112878;
+const LAT_MAX: f64 = 85.05112878;
+/// 经度与纬度各26 bits，交错后为52 bits，f64可以精确地表示
+const STEP_MAX: u32 = 26;
+const EARTH_RADIUS: f64 = 6372797.560856;
+const MERCATOR_MAX: f64 = 20037726.37;
+const BASE32: &[u8] = b"0123456789bcdefghjkmnpqrstuvwxyz";
+
+impl<T: WrapDb> Geo<T> for GeoImpl {
+    fn add(&self, t: &T, key: &[u8], members: &[(GeoCoord, &[u8])], opts: &ZAddOptions) -> Result<LenType, RrError> {
+        if opts.compare.is_some() {
+            return Err(RrError::message("GT and LT options are not supported by geoadd".to_owned()));
+        }
+        let mut scored = Vec::with_capacity(members.len());
+        for (coord, member) in members {
+            check_coord(coord)?;
+            scored.push((encode(coord, STEP_MAX) as f64, *member));
+        }
+        SortedSetImp {}.add(t, key, &scored, opts)
+    }
+
+    fn pos(&self, t: &T, key: &[u8], members: &[&[u8]]) -> Result<Vec<Option<GeoCoord>>, RrError> {
+        let mut re = Vec::with_capacity(members.len());
+        for member in members {
+            re.push(get_coord(t, key, member)?);
+        }
+        Ok(re)
+    }
+
+    fn dist(&self, t: &T, key: &[u8], member1: &[u8], member2: &[u8]) -> Result<Option<f64>, RrError> {
+        match (get_coord(t, key, member1)?, get_coord(t, key, member2)?) {
+            (Some(c1), Some(c2)) => Ok(Some(distance(&c1, &c2))),
+            _ => Ok(None),
+        }
+    }
+
+    fn hash(&self, t: &T, key: &[u8], members: &[&[u8]]) -> Result<Vec<Option<String>>, RrError> {
+        let mut re = Vec::with_capacity(members.len());
+        for member in members {
+            re.push(get_coord(t, key, member)?.map(|c| geohash_string(&c)));
+        }
+        Ok(re)
+    }
+
+    fn search(&self, t: &T, key: &[u8], opts: &GeoSearch) -> Result<Vec<GeoMatch>, RrError> {
+        let zset = SortedSetImp {};
+        //与redis一样，key不存在时不检查成员
+        if zset.len(t, key)?.is_none() {
+            return Ok(Vec::new());
+        }
+        let center = match &opts.from {
+            GeoFrom::Coord(c) => {
+                check_coord(c)?;
+                *c
+            }
+            GeoFrom::Member(m) => get_coord(t, key, m)?.ok_or_else(|| RrError::message("could not decode requested zset member".to_owned()))?,
+        };
+        let order = match opts.order {
+            None if opts.count.is_some() && !opts.any => Some(GeoOrder::Asc),
+            order => order,
+        };
+        let limit = if opts.any { opts.count } else { None };
+
+        let mut matches = Vec::new();
+        'areas: for (min, max) in search_areas(&center, &opts.shape) {
+            let members = zset.range_by_score(t, key, &ScoreBound::Inclusive(min as f64), &ScoreBound::Exclusive(max as f64), false, None)?;
+            for (member, score) in members {
+                let hash = score as u64;
+                let coord = decode(hash, STEP_MAX);
+                if let Some(dist) = in_shape(&center, &opts.shape, &coord) {
+                    matches.push(GeoMatch { member, dist, hash, coord });
+                    if limit.is_some_and(|l| matches.len() as LenType >= l) {
+                        break 'areas;
+                    }
+                }
+            }
+        }
+        match order {
+            Some(GeoOrder::Asc) => matches.sort_by(|a, b| a.dist.total_cmp(&b.dist)),
+            Some(GeoOrder::Desc) => matches.sort_by(|a, b| b.dist.total_cmp(&a.dist)),
+            None => {}
+        }
+        if let Some(count) = opts.count {
+            matches.truncate(count as usize);
+        }
+        Ok(matches)
+    }
+}
+
+fn check_coord(coord: &GeoCoord) -> Result<(), RrError> {
+    if (LON_MIN..=LON_MAX).contains(&coord.longitude) && (LAT_MIN..=LAT_MAX).contains(&coord.latitude) {
+        return Ok(());
+    }
+    Err(RrError::message(format!(
+        "invalid longitude,latitude pair {:.6},{:.6}",
+        coord.longitude, coord.latitude
+    )))
+}
+
+fn get_coord<T: WrapDb>(t: &T, key: &[u8], member: &[u8]) -> Result<Option<GeoCoord>, RrError> {
+    let score = SortedSetImp {}.score(t, key, member)?;
+    Ok(score.map(|s| decode(s as u64, STEP_MAX)))
+}
+
+/// 低位开始交错，纬度在偶数位，经度在奇数位
+fn interleave(lat: u32, lon: u32) -> u64 {
+    let spread = |v: u32| {
+        let mut v = v as u64;
+        v = (v | (v << 16)) & 0x0000ffff0000ffff;
+        v = (v | (v << 8)) & 0x00ff00ff00ff00ff;
+        v = (v | (v << 4)) & 0x0f0f0f0f0f0f0f0f;
+        v = (v | (v << 2)) & 0x3333333333333333;
+        (v | (v << 1)) & 0x5555555555555555
+    };
+    spread(lat) | (spread(lon) << 1)
+}
+
+/// 返回 (纬度, 经度)
+fn deinterleave(hash: u64) -> (u32, u32) {
+    let squash = |v: u64| {
+        let mut v = v & 0x5555555555555555;
+        v = (v | (v >> 1)) & 0x3333333333333333;
+        v = (v | (v >> 2)) & 0x0f0f0f0f0f0f0f0f;
+        v = (v | (v >> 4)) & 0x00ff00ff00ff00ff;
+        v = (v | (v >> 8)) & 0x0000ffff0000ffff;
+        ((v | (v >> 16)) & 0x00000000ffffffff) as u32
+    };
+    (squash(hash), squash(hash >> 1))
+}
+
+/// 在step精度下的格子，经纬度都是 [0, 2^step)
+fn cell(coord: &GeoCoord, step: u32, lat_min: f64, lat_max: f64) -> (u32, u32) {
+    let cells = (1u64 << step) as f64;
+    let lat = ((coord.latitude - lat_min) / (lat_max - lat_min) * cells) as u64;
+    let lon = ((coord.longitude - LON_MIN) / (LON_MAX - LON_MIN) * cells) as u64;
+    let max = (1u64 << step) - 1;
+    (lat.min(max) as u32, lon.min(max) as u32)
+}
+
+fn encode(coord: &GeoCoord, step: u32) -> u64 {
+    let (lat, lon) = cell(coord, step, LAT_MIN, LAT_MAX);
+    interleave(lat, lon)
+}
+
+/// 格子的中心
+fn decode(hash: u64, step: u32) -> GeoCoord {
+    let (lat, lon) = deinterleave(hash);
+    let cells = (1u64 << step) as f64;
+    let lat_size = (LAT_MAX - LAT_MIN) / cells;
+    let lon_size = (LON_MAX - LON_MIN) / cells;
+    GeoCoord {
+        longitude: (LON_MIN + (lon as f64 + 0.5) * lon_size).clamp(LON_MIN, LON_MAX),
+        latitude: (LAT_MIN + (lat as f64 + 0.5) * lat_size).clamp(LAT_MIN, LAT_MAX),
+    }
+}
+
+/// 与redis一样，标准的geohash使用[-90, 90]的纬度，所以要重新编码
+fn geohash_string(coord: &GeoCoord) -> String {
+    let (lat, lon) = cell(coord, STEP_MAX, -90.0, 90.0);
+    let hash = interleave(lat, lon);
+    (0..11)
+        .map(|i| {
+            //52 bits只够10个字符，最后一个字符为0
+            let index = if i == 10 { 0 } else { (hash >> (52 - (i + 1) * 5)) & 0x1f };
+            BASE32[index as usize] as char
+        })
+        .collect()
+}
+
+/// haversine公式，返回米
+fn distance(c1: &GeoCoord, c2: &GeoCoord) -> f64 {
+    let (lat1, lat2) = (c1.latitude.to_radians(), c2.latitude.to_radians());
+    let u = ((lat2 - lat1) / 2.0).sin();
+    let v = ((c2.longitude.to_radians() - c1.longitude.to_radians()) / 2.0).sin();
+    2.0 * EARTH_RADIUS * (u * u + lat1.cos() * lat2.cos() * v * v).sqrt().asin()
+}
+
+/// 在范围内时返回到中心的距离
+fn in_shape(center: &GeoCoord, shape: &GeoShape, coord: &GeoCoord) -> Option<f64> {
+    match *shape {
+        GeoShape::Radius(radius) => Some(distance(center, coord)).filter(|d| *d <= radius),
+        GeoShape::Box { width, height } => {
+            //与redis一样，纬度方向的距离沿经线计算，经度方向的距离在成员所在的纬度上计算
+            let lat_dist = EARTH_RADIUS * (coord.latitude.to_radians() - center.latitude.to_radians()).abs();
+            if lat_dist > height / 2.0 {
+                return None;
+            }
+            let lon_dist = distance(&GeoCoord::new(center.longitude, coord.latitude), coord);
+            if lon_dist > width / 2.0 {
+                return None;
+            }
+            Some(distance(center, coord))
+        }
+    }
+}
+
+/// 范围的外接矩形 (经度的半宽, 最小纬度, 最大纬度)，经度的半宽不小于180时包含所有的经度
+fn bounding_box(center: &GeoCoord, shape: &GeoShape) -> (f64, f64, f64) {
+    let (half_width, half_height) = match *shape {
+        GeoShape::Radius(r) => (r, r),
+        GeoShape::Box { width, height } => (width / 2.0, height / 2.0),
+    };
+    let lat_delta = (half_height / EARTH_RADIUS).to_degrees();
+    let lat_min = (center.latitude - lat_delta).max(LAT_MIN);
+    let lat_max = (center.latitude + lat_delta).min(LAT_MAX);
+    let ratio = match *shape {
+        //半径为a的圆，最大的经度差为 asin(sin(a) / cos(lat))
+        GeoShape::Radius(r) => {
+            let a = r / EARTH_RADIUS;
+            if a >= std::f64::consts::FRAC_PI_2 {
+                f64::INFINITY
+            } else {
+                a.sin() / center.latitude.to_radians().cos()
+            }
+        }
+        //在纬度lat上距离为w的经度差为 2 * asin(sin(w / 2R) / cos(lat))，取纬度绝对值最大的地方
+        GeoShape::Box { .. } => {
+            let a = half_width / EARTH_RADIUS / 2.0;
+            if a >= std::f64::consts::FRAC_PI_2 {
+                f64::INFINITY
+            } else {
+                let max_lat = lat_min.abs().max(lat_max.abs());
+                a.sin() / max_lat.to_radians().cos()
+            }
+        }
+    };
+    let lon_delta = if ratio >= 1.0 {
+        LON_MAX
+    } else {
+        match shape {
+            GeoShape::Radius(_) => ratio.asin().to_degrees(),
+            GeoShape::Box { .. } => 2.0 * ratio.asin().to_degrees(),
+        }
+    };
+    (lon_delta, lat_min, lat_max)
+}
+
+/// 与redis的geohashEstimateStepsByRadius一样估算格子的大小
+fn estimate_step(radius: f64, latitude: f64) -> u32 {
+    if radius == 0.0 {
+        return STEP_MAX;
+    }
+    let mut range = radius;
+    let mut step: i32 = 1;
+    while range < MERCATOR_MAX {
+        range *= 2.0;
+        step += 1;
+    }
+    step -= 2;
+    if latitude.abs() > 66.0 {
+        step -= 1;
+        if latitude.abs() > 80.0 {
+            step -= 1;
+        }
+    }
+    step.clamp(1, STEP_MAX as i32) as u32
+}
+
+/// 返回要遍历的score范围 [min, max)，中心所在的格子与它周围的8个格子
+/// 估算的格子不能覆盖外接矩形时使用更大的格子，所以结果是精确的
+fn search_areas(center: &GeoCoord, shape: &GeoShape) -> Vec<(u64, u64)> {
+    let radius = match *shape {
+        GeoShape::Radius(r) => r,
+        GeoShape::Box { width, height } => (width / 2.0).hypot(height / 2.0),
+    };
+    let (lon_delta, lat_min, lat_max) = bounding_box(center, shape);
+    let mut step = estimate_step(radius, center.latitude);
+    let (lat, lon) = loop {
+        let (lat, lon) = cell(center, step, LAT_MIN, LAT_MAX);
+        let cells = (1u64 << step) as f64;
+        let lat_size = (LAT_MAX - LAT_MIN) / cells;
+        let lon_size = (LON_MAX - LON_MIN) / cells;
+        //3个格子的范围
+        let cell_lat_min = LAT_MIN + (lat as f64 - 1.0) * lat_size;
+        let cell_lat_max = LAT_MIN + (lat as f64 + 2.0) * lat_size;
+        let cell_lon_min = LON_MIN + (lon as f64 - 1.0) * lon_size;
+        let cell_lon_max = LON_MIN + (lon as f64 + 2.0) * lon_size;
+        let covered = lat_min >= cell_lat_min
+            && lat_max < cell_lat_max
+            && (3.0 * lon_size >= LON_MAX - LON_MIN || (center.longitude - lon_delta >= cell_lon_min && center.longitude + lon_delta < cell_lon_max));
+        if covered || step == 1 {
+            break (lat, lon);
+        }
+        step -= 1;
+    };
+
+    let cells = 1i64 << step;
+    let shift = 2 * (STEP_MAX - step);
+    let mut areas = Vec::with_capacity(9);
+    for d_lat in -1..=1 {
+        let n_lat = lat as i64 + d_lat;
+        if n_lat < 0 || n_lat >= cells {
+            continue;
+        }
+        for d_lon in -1..=1 {
+            //经度是环绕的
+            let n_lon = (lon as i64 + d_lon).rem_euclid(cells);
+            let hash = interleave(n_lat as u32, n_lon as u32);
+            areas.push((hash << shift, (hash + 1) << shift));
+        }
+    }
+    areas.sort_unstable();
+    areas.dedup();
+    areas
+}
@@ -1,9 +1,10 @@
 use crate::{
     rocksdb_impl::{
         heap::heap_::{FieldHeap, MinHeapCompare},
+        key_space_impl::{check_type, ensure_type, remove_if_empty, remove_key_meta, touch_type},
         shared::{make_field_key, make_head_key},
     },
-    Heap, LenType, RrError, WrapDb,
+    Heap, KeyType, LenType, RrError, WrapDb,
 };
 
 /// 字段名使用 min binary head存放
@@ -11,7 +12,8 @@ pub struct MinHeap {}
 
 impl<T: WrapDb> Heap<T> for MinHeap {
     fn peek(&self, t: &T, key: &[u8]) -> Result<Option<(Vec<u8>, Vec<u8>)>, RrError> {
-        let head_key = make_head_key(key);
+        check_type(t, key, KeyType::MinHeap)?;
+        let head_key = make_head_key(KeyType::MinHeap, key);
         let mut heap = {
             match t.get(&head_key)? {
                 None => return Ok(None),
@@ -24,13 +26,14 @@ impl<T: WrapDb> Heap<T> for MinHeap {
             None => return Ok(None),
             Some(f) => f,
         };
-        let field_key = make_field_key(key, &field);
+        let field_key = make_field_key(KeyType::MinHeap, key, &field);
         let v = { (t.get(&field_key)?).unwrap_or_default() };
         Ok(Some((field, v)))
     }
 
     fn pop(&self, t: &T, key: &[u8]) -> Result<Option<(Vec<u8>, Vec<u8>)>, RrError> {
-        let head_key = make_head_key(key);
+        touch_type(t, key, KeyType::MinHeap)?;
+        let head_key = make_head_key(KeyType::MinHeap, key);
         let mut heap = {
             match t.get(&head_key)? {
                 None => return Ok(None),
@@ -43,17 +46,19 @@ impl<T: WrapDb> Heap<T> for MinHeap {
             None => return Ok(None),
             Some(f) => f,
         };
-        let field_key = make_field_key(key, &field);
+        let field_key = make_field_key(KeyType::MinHeap, key, &field);
         let v = { (t.get(&field_key)?).unwrap_or_default() };
         t.put(&head_key, &heap.data)?;
         t.delete(&field_key)?;
+        remove_if_empty(t, key, KeyType::MinHeap)?;
         Ok(Some((field, v)))
     }
 
     fn push(&self, t: &T, key: &[u8], field: &[u8], value: &[u8]) -> Result<(), RrError> {
-        let field_key = make_field_key(key, field);
+        ensure_type(t, key, KeyType::MinHeap)?;
+        let field_key = make_field_key(KeyType::MinHeap, key, field);
         if !t.exist(&field_key)? {
-            let head_key = make_head_key(key);
+            let head_key = make_head_key(KeyType::MinHeap, key);
             let mut heap = {
                 match t.get(&head_key)? {
                     None => FieldHeap::new(vec![]),
@@ -70,7 +75,8 @@ impl<T: WrapDb> Heap<T> for MinHeap {
     }
 
     fn len(&self, t: &T, key: &[u8]) -> Result<Option<LenType>, RrError> {
-        let head_key = make_head_key(key);
+        check_type(t, key, KeyType::MinHeap)?;
+        let head_key = make_head_key(KeyType::MinHeap, key);
         let heap = match t.get(&head_key)? {
             None => return Ok(None),
             Some(v) => FieldHeap::<MinHeapCompare>::new(v),
@@ -79,7 +85,9 @@ impl<T: WrapDb> Heap<T> for MinHeap {
     }
 
     fn remove_key(&self, t: &T, key: &[u8]) -> Result<(), RrError> {
-        let head_key = make_head_key(key);
+        check_type(t, key, KeyType::MinHeap)?;
+        remove_key_meta(t, key)?;
+        let head_key = make_head_key(KeyType::MinHeap, key);
         let mut heap = {
             match t.get(&head_key)? {
                 None => return Ok(()),
@@ -93,7 +101,7 @@ impl<T: WrapDb> Heap<T> for MinHeap {
                 None => break,
                 Some(f) => f,
             };
-            let field_key = make_field_key(key, &field);
+            let field_key = make_field_key(KeyType::MinHeap, key, &field);
             t.delete(&field_key)?;
         }
         t.delete(&head_key)?;
@@ -0,0 +1,326 @@
+use crate::{
+    rocksdb_impl::key_value_impl::{get_value, get_value_for_update, update_value},
+    HyperLogLog, LenType, RrError, WrapDb,
+};
+
+/// 与redis一样使用[crate::KeyType::String]存放，格式为
+/// "HYLL" + 编码（1 byte，0为dense，1为sparse） + 3 bytes未使用 + 缓存的基数（8 bytes LE，最高位为1时无效） + 寄存器
+/// dense: 16384个6 bits的寄存器，共12288 bytes
+/// sparse: ZERO(00xxxxxx)、XZERO(01xxxxxx yyyyyyyy)与VAL(1vvvvvxx)三种操作码，
+/// 寄存器的值大于32或长度超过[SPARSE_MAX_BYTES]时转换为dense，不会再转换回来
+pub struct HyperLogLogImpl {}
+
+const P: u32 = 14;
+const Q: u32 = 64 - P;
+const REGISTERS: usize = 1 << P;
+const REGISTER_MAX: u8 = (1 << 6) - 1;
+const HEADER_SIZE: usize = 16;
+const DENSE_SIZE: usize = HEADER_SIZE + REGISTERS * 6 / 8;
+const MAGIC: &[u8] = b"HYLL";
+const DENSE: u8 = 0;
+const SPARSE: u8 = 1;
+/// 与redis的hll-sparse-max-bytes默认值一样，包含头部
+const SPARSE_MAX_BYTES: usize = 3000;
+const SPARSE_VAL_MAX: u8 = 32;
+/// 与redis一样的MurmurHash64A种子
+const HASH_SEED: u64 = 0xadc83b19;
+
+impl<T: WrapDb> HyperLogLog<T> for HyperLogLogImpl {
+    fn pf_add(&self, t: &T, key: &[u8], elements: &[&[u8]]) -> Result<bool, RrError> {
+        let old = get_value_for_update(t, key)?;
+        let mut hll = match &old {
+            None => Hll::new(),
+            Some(v) => Hll::decode(v)?,
+        };
+        let mut changed = old.is_none();
+        for element in elements {
+            changed |= hll.add(element);
+        }
+        if changed {
+            hll.cached = None;
+            update_value(t, key, &hll.encode())?;
+        }
+        Ok(changed)
+    }
+
+    fn pf_count(&self, t: &T, keys: &[&[u8]]) -> Result<LenType, RrError> {
+        //与redis不同，不写回缓存的基数，所以pfcount不会修改key
+        if let [key] = keys {
+            return match get_value(t, key)? {
+                None => Ok(0),
+                Some(v) => {
+                    let hll = Hll::decode(&v)?;
+                    Ok(hll.cached.unwrap_or_else(|| hll.count()))
+                }
+            };
+        }
+        let mut merged = Hll::new();
+        for key in keys {
+            if let Some(v) = get_value(t, key)? {
+                merged.merge(&Hll::decode(&v)?);
+            }
+        }
+        Ok(merged.count())
+    }
+
+    fn pf_merge(&self, t: &T, dst: &[u8], keys: &[&[u8]]) -> Result<(), RrError> {
+        let mut merged = Hll::new();
+        if let Some(v) = get_value_for_update(t, dst)? {
+            merged.merge(&Hll::decode(&v)?);
+        }
+        for key in keys {
+            if let Some(v) = get_value(t, key)? {
+                merged.merge(&Hll::decode(&v)?);
+            }
+        }
+        update_value(t, dst, &merged.encode())
+    }
+}
+
+struct Hll {
+    registers: Vec<u8>,
+    /// 优先使用sparse编码
+    sparse: bool,
+    /// 缓存的基数，None表示无效
+    cached: Option<u64>,
+}
+
+impl Hll {
+    fn new() -> Self {
+        Hll {
+            registers: vec![0; REGISTERS],
+            sparse: true,
+            cached: None,
+        }
+    }
+
+    /// 与redis一样，不是HyperLogLog的字符串返回WRONGTYPE错误
+    fn decode(v: &[u8]) -> Result<Self, RrError> {
+        let invalid = || RrError::message("WRONGTYPE Key is not a valid HyperLogLog string value.".to_owned());
+        if v.len() < HEADER_SIZE || !v.starts_with(MAGIC) {
+            return Err(invalid());
+        }
+        let card = <[u8; 8]>::try_from(&v[8..HEADER_SIZE])?;
+        let mut hll = Hll::new();
+        if card[7] & 0x80 == 0 {
+            hll.cached = Some(u64::from_le_bytes(card));
+        }
+        let data = &v[HEADER_SIZE..];
+        match v[4] {
+            DENSE if v.len() == DENSE_SIZE => {
+                hll.sparse = false;
+                for (i, r) in hll.registers.iter_mut().enumerate() {
+                    *r = dense_get(data, i);
+                }
+            }
+            SPARSE => {
+                let mut i = 0;
+                let mut p = 0;
+                while p < data.len() {
+                    let op = data[p];
+                    let (value, run) = match op & 0xc0 {
+                        0x00 => (0, (op & 0x3f) as usize + 1),
+                        0x40 => {
+                            p += 1;
+                            let low = *data.get(p).ok_or_else(invalid)?;
+                            (0, ((((op & 0x3f) as usize) << 8) | low as usize) + 1)
+                        }
+                        _ => (((op >> 2) & 0x1f) + 1, (op & 0x03) as usize + 1),
+                    };
+                    if i + run > REGISTERS {
+                        return Err(invalid());
+                    }
+                    hll.registers[i..i + run].fill(value);
+                    i += run;
+                    p += 1;
+                }
+                if i != REGISTERS {
+                    return Err(invalid());
+                }
+            }
+            _ => return Err(invalid()),
+        }
+        Ok(hll)
+    }
+
+    fn encode(&self) -> Vec<u8> {
+        let sparse = if self.sparse { self.encode_sparse() } else { None };
+        let (encoding, data) = match sparse {
+            Some(data) => (SPARSE, data),
+            None => {
+                let mut data = vec![0; DENSE_SIZE - HEADER_SIZE];
+                for (i, r) in self.registers.iter().enumerate() {
+                    dense_set(&mut data, i, *r);
+                }
+                (DENSE, data)
+            }
+        };
+        let mut v = Vec::with_capacity(HEADER_SIZE + data.len());
+        v.extend_from_slice(MAGIC);
+        v.extend_from_slice(&[encoding, 0, 0, 0]);
+        match self.cached {
+            Some(card) => v.extend_from_slice(&card.to_le_bytes()),
+            None => v.extend_from_slice(&[0, 0, 0, 0, 0, 0, 0, 0x80]),
+        }
+        v.extend_from_slice(&data);
+        v
+    }
+
+    /// 有寄存器的值大于32或长度超过[SPARSE_MAX_BYTES]时返回None
+    fn encode_sparse(&self) -> Option<Vec<u8>> {
+        let mut data = Vec::new();
+        let mut i = 0;
+        while i < REGISTERS {
+            let value = self.registers[i];
+            let run = self.registers[i..].iter().take_while(|r| **r == value).count();
+            let mut left = run;
+            while left > 0 {
+                if value == 0 && left > 64 {
+                    let n = left.min(REGISTERS);
+                    data.push(0x40 | ((n - 1) >> 8) as u8);
+                    data.push(((n - 1) & 0xff) as u8);
+                    left -= n;
+                } else if value == 0 {
+                    data.push((left - 1) as u8);
+                    left = 0;
+                } else if value <= SPARSE_VAL_MAX {
+                    let n = left.min(4);
+                    data.push(0x80 | ((value - 1) << 2) | (n - 1) as u8);
+                    left -= n;
+                } else {
+                    return None;
+                }
+            }
+            if HEADER_SIZE + data.len() > SPARSE_MAX_BYTES {
+                return None;
+            }
+            i += run;
+        }
+        Some(data)
+    }
+
+    /// 返回是否修改了寄存器
+    fn add(&mut self, element: &[u8]) -> bool {
+        let (index, count) = hash_position(element);
+        if count > self.registers[index] {
+            self.registers[index] = count;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// 每一个寄存器取最大值，有一个是dense时结果也是dense
+    fn merge(&mut self, other: &Hll) {
+        for (r, o) in self.registers.iter_mut().zip(other.registers.iter()) {
+            *r = (*r).max(*o);
+        }
+        self.sparse &= other.sparse;
+    }
+
+    /// 与redis一样使用Otmar Ertl的估算方法
+    fn count(&self) -> u64 {
+        let m = REGISTERS as f64;
+        let mut histogram = [0u32; 64];
+        for r in &self.registers {
+            histogram[*r as usize] += 1;
+        }
+        let mut z = m * tau((m - histogram[Q as usize + 1] as f64) / m);
+        for j in (1..=Q as usize).rev() {
+            z += histogram[j] as f64;
+            z *= 0.5;
+        }
+        z += m * sigma(histogram[0] as f64 / m);
+        //0.5/ln(2)
+        let alpha_inf = 0.721_347_520_444_481_7;
+        (alpha_inf * m * m / z).round() as u64
+    }
+}
+
+fn sigma(mut x: f64) -> f64 {
+    if x == 1.0 {
+        return f64::INFINITY;
+    }
+    let mut y = 1.0;
+    let mut z = x;
+    loop {
+        x *= x;
+        let prev = z;
+        z += x * y;
+        y += y;
+        if prev == z {
+            return z;
+        }
+    }
+}
+
+fn tau(mut x: f64) -> f64 {
+    if x == 0.0 || x == 1.0 {
+        return 0.0;
+    }
+    let mut y = 1.0;
+    let mut z = 1.0 - x;
+    loop {
+        x = x.sqrt();
+        let prev = z;
+        y *= 0.5;
+        z -= (1.0 - x).powi(2) * y;
+        if prev == z {
+            return z / 3.0;
+        }
+    }
+}
+
+/// 与redis的HLL_DENSE_GET_REGISTER一样，寄存器从低位开始存放
+fn dense_get(data: &[u8], i: usize) -> u8 {
+    let byte = i * 6 / 8;
+    let fb = (i * 6) & 7;
+    let b0 = data[byte] as u16;
+    let b1 = data.get(byte + 1).copied().unwrap_or(0) as u16;
+    (((b0 >> fb) | (b1 << (8 - fb))) as u8) & REGISTER_MAX
+}
+
+fn dense_set(data: &mut [u8], i: usize, value: u8) {
+    let byte = i * 6 / 8;
+    let fb = (i * 6) & 7;
+    let v = value as u16;
+    data[byte] = (data[byte] as u16 & !((REGISTER_MAX as u16) << fb) | (v << fb)) as u8;
+    if let Some(next) = data.get_mut(byte + 1) {
+        *next = (*next as u16 & !((REGISTER_MAX as u16) >> (8 - fb)) | (v >> (8 - fb))) as u8;
+    }
+}
+
+/// 返回元素对应的寄存器与值（hash中第一个1的位置）
+fn hash_position(element: &[u8]) -> (usize, u8) {
+    let hash = murmur_hash64a(element, HASH_SEED);
+    let index = (hash & (REGISTERS as u64 - 1)) as usize;
+    let hash = (hash >> P) | (1 << Q);
+    (index, hash.trailing_zeros() as u8 + 1)
+}
+
+/// 与redis的MurmurHash64A一样（little endian）
+fn murmur_hash64a(data: &[u8], seed: u64) -> u64 {
+    const M: u64 = 0xc6a4a7935bd1e995;
+    const R: u32 = 47;
+    let mut h = seed ^ (data.len() as u64).wrapping_mul(M);
+    let mut chunks = data.chunks_exact(8);
+    for chunk in &mut chunks {
+        let mut k = u64::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3], chunk[4], chunk[5], chunk[6], chunk[7]]);
+        k = k.wrapping_mul(M);
+        k ^= k >> R;
+        k = k.wrapping_mul(M);
+        h ^= k;
+        h = h.wrapping_mul(M);
+    }
+    let tail = chunks.remainder();
+    if !tail.is_empty() {
+        for (i, b) in tail.iter().enumerate() {
+            h ^= (*b as u64) << (i * 8);
+        }
+        h = h.wrapping_mul(M);
+    }
+    h ^= h >> R;
+    h = h.wrapping_mul(M);
+    h ^= h >> R;
+    h
+}
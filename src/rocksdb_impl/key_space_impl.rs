@@ -0,0 +1,360 @@
+use std::{
+    mem,
+    sync::atomic::{AtomicU64, Ordering},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    rocksdb_impl::{
+        expire_impl::{clear_expire, expire_if_needed, get_expire, now_millis, set_expire},
+        quick_list::QuickList,
+        quick_list_node::QuickListNode,
+        scan_impl::scan_prefix,
+        shared::{make_field_key, make_head_key, TAG_TYPE},
+    },
+    KeySpace, KeyType, LenType, RedisRocksdb, RrError, ScanBatch, ScanCursor, ScanOptions, TransactionalDb, WrapDb,
+};
+
+/// key的类型，value为[KeyType]的值（u8） + 版本（u64 LE）
+/// 每一次修改key时都写入新的版本，[crate::RedisTransaction::watch]通过版本发现key被修改了
+const KEY_TYPE_PREFIX: &[u8] = &[TAG_TYPE];
+
+/// 使用登记的类型处理所有类型的key，见[KeySpace]
+pub struct KeySpaceImpl {}
+
+impl<T: WrapDb> KeySpace<T> for KeySpaceImpl {
+    fn del(&self, t: &T, key: &[u8]) -> Result<bool, RrError> {
+        if expire_if_needed(t, key)? {
+            return Ok(false);
+        }
+        delete_key(t, key)
+    }
+
+    fn dels(&self, t: &T, keys: &[&[u8]]) -> Result<LenType, RrError> {
+        let mut count = 0;
+        for key in keys {
+            if self.del(t, key)? {
+                count += 1;
+            }
+        }
+        Ok(count)
+    }
+
+    fn unlink(&self, t: &T, keys: &[&[u8]]) -> Result<LenType, RrError> {
+        self.dels(t, keys)
+    }
+
+    fn exists(&self, t: &T, keys: &[&[u8]]) -> Result<LenType, RrError> {
+        let mut count = 0;
+        for key in keys {
+            if self.key_type(t, key)?.is_some() {
+                count += 1;
+            }
+        }
+        Ok(count)
+    }
+
+    fn key_type(&self, t: &T, key: &[u8]) -> Result<Option<KeyType>, RrError> {
+        expire_if_needed(t, key)?;
+        get_type(t, key)
+    }
+
+    fn rename(&self, t: &T, key: &[u8], new_key: &[u8]) -> Result<(), RrError> {
+        let key_type = self.key_type(t, key)?.ok_or(RrError::not_find("key"))?;
+        if key == new_key {
+            return Ok(());
+        }
+        self.del(t, new_key)?;
+        copy_key(t, key, new_key, key_type)?;
+        delete_key(t, key)?;
+        Ok(())
+    }
+
+    fn rename_nx(&self, t: &T, key: &[u8], new_key: &[u8]) -> Result<bool, RrError> {
+        let key_type = self.key_type(t, key)?.ok_or(RrError::not_find("key"))?;
+        if self.key_type(t, new_key)?.is_some() {
+            return Ok(false);
+        }
+        copy_key(t, key, new_key, key_type)?;
+        delete_key(t, key)?;
+        Ok(true)
+    }
+
+    fn copy(&self, t: &T, src: &[u8], dst: &[u8], replace: bool) -> Result<bool, RrError> {
+        let key_type = match self.key_type(t, src)? {
+            None => return Ok(false),
+            Some(k) => k,
+        };
+        if src == dst {
+            return Ok(false);
+        }
+        if self.key_type(t, dst)?.is_some() {
+            if !replace {
+                return Ok(false);
+            }
+            delete_key(t, dst)?;
+        }
+        copy_key(t, src, dst, key_type)?;
+        Ok(true)
+    }
+
+    fn scan(&self, t: &T, cursor: &ScanCursor, opts: &ScanOptions) -> Result<ScanBatch<Vec<u8>>, RrError> {
+        let now = now_millis();
+        let mut items = Vec::new();
+        //遍历类型的登记，不用遍历所有的子key
+        let cursor = scan_prefix(t, KEY_TYPE_PREFIX, cursor, opts, |key, v| {
+            let key_type = match v {
+                [b, ..] => KeyType::try_from(*b)?,
+                _ => return Err(RrError::corruption(&make_type_key(key), "key type")),
+            };
+            //按名字比较，TYPE string也返回位图
+            if opts.key_type.is_some_and(|k| k.name() != key_type.name()) {
+                return Ok(true);
+            }
+            //已过期的key由sweeper或下一次访问时删除，这里只是不返回
+            if get_expire(t, key)?.is_some_and(|deadline| deadline <= now) {
+                return Ok(true);
+            }
+            items.push(key.to_vec());
+            Ok(true)
+        })?;
+        Ok(ScanBatch { cursor, items })
+    }
+}
+
+impl<D: TransactionalDb> RedisRocksdb<D> {
+    /// 删除过期的key并检查类型，返回false表示key不存在
+    pub(crate) fn check_key(&self, key: &[u8], key_type: KeyType) -> Result<bool, RrError> {
+        self.check_expired(key)?;
+        match_type(get_type(&self.wrap_db(), key)?, key_type)
+    }
+}
+
+pub(crate) fn make_type_key(key: &[u8]) -> Vec<u8> {
+    [KEY_TYPE_PREFIX, key].concat()
+}
+
+/// 不会重复的版本，使用纳秒初始化，这样删除后重建的key或重启后也不会使用相同的版本
+fn next_version() -> u64 {
+    static VERSION: AtomicU64 = AtomicU64::new(0);
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_nanos() as u64).unwrap_or(0);
+    let mut prev = VERSION.load(Ordering::Relaxed);
+    loop {
+        let next = now.max(prev + 1);
+        match VERSION.compare_exchange_weak(prev, next, Ordering::Relaxed, Ordering::Relaxed) {
+            Ok(_) => return next,
+            Err(p) => prev = p,
+        }
+    }
+}
+
+/// 返回登记的类型，没有登记时返回None
+pub(crate) fn get_type<T: WrapDb>(t: &T, key: &[u8]) -> Result<Option<KeyType>, RrError> {
+    match t.get(&make_type_key(key))? {
+        None => Ok(None),
+        Some(v) => match v.as_slice() {
+            //旧的数据没有版本
+            [b, ..] => Ok(Some(KeyType::try_from(*b)?)),
+            _ => Err(RrError::corruption(&make_type_key(key), "key type")),
+        },
+    }
+}
+
+/// 登记类型并写入新的版本
+pub(crate) fn set_type<T: WrapDb>(t: &T, key: &[u8], key_type: KeyType) -> Result<(), RrError> {
+    let mut v = Vec::with_capacity(1 + mem::size_of::<u64>());
+    v.push(key_type as u8);
+    v.extend_from_slice(&next_version().to_le_bytes());
+    t.put(&make_type_key(key), &v)
+}
+
+/// found与key_type不一样时返回WRONGTYPE错误，返回false表示key不存在
+pub(crate) fn match_type(found: Option<KeyType>, key_type: KeyType) -> Result<bool, RrError> {
+    match found {
+        None => Ok(false),
+        Some(k) if k == key_type => Ok(true),
+        Some(_) => Err(RrError::wrong_type()),
+    }
+}
+
+/// 读取之前调用，删除过期的key并检查类型，返回false表示key不存在
+pub(crate) fn check_type<T: WrapDb>(t: &T, key: &[u8], key_type: KeyType) -> Result<bool, RrError> {
+    expire_if_needed(t, key)?;
+    match_type(get_type(t, key)?, key_type)
+}
+
+/// 修改已存在的key之前调用，与[check_type]一样，key存在时写入新的版本
+pub(crate) fn touch_type<T: WrapDb>(t: &T, key: &[u8], key_type: KeyType) -> Result<bool, RrError> {
+    let exists = check_type(t, key, key_type)?;
+    if exists {
+        set_type(t, key, key_type)?;
+    }
+    Ok(exists)
+}
+
+/// fields中是否有存在的字段，删除字段之前调用，有字段被删除时才写入新的版本
+pub(crate) fn any_field<T: WrapDb>(t: &T, key_type: KeyType, key: &[u8], fields: &[&[u8]]) -> Result<bool, RrError> {
+    for field in fields {
+        if t.get(&make_field_key(key_type, key, field))?.is_some() {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/// 写入之前调用，与[check_type]一样，登记类型并写入新的版本
+pub(crate) fn ensure_type<T: WrapDb>(t: &T, key: &[u8], key_type: KeyType) -> Result<(), RrError> {
+    check_type(t, key, key_type)?;
+    set_type(t, key, key_type)
+}
+
+/// 删除key的类型与过期时间，各类型的del_key等方法删除数据后调用
+pub(crate) fn remove_key_meta<T: WrapDb>(t: &T, key: &[u8]) -> Result<(), RrError> {
+    t.delete(&make_type_key(key))?;
+    clear_expire(t, key)?;
+    Ok(())
+}
+
+/// 删除元素后调用，head key之外没有记录（元素）时，与redis一样删除空的key：head key、类型与过期时间
+/// 返回true表示key被删除了
+pub(crate) fn remove_if_empty<T: WrapDb>(t: &T, key: &[u8], key_type: KeyType) -> Result<bool, RrError> {
+    let head_key = make_head_key(key_type, key);
+    for item in t.prefix_iterator(&head_key) {
+        let (k, _) = item?;
+        if !k.starts_with(&head_key) {
+            break;
+        }
+        if k.len() > head_key.len() {
+            return Ok(false);
+        }
+    }
+    t.delete(&head_key)?;
+    remove_key_meta(t, key)?;
+    Ok(true)
+}
+
+/// 删除key的数据、类型与过期时间，返回false表示key没有登记
+pub(crate) fn delete_key<T: WrapDb>(t: &T, key: &[u8]) -> Result<bool, RrError> {
+    let key_type = get_type(t, key)?;
+    delete_data(t, key, key_type)?;
+    remove_key_meta(t, key)?;
+    Ok(key_type.is_some())
+}
+
+/// 按类型删除数据，一个key的数据都以它的head key开始，list的node与zip list另外删除
+fn delete_data<T: WrapDb>(t: &T, key: &[u8], key_type: Option<KeyType>) -> Result<(), RrError> {
+    match key_type {
+        None => Ok(()),
+        Some(KeyType::List) => delete_list(t, &make_head_key(KeyType::List, key)),
+        Some(k) => delete_prefix(t, &make_head_key(k, key)),
+    }
+}
+
+/// head_key为存放quick list的key，不存在或不是quick list时返回None
+pub(crate) fn get_quick_list<T: WrapDb>(t: &T, head_key: &[u8]) -> Result<Option<QuickList>, RrError> {
+    match t.get(head_key)? {
+        None => Ok(None),
+        Some(v) => match <[u8; mem::size_of::<QuickList>()]>::try_from(v.as_slice()) {
+            Err(_) => Ok(None),
+            Ok(data) => Ok(Some(QuickList::from(data))),
+        },
+    }
+}
+
+fn get_quick_list_node<T: WrapDb>(t: &T, key: &[u8]) -> Result<QuickListNode, RrError> {
+    let v = t.get(key)?.ok_or_else(|| RrError::corruption(key, "quick list node"))?;
+    let data = <[u8; mem::size_of::<QuickListNode>()]>::try_from(v.as_slice())?;
+    Ok(QuickListNode::from(data))
+}
+
+/// list: head_key中存放quick list，node与zip list存放在meta key中
+pub(crate) fn delete_list<T: WrapDb>(t: &T, head_key: &[u8]) -> Result<(), RrError> {
+    if let Some(quick) = get_quick_list(t, head_key)? {
+        let mut node_key = quick.right().cloned();
+        while let Some(k) = node_key {
+            let node = match get_quick_list_node(t, k.as_ref()) {
+                Err(_) => break,
+                Ok(n) => n,
+            };
+            if let Some(values_key) = node.values_key() {
+                t.delete(values_key.as_ref())?;
+            }
+            t.delete(k.as_ref())?;
+            node_key = node.left().cloned();
+        }
+    }
+    t.delete(head_key)
+}
+
+pub(crate) fn delete_prefix<T: WrapDb>(t: &T, prefix: &[u8]) -> Result<(), RrError> {
+    for item in t.prefix_iterator(prefix) {
+        let (k, _) = item?;
+        if !k.starts_with(prefix) {
+            break;
+        }
+        t.delete(&k)?;
+    }
+    Ok(())
+}
+
+/// 复制数据、类型与过期时间，dst必须是不存在的
+fn copy_key<T: WrapDb>(t: &T, src: &[u8], dst: &[u8], key_type: KeyType) -> Result<(), RrError> {
+    match key_type {
+        KeyType::List => copy_list(t, &make_head_key(KeyType::List, src), dst)?,
+        k => copy_prefix(t, &make_head_key(k, src), &make_head_key(k, dst))?,
+    }
+    set_type(t, dst, key_type)?;
+    if let Some(deadline) = get_expire(t, src)? {
+        set_expire(t, dst, deadline)?;
+    }
+    Ok(())
+}
+
+fn copy_prefix<T: WrapDb>(t: &T, src_prefix: &[u8], dst_prefix: &[u8]) -> Result<(), RrError> {
+    let mut kvs = Vec::new();
+    for item in t.prefix_iterator(src_prefix) {
+        let (k, v) = item?;
+        if !k.starts_with(src_prefix) {
+            break;
+        }
+        kvs.push(([dst_prefix, &k[src_prefix.len()..]].concat(), v));
+    }
+    for (k, v) in kvs {
+        t.put(&k, &v)?;
+    }
+    Ok(())
+}
+
+/// node与zip list的meta key由list的key产生，所以要使用dst产生新的meta key，zip list的内容直接复制
+/// src_head为src存放quick list的key
+pub(crate) fn copy_list<T: WrapDb>(t: &T, src_head: &[u8], dst: &[u8]) -> Result<(), RrError> {
+    let src = src_head;
+    let mut quick = get_quick_list(t, src)?.ok_or_else(|| RrError::corruption(src, "quick list"))?;
+    quick.init_meta_key(dst);
+    let mut nodes = Vec::new();
+    let mut node_key = quick.left().cloned();
+    while let Some(k) = node_key {
+        let node = get_quick_list_node(t, k.as_ref())?;
+        let values_key = node.values_key().ok_or_else(|| RrError::corruption(src, "value key"))?;
+        let zip = t
+            .get(values_key.as_ref())?
+            .ok_or_else(|| RrError::corruption(values_key.as_ref(), "zip list"))?;
+        node_key = node.right().cloned();
+        let new_key = quick.next_meta_key().ok_or_else(|| RrError::corruption(src, "next_meta_key"))?;
+        let new_values_key = quick.next_meta_key().ok_or_else(|| RrError::corruption(src, "next_meta_key"))?;
+        t.put(new_values_key.as_ref(), &zip)?;
+        nodes.push((new_key, new_values_key, node));
+    }
+    for i in 0..nodes.len() {
+        let left = if i > 0 { Some(nodes[i - 1].0.clone()) } else { None };
+        let right = nodes.get(i + 1).map(|n| n.0.clone());
+        let (new_key, new_values_key, node) = &mut nodes[i];
+        node.set_left(&left.as_ref());
+        node.set_right(&right.as_ref());
+        node.set_values_key(&Some(new_values_key));
+        t.put(new_key.as_ref(), node.as_ref())?;
+    }
+    quick.set_left(&nodes.first().map(|n| &n.0));
+    quick.set_right(&nodes.last().map(|n| &n.0));
+    t.put(&make_head_key(KeyType::List, dst), quick.as_ref())
+}
@@ -1,18 +1,421 @@
-use crate::{Bytes, KeyValue, RedisRocksdb, RrError};
+use crate::{
+    rocksdb_impl::{
+        bitmap_impl::{delete_bitmap, read_bitmap},
+        expire_impl::{clear_expire, expire_if_needed, get_expire, now_millis, pexpire_at_key, set_expire},
+        key_space_impl::{delete_key, get_type, make_type_key, set_type},
+        pub_sub_impl::expiration_event,
+        shared::make_head_key,
+    },
+    Bytes, Expiration, KeyType, KeyValue, LenType, NotifyEvents, RedisRocksdb, RrError, SetCondition, SetOptions, TransactionalDb, WrapDb,
+};
 
-impl KeyValue for RedisRocksdb {
-    fn get<K: Bytes, V: Bytes>(&self, key: &K) -> Result<Option<Vec<u8>>, RrError> {
-        let v = self.db.get(key.as_ref())?;
-        match v {
-            None => Ok(None),
-            Some(v) => Ok(Some(v.to_vec())),
+/// 与redis的proto-max-bulk-len默认值一样
+pub(crate) const MAX_STRING_LEN: usize = 512 * 1024 * 1024;
+
+impl<D: TransactionalDb> KeyValue for RedisRocksdb<D> {
+    fn get<K: Bytes>(&self, key: &K) -> Result<Option<Vec<u8>>, RrError> {
+        self.check_expired(key.as_ref())?;
+        read_string(&self.wrap_db(), key.as_ref())
+    }
+
+    /// 与redis的set一样，key为其它类型时覆盖它，并清除key的过期时间
+    fn put<K: Bytes, V: Bytes>(&self, key: &K, value: &V) -> Result<(), RrError> {
+        self.in_transaction(|t| put_value(t, key.as_ref(), value.as_ref()))?;
+        self.notify(NotifyEvents::STRING, "set", key.as_ref());
+        Ok(())
+    }
+
+    fn put_with<K: Bytes, V: Bytes>(&self, key: &K, value: &V, options: &SetOptions) -> Result<(bool, Option<Vec<u8>>), RrError> {
+        let re = self.in_transaction(|t| put_with_value(t, key.as_ref(), value.as_ref(), options))?;
+        if re.0 {
+            self.index_expiration(key.as_ref(), options.expiration);
+            self.notify(NotifyEvents::STRING, "set", key.as_ref());
+            self.expiration_notify(key.as_ref(), options.expiration);
+        }
+        Ok(re)
+    }
+
+    fn put_not_exist<K: Bytes, V: Bytes>(&self, key: &K, value: &V) -> Result<bool, RrError> {
+        let re = self.in_transaction(|t| put_not_exist_value(t, key.as_ref(), value.as_ref()))?;
+        if re {
+            self.notify(NotifyEvents::STRING, "set", key.as_ref());
+        }
+        Ok(re)
+    }
+
+    fn get_set<K: Bytes, V: Bytes>(&self, key: &K, value: &V) -> Result<Option<Vec<u8>>, RrError> {
+        let re = self.in_transaction(|t| get_set_value(t, key.as_ref(), value.as_ref()))?;
+        self.notify(NotifyEvents::STRING, "set", key.as_ref());
+        Ok(re)
+    }
+
+    fn get_del<K: Bytes>(&self, key: &K) -> Result<Option<Vec<u8>>, RrError> {
+        let re = self.in_transaction(|t| get_del_value(t, key.as_ref()))?;
+        if re.is_some() {
+            self.notify(NotifyEvents::GENERIC, "del", key.as_ref());
+        }
+        Ok(re)
+    }
+
+    fn get_ex<K: Bytes>(&self, key: &K, expiration: Option<Expiration>) -> Result<Option<Vec<u8>>, RrError> {
+        let re = self.in_transaction(|t| get_ex_value(t, key.as_ref(), expiration))?;
+        if re.is_some() {
+            self.index_expiration(key.as_ref(), expiration);
+            self.expiration_notify(key.as_ref(), expiration);
+        }
+        Ok(re)
+    }
+
+    fn incr_by<K: Bytes>(&self, key: &K, increment: i64) -> Result<i64, RrError> {
+        let re = self.in_transaction(|t| incr_by_value(t, key.as_ref(), increment))?;
+        self.notify(NotifyEvents::STRING, "incrby", key.as_ref());
+        Ok(re)
+    }
+
+    fn decr_by<K: Bytes>(&self, key: &K, decrement: i64) -> Result<i64, RrError> {
+        self.incr_by(key, decrement.checked_neg().ok_or_else(overflow_error)?)
+    }
+
+    fn incr_by_float<K: Bytes>(&self, key: &K, increment: f64) -> Result<f64, RrError> {
+        let re = self.in_transaction(|t| incr_by_float_value(t, key.as_ref(), increment))?;
+        self.notify(NotifyEvents::STRING, "incrbyfloat", key.as_ref());
+        Ok(re)
+    }
+
+    fn append<K: Bytes, V: Bytes>(&self, key: &K, value: &V) -> Result<LenType, RrError> {
+        let re = self.in_transaction(|t| append_value(t, key.as_ref(), value.as_ref()))?;
+        self.notify(NotifyEvents::STRING, "append", key.as_ref());
+        Ok(re)
+    }
+
+    fn str_len<K: Bytes>(&self, key: &K) -> Result<LenType, RrError> {
+        Ok(self.get(key)?.map_or(0, |v| v.len() as LenType))
+    }
+
+    fn get_range<K: Bytes>(&self, key: &K, start: i64, end: i64) -> Result<Vec<u8>, RrError> {
+        Ok(self.get(key)?.map(|v| sub_range(&v, start, end).to_vec()).unwrap_or_default())
+    }
+
+    fn set_range<K: Bytes, V: Bytes>(&self, key: &K, offset: LenType, value: &V) -> Result<LenType, RrError> {
+        let re = self.in_transaction(|t| set_range_value(t, key.as_ref(), offset, value.as_ref()))?;
+        if !value.as_ref().is_empty() {
+            self.notify(NotifyEvents::STRING, "setrange", key.as_ref());
+        }
+        Ok(re)
+    }
+
+    fn mget<K: Bytes>(&self, keys: &[K]) -> Result<Vec<Option<Vec<u8>>>, RrError> {
+        let t = self.wrap_db();
+        keys.iter()
+            .map(|key| {
+                self.check_expired(key.as_ref())?;
+                get_string_or_none(&t, key.as_ref())
+            })
+            .collect()
+    }
+
+    fn mset<K: Bytes, V: Bytes>(&self, items: &[(K, V)]) -> Result<(), RrError> {
+        self.in_transaction(|t| mset_value(t, items))?;
+        for (key, _) in items {
+            self.notify(NotifyEvents::STRING, "set", key.as_ref());
+        }
+        Ok(())
+    }
+
+    fn mset_not_exist<K: Bytes, V: Bytes>(&self, items: &[(K, V)]) -> Result<bool, RrError> {
+        let re = self.in_transaction(|t| mset_not_exist_value(t, items))?;
+        if re {
+            for (key, _) in items {
+                self.notify(NotifyEvents::STRING, "set", key.as_ref());
+            }
+        }
+        Ok(re)
+    }
+}
+
+impl<D: TransactionalDb> RedisRocksdb<D> {
+    /// 提交之后，把设置的过期时间加入到[crate::ExpireIndex]
+    fn index_expiration(&self, key: &[u8], expiration: Option<Expiration>) {
+        if let Some(deadline) = future_deadline(expiration) {
+            self.index_expire(key, deadline);
+        }
+    }
+
+    fn expiration_notify(&self, key: &[u8], expiration: Option<Expiration>) {
+        if let Some(event) = expiration_event(expiration) {
+            self.notify(NotifyEvents::GENERIC, event, key);
+        }
+    }
+}
+
+impl Expiration {
+    /// 从现在开始milliseconds毫秒后过期，溢出时返回None
+    pub fn after(milliseconds: i64) -> Option<Expiration> {
+        now_millis().checked_add(milliseconds).map(Expiration::At)
+    }
+}
+
+/// expiration为将来的时间时返回它，提交之后要加入到[crate::ExpireIndex]
+pub(crate) fn future_deadline(expiration: Option<Expiration>) -> Option<i64> {
+    match expiration {
+        Some(Expiration::At(deadline)) if deadline > now_millis() => Some(deadline),
+        _ => None,
+    }
+}
+
+pub(crate) fn get_value<T: WrapDb>(t: &T, key: &[u8]) -> Result<Option<Vec<u8>>, RrError> {
+    expire_if_needed(t, key)?;
+    read_string(t, key)
+}
+
+/// 字符串有两种存放方式，[KeyType::Bitmap]时合并所有的块，key为其它类型时返回WRONGTYPE错误
+fn read_string<T: WrapDb>(t: &T, key: &[u8]) -> Result<Option<Vec<u8>>, RrError> {
+    match get_type(t, key)? {
+        None => Ok(None),
+        Some(KeyType::String) => t.get(&make_head_key(KeyType::String, key)),
+        Some(KeyType::Bitmap) => read_bitmap(t, key).map(Some),
+        Some(_) => Err(RrError::wrong_type()),
+    }
+}
+
+pub(crate) fn put_value<T: WrapDb>(t: &T, key: &[u8], value: &[u8]) -> Result<(), RrError> {
+    expire_if_needed(t, key)?;
+    match get_type(t, key)? {
+        Some(KeyType::String) => {
+            clear_expire(t, key)?;
+        }
+        Some(_) => {
+            delete_key(t, key)?;
         }
+        None => {}
     }
+    set_type(t, key, KeyType::String)?;
+    t.put(&make_head_key(KeyType::String, key), value)
+}
+
+/// 读取并锁定字符串的值，key为其它类型时返回WRONGTYPE错误
+/// 所有的写入都会写类型的key，所以锁定它之后直到提交，key都不会被其它的事务修改，并发的incr不会丢失更新
+pub(crate) fn get_value_for_update<T: WrapDb>(t: &T, key: &[u8]) -> Result<Option<Vec<u8>>, RrError> {
+    t.get_for_update(&make_type_key(key))?;
+    expire_if_needed(t, key)?;
+    read_string(t, key)
+}
+
+/// 修改已存在的值，保留过期时间，调用之前要使用get_value_for_update检查类型
+/// 位图转换为一个值存放
+pub(crate) fn update_value<T: WrapDb>(t: &T, key: &[u8], value: &[u8]) -> Result<(), RrError> {
+    if get_type(t, key)? == Some(KeyType::Bitmap) {
+        delete_bitmap(t, key)?;
+    }
+    set_type(t, key, KeyType::String)?;
+    t.put(&make_head_key(KeyType::String, key), value)
+}
+
+/// mget中不是字符串的key返回None，调用之前要删除已过期的key
+fn get_string_or_none<T: WrapDb>(t: &T, key: &[u8]) -> Result<Option<Vec<u8>>, RrError> {
+    match get_type(t, key)? {
+        Some(KeyType::String | KeyType::Bitmap) => read_string(t, key),
+        _ => Ok(None),
+    }
+}
+
+/// 锁定key并返回它是否存在
+fn lock_exists<T: WrapDb>(t: &T, key: &[u8]) -> Result<bool, RrError> {
+    t.get_for_update(&make_type_key(key))?;
+    expire_if_needed(t, key)?;
+    Ok(get_type(t, key)?.is_some())
+}
+
+pub(crate) fn put_with_value<T: WrapDb>(t: &T, key: &[u8], value: &[u8], options: &SetOptions) -> Result<(bool, Option<Vec<u8>>), RrError> {
+    let exists = lock_exists(t, key)?;
+    let old = if options.get { read_string(t, key)? } else { None };
+    let skip = match options.condition {
+        Some(SetCondition::NotExists) => exists,
+        Some(SetCondition::Exists) => !exists,
+        None => false,
+    };
+    if skip {
+        return Ok((false, old));
+    }
+    let keep = match options.expiration {
+        Some(Expiration::KeepTtl) => get_expire(t, key)?,
+        _ => None,
+    };
+    put_value(t, key, value)?;
+    match options.expiration {
+        Some(Expiration::At(deadline)) => {
+            pexpire_at_key(t, key, deadline)?;
+        }
+        Some(Expiration::KeepTtl) => {
+            if let Some(deadline) = keep {
+                set_expire(t, key, deadline)?;
+            }
+        }
+        Some(Expiration::Persist) | None => {}
+    }
+    Ok((true, old))
+}
+
+pub(crate) fn put_not_exist_value<T: WrapDb>(t: &T, key: &[u8], value: &[u8]) -> Result<bool, RrError> {
+    let options = SetOptions {
+        condition: Some(SetCondition::NotExists),
+        ..Default::default()
+    };
+    Ok(put_with_value(t, key, value, &options)?.0)
+}
+
+pub(crate) fn get_set_value<T: WrapDb>(t: &T, key: &[u8], value: &[u8]) -> Result<Option<Vec<u8>>, RrError> {
+    let options = SetOptions {
+        get: true,
+        ..Default::default()
+    };
+    Ok(put_with_value(t, key, value, &options)?.1)
+}
+
+pub(crate) fn get_del_value<T: WrapDb>(t: &T, key: &[u8]) -> Result<Option<Vec<u8>>, RrError> {
+    let v = get_value_for_update(t, key)?;
+    if v.is_some() {
+        delete_key(t, key)?;
+    }
+    Ok(v)
+}
+
+pub(crate) fn get_ex_value<T: WrapDb>(t: &T, key: &[u8], expiration: Option<Expiration>) -> Result<Option<Vec<u8>>, RrError> {
+    let v = get_value_for_update(t, key)?;
+    if v.is_some() {
+        match expiration {
+            Some(Expiration::At(deadline)) => {
+                pexpire_at_key(t, key, deadline)?;
+            }
+            Some(Expiration::Persist) => {
+                clear_expire(t, key)?;
+            }
+            Some(Expiration::KeepTtl) | None => {}
+        }
+    }
+    Ok(v)
+}
+
+pub(crate) fn incr_by_value<T: WrapDb>(t: &T, key: &[u8], increment: i64) -> Result<i64, RrError> {
+    let old = match get_value_for_update(t, key)? {
+        None => 0,
+        Some(v) => parse_int(&v)?,
+    };
+    let new = old.checked_add(increment).ok_or_else(overflow_error)?;
+    update_value(t, key, new.to_string().as_bytes())?;
+    Ok(new)
+}
+
+pub(crate) fn incr_by_float_value<T: WrapDb>(t: &T, key: &[u8], increment: f64) -> Result<f64, RrError> {
+    let old = match get_value_for_update(t, key)? {
+        None => 0.0,
+        Some(v) => parse_float(&v)?,
+    };
+    let new = old + increment;
+    if !new.is_finite() {
+        return Err(RrError::message("increment would produce NaN or Infinity".to_owned()));
+    }
+    update_value(t, key, new.to_string().as_bytes())?;
+    Ok(new)
+}
+
+pub(crate) fn append_value<T: WrapDb>(t: &T, key: &[u8], value: &[u8]) -> Result<LenType, RrError> {
+    let mut v = get_value_for_update(t, key)?.unwrap_or_default();
+    check_string_len(v.len() + value.len())?;
+    v.extend_from_slice(value);
+    update_value(t, key, &v)?;
+    Ok(v.len() as LenType)
+}
+
+/// 与redis一样，value为空时不创建key
+pub(crate) fn set_range_value<T: WrapDb>(t: &T, key: &[u8], offset: LenType, value: &[u8]) -> Result<LenType, RrError> {
+    let old = get_value_for_update(t, key)?;
+    if value.is_empty() {
+        return Ok(old.map_or(0, |v| v.len() as LenType));
+    }
+    let start = usize::try_from(offset).map_err(|_| string_len_error())?;
+    let end = start.checked_add(value.len()).ok_or_else(string_len_error)?;
+    check_string_len(end)?;
+    let mut v = old.unwrap_or_default();
+    if v.len() < end {
+        v.resize(end, 0);
+    }
+    v[start..end].copy_from_slice(value);
+    update_value(t, key, &v)?;
+    Ok(v.len() as LenType)
+}
 
-    fn put<K: Bytes, V: Bytes>(&mut self, key: &K, value: &V) -> Result<(), RrError> {
-        match self.db.put(key.as_ref(), value.as_ref()) {
-            Ok(t) => Ok(t),
-            Err(e) => Err(RrError::from(e)),
+pub(crate) fn mget_value<T: WrapDb>(t: &T, key: &[u8]) -> Result<Option<Vec<u8>>, RrError> {
+    expire_if_needed(t, key)?;
+    get_string_or_none(t, key)
+}
+
+pub(crate) fn mset_value<T: WrapDb, K: Bytes, V: Bytes>(t: &T, items: &[(K, V)]) -> Result<(), RrError> {
+    for (key, value) in items {
+        put_value(t, key.as_ref(), value.as_ref())?;
+    }
+    Ok(())
+}
+
+pub(crate) fn mset_not_exist_value<T: WrapDb, K: Bytes, V: Bytes>(t: &T, items: &[(K, V)]) -> Result<bool, RrError> {
+    for (key, _) in items {
+        if lock_exists(t, key.as_ref())? {
+            return Ok(false);
         }
     }
+    mset_value(t, items)?;
+    Ok(true)
+}
+
+/// 与redis的getrange一样，start与end都包含，负数从尾部开始计算，超出范围的部分被忽略
+pub(crate) fn sub_range(v: &[u8], start: i64, end: i64) -> &[u8] {
+    match clamp_range(v.len() as u64, start, end) {
+        None => &[],
+        Some((start, end)) => &v[start as usize..=end as usize],
+    }
+}
+
+/// [sub_range]的范围，也用于bitcount与bitpos，范围为空时返回None
+pub(crate) fn clamp_range(len: u64, start: i64, end: i64) -> Option<(u64, u64)> {
+    let len = len as i64;
+    if start < 0 && end < 0 && start > end {
+        return None;
+    }
+    let start = if start < 0 { (len + start).max(0) } else { start };
+    let end = if end < 0 { (len + end).max(0) } else { end }.min(len - 1);
+    if len == 0 || start > end {
+        return None;
+    }
+    Some((start as u64, end as u64))
+}
+
+/// 与redis一样，只接受规范的整数，如不能有前导的0与+号
+fn parse_int(v: &[u8]) -> Result<i64, RrError> {
+    std::str::from_utf8(v)
+        .ok()
+        .and_then(|s| s.parse::<i64>().ok().filter(|i| i.to_string() == s))
+        .ok_or_else(|| RrError::message("value is not an integer or out of range".to_owned()))
+}
+
+fn parse_float(v: &[u8]) -> Result<f64, RrError> {
+    std::str::from_utf8(v)
+        .ok()
+        .and_then(|s| s.parse::<f64>().ok())
+        .filter(|f| f.is_finite())
+        .ok_or_else(|| RrError::message("value is not a valid float".to_owned()))
+}
+
+pub(crate) fn overflow_error() -> RrError {
+    RrError::message("increment or decrement would overflow".to_owned())
+}
+
+fn string_len_error() -> RrError {
+    RrError::message("string exceeds maximum allowed size (proto-max-bulk-len)".to_owned())
+}
+
+fn check_string_len(len: usize) -> Result<(), RrError> {
+    if len > MAX_STRING_LEN {
+        return Err(string_len_error());
+    }
+    Ok(())
 }
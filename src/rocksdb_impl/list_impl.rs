@@ -1,424 +1,471 @@
-use anyhow::Context;
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 use crate::{
-    rocksdb_impl::{quick_list::QuickList, quick_list_node::QuickListNode, zip_list::ZipList},
-    Bytes, LenType, RedisList, RedisRocksdb, RrError,
+    rocksdb_impl::{
+        key_space_impl::{check_type, ensure_type, remove_key_meta, touch_type},
+        list_waiters::Waiter,
+        quick_list::QuickList,
+        quick_list_node::QuickListNode,
+        zip_list::ZipList,
+    },
+    Bytes, FieldValue, KeyType, LenType, List, NotifyEvents, RedisList, RedisRocksdb, RrError, TransactionalDb, WrapDb,
 };
 
 /// [see] (https://xindoo.blog.csdn.net/article/details/109150975)
 /// ssdb没有实现list，只实现了queue
 ///
 /// redis中的list使用quicklist与ziplist实现
-impl RedisList for RedisRocksdb {
-    fn list_blpop<K: Bytes, V: Bytes>(&mut self, _key: &K, _timeout: i64) -> Result<V, RrError> {
-        todo!()
+/// 每一个方法在一个新的事务中调用[ListImpl]，push成功后唤醒阻塞的等待者，提交后发送keyspace通知
+impl<D: TransactionalDb> RedisList for RedisRocksdb<D> {
+    fn list_blpop<K: Bytes>(&self, key: &K, timeout: i64) -> Result<Option<Vec<u8>>, RrError> {
+        self.blocking_pop(key.as_ref(), timeout, || self.list_pop_front(key))
     }
 
-    fn list_brpop<K: Bytes, V: Bytes>(&mut self, _key: &K, _timeout: i64) -> Result<V, RrError> {
-        todo!()
+    fn list_brpop<K: Bytes>(&self, key: &K, timeout: i64) -> Result<Option<Vec<u8>>, RrError> {
+        self.blocking_pop(key.as_ref(), timeout, || self.list_pop_back(key))
     }
 
-    fn list_brpoplpush<K: Bytes, V: Bytes>(&mut self, _srckey: &K, _dstkey: &K, _timeout: i64) -> Result<V, RrError> {
-        todo!()
+    fn list_brpoplpush<K: Bytes>(&self, srckey: &K, dstkey: &K, timeout: i64) -> Result<Option<Vec<u8>>, RrError> {
+        self.blocking_pop(srckey.as_ref(), timeout, || self.list_replace_back(srckey, dstkey))
     }
 
     fn list_index<K: Bytes>(&self, key: &K, index: i32) -> Result<Vec<u8>, RrError> {
-        let t = QuickList::get(&self.db, key.as_ref())?.ok_or(RrError::not_find("key of list"))?;
-        if index >= t.len_list() as i32 {
-            return Err(RrError::not_find(&format!("the index {}", index)));
+        self.in_transaction(|t| ListImpl {}.index(t, key.as_ref(), index))
+    }
+
+    fn list_insert_before<K: Bytes, V: Bytes>(&self, key: &K, pivot: &V, value: &V) -> Result<i32, RrError> {
+        let len = self.in_transaction(|t| ListImpl {}.insert_before(t, key.as_ref(), pivot.as_ref(), value.as_ref()))?;
+        Ok(self.list_modified(key.as_ref(), len, "linsert"))
+    }
+
+    fn list_insert_after<K: Bytes, V: Bytes>(&self, key: &K, pivot: &V, value: &V) -> Result<i32, RrError> {
+        let len = self.in_transaction(|t| ListImpl {}.insert_after(t, key.as_ref(), pivot.as_ref(), value.as_ref()))?;
+        Ok(self.list_modified(key.as_ref(), len, "linsert"))
+    }
+
+    fn list_len<K: Bytes>(&self, key: &K) -> Result<i32, RrError> {
+        self.in_transaction(|t| ListImpl {}.len(t, key.as_ref()))
+    }
+
+    fn list_pop_front<K: Bytes>(&self, key: &K) -> Result<Option<Vec<u8>>, RrError> {
+        let re = self.in_transaction(|t| ListImpl {}.pop_front(t, key.as_ref()))?;
+        if re.is_some() {
+            self.notify(NotifyEvents::LIST, "lpop", key.as_ref());
         }
-        //todo read only
-        let tr = self.db.transaction();
-        let node_key = t.left().context("left of quick list")?;
-        let mut node = QuickListNode::get(&tr, node_key.as_ref())?.context("left node")?;
+        Ok(re)
+    }
+
+    fn list_push_front<K: Bytes, V: Bytes>(&self, key: &K, value: &V) -> Result<i32, RrError> {
+        let re = self.in_transaction(|t| ListImpl {}.push_front(t, key.as_ref(), value.as_ref()))?;
+        self.list_waiters.notify(key.as_ref());
+        Ok(self.list_modified(key.as_ref(), re, "lpush"))
+    }
+
+    fn list_push_front_exists<K: Bytes, V: Bytes>(&self, key: &K, value: &V) -> Result<i32, RrError> {
+        let re = self.in_transaction(|t| ListImpl {}.push_front_exists(t, key.as_ref(), value.as_ref()))?;
+        self.list_waiters.notify(key.as_ref());
+        Ok(self.list_modified(key.as_ref(), re, "lpush"))
+    }
+
+    fn list_range<K: Bytes>(&self, key: &K, start: i32, stop: i32) -> Result<Vec<Vec<u8>>, RrError> {
+        self.in_transaction(|t| ListImpl {}.range(t, key.as_ref(), start, stop))
+    }
+
+    fn list_rem<K: Bytes, V: Bytes>(&self, list_key: &K, count: i32, value: &V) -> Result<LenType, RrError> {
+        let removed = self.in_transaction(|t| ListImpl {}.rem(t, list_key.as_ref(), count, value.as_ref()))?;
+        if removed > 0 {
+            self.notify(NotifyEvents::LIST, "lrem", list_key.as_ref());
+        }
+        Ok(removed)
+    }
+
+    fn list_trim<K: Bytes>(&self, key: &K, start: i32, stop: i32) -> Result<i32, RrError> {
+        let re = self.in_transaction(|t| ListImpl {}.trim(t, key.as_ref(), start, stop))?;
+        self.notify(NotifyEvents::LIST, "ltrim", key.as_ref());
+        Ok(re)
+    }
+
+    fn list_set<K: Bytes, V: Bytes>(&self, key: &K, index: i32, value: &V) -> Result<Vec<u8>, RrError> {
+        let re = self.in_transaction(|t| ListImpl {}.set(t, key.as_ref(), index, value.as_ref()))?;
+        self.notify(NotifyEvents::LIST, "lset", key.as_ref());
+        Ok(re)
+    }
+
+    fn list_pop_back<K: Bytes>(&self, key: &K) -> Result<Option<Vec<u8>>, RrError> {
+        let re = self.in_transaction(|t| ListImpl {}.pop_back(t, key.as_ref()))?;
+        if re.is_some() {
+            self.notify(NotifyEvents::LIST, "rpop", key.as_ref());
+        }
+        Ok(re)
+    }
+
+    fn list_replace_back<K: Bytes>(&self, key: &K, dstkey: &K) -> Result<Option<Vec<u8>>, RrError> {
+        let re = self.in_transaction(|t| ListImpl {}.replace_back(t, key.as_ref(), dstkey.as_ref()))?;
+        if re.is_some() {
+            self.list_waiters.notify(dstkey.as_ref());
+            self.notify(NotifyEvents::LIST, "rpop", key.as_ref());
+            self.notify(NotifyEvents::LIST, "lpush", dstkey.as_ref());
+        }
+        Ok(re)
+    }
+
+    fn list_push_back<K: Bytes, V: Bytes>(&self, key: &K, value: &V) -> Result<i32, RrError> {
+        let re = self.in_transaction(|t| ListImpl {}.push_back(t, key.as_ref(), value.as_ref()))?;
+        self.list_waiters.notify(key.as_ref());
+        Ok(self.list_modified(key.as_ref(), re, "rpush"))
+    }
+
+    fn list_push_back_exists<K: Bytes, V: Bytes>(&self, key: &K, value: &V) -> Result<i32, RrError> {
+        let re = self.in_transaction(|t| ListImpl {}.push_back_exists(t, key.as_ref(), value.as_ref()))?;
+        self.list_waiters.notify(key.as_ref());
+        Ok(self.list_modified(key.as_ref(), re, "rpush"))
+    }
+
+    fn list_clear<K: Bytes>(&self, key: &K) -> Result<i32, RrError> {
+        let re = self.in_transaction(|t| ListImpl {}.clear(t, key.as_ref()))?;
+        if re > 0 {
+            self.notify(NotifyEvents::GENERIC, "del", key.as_ref());
+        }
+        Ok(re)
+    }
+}
+
+impl<D: TransactionalDb> RedisRocksdb<D> {
+    /// len大于0表示list被修改了
+    fn list_modified(&self, key: &[u8], len: i32, event: &str) -> i32 {
+        if len > 0 {
+            self.notify(NotifyEvents::LIST, event, key);
+        }
+        len
+    }
+
+    /// 阻塞的pop，timeout为秒，参见[RedisRocksdb::list_block]
+    fn blocking_pop<F>(&self, key: &[u8], timeout: i64, pop: F) -> Result<Option<Vec<u8>>, RrError>
+    where
+        F: Fn() -> Result<Option<Vec<u8>>, RrError>,
+    {
+        if timeout < 0 {
+            return Err(RrError::message(format!("timeout is negative: {}", timeout)));
+        }
+        let re = self.list_block(&[key], timeout.saturating_mul(1000), |_| pop())?;
+        Ok(re.map(|(_, v)| v))
+    }
+
+    /// 阻塞的blpop/brpop/brpoplpush，pop返回None时等待keys中的list有push的唤醒，直到取得数据或超时（返回None）
+    /// timeout为毫秒，0或者大到超出时间范围时表示一直等待，返回取得数据的key与pop返回的值
+    /// 与redis一样按keys的顺序取数据，多个等待者阻塞在同一个key上时，按到达的先后顺序取得数据
+    pub fn list_block<F>(&self, keys: &[&[u8]], timeout: i64, pop: F) -> Result<Option<FieldValue>, RrError>
+    where
+        F: Fn(&[u8]) -> Result<Option<Vec<u8>>, RrError>,
+    {
+        if timeout < 0 {
+            return Err(RrError::message(format!("timeout is negative: {}", timeout)));
+        }
+        //timeout太大，超出Instant的范围时与0一样一直等待
+        let deadline = if timeout == 0 {
+            None
+        } else {
+            Instant::now().checked_add(Duration::from_millis(timeout as u64))
+        };
+
+        let waiter = Arc::new(Waiter::default());
+        for key in keys {
+            self.list_waiters.add(key, &waiter);
+        }
+        let result = 'wait: loop {
+            waiter.reset();
+            for key in keys {
+                //只有队首的等待者才能取数据，这样先到的先得
+                if self.list_waiters.is_front(key, &waiter) {
+                    match pop(key) {
+                        Ok(None) => {}
+                        Ok(Some(v)) => break 'wait Ok(Some((key.to_vec(), v))),
+                        Err(e) => break 'wait Err(e),
+                    }
+                }
+            }
+            if !waiter.wait(deadline) {
+                break Ok(None);
+            }
+        };
+        for key in keys {
+            self.list_waiters.remove(key, &waiter);
+        }
+        result
+    }
+}
+
+pub struct ListImpl {}
+
+impl<T: WrapDb> List<T> for ListImpl {
+    fn index(&self, t: &T, key: &[u8], index: i32) -> Result<Vec<u8>, RrError> {
+        check_type(t, key, KeyType::List)?;
+        let quick = QuickList::get(t, key)?.ok_or_else(|| RrError::not_find("key"))?;
+        if index >= quick.len_list() as i32 {
+            return Err(RrError::index_out_of_range(index));
+        }
+        let node_key = quick.left().ok_or_else(|| RrError::corruption(key, "left of quick list"))?;
+        let mut node = QuickListNode::get(t, node_key.as_ref())?.ok_or_else(|| RrError::corruption(node_key.as_ref(), "left node"))?;
         let mut it_index = 0i32;
         it_index += node.len_list() as i32;
         while index >= it_index {
-            let next_key = node.right().context("right node")?;
-            node = QuickListNode::get(&tr, next_key.as_ref())?.context("next node")?;
+            let next_key = node.right().ok_or_else(|| RrError::corruption(key, "right node"))?;
+            node = QuickListNode::get(t, next_key.as_ref())?.ok_or_else(|| RrError::corruption(next_key.as_ref(), "next node"))?;
             it_index += node.len_list() as i32;
         }
 
-        let value_key = node.values_key().context("value key")?;
-        let zip = ZipList::get(&tr, value_key.as_ref())?.context("zip list")?;
+        let value_key = node.values_key().ok_or_else(|| RrError::corruption(key, "value key"))?;
+        let zip = ZipList::get(t, value_key.as_ref())?.ok_or_else(|| RrError::corruption(value_key.as_ref(), "zip list"))?;
         let zip_index = index - (it_index - node.len_list() as i32);
-        let v = zip.index(zip_index).ok_or(RrError::not_find(&format!("the index {}", index)))?;
-        tr.commit()?;
+        let v = zip.index(zip_index).ok_or_else(|| RrError::index_out_of_range(index))?;
         Ok(v.to_vec())
     }
-    fn list_insert_before<K: Bytes, V: Bytes>(&mut self, key: &K, pivot: &V, value: &V) -> Result<i32, RrError> {
-        let mut quick = {
-            match QuickList::get(&self.db, key.as_ref())? {
-                None => return Ok(0),
-                Some(q) => q,
-            }
-        };
-        let tr = self.db.transaction();
-        let result = quick.list_insert(&tr, key.as_ref(), pivot.as_ref(), value.as_ref(), ZipList::insert_value_left)?;
-        tr.commit()?;
-        Ok(result)
-    }
 
-    fn list_insert_after<K: Bytes, V: Bytes>(&mut self, key: &K, pivot: &V, value: &V) -> Result<i32, RrError> {
-        let mut quick = {
-            match QuickList::get(&self.db, key.as_ref())? {
-                None => return Ok(0),
-                Some(q) => q,
-            }
-        };
+    fn insert_before(&self, t: &T, key: &[u8], pivot: &[u8], value: &[u8]) -> Result<i32, RrError> {
+        insert(t, key, pivot, value, ZipList::insert_value_left)
+    }
 
-        let tr = self.db.transaction();
-        let result = quick.list_insert(&tr, key.as_ref(), pivot.as_ref(), value.as_ref(), ZipList::insert_value_right)?;
-        tr.commit()?;
-        Ok(result)
+    fn insert_after(&self, t: &T, key: &[u8], pivot: &[u8], value: &[u8]) -> Result<i32, RrError> {
+        insert(t, key, pivot, value, ZipList::insert_value_right)
     }
 
-    fn list_len<K: Bytes>(&self, key: &K) -> Result<i32, RrError> {
-        match QuickList::get(&self.db, key.as_ref())? {
+    fn len(&self, t: &T, key: &[u8]) -> Result<i32, RrError> {
+        check_type(t, key, KeyType::List)?;
+        match QuickList::get(t, key)? {
             None => Ok(-1),
             Some(quick) => Ok(quick.len_list() as i32),
         }
     }
 
-    fn list_pop_front<K: Bytes>(&mut self, key: &K) -> Result<Option<Vec<u8>>, RrError> {
-        let tr = self.db.transaction();
-        let mut quick = match QuickList::get(&self.db, key.as_ref())? {
-            None => return Ok(None),
-            Some(q) => q,
-        };
-        if quick.len_list() < 1 {
-            return Ok(None);
-        }
-        let node_key = quick.left().ok_or(RrError::none_error("left key"))?.clone();
-        let mut node = QuickListNode::get(&tr, node_key.as_ref())?.ok_or(RrError::none_error("left node"))?;
-        let zip_key = node.values_key().ok_or(RrError::none_error("zip key"))?.clone();
-        let mut zip = ZipList::get(&tr, zip_key.as_ref())?.ok_or(RrError::none_error("zip list"))?;
-        let value = zip.pop_left();
-
-        if zip.len() == 0 {
-            //没有数据，删除quick list node
-            if quick.len_node() == 1 {
-                tr.delete(zip_key)?;
-                tr.delete(node_key)?;
-                quick.set_right(&None);
-                quick.set_left(&None);
-                quick.set_len_list(0);
-                quick.set_len_node(0);
-                tr.put(key.as_ref(), quick)?;
-            } else {
-                let _left = node.right();
-                quick.set_right(&node.left());
-                quick.set_len_node(quick.len_node() - 1);
-                quick.set_len_list(quick.len_list() - 1);
-                tr.delete(zip_key)?;
-                tr.delete(node_key)?;
-                tr.put(key.as_ref(), quick)?;
-            }
-        } else {
-            node.set_len_list(zip.len());
-            node.set_len_bytes(zip.as_ref().len() as LenType);
-            quick.set_len_list(quick.len_list() - 1);
-
-            tr.put(zip_key, zip)?;
-            tr.put(node_key, node.as_ref())?;
+    fn pop_front(&self, t: &T, key: &[u8]) -> Result<Option<Vec<u8>>, RrError> {
+        match quick_for_update(t, key)? {
+            None => Ok(None),
+            Some(mut quick) => quick.lpop(t, key),
         }
-
-        tr.commit()?;
-        Ok(value)
     }
 
-    fn list_push_front<K: Bytes, V: Bytes>(&mut self, key: &K, value: &V) -> Result<i32, RrError> {
-        let tr = self.db.transaction();
-        let mut quick = match QuickList::get(&self.db, key.as_ref())? {
-            None => {
-                let mut q = QuickList::new();
-                q.init_meta_key(key.as_ref());
-                q
-            }
-            Some(q) => q,
-        };
-        let re = quick.lpush(&tr, key.as_ref(), value.as_ref())?;
-        tr.commit()?;
-        Ok(re)
+    fn push_front(&self, t: &T, key: &[u8], value: &[u8]) -> Result<i32, RrError> {
+        match quick_for_push(t, key, false)? {
+            None => Ok(0),
+            Some(mut quick) => quick.lpush(t, key, value),
+        }
     }
 
-    fn list_push_front_exists<K: Bytes, V: Bytes>(&mut self, key: &K, value: &V) -> Result<i32, RrError> {
-        let mut quick = match QuickList::get(&self.db, key.as_ref())? {
-            None => return Ok(0),
-            Some(q) => q,
-        };
-        let tr = self.db.transaction();
-        let re = quick.lpush(&tr, key.as_ref(), value.as_ref())?;
-        tr.commit()?;
-        Ok(re)
+    /// key不存在时返回0
+    fn push_front_exists(&self, t: &T, key: &[u8], value: &[u8]) -> Result<i32, RrError> {
+        match quick_for_push(t, key, true)? {
+            None => Ok(0),
+            Some(mut quick) => quick.lpush(t, key, value),
+        }
     }
 
-    fn list_range<K: Bytes>(&self, key: &K, start: i32, stop: i32) -> Result<Vec<Vec<u8>>, RrError> {
+    fn range(&self, t: &T, key: &[u8], start: i32, stop: i32) -> Result<Vec<Vec<u8>>, RrError> {
+        check_type(t, key, KeyType::List)?;
         let mut result = Vec::new();
-        let quick = match QuickList::get(&self.db, key.as_ref())? {
+        let quick = match QuickList::get(t, key)? {
             None => return Ok(result),
             Some(q) => q,
         };
-        if quick.len_list() < 1 {
-            return Ok(result);
-        }
-
-        let start_index = ZipList::count_index(quick.len_list() as i32, start) as usize;
-        let stop_index = ZipList::count_index(quick.len_list() as i32, stop) as usize;
-        if start_index > stop_index {
-            return Ok(result);
-        }
+        let (start_index, stop_index) = match count_range(quick.len_list(), start, stop) {
+            None => return Ok(result),
+            Some(r) => r,
+        };
 
-        //todo read only
-        let tr = self.db.transaction();
-
-        let node_key = quick.left().ok_or(RrError::none_error("left key"))?;
-        let mut node = QuickListNode::get(&tr, node_key.as_ref())?.ok_or(RrError::none_error("quick list node"))?;
-        let offset = 0usize;
-        loop {
-            let len_zip = node.len_list();
-            if start_index < len_zip as usize + offset {
-                let temp = ZipList::count_in_index(len_zip, offset, start_index, stop_index);
-                if let Some((start_in, stop_in)) = temp {
-                    let zip_key = node.values_key().ok_or(RrError::none_error("zip key"))?;
-                    let zip = ZipList::get(&tr, zip_key.as_ref())?.ok_or(RrError::none_error("zip"))?;
-                    let one = zip.range(start_in as i32, stop_in as i32);
-                    result.extend(one);
-                } //else 是没有数据
+        let mut node_key = quick.left().cloned();
+        let mut offset = 0usize;
+        while let Some(key) = node_key {
+            let node = QuickListNode::get(t, key.as_ref())?.ok_or_else(|| RrError::corruption(key.as_ref(), "quick list node"))?;
+            let len_zip = node.len_list() as usize;
+            if start_index < offset + len_zip {
+                let zip_key = node.values_key().ok_or_else(|| RrError::corruption(key.as_ref(), "zip key"))?;
+                let zip = ZipList::get(t, zip_key.as_ref())?.ok_or_else(|| RrError::corruption(zip_key.as_ref(), "zip"))?;
+                let start_in = start_index.saturating_sub(offset);
+                let stop_in = stop_index.min(offset + len_zip - 1) - offset;
+                result.extend(zip.range(start_in as i32, stop_in as i32));
             }
-
-            if stop_index < len_zip as usize + offset {
+            offset += len_zip;
+            if stop_index < offset {
                 //取了所有数据
                 break;
             }
-
-            if let Some(t) = node.right() {
-                node = QuickListNode::get(&tr, t.as_ref())?.ok_or(RrError::none_error("quick list node"))?;
-            } else {
-                // 没有更多的节点
-                break;
-            }
+            node_key = node.right().cloned();
         }
-
-        tr.commit()?;
         Ok(result)
     }
 
-    fn list_rem<K: Bytes, V: Bytes>(&mut self, list_key: &K, count: i32, value: &V) -> Result<LenType, RrError> {
-        let mut quick = match QuickList::get(&self.db, list_key.as_ref())? {
+    fn rem(&self, t: &T, list_key: &[u8], count: i32, value: &[u8]) -> Result<LenType, RrError> {
+        let mut quick = match quick_for_update(t, list_key)? {
             None => return Ok(0),
             Some(q) => q,
         };
+        if quick.len_list() < 1 {
+            return Ok(0);
+        }
 
-        let mut rem_count = 0u64;
-
-        let tr = self.db.transaction();
-
-        if count > 0 {
-            //正向遍历
-            let count = count as usize;
-            let mut node_key = quick.left().ok_or(RrError::none_error("left key"))?.clone();
-            let mut node = QuickListNode::get(&tr, node_key.as_ref())?.ok_or(RrError::none_error("left node"))?;
-
-            loop {
-                let zip_key = node.values_key().ok_or(RrError::none_error("zip key"))?.clone();
-                let mut zip = ZipList::get(&tr, zip_key.as_ref())?.ok_or(RrError::none_error("zip"))?;
-
-                let done = zip.rem((count - rem_count as usize) as i32, value.as_ref());
-                rem_count += done;
-
-                if done != 0 {
-                    quick.modify_node(&tr, list_key.as_ref(), zip_key.as_ref(), &mut zip, node_key.as_ref(), &mut node)?;
-                }
-
-                if rem_count == count as u64 {
-                    break;
-                }
-                if let Some(t) = node.right() {
-                    node_key = t.clone();
-                } else {
-                    break;
-                }
-                node = QuickListNode::get(&tr, node_key.as_ref())?.ok_or(RrError::none_error("right node"))?;
-            }
-        } else if count < 0 {
-            //反向遍历
-            let count = count.unsigned_abs() as usize;
-            let mut node_key = quick.right().ok_or(RrError::none_error("left key"))?.clone();
-            let mut node = QuickListNode::get(&tr, node_key.as_ref())?.ok_or(RrError::none_error("node"))?;
-
-            loop {
-                let zip_key = node.values_key().ok_or(RrError::none_error("zip key"))?.clone();
-                let mut zip = ZipList::get(&tr, zip_key.as_ref())?.ok_or(RrError::none_error("zip"))?;
-
-                let will_count = count as i32 - rem_count as i32;
-                let done = zip.rem(-will_count, value.as_ref());
-                rem_count += done;
-
-                if done != 0 {
-                    quick.modify_node(&tr, list_key.as_ref(), zip_key.as_ref(), &mut zip, node_key.as_ref(), &mut node)?;
-                }
-
-                if rem_count == count as u64 {
-                    break;
-                }
-                if let Some(t) = node.left() {
-                    node_key = t.clone();
-                } else {
-                    break;
-                }
-                node = QuickListNode::get(&tr, node_key.as_ref())?.ok_or(RrError::none_error("right node"))?;
+        //count为0时删除所有相等的值，大于0时正向遍历，小于0时反向遍历
+        let max = count.unsigned_abs() as LenType;
+        let mut rem_count: LenType = 0;
+        let mut node_key = if count < 0 { quick.right() } else { quick.left() }.cloned();
+        while let Some(key) = node_key {
+            let mut node = QuickListNode::get(t, key.as_ref())?.ok_or_else(|| RrError::corruption(key.as_ref(), "quick list node"))?;
+            let zip_key = node.values_key().ok_or_else(|| RrError::corruption(key.as_ref(), "zip key"))?.clone();
+            let mut zip = ZipList::get(t, zip_key.as_ref())?.ok_or_else(|| RrError::corruption(zip_key.as_ref(), "zip"))?;
+
+            let will_count = if count == 0 { 0 } else { count.signum() * (max - rem_count) as i32 };
+            let done = zip.rem(will_count, value);
+            rem_count += done;
+
+            if done != 0 {
+                quick.modify_node(t, zip_key.as_ref(), &mut zip, key.as_ref(), &mut node)?;
             }
-        } else {
-            //正向删除所有相等的值
-            let count = count as usize;
-            let mut node_key = quick.left().ok_or(RrError::none_error("left key"))?.clone();
-            let mut node = QuickListNode::get(&tr, node_key.as_ref())?.ok_or(RrError::none_error("left node"))?;
-
-            loop {
-                let zip_key = node.values_key().ok_or(RrError::none_error("zip key"))?.clone();
-                let mut zip = ZipList::get(&tr, zip_key.as_ref())?.ok_or(RrError::none_error("zip"))?;
-
-                let done = zip.rem((count - rem_count as usize) as i32, value.as_ref());
-                rem_count += done;
-
-                if done != 0 {
-                    quick.modify_node(&tr, list_key.as_ref(), zip_key.as_ref(), &mut zip, node_key.as_ref(), &mut node)?;
-                }
-                if let Some(t) = node.right() {
-                    node_key = t.clone();
-                } else {
-                    break;
-                }
-                node = QuickListNode::get(&tr, node_key.as_ref())?.ok_or(RrError::none_error("right node"))?;
+            if count != 0 && rem_count == max {
+                break;
             }
+            node_key = if count < 0 { node.left() } else { node.right() }.cloned();
         }
 
         if rem_count > 0 {
             quick.set_len_list(quick.len_list() - rem_count);
-            tr.put(list_key, quick)?;
+            quick.save_head(t, list_key)?;
         }
-
-        tr.commit()?;
         Ok(rem_count)
     }
 
-    fn list_trim<K: Bytes>(&mut self, _key: K, _start: i32, _stop: i32) -> Result<i32, RrError> {
-        todo!()
+    fn trim(&self, t: &T, key: &[u8], start: i32, stop: i32) -> Result<i32, RrError> {
+        let mut quick = match quick_for_update(t, key)? {
+            None => return Ok(0),
+            Some(q) => q,
+        };
+        match count_range(quick.len_list(), start, stop) {
+            None => {
+                //区间为空，与redis一样删除整个list
+                let len = quick.len_list();
+                quick.clear(t, key)?;
+                remove_key_meta(t, key)?;
+                Ok(len as i32)
+            }
+            Some((start, stop)) => Ok(quick.trim(t, key, start, stop)? as i32),
+        }
     }
 
-    fn list_set<K: Bytes, V: Bytes>(&mut self, key: &K, index: i32, value: &V) -> Result<Vec<u8>, RrError> {
-        let t = QuickList::get(&self.db, key.as_ref())?.ok_or(RrError::not_find("key of list"))?;
-        if index >= t.len_list() as i32 || index < 0 {
-            return Err(RrError::not_find(&format!("the index {}", index)));
+    fn set(&self, t: &T, key: &[u8], index: i32, value: &[u8]) -> Result<Vec<u8>, RrError> {
+        let quick = quick_for_update(t, key)?.ok_or_else(|| RrError::not_find("key"))?;
+        if index >= quick.len_list() as i32 || index < 0 {
+            return Err(RrError::index_out_of_range(index));
         }
-        //todo read only
-        let tr = self.db.transaction();
-        let node_key = t.left().context("left of quick list")?;
-        let mut node = QuickListNode::get(&tr, node_key.as_ref())?.context("left node")?;
+        let mut node_key = quick.left().ok_or_else(|| RrError::corruption(key, "left of quick list"))?.clone();
+        let mut node = QuickListNode::get(t, node_key.as_ref())?.ok_or_else(|| RrError::corruption(node_key.as_ref(), "left node"))?;
         let mut it_index = 0i32;
         it_index += node.len_list() as i32;
         while index >= it_index {
-            let next_key = node.right().context("right node")?;
-            node = QuickListNode::get(&tr, next_key.as_ref())?.context("next node")?;
+            node_key = node.right().ok_or_else(|| RrError::corruption(key, "right node"))?.clone();
+            node = QuickListNode::get(t, node_key.as_ref())?.ok_or_else(|| RrError::corruption(node_key.as_ref(), "next node"))?;
             it_index += node.len_list() as i32;
         }
 
-        let value_key = node.values_key().context("value key")?;
-        let mut zip = ZipList::get(&tr, value_key.as_ref())?.context("zip list")?;
+        let value_key = node.values_key().ok_or_else(|| RrError::corruption(key, "value key"))?.clone();
+        let mut zip = ZipList::get(t, value_key.as_ref())?.ok_or_else(|| RrError::corruption(value_key.as_ref(), "zip list"))?;
         let zip_index = index - (it_index - node.len_list() as i32);
-        let v = zip.set(zip_index, value.as_ref()).ok_or(RrError::not_find(&format!("the index {}", index)))?;
-        tr.put(value_key, zip)?;
-        tr.commit()?;
+        let v = zip.set(zip_index, value).ok_or_else(|| RrError::index_out_of_range(index))?;
+        node.set_len_bytes(zip.as_ref().len() as LenType);
+        t.put(value_key.as_ref(), zip.as_ref())?;
+        t.put(node_key.as_ref(), node.as_ref())?;
         Ok(v)
     }
 
-    fn list_pop_back<K: Bytes>(&mut self, key: &K) -> Result<Option<Vec<u8>>, RrError> {
-        let tr = self.db.transaction();
-        let mut quick = match QuickList::get(&self.db, key.as_ref())? {
-            None => return Ok(None),
-            Some(q) => q,
-        };
-        if quick.len_list() < 1 {
-            return Ok(None);
-        }
-        let node_key = quick.right().ok_or(RrError::none_error("right key"))?.clone();
-        let mut node = QuickListNode::get(&tr, node_key.as_ref())?.ok_or(RrError::none_error("right node"))?;
-        let zip_key = node.values_key().ok_or(RrError::none_error("zip key"))?.clone();
-        let mut zip = ZipList::get(&tr, zip_key.as_ref())?.ok_or(RrError::none_error("zip list"))?;
-        let value = zip.pop_right();
-
-        if zip.len() == 0 {
-            //没有数据，删除quick list node
-            if quick.len_node() == 1 {
-                tr.delete(zip_key)?;
-                tr.delete(node_key)?;
-                quick.set_right(&None);
-                quick.set_left(&None);
-                quick.set_len_list(0);
-                quick.set_len_node(0);
-                tr.put(key.as_ref(), quick)?;
-            } else {
-                let _left = node.left();
-                quick.set_right(&node.left());
-                quick.set_len_node(quick.len_node() - 1);
-                quick.set_len_list(quick.len_list() - 1);
-                tr.delete(zip_key)?;
-                tr.delete(node_key)?;
-                tr.put(key.as_ref(), quick)?;
-            }
-        } else {
-            node.set_len_list(zip.len());
-            node.set_len_bytes(zip.as_ref().len() as LenType);
-            quick.set_len_list(quick.len_list() - 1);
-
-            tr.put(zip_key.as_ref(), zip)?;
-            tr.put(node_key.as_ref(), node.as_ref())?;
+    fn pop_back(&self, t: &T, key: &[u8]) -> Result<Option<Vec<u8>>, RrError> {
+        match quick_for_update(t, key)? {
+            None => Ok(None),
+            Some(mut quick) => quick.rpop(t, key),
         }
-
-        tr.commit()?;
-        Ok(value)
     }
 
-    fn list_replace_back<K: Bytes, V: Bytes>(&mut self, _key: &K, _dstkey: &K) -> Result<V, RrError> {
-        todo!()
+    /// 先检查dstkey的类型，这样类型不对时不会修改key
+    fn replace_back(&self, t: &T, key: &[u8], dstkey: &[u8]) -> Result<Option<Vec<u8>>, RrError> {
+        check_type(t, dstkey, KeyType::List)?;
+        let value = match self.pop_back(t, key)? {
+            None => return Ok(None),
+            Some(v) => v,
+        };
+        //key与dstkey相同时，读到的是上面pop_back修改后的quick list
+        self.push_front(t, dstkey, &value)?;
+        Ok(Some(value))
     }
 
-    fn list_push_back<K: Bytes, V: Bytes>(&mut self, key: &K, value: &V) -> Result<i32, RrError> {
-        let tr = self.db.transaction();
-        let mut quick = match QuickList::get(&self.db, key.as_ref())? {
-            None => {
-                let mut q = QuickList::new();
-                q.init_meta_key(key.as_ref());
-                q
-            }
-            Some(q) => q,
-        };
-        let re = quick.rpush(&tr, key.as_ref(), value.as_ref())?;
-        tr.commit()?;
-        Ok(re)
+    fn push_back(&self, t: &T, key: &[u8], value: &[u8]) -> Result<i32, RrError> {
+        match quick_for_push(t, key, false)? {
+            None => Ok(0),
+            Some(mut quick) => quick.rpush(t, key, value),
+        }
     }
 
-    fn list_push_back_exists<K: Bytes, V: Bytes>(&mut self, key: &K, value: &V) -> Result<i32, RrError> {
-        let tr = self.db.transaction();
-        let mut quick = match QuickList::get(&self.db, key.as_ref())? {
-            None => return Ok(0),
-            Some(q) => q,
-        };
-        let re = quick.rpush(&tr, key.as_ref(), value.as_ref())?;
-        tr.commit()?;
-        Ok(re)
+    /// key不存在时返回0
+    fn push_back_exists(&self, t: &T, key: &[u8], value: &[u8]) -> Result<i32, RrError> {
+        match quick_for_push(t, key, true)? {
+            None => Ok(0),
+            Some(mut quick) => quick.rpush(t, key, value),
+        }
     }
 
-    fn list_clear<K: Bytes>(&mut self, key: &K) -> Result<i32, RrError> {
-        let tr = self.db.transaction();
-        let mut quick = match QuickList::get(&self.db, key.as_ref())? {
+    fn clear(&self, t: &T, key: &[u8]) -> Result<i32, RrError> {
+        check_type(t, key, KeyType::List)?;
+        let mut quick = match QuickList::get_for_update(t, key)? {
             None => return Ok(0),
             Some(q) => q,
         };
         let re = quick.len_node();
-        quick.clear(&tr, key.as_ref())?;
-        tr.commit()?;
+        quick.clear(t, key)?;
+        remove_key_meta(t, key)?;
         Ok(re as i32)
     }
 }
+
+/// 与redis一样把start与stop转换为有效的下标，区间为空时返回None
+fn count_range(len: LenType, start: i32, stop: i32) -> Option<(usize, usize)> {
+    let len = len as i64;
+    let start = if start < 0 { (start as i64 + len).max(0) } else { start as i64 };
+    let stop = if stop < 0 { stop as i64 + len } else { (stop as i64).min(len - 1) };
+    if start > stop || start >= len {
+        return None;
+    }
+    Some((start as usize, stop as usize))
+}
+
+/// 检查类型后读取并锁定quick list，key不存在时返回None
+fn quick_for_update<T: WrapDb>(t: &T, key: &[u8]) -> Result<Option<QuickList>, RrError> {
+    if !touch_type(t, key, KeyType::List)? {
+        return Ok(None);
+    }
+    QuickList::get_for_update(t, key)
+}
+
+/// 读取并锁定quick list，exists为true时key不存在返回None，否则新建一个
+fn quick_for_push<T: WrapDb>(t: &T, key: &[u8], exists: bool) -> Result<Option<QuickList>, RrError> {
+    if exists {
+        if !touch_type(t, key, KeyType::List)? {
+            return Ok(None);
+        }
+    } else {
+        ensure_type(t, key, KeyType::List)?;
+    }
+    match QuickList::get_for_update(t, key)? {
+        Some(q) => Ok(Some(q)),
+        None if exists => Ok(None),
+        None => {
+            let mut q = QuickList::new();
+            q.init_meta_key(key);
+            Ok(Some(q))
+        }
+    }
+}
+
+fn insert<T: WrapDb>(t: &T, key: &[u8], pivot: &[u8], value: &[u8], f: fn(&mut ZipList, &[u8], &[u8]) -> Option<i32>) -> Result<i32, RrError> {
+    match quick_for_update(t, key)? {
+        None => Ok(0),
+        Some(q) if q.len_list() < 1 => Ok(0),
+        Some(mut quick) => quick.list_insert(t, key, pivot, value, f),
+    }
+}
@@ -0,0 +1,124 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Arc, Condvar, Mutex, MutexGuard},
+    time::Instant,
+};
+
+/// key为 数据库的编号(u32 BE) + key
+type Queues = Mutex<HashMap<Vec<u8>, VecDeque<Arc<Waiter>>>>;
+
+/// 阻塞在list上的等待者，每个key一个队列，按到达的先后顺序排队
+/// 只有队首的等待者可以取数据，push时唤醒队首的等待者，这样与redis一样是公平的
+/// 不同数据库中的同名key使用不同的队列，[ListWaiters::select]返回的对象共享所有的队列
+#[derive(Default)]
+pub(crate) struct ListWaiters {
+    queues: Arc<Queues>,
+    namespace: u32,
+}
+
+#[derive(Default)]
+pub(crate) struct Waiter {
+    ready: Mutex<bool>,
+    cond: Condvar,
+}
+
+impl Waiter {
+    /// 清除唤醒标志，在尝试pop之前调用，这样在pop与wait之间发生的push不会丢失
+    pub fn reset(&self) {
+        *lock(&self.ready) = false;
+    }
+
+    pub fn notify(&self) {
+        let mut ready = lock(&self.ready);
+        *ready = true;
+        self.cond.notify_one();
+    }
+
+    /// deadline为None时一直等待，返回false表示超时
+    pub fn wait(&self, deadline: Option<Instant>) -> bool {
+        let mut ready = lock(&self.ready);
+        while !*ready {
+            match deadline {
+                None => ready = self.cond.wait(ready).unwrap_or_else(|e| e.into_inner()),
+                Some(deadline) => {
+                    let now = Instant::now();
+                    if now >= deadline {
+                        return false;
+                    }
+                    ready = self.cond.wait_timeout(ready, deadline - now).unwrap_or_else(|e| e.into_inner()).0;
+                }
+            }
+        }
+        true
+    }
+}
+
+impl ListWaiters {
+    pub fn select(&self, namespace: u32) -> Self {
+        ListWaiters {
+            queues: self.queues.clone(),
+            namespace,
+        }
+    }
+
+    fn queue_key(&self, key: &[u8]) -> Vec<u8> {
+        [&self.namespace.to_be_bytes()[..], key].concat()
+    }
+
+    /// 加入到key的等待队列的尾部，一个等待者可以加入多个key的队列，用于同时等待多个list或stream
+    pub fn add(&self, key: &[u8], waiter: &Arc<Waiter>) {
+        lock(&self.queues).entry(self.queue_key(key)).or_default().push_back(waiter.clone());
+    }
+
+    pub fn is_front(&self, key: &[u8], waiter: &Arc<Waiter>) -> bool {
+        match lock(&self.queues).get(&self.queue_key(key)).and_then(|q| q.front()) {
+            None => false,
+            Some(front) => Arc::ptr_eq(front, waiter),
+        }
+    }
+
+    /// 从等待队列中删除，如果删除的是队首，唤醒新的队首，以免list中剩余的数据没有人取
+    pub fn remove(&self, key: &[u8], waiter: &Arc<Waiter>) {
+        let key = self.queue_key(key);
+        let mut queues = lock(&self.queues);
+        if let Some(queue) = queues.get_mut(&key) {
+            let was_front = queue.front().map(|f| Arc::ptr_eq(f, waiter)).unwrap_or(false);
+            queue.retain(|w| !Arc::ptr_eq(w, waiter));
+            if queue.is_empty() {
+                queues.remove(&key);
+            } else if was_front {
+                if let Some(front) = queue.front() {
+                    front.notify();
+                }
+            }
+        }
+    }
+
+    /// list有新数据时调用，唤醒队首的等待者
+    pub fn notify(&self, key: &[u8]) {
+        if let Some(front) = lock(&self.queues).get(&self.queue_key(key)).and_then(|q| q.front()) {
+            front.notify();
+        }
+    }
+
+    /// 数据库被交换时调用，唤醒namespace中所有的等待者，它们会重新检查自己等待的key
+    pub fn notify_namespace(&self) {
+        let prefix = self.namespace.to_be_bytes();
+        lock(&self.queues)
+            .iter()
+            .filter(|(key, _)| key.starts_with(&prefix))
+            .for_each(|(_, queue)| queue.iter().for_each(|w| w.notify()));
+    }
+
+    /// stream有新数据时调用，xread不会取走数据，所以唤醒所有的等待者
+    pub fn notify_all(&self, key: &[u8]) {
+        if let Some(queue) = lock(&self.queues).get(&self.queue_key(key)) {
+            queue.iter().for_each(|w| w.notify());
+        }
+    }
+}
+
+/// 等待者中没有会panic的代码，所以锁中毒时直接使用里面的数据
+fn lock<T>(m: &Mutex<T>) -> MutexGuard<'_, T> {
+    m.lock().unwrap_or_else(|e| e.into_inner())
+}
@@ -0,0 +1,216 @@
+use std::collections::HashMap;
+
+use rocksdb::{Direction, IteratorMode};
+
+use crate::{
+    rocksdb_impl::{
+        expire_impl::set_expire,
+        key_space_impl::{copy_list, delete_list, delete_prefix, get_quick_list, get_type, make_type_key},
+        shared::{make_field_key, make_head_key, KEY_LAYOUT_VERSION, LAYOUT_VERSION_KEY},
+        wrap_db_impl::total_order_read,
+    },
+    KeyType, LenType, RedisRocksdb, RrError, TransactionalDb, WrapDb, WrapTransaction,
+};
+
+/// 最初的格式（版本0）与版本1一样存放数据，只是没有登记类型与过期时间，不能从数据判断key的类型
+/// 旧的格式（版本1）：类型与过期时间使用下面的前缀，string、list、stack的head直接存放在key中
+/// stack的元素存放在 key + '_' + index(i64 LE) 中，其它类型的数据存放在 key + ":__" + field 中
+/// list的node与zip list存放在meta key中，不用修改key，复制到新的list中
+const OLD_KEY_TYPE_PREFIX: &[u8] = b"\0__key_type:";
+const OLD_EXPIRE_KEY_PREFIX: &[u8] = b"\0__expire_key:";
+const OLD_EXPIRE_TIME_PREFIX: &[u8] = b"\0__expire_time:";
+const OLD_FIELD_SEP: &[u8] = b":__";
+const OLD_STACK_SEP: &[u8] = b"_";
+
+impl<D: TransactionalDb> RedisRocksdb<D> {
+    /// 数据库中key的格式的版本，参见[RedisRocksdb::migrate_key_layout]
+    /// 没有版本记录时从记录判断：有旧格式的类型登记为1，有其它的数据为0（最初的格式），只有空的数据库为当前的版本
+    pub fn key_layout_version(db: &D) -> Result<u8, RrError> {
+        if let Some(v) = db.get_in(None, LAYOUT_VERSION_KEY)? {
+            return v.first().copied().ok_or_else(|| RrError::corruption(LAYOUT_VERSION_KEY, "layout version"));
+        }
+        let old = match db.prefix_iterator_in(None, OLD_KEY_TYPE_PREFIX).next() {
+            None => false,
+            Some(item) => item?.0.starts_with(OLD_KEY_TYPE_PREFIX),
+        };
+        if old {
+            return Ok(1);
+        }
+        //以0开始的是数据库自己的数据，如数据库的映射
+        let mode = IteratorMode::From(&[1], Direction::Forward);
+        let data = match db.iterator_in(None, total_order_read(), mode).next() {
+            None => false,
+            Some(item) => item.is_ok(),
+        };
+        Ok(if data { 0 } else { KEY_LAYOUT_VERSION })
+    }
+
+    /// 打开数据库时调用，空的数据库写入当前的版本，这样之后没有版本记录的数据库都是旧的格式
+    /// 旧的格式返回错误，不然读取不到旧的数据，写入同名的key后转换时还会删除旧的数据
+    pub(crate) fn check_key_layout(db: &D) -> Result<(), RrError> {
+        let version = RedisRocksdb::key_layout_version(db)?;
+        if version != KEY_LAYOUT_VERSION {
+            return Err(RrError::message(format!(
+                "the database uses key layout version {}, migrate it with RedisRocksdb::migrate_key_layout before opening",
+                version
+            )));
+        }
+        if db.get_in(None, LAYOUT_VERSION_KEY)?.is_none() {
+            db.put_in(None, LAYOUT_VERSION_KEY, &[KEY_LAYOUT_VERSION])?;
+        }
+        Ok(())
+    }
+
+    /// 把旧格式的数据转换为当前的格式，返回转换的key的个数，已经是当前的格式时返回0
+    /// 旧的格式中 key + ":__" 可能是另一个key的字段，转换时字段属于最长的那一个已登记的key
+    /// 每一个key在一个事务中转换，中断后再次调用会继续转换剩下的key，转换时不要有其它的读写
+    /// 没有登记类型的数据（版本0写入的）不知道属于哪一个key，保留不动，版本0的数据库返回错误，使用[RedisRocksdb::migrate_key_layout_with]
+    /// 旧格式的数据库不能用来新建[RedisRocksdb]，所以在打开之前转换，也可以使用[crate::RedisRocksdbBuilder::migrate_key_layout]
+    pub fn migrate_key_layout(db: &D) -> Result<LenType, RrError> {
+        RedisRocksdb::migrate_key_layout_with(db, &[])
+    }
+
+    /// 与[RedisRocksdb::migrate_key_layout]一样，key_types给出没有登记类型的key（版本0写入的）的类型
+    /// 先按版本1的格式登记其中有数据的key，然后一起转换，没有数据的key忽略
+    pub fn migrate_key_layout_with(db: &D, key_types: &[(&[u8], KeyType)]) -> Result<LenType, RrError> {
+        match RedisRocksdb::key_layout_version(db)? {
+            KEY_LAYOUT_VERSION => return Ok(0),
+            0 if key_types.is_empty() => {
+                return Err(RrError::message(
+                    "the database was written without key types, use migrate_key_layout_with to give the types".to_owned(),
+                ))
+            }
+            _ => {}
+        }
+        let tr = db.transaction();
+        let t = WrapTransaction::new(&tr);
+        for (key, key_type) in key_types {
+            let type_key = [OLD_KEY_TYPE_PREFIX, key].concat();
+            if t.get(&type_key)?.is_none() && has_old_data(&t, key, *key_type)? {
+                t.put(&type_key, &[*key_type as u8])?;
+            }
+        }
+        tr.commit()?;
+
+        let mut keys = HashMap::new();
+        for item in db.prefix_iterator_in(None, OLD_KEY_TYPE_PREFIX) {
+            let (k, v) = item?;
+            if !k.starts_with(OLD_KEY_TYPE_PREFIX) {
+                break;
+            }
+            let key_type = match v.first() {
+                Some(b) => KeyType::try_from(*b)?,
+                None => return Err(RrError::corruption(&k, "key type")),
+            };
+            keys.insert(k[OLD_KEY_TYPE_PREFIX.len()..].to_vec(), (key_type, v.to_vec()));
+        }
+        for (key, (key_type, type_value)) in &keys {
+            let tr = db.transaction();
+            migrate_key(&WrapTransaction::new(&tr), key, *key_type, type_value, &keys)?;
+            tr.commit()?;
+        }
+        //剩下的是没有登记类型的key的过期时间
+        let tr = db.transaction();
+        let t = WrapTransaction::new(&tr);
+        delete_prefix(&t, OLD_EXPIRE_KEY_PREFIX)?;
+        delete_prefix(&t, OLD_EXPIRE_TIME_PREFIX)?;
+        t.put(LAYOUT_VERSION_KEY, &[KEY_LAYOUT_VERSION])?;
+        tr.commit()?;
+        Ok(keys.len() as LenType)
+    }
+}
+
+type OldKeys = HashMap<Vec<u8>, (KeyType, Vec<u8>)>;
+
+/// 转换一个key的数据、类型与过期时间，已经有当前格式的同名key时，旧的数据已经不能访问了，只删除它
+fn migrate_key<T: WrapDb>(t: &T, key: &[u8], key_type: KeyType, type_value: &[u8], keys: &OldKeys) -> Result<(), RrError> {
+    let exists = get_type(t, key)?.is_some();
+    match key_type {
+        KeyType::String => move_record(t, key, &make_head_key(KeyType::String, key), exists)?,
+        KeyType::List => {
+            if !exists && get_quick_list(t, key)?.is_some() {
+                copy_list(t, key, key)?;
+            }
+            delete_list(t, key)?;
+        }
+        KeyType::Stack => {
+            move_record(t, key, &make_head_key(KeyType::Stack, key), exists)?;
+            let prefix = [key, OLD_STACK_SEP].concat();
+            for k in old_records(t, &prefix)? {
+                if k.len() == prefix.len() + 8 && !keys.contains_key(&k) {
+                    move_record(t, &k, &make_field_key(KeyType::Stack, key, &k[prefix.len()..]), exists)?;
+                }
+            }
+        }
+        _ => {
+            let prefix = [key, OLD_FIELD_SEP].concat();
+            for k in old_records(t, &prefix)? {
+                if !keys.contains_key(&k) && field_owner(&k, keys) == Some(key) {
+                    move_record(t, &k, &make_field_key(key_type, key, &k[prefix.len()..]), exists)?;
+                }
+            }
+        }
+    }
+    t.delete(&[OLD_KEY_TYPE_PREFIX, key].concat())?;
+    let old_expire_key = [OLD_EXPIRE_KEY_PREFIX, key].concat();
+    if let Some(v) = t.get(&old_expire_key)? {
+        let deadline = i64::from_le_bytes(v.as_slice().try_into()?);
+        t.delete(&old_expire_key)?;
+        t.delete(&[OLD_EXPIRE_TIME_PREFIX, &(deadline.max(0) as u64).to_be_bytes(), key].concat())?;
+        if !exists {
+            set_expire(t, key, deadline)?;
+        }
+    }
+    if !exists {
+        t.put(&make_type_key(key), type_value)?;
+    }
+    Ok(())
+}
+
+/// 版本0的key是否有数据，string、list、stack的head直接存放在key中，其它类型有 key + ":__" 开始的记录
+fn has_old_data<T: WrapDb>(t: &T, key: &[u8], key_type: KeyType) -> Result<bool, RrError> {
+    match key_type {
+        KeyType::String | KeyType::List | KeyType::Stack => Ok(t.get(key)?.is_some()),
+        _ => {
+            let prefix = [key, OLD_FIELD_SEP].concat();
+            match t.prefix_iterator(&prefix).next() {
+                None => Ok(false),
+                Some(item) => Ok(item?.0.starts_with(&prefix)),
+            }
+        }
+    }
+}
+
+/// 把old的值移动到new中，drop为true时只删除old
+fn move_record<T: WrapDb>(t: &T, old: &[u8], new: &[u8], drop: bool) -> Result<(), RrError> {
+    if let Some(v) = t.get(old)? {
+        if !drop {
+            t.put(new, &v)?;
+        }
+        t.delete(old)?;
+    }
+    Ok(())
+}
+
+/// 以prefix开始的key，先全部读出来，因为要边遍历边写入
+fn old_records<T: WrapDb>(t: &T, prefix: &[u8]) -> Result<Vec<Vec<u8>>, RrError> {
+    let mut re = Vec::new();
+    for item in t.prefix_iterator(prefix) {
+        let (k, _) = item?;
+        if !k.starts_with(prefix) {
+            break;
+        }
+        re.push(k.to_vec());
+    }
+    Ok(re)
+}
+
+/// 旧的字段key为 key + ":__" + field，返回最长的、使用字段存放数据的已登记的key
+fn field_owner<'a>(field_key: &'a [u8], keys: &OldKeys) -> Option<&'a [u8]> {
+    field_key
+        .windows(OLD_FIELD_SEP.len())
+        .enumerate()
+        .filter(|(_, w)| *w == OLD_FIELD_SEP)
+        .map(|(pos, _)| &field_key[..pos])
+        .rfind(|k| matches!(keys.get(*k), Some((t, _)) if !matches!(t, KeyType::String | KeyType::List | KeyType::Stack)))
+}
@@ -1,19 +1,51 @@
+pub use bitmap_impl::BitmapImpl;
+pub use bptree::*;
+pub use column_families::ColumnFamilies;
+pub use expire_impl::{ExpireIndex, ExpireSweeper};
+pub use geo_impl::GeoImpl;
 pub use heap::*;
+pub use hyper_log_log_impl::HyperLogLogImpl;
+pub use key_space_impl::KeySpaceImpl;
+pub use list_impl::ListImpl;
 pub use object_bit::*;
 pub use object_impl::*;
+pub use pub_sub_impl::Subscriber;
 pub use redis_rocksdb::RedisRocksdb;
+pub use redis_rocksdb_builder::RedisRocksdbBuilder;
+pub use redis_transaction::{RedisTransaction, WatchedKeys};
+pub use set_impl::SetImpl;
+pub use sorted_set_impl::*;
+pub use stream_impl::StreamImpl;
+pub use transactional_db::TransactionalDb;
 pub use wrap_db_impl::*;
 
+mod bitmap_impl;
 mod bptree;
+mod column_families;
+mod expire_impl;
+mod geo_impl;
 mod heap;
+mod hyper_log_log_impl;
+mod key_space_impl;
 mod key_value_impl;
 mod list_impl;
+mod list_waiters;
+mod migrate_impl;
+mod namespace_impl;
 mod object_bit;
 mod object_impl;
+mod pub_sub_impl;
 mod quick_list;
 mod quick_list_node;
 mod redis_rocksdb;
+mod redis_rocksdb_builder;
+mod redis_transaction;
+mod scan_impl;
+mod set_impl;
 mod shared;
+mod sorted_set_impl;
 mod stack_impl;
+mod stream_impl;
+mod transactional_db;
 mod wrap_db_impl;
 mod zip_list;
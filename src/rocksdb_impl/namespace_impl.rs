@@ -0,0 +1,243 @@
+use std::{
+    collections::HashMap,
+    sync::{RwLock, RwLockReadGuard, RwLockWriteGuard},
+};
+
+use rocksdb::{ColumnFamily, Direction, IteratorMode, WriteBatchWithTransaction};
+
+use crate::{
+    rocksdb_impl::{
+        shared::{namespace_key, namespace_prefix, split_namespace, TAG_EXPIRE, TAG_NAMESPACE, TAG_TYPE},
+        wrap_db_impl::total_order_read,
+    },
+    ExpireIndex, LenType, RedisRocksdb, RrError, TransactionalDb, WrapDb,
+};
+
+/// 数据库的编号与存放数据的namespace的对应关系，只保存不相同的，value为 (编号 u32 BE + namespace u32 BE)*
+/// SWAPDB只交换对应关系，不用移动数据
+const NAMESPACES_KEY: &[u8] = b"\0namespaces";
+
+/// 分批删除时，一批的key的个数
+const FLUSH_BATCH: usize = 1024;
+
+/// 数据库的编号（SELECT的参数）到namespace的映射，没有记录的编号与namespace相同
+#[derive(Default)]
+pub(crate) struct Namespaces {
+    map: RwLock<HashMap<u32, u32>>,
+}
+
+impl Namespaces {
+    pub fn load<D: TransactionalDb>(db: &D) -> Result<Self, RrError> {
+        let mut map = HashMap::new();
+        if let Some(v) = db.get_in(None, NAMESPACES_KEY)? {
+            if v.len() % 8 != 0 {
+                return Err(RrError::corruption(NAMESPACES_KEY, "namespaces"));
+            }
+            for pair in v.chunks_exact(8) {
+                let (index, namespace) = pair.split_at(4);
+                map.insert(u32::from_be_bytes(index.try_into()?), u32::from_be_bytes(namespace.try_into()?));
+            }
+        }
+        Ok(Namespaces { map: RwLock::new(map) })
+    }
+
+    /// 编号为index的数据库存放数据的namespace
+    pub fn physical(&self, index: u32) -> u32 {
+        self.read().get(&index).copied().unwrap_or(index)
+    }
+
+    /// [Namespaces::physical]的反向
+    pub fn logical(&self, namespace: u32) -> u32 {
+        self.read().iter().find(|(_, n)| **n == namespace).map(|(i, _)| *i).unwrap_or(namespace)
+    }
+
+    /// 交换两个数据库的namespace，先保存再修改内存中的映射
+    fn swap<D: TransactionalDb>(&self, db: &D, a: u32, b: u32) -> Result<(), RrError> {
+        let mut map = self.write();
+        let (na, nb) = (map.get(&a).copied().unwrap_or(a), map.get(&b).copied().unwrap_or(b));
+        let mut new_map = map.clone();
+        for (index, namespace) in [(a, nb), (b, na)] {
+            if index == namespace {
+                new_map.remove(&index);
+            } else {
+                new_map.insert(index, namespace);
+            }
+        }
+        let value: Vec<u8> = new_map.iter().flat_map(|(i, n)| [i.to_be_bytes(), n.to_be_bytes()].concat()).collect();
+        db.put_in(None, NAMESPACES_KEY, &value)?;
+        *map = new_map;
+        Ok(())
+    }
+
+    fn read(&self) -> RwLockReadGuard<'_, HashMap<u32, u32>> {
+        self.map.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn write(&self) -> RwLockWriteGuard<'_, HashMap<u32, u32>> {
+        self.map.write().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// 对应redis的SELECT、DBSIZE、FLUSHDB、FLUSHALL与SWAPDB
+/// 不是0号的数据库的key加上namespace的前缀后存放，所以各个数据库的数据是分开的，参见[WrapDb::namespace]
+/// FLUSHDB与FLUSHALL在[rocksdb::OptimisticTransactionDB]上使用DeleteRange，TransactionDB不支持DeleteRange，只能遍历要删除的范围分批删除
+/// 两种都与同时进行的写入不是原子的
+impl<D: TransactionalDb> RedisRocksdb<D> {
+    /// 返回编号为index的数据库，与self共享db、阻塞的等待者、过期索引与订阅关系
+    pub fn select(&self, index: u32) -> RedisRocksdb<D> {
+        RedisRocksdb {
+            db: self.db.clone(),
+            namespace: index,
+            namespaces: self.namespaces.clone(),
+            list_waiters: self.list_waiters.select(index),
+            stream_waiters: self.stream_waiters.select(index),
+            expire_index: self.expire_index.clone(),
+            pub_sub: self.pub_sub.clone(),
+            max_retries: self.max_retries,
+        }
+    }
+
+    /// 选择的数据库的编号
+    pub fn namespace(&self) -> u32 {
+        self.namespace
+    }
+
+    /// 选择的数据库中key的个数，包含已过期还没有删除的key
+    pub fn db_size(&self) -> Result<LenType, RrError> {
+        let t = self.wrap_db();
+        let mut size = 0;
+        for item in t.prefix_iterator(&[TAG_TYPE]) {
+            let (k, _) = item?;
+            if k.first() != Some(&TAG_TYPE) {
+                break;
+            }
+            size += 1;
+        }
+        Ok(size)
+    }
+
+    /// 删除选择的数据库中所有的key
+    /// 在[rocksdb::TransactionDB]上要遍历删除每一个key，key很多时很慢，参见[TransactionalDb::delete_range_in]
+    pub fn flush_db(&self) -> Result<(), RrError> {
+        match self.namespaces.physical(self.namespace) {
+            0 => {
+                self.delete_range(&[TAG_TYPE], Some(&[TAG_NAMESPACE]))?;
+                self.delete_range(&[TAG_NAMESPACE + 1], None)
+            }
+            namespace => match namespace.checked_add(1) {
+                Some(next) => self.delete_range(&namespace_prefix(namespace), Some(&namespace_prefix(next))),
+                None => self.delete_range(&namespace_prefix(namespace), Some(&[TAG_NAMESPACE + 1])),
+            },
+        }
+    }
+
+    /// 删除所有数据库中的key，保留数据库自己的数据（如key格式的版本与数据库的映射）
+    /// 与[RedisRocksdb::flush_db]一样，在[rocksdb::TransactionDB]上要遍历删除每一个key
+    pub fn flush_all(&self) -> Result<(), RrError> {
+        self.delete_range(&[TAG_TYPE], None)
+    }
+
+    /// 交换两个数据库，之后使用a的连接看到的是原来b中的数据，阻塞在这两个数据库上的等待者会重新检查它们的key
+    pub fn swap_db(&self, a: u32, b: u32) -> Result<(), RrError> {
+        if a != b {
+            self.namespaces.swap(&*self.db, a, b)?;
+            for index in [a, b] {
+                self.list_waiters.select(index).notify_namespace();
+                self.stream_waiters.select(index).notify_namespace();
+            }
+        }
+        Ok(())
+    }
+
+    /// 有数据的namespace，0总是包含在内，key的类型登记都在default中，所以只遍历default
+    /// 每次找到一个namespace后跳到下一个namespace的开始，不用遍历所有的key
+    pub(crate) fn physical_namespaces(&self) -> Result<Vec<u32>, RrError> {
+        let mut re = vec![0];
+        let mut from = namespace_prefix(1);
+        loop {
+            let mode = IteratorMode::From(&from, Direction::Forward);
+            let k = match self.db.iterator_in(None, total_order_read(), mode).next() {
+                None => break,
+                Some(item) => item?.0,
+            };
+            if k.first() != Some(&TAG_NAMESPACE) {
+                break;
+            }
+            let namespace = split_namespace(&k).0;
+            if namespace == 0 {
+                return Err(RrError::corruption(&k, "namespace"));
+            }
+            re.push(namespace);
+            match namespace.checked_add(1) {
+                None => break,
+                Some(next) => from = namespace_prefix(next),
+            }
+        }
+        Ok(re)
+    }
+
+    /// 删除default与所有column family中[from, to)的key，to为None时删除到最后
+    fn delete_range(&self, from: &[u8], to: Option<&[u8]>) -> Result<(), RrError> {
+        self.forget_expires(from, to)?;
+        self.delete_range_cf(None, from, to)?;
+        for cf in self.column_families().handles() {
+            self.delete_range_cf(Some(cf), from, to)?;
+        }
+        Ok(())
+    }
+
+    /// 删除的key不能再被compaction filter当作已过期，不然新写入的同名key的数据会被删除
+    /// 所以先从[ExpireIndex]中移除范围中的过期时间，只遍历过期时间的记录，它们都在default中
+    fn forget_expires(&self, from: &[u8], to: Option<&[u8]>) -> Result<(), RrError> {
+        for namespace in self.physical_namespaces()? {
+            let prefix = namespace_key(namespace, &[TAG_EXPIRE]);
+            if &prefix[..] < from || to.is_some_and(|to| &prefix[..] >= to) {
+                continue;
+            }
+            let mode = IteratorMode::From(&prefix, Direction::Forward);
+            for item in self.db.iterator_in(None, total_order_read(), mode) {
+                let (k, _) = item?;
+                if !k.starts_with(&prefix) {
+                    break;
+                }
+                ExpireIndex::remove_all(&namespace_key(namespace, &k[prefix.len()..]));
+            }
+        }
+        Ok(())
+    }
+
+    fn delete_range_cf(&self, cf: Option<&ColumnFamily>, from: &[u8], to: Option<&[u8]>) -> Result<(), RrError> {
+        //DeleteRange要有结束的key，没有时使用最后一个key之后的key
+        let to = match to {
+            Some(to) => to.to_vec(),
+            None => match self.db.iterator_in(cf, total_order_read(), IteratorMode::End).next() {
+                Some(item) => [&item?.0[..], &[0]].concat(),
+                None => return Ok(()),
+            },
+        };
+        if to.as_slice() <= from || self.db.delete_range_in(cf, from, &to)? {
+            return Ok(());
+        }
+        let mut opts = total_order_read();
+        opts.set_iterate_upper_bound(to);
+        let it = self.db.iterator_in(cf, opts, IteratorMode::From(from, Direction::Forward));
+        let mut batch = WriteBatchWithTransaction::<true>::default();
+        let mut count = 0;
+        for item in it {
+            let (k, _) = item?;
+            match cf {
+                Some(cf) => batch.delete_cf(cf, &k),
+                None => batch.delete(&k),
+            }
+            count += 1;
+            if count >= FLUSH_BATCH {
+                self.db.write(std::mem::take(&mut batch))?;
+                count = 0;
+            }
+        }
+        if count > 0 {
+            self.db.write(batch)?;
+        }
+        Ok(())
+    }
+}
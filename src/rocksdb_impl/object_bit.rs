@@ -2,8 +2,12 @@ use std::{mem, ptr, slice};
 
 use crate::{
     read_int, read_int_ptr,
-    rocksdb_impl::shared::{make_field_key, make_head_key},
-    write_int_ptr, LenType, Object, RrError, WrapDb,
+    rocksdb_impl::{
+        key_space_impl::{any_field, check_type, ensure_type, remove_if_empty, remove_key_meta, set_type},
+        scan_impl::scan_prefix,
+        shared::{make_field_key, make_head_key},
+    },
+    write_int_ptr, FieldValue, KeyType, LenType, Object, RrError, ScanBatch, ScanCursor, ScanOptions, WrapDb,
 };
 
 /// 这个对应redis中的hash, 字段数据量建议在2048个以内，在遍历数据时，性能比[ObjectImp]好
@@ -17,55 +21,68 @@ pub struct BitObject {}
 
 impl<T: WrapDb> Object<T> for BitObject {
     fn del(&self, t: &T, key: &[u8], field: &[u8]) -> Result<(), RrError> {
-        let head_key = make_head_key(key);
+        let exists = check_type(t, key, KeyType::BitHash)?;
+        if exists && any_field(t, KeyType::BitHash, key, &[field])? {
+            set_type(t, key, KeyType::BitHash)?;
+        }
+        let head_key = make_head_key(KeyType::BitHash, key);
         if let Some(fv) = t.get(&head_key)? {
             let mut f = BitField::new(fv);
             f.del(field);
             t.put(&head_key, &f.data)?;
         }
-        let new_key = make_field_key(key, field);
+        let new_key = make_field_key(KeyType::BitHash, key, field);
         t.delete(&new_key)?;
-
+        if exists {
+            remove_if_empty(t, key, KeyType::BitHash)?;
+        }
         Ok(())
     }
 
     fn dels(&self, t: &T, key: &[u8], fields: &[&[u8]]) -> Result<LenType, RrError> {
+        if check_type(t, key, KeyType::BitHash)? && any_field(t, KeyType::BitHash, key, fields)? {
+            set_type(t, key, KeyType::BitHash)?;
+        }
         let mut count = 0;
         for f in fields {
-            let new_key = make_field_key(key, f);
+            let new_key = make_field_key(KeyType::BitHash, key, f);
             t.delete(&new_key)?;
             count += 1;
         }
-        let head_key = make_head_key(key);
+        let head_key = make_head_key(KeyType::BitHash, key);
         if let Some(fv) = t.get(&head_key)? {
             let mut f = BitField::new(fv);
             for field in fields {
                 f.del(field);
             }
             t.put(&head_key, &f.data)?;
+            remove_if_empty(t, key, KeyType::BitHash)?;
         }
         Ok(count)
     }
 
     fn exists(&self, t: &T, key: &[u8], field: &[u8]) -> Result<bool, RrError> {
-        let new_key = make_field_key(key, field);
+        check_type(t, key, KeyType::BitHash)?;
+        let new_key = make_field_key(KeyType::BitHash, key, field);
         let old = t.get(&new_key)?;
         Ok(old.is_some())
     }
 
     fn get(&self, t: &T, key: &[u8], field: &[u8]) -> Result<Option<Vec<u8>>, RrError> {
-        let new_key = make_field_key(key, field);
+        check_type(t, key, KeyType::BitHash)?;
+        let new_key = make_field_key(KeyType::BitHash, key, field);
         let v = t.get(&new_key)?;
         Ok(v)
     }
 
     fn get_all(&self, t: &T, key: &[u8]) -> Result<Option<Vec<(Vec<u8>, Vec<u8>)>>, RrError> {
-        let head_key = make_head_key(key);
+        check_type(t, key, KeyType::BitHash)?;
+        let head_key = make_head_key(KeyType::BitHash, key);
         if let Some(fv) = t.get(&head_key)? {
             let few_field = BitField::new(fv);
             let mut re = Vec::with_capacity(few_field.len());
             for field in few_field.new_field_it() {
-                let new_key = make_field_key(key, field.field);
+                let new_key = make_field_key(KeyType::BitHash, key, field.field);
                 let v = t.get(&new_key)?;
                 if let Some(v) = v {
                     re.push((field.field.to_vec(), v));
@@ -80,7 +97,8 @@ impl<T: WrapDb> Object<T> for BitObject {
     }
 
     fn keys(&self, t: &T, key: &[u8]) -> Result<Option<Vec<Vec<u8>>>, RrError> {
-        let head_key = make_head_key(key);
+        check_type(t, key, KeyType::BitHash)?;
+        let head_key = make_head_key(KeyType::BitHash, key);
         if let Some(fv) = t.get(&head_key)? {
             let few_field = BitField::new(fv);
             let mut re = Vec::with_capacity(few_field.len());
@@ -94,7 +112,8 @@ impl<T: WrapDb> Object<T> for BitObject {
     }
 
     fn len(&self, t: &T, key: &[u8]) -> Result<Option<LenType>, RrError> {
-        let head_key = make_head_key(key);
+        check_type(t, key, KeyType::BitHash)?;
+        let head_key = make_head_key(KeyType::BitHash, key);
         if let Some(fv) = t.get(&head_key)? {
             let few_field = BitField { data: fv };
             Ok(Some(few_field.len() as LenType))
@@ -104,9 +123,10 @@ impl<T: WrapDb> Object<T> for BitObject {
     }
 
     fn mget(&self, t: &T, key: &[u8], fields: &[&[u8]]) -> Result<Vec<Option<Vec<u8>>>, RrError> {
+        check_type(t, key, KeyType::BitHash)?;
         let mut values = Vec::with_capacity(fields.len());
         for f in fields {
-            let new_key = make_field_key(key, f);
+            let new_key = make_field_key(KeyType::BitHash, key, f);
             if let Some(v) = t.get(&new_key)? {
                 values.push(Some(v));
             } else {
@@ -117,7 +137,8 @@ impl<T: WrapDb> Object<T> for BitObject {
     }
 
     fn set(&self, t: &T, key: &[u8], field: &[u8], value: &[u8]) -> Result<(), RrError> {
-        let head_key = make_head_key(key);
+        ensure_type(t, key, KeyType::BitHash)?;
+        let head_key = make_head_key(KeyType::BitHash, key);
         if let Some(fv) = t.get(&head_key)? {
             let mut few_field = BitField::new(fv);
             few_field.set(field);
@@ -127,17 +148,20 @@ impl<T: WrapDb> Object<T> for BitObject {
             few_field.set(field);
             t.put(&head_key, &few_field.data)?;
         }
-        let new_key = make_field_key(key, field);
+        let new_key = make_field_key(KeyType::BitHash, key, field);
         t.put(&new_key, value)?;
         Ok(())
     }
 
     fn set_not_exist(&self, t: &T, key: &[u8], field: &[u8], value: &[u8]) -> Result<i32, RrError> {
-        let new_key = make_field_key(key, field);
+        //字段已存在时没有修改，不写入类型的版本，不然会使watch这个key的事务失败
+        check_type(t, key, KeyType::BitHash)?;
+        let new_key = make_field_key(KeyType::BitHash, key, field);
         if t.get(&new_key)?.is_none() {
+            set_type(t, key, KeyType::BitHash)?;
             t.put(&new_key, value)?;
 
-            let head_key = make_head_key(key);
+            let head_key = make_head_key(KeyType::BitHash, key);
             if let Some(fv) = t.get(&head_key)? {
                 let mut few_field = BitField::new(fv);
                 few_field.set(field);
@@ -155,8 +179,10 @@ impl<T: WrapDb> Object<T> for BitObject {
     }
 
     fn set_exist(&self, t: &T, key: &[u8], field: &[u8], value: &[u8]) -> Result<i32, RrError> {
-        let new_key = make_field_key(key, field);
+        check_type(t, key, KeyType::BitHash)?;
+        let new_key = make_field_key(KeyType::BitHash, key, field);
         if t.get(&new_key)?.is_some() {
+            set_type(t, key, KeyType::BitHash)?;
             t.put(&new_key, value)?;
             //由于key是存在的，所以这里不用再修 head key了
             Ok(1)
@@ -166,12 +192,13 @@ impl<T: WrapDb> Object<T> for BitObject {
     }
 
     fn vals(&self, t: &T, key: &[u8]) -> Result<Vec<Vec<u8>>, RrError> {
-        let head_key = make_head_key(key);
+        check_type(t, key, KeyType::BitHash)?;
+        let head_key = make_head_key(KeyType::BitHash, key);
         if let Some(fv) = t.get(&head_key)? {
             let few_field = BitField::new(fv);
             let mut re = Vec::with_capacity(few_field.len());
             for field in few_field.new_field_it() {
-                let new_key = make_field_key(key, field.field);
+                let new_key = make_field_key(KeyType::BitHash, key, field.field);
                 let v = t.get(&new_key)?;
                 if let Some(v) = v {
                     re.push(v);
@@ -185,12 +212,30 @@ impl<T: WrapDb> Object<T> for BitObject {
         }
     }
 
+    fn scan(&self, t: &T, key: &[u8], cursor: &ScanCursor, opts: &ScanOptions) -> Result<ScanBatch<FieldValue>, RrError> {
+        if !check_type(t, key, KeyType::BitHash)? {
+            return Ok(ScanBatch::default());
+        }
+        let mut items = Vec::new();
+        //不使用head中的字段数组，直接遍历字段的key，这样不用读取所有的字段。字段为空的是head key
+        let cursor = scan_prefix(t, &make_head_key(KeyType::BitHash, key), cursor, opts, |field, value| {
+            if field.is_empty() {
+                return Ok(false);
+            }
+            items.push((field.to_vec(), value.to_vec()));
+            Ok(true)
+        })?;
+        Ok(ScanBatch { cursor, items })
+    }
+
     fn del_key(&self, t: &T, key: &[u8]) -> Result<(), RrError> {
-        let head_key = make_head_key(key);
+        check_type(t, key, KeyType::BitHash)?;
+        remove_key_meta(t, key)?;
+        let head_key = make_head_key(KeyType::BitHash, key);
         if let Some(fv) = t.get(&head_key)? {
             let few_field = BitField::new(fv);
             for field in few_field.new_field_it() {
-                let new_key = make_field_key(key, field.field);
+                let new_key = make_field_key(KeyType::BitHash, key, field.field);
                 t.delete(&new_key)?;
             }
             t.delete(&head_key)?;
@@ -1,6 +1,10 @@
 use crate::{
-    rocksdb_impl::shared::{get_field_from_key, make_field_key},
-    LenType, Object, RrError, WrapDb,
+    rocksdb_impl::{
+        key_space_impl::{any_field, check_type, ensure_type, remove_if_empty, remove_key_meta, set_type},
+        scan_impl::scan_prefix,
+        shared::{get_field_from_key, make_field_key, make_head_key},
+    },
+    FieldValue, KeyType, LenType, Object, RrError, ScanBatch, ScanCursor, ScanOptions, WrapDb,
 };
 
 /// 直接使用key + field的方式，把value的值存入数据库中
@@ -9,39 +13,57 @@ pub struct ObjectImp {}
 
 impl<T: WrapDb> Object<T> for ObjectImp {
     fn del(&self, t: &T, key: &[u8], field: &[u8]) -> Result<(), RrError> {
-        let new_key = make_field_key(key, field);
-        t.delete(&new_key)?;
+        let new_key = make_field_key(KeyType::Hash, key, field);
+        if check_type(t, key, KeyType::Hash)? && t.get(&new_key)?.is_some() {
+            set_type(t, key, KeyType::Hash)?;
+            t.delete(&new_key)?;
+            remove_if_empty(t, key, KeyType::Hash)?;
+        }
         Ok(())
     }
 
     fn dels(&self, t: &T, key: &[u8], fields: &[&[u8]]) -> Result<LenType, RrError> {
+        let exists = check_type(t, key, KeyType::Hash)?;
+        if exists && any_field(t, KeyType::Hash, key, fields)? {
+            set_type(t, key, KeyType::Hash)?;
+        }
         let mut count = 0;
         for f in fields {
-            let new_key = make_field_key(key, f);
+            let new_key = make_field_key(KeyType::Hash, key, f);
             t.delete(&new_key)?;
             count += 1;
         }
+        if exists {
+            remove_if_empty(t, key, KeyType::Hash)?;
+        }
         Ok(count)
     }
 
     fn exists(&self, t: &T, key: &[u8], field: &[u8]) -> Result<bool, RrError> {
-        let new_key = make_field_key(key, field);
+        check_type(t, key, KeyType::Hash)?;
+        let new_key = make_field_key(KeyType::Hash, key, field);
         let old = t.get(&new_key)?;
         Ok(old.is_some())
     }
 
     fn get(&self, t: &T, key: &[u8], field: &[u8]) -> Result<Option<Vec<u8>>, RrError> {
-        let new_key = make_field_key(key, field);
+        check_type(t, key, KeyType::Hash)?;
+        let new_key = make_field_key(KeyType::Hash, key, field);
         let v = t.get(&new_key)?;
         Ok(v)
     }
 
     fn get_all(&self, t: &T, key: &[u8]) -> Result<Option<Vec<(Vec<u8>, Vec<u8>)>>, RrError> {
+        check_type(t, key, KeyType::Hash)?;
         let mut re = Vec::with_capacity(10);
-        let new_key = make_field_key(key, &[]);
+        let new_key = make_field_key(KeyType::Hash, key, &[]);
         let it = t.prefix_iterator(&new_key);
         for k in it {
             let kk = k?;
+            //prefix_iterator不会在prefix处停止
+            if !kk.0.starts_with(&new_key) {
+                break;
+            }
             let field_key = get_field_from_key(key, &kk.0);
             re.push((field_key.to_vec(), kk.1.to_vec()));
         }
@@ -53,11 +75,16 @@ impl<T: WrapDb> Object<T> for ObjectImp {
     }
 
     fn keys(&self, t: &T, key: &[u8]) -> Result<Option<Vec<Vec<u8>>>, RrError> {
+        check_type(t, key, KeyType::Hash)?;
         let mut re = Vec::with_capacity(10);
-        let new_key = make_field_key(key, &[]);
+        let new_key = make_field_key(KeyType::Hash, key, &[]);
         let it = t.prefix_iterator(&new_key);
         for k in it {
             let kk = k?;
+            //prefix_iterator不会在prefix处停止
+            if !kk.0.starts_with(&new_key) {
+                break;
+            }
             let field_key = get_field_from_key(key, &kk.0);
             re.push(field_key.to_vec());
         }
@@ -69,9 +96,16 @@ impl<T: WrapDb> Object<T> for ObjectImp {
     }
 
     fn len(&self, t: &T, key: &[u8]) -> Result<Option<LenType>, RrError> {
-        let new_key = make_field_key(key, &[]);
+        check_type(t, key, KeyType::Hash)?;
+        let new_key = make_field_key(KeyType::Hash, key, &[]);
         let it = t.prefix_iterator(&new_key);
-        let l = it.count();
+        let mut l = 0;
+        for k in it {
+            if !k?.0.starts_with(&new_key) {
+                break;
+            }
+            l += 1;
+        }
         if l == 0 {
             Ok(None)
         } else {
@@ -80,9 +114,10 @@ impl<T: WrapDb> Object<T> for ObjectImp {
     }
 
     fn mget(&self, t: &T, key: &[u8], fields: &[&[u8]]) -> Result<Vec<Option<Vec<u8>>>, RrError> {
+        check_type(t, key, KeyType::Hash)?;
         let mut values = Vec::with_capacity(fields.len());
         for f in fields {
-            let new_key = make_field_key(key, f);
+            let new_key = make_field_key(KeyType::Hash, key, f);
             if let Some(v) = t.get(&new_key)? {
                 values.push(Some(v));
             } else {
@@ -93,14 +128,18 @@ impl<T: WrapDb> Object<T> for ObjectImp {
     }
 
     fn set(&self, t: &T, key: &[u8], field: &[u8], value: &[u8]) -> Result<(), RrError> {
-        let new_key = make_field_key(key, field);
+        ensure_type(t, key, KeyType::Hash)?;
+        let new_key = make_field_key(KeyType::Hash, key, field);
         t.put(&new_key, value)?;
         Ok(())
     }
 
     fn set_not_exist(&self, t: &T, key: &[u8], field: &[u8], value: &[u8]) -> Result<i32, RrError> {
-        let new_key = make_field_key(key, field);
+        //字段已存在时没有修改，不写入类型的版本，不然会使watch这个key的事务失败
+        check_type(t, key, KeyType::Hash)?;
+        let new_key = make_field_key(KeyType::Hash, key, field);
         if t.get(&new_key)?.is_none() {
+            set_type(t, key, KeyType::Hash)?;
             t.put(&new_key, value)?;
             Ok(1)
         } else {
@@ -109,8 +148,10 @@ impl<T: WrapDb> Object<T> for ObjectImp {
     }
 
     fn set_exist(&self, t: &T, key: &[u8], field: &[u8], value: &[u8]) -> Result<i32, RrError> {
-        let new_key = make_field_key(key, field);
+        check_type(t, key, KeyType::Hash)?;
+        let new_key = make_field_key(KeyType::Hash, key, field);
         if t.get(&new_key)?.is_some() {
+            set_type(t, key, KeyType::Hash)?;
             t.put(&new_key, value)?;
             Ok(1)
         } else {
@@ -119,21 +160,48 @@ impl<T: WrapDb> Object<T> for ObjectImp {
     }
 
     fn vals(&self, t: &T, key: &[u8]) -> Result<Vec<Vec<u8>>, RrError> {
+        check_type(t, key, KeyType::Hash)?;
         let mut re = Vec::with_capacity(10);
-        let new_key = make_field_key(key, &[]);
+        let new_key = make_field_key(KeyType::Hash, key, &[]);
         let it = t.prefix_iterator(&new_key);
         for k in it {
             let kk = k?;
+            //prefix_iterator不会在prefix处停止
+            if !kk.0.starts_with(&new_key) {
+                break;
+            }
             re.push(kk.1.to_vec());
         }
         Ok(re)
     }
 
+    fn scan(&self, t: &T, key: &[u8], cursor: &ScanCursor, opts: &ScanOptions) -> Result<ScanBatch<FieldValue>, RrError> {
+        if !check_type(t, key, KeyType::Hash)? {
+            return Ok(ScanBatch::default());
+        }
+        let mut items = Vec::new();
+        //字段为空的是head key，不是字段
+        let cursor = scan_prefix(t, &make_head_key(KeyType::Hash, key), cursor, opts, |field, value| {
+            if field.is_empty() {
+                return Ok(false);
+            }
+            items.push((field.to_vec(), value.to_vec()));
+            Ok(true)
+        })?;
+        Ok(ScanBatch { cursor, items })
+    }
+
     fn del_key(&self, t: &T, key: &[u8]) -> Result<(), RrError> {
-        let new_key = make_field_key(key, &[]);
+        check_type(t, key, KeyType::Hash)?;
+        remove_key_meta(t, key)?;
+        let new_key = make_field_key(KeyType::Hash, key, &[]);
         let it = t.prefix_iterator(&new_key);
         for k in it {
             let kk = k?;
+            //prefix_iterator不会在prefix处停止
+            if !kk.0.starts_with(&new_key) {
+                break;
+            }
             t.delete(&kk.0)?;
         }
         Ok(())
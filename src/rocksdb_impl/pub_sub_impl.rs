@@ -0,0 +1,265 @@
+use std::{
+    cell::RefCell,
+    collections::{BTreeSet, HashMap},
+    sync::{
+        atomic::{AtomicU32, AtomicU64, Ordering},
+        mpsc::{self, Receiver, SyncSender, TrySendError},
+        Arc, Mutex, MutexGuard,
+    },
+    time::Duration,
+};
+
+use crate::{glob_match, rocksdb_impl::expire_impl::now_millis, Expiration, LenType, NotifyEvents, PubSubMessage, RedisRocksdb, TransactionalDb};
+
+/// 进程内的订阅关系，每一个[Subscriber]有自己的消息队列，publish时复制到每一个队列中
+#[derive(Default)]
+pub(crate) struct PubSub {
+    registry: Mutex<Registry>,
+    next_id: AtomicU64,
+    /// [NotifyEvents]的bits
+    notify_events: AtomicU32,
+}
+
+#[derive(Default)]
+struct Registry {
+    channels: HashMap<Vec<u8>, Vec<(u64, SyncSender<PubSubMessage>)>>,
+    patterns: Vec<(Vec<u8>, u64, SyncSender<PubSubMessage>)>,
+}
+
+/// 提交之后要发送的通知，参见[crate::RedisTransaction::notify]
+pub(crate) type Event = (NotifyEvents, &'static str, Vec<u8>);
+
+/// WrapDb惰性删除过期的key后怎样发送expired通知，参见[crate::WrapDb::expired]
+#[derive(Clone, Copy)]
+pub(crate) enum ExpiredNotify<'a> {
+    /// 不在事务中，删除马上生效，马上发送，u32为选择的数据库
+    Now(&'a PubSub, u32),
+    /// 在事务中，先记下来，提交之后发送
+    Later(&'a RefCell<Vec<Event>>),
+}
+
+impl ExpiredNotify<'_> {
+    pub(crate) fn send(&self, key: &[u8]) {
+        match self {
+            ExpiredNotify::Now(pub_sub, namespace) => pub_sub.notify(*namespace, NotifyEvents::EXPIRED, "expired", key),
+            ExpiredNotify::Later(events) => events.borrow_mut().push((NotifyEvents::EXPIRED, "expired", key.to_vec())),
+        }
+    }
+}
+
+impl PubSub {
+    fn registry(&self) -> MutexGuard<'_, Registry> {
+        self.registry.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// 返回收到消息的订阅者个数，同一个订阅者的channel与pattern都匹配时收到多次，与redis一样
+    fn publish(&self, channel: &[u8], payload: &[u8]) -> LenType {
+        let registry = self.registry();
+        let mut received = 0;
+        if let Some(subscribers) = registry.channels.get(channel) {
+            for (_, sender) in subscribers {
+                let message = PubSubMessage {
+                    pattern: None,
+                    channel: channel.to_vec(),
+                    payload: payload.to_vec(),
+                };
+                if try_send(sender, message) {
+                    received += 1;
+                }
+            }
+        }
+        for (pattern, _, sender) in &registry.patterns {
+            if !glob_match(pattern, channel) {
+                continue;
+            }
+            let message = PubSubMessage {
+                pattern: Some(pattern.clone()),
+                channel: channel.to_vec(),
+                payload: payload.to_vec(),
+            };
+            if try_send(sender, message) {
+                received += 1;
+            }
+        }
+        received
+    }
+
+    /// 发送keyspace/keyevent通知，namespace为选择的数据库，参见[RedisRocksdb::notify]
+    fn notify(&self, namespace: u32, class: NotifyEvents, event: &str, key: &[u8]) {
+        let events = NotifyEvents::from_bits(self.notify_events.load(Ordering::Relaxed));
+        if !events.is_enabled(class) {
+            return;
+        }
+        if events.contains(NotifyEvents::KEYSPACE) {
+            let channel = [format!("__keyspace@{}__:", namespace).as_bytes(), key].concat();
+            self.publish(&channel, event.as_bytes());
+        }
+        if events.contains(NotifyEvents::KEYEVENT) {
+            let channel = [format!("__keyevent@{}__:", namespace).as_bytes(), event.as_bytes()].concat();
+            self.publish(&channel, key);
+        }
+    }
+}
+
+/// 订阅者的队列满了时丢弃这条消息，不阻塞publish，也不算作收到
+fn try_send(sender: &SyncSender<PubSubMessage>, message: PubSubMessage) -> bool {
+    match sender.try_send(message) {
+        Ok(()) => true,
+        Err(TrySendError::Full(_)) => {
+            log::debug!("subscriber queue is full, message dropped");
+            false
+        }
+        Err(TrySendError::Disconnected(_)) => false,
+    }
+}
+
+/// 订阅者，使用[RedisRocksdb::subscriber]创建，drop时取消所有的订阅
+/// 不会阻塞publish，消息在队列中等待[Subscriber::recv_timeout]或[Subscriber::try_recv]
+/// 队列的长度有上限，满了之后新的消息被丢弃，所以要及时取出消息
+pub struct Subscriber {
+    id: u64,
+    pub_sub: Arc<PubSub>,
+    sender: SyncSender<PubSubMessage>,
+    receiver: Receiver<PubSubMessage>,
+    channels: BTreeSet<Vec<u8>>,
+    patterns: BTreeSet<Vec<u8>>,
+}
+
+impl Subscriber {
+    /// [RedisRocksdb::subscriber]的队列长度
+    pub const DEFAULT_CAPACITY: usize = 10_000;
+
+    /// 对应redis的subscribe，已订阅的channel不会重复订阅
+    pub fn subscribe(&mut self, channels: &[&[u8]]) {
+        let mut registry = self.pub_sub.registry();
+        for channel in channels {
+            if self.channels.insert(channel.to_vec()) {
+                registry.channels.entry(channel.to_vec()).or_default().push((self.id, self.sender.clone()));
+            }
+        }
+    }
+
+    /// 对应redis的psubscribe，pattern为glob，与[crate::ScanOptions]的pattern一样
+    pub fn psubscribe(&mut self, patterns: &[&[u8]]) {
+        let mut registry = self.pub_sub.registry();
+        for pattern in patterns {
+            if self.patterns.insert(pattern.to_vec()) {
+                registry.patterns.push((pattern.to_vec(), self.id, self.sender.clone()));
+            }
+        }
+    }
+
+    /// 对应redis的unsubscribe，channels为空时取消所有的channel，返回取消的channel
+    pub fn unsubscribe(&mut self, channels: &[&[u8]]) -> Vec<Vec<u8>> {
+        let removed: Vec<Vec<u8>> = if channels.is_empty() {
+            std::mem::take(&mut self.channels).into_iter().collect()
+        } else {
+            channels.iter().filter(|c| self.channels.remove(**c)).map(|c| c.to_vec()).collect()
+        };
+        let mut registry = self.pub_sub.registry();
+        for channel in &removed {
+            if let Some(subscribers) = registry.channels.get_mut(channel) {
+                subscribers.retain(|(id, _)| *id != self.id);
+                if subscribers.is_empty() {
+                    registry.channels.remove(channel);
+                }
+            }
+        }
+        removed
+    }
+
+    /// 对应redis的punsubscribe，patterns为空时取消所有的pattern，返回取消的pattern
+    pub fn punsubscribe(&mut self, patterns: &[&[u8]]) -> Vec<Vec<u8>> {
+        let removed: Vec<Vec<u8>> = if patterns.is_empty() {
+            std::mem::take(&mut self.patterns).into_iter().collect()
+        } else {
+            patterns.iter().filter(|p| self.patterns.remove(**p)).map(|p| p.to_vec()).collect()
+        };
+        let id = self.id;
+        self.pub_sub
+            .registry()
+            .patterns
+            .retain(|(pattern, sub, _)| *sub != id || !removed.contains(pattern));
+        removed
+    }
+
+    /// 订阅的channel与pattern的个数
+    pub fn count(&self) -> LenType {
+        (self.channels.len() + self.patterns.len()) as LenType
+    }
+
+    pub fn channels(&self) -> Vec<Vec<u8>> {
+        self.channels.iter().cloned().collect()
+    }
+
+    pub fn patterns(&self) -> Vec<Vec<u8>> {
+        self.patterns.iter().cloned().collect()
+    }
+
+    /// 等待下一条消息，超时返回None
+    pub fn recv_timeout(&self, timeout: Duration) -> Option<PubSubMessage> {
+        self.receiver.recv_timeout(timeout).ok()
+    }
+
+    /// 没有消息时马上返回None
+    pub fn try_recv(&self) -> Option<PubSubMessage> {
+        self.receiver.try_recv().ok()
+    }
+}
+
+impl Drop for Subscriber {
+    fn drop(&mut self) {
+        self.unsubscribe(&[]);
+        self.punsubscribe(&[]);
+    }
+}
+
+impl<D: TransactionalDb> RedisRocksdb<D> {
+    /// 对应redis的publish，返回收到消息的订阅者个数
+    pub fn publish(&self, channel: &[u8], message: &[u8]) -> LenType {
+        self.pub_sub.publish(channel, message)
+    }
+
+    /// 创建一个没有订阅任何channel的订阅者，它可以在其它线程中使用，队列长度为[Subscriber::DEFAULT_CAPACITY]
+    pub fn subscriber(&self) -> Subscriber {
+        self.subscriber_with_capacity(Subscriber::DEFAULT_CAPACITY)
+    }
+
+    /// 队列中最多有capacity条消息，满了之后publish丢弃新的消息，capacity至少为1
+    pub fn subscriber_with_capacity(&self, capacity: usize) -> Subscriber {
+        let (sender, receiver) = mpsc::sync_channel(capacity.max(1));
+        Subscriber {
+            id: self.pub_sub.next_id.fetch_add(1, Ordering::Relaxed),
+            pub_sub: self.pub_sub.clone(),
+            sender,
+            receiver,
+            channels: BTreeSet::new(),
+            patterns: BTreeSet::new(),
+        }
+    }
+
+    /// 对应redis的config set notify-keyspace-events，参见[NotifyEvents]
+    pub fn set_notify_events(&self, events: NotifyEvents) {
+        self.pub_sub.notify_events.store(events.bits(), Ordering::Relaxed);
+    }
+
+    pub fn notify_events(&self) -> NotifyEvents {
+        NotifyEvents::from_bits(self.pub_sub.notify_events.load(Ordering::Relaxed))
+    }
+
+    /// 发送keyspace/keyevent通知，class没有开启时什么也不做
+    /// 内置的方法在修改提交之后调用它，直接使用[crate::WrapDb]的方法（如[crate::Object]）时，由调用者在提交之后调用
+    pub fn notify(&self, class: NotifyEvents, event: &str, key: &[u8]) {
+        self.pub_sub.notify(self.namespace, class, event, key);
+    }
+}
+
+/// 设置过期时间后的事件：将来的时间为expire，过去的时间会删除key，为del
+pub(crate) fn expiration_event(expiration: Option<Expiration>) -> Option<&'static str> {
+    match expiration? {
+        Expiration::At(deadline) if deadline > now_millis() => Some("expire"),
+        Expiration::At(_) => Some("del"),
+        Expiration::Persist => Some("persist"),
+        Expiration::KeepTtl => None,
+    }
+}
@@ -4,12 +4,15 @@ use std::{
     hash::{Hash, Hasher},
 };
 
-use rocksdb::{Transaction, TransactionDB};
-
 use crate::{
     read_len_type,
-    rocksdb_impl::{quick_list_node::QuickListNode, zip_list::ZipList},
-    write_len_type, LenType, MetaKey, RrError, BYTES_LEN_TYPE,
+    rocksdb_impl::{
+        key_space_impl::remove_key_meta,
+        quick_list_node::QuickListNode,
+        shared::{make_head_key, TAG_LIST_NODE},
+        zip_list::ZipList,
+    },
+    write_len_type, KeyType, LenType, MetaKey, RrError, WrapDb, BYTES_LEN_TYPE,
 };
 
 struct _QuickList {
@@ -38,29 +41,23 @@ impl QuickList {
         QuickList([0; mem::size_of::<_QuickList>()])
     }
 
-    pub(crate) fn get(db: &TransactionDB, key: &[u8]) -> Result<Option<QuickList>, RrError> {
-        let v = db.get(key)?;
-        match v {
-            None => Ok(None),
-            Some(v) => {
-                if v.len() == mem::size_of::<QuickList>() {
-                    let t: [u8; mem::size_of::<QuickList>()] = v.to_vec().as_slice().try_into()?;
-                    Ok(Some(QuickList::from(t)))
-                } else {
-                    Err(RrError::message("can not convert vec to QuickList, the len is not eq".to_owned()))
-                }
-            }
-        }
+    /// quick list存放在list的head key中
+    pub(crate) fn get<T: WrapDb>(t: &T, key: &[u8]) -> Result<Option<QuickList>, RrError> {
+        QuickList::from_value(t.get(&make_head_key(KeyType::List, key))?)
     }
 
-    pub(crate) fn get_node(db: &TransactionDB, key: &[u8]) -> Result<Option<QuickListNode>, RrError> {
-        let v = db.get(key)?;
+    /// 在事务中读取并锁定quick list，防止并发修改时丢失更新
+    pub(crate) fn get_for_update<T: WrapDb>(t: &T, key: &[u8]) -> Result<Option<QuickList>, RrError> {
+        QuickList::from_value(t.get_for_update(&make_head_key(KeyType::List, key))?)
+    }
+
+    fn from_value(v: Option<Vec<u8>>) -> Result<Option<QuickList>, RrError> {
         match v {
             None => Ok(None),
             Some(v) => {
-                if v.len() == mem::size_of::<QuickListNode>() {
-                    let t: [u8; mem::size_of::<QuickListNode>()] = v.to_vec().as_slice().try_into()?;
-                    Ok(Some(QuickListNode::from(t)))
+                if v.len() == mem::size_of::<QuickList>() {
+                    let t: [u8; mem::size_of::<QuickList>()] = v.as_slice().try_into()?;
+                    Ok(Some(QuickList::from(t)))
                 } else {
                     Err(RrError::message("can not convert vec to QuickList, the len is not eq".to_owned()))
                 }
@@ -68,44 +65,44 @@ impl QuickList {
         }
     }
 
-    pub(crate) fn lpush(&mut self, tr: &Transaction<TransactionDB>, list_key: &[u8], value: &[u8]) -> Result<i32, RrError> {
+    pub(crate) fn lpush<T: WrapDb>(&mut self, t: &T, list_key: &[u8], value: &[u8]) -> Result<i32, RrError> {
         let quick = self;
         if quick.len_node() == 0 {
             //可能是第一次创建，也可能是删除后，没有数据了
-            let node_key = quick.next_meta_key().ok_or(RrError::none_error("next_meta_key"))?;
+            let node_key = quick.next_meta_key().ok_or_else(|| RrError::corruption(list_key, "next_meta_key"))?;
             let mut node = QuickListNode::new();
             {
-                let zip_key = quick.next_meta_key().ok_or(RrError::none_error("next_meta_key"))?;
+                let zip_key = quick.next_meta_key().ok_or_else(|| RrError::corruption(list_key, "next_meta_key"))?;
                 let mut zip = ZipList::new();
                 zip.push_left(value.as_ref());
-                tr.put(zip_key.as_ref(), zip.as_ref())?;
+                t.put(zip_key.as_ref(), zip.as_ref())?;
 
                 node.set_len_list(1);
                 node.set_len_bytes(zip.as_ref().len() as LenType);
                 node.set_values_key(&Some(&zip_key));
             }
-            tr.put(node_key.as_ref(), node.as_ref())?;
+            t.put(node_key.as_ref(), node.as_ref())?;
 
             quick.set_len_node(1);
             quick.set_len_list(node.len_list());
             quick.set_left(&Some(&node_key));
             quick.set_right(&Some(&node_key));
-            tr.put(list_key.as_ref(), quick.as_ref())?;
+            t.put(&make_head_key(KeyType::List, list_key), quick.as_ref())?;
         } else {
             let node_key = quick.left().ok_or(RrError::message("quick.left() return None".to_owned()))?.clone();
 
-            let mut node = QuickListNode::get(tr, node_key.as_ref())?.ok_or(RrError::message("quick.left() return None".to_owned()))?;
+            let mut node = QuickListNode::get(t, node_key.as_ref())?.ok_or(RrError::message("quick.left() return None".to_owned()))?;
 
             // zip中的元素过多，或内存过大，都会新增加node
             if node.len_list() > QuickListNode::MAX_LEN || node.len_bytes() > QuickListNode::MAX_BYTES {
                 //增加node
-                let new_node_key = quick.next_meta_key().ok_or(RrError::none_error("next_meta_key"))?;
+                let new_node_key = quick.next_meta_key().ok_or_else(|| RrError::corruption(list_key, "next_meta_key"))?;
                 let new_node = {
                     let mut new_node = QuickListNode::new();
                     let mut zip = ZipList::new();
                     zip.push_left(value.as_ref());
                     let zip_key = quick.next_meta_key().ok_or(RrError::message("next_meta_key return None".to_owned()))?;
-                    tr.put(zip_key.as_ref(), zip.as_ref())?;
+                    t.put(zip_key.as_ref(), zip.as_ref())?;
 
                     new_node.set_values_key(&Some(&zip_key));
                     new_node.set_len_list(zip.len());
@@ -116,68 +113,68 @@ impl QuickList {
 
                     new_node
                 };
-                tr.put(new_node_key.as_ref(), new_node.as_ref())?;
-                tr.put(node_key.as_ref(), node.as_ref())?;
+                t.put(new_node_key.as_ref(), new_node.as_ref())?;
+                t.put(node_key.as_ref(), node.as_ref())?;
 
                 quick.set_len_node(quick.len_node() + 1);
                 quick.set_len_list(quick.len_list() + 1);
                 quick.set_left(&Some(&new_node_key));
-                tr.put(list_key.as_ref(), quick.as_ref())?;
+                t.put(&make_head_key(KeyType::List, list_key), quick.as_ref())?;
             } else {
-                let zip_key = node.values_key().ok_or(RrError::none_error("values_key"))?.clone();
-                let mut zip = ZipList::get(tr, zip_key.as_ref())?.ok_or(RrError::none_error("ZipList::get"))?;
+                let zip_key = node.values_key().ok_or_else(|| RrError::corruption(list_key, "values_key"))?.clone();
+                let mut zip = ZipList::get(t, zip_key.as_ref())?.ok_or_else(|| RrError::corruption(zip_key.as_ref(), "ZipList::get"))?;
                 zip.push_left(value.as_ref());
 
                 node.set_len_list(zip.len());
                 node.set_len_bytes(zip.as_ref().len() as LenType);
-                tr.put(zip_key.as_ref(), zip.as_ref())?;
-                tr.put(node_key.as_ref(), node.as_ref())?;
+                t.put(zip_key.as_ref(), zip.as_ref())?;
+                t.put(node_key.as_ref(), node.as_ref())?;
 
                 quick.set_len_list(quick.len_list() + 1);
-                tr.put(list_key.as_ref(), quick.as_ref())?;
+                t.put(&make_head_key(KeyType::List, list_key), quick.as_ref())?;
             }
         }
         Ok(quick.len_list() as i32)
     }
 
-    pub(crate) fn rpush(&mut self, tr: &Transaction<TransactionDB>, list_key: &[u8], value: &[u8]) -> Result<i32, RrError> {
+    pub(crate) fn rpush<T: WrapDb>(&mut self, t: &T, list_key: &[u8], value: &[u8]) -> Result<i32, RrError> {
         let quick = self;
         if quick.len_node() == 0 {
             //可能是第一次创建，也可能是删除后，没有数据了
-            let node_key = quick.next_meta_key().ok_or(RrError::none_error("next_meta_key"))?;
+            let node_key = quick.next_meta_key().ok_or_else(|| RrError::corruption(list_key, "next_meta_key"))?;
             let mut node = QuickListNode::new();
             {
-                let zip_key = quick.next_meta_key().ok_or(RrError::none_error("next_meta_key"))?;
+                let zip_key = quick.next_meta_key().ok_or_else(|| RrError::corruption(list_key, "next_meta_key"))?;
                 let mut zip = ZipList::new();
                 zip.push_right(value.as_ref());
-                tr.put(zip_key.as_ref(), zip.as_ref())?;
+                t.put(zip_key.as_ref(), zip.as_ref())?;
 
                 node.set_len_list(1);
                 node.set_len_bytes(zip.as_ref().len() as LenType);
                 node.set_values_key(&Some(&zip_key));
             }
-            tr.put(node_key.as_ref(), node.as_ref())?;
+            t.put(node_key.as_ref(), node.as_ref())?;
 
             quick.set_len_node(1);
             quick.set_len_list(node.len_list());
             quick.set_left(&Some(&node_key));
             quick.set_right(&Some(&node_key));
-            tr.put(list_key.as_ref(), quick.as_ref())?;
+            t.put(&make_head_key(KeyType::List, list_key), quick.as_ref())?;
         } else {
             let node_key = quick.right().ok_or(RrError::message("quick.right() return None".to_owned()))?.clone();
 
-            let mut node = QuickListNode::get(tr, node_key.as_ref())?.ok_or(RrError::message("quick.right() return None".to_owned()))?;
+            let mut node = QuickListNode::get(t, node_key.as_ref())?.ok_or(RrError::message("quick.right() return None".to_owned()))?;
 
             // zip中的元素过多，或内存过大，都会新增加node
             if node.len_list() > QuickListNode::MAX_LEN || node.len_bytes() > QuickListNode::MAX_BYTES {
                 //增加node
-                let new_node_key = quick.next_meta_key().ok_or(RrError::none_error("next_meta_key"))?;
+                let new_node_key = quick.next_meta_key().ok_or_else(|| RrError::corruption(list_key, "next_meta_key"))?;
                 let new_node = {
                     let mut new_node = QuickListNode::new();
                     let mut zip = ZipList::new();
                     zip.push_right(value.as_ref());
                     let zip_key = quick.next_meta_key().ok_or(RrError::message("next_meta_key return None".to_owned()))?;
-                    tr.put(zip_key.as_ref(), zip.as_ref())?;
+                    t.put(zip_key.as_ref(), zip.as_ref())?;
 
                     new_node.set_values_key(&Some(&zip_key));
                     new_node.set_len_list(zip.len());
@@ -188,54 +185,149 @@ impl QuickList {
 
                     new_node
                 };
-                tr.put(new_node_key.as_ref(), new_node.as_ref())?;
-                tr.put(node_key.as_ref(), node.as_ref())?;
+                t.put(new_node_key.as_ref(), new_node.as_ref())?;
+                t.put(node_key.as_ref(), node.as_ref())?;
 
                 quick.set_len_node(quick.len_node() + 1);
                 quick.set_len_list(quick.len_list() + 1);
                 quick.set_right(&Some(&new_node_key));
-                tr.put(list_key.as_ref(), quick.as_ref())?;
+                t.put(&make_head_key(KeyType::List, list_key), quick.as_ref())?;
             } else {
-                let zip_key = node.values_key().ok_or(RrError::none_error("values_key"))?.clone();
-                let mut zip = ZipList::get(tr, zip_key.as_ref())?.ok_or(RrError::none_error("ZipList::get"))?;
+                let zip_key = node.values_key().ok_or_else(|| RrError::corruption(list_key, "values_key"))?.clone();
+                let mut zip = ZipList::get(t, zip_key.as_ref())?.ok_or_else(|| RrError::corruption(zip_key.as_ref(), "ZipList::get"))?;
                 zip.push_right(value.as_ref());
 
                 node.set_len_list(zip.len());
                 node.set_len_bytes(zip.as_ref().len() as LenType);
-                tr.put(zip_key.as_ref(), zip.as_ref())?;
-                tr.put(node_key.as_ref(), node.as_ref())?;
+                t.put(zip_key.as_ref(), zip.as_ref())?;
+                t.put(node_key.as_ref(), node.as_ref())?;
 
                 quick.set_len_list(quick.len_list() + 1);
-                tr.put(list_key.as_ref(), quick.as_ref())?;
+                t.put(&make_head_key(KeyType::List, list_key), quick.as_ref())?;
             }
         }
         Ok(quick.len_list() as i32)
     }
 
-    pub(crate) fn list_insert(
+    /// 保存修改后的head，list为空时与redis一样删除key（head、类型与过期时间）
+    pub(crate) fn save_head<T: WrapDb>(&self, t: &T, list_key: &[u8]) -> Result<(), RrError> {
+        if self.len_list() == 0 {
+            t.delete(&make_head_key(KeyType::List, list_key))?;
+            remove_key_meta(t, list_key)
+        } else {
+            t.put(&make_head_key(KeyType::List, list_key), self.as_ref())
+        }
+    }
+
+    /// 删除并返回第一个元素，如果list为空返回None
+    pub(crate) fn lpop<T: WrapDb>(&mut self, t: &T, list_key: &[u8]) -> Result<Option<Vec<u8>>, RrError> {
+        let quick = self;
+        if quick.len_list() < 1 {
+            return Ok(None);
+        }
+        let node_key = quick.left().ok_or_else(|| RrError::corruption(list_key, "left key"))?.clone();
+        let mut node = QuickListNode::get(t, node_key.as_ref())?.ok_or_else(|| RrError::corruption(node_key.as_ref(), "left node"))?;
+        let zip_key = node.values_key().ok_or_else(|| RrError::corruption(list_key, "zip key"))?.clone();
+        let mut zip = ZipList::get(t, zip_key.as_ref())?.ok_or_else(|| RrError::corruption(zip_key.as_ref(), "zip list"))?;
+        let value = zip.pop_left();
+
+        if zip.len() == 0 {
+            //没有数据，删除quick list node
+            t.delete(zip_key.as_ref())?;
+            t.delete(node_key.as_ref())?;
+            match node.right() {
+                None => {
+                    quick.set_left(&None);
+                    quick.set_right(&None);
+                    quick.set_len_node(0);
+                    quick.set_len_list(0);
+                }
+                Some(right_key) => {
+                    let mut right_node = QuickListNode::get(t, right_key.as_ref())?.ok_or_else(|| RrError::corruption(right_key.as_ref(), "right node"))?;
+                    right_node.set_left(&None);
+                    t.put(right_key.as_ref(), right_node.as_ref())?;
+                    quick.set_left(&Some(right_key));
+                    quick.set_len_node(quick.len_node() - 1);
+                    quick.set_len_list(quick.len_list() - 1);
+                }
+            }
+        } else {
+            node.set_len_list(zip.len());
+            node.set_len_bytes(zip.as_ref().len() as LenType);
+            quick.set_len_list(quick.len_list() - 1);
+            t.put(zip_key.as_ref(), zip.as_ref())?;
+            t.put(node_key.as_ref(), node.as_ref())?;
+        }
+        quick.save_head(t, list_key)?;
+        Ok(value)
+    }
+
+    /// 删除并返回最后一个元素，如果list为空返回None
+    pub(crate) fn rpop<T: WrapDb>(&mut self, t: &T, list_key: &[u8]) -> Result<Option<Vec<u8>>, RrError> {
+        let quick = self;
+        if quick.len_list() < 1 {
+            return Ok(None);
+        }
+        let node_key = quick.right().ok_or_else(|| RrError::corruption(list_key, "right key"))?.clone();
+        let mut node = QuickListNode::get(t, node_key.as_ref())?.ok_or_else(|| RrError::corruption(node_key.as_ref(), "right node"))?;
+        let zip_key = node.values_key().ok_or_else(|| RrError::corruption(list_key, "zip key"))?.clone();
+        let mut zip = ZipList::get(t, zip_key.as_ref())?.ok_or_else(|| RrError::corruption(zip_key.as_ref(), "zip list"))?;
+        let value = zip.pop_right();
+
+        if zip.len() == 0 {
+            //没有数据，删除quick list node
+            t.delete(zip_key.as_ref())?;
+            t.delete(node_key.as_ref())?;
+            match node.left() {
+                None => {
+                    quick.set_left(&None);
+                    quick.set_right(&None);
+                    quick.set_len_node(0);
+                    quick.set_len_list(0);
+                }
+                Some(left_key) => {
+                    let mut left_node = QuickListNode::get(t, left_key.as_ref())?.ok_or_else(|| RrError::corruption(left_key.as_ref(), "left node"))?;
+                    left_node.set_right(&None);
+                    t.put(left_key.as_ref(), left_node.as_ref())?;
+                    quick.set_right(&Some(left_key));
+                    quick.set_len_node(quick.len_node() - 1);
+                    quick.set_len_list(quick.len_list() - 1);
+                }
+            }
+        } else {
+            node.set_len_list(zip.len());
+            node.set_len_bytes(zip.as_ref().len() as LenType);
+            quick.set_len_list(quick.len_list() - 1);
+            t.put(zip_key.as_ref(), zip.as_ref())?;
+            t.put(node_key.as_ref(), node.as_ref())?;
+        }
+        quick.save_head(t, list_key)?;
+        Ok(value)
+    }
+
+    pub(crate) fn list_insert<T: WrapDb>(
         &mut self,
-        tr: &Transaction<TransactionDB>,
+        t: &T,
         list_key: &[u8],
         pivot: &[u8],
         value: &[u8],
         f: fn(&mut ZipList, &[u8], &[u8]) -> Option<i32>,
     ) -> Result<i32, RrError> {
         let quick = self;
-        let mut node_key = quick.left().ok_or(RrError::none_error("left key"))?.clone();
-        let mut node = QuickListNode::get(tr, node_key.as_ref())?.ok_or(RrError::none_error("left node"))?;
+        let mut node_key = quick.left().ok_or_else(|| RrError::corruption(list_key, "left key"))?.clone();
+        let mut node = QuickListNode::get(t, node_key.as_ref())?.ok_or_else(|| RrError::corruption(node_key.as_ref(), "left node"))?;
 
         let (zip, zip_key) = loop {
-            let zip_key = node.values_key().ok_or(RrError::none_error("zip key"))?;
-            let mut zip = ZipList::get(tr, zip_key.as_ref())?.ok_or(RrError::none_error("zip list"))?;
-            let t = f(&mut zip, pivot, value);
-            if t.is_some() {
+            let zip_key = node.values_key().ok_or_else(|| RrError::corruption(list_key, "zip key"))?;
+            let mut zip = ZipList::get(t, zip_key.as_ref())?.ok_or_else(|| RrError::corruption(zip_key.as_ref(), "zip list"))?;
+            if f(&mut zip, pivot, value).is_some() {
                 break (Some(zip), zip_key.clone());
             }
             match node.right() {
                 None => break (None, zip_key.clone()), //双向链表完成
-                Some(t) => {
-                    node_key = t.clone();
-                    node = QuickListNode::get(tr, node_key.as_ref())?.ok_or(RrError::none_error("right node"))?;
+                Some(right_key) => {
+                    node_key = right_key.clone();
+                    node = QuickListNode::get(t, node_key.as_ref())?.ok_or_else(|| RrError::corruption(node_key.as_ref(), "right node"))?;
                 }
             }
         };
@@ -246,14 +338,14 @@ impl QuickList {
             quick.set_len_list(quick.len_list() + 1);
             result = quick.len_list() as i32;
 
-            tr.put(zip_key, zip.as_ref())?;
-            tr.put(node_key.as_ref(), node.as_ref())?;
-            tr.put(list_key.as_ref(), quick.as_ref())?;
+            t.put(zip_key.as_ref(), zip.as_ref())?;
+            t.put(node_key.as_ref(), node.as_ref())?;
+            t.put(&make_head_key(KeyType::List, list_key), quick.as_ref())?;
         }
         Ok(result)
     }
 
-    pub(crate) fn clear(&mut self, tr: &Transaction<TransactionDB>, list_key: &[u8]) -> Result<i32, RrError> {
+    pub(crate) fn clear<T: WrapDb>(&mut self, t: &T, list_key: &[u8]) -> Result<i32, RrError> {
         let l = self.len_node();
         let quick = self;
 
@@ -263,17 +355,68 @@ impl QuickList {
         let mut node;
         while let Some(key) = node_key {
             let key = key.clone();
-            node = QuickListNode::get(tr, key.as_ref())?.ok_or(RrError::none_error("node"))?;
-            let value_key = node.values_key().ok_or(RrError::none_error("value key"))?;
-            tr.delete(value_key)?;
-            tr.delete(key)?;
+            node = QuickListNode::get(t, key.as_ref())?.ok_or_else(|| RrError::corruption(key.as_ref(), "node"))?;
+            let value_key = node.values_key().ok_or_else(|| RrError::corruption(list_key, "value key"))?;
+            t.delete(value_key.as_ref())?;
+            t.delete(key.as_ref())?;
             node_key = node.left();
         }
-        tr.delete(list_key)?;
-        // tr.commit()?;
+        t.delete(&make_head_key(KeyType::List, list_key))?;
         Ok(l as i32)
     }
 
+    /// 只保留下标在[start, stop]中的元素，start与stop已经是有效的下标，且start <= stop
+    /// 整个在区间之外的node直接删除，跨区间的node只保留区间内的元素，返回删除的元素数量
+    pub(crate) fn trim<T: WrapDb>(&mut self, t: &T, list_key: &[u8], start: usize, stop: usize) -> Result<LenType, RrError> {
+        let quick = self;
+        let old_len = quick.len_list();
+        let mut kept: Vec<(MetaKey, QuickListNode)> = Vec::new();
+        let mut offset = 0usize;
+        let mut node_key = quick.left().cloned();
+        while let Some(key) = node_key {
+            let mut node = QuickListNode::get(t, key.as_ref())?.ok_or_else(|| RrError::corruption(key.as_ref(), "node"))?;
+            let zip_key = node.values_key().ok_or_else(|| RrError::corruption(list_key, "zip key"))?.clone();
+            let len_zip = node.len_list() as usize;
+            node_key = node.right().cloned();
+            if offset + len_zip <= start || offset > stop {
+                t.delete(zip_key.as_ref())?;
+                t.delete(key.as_ref())?;
+            } else {
+                if offset < start || offset + len_zip > stop + 1 {
+                    let zip = ZipList::get(t, zip_key.as_ref())?.ok_or_else(|| RrError::corruption(zip_key.as_ref(), "zip list"))?;
+                    let from = start.saturating_sub(offset);
+                    let to = stop.min(offset + len_zip - 1) - offset;
+                    let mut new_zip = ZipList::new();
+                    for v in zip.range(from as i32, to as i32) {
+                        new_zip.push_right(&v);
+                    }
+                    node.set_len_list(new_zip.len());
+                    node.set_len_bytes(new_zip.as_ref().len() as LenType);
+                    t.put(zip_key.as_ref(), new_zip.as_ref())?;
+                }
+                kept.push((key, node));
+            }
+            offset += len_zip;
+        }
+
+        //保留的node是连续的，只需要修改两端的链接
+        if let Some((_, first)) = kept.first_mut() {
+            first.set_left(&None);
+        }
+        if let Some((_, last)) = kept.last_mut() {
+            last.set_right(&None);
+        }
+        for (key, node) in &kept {
+            t.put(key.as_ref(), node.as_ref())?;
+        }
+        quick.set_left(&kept.first().map(|(k, _)| k));
+        quick.set_right(&kept.last().map(|(k, _)| k));
+        quick.set_len_node(kept.len() as LenType);
+        quick.set_len_list(kept.iter().map(|(_, n)| n.len_list()).sum());
+        t.put(&make_head_key(KeyType::List, list_key), quick.as_ref())?;
+        Ok(old_len - quick.len_list())
+    }
+
     pub(crate) fn next_meta_key(&mut self) -> Option<MetaKey> {
         match MetaKey::read_mut(&self.0[QuickList::OFFSET_META_KEY..]) {
             None => None,
@@ -317,9 +460,12 @@ impl QuickList {
         // todo 不能初始化两次
         let meta_key = {
             let mut meta_key = MetaKey::new();
+            //与旧格式的meta key使用不一样的hash，转换时不会覆盖旧的node
             let mut hasher = DefaultHasher::new();
+            TAG_LIST_NODE.hash(&mut hasher);
             list_key.hash(&mut hasher);
-            meta_key.set_key(hasher.finish());
+            //little endian，第一个byte为数据库中的tag，见[crate::rocksdb_impl::shared]
+            meta_key.set_key((hasher.finish() & !0xff) | TAG_LIST_NODE as u64);
             meta_key
         };
 
@@ -343,11 +489,11 @@ impl QuickList {
         MetaKey::write(&mut self.0[QuickList::OFFSET_RIGHT..], meta_key)
     }
 
-    //////
-    pub(crate) fn modify_node(
+    /// zip修改后保存node，zip为空时删除node并修改相邻node的链接
+    /// 只修改quick的node链接，len_list与quick由调用者保存
+    pub(crate) fn modify_node<T: WrapDb>(
         &mut self,
-        tr: &Transaction<TransactionDB>,
-        list_key: &[u8],
+        t: &T,
         zip_key: &[u8],
         zip: &mut ZipList,
         node_key: &[u8],
@@ -356,7 +502,8 @@ impl QuickList {
         let quick = self;
         if zip.len() == 0 {
             //删除当前node
-            tr.delete(zip_key)?;
+            t.delete(zip_key)?;
+            t.delete(node_key)?;
 
             let left = node.left();
             let right = node.right();
@@ -364,40 +511,36 @@ impl QuickList {
             match (left, right) {
                 (None, None) => {
                     //都没有数据，清空list
-                    tr.delete(node_key)?;
                     quick.set_left(&None);
                     quick.set_right(&None);
-                    quick.set_len_node(0);
-                    quick.set_len_list(0);
-                    tr.put(list_key, quick.as_ref())?;
                 }
                 (Some(left_key), None) => {
-                    let mut left_node = QuickListNode::get(tr, left_key.as_ref())?.ok_or(RrError::none_error("left node"))?;
+                    let mut left_node = QuickListNode::get(t, left_key.as_ref())?.ok_or_else(|| RrError::corruption(left_key.as_ref(), "left node"))?;
                     left_node.set_right(&None);
-                    tr.delete(node_key)?;
-                    tr.put(list_key, &left_node)?;
+                    t.put(left_key.as_ref(), left_node.as_ref())?;
+                    quick.set_right(&Some(left_key));
                 }
                 (Some(left_key), Some(right_key)) => {
-                    let mut left_node = QuickListNode::get(tr, left_key.as_ref())?.ok_or(RrError::none_error("left node"))?;
-                    let mut right_node = QuickListNode::get(tr, right_key.as_ref())?.ok_or(RrError::none_error("right node"))?;
+                    let mut left_node = QuickListNode::get(t, left_key.as_ref())?.ok_or_else(|| RrError::corruption(left_key.as_ref(), "left node"))?;
+                    let mut right_node = QuickListNode::get(t, right_key.as_ref())?.ok_or_else(|| RrError::corruption(right_key.as_ref(), "right node"))?;
                     left_node.set_right(&Some(right_key));
-                    right_node.set_right(&Some(left_key));
-                    tr.delete(node_key)?;
-                    tr.put(left_key, &left_node)?;
-                    tr.put(right_key, &right_node)?;
+                    right_node.set_left(&Some(left_key));
+                    t.put(left_key.as_ref(), left_node.as_ref())?;
+                    t.put(right_key.as_ref(), right_node.as_ref())?;
                 }
                 (None, Some(right_key)) => {
-                    let mut right_node = QuickListNode::get(tr, right_key.as_ref())?.ok_or(RrError::none_error("right node"))?;
+                    let mut right_node = QuickListNode::get(t, right_key.as_ref())?.ok_or_else(|| RrError::corruption(right_key.as_ref(), "right node"))?;
                     right_node.set_left(&None);
-                    //todo quick 的right是否要处理
+                    t.put(right_key.as_ref(), right_node.as_ref())?;
                     quick.set_left(&Some(right_key));
                 }
             }
+            quick.set_len_node(quick.len_node() - 1);
         } else {
             node.set_len_list(zip.len());
             node.set_len_bytes(zip.as_ref().len() as LenType);
-            tr.put(zip_key, &zip)?;
-            tr.put(node_key, &node)?;
+            t.put(zip_key, zip.as_ref())?;
+            t.put(node_key, node.as_ref())?;
         }
 
         Ok(())
@@ -1,8 +1,6 @@
 use core::mem;
 
-use rocksdb::{Transaction, TransactionDB};
-
-use crate::{read_len_type, write_len_type, LenType, MetaKey, RrError, BYTES_LEN_TYPE};
+use crate::{read_len_type, write_len_type, LenType, MetaKey, RrError, WrapDb, BYTES_LEN_TYPE};
 
 /// Sample
 /// ```rust
@@ -37,8 +35,8 @@ impl QuickListNode {
         QuickListNode([0; mem::size_of::<_QuickListNode>()])
     }
 
-    pub(crate) fn get(tr: &Transaction<TransactionDB>, key: &[u8]) -> Result<Option<QuickListNode>, RrError> {
-        let v = tr.get(key)?;
+    pub(crate) fn get<T: WrapDb>(t: &T, key: &[u8]) -> Result<Option<QuickListNode>, RrError> {
+        let v = t.get(key)?;
         match v {
             None => Ok(None),
             Some(v) => {
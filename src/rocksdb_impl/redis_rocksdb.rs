@@ -1,14 +1,216 @@
-use crate::{BitObject, MaxHeap, MinHeap, ObjectImp};
+use std::{cell::RefCell, sync::Arc};
 
-pub struct RedisRocksdb {
-    pub(crate) db: rocksdb::TransactionDB,
+use rocksdb::{Transaction, TransactionDB};
+
+use crate::{
+    rocksdb_impl::{
+        list_waiters::ListWaiters,
+        namespace_impl::Namespaces,
+        pub_sub_impl::{Event, ExpiredNotify, PubSub},
+    },
+    BPTreeImpl, BitObject, BitmapImpl, ColumnFamilies, ExpireIndex, FieldValue, GeoImpl, Heap, HyperLogLogImpl, KeySpaceImpl, LenType, ListImpl, MaxHeap,
+    MinHeap, NotifyEvents, Object, ObjectImp, RedisRocksdbBuilder, RedisTransaction, RrError, SetImpl, SortedSetImp, StreamImpl, TransactionalDb,
+    WrapTransaction, WrapTransactionDB,
+};
+
+/// 除了namespace，其它的字段在[RedisRocksdb::select]返回的对象之间共享
+/// 默认使用悲观事务的[TransactionDB]，读多写少、冲突少时可以使用[rocksdb::OptimisticTransactionDB]，参见[TransactionalDb]
+pub struct RedisRocksdb<D = TransactionDB> {
+    pub(crate) db: Arc<D>,
+    /// 选择的数据库的编号，对应redis的SELECT，实际存放的namespace见[Namespaces]
+    pub(crate) namespace: u32,
+    pub(crate) namespaces: Arc<Namespaces>,
+    /// 阻塞在list上的等待者(blpop, brpop, brpoplpush)
+    pub(crate) list_waiters: ListWaiters,
+    /// 阻塞在stream上的等待者(xread, xreadgroup)
+    pub(crate) stream_waiters: ListWaiters,
+    /// key的过期时间，供compaction filter使用
+    pub(crate) expire_index: ExpireIndex,
+    /// publish/subscribe与keyspace通知
+    pub(crate) pub_sub: Arc<PubSub>,
+    /// 事务冲突时最多重试的次数，参见[RedisRocksdb::in_transaction]
+    pub(crate) max_retries: u32,
 }
 
-impl RedisRocksdb {
-    pub fn new(db: rocksdb::TransactionDB) -> Self {
-        RedisRocksdb { db }
+impl<D: TransactionalDb> RedisRocksdb<D> {
+    /// 与[RedisRocksdb::try_new]一样，出错时panic
+    pub fn new(db: D) -> Self {
+        RedisRocksdb::try_new(db).unwrap_or_else(|e| panic!("open RedisRocksdb: {}", e))
+    }
+
+    /// 与[RedisRocksdb::try_with_expire_index]一样，出错时panic
+    pub fn with_expire_index(db: D, expire_index: ExpireIndex) -> Self {
+        RedisRocksdb::try_with_expire_index(db, expire_index).unwrap_or_else(|e| panic!("open RedisRocksdb: {}", e))
+    }
+
+    pub fn try_new(db: D) -> Result<Self, RrError> {
+        RedisRocksdb::try_with_expire_index(db, ExpireIndex::new())
+    }
+
+    /// 打开db时使用了[ExpireIndex::set_compaction_filter]，要把同一个expire_index传入
+    /// 读取数据库的映射（SWAPDB）出错时返回错误，不然选择的数据库可能读写其它数据库的数据
+    /// key的格式不是当前的版本时返回错误，先使用[RedisRocksdb::migrate_key_layout]转换
+    pub fn try_with_expire_index(db: D, expire_index: ExpireIndex) -> Result<Self, RrError> {
+        let namespaces = Namespaces::load(&db)?;
+        RedisRocksdb::check_key_layout(&db)?;
+        Ok(RedisRocksdb {
+            db: Arc::new(db),
+            namespace: 0,
+            namespaces: Arc::new(namespaces),
+            list_waiters: ListWaiters::default(),
+            stream_waiters: ListWaiters::default(),
+            expire_index,
+            pub_sub: Arc::new(PubSub::default()),
+            max_retries: RedisRocksdbBuilder::DEFAULT_MAX_RETRIES,
+        })
+    }
+
+    /// 事务冲突时最多重试的次数，0为不重试，在[RedisRocksdb::select]之前设置
+    pub fn set_max_retries(&mut self, max_retries: u32) {
+        self.max_retries = max_retries;
+    }
+
+    pub fn max_retries(&self) -> u32 {
+        self.max_retries
+    }
+
+    /// 开始一个事务，对应redis的MULTI，参见[RedisTransaction]
+    pub fn transaction(&self) -> RedisTransaction<'_, D> {
+        RedisTransaction::new(self)
+    }
+
+    /// 使用column family时，直接读写db要自己选择column family，参见[ColumnFamilies]
+    pub fn get_db(&self) -> &D {
+        &self.db
+    }
+
+    /// 数据库中的column family，没有时都为None，数据存放在default中
+    pub fn column_families(&self) -> ColumnFamilies<'_> {
+        ColumnFamilies::new(&*self.db)
+    }
+
+    /// 用于WrapDb的方法，按key选择column family，读写选择的数据库中的key，删除已过期的key时发送expired通知
+    pub fn wrap_db(&self) -> WrapTransactionDB<'_, D> {
+        WrapTransactionDB {
+            db: &self.db,
+            cfs: self.column_families(),
+            namespace: self.namespaces.physical(self.namespace),
+            expired: Some(ExpiredNotify::Now(&self.pub_sub, self.namespace)),
+        }
+    }
+
+    /// tr要是[RedisRocksdb::get_db]的事务，用于WrapDb的方法，按key选择column family，读写选择的数据库中的key
+    /// 这里不知道事务什么时候提交，所以删除已过期的key时不发送expired通知，需要通知时使用[RedisRocksdb::in_transaction]
+    pub fn wrap_transaction<'a>(&'a self, tr: &'a Transaction<'a, D>) -> WrapTransaction<'a, D> {
+        WrapTransaction {
+            db: tr,
+            cfs: self.column_families(),
+            namespace: self.namespaces.physical(self.namespace),
+            expired: None,
+        }
+    }
+
+    /// 与[RedisRocksdb::wrap_transaction]一样，删除已过期的key时把expired通知记到events中，由调用者在提交之后发送
+    pub(crate) fn wrap_transaction_notify<'a>(&'a self, tr: &'a Transaction<'a, D>, events: &'a RefCell<Vec<Event>>) -> WrapTransaction<'a, D> {
+        WrapTransaction {
+            expired: Some(ExpiredNotify::Later(events)),
+            ..self.wrap_transaction(tr)
+        }
+    }
+
+    /// 在一个新的事务中执行f，成功后提交，提交时冲突（乐观事务）或加锁冲突时重新执行f，最多重试[RedisRocksdb::max_retries]次
+    /// 所以f可能执行多次，不要在f中产生事务之外的影响；list与string的方法都使用它，hash等在调用者事务中的类型可以这样使用：
+    /// `redis_db.in_transaction(|t| RedisRocksdb::object().set(t, key, field, value))`
+    pub fn in_transaction<R, F>(&self, f: F) -> Result<R, RrError>
+    where
+        F: Fn(&WrapTransaction<'_, D>) -> Result<R, RrError>,
+    {
+        let mut retries = 0;
+        loop {
+            match self.commit_once(&f) {
+                Err(RrError::TransactionConflict) if retries < self.max_retries => {
+                    retries += 1;
+                    log::debug!("transaction conflict, retry {}", retries);
+                }
+                re => return re,
+            }
+        }
+    }
+
+    /// 在一个新的事务中执行f，成功后提交，不重试，提交之后发送f删除已过期的key的expired通知
+    pub(crate) fn commit_once<R, F>(&self, f: F) -> Result<R, RrError>
+    where
+        F: FnOnce(&WrapTransaction<'_, D>) -> Result<R, RrError>,
+    {
+        let events = RefCell::new(Vec::new());
+        let tr = self.db.transaction();
+        let re = f(&self.wrap_transaction_notify(&tr, &events))?;
+        tr.commit()?;
+        for (class, event, key) in events.into_inner() {
+            self.notify(class, event, &key);
+        }
+        Ok(re)
     }
 
+    /// 对应[Heap::push]，在[RedisRocksdb::in_transaction]中执行，提交后发送heappush通知
+    pub fn heap_push<H>(&self, heap: &H, key: &[u8], field: &[u8], value: &[u8]) -> Result<(), RrError>
+    where
+        H: for<'a> Heap<WrapTransaction<'a, D>>,
+    {
+        self.in_transaction(|t| heap.push(t, key, field, value))?;
+        self.notify(NotifyEvents::HEAP, "heappush", key);
+        Ok(())
+    }
+
+    /// 对应[Heap::pop]，取出字段时提交后发送heappop通知
+    pub fn heap_pop<H>(&self, heap: &H, key: &[u8]) -> Result<Option<FieldValue>, RrError>
+    where
+        H: for<'a> Heap<WrapTransaction<'a, D>>,
+    {
+        let re = self.in_transaction(|t| heap.pop(t, key))?;
+        if re.is_some() {
+            self.notify(NotifyEvents::HEAP, "heappop", key);
+        }
+        Ok(re)
+    }
+
+    /// 对应redis的hset，参见[Object::set]，提交后发送hset通知
+    pub fn hash_set<O>(&self, object: &O, key: &[u8], field: &[u8], value: &[u8]) -> Result<(), RrError>
+    where
+        O: for<'a> Object<WrapTransaction<'a, D>>,
+    {
+        self.in_transaction(|t| object.set(t, key, field, value))?;
+        self.notify(NotifyEvents::HASH, "hset", key);
+        Ok(())
+    }
+
+    /// 对应redis的hsetnx，参见[Object::set_not_exist]，设置了字段时提交后发送hset通知
+    pub fn hash_set_not_exist<O>(&self, object: &O, key: &[u8], field: &[u8], value: &[u8]) -> Result<i32, RrError>
+    where
+        O: for<'a> Object<WrapTransaction<'a, D>>,
+    {
+        let re = self.in_transaction(|t| object.set_not_exist(t, key, field, value))?;
+        if re > 0 {
+            self.notify(NotifyEvents::HASH, "hset", key);
+        }
+        Ok(re)
+    }
+
+    /// 对应redis的hdel，参见[Object::dels]，删除了字段时提交后发送hdel通知
+    pub fn hash_del<O>(&self, object: &O, key: &[u8], fields: &[&[u8]]) -> Result<LenType, RrError>
+    where
+        O: for<'a> Object<WrapTransaction<'a, D>>,
+    {
+        let re = self.in_transaction(|t| object.dels(t, key, fields))?;
+        if re > 0 {
+            self.notify(NotifyEvents::HASH, "hdel", key);
+        }
+        Ok(re)
+    }
+}
+
+/// 这些方法与数据库无关，放在默认的[RedisRocksdb]上，这样调用时不用指定类型参数
+impl RedisRocksdb {
     pub fn object() -> ObjectImp {
         ObjectImp {}
     }
@@ -25,7 +227,44 @@ impl RedisRocksdb {
         MinHeap {}
     }
 
-    pub fn get_db(&self) -> &rocksdb::TransactionDB {
-        &self.db
+    pub fn bp_tree() -> BPTreeImpl {
+        BPTreeImpl::default()
+    }
+
+    pub fn sorted_set() -> SortedSetImp {
+        SortedSetImp {}
+    }
+
+    pub fn set() -> SetImpl {
+        SetImpl {}
+    }
+
+    /// 位图就是字符串，参见[crate::Bitmap]
+    pub fn bitmap() -> BitmapImpl {
+        BitmapImpl {}
+    }
+
+    /// HyperLogLog也是字符串，参见[crate::HyperLogLog]
+    pub fn hyper_log_log() -> HyperLogLogImpl {
+        HyperLogLogImpl {}
+    }
+
+    /// geo也是sorted set，参见[crate::Geo]
+    pub fn geo() -> GeoImpl {
+        GeoImpl {}
+    }
+
+    /// 阻塞的读取见[RedisRocksdb::stream_block]，参见[crate::Stream]
+    pub fn stream() -> StreamImpl {
+        StreamImpl {}
+    }
+
+    pub fn key_space() -> KeySpaceImpl {
+        KeySpaceImpl {}
+    }
+
+    /// 在调用者的事务中使用的list，参见[crate::List]
+    pub fn list() -> ListImpl {
+        ListImpl {}
     }
 }
@@ -0,0 +1,197 @@
+use std::path::{Path, PathBuf};
+
+use rocksdb::{
+    BlockBasedOptions, Cache, ColumnFamilyDescriptor, DBCompressionType, OptimisticTransactionDB, Options, TransactionDB, TransactionDBOptions,
+    DEFAULT_COLUMN_FAMILY_NAME,
+};
+
+use crate::{
+    rocksdb_impl::column_families::{prefix_extractor, ColumnFamilies},
+    ExpireIndex, RedisRocksdb, RrError, TransactionalDb,
+};
+
+/// 使用适合本crate的key格式的参数打开或新建数据库：
+/// 按head key的prefix extractor、bloom filter、按level的压缩、共享的block cache，以及过期key的compaction filter
+/// 默认打开[TransactionDB]，读多写少、冲突少时可以使用乐观事务的[OptimisticTransactionDB]（[RedisRocksdbBuilder::build_optimistic]），
+/// 也可以打开不带事务的[rocksdb::DB]（用于[crate::WrapRocksDb]）
+///
+/// 本crate没有使用merge operator（参见[crate::KeyValue]），需要注册的只有[ExpireIndex]的compaction filter
+pub struct RedisRocksdbBuilder {
+    path: PathBuf,
+    opts: Options,
+    txn_db_opts: TransactionDBOptions,
+    column_families: bool,
+    block_cache_size: usize,
+    bloom_bits_per_key: f64,
+    compression_per_level: Vec<DBCompressionType>,
+    expire_index: ExpireIndex,
+    max_retries: u32,
+    migrate_key_layout: bool,
+}
+
+impl RedisRocksdbBuilder {
+    pub const DEFAULT_BLOCK_CACHE_SIZE: usize = 64 * 1024 * 1024;
+    pub const DEFAULT_BLOOM_BITS_PER_KEY: f64 = 10.0;
+    pub const DEFAULT_MAX_RETRIES: u32 = 3;
+
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        RedisRocksdbBuilder {
+            path: path.as_ref().to_path_buf(),
+            opts: Options::default(),
+            txn_db_opts: TransactionDBOptions::default(),
+            column_families: false,
+            block_cache_size: Self::DEFAULT_BLOCK_CACHE_SIZE,
+            bloom_bits_per_key: Self::DEFAULT_BLOOM_BITS_PER_KEY,
+            //上层的数据很快会被compaction，不压缩；最下层的数据最多，使用压缩率高的zstd
+            compression_per_level: vec![
+                DBCompressionType::None,
+                DBCompressionType::None,
+                DBCompressionType::Lz4,
+                DBCompressionType::Lz4,
+                DBCompressionType::Lz4,
+                DBCompressionType::Lz4,
+                DBCompressionType::Zstd,
+            ],
+            expire_index: ExpireIndex::new(),
+            max_retries: Self::DEFAULT_MAX_RETRIES,
+            migrate_key_layout: false,
+        }
+    }
+
+    /// 其它的参数（如increase_parallelism、write buffer）在opts中设置，builder的参数会覆盖opts中相同的参数
+    pub fn options(mut self, opts: Options) -> Self {
+        self.opts = opts;
+        self
+    }
+
+    pub fn transaction_db_options(mut self, txn_db_opts: TransactionDBOptions) -> Self {
+        self.txn_db_opts = txn_db_opts;
+        self
+    }
+
+    /// 按类型使用column family，只能在新建数据库时选择，只用于build与build_optimistic，参见[ColumnFamilies]
+    pub fn column_families(mut self, column_families: bool) -> Self {
+        self.column_families = column_families;
+        self
+    }
+
+    /// 所有column family共享的block cache的大小（bytes）
+    pub fn block_cache_size(mut self, size: usize) -> Self {
+        self.block_cache_size = size;
+        self
+    }
+
+    /// bloom filter每个key使用的bit数，为0时不使用bloom filter
+    pub fn bloom_bits_per_key(mut self, bits: f64) -> Self {
+        self.bloom_bits_per_key = bits;
+        self
+    }
+
+    /// 每一个level的压缩方式，第一个为level 0
+    pub fn compression_per_level(mut self, compression: Vec<DBCompressionType>) -> Self {
+        self.compression_per_level = compression;
+        self
+    }
+
+    /// 要与其它的数据库共享过期索引时使用，默认为新的[ExpireIndex]
+    pub fn expire_index(mut self, expire_index: ExpireIndex) -> Self {
+        self.expire_index = expire_index;
+        self
+    }
+
+    /// 事务冲突时最多重试的次数，参见[RedisRocksdb::in_transaction]
+    pub fn max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// 打开时把旧格式的数据转换为当前的格式，参见[RedisRocksdb::migrate_key_layout]，默认不转换，旧格式的数据库打开时返回错误
+    pub fn migrate_key_layout(mut self, migrate: bool) -> Self {
+        self.migrate_key_layout = migrate;
+        self
+    }
+
+    /// 打开[TransactionDB]并返回[RedisRocksdb]
+    pub fn build(self) -> Result<RedisRocksdb, RrError> {
+        let cache = Cache::new_lru_cache(self.block_cache_size);
+        let opts = self.db_options(&cache);
+        let db = if self.column_families {
+            ColumnFamilies::open_with(opts, &self.txn_db_opts, &self.path, &self.expire_index, |name| self.table_options(&cache, name))?
+        } else {
+            TransactionDB::open(&opts, &self.txn_db_opts, &self.path)?
+        };
+        self.redis_rocksdb(db)
+    }
+
+    /// 打开[OptimisticTransactionDB]并返回[RedisRocksdb]，transaction_db_options不起作用
+    /// 乐观事务不加锁，提交时发现冲突返回错误，list、string等的方法在冲突时自动重试，参见[RedisRocksdb::in_transaction]
+    pub fn build_optimistic(self) -> Result<RedisRocksdb<OptimisticTransactionDB>, RrError> {
+        let cache = Cache::new_lru_cache(self.block_cache_size);
+        let mut opts = self.db_options(&cache);
+        let db = if self.column_families {
+            let descriptors = ColumnFamilies::descriptors(&mut opts, &self.path, &self.expire_index, |name| self.table_options(&cache, name))?;
+            OptimisticTransactionDB::open_cf_descriptors(&opts, &self.path, descriptors)?
+        } else {
+            //明确打开default，这样有它的handle，FLUSHDB与FLUSHALL可以使用DeleteRange
+            let descriptors = [ColumnFamilyDescriptor::new(DEFAULT_COLUMN_FAMILY_NAME, opts.clone())];
+            OptimisticTransactionDB::open_cf_descriptors(&opts, &self.path, descriptors)?
+        };
+        self.redis_rocksdb(db)
+    }
+
+    /// 打开不带事务的数据库，用于[crate::WrapRocksDb]，不是[RedisRocksdb]，所以不设置过期key的compaction filter
+    pub fn open_db(self) -> Result<rocksdb::DB, RrError> {
+        self.check_no_column_families()?;
+        let opts = self.table_options(&Cache::new_lru_cache(self.block_cache_size), "");
+        Ok(rocksdb::DB::open(&opts, &self.path)?)
+    }
+
+    /// 打开乐观事务的数据库，不是[RedisRocksdb]，与[RedisRocksdbBuilder::open_db]一样不设置compaction filter
+    pub fn open_optimistic(self) -> Result<OptimisticTransactionDB, RrError> {
+        self.check_no_column_families()?;
+        let opts = self.table_options(&Cache::new_lru_cache(self.block_cache_size), "");
+        Ok(OptimisticTransactionDB::open(&opts, &self.path)?)
+    }
+
+    fn check_no_column_families(&self) -> Result<(), RrError> {
+        if self.column_families {
+            return Err(RrError::message("column families are only supported when building RedisRocksdb".to_owned()));
+        }
+        Ok(())
+    }
+
+    fn redis_rocksdb<D: TransactionalDb>(self, db: D) -> Result<RedisRocksdb<D>, RrError> {
+        if self.migrate_key_layout {
+            let migrated = RedisRocksdb::migrate_key_layout(&db)?;
+            if migrated > 0 {
+                log::info!("migrated {} keys to the current key layout", migrated);
+            }
+        }
+        let mut redis_db = RedisRocksdb::try_with_expire_index(db, self.expire_index)?;
+        redis_db.set_max_retries(self.max_retries);
+        Ok(redis_db)
+    }
+
+    /// default的参数
+    fn db_options(&self, cache: &Cache) -> Options {
+        let mut opts = self.table_options(cache, "");
+        self.expire_index.set_compaction_filter(&mut opts);
+        opts
+    }
+
+    /// name为column family的名字，default为""
+    fn table_options(&self, cache: &Cache, name: &str) -> Options {
+        let mut opts = self.opts.clone();
+        opts.create_if_missing(true);
+        opts.set_prefix_extractor(prefix_extractor());
+        opts.set_compression_per_level(&self.compression_per_level);
+        let mut block_opts = BlockBasedOptions::default();
+        block_opts.set_block_cache(cache);
+        if self.bloom_bits_per_key > 0.0 {
+            block_opts.set_bloom_filter(self.bloom_bits_per_key, false);
+        }
+        ColumnFamilies::tune(name, &mut opts, &mut block_opts);
+        opts.set_block_based_table_factory(&block_opts);
+        opts
+    }
+}
@@ -0,0 +1,556 @@
+use std::cell::RefCell;
+
+use rocksdb::{Transaction, TransactionDB};
+
+use crate::{
+    rocksdb_impl::{
+        expire_impl::{expire_if_needed, get_expire, make_expire_key, now_millis, persist_key, pexpire_at_key, pttl_key, ttl_from_pttl},
+        key_space_impl::make_type_key,
+        key_value_impl::{
+            append_value, future_deadline, get_del_value, get_ex_value, get_set_value, get_value, incr_by_float_value, incr_by_value, mget_value,
+            mset_not_exist_value, mset_value, overflow_error, put_not_exist_value, put_value, put_with_value, set_range_value, sub_range,
+        },
+        pub_sub_impl::{expiration_event, Event},
+    },
+    Bytes, Expiration, Heap, KeyExpire, KeyValue, LenType, List, ListImpl, NotifyEvents, RedisList, RedisRocksdb, RrError, SetOptions, Stream,
+    StreamAddOptions, StreamId, StreamImpl, TransactionalDb, WrapDb, WrapTransaction, XAddId,
+};
+
+/// watch时key的状态：类型登记的值（包含版本，每一次修改都会改变）与过期时间
+/// 已过期的key与不存在的key一样
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct KeyState {
+    key_type: Option<Vec<u8>>,
+    deadline: Option<i64>,
+}
+
+impl KeyState {
+    fn read<T: WrapDb>(t: &T, key: &[u8]) -> Result<KeyState, RrError> {
+        let deadline = get_expire(t, key)?;
+        if deadline.is_some_and(|d| d <= now_millis()) {
+            return Ok(KeyState {
+                key_type: None,
+                deadline: None,
+            });
+        }
+        Ok(KeyState {
+            key_type: t.get(&make_type_key(key))?,
+            deadline,
+        })
+    }
+}
+
+/// WATCH的key与它们在watch时的状态，不依赖事务，所以可以在多个命令之间保存（比如redis的WATCH与MULTI之间）
+#[derive(Clone, Debug, Default)]
+pub struct WatchedKeys {
+    keys: Vec<(Vec<u8>, KeyState)>,
+}
+
+impl WatchedKeys {
+    pub fn new() -> Self {
+        WatchedKeys::default()
+    }
+
+    /// 记录key当前的状态，之后key的任何修改（包括删除、过期与过期时间的修改）都会使[RedisTransaction::exec]失败
+    /// 与redis一样，写入相同的值也算作修改
+    pub fn watch<D: TransactionalDb>(&mut self, db: &RedisRocksdb<D>, key: &[u8]) -> Result<(), RrError> {
+        let state = KeyState::read(&db.wrap_db(), key)?;
+        self.keys.push((key.to_vec(), state));
+        Ok(())
+    }
+
+    /// 锁定所有watch的key并检查它们没有被修改，返回false表示有key被修改了
+    /// 成功时key一直锁定到tx结束，所以在tx提交之前不会再被修改
+    /// 要在tx的第一个修改之前调用，不然tx自己的修改也会被当作修改
+    pub fn check<D: TransactionalDb>(&self, tx: &RedisTransaction<'_, D>) -> Result<bool, RrError> {
+        let t = tx.wrap_unchecked();
+        for (key, state) in &self.keys {
+            t.get_for_update(&make_type_key(key))?;
+            t.get_for_update(&make_expire_key(key))?;
+            if KeyState::read(&t, key)? != *state {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.keys.clear();
+    }
+}
+
+/// 与redis的MULTI/EXEC一样，多个命令在一个rocksdb事务中执行，[RedisTransaction::exec]时一起提交，
+/// drop或[RedisTransaction::discard]时放弃所有的修改
+///
+/// 实现了[KeyValue]、[RedisList]与[KeyExpire]，每一个方法是原子的，出错时不会留下部分的修改，
+/// Object、Heap等使用[RedisTransaction::wrap]，如 `RedisRocksdb::object().set(&tx.wrap(), key, field, value)`
+///
+/// 修改过的key在事务中被锁定，直到提交或放弃，所以不要长时间持有
+pub struct RedisTransaction<'db, D = TransactionDB> {
+    db: &'db RedisRocksdb<D>,
+    tr: Transaction<'db, D>,
+    watched: WatchedKeys,
+    /// watch的key的检查结果，在第一个命令之前检查，参见[RedisTransaction::watch]
+    checked: RefCell<Option<Result<bool, RrError>>>,
+    /// push过的list，提交后唤醒阻塞的等待者
+    pushed: RefCell<Vec<Vec<u8>>>,
+    /// xadd过的stream，提交后唤醒阻塞的xread/xreadgroup
+    streams: RefCell<Vec<Vec<u8>>>,
+    /// 设置的过期时间，提交后才加入到[crate::ExpireIndex]，不然放弃的事务可能使compaction filter删除数据
+    expires: RefCell<Vec<(Vec<u8>, i64)>>,
+    /// keyspace/keyevent通知，提交后才发送，放弃的事务不发送
+    events: RefCell<Vec<Event>>,
+}
+
+impl<'db, D: TransactionalDb> RedisTransaction<'db, D> {
+    pub(crate) fn new(db: &'db RedisRocksdb<D>) -> Self {
+        RedisTransaction {
+            db,
+            tr: db.db.transaction(),
+            watched: WatchedKeys::default(),
+            checked: RefCell::new(None),
+            pushed: RefCell::new(Vec::new()),
+            streams: RefCell::new(Vec::new()),
+            expires: RefCell::new(Vec::new()),
+            events: RefCell::new(Vec::new()),
+        }
+    }
+
+    /// 对应redis的watch，参见[WatchedKeys::watch]
+    /// 在读取key之前调用，这样读取到的数据在提交时一定没有被修改
+    /// 与redis的MULTI之后不能WATCH一样，只能在事务的第一个命令之前调用
+    /// watch的key在第一个命令之前检查并锁定，所以事务自己对key的修改不影响[RedisTransaction::exec]
+    pub fn watch(&mut self, key: &[u8]) -> Result<(), RrError> {
+        if self.checked.borrow().is_some() {
+            return Err(RrError::message("WATCH inside MULTI is not allowed".to_owned()));
+        }
+        self.watched.watch(self.db, key)
+    }
+
+    /// 对应redis的exec，watch的key被修改时放弃所有的修改并返回false
+    /// 乐观事务在提交时才检查冲突，有冲突时返回[RrError::TransactionConflict]，要重新执行整个事务
+    pub fn exec(self) -> Result<bool, RrError> {
+        self.check_watched();
+        if !self.checked.take().unwrap_or(Ok(true))? {
+            self.tr.rollback()?;
+            return Ok(false);
+        }
+        self.tr.commit()?;
+        for key in self.pushed.into_inner() {
+            self.db.list_waiters.notify(&key);
+        }
+        for key in self.streams.into_inner() {
+            self.db.stream_waiters.notify_all(&key);
+        }
+        for (key, deadline) in self.expires.into_inner() {
+            self.db.index_expire(&key, deadline);
+        }
+        for (class, event, key) in self.events.into_inner() {
+            self.db.notify(class, event, &key);
+        }
+        Ok(true)
+    }
+
+    /// 对应redis的discard，放弃所有的修改，与drop一样
+    pub fn discard(self) -> Result<(), RrError> {
+        Ok(self.tr.rollback()?)
+    }
+
+    /// 用于WrapDb的方法，如[crate::Object]、[crate::Heap]
+    pub fn wrap(&self) -> WrapTransaction<'_, D> {
+        self.check_watched();
+        self.wrap_unchecked()
+    }
+
+    fn wrap_unchecked(&self) -> WrapTransaction<'_, D> {
+        self.db.wrap_transaction_notify(&self.tr, &self.events)
+    }
+
+    /// 第一次使用事务时检查watch的key，结果（包括错误）在[RedisTransaction::exec]时返回
+    fn check_watched(&self) {
+        if self.checked.borrow().is_none() {
+            let re = self.watched.check(self);
+            *self.checked.borrow_mut() = Some(re);
+        }
+    }
+
+    /// 执行f，出错时撤销f所做的修改，事务中之前的修改不受影响
+    /// 与redis一样，事务中的一个命令出错不影响其它的命令
+    pub fn atomic<R, F>(&self, f: F) -> Result<R, RrError>
+    where
+        F: FnOnce(&Self) -> Result<R, RrError>,
+    {
+        //在savepoint之前锁定watch的key，撤销f时不会释放
+        self.check_watched();
+        self.tr.set_savepoint();
+        let events = self.events.borrow().len();
+        match f(self) {
+            Ok(re) => Ok(re),
+            Err(e) => {
+                self.tr.rollback_to_savepoint()?;
+                //撤销的修改中删除的过期key也不再发送通知
+                self.events.borrow_mut().truncate(events);
+                Err(e)
+            }
+        }
+    }
+
+    /// 对应redis的xadd，参见[Stream::add]，与直接使用[crate::StreamImpl]不同，提交后唤醒阻塞在key上的读取
+    pub fn stream_add(&self, key: &[u8], id: &XAddId, fields: &[(&[u8], &[u8])], opts: &StreamAddOptions) -> Result<Option<StreamId>, RrError> {
+        let re = self.atomic(|tx| StreamImpl {}.add(&tx.wrap(), key, id, fields, opts))?;
+        if re.is_some() {
+            self.streams.borrow_mut().push(key.to_vec());
+        }
+        Ok(re)
+    }
+
+    /// 提交之后发送keyspace/keyevent通知，参见[RedisRocksdb::notify]
+    /// 用于[RedisTransaction::wrap]的修改，如 `tx.notify(NotifyEvents::HASH, "hset", key)`
+    pub fn notify(&self, class: NotifyEvents, event: &'static str, key: &[u8]) {
+        self.events.borrow_mut().push((class, event, key.to_vec()));
+    }
+
+    /// 对应[Heap::push]，提交后发送heappush通知
+    pub fn heap_push<H>(&self, heap: &H, key: &[u8], field: &[u8], value: &[u8]) -> Result<(), RrError>
+    where
+        H: for<'a> Heap<WrapTransaction<'a, D>>,
+    {
+        self.atomic(|tx| heap.push(&tx.wrap(), key, field, value))?;
+        self.notify(NotifyEvents::HEAP, "heappush", key);
+        Ok(())
+    }
+
+    /// 对应[Heap::pop]，取出字段时提交后发送heappop通知
+    pub fn heap_pop<H>(&self, heap: &H, key: &[u8]) -> Result<Option<(Vec<u8>, Vec<u8>)>, RrError>
+    where
+        H: for<'a> Heap<WrapTransaction<'a, D>>,
+    {
+        let re = self.atomic(|tx| heap.pop(&tx.wrap(), key))?;
+        if re.is_some() {
+            self.notify(NotifyEvents::HEAP, "heappop", key);
+        }
+        Ok(re)
+    }
+
+    fn list_atomic<R, F>(&self, f: F) -> Result<R, RrError>
+    where
+        F: FnOnce(&ListImpl, &WrapTransaction<'_, D>) -> Result<R, RrError>,
+    {
+        self.atomic(|tx| f(&ListImpl {}, &tx.wrap()))
+    }
+
+    /// 设置的过期时间在提交之后加入到[crate::ExpireIndex]
+    fn expire_later(&self, key: &[u8], expiration: Option<Expiration>) {
+        if let Some(deadline) = future_deadline(expiration) {
+            self.expires.borrow_mut().push((key.to_vec(), deadline));
+        }
+    }
+
+    fn list_pushed(&self, key: &[u8], len: i32, event: &'static str) -> i32 {
+        if len > 0 {
+            self.pushed.borrow_mut().push(key.to_vec());
+        }
+        self.list_modified(key, len, event)
+    }
+
+    /// len大于0表示list被修改了
+    fn list_modified(&self, key: &[u8], len: i32, event: &'static str) -> i32 {
+        if len > 0 {
+            self.notify(NotifyEvents::LIST, event, key);
+        }
+        len
+    }
+
+    fn expiration_notify(&self, key: &[u8], expiration: Option<Expiration>) {
+        if let Some(event) = expiration_event(expiration) {
+            self.notify(NotifyEvents::GENERIC, event, key);
+        }
+    }
+}
+
+impl<D: TransactionalDb> KeyValue for RedisTransaction<'_, D> {
+    fn get<K: Bytes>(&self, key: &K) -> Result<Option<Vec<u8>>, RrError> {
+        self.atomic(|tx| get_value(&tx.wrap(), key.as_ref()))
+    }
+
+    fn put<K: Bytes, V: Bytes>(&self, key: &K, value: &V) -> Result<(), RrError> {
+        self.atomic(|tx| put_value(&tx.wrap(), key.as_ref(), value.as_ref()))?;
+        self.notify(NotifyEvents::STRING, "set", key.as_ref());
+        Ok(())
+    }
+
+    fn put_with<K: Bytes, V: Bytes>(&self, key: &K, value: &V, options: &SetOptions) -> Result<(bool, Option<Vec<u8>>), RrError> {
+        let re = self.atomic(|tx| put_with_value(&tx.wrap(), key.as_ref(), value.as_ref(), options))?;
+        if re.0 {
+            self.expire_later(key.as_ref(), options.expiration);
+            self.notify(NotifyEvents::STRING, "set", key.as_ref());
+            self.expiration_notify(key.as_ref(), options.expiration);
+        }
+        Ok(re)
+    }
+
+    fn put_not_exist<K: Bytes, V: Bytes>(&self, key: &K, value: &V) -> Result<bool, RrError> {
+        let re = self.atomic(|tx| put_not_exist_value(&tx.wrap(), key.as_ref(), value.as_ref()))?;
+        if re {
+            self.notify(NotifyEvents::STRING, "set", key.as_ref());
+        }
+        Ok(re)
+    }
+
+    fn get_set<K: Bytes, V: Bytes>(&self, key: &K, value: &V) -> Result<Option<Vec<u8>>, RrError> {
+        let re = self.atomic(|tx| get_set_value(&tx.wrap(), key.as_ref(), value.as_ref()))?;
+        self.notify(NotifyEvents::STRING, "set", key.as_ref());
+        Ok(re)
+    }
+
+    fn get_del<K: Bytes>(&self, key: &K) -> Result<Option<Vec<u8>>, RrError> {
+        let re = self.atomic(|tx| get_del_value(&tx.wrap(), key.as_ref()))?;
+        if re.is_some() {
+            self.notify(NotifyEvents::GENERIC, "del", key.as_ref());
+        }
+        Ok(re)
+    }
+
+    fn get_ex<K: Bytes>(&self, key: &K, expiration: Option<Expiration>) -> Result<Option<Vec<u8>>, RrError> {
+        let re = self.atomic(|tx| get_ex_value(&tx.wrap(), key.as_ref(), expiration))?;
+        if re.is_some() {
+            self.expire_later(key.as_ref(), expiration);
+            self.expiration_notify(key.as_ref(), expiration);
+        }
+        Ok(re)
+    }
+
+    fn incr_by<K: Bytes>(&self, key: &K, increment: i64) -> Result<i64, RrError> {
+        let re = self.atomic(|tx| incr_by_value(&tx.wrap(), key.as_ref(), increment))?;
+        self.notify(NotifyEvents::STRING, "incrby", key.as_ref());
+        Ok(re)
+    }
+
+    fn decr_by<K: Bytes>(&self, key: &K, decrement: i64) -> Result<i64, RrError> {
+        self.incr_by(key, decrement.checked_neg().ok_or_else(overflow_error)?)
+    }
+
+    fn incr_by_float<K: Bytes>(&self, key: &K, increment: f64) -> Result<f64, RrError> {
+        let re = self.atomic(|tx| incr_by_float_value(&tx.wrap(), key.as_ref(), increment))?;
+        self.notify(NotifyEvents::STRING, "incrbyfloat", key.as_ref());
+        Ok(re)
+    }
+
+    fn append<K: Bytes, V: Bytes>(&self, key: &K, value: &V) -> Result<LenType, RrError> {
+        let re = self.atomic(|tx| append_value(&tx.wrap(), key.as_ref(), value.as_ref()))?;
+        self.notify(NotifyEvents::STRING, "append", key.as_ref());
+        Ok(re)
+    }
+
+    fn str_len<K: Bytes>(&self, key: &K) -> Result<LenType, RrError> {
+        Ok(self.get(key)?.map_or(0, |v| v.len() as LenType))
+    }
+
+    fn get_range<K: Bytes>(&self, key: &K, start: i64, end: i64) -> Result<Vec<u8>, RrError> {
+        Ok(self.get(key)?.map(|v| sub_range(&v, start, end).to_vec()).unwrap_or_default())
+    }
+
+    fn set_range<K: Bytes, V: Bytes>(&self, key: &K, offset: LenType, value: &V) -> Result<LenType, RrError> {
+        let re = self.atomic(|tx| set_range_value(&tx.wrap(), key.as_ref(), offset, value.as_ref()))?;
+        if !value.as_ref().is_empty() {
+            self.notify(NotifyEvents::STRING, "setrange", key.as_ref());
+        }
+        Ok(re)
+    }
+
+    fn mget<K: Bytes>(&self, keys: &[K]) -> Result<Vec<Option<Vec<u8>>>, RrError> {
+        self.atomic(|tx| {
+            let t = tx.wrap();
+            keys.iter().map(|key| mget_value(&t, key.as_ref())).collect()
+        })
+    }
+
+    fn mset<K: Bytes, V: Bytes>(&self, items: &[(K, V)]) -> Result<(), RrError> {
+        self.atomic(|tx| mset_value(&tx.wrap(), items))?;
+        for (key, _) in items {
+            self.notify(NotifyEvents::STRING, "set", key.as_ref());
+        }
+        Ok(())
+    }
+
+    fn mset_not_exist<K: Bytes, V: Bytes>(&self, items: &[(K, V)]) -> Result<bool, RrError> {
+        let re = self.atomic(|tx| mset_not_exist_value(&tx.wrap(), items))?;
+        if re {
+            for (key, _) in items {
+                self.notify(NotifyEvents::STRING, "set", key.as_ref());
+            }
+        }
+        Ok(re)
+    }
+}
+
+/// 与redis的MULTI中一样，阻塞的pop不会等待，list为空时直接返回None
+impl<D: TransactionalDb> RedisList for RedisTransaction<'_, D> {
+    fn list_blpop<K: Bytes>(&self, key: &K, timeout: i64) -> Result<Option<Vec<u8>>, RrError> {
+        check_timeout(timeout)?;
+        self.list_pop_front(key)
+    }
+
+    fn list_brpop<K: Bytes>(&self, key: &K, timeout: i64) -> Result<Option<Vec<u8>>, RrError> {
+        check_timeout(timeout)?;
+        self.list_pop_back(key)
+    }
+
+    fn list_brpoplpush<K: Bytes>(&self, srckey: &K, dstkey: &K, timeout: i64) -> Result<Option<Vec<u8>>, RrError> {
+        check_timeout(timeout)?;
+        self.list_replace_back(srckey, dstkey)
+    }
+
+    fn list_index<K: Bytes>(&self, key: &K, index: i32) -> Result<Vec<u8>, RrError> {
+        self.list_atomic(|l, t| l.index(t, key.as_ref(), index))
+    }
+
+    fn list_insert_before<K: Bytes, V: Bytes>(&self, key: &K, pivot: &V, value: &V) -> Result<i32, RrError> {
+        let len = self.list_atomic(|l, t| l.insert_before(t, key.as_ref(), pivot.as_ref(), value.as_ref()))?;
+        Ok(self.list_modified(key.as_ref(), len, "linsert"))
+    }
+
+    fn list_insert_after<K: Bytes, V: Bytes>(&self, key: &K, pivot: &V, value: &V) -> Result<i32, RrError> {
+        let len = self.list_atomic(|l, t| l.insert_after(t, key.as_ref(), pivot.as_ref(), value.as_ref()))?;
+        Ok(self.list_modified(key.as_ref(), len, "linsert"))
+    }
+
+    fn list_len<K: Bytes>(&self, key: &K) -> Result<i32, RrError> {
+        self.list_atomic(|l, t| l.len(t, key.as_ref()))
+    }
+
+    fn list_pop_front<K: Bytes>(&self, key: &K) -> Result<Option<Vec<u8>>, RrError> {
+        let re = self.list_atomic(|l, t| l.pop_front(t, key.as_ref()))?;
+        if re.is_some() {
+            self.notify(NotifyEvents::LIST, "lpop", key.as_ref());
+        }
+        Ok(re)
+    }
+
+    fn list_push_front<K: Bytes, V: Bytes>(&self, key: &K, value: &V) -> Result<i32, RrError> {
+        let len = self.list_atomic(|l, t| l.push_front(t, key.as_ref(), value.as_ref()))?;
+        Ok(self.list_pushed(key.as_ref(), len, "lpush"))
+    }
+
+    fn list_push_front_exists<K: Bytes, V: Bytes>(&self, key: &K, value: &V) -> Result<i32, RrError> {
+        let len = self.list_atomic(|l, t| l.push_front_exists(t, key.as_ref(), value.as_ref()))?;
+        Ok(self.list_pushed(key.as_ref(), len, "lpush"))
+    }
+
+    fn list_range<K: Bytes>(&self, key: &K, start: i32, stop: i32) -> Result<Vec<Vec<u8>>, RrError> {
+        self.list_atomic(|l, t| l.range(t, key.as_ref(), start, stop))
+    }
+
+    fn list_rem<K: Bytes, V: Bytes>(&self, key: &K, count: i32, value: &V) -> Result<LenType, RrError> {
+        let removed = self.list_atomic(|l, t| l.rem(t, key.as_ref(), count, value.as_ref()))?;
+        if removed > 0 {
+            self.notify(NotifyEvents::LIST, "lrem", key.as_ref());
+        }
+        Ok(removed)
+    }
+
+    fn list_trim<K: Bytes>(&self, key: &K, start: i32, stop: i32) -> Result<i32, RrError> {
+        let re = self.list_atomic(|l, t| l.trim(t, key.as_ref(), start, stop))?;
+        self.notify(NotifyEvents::LIST, "ltrim", key.as_ref());
+        Ok(re)
+    }
+
+    fn list_set<K: Bytes, V: Bytes>(&self, key: &K, index: i32, value: &V) -> Result<Vec<u8>, RrError> {
+        let re = self.list_atomic(|l, t| l.set(t, key.as_ref(), index, value.as_ref()))?;
+        self.notify(NotifyEvents::LIST, "lset", key.as_ref());
+        Ok(re)
+    }
+
+    fn list_pop_back<K: Bytes>(&self, key: &K) -> Result<Option<Vec<u8>>, RrError> {
+        let re = self.list_atomic(|l, t| l.pop_back(t, key.as_ref()))?;
+        if re.is_some() {
+            self.notify(NotifyEvents::LIST, "rpop", key.as_ref());
+        }
+        Ok(re)
+    }
+
+    fn list_replace_back<K: Bytes>(&self, key: &K, dstkey: &K) -> Result<Option<Vec<u8>>, RrError> {
+        let re = self.list_atomic(|l, t| l.replace_back(t, key.as_ref(), dstkey.as_ref()))?;
+        if re.is_some() {
+            self.notify(NotifyEvents::LIST, "rpop", key.as_ref());
+            self.list_pushed(dstkey.as_ref(), 1, "lpush");
+        }
+        Ok(re)
+    }
+
+    fn list_push_back<K: Bytes, V: Bytes>(&self, key: &K, value: &V) -> Result<i32, RrError> {
+        let len = self.list_atomic(|l, t| l.push_back(t, key.as_ref(), value.as_ref()))?;
+        Ok(self.list_pushed(key.as_ref(), len, "rpush"))
+    }
+
+    fn list_push_back_exists<K: Bytes, V: Bytes>(&self, key: &K, value: &V) -> Result<i32, RrError> {
+        let len = self.list_atomic(|l, t| l.push_back_exists(t, key.as_ref(), value.as_ref()))?;
+        Ok(self.list_pushed(key.as_ref(), len, "rpush"))
+    }
+
+    fn list_clear<K: Bytes>(&self, key: &K) -> Result<i32, RrError> {
+        let re = self.list_atomic(|l, t| l.clear(t, key.as_ref()))?;
+        if re > 0 {
+            self.notify(NotifyEvents::GENERIC, "del", key.as_ref());
+        }
+        Ok(re)
+    }
+}
+
+fn check_timeout(timeout: i64) -> Result<(), RrError> {
+    if timeout < 0 {
+        return Err(RrError::message(format!("timeout is negative: {}", timeout)));
+    }
+    Ok(())
+}
+
+impl<D: TransactionalDb> KeyExpire for RedisTransaction<'_, D> {
+    fn expire<K: Bytes>(&self, key: &K, seconds: i64) -> Result<bool, RrError> {
+        self.pexpire_at(key, now_millis().saturating_add(seconds.saturating_mul(1000)))
+    }
+
+    fn pexpire<K: Bytes>(&self, key: &K, milliseconds: i64) -> Result<bool, RrError> {
+        self.pexpire_at(key, now_millis().saturating_add(milliseconds))
+    }
+
+    fn expire_at<K: Bytes>(&self, key: &K, timestamp: i64) -> Result<bool, RrError> {
+        self.pexpire_at(key, timestamp.saturating_mul(1000))
+    }
+
+    fn pexpire_at<K: Bytes>(&self, key: &K, timestamp: i64) -> Result<bool, RrError> {
+        let re = self.atomic(|tx| pexpire_at_key(&tx.wrap(), key.as_ref(), timestamp))?;
+        if re {
+            if timestamp > now_millis() {
+                self.expires.borrow_mut().push((key.as_ref().to_vec(), timestamp));
+                self.notify(NotifyEvents::GENERIC, "expire", key.as_ref());
+            } else {
+                self.notify(NotifyEvents::GENERIC, "del", key.as_ref());
+            }
+        }
+        Ok(re)
+    }
+
+    fn ttl<K: Bytes>(&self, key: &K) -> Result<i64, RrError> {
+        Ok(ttl_from_pttl(self.pttl(key)?))
+    }
+
+    fn pttl<K: Bytes>(&self, key: &K) -> Result<i64, RrError> {
+        self.atomic(|tx| {
+            let t = tx.wrap();
+            expire_if_needed(&t, key.as_ref())?;
+            pttl_key(&t, key.as_ref())
+        })
+    }
+
+    fn persist<K: Bytes>(&self, key: &K) -> Result<bool, RrError> {
+        let re = self.atomic(|tx| persist_key(&tx.wrap(), key.as_ref()))?;
+        if re {
+            self.notify(NotifyEvents::GENERIC, "persist", key.as_ref());
+        }
+        Ok(re)
+    }
+}
@@ -0,0 +1,37 @@
+use rocksdb::Direction;
+
+use crate::{RrError, ScanCursor, ScanOptions, WrapDb};
+
+/// 按key的顺序遍历以prefix开始的key，从cursor之后开始，最多遍历opts.count个
+/// f 的参数为 (去掉prefix的key, value)，只有匹配opts.pattern的才会调用，返回false时表示这个元素不计入遍历的个数
+/// 返回下一批的游标，遍历完成时返回空的游标
+pub(crate) fn scan_prefix<T: WrapDb, F>(t: &T, prefix: &[u8], cursor: &ScanCursor, opts: &ScanOptions, mut f: F) -> Result<ScanCursor, RrError>
+where
+    F: FnMut(&[u8], &[u8]) -> Result<bool, RrError>,
+{
+    let last = cursor.last();
+    let seek = match last {
+        None => prefix.to_vec(),
+        Some(element) => [prefix, element].concat(),
+    };
+    let count = opts.count.max(1);
+    let mut scanned = 0;
+    for item in t.iterator_from(&seek, Direction::Forward) {
+        let (k, v) = item?;
+        if !k.starts_with(prefix) {
+            break;
+        }
+        let element = &k[prefix.len()..];
+        if last == Some(element) {
+            continue;
+        }
+        let counted = if opts.is_match(element) { f(element, &v)? } else { true };
+        if counted {
+            scanned += 1;
+            if scanned >= count {
+                return Ok(ScanCursor::after(element));
+            }
+        }
+    }
+    Ok(ScanCursor::start())
+}
@@ -0,0 +1,342 @@
+use std::{
+    collections::{hash_map::RandomState, BTreeMap, BTreeSet},
+    hash::{BuildHasher, Hasher},
+};
+
+use crate::{
+    read_len_type,
+    rocksdb_impl::{
+        expire_impl::expire_if_needed,
+        key_space_impl::{check_type, delete_key, remove_key_meta, set_type},
+        scan_impl::scan_prefix,
+        shared::{change_len, make_field_key, make_head_key},
+    },
+    KeyType, LenType, RrError, ScanBatch, ScanCursor, ScanOptions, Set, WrapDb, WrapIterator,
+};
+
+/// 使用两种key存放数据
+/// head key                : 成员的数量
+/// head key + "m" + member : 成员，value为空，按成员的bytes排序
+pub struct SetImpl {}
+
+const MEMBER_TAG: &[u8] = b"m";
+/// count为负数时返回的成员个数与集合大小无关，限制个数以免一次分配过多的内存
+const MAX_RAND_COUNT: u64 = 1 << 24;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum SetOp {
+    Inter,
+    Union,
+    Diff,
+}
+
+impl<T: WrapDb> Set<T> for SetImpl {
+    fn add(&self, t: &T, key: &[u8], members: &[&[u8]]) -> Result<LenType, RrError> {
+        check_type(t, key, KeyType::Set)?;
+        let mut added = 0;
+        for member in members {
+            let k = member_key(key, member);
+            if t.get(&k)?.is_none() {
+                t.put(&k, &[])?;
+                added += 1;
+            }
+        }
+        //成员都已存在时不算作修改
+        if added > 0 {
+            set_type(t, key, KeyType::Set)?;
+        }
+        change_len(t, KeyType::Set, key, added as i64)?;
+        Ok(added)
+    }
+
+    fn rem(&self, t: &T, key: &[u8], members: &[&[u8]]) -> Result<LenType, RrError> {
+        if !check_type(t, key, KeyType::Set)? {
+            return Ok(0);
+        }
+        let mut removed = 0;
+        for member in members {
+            let k = member_key(key, member);
+            if t.get(&k)?.is_some() {
+                t.delete(&k)?;
+                removed += 1;
+            }
+        }
+        if removed > 0 {
+            set_type(t, key, KeyType::Set)?;
+        }
+        change_len(t, KeyType::Set, key, -(removed as i64))?;
+        Ok(removed)
+    }
+
+    fn is_member(&self, t: &T, key: &[u8], member: &[u8]) -> Result<bool, RrError> {
+        if !check_type(t, key, KeyType::Set)? {
+            return Ok(false);
+        }
+        Ok(t.get(&member_key(key, member))?.is_some())
+    }
+
+    fn mis_member(&self, t: &T, key: &[u8], members: &[&[u8]]) -> Result<Vec<bool>, RrError> {
+        if !check_type(t, key, KeyType::Set)? {
+            return Ok(vec![false; members.len()]);
+        }
+        members.iter().map(|m| Ok(t.get(&member_key(key, m))?.is_some())).collect()
+    }
+
+    fn len(&self, t: &T, key: &[u8]) -> Result<LenType, RrError> {
+        check_type(t, key, KeyType::Set)?;
+        get_len(t, key)
+    }
+
+    fn members(&self, t: &T, key: &[u8]) -> Result<Vec<Vec<u8>>, RrError> {
+        check_type(t, key, KeyType::Set)?;
+        Members::new(t, key).collect()
+    }
+
+    fn pop(&self, t: &T, key: &[u8], count: LenType) -> Result<Vec<Vec<u8>>, RrError> {
+        if !check_type(t, key, KeyType::Set)? {
+            return Ok(Vec::new());
+        }
+        let len = get_len(t, key)?;
+        let members: Vec<Vec<u8>> = get_members_at(t, key, &random_indexes(len, count.min(len)))?.into_values().collect();
+        if !members.is_empty() {
+            set_type(t, key, KeyType::Set)?;
+        }
+        for member in &members {
+            t.delete(&member_key(key, member))?;
+        }
+        change_len(t, KeyType::Set, key, -(members.len() as i64))?;
+        Ok(members)
+    }
+
+    fn rand_member(&self, t: &T, key: &[u8], count: i64) -> Result<Vec<Vec<u8>>, RrError> {
+        if !check_type(t, key, KeyType::Set)? {
+            return Ok(Vec::new());
+        }
+        let len = get_len(t, key)?;
+        if len == 0 {
+            return Ok(Vec::new());
+        }
+        if count >= 0 {
+            let indexes = random_indexes(len, (count as LenType).min(len));
+            return Ok(get_members_at(t, key, &indexes)?.into_values().collect());
+        }
+        //可以重复，按产生的随机下标的顺序返回
+        let count = count.unsigned_abs();
+        if count > MAX_RAND_COUNT {
+            return Err(RrError::message("value is out of range".to_owned()));
+        }
+        let indexes: Vec<LenType> = (0..count).map(|_| random_u64() % len).collect();
+        let members = get_members_at(t, key, &indexes.iter().copied().collect())?;
+        indexes
+            .iter()
+            .map(|i| {
+                members
+                    .get(i)
+                    .cloned()
+                    .ok_or_else(|| RrError::corruption(&make_head_key(KeyType::Set, key), "length of set"))
+            })
+            .collect()
+    }
+
+    fn move_member(&self, t: &T, src: &[u8], dst: &[u8], member: &[u8]) -> Result<bool, RrError> {
+        let src_exists = check_type(t, src, KeyType::Set)?;
+        check_type(t, dst, KeyType::Set)?;
+        if !src_exists || t.get(&member_key(src, member))?.is_none() {
+            return Ok(false);
+        }
+        if src == dst {
+            return Ok(true);
+        }
+        self.rem(t, src, &[member])?;
+        self.add(t, dst, &[member])?;
+        Ok(true)
+    }
+
+    fn inter(&self, t: &T, keys: &[&[u8]]) -> Result<Vec<Vec<u8>>, RrError> {
+        merge(t, keys, SetOp::Inter)
+    }
+
+    fn union(&self, t: &T, keys: &[&[u8]]) -> Result<Vec<Vec<u8>>, RrError> {
+        merge(t, keys, SetOp::Union)
+    }
+
+    fn diff(&self, t: &T, keys: &[&[u8]]) -> Result<Vec<Vec<u8>>, RrError> {
+        merge(t, keys, SetOp::Diff)
+    }
+
+    fn inter_store(&self, t: &T, dst: &[u8], keys: &[&[u8]]) -> Result<LenType, RrError> {
+        store(t, dst, merge(t, keys, SetOp::Inter)?)
+    }
+
+    fn union_store(&self, t: &T, dst: &[u8], keys: &[&[u8]]) -> Result<LenType, RrError> {
+        store(t, dst, merge(t, keys, SetOp::Union)?)
+    }
+
+    fn diff_store(&self, t: &T, dst: &[u8], keys: &[&[u8]]) -> Result<LenType, RrError> {
+        store(t, dst, merge(t, keys, SetOp::Diff)?)
+    }
+
+    fn scan(&self, t: &T, key: &[u8], cursor: &ScanCursor, opts: &ScanOptions) -> Result<ScanBatch<Vec<u8>>, RrError> {
+        if !check_type(t, key, KeyType::Set)? {
+            return Ok(ScanBatch::default());
+        }
+        let mut items = Vec::new();
+        let cursor = scan_prefix(t, &make_field_key(KeyType::Set, key, MEMBER_TAG), cursor, opts, |member, _| {
+            items.push(member.to_vec());
+            Ok(true)
+        })?;
+        Ok(ScanBatch { cursor, items })
+    }
+
+    fn del_key(&self, t: &T, key: &[u8]) -> Result<(), RrError> {
+        check_type(t, key, KeyType::Set)?;
+        remove_key_meta(t, key)?;
+        let head_key = make_head_key(KeyType::Set, key);
+        for item in t.prefix_iterator(&head_key) {
+            let (k, _) = item?;
+            if !k.starts_with(&head_key) {
+                break;
+            }
+            t.delete(&k)?;
+        }
+        Ok(())
+    }
+}
+
+/// 按bytes的顺序遍历集合的成员
+struct Members<'a> {
+    prefix: Vec<u8>,
+    it: WrapIterator<'a>,
+}
+
+impl<'a> Members<'a> {
+    fn new<T: WrapDb>(t: &'a T, key: &[u8]) -> Self {
+        let prefix = make_field_key(KeyType::Set, key, MEMBER_TAG);
+        let it = t.prefix_iterator(&prefix);
+        Members { prefix, it }
+    }
+}
+
+impl Iterator for Members<'_> {
+    type Item = Result<Vec<u8>, RrError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.it.next()? {
+            Err(e) => Some(Err(e.into())),
+            //prefix_iterator不会在prefix处停止
+            Ok((k, _)) => k.strip_prefix(self.prefix.as_slice()).map(|m| Ok(m.to_vec())),
+        }
+    }
+}
+
+/// 同时按顺序遍历所有的集合，每一次取出所有集合中最小的成员，由哪些集合中有这个成员决定是否输出
+/// 交集在有一个集合遍历完时结束，差集在第一个集合遍历完时结束
+fn merge<T: WrapDb>(t: &T, keys: &[&[u8]], op: SetOp) -> Result<Vec<Vec<u8>>, RrError> {
+    let mut iters = Vec::with_capacity(keys.len());
+    let mut heads = Vec::with_capacity(keys.len());
+    for key in keys {
+        //与redis一样，所有的key都要检查类型
+        check_type(t, key, KeyType::Set)?;
+        let mut it = Members::new(t, key);
+        heads.push(it.next().transpose()?);
+        iters.push(it);
+    }
+    let mut re = Vec::new();
+    loop {
+        let done = match op {
+            SetOp::Inter => heads.iter().any(|h| h.is_none()),
+            SetOp::Diff => heads.first().is_none_or(|h| h.is_none()),
+            SetOp::Union => false,
+        };
+        if done {
+            break;
+        }
+        let min = match heads.iter().flatten().min() {
+            None => break,
+            Some(m) => m.clone(),
+        };
+        let mut count = 0;
+        let mut in_first = false;
+        for (i, (head, it)) in heads.iter_mut().zip(iters.iter_mut()).enumerate() {
+            if head.as_ref() == Some(&min) {
+                count += 1;
+                in_first |= i == 0;
+                *head = it.next().transpose()?;
+            }
+        }
+        let output = match op {
+            SetOp::Inter => count == keys.len(),
+            SetOp::Union => true,
+            SetOp::Diff => in_first && count == 1,
+        };
+        if output {
+            re.push(min);
+        }
+    }
+    Ok(re)
+}
+
+/// 覆盖dst，dst可以是参与运算的key，所以先计算出结果再写入
+fn store<T: WrapDb>(t: &T, dst: &[u8], members: Vec<Vec<u8>>) -> Result<LenType, RrError> {
+    expire_if_needed(t, dst)?;
+    delete_key(t, dst)?;
+    if members.is_empty() {
+        return Ok(0);
+    }
+    set_type(t, dst, KeyType::Set)?;
+    for member in &members {
+        t.put(&member_key(dst, member), &[])?;
+    }
+    change_len(t, KeyType::Set, dst, members.len() as i64)?;
+    Ok(members.len() as LenType)
+}
+
+fn get_len<T: WrapDb>(t: &T, key: &[u8]) -> Result<LenType, RrError> {
+    Ok(t.get(&make_head_key(KeyType::Set, key))?.map(|v| read_len_type(&v)).unwrap_or(0))
+}
+
+/// 返回下标（按成员排序）对应的成员，只遍历到最大的下标
+fn get_members_at<T: WrapDb>(t: &T, key: &[u8], indexes: &BTreeSet<LenType>) -> Result<BTreeMap<LenType, Vec<u8>>, RrError> {
+    let mut re = BTreeMap::new();
+    let last = match indexes.last() {
+        None => return Ok(re),
+        Some(last) => *last,
+    };
+    for (i, member) in Members::new(t, key).enumerate() {
+        let member = member?;
+        let i = i as LenType;
+        if indexes.contains(&i) {
+            re.insert(i, member);
+        }
+        if i >= last {
+            break;
+        }
+    }
+    if re.len() != indexes.len() {
+        return Err(RrError::corruption(&make_head_key(KeyType::Set, key), "length of set"));
+    }
+    Ok(re)
+}
+
+/// 从[0, len)中随机选出count个不重复的下标，count不能大于len（Floyd的算法）
+fn random_indexes(len: LenType, count: LenType) -> BTreeSet<LenType> {
+    let mut re = BTreeSet::new();
+    for j in len - count..len {
+        let r = random_u64() % (j + 1);
+        if !re.insert(r) {
+            re.insert(j);
+        }
+    }
+    re
+}
+
+/// 不依赖rand，每一个RandomState使用不同的随机key
+fn random_u64() -> u64 {
+    RandomState::new().build_hasher().finish()
+}
+
+fn member_key(key: &[u8], member: &[u8]) -> Vec<u8> {
+    let mut k = make_field_key(KeyType::Set, key, MEMBER_TAG);
+    k.extend_from_slice(member);
+    k
+}
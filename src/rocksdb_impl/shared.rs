@@ -1,27 +1,113 @@
-use std::ptr;
-
-pub(crate) fn make_field_key(key: &[u8], field: &[u8]) -> Vec<u8> {
-    let mut new_key = Vec::with_capacity(key.len() + field.len() + 3);
-    unsafe {
-        //这里使用性能更高的 copy_nonoverlapping
-        let mut p = new_key.as_mut_ptr();
-        ptr::copy_nonoverlapping(key.as_ptr(), p, key.len());
-        p = p.add(key.len());
-        *p = b':';
-        *(p.offset(1)) = b'_';
-        *(p.offset(2)) = b'_';
-        p = p.offset(3);
-        ptr::copy_nonoverlapping(field.as_ptr(), p, field.len());
-        new_key.set_len(new_key.capacity());
-    }
+use std::borrow::Cow;
+
+use crate::{read_len_type, rocksdb_impl::key_space_impl::remove_key_meta, write_len_type, KeyType, LenType, RrError, WrapDb, BYTES_LEN_TYPE};
+
+/// 数据库中key的格式，第一个byte为记录的tag：
+/// 0 + name                      : 数据库自己的数据，如[LAYOUT_VERSION_KEY]
+/// TAG_TYPE + key                : key的类型
+/// TAG_EXPIRE + key              : key的过期时间
+/// TAG_EXPIRE_TIME + 时间 + key  : 按过期时间排序的索引
+/// TAG_LIST_NODE + ...           : list的node与zip list，见[crate::MetaKey]
+/// TAG_NAMESPACE + id + ...      : 不是0号namespace的记录，id为u32 BE，... 为上面的记录，见[crate::RedisRocksdb::select]
+/// data_tag(类型) + len + key + field : 各类型的数据，len为key的长度（u32 BE），field为空时就是head key
+/// 数据的key中有长度，所以一个key的所有数据都以它的head key开始，prefix_iterator不会遍历到其它key的数据
+pub(crate) const TAG_TYPE: u8 = 1;
+pub(crate) const TAG_EXPIRE: u8 = 2;
+pub(crate) const TAG_EXPIRE_TIME: u8 = 3;
+pub(crate) const TAG_LIST_NODE: u8 = 4;
+pub(crate) const TAG_NAMESPACE: u8 = 5;
+const TAG_DATA: u8 = 0x10;
+const LEN_KEY_LEN: usize = 4;
+const LEN_NAMESPACE: usize = 1 + 4;
+
+/// 当前的key格式的版本，没有版本记录时：有旧格式的类型登记为1，没有类型登记的最初的格式为0，参见[crate::RedisRocksdb::key_layout_version]
+pub(crate) const KEY_LAYOUT_VERSION: u8 = 2;
+pub(crate) const LAYOUT_VERSION_KEY: &[u8] = b"\0layout_version";
+
+#[inline]
+fn data_tag(key_type: KeyType) -> u8 {
+    TAG_DATA + key_type as u8
+}
+
+pub(crate) fn make_field_key(key_type: KeyType, key: &[u8], field: &[u8]) -> Vec<u8> {
+    let mut new_key = Vec::with_capacity(1 + LEN_KEY_LEN + key.len() + field.len());
+    new_key.push(data_tag(key_type));
+    new_key.extend_from_slice(&(key.len() as u32).to_be_bytes());
+    new_key.extend_from_slice(key);
+    new_key.extend_from_slice(field);
     new_key
 }
 
+/// 字符串的值、list与stack的头也存放在head key中
 #[inline]
-pub(crate) fn make_head_key(key: &[u8]) -> Vec<u8> {
-    make_field_key(key, &[])
+pub(crate) fn make_head_key(key_type: KeyType, key: &[u8]) -> Vec<u8> {
+    make_field_key(key_type, key, &[])
 }
 
 pub(crate) fn get_field_from_key<'a>(key: &[u8], field_key: &'a [u8]) -> &'a [u8] {
-    &field_key[key.len() + 3..]
+    &field_key[1 + LEN_KEY_LEN + key.len()..]
+}
+
+/// 数据的key的类型，不是数据的key时返回None
+pub(crate) fn data_key_type(field_key: &[u8]) -> Option<KeyType> {
+    KeyType::try_from(field_key.first()?.checked_sub(TAG_DATA)?).ok()
+}
+
+/// [make_field_key]的反向，返回 (类型, key, field)，不是数据的key时返回None
+pub(crate) fn split_field_key(field_key: &[u8]) -> Option<(KeyType, &[u8], &[u8])> {
+    let key_type = data_key_type(field_key)?;
+    let rest = &field_key[1..];
+    let len = u32::from_be_bytes(rest.get(..LEN_KEY_LEN)?.try_into().ok()?) as usize;
+    let rest = &rest[LEN_KEY_LEN..];
+    let key = rest.get(..len)?;
+    Some((key_type, key, &rest[len..]))
+}
+
+/// 数据的key中head key部分，不是数据的key时返回None
+pub(crate) fn head_key_of(field_key: &[u8]) -> Option<&[u8]> {
+    split_field_key(field_key).map(|(_, key, _)| &field_key[..1 + LEN_KEY_LEN + key.len()])
+}
+
+/// 存放namespace中的key时使用的key，0号namespace没有前缀，与没有namespace时写入的数据兼容
+pub(crate) fn namespace_key(namespace: u32, key: &[u8]) -> Cow<'_, [u8]> {
+    if namespace == 0 {
+        Cow::Borrowed(key)
+    } else {
+        Cow::Owned([&namespace_prefix(namespace)[..], key].concat())
+    }
+}
+
+pub(crate) fn namespace_prefix(namespace: u32) -> [u8; LEN_NAMESPACE] {
+    let mut prefix = [TAG_NAMESPACE; LEN_NAMESPACE];
+    prefix[1..].copy_from_slice(&namespace.to_be_bytes());
+    prefix
+}
+
+/// [namespace_key]的反向，返回 (namespace, key)
+pub(crate) fn split_namespace(key: &[u8]) -> (u32, &[u8]) {
+    match key.split_first() {
+        Some((&TAG_NAMESPACE, rest)) if rest.len() >= LEN_NAMESPACE - 1 => {
+            let (id, rest) = rest.split_at(LEN_NAMESPACE - 1);
+            (u32::from_be_bytes(id.try_into().unwrap_or_default()), rest)
+        }
+        _ => (0, key),
+    }
+}
+
+/// 修改head key中成员的数量，用于sorted set与set
+/// 为0时与redis一样删除key（包括类型与过期时间），所以没有加入成员的新key也不会留下
+pub(crate) fn change_len<T: WrapDb>(t: &T, key_type: KeyType, key: &[u8], diff: i64) -> Result<(), RrError> {
+    let head_key = make_head_key(key_type, key);
+    let old = t.get(&head_key)?.map(|v| read_len_type(&v)).unwrap_or(0);
+    let new = old as i64 + diff;
+    if new <= 0 {
+        t.delete(&head_key)?;
+        remove_key_meta(t, key)
+    } else if diff != 0 {
+        let mut v = [0u8; BYTES_LEN_TYPE];
+        write_len_type(&mut v, new as LenType);
+        t.put(&head_key, &v)
+    } else {
+        Ok(())
+    }
 }
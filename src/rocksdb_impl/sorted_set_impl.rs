@@ -0,0 +1,510 @@
+use rocksdb::Direction;
+
+use crate::{
+    read_len_type,
+    rocksdb_impl::{
+        key_space_impl::{check_type, ensure_type, remove_key_meta, set_type},
+        scan_impl::scan_prefix,
+        shared::{change_len, make_field_key, make_head_key},
+    },
+    KeyType, LenType, LexBound, RrError, ScanBatch, ScanCursor, ScanOptions, ScoreBound, SortedSet, WrapDb, ZAddOptions, ZCompare, ZExist,
+};
+
+/// 使用三种key存放数据
+/// head key                           : 成员的数量
+/// head key + "m" + member            : 成员的score
+/// head key + "s" + score(8) + member : score的索引，score编码后按bytes的顺序与数值的顺序一样，所以可以直接使用rocksdb的遍历
+pub struct SortedSetImp {}
+
+const MEMBER_TAG: &[u8] = b"m";
+const SCORE_TAG: &[u8] = b"s";
+/// score索引之后的第一个key，反向遍历时使用
+const SCORE_TAG_END: &[u8] = b"t";
+const SCORE_LEN: usize = 8;
+
+enum AddResult {
+    Added(f64),
+    Changed(f64),
+    Unchanged(f64),
+    /// 因为选项没有执行
+    Skipped,
+}
+
+impl<T: WrapDb> SortedSet<T> for SortedSetImp {
+    fn add(&self, t: &T, key: &[u8], members: &[(f64, &[u8])], opts: &ZAddOptions) -> Result<LenType, RrError> {
+        check_options(opts)?;
+        check_type(t, key, KeyType::SortedSet)?;
+        let mut added = 0;
+        let mut changed = 0;
+        for (score, member) in members {
+            match add_member(t, key, *score, member, opts, false)? {
+                AddResult::Added(_) => added += 1,
+                AddResult::Changed(_) => changed += 1,
+                AddResult::Unchanged(_) | AddResult::Skipped => {}
+            }
+        }
+        if added + changed > 0 {
+            set_type(t, key, KeyType::SortedSet)?;
+        }
+        change_len(t, KeyType::SortedSet, key, added as i64)?;
+        if opts.ch {
+            Ok(added + changed)
+        } else {
+            Ok(added)
+        }
+    }
+
+    fn add_incr(&self, t: &T, key: &[u8], increment: f64, member: &[u8], opts: &ZAddOptions) -> Result<Option<f64>, RrError> {
+        check_options(opts)?;
+        check_type(t, key, KeyType::SortedSet)?;
+        match add_member(t, key, increment, member, opts, true)? {
+            AddResult::Added(s) => {
+                set_type(t, key, KeyType::SortedSet)?;
+                change_len(t, KeyType::SortedSet, key, 1)?;
+                Ok(Some(s))
+            }
+            AddResult::Changed(s) => {
+                set_type(t, key, KeyType::SortedSet)?;
+                Ok(Some(s))
+            }
+            AddResult::Unchanged(s) => Ok(Some(s)),
+            AddResult::Skipped => Ok(None),
+        }
+    }
+
+    fn incr_by(&self, t: &T, key: &[u8], increment: f64, member: &[u8]) -> Result<f64, RrError> {
+        ensure_type(t, key, KeyType::SortedSet)?;
+        let re = self.add_incr(t, key, increment, member, &ZAddOptions::default())?;
+        re.ok_or_else(|| RrError::corruption(&member_key(key, member), "score of sorted set"))
+    }
+
+    fn score(&self, t: &T, key: &[u8], member: &[u8]) -> Result<Option<f64>, RrError> {
+        check_type(t, key, KeyType::SortedSet)?;
+        get_score(t, key, member)
+    }
+
+    fn rank(&self, t: &T, key: &[u8], member: &[u8]) -> Result<Option<LenType>, RrError> {
+        check_type(t, key, KeyType::SortedSet)?;
+        find_rank(t, key, member, false)
+    }
+
+    fn rev_rank(&self, t: &T, key: &[u8], member: &[u8]) -> Result<Option<LenType>, RrError> {
+        check_type(t, key, KeyType::SortedSet)?;
+        find_rank(t, key, member, true)
+    }
+
+    fn range(&self, t: &T, key: &[u8], start: i64, stop: i64, rev: bool) -> Result<Vec<(Vec<u8>, f64)>, RrError> {
+        check_type(t, key, KeyType::SortedSet)?;
+        collect_by_rank(t, key, start, stop, rev)
+    }
+
+    fn range_by_score(
+        &self,
+        t: &T,
+        key: &[u8],
+        min: &ScoreBound,
+        max: &ScoreBound,
+        rev: bool,
+        limit: Option<(LenType, LenType)>,
+    ) -> Result<Vec<(Vec<u8>, f64)>, RrError> {
+        check_type(t, key, KeyType::SortedSet)?;
+        collect_by_score(t, key, min, max, rev, limit)
+    }
+
+    fn range_by_lex(&self, t: &T, key: &[u8], min: &LexBound, max: &LexBound, rev: bool, limit: Option<(LenType, LenType)>) -> Result<Vec<Vec<u8>>, RrError> {
+        check_type(t, key, KeyType::SortedSet)?;
+        let re = collect_by_lex(t, key, min, max, rev, limit)?;
+        Ok(re.into_iter().map(|(m, _)| m).collect())
+    }
+
+    fn count(&self, t: &T, key: &[u8], min: &ScoreBound, max: &ScoreBound) -> Result<LenType, RrError> {
+        check_type(t, key, KeyType::SortedSet)?;
+        let mut count = 0;
+        scan(t, key, Some(min.value()), false, |score, _| {
+            if !above_min(min, score) {
+                return Ok(true);
+            }
+            if !below_max(max, score) {
+                return Ok(false);
+            }
+            count += 1;
+            Ok(true)
+        })?;
+        Ok(count)
+    }
+
+    fn rem(&self, t: &T, key: &[u8], members: &[&[u8]]) -> Result<LenType, RrError> {
+        check_type(t, key, KeyType::SortedSet)?;
+        let mut removed = 0;
+        for member in members {
+            if let Some(score) = get_score(t, key, member)? {
+                t.delete(&member_key(key, member))?;
+                t.delete(&score_key(key, score, member))?;
+                removed += 1;
+            }
+        }
+        if removed > 0 {
+            set_type(t, key, KeyType::SortedSet)?;
+        }
+        change_len(t, KeyType::SortedSet, key, -(removed as i64))?;
+        Ok(removed)
+    }
+
+    fn rem_range_by_rank(&self, t: &T, key: &[u8], start: i64, stop: i64) -> Result<LenType, RrError> {
+        check_type(t, key, KeyType::SortedSet)?;
+        let entries = collect_by_rank(t, key, start, stop, false)?;
+        remove_entries(t, key, &entries)
+    }
+
+    fn rem_range_by_score(&self, t: &T, key: &[u8], min: &ScoreBound, max: &ScoreBound) -> Result<LenType, RrError> {
+        check_type(t, key, KeyType::SortedSet)?;
+        let entries = collect_by_score(t, key, min, max, false, None)?;
+        remove_entries(t, key, &entries)
+    }
+
+    fn rem_range_by_lex(&self, t: &T, key: &[u8], min: &LexBound, max: &LexBound) -> Result<LenType, RrError> {
+        check_type(t, key, KeyType::SortedSet)?;
+        let entries = collect_by_lex(t, key, min, max, false, None)?;
+        remove_entries(t, key, &entries)
+    }
+
+    fn pop_min(&self, t: &T, key: &[u8], count: LenType) -> Result<Vec<(Vec<u8>, f64)>, RrError> {
+        check_type(t, key, KeyType::SortedSet)?;
+        let entries = collect_by_score(t, key, &ScoreBound::min(), &ScoreBound::max(), false, Some((0, count)))?;
+        remove_entries(t, key, &entries)?;
+        Ok(entries)
+    }
+
+    fn pop_max(&self, t: &T, key: &[u8], count: LenType) -> Result<Vec<(Vec<u8>, f64)>, RrError> {
+        check_type(t, key, KeyType::SortedSet)?;
+        let entries = collect_by_score(t, key, &ScoreBound::min(), &ScoreBound::max(), true, Some((0, count)))?;
+        remove_entries(t, key, &entries)?;
+        Ok(entries)
+    }
+
+    fn len(&self, t: &T, key: &[u8]) -> Result<Option<LenType>, RrError> {
+        check_type(t, key, KeyType::SortedSet)?;
+        Ok(t.get(&make_head_key(KeyType::SortedSet, key))?.map(|v| read_len_type(&v)))
+    }
+
+    fn scan(&self, t: &T, key: &[u8], cursor: &ScanCursor, opts: &ScanOptions) -> Result<ScanBatch<(Vec<u8>, f64)>, RrError> {
+        if !check_type(t, key, KeyType::SortedSet)? {
+            return Ok(ScanBatch::default());
+        }
+        let mut items = Vec::new();
+        let cursor = scan_prefix(t, &make_field_key(KeyType::SortedSet, key, MEMBER_TAG), cursor, opts, |member, score| {
+            let bytes: [u8; SCORE_LEN] = score.try_into()?;
+            items.push((member.to_vec(), f64::from_le_bytes(bytes)));
+            Ok(true)
+        })?;
+        Ok(ScanBatch { cursor, items })
+    }
+
+    fn del_key(&self, t: &T, key: &[u8]) -> Result<(), RrError> {
+        check_type(t, key, KeyType::SortedSet)?;
+        remove_key_meta(t, key)?;
+        let head_key = make_head_key(KeyType::SortedSet, key);
+        for item in t.prefix_iterator(&head_key) {
+            let (k, _) = item?;
+            if !k.starts_with(&head_key) {
+                break;
+            }
+            t.delete(&k)?;
+        }
+        Ok(())
+    }
+}
+
+fn check_options(opts: &ZAddOptions) -> Result<(), RrError> {
+    if opts.exist == Some(ZExist::Nx) && opts.compare.is_some() {
+        return Err(RrError::message("GT, LT, and/or NX options at the same time are not compatible".to_owned()));
+    }
+    Ok(())
+}
+
+fn add_member<T: WrapDb>(t: &T, key: &[u8], score: f64, member: &[u8], opts: &ZAddOptions, incr: bool) -> Result<AddResult, RrError> {
+    if score.is_nan() {
+        return Err(RrError::message("value is not a valid float".to_owned()));
+    }
+    let old = get_score(t, key, member)?;
+    match (opts.exist, old) {
+        (Some(ZExist::Nx), Some(_)) | (Some(ZExist::Xx), None) => return Ok(AddResult::Skipped),
+        _ => {}
+    }
+    let new = match (incr, old) {
+        (true, Some(old)) => old + score,
+        _ => score,
+    };
+    if new.is_nan() {
+        return Err(RrError::message("resulting score is not a number (NaN)".to_owned()));
+    }
+    let old = match old {
+        None => {
+            put_member(t, key, new, member)?;
+            return Ok(AddResult::Added(new));
+        }
+        Some(old) => old,
+    };
+    let skip = match opts.compare {
+        Some(ZCompare::Gt) => new <= old,
+        Some(ZCompare::Lt) => new >= old,
+        None => false,
+    };
+    if skip {
+        Ok(AddResult::Skipped)
+    } else if new == old {
+        Ok(AddResult::Unchanged(old))
+    } else {
+        t.delete(&score_key(key, old, member))?;
+        put_member(t, key, new, member)?;
+        Ok(AddResult::Changed(new))
+    }
+}
+
+fn put_member<T: WrapDb>(t: &T, key: &[u8], score: f64, member: &[u8]) -> Result<(), RrError> {
+    t.put(&member_key(key, member), &score.to_le_bytes())?;
+    t.put(&score_key(key, score, member), &[])?;
+    Ok(())
+}
+
+fn get_score<T: WrapDb>(t: &T, key: &[u8], member: &[u8]) -> Result<Option<f64>, RrError> {
+    match t.get(&member_key(key, member))? {
+        None => Ok(None),
+        Some(v) => {
+            let bytes: [u8; SCORE_LEN] = v.as_slice().try_into()?;
+            Ok(Some(f64::from_le_bytes(bytes)))
+        }
+    }
+}
+
+/// 删除了成员时才写入新的版本
+fn remove_entries<T: WrapDb>(t: &T, key: &[u8], entries: &[(Vec<u8>, f64)]) -> Result<LenType, RrError> {
+    if !entries.is_empty() {
+        set_type(t, key, KeyType::SortedSet)?;
+    }
+    for (member, score) in entries {
+        t.delete(&member_key(key, member))?;
+        t.delete(&score_key(key, *score, member))?;
+    }
+    change_len(t, KeyType::SortedSet, key, -(entries.len() as i64))?;
+    Ok(entries.len() as LenType)
+}
+
+fn find_rank<T: WrapDb>(t: &T, key: &[u8], member: &[u8], rev: bool) -> Result<Option<LenType>, RrError> {
+    if get_score(t, key, member)?.is_none() {
+        return Ok(None);
+    }
+    let mut rank = 0;
+    let mut found = false;
+    scan(t, key, None, rev, |_, m| {
+        if m == member {
+            found = true;
+            return Ok(false);
+        }
+        rank += 1;
+        Ok(true)
+    })?;
+    if found {
+        Ok(Some(rank))
+    } else {
+        Err(RrError::corruption(&member_key(key, member), "score index of sorted set"))
+    }
+}
+
+fn collect_by_rank<T: WrapDb>(t: &T, key: &[u8], start: i64, stop: i64, rev: bool) -> Result<Vec<(Vec<u8>, f64)>, RrError> {
+    let mut re = Vec::new();
+    let len = match t.get(&make_head_key(KeyType::SortedSet, key))? {
+        None => return Ok(re),
+        Some(v) => read_len_type(&v) as i64,
+    };
+    let start = if start < 0 { (len + start).max(0) } else { start };
+    let stop = if stop < 0 { len + stop } else { stop.min(len - 1) };
+    if start > stop || start >= len {
+        return Ok(re);
+    }
+    let mut index = 0;
+    scan(t, key, None, rev, |score, member| {
+        if index >= start {
+            re.push((member.to_vec(), score));
+        }
+        index += 1;
+        Ok(index <= stop)
+    })?;
+    Ok(re)
+}
+
+fn collect_by_score<T: WrapDb>(
+    t: &T,
+    key: &[u8],
+    min: &ScoreBound,
+    max: &ScoreBound,
+    rev: bool,
+    limit: Option<(LenType, LenType)>,
+) -> Result<Vec<(Vec<u8>, f64)>, RrError> {
+    let mut re = Vec::new();
+    let (mut offset, mut count) = limit.unwrap_or((0, LenType::MAX));
+    if count == 0 {
+        return Ok(re);
+    }
+    let from = if rev { max.value() } else { min.value() };
+    scan(t, key, Some(from), rev, |score, member| {
+        //开始的边界不满足时跳过，结束的边界不满足时停止
+        let (begin_ok, end_ok) = if rev {
+            (below_max(max, score), above_min(min, score))
+        } else {
+            (above_min(min, score), below_max(max, score))
+        };
+        if !begin_ok {
+            return Ok(true);
+        }
+        if !end_ok {
+            return Ok(false);
+        }
+        if offset > 0 {
+            offset -= 1;
+            return Ok(true);
+        }
+        re.push((member.to_vec(), score));
+        count -= 1;
+        Ok(count > 0)
+    })?;
+    Ok(re)
+}
+
+fn collect_by_lex<T: WrapDb>(
+    t: &T,
+    key: &[u8],
+    min: &LexBound,
+    max: &LexBound,
+    rev: bool,
+    limit: Option<(LenType, LenType)>,
+) -> Result<Vec<(Vec<u8>, f64)>, RrError> {
+    let mut re = Vec::new();
+    let (mut offset, mut count) = limit.unwrap_or((0, LenType::MAX));
+    if count == 0 {
+        return Ok(re);
+    }
+    scan(t, key, None, rev, |score, member| {
+        let (begin_ok, end_ok) = if rev {
+            (lex_below_max(max, member), lex_above_min(min, member))
+        } else {
+            (lex_above_min(min, member), lex_below_max(max, member))
+        };
+        if !begin_ok {
+            return Ok(true);
+        }
+        if !end_ok {
+            return Ok(false);
+        }
+        if offset > 0 {
+            offset -= 1;
+            return Ok(true);
+        }
+        re.push((member.to_vec(), score));
+        count -= 1;
+        Ok(count > 0)
+    })?;
+    Ok(re)
+}
+
+/// 按score的顺序遍历score索引，from为开始的score(rev为true时是最大的score)，None表示从头(尾)开始
+/// f 的参数为 (score, member)，返回false时停止
+fn scan<T: WrapDb, F>(t: &T, key: &[u8], from: Option<f64>, rev: bool, mut f: F) -> Result<(), RrError>
+where
+    F: FnMut(f64, &[u8]) -> Result<bool, RrError>,
+{
+    let prefix = make_field_key(KeyType::SortedSet, key, SCORE_TAG);
+    let mut seek = prefix.clone();
+    match (from, rev) {
+        (Some(score), false) => seek.extend_from_slice(&encode_score(score)),
+        //反向时从比score大一点的位置开始，这样可以包含所有score相同的成员
+        (Some(score), true) => match u64::from_be_bytes(encode_score(score)).checked_add(1) {
+            Some(next) => seek.extend_from_slice(&next.to_be_bytes()),
+            None => seek = make_field_key(KeyType::SortedSet, key, SCORE_TAG_END),
+        },
+        (None, true) => seek = make_field_key(KeyType::SortedSet, key, SCORE_TAG_END),
+        (None, false) => {}
+    }
+    let direction = if rev { Direction::Reverse } else { Direction::Forward };
+    for item in t.iterator_from(&seek, direction) {
+        let (k, _) = item?;
+        if !k.starts_with(&prefix) {
+            //反向时，开始的位置可能在索引之后
+            if rev && k.as_ref() > prefix.as_slice() {
+                continue;
+            }
+            break;
+        }
+        if k.len() < prefix.len() + SCORE_LEN {
+            return Err(RrError::corruption(&k, "score index of sorted set"));
+        }
+        let score = decode_score(&k[prefix.len()..prefix.len() + SCORE_LEN]);
+        if !f(score, &k[prefix.len() + SCORE_LEN..])? {
+            break;
+        }
+    }
+    Ok(())
+}
+
+fn above_min(min: &ScoreBound, score: f64) -> bool {
+    match min {
+        ScoreBound::Inclusive(v) => score >= *v,
+        ScoreBound::Exclusive(v) => score > *v,
+    }
+}
+
+fn below_max(max: &ScoreBound, score: f64) -> bool {
+    match max {
+        ScoreBound::Inclusive(v) => score <= *v,
+        ScoreBound::Exclusive(v) => score < *v,
+    }
+}
+
+fn lex_above_min(min: &LexBound, member: &[u8]) -> bool {
+    match min {
+        LexBound::Inclusive(v) => member >= v.as_slice(),
+        LexBound::Exclusive(v) => member > v.as_slice(),
+        LexBound::Min => true,
+        LexBound::Max => false,
+    }
+}
+
+fn lex_below_max(max: &LexBound, member: &[u8]) -> bool {
+    match max {
+        LexBound::Inclusive(v) => member <= v.as_slice(),
+        LexBound::Exclusive(v) => member < v.as_slice(),
+        LexBound::Min => false,
+        LexBound::Max => true,
+    }
+}
+
+fn member_key(key: &[u8], member: &[u8]) -> Vec<u8> {
+    let mut k = make_field_key(KeyType::SortedSet, key, MEMBER_TAG);
+    k.extend_from_slice(member);
+    k
+}
+
+fn score_key(key: &[u8], score: f64, member: &[u8]) -> Vec<u8> {
+    let mut k = make_field_key(KeyType::SortedSet, key, SCORE_TAG);
+    k.extend_from_slice(&encode_score(score));
+    k.extend_from_slice(member);
+    k
+}
+
+/// 编码后按bytes比较的顺序与f64的大小顺序一样
+/// 正数把符号位置1，负数所有位取反，使用big endian
+fn encode_score(score: f64) -> [u8; SCORE_LEN] {
+    // -0.0 与 0.0 相等，统一为 0.0
+    let score = if score == 0.0 { 0.0 } else { score };
+    let bits = score.to_bits();
+    let bits = if bits & (1 << 63) != 0 { !bits } else { bits | (1 << 63) };
+    bits.to_be_bytes()
+}
+
+fn decode_score(bytes: &[u8]) -> f64 {
+    let mut b = [0u8; SCORE_LEN];
+    b.copy_from_slice(&bytes[..SCORE_LEN]);
+    let bits = u64::from_be_bytes(b);
+    let bits = if bits & (1 << 63) != 0 { bits & !(1 << 63) } else { !bits };
+    f64::from_bits(bits)
+}
@@ -1,54 +1,178 @@
-use std::{ops::Deref, ptr};
+use std::ops::Deref;
 
-use crate::{read_int, write_int, Bytes, RedisRocksdb, RrError, Stack};
+use crate::{
+    read_int,
+    rocksdb_impl::{
+        key_space_impl::{ensure_type, remove_key_meta, touch_type},
+        shared::{make_field_key, make_head_key},
+    },
+    write_int, Bytes, KeyType, RedisRocksdb, RrError, Stack, TransactionalDb, WrapDb,
+};
 
-impl Stack for RedisRocksdb {
-    fn index<K: Bytes>(&self, key: &K, _index: i64) -> Result<Vec<u8>, RrError> {
-        let stack = StackHeader::get_stack(&self.db, key.as_ref())?;
-        match stack {
-            None => Ok(vec![]),
-            Some(v) => Ok(v.size.to_vec()),
-        }
+/// head key中存放[StackHeader]（元素的个数），每一个元素存放在 head key + index(i64 LE) 中
+/// 修改多个key的操作都在一个事务中完成
+impl<D: TransactionalDb> Stack for RedisRocksdb<D> {
+    fn index<K: Bytes>(&self, key: &K, index: i64) -> Result<Vec<u8>, RrError> {
+        self.check_key(key.as_ref(), KeyType::Stack)?;
+        let t = self.wrap_db();
+        let stack = StackHeader::get_stack(&t, key.as_ref())?.ok_or_else(|| RrError::not_find("key"))?;
+        let index = stack.count_index(index).ok_or_else(|| RrError::index_out_of_range(index))?;
+        let index_key = StackHeader::make_key_index(key.as_ref(), index);
+        let v = t.get(&index_key)?;
+        v.ok_or_else(|| RrError::corruption(&index_key, "value of stack"))
     }
 
-    fn len<K: Bytes>(&self, _key: &K) -> Result<i64, RrError> {
-        todo!()
+    fn len<K: Bytes>(&self, key: &K) -> Result<i64, RrError> {
+        self.check_key(key.as_ref(), KeyType::Stack)?;
+        match StackHeader::get_stack(&self.wrap_db(), key.as_ref())? {
+            None => Ok(-1),
+            Some(stack) => Ok(stack.size_i64()),
+        }
     }
 
-    fn push<K: Bytes, V: Bytes>(&mut self, _key: &K, _value: &V) -> Result<i64, RrError> {
-        todo!()
+    fn push<K: Bytes, V: Bytes>(&self, key: &K, value: &V) -> Result<i64, RrError> {
+        self.commit_once(|t| {
+            ensure_type(t, key.as_ref(), KeyType::Stack)?;
+            let mut stack = StackHeader::get_stack_for_update(t, key.as_ref())?.unwrap_or_else(|| StackHeader::from(0));
+            let re = stack.push(t, key.as_ref(), value.as_ref())?;
+            Ok(re)
+        })
     }
 
-    fn pushs<K: Bytes, V: Bytes>(&mut self, _key: &K, _values: &[&V]) -> Result<i64, RrError> {
-        todo!()
+    fn pushs<K: Bytes, V: Bytes>(&self, key: &K, values: &[&V]) -> Result<i64, RrError> {
+        self.commit_once(|t| {
+            ensure_type(t, key.as_ref(), KeyType::Stack)?;
+            let mut stack = StackHeader::get_stack_for_update(t, key.as_ref())?.unwrap_or_else(|| StackHeader::from(0));
+            for v in values {
+                let index = stack.size_i64();
+                t.put(&StackHeader::make_key_index(key.as_ref(), index), v.as_ref())?;
+                stack.add_1();
+            }
+            t.put(&StackHeader::make_key(key.as_ref()), stack.as_ref())?;
+            Ok(stack.size_i64())
+        })
     }
 
-    fn push_exists<K: Bytes, V: Bytes>(&mut self, _key: &K, _value: &V) -> Result<i64, RrError> {
-        todo!()
+    fn push_exists<K: Bytes, V: Bytes>(&self, key: &K, value: &V) -> Result<i64, RrError> {
+        self.commit_once(|t| {
+            touch_type(t, key.as_ref(), KeyType::Stack)?;
+            let mut stack = match StackHeader::get_stack_for_update(t, key.as_ref())? {
+                None => return Ok(-1),
+                Some(s) => s,
+            };
+            let re = stack.push(t, key.as_ref(), value.as_ref())?;
+            Ok(re)
+        })
     }
 
-    fn range<K: Bytes>(&self, _key: &K, _start: i64, _stop: i64) -> Result<Vec<Vec<u8>>, RrError> {
-        todo!()
+    fn range<K: Bytes>(&self, key: &K, start: i64, stop: i64) -> Result<Vec<Vec<u8>>, RrError> {
+        self.check_key(key.as_ref(), KeyType::Stack)?;
+        let mut result = Vec::new();
+        //在事务中锁定header，读到的元素与header是一致的
+        let tr = self.db.transaction();
+        let t = self.wrap_transaction(&tr);
+        let stack = match StackHeader::get_stack_for_update(&t, key.as_ref())? {
+            None => return Ok(result),
+            Some(s) => s,
+        };
+        let len = stack.size_i64();
+        let start = if start < 0 { (len + start).max(0) } else { start };
+        let stop = if stop < 0 { len + stop } else { stop.min(len - 1) };
+        if start > stop || start >= len {
+            return Ok(result);
+        }
+
+        result.reserve((stop - start + 1) as usize);
+        for index in start..=stop {
+            let index_key = StackHeader::make_key_index(key.as_ref(), index);
+            let v = t.get(&index_key)?;
+            result.push(v.ok_or_else(|| RrError::corruption(&index_key, "value of stack"))?);
+        }
+        Ok(result)
     }
 
-    fn set<K: Bytes, V: Bytes>(&mut self, _key: &K, _index: i64, _value: &V) -> Result<Vec<u8>, RrError> {
-        todo!()
+    fn set<K: Bytes, V: Bytes>(&self, key: &K, index: i64, value: &V) -> Result<Vec<u8>, RrError> {
+        self.commit_once(|t| {
+            touch_type(t, key.as_ref(), KeyType::Stack)?;
+            let stack = StackHeader::get_stack_for_update(t, key.as_ref())?.ok_or_else(|| RrError::not_find("key"))?;
+            let index = stack.count_index(index).ok_or_else(|| RrError::index_out_of_range(index))?;
+            let index_key = StackHeader::make_key_index(key.as_ref(), index);
+            let old = t.get_for_update(&index_key)?.ok_or_else(|| RrError::corruption(&index_key, "value of stack"))?;
+            t.put(&index_key, value.as_ref())?;
+            Ok(old)
+        })
     }
 
-    fn pop<K: Bytes>(&self, _key: &K) -> Result<Vec<u8>, RrError> {
-        todo!()
+    fn pop<K: Bytes>(&self, key: &K) -> Result<Option<Vec<u8>>, RrError> {
+        self.commit_once(|t| {
+            touch_type(t, key.as_ref(), KeyType::Stack)?;
+            let mut stack = match StackHeader::get_stack_for_update(t, key.as_ref())? {
+                None => return Ok(None),
+                Some(s) => s,
+            };
+            let v = stack.pop(t, key.as_ref())?;
+            Ok(v)
+        })
     }
 
-    fn pops<K: Bytes>(&self, _key: &K, _amount: u64) -> Result<Vec<Vec<u8>>, RrError> {
-        todo!()
+    fn pops<K: Bytes>(&self, key: &K, amount: u64) -> Result<Vec<Vec<u8>>, RrError> {
+        self.commit_once(|t| {
+            touch_type(t, key.as_ref(), KeyType::Stack)?;
+            let mut stack = match StackHeader::get_stack_for_update(t, key.as_ref())? {
+                None => return Ok(vec![]),
+                Some(s) => s,
+            };
+            let amount = amount.min(stack.size_i64() as u64);
+            let mut result = Vec::with_capacity(amount as usize);
+            for _ in 0..amount {
+                let index = stack.size_i64() - 1;
+                let index_key = StackHeader::make_key_index(key.as_ref(), index);
+                let v = t.get(&index_key)?.ok_or_else(|| RrError::corruption(&index_key, "value of stack"))?;
+                t.delete(&index_key)?;
+                stack.add(-1);
+                result.push(v);
+            }
+            if amount > 0 {
+                t.put(&StackHeader::make_key(key.as_ref()), stack.as_ref())?;
+            }
+            Ok(result)
+        })
     }
 
-    fn poplpush<K: Bytes, V: Bytes>(&mut self, _key: &K, _dstkey: &K) -> Result<V, RrError> {
-        todo!()
+    fn poplpush<K: Bytes>(&self, key: &K, dstkey: &K) -> Result<Option<Vec<u8>>, RrError> {
+        self.commit_once(|t| {
+            touch_type(t, key.as_ref(), KeyType::Stack)?;
+            ensure_type(t, dstkey.as_ref(), KeyType::Stack)?;
+            let mut stack = match StackHeader::get_stack_for_update(t, key.as_ref())? {
+                None => return Ok(None),
+                Some(s) => s,
+            };
+            let v = match stack.pop(t, key.as_ref())? {
+                None => return Ok(None),
+                Some(v) => v,
+            };
+            //key与dstkey相同时，读到的是上面pop修改后的header
+            let mut dst = StackHeader::get_stack_for_update(t, dstkey.as_ref())?.unwrap_or_else(|| StackHeader::from(0));
+            dst.push(t, dstkey.as_ref(), &v)?;
+            Ok(Some(v))
+        })
     }
 
-    fn clear<K: Bytes>(&mut self, _key: &K) -> Result<i64, RrError> {
-        todo!()
+    fn clear<K: Bytes>(&self, key: &K) -> Result<i64, RrError> {
+        self.check_key(key.as_ref(), KeyType::Stack)?;
+        self.commit_once(|t| {
+            let stack = match StackHeader::get_stack_for_update(t, key.as_ref())? {
+                None => return Ok(-1),
+                Some(s) => s,
+            };
+            let len = stack.size_i64();
+            for index in 0..len {
+                t.delete(&StackHeader::make_key_index(key.as_ref(), index))?;
+            }
+            t.delete(&StackHeader::make_key(key.as_ref()))?;
+            remove_key_meta(t, key.as_ref())?;
+            Ok(len)
+        })
     }
 }
 
@@ -75,43 +199,69 @@ impl StackHeader {
         t
     }
 
-    fn make_key_index(key: &[u8], index: i64) -> Vec<u8> {
-        let mut v = Vec::with_capacity(key.len() + 9);
-        unsafe {
-            v.set_len(v.capacity());
-            ptr::copy(key.as_ptr(), v.as_mut_ptr(), key.len());
+    /// 把负数的index转换为正数，超出范围返回None
+    fn count_index(&self, index: i64) -> Option<i64> {
+        let len = self.size_i64();
+        let index = if index < 0 { len + index } else { index };
+        if index < 0 || index >= len {
+            None
+        } else {
+            Some(index)
         }
-        let vv = v.as_mut_slice();
-        vv[key.len()] = b'_';
-        write_int(&mut vv[key.len() + 1..], index);
-        v
     }
 
-    fn get_stack(db: &rocksdb::TransactionDB, key: &[u8]) -> Result<Option<Self>, RrError> {
-        let v = db.get(key)?;
+    /// header存放在head key中
+    fn make_key(key: &[u8]) -> Vec<u8> {
+        make_head_key(KeyType::Stack, key)
+    }
+
+    fn make_key_index(key: &[u8], index: i64) -> Vec<u8> {
+        let mut field = [0u8; 8];
+        write_int(&mut field[..], index);
+        make_field_key(KeyType::Stack, key, &field)
+    }
+
+    fn get_stack<T: WrapDb>(t: &T, key: &[u8]) -> Result<Option<Self>, RrError> {
+        StackHeader::from_value(t.get(&StackHeader::make_key(key))?)
+    }
+
+    /// 在事务中读取并锁定header，防止并发修改时丢失更新
+    fn get_stack_for_update<T: WrapDb>(t: &T, key: &[u8]) -> Result<Option<Self>, RrError> {
+        StackHeader::from_value(t.get_for_update(&StackHeader::make_key(key))?)
+    }
+
+    fn from_value(v: Option<Vec<u8>>) -> Result<Option<Self>, RrError> {
         match v {
             None => Ok(None),
             Some(v) => {
                 if v.len() < 8 {
-                    Err(RrError::data_error("key < 8"))
+                    Err(RrError::corruption(&[], "stack header < 8"))
                 } else {
-                    Ok(Some(StackHeader::from(v.deref())))
+                    Ok(Some(StackHeader::from(&v.deref()[..8])))
                 }
             }
         }
     }
-    fn get_index(&self, db: &rocksdb::TransactionDB, key: &[u8], _index: i64) -> Result<Vec<u8>, RrError> {
-        let v = db.get(key)?;
-        match v {
-            None => Ok(vec![]),
-            Some(v) => {
-                if v.len() < 8 {
-                    Err(RrError::data_error("key < 8"))
-                } else {
-                    Ok(v.to_vec())
-                }
-            }
+
+    /// 在事务中push，并保存header，返回新的len
+    fn push<T: WrapDb>(&mut self, t: &T, key: &[u8], value: &[u8]) -> Result<i64, RrError> {
+        t.put(&StackHeader::make_key_index(key, self.size_i64()), value)?;
+        let re = self.add_1();
+        t.put(&StackHeader::make_key(key), self.as_ref())?;
+        Ok(re)
+    }
+
+    /// 在事务中pop，并保存header
+    fn pop<T: WrapDb>(&mut self, t: &T, key: &[u8]) -> Result<Option<Vec<u8>>, RrError> {
+        if self.size_i64() < 1 {
+            return Ok(None);
         }
+        let index_key = StackHeader::make_key_index(key, self.size_i64() - 1);
+        let v = t.get(&index_key)?.ok_or_else(|| RrError::corruption(&index_key, "value of stack"))?;
+        t.delete(&index_key)?;
+        self.add(-1);
+        t.put(&StackHeader::make_key(key), self.as_ref())?;
+        Ok(Some(v))
     }
 }
 
@@ -0,0 +1,751 @@
+use std::{
+    collections::BTreeMap,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use rocksdb::Direction;
+
+use crate::{
+    read_len_type,
+    rocksdb_impl::{
+        expire_impl::now_millis,
+        key_space_impl::{check_type, delete_prefix, remove_key_meta, set_type},
+        list_waiters::Waiter,
+        shared::{make_field_key, make_head_key},
+    },
+    write_len_type, AutoClaimOptions, AutoClaimed, ClaimOptions, KeyType, LenType, PendingEntry, PendingFilter, PendingSummary, ReadGroupOptions, RedisRocksdb,
+    RrError, Stream, StreamAddOptions, StreamEntry, StreamId, StreamRead, StreamTrim, TransactionalDb, WrapDb, WrapTransaction, XAddId, BYTES_LEN_TYPE,
+};
+
+/// 使用以下的key存放数据，group使用u32(big endian)的长度作为前缀，这样一个组的前缀不会是另一个组的前缀
+/// head key                                : 项的个数 + 最后的id
+/// head key + "e" + id                     : 项的fields，id为big endian，所以按id排序
+/// head key + "g" + group                  : 组最后投递的id
+/// head key + "c" + len + group + consumer : 消费者最后活动的时间
+/// head key + "p" + len + group + id       : pending entries list，投递的时间 + 投递的次数 + 消费者
+pub struct StreamImpl {}
+
+const ENTRY_TAG: &[u8] = b"e";
+const GROUP_TAG: &[u8] = b"g";
+const CONSUMER_TAG: &[u8] = b"c";
+const PENDING_TAG: &[u8] = b"p";
+
+impl<T: WrapDb> Stream<T> for StreamImpl {
+    fn add(&self, t: &T, key: &[u8], id: &XAddId, fields: &[(&[u8], &[u8])], opts: &StreamAddOptions) -> Result<Option<StreamId>, RrError> {
+        if fields.is_empty() {
+            return Err(RrError::message("fields of stream entry is empty".to_owned()));
+        }
+        if !check_type(t, key, KeyType::Stream)? && opts.no_mk_stream {
+            return Ok(None);
+        }
+        let mut meta = Meta::get_for_update(t, key)?;
+        let id = next_id(&meta.last_id, id)?;
+        set_type(t, key, KeyType::Stream)?;
+        t.put(&entry_key(key, &id), &encode_fields(fields))?;
+        meta.len += 1;
+        meta.last_id = id;
+        if let Some(trim) = &opts.trim {
+            trim_entries(t, key, &mut meta, trim)?;
+        }
+        meta.put(t, key)?;
+        Ok(Some(id))
+    }
+
+    fn len(&self, t: &T, key: &[u8]) -> Result<LenType, RrError> {
+        if !check_type(t, key, KeyType::Stream)? {
+            return Ok(0);
+        }
+        Ok(Meta::get(t, key)?.len)
+    }
+
+    fn last_id(&self, t: &T, key: &[u8]) -> Result<Option<StreamId>, RrError> {
+        if !check_type(t, key, KeyType::Stream)? {
+            return Ok(None);
+        }
+        Ok(Some(Meta::get(t, key)?.last_id))
+    }
+
+    fn range(&self, t: &T, key: &[u8], start: &StreamId, end: &StreamId, count: Option<LenType>) -> Result<Vec<StreamEntry>, RrError> {
+        if !check_type(t, key, KeyType::Stream)? {
+            return Ok(Vec::new());
+        }
+        get_entries(t, key, start, end, count, Direction::Forward)
+    }
+
+    fn rev_range(&self, t: &T, key: &[u8], end: &StreamId, start: &StreamId, count: Option<LenType>) -> Result<Vec<StreamEntry>, RrError> {
+        if !check_type(t, key, KeyType::Stream)? {
+            return Ok(Vec::new());
+        }
+        get_entries(t, key, start, end, count, Direction::Reverse)
+    }
+
+    fn trim(&self, t: &T, key: &[u8], trim: &StreamTrim) -> Result<LenType, RrError> {
+        if !check_type(t, key, KeyType::Stream)? {
+            return Ok(0);
+        }
+        let mut meta = Meta::get_for_update(t, key)?;
+        let trimmed = trim_entries(t, key, &mut meta, trim)?;
+        if trimmed > 0 {
+            set_type(t, key, KeyType::Stream)?;
+            meta.put(t, key)?;
+        }
+        Ok(trimmed)
+    }
+
+    fn del(&self, t: &T, key: &[u8], ids: &[StreamId]) -> Result<LenType, RrError> {
+        if !check_type(t, key, KeyType::Stream)? {
+            return Ok(0);
+        }
+        let mut meta = Meta::get_for_update(t, key)?;
+        let mut deleted = 0;
+        for id in ids {
+            let k = entry_key(key, id);
+            if t.get(&k)?.is_some() {
+                t.delete(&k)?;
+                deleted += 1;
+            }
+        }
+        if deleted > 0 {
+            meta.len -= deleted;
+            set_type(t, key, KeyType::Stream)?;
+            meta.put(t, key)?;
+        }
+        Ok(deleted)
+    }
+
+    fn read(&self, t: &T, keys: &[(&[u8], StreamId)], count: Option<LenType>) -> Result<StreamRead, RrError> {
+        let mut re = Vec::new();
+        for (key, id) in keys {
+            if !check_type(t, key, KeyType::Stream)? {
+                continue;
+            }
+            let start = match id.next() {
+                None => continue,
+                Some(start) => start,
+            };
+            let entries = get_entries(t, key, &start, &StreamId::MAX, count, Direction::Forward)?;
+            if !entries.is_empty() {
+                re.push((key.to_vec(), entries));
+            }
+        }
+        Ok(re)
+    }
+
+    fn group_create(&self, t: &T, key: &[u8], group: &[u8], id: &StreamId, mk_stream: bool) -> Result<(), RrError> {
+        if !check_type(t, key, KeyType::Stream)? {
+            if !mk_stream {
+                return Err(RrError::message(
+                    "The XGROUP subcommand requires the key to exist. Note that for CREATE you may want to use the MKSTREAM option to create an empty stream automatically."
+                        .to_owned(),
+                ));
+            }
+            Meta::default().put(t, key)?;
+        }
+        let k = group_key(key, group);
+        if t.get_for_update(&k)?.is_some() {
+            return Err(RrError::message("BUSYGROUP Consumer Group name already exists".to_owned()));
+        }
+        set_type(t, key, KeyType::Stream)?;
+        t.put(&k, &id.to_bytes())
+    }
+
+    fn group_set_id(&self, t: &T, key: &[u8], group: &[u8], id: &StreamId) -> Result<(), RrError> {
+        lock_group(t, key, group)?;
+        set_type(t, key, KeyType::Stream)?;
+        t.put(&group_key(key, group), &id.to_bytes())
+    }
+
+    fn group_destroy(&self, t: &T, key: &[u8], group: &[u8]) -> Result<bool, RrError> {
+        if !check_type(t, key, KeyType::Stream)? {
+            return Ok(false);
+        }
+        let k = group_key(key, group);
+        if t.get_for_update(&k)?.is_none() {
+            return Ok(false);
+        }
+        t.delete(&k)?;
+        delete_prefix(t, &group_prefix(key, CONSUMER_TAG, group))?;
+        delete_prefix(t, &group_prefix(key, PENDING_TAG, group))?;
+        set_type(t, key, KeyType::Stream)?;
+        Ok(true)
+    }
+
+    fn group_create_consumer(&self, t: &T, key: &[u8], group: &[u8], consumer: &[u8]) -> Result<bool, RrError> {
+        lock_group(t, key, group)?;
+        if t.get(&consumer_key(key, group, consumer))?.is_some() {
+            return Ok(false);
+        }
+        touch_consumer(t, key, group, consumer, now_millis())?;
+        set_type(t, key, KeyType::Stream)?;
+        Ok(true)
+    }
+
+    fn group_del_consumer(&self, t: &T, key: &[u8], group: &[u8], consumer: &[u8]) -> Result<LenType, RrError> {
+        lock_group(t, key, group)?;
+        let k = consumer_key(key, group, consumer);
+        if t.get(&k)?.is_none() {
+            return Ok(0);
+        }
+        t.delete(&k)?;
+        let prefix = group_prefix(key, PENDING_TAG, group);
+        let mut deleted = 0;
+        for item in t.prefix_iterator(&prefix) {
+            let (k, v) = item?;
+            if !k.starts_with(&prefix) {
+                break;
+            }
+            if Pending::decode(&v)?.consumer == consumer {
+                t.delete(&k)?;
+                deleted += 1;
+            }
+        }
+        set_type(t, key, KeyType::Stream)?;
+        Ok(deleted)
+    }
+
+    fn read_group(&self, t: &T, group: &[u8], consumer: &[u8], keys: &[(&[u8], Option<StreamId>)], opts: &ReadGroupOptions) -> Result<StreamRead, RrError> {
+        //与redis一样，先检查所有的组，有一个不存在时什么也不做
+        let mut last_delivered = Vec::with_capacity(keys.len());
+        for (key, _) in keys {
+            last_delivered.push(lock_group(t, key, group)?);
+        }
+        let now = now_millis();
+        let mut re = Vec::new();
+        for ((key, id), last) in keys.iter().zip(last_delivered) {
+            touch_consumer(t, key, group, consumer, now)?;
+            let entries = match id {
+                None => deliver_new(t, key, group, consumer, &last, opts, now)?,
+                Some(id) => deliver_pending(t, key, group, consumer, id, opts.count, now)?,
+            };
+            if !entries.is_empty() {
+                set_type(t, key, KeyType::Stream)?;
+            }
+            if id.is_some() || !entries.is_empty() {
+                re.push((key.to_vec(), entries));
+            }
+        }
+        Ok(re)
+    }
+
+    fn ack(&self, t: &T, key: &[u8], group: &[u8], ids: &[StreamId]) -> Result<LenType, RrError> {
+        if !check_type(t, key, KeyType::Stream)? || t.get_for_update(&group_key(key, group))?.is_none() {
+            return Ok(0);
+        }
+        let mut acked = 0;
+        for id in ids {
+            let k = pending_key(key, group, id);
+            if t.get(&k)?.is_some() {
+                t.delete(&k)?;
+                acked += 1;
+            }
+        }
+        if acked > 0 {
+            set_type(t, key, KeyType::Stream)?;
+        }
+        Ok(acked)
+    }
+
+    fn pending(&self, t: &T, key: &[u8], group: &[u8]) -> Result<PendingSummary, RrError> {
+        get_group(t, key, group)?;
+        let prefix = group_prefix(key, PENDING_TAG, group);
+        let mut summary = PendingSummary::default();
+        let mut consumers = BTreeMap::new();
+        for item in t.prefix_iterator(&prefix) {
+            let (k, v) = item?;
+            let id = match k.strip_prefix(prefix.as_slice()) {
+                None => break,
+                Some(id) => StreamId::from_bytes(id)?,
+            };
+            summary.count += 1;
+            summary.min.get_or_insert(id);
+            summary.max = Some(id);
+            *consumers.entry(Pending::decode(&v)?.consumer).or_insert(0) += 1;
+        }
+        summary.consumers = consumers.into_iter().collect();
+        Ok(summary)
+    }
+
+    fn pending_range(&self, t: &T, key: &[u8], group: &[u8], filter: &PendingFilter) -> Result<Vec<PendingEntry>, RrError> {
+        get_group(t, key, group)?;
+        let prefix = group_prefix(key, PENDING_TAG, group);
+        let now = now_millis();
+        let mut re = Vec::new();
+        if filter.count == 0 || filter.start > filter.end {
+            return Ok(re);
+        }
+        for item in t.iterator_from(&[prefix.as_slice(), &filter.start.to_bytes()].concat(), Direction::Forward) {
+            let (k, v) = item?;
+            let id = match k.strip_prefix(prefix.as_slice()) {
+                None => break,
+                Some(id) => StreamId::from_bytes(id)?,
+            };
+            if id > filter.end {
+                break;
+            }
+            let pending = Pending::decode(&v)?;
+            let idle = now - pending.delivery_time;
+            if filter.consumer.as_ref().is_some_and(|c| *c != pending.consumer) || filter.min_idle.is_some_and(|m| idle < m) {
+                continue;
+            }
+            re.push(PendingEntry {
+                id,
+                consumer: pending.consumer,
+                idle,
+                delivery_count: pending.delivery_count,
+            });
+            if re.len() as LenType >= filter.count {
+                break;
+            }
+        }
+        Ok(re)
+    }
+
+    fn claim(&self, t: &T, key: &[u8], group: &[u8], consumer: &[u8], ids: &[StreamId], opts: &ClaimOptions) -> Result<Vec<StreamEntry>, RrError> {
+        lock_group(t, key, group)?;
+        let now = now_millis();
+        let mut re = Vec::new();
+        for id in ids {
+            let k = pending_key(key, group, id);
+            let fields = t.get(&entry_key(key, id))?;
+            let pending = match t.get(&k)? {
+                Some(v) => Some(Pending::decode(&v)?),
+                None if opts.force && fields.is_some() => None,
+                None => continue,
+            };
+            let fields = match fields {
+                None => {
+                    //已被xdel删除
+                    t.delete(&k)?;
+                    continue;
+                }
+                Some(fields) => fields,
+            };
+            if pending.as_ref().is_some_and(|p| now - p.delivery_time < opts.min_idle) {
+                continue;
+            }
+            let old_count = pending.map(|p| p.delivery_count).unwrap_or(0);
+            let pending = Pending {
+                delivery_time: opts.time.unwrap_or(now - opts.idle.unwrap_or(0)),
+                delivery_count: opts.retry_count.unwrap_or(if opts.just_id { old_count } else { old_count + 1 }),
+                consumer: consumer.to_vec(),
+            };
+            t.put(&k, &pending.encode())?;
+            re.push(decode_entry(*id, if opts.just_id { &[] } else { &fields })?);
+        }
+        touch_consumer(t, key, group, consumer, now)?;
+        set_type(t, key, KeyType::Stream)?;
+        Ok(re)
+    }
+
+    fn auto_claim(&self, t: &T, key: &[u8], group: &[u8], consumer: &[u8], start: &StreamId, opts: &AutoClaimOptions) -> Result<AutoClaimed, RrError> {
+        lock_group(t, key, group)?;
+        let now = now_millis();
+        let prefix = group_prefix(key, PENDING_TAG, group);
+        let mut re = AutoClaimed::default();
+        let mut attempts = opts.count;
+        for item in t.iterator_from(&[prefix.as_slice(), &start.to_bytes()].concat(), Direction::Forward) {
+            let (k, v) = item?;
+            let id = match k.strip_prefix(prefix.as_slice()) {
+                None => break,
+                Some(id) => StreamId::from_bytes(id)?,
+            };
+            if attempts == 0 {
+                re.next = id;
+                break;
+            }
+            attempts -= 1;
+            let mut pending = Pending::decode(&v)?;
+            match t.get(&entry_key(key, &id))? {
+                None => {
+                    t.delete(&k)?;
+                    re.deleted.push(id);
+                }
+                Some(fields) if now - pending.delivery_time >= opts.min_idle => {
+                    pending.delivery_time = now;
+                    if !opts.just_id {
+                        pending.delivery_count += 1;
+                    }
+                    pending.consumer = consumer.to_vec();
+                    t.put(&k, &pending.encode())?;
+                    re.entries.push(decode_entry(id, if opts.just_id { &[] } else { &fields })?);
+                }
+                Some(_) => {}
+            }
+        }
+        touch_consumer(t, key, group, consumer, now)?;
+        set_type(t, key, KeyType::Stream)?;
+        Ok(re)
+    }
+
+    fn del_key(&self, t: &T, key: &[u8]) -> Result<(), RrError> {
+        check_type(t, key, KeyType::Stream)?;
+        remove_key_meta(t, key)?;
+        delete_prefix(t, &make_head_key(KeyType::Stream, key))
+    }
+}
+
+impl<D: TransactionalDb> RedisRocksdb<D> {
+    /// 对应redis的xadd，参见[Stream::add]，在自己的事务中执行，提交后唤醒阻塞在key上的[RedisRocksdb::stream_block]
+    pub fn stream_add(&self, key: &[u8], id: &XAddId, fields: &[(&[u8], &[u8])], opts: &StreamAddOptions) -> Result<Option<StreamId>, RrError> {
+        let re = self.in_transaction(|t| StreamImpl {}.add(t, key, id, fields, opts))?;
+        if re.is_some() {
+            self.stream_waiters.notify_all(key);
+        }
+        Ok(re)
+    }
+
+    /// 阻塞的xread/xreadgroup，read返回None时等待keys中有新的项，直到read返回Some或超时（返回None）
+    /// timeout为毫秒，0或者大到超出时间范围时表示一直等待，每一次read在一个新的事务中执行，成功后提交
+    /// 只有[RedisRocksdb::stream_add]与[crate::RedisTransaction::stream_add]在提交后唤醒等待者
+    pub fn stream_block<R, F>(&self, keys: &[&[u8]], timeout: i64, read: F) -> Result<Option<R>, RrError>
+    where
+        F: Fn(&WrapTransaction<'_, D>) -> Result<Option<R>, RrError>,
+    {
+        if timeout < 0 {
+            return Err(RrError::message(format!("timeout is negative: {}", timeout)));
+        }
+        let deadline = if timeout == 0 {
+            None
+        } else {
+            Instant::now().checked_add(Duration::from_millis(timeout as u64))
+        };
+
+        let waiter = Arc::new(Waiter::default());
+        for key in keys {
+            self.stream_waiters.add(key, &waiter);
+        }
+        let result = loop {
+            waiter.reset();
+            match self.in_transaction(&read) {
+                Ok(None) => {}
+                other => break other,
+            }
+            if !waiter.wait(deadline) {
+                break Ok(None);
+            }
+        };
+        for key in keys {
+            self.stream_waiters.remove(key, &waiter);
+        }
+        result
+    }
+}
+
+/// head key中的数据
+#[derive(Default)]
+struct Meta {
+    len: LenType,
+    /// 最后的id，删除后也不会变小，新的id一定大于它
+    last_id: StreamId,
+}
+
+impl Meta {
+    fn decode(v: &[u8]) -> Result<Self, RrError> {
+        if v.len() != BYTES_LEN_TYPE + 16 {
+            return Err(RrError::corruption(&[], "head of stream"));
+        }
+        Ok(Meta {
+            len: read_len_type(v),
+            last_id: StreamId::from_bytes(&v[BYTES_LEN_TYPE..])?,
+        })
+    }
+
+    fn get<T: WrapDb>(t: &T, key: &[u8]) -> Result<Self, RrError> {
+        t.get(&make_head_key(KeyType::Stream, key))?.map_or(Ok(Meta::default()), |v| Meta::decode(&v))
+    }
+
+    /// 锁定head key，这样并发的xadd产生的id不会重复
+    fn get_for_update<T: WrapDb>(t: &T, key: &[u8]) -> Result<Self, RrError> {
+        t.get_for_update(&make_head_key(KeyType::Stream, key))?
+            .map_or(Ok(Meta::default()), |v| Meta::decode(&v))
+    }
+
+    fn put<T: WrapDb>(&self, t: &T, key: &[u8]) -> Result<(), RrError> {
+        let mut v = vec![0u8; BYTES_LEN_TYPE];
+        write_len_type(&mut v, self.len);
+        v.extend_from_slice(&self.last_id.to_bytes());
+        t.put(&make_head_key(KeyType::Stream, key), &v)
+    }
+}
+
+/// pending entries list中的一项
+struct Pending {
+    /// 最后一次投递的unix时间（毫秒）
+    delivery_time: i64,
+    delivery_count: u64,
+    consumer: Vec<u8>,
+}
+
+impl Pending {
+    fn decode(v: &[u8]) -> Result<Self, RrError> {
+        if v.len() < 16 {
+            return Err(RrError::corruption(&[], "pending entry of stream"));
+        }
+        Ok(Pending {
+            delivery_time: i64::from_le_bytes(v[..8].try_into()?),
+            delivery_count: u64::from_le_bytes(v[8..16].try_into()?),
+            consumer: v[16..].to_vec(),
+        })
+    }
+
+    fn encode(&self) -> Vec<u8> {
+        let mut v = Vec::with_capacity(16 + self.consumer.len());
+        v.extend_from_slice(&self.delivery_time.to_le_bytes());
+        v.extend_from_slice(&self.delivery_count.to_le_bytes());
+        v.extend_from_slice(&self.consumer);
+        v
+    }
+}
+
+fn exhausted_error() -> RrError {
+    RrError::message("The stream has exhausted the last possible ID, unable to add more items".to_owned())
+}
+
+fn too_small_error() -> RrError {
+    RrError::message("The ID specified in XADD is equal or smaller than the target stream top item".to_owned())
+}
+
+/// 与redis一样产生新的id，新的id必须大于last
+fn next_id(last: &StreamId, id: &XAddId) -> Result<StreamId, RrError> {
+    let id = match id {
+        XAddId::Auto => {
+            let ms = now_millis().max(0) as u64;
+            if ms > last.ms {
+                StreamId::new(ms, 0)
+            } else {
+                last.next().ok_or_else(exhausted_error)?
+            }
+        }
+        XAddId::AutoSeq(ms) if *ms == last.ms => StreamId::new(*ms, last.seq.checked_add(1).ok_or_else(too_small_error)?),
+        XAddId::AutoSeq(ms) if *ms > last.ms => StreamId::new(*ms, 0),
+        XAddId::AutoSeq(_) => return Err(too_small_error()),
+        XAddId::Explicit(id) => *id,
+    };
+    if id == StreamId::MIN {
+        return Err(RrError::message("The ID specified in XADD must be greater than 0-0".to_owned()));
+    }
+    if id <= *last {
+        return Err(too_small_error());
+    }
+    Ok(id)
+}
+
+/// 从最早的项开始删除，直到满足trim
+fn trim_entries<T: WrapDb>(t: &T, key: &[u8], meta: &mut Meta, trim: &StreamTrim) -> Result<LenType, RrError> {
+    let prefix = make_field_key(KeyType::Stream, key, ENTRY_TAG);
+    let mut trimmed = 0;
+    for item in t.prefix_iterator(&prefix) {
+        let (k, _) = item?;
+        let id = match k.strip_prefix(prefix.as_slice()) {
+            None => break,
+            Some(id) => StreamId::from_bytes(id)?,
+        };
+        let remove = match trim {
+            StreamTrim::MaxLen(max) => meta.len > *max,
+            StreamTrim::MinId(min) => id < *min,
+        };
+        if !remove {
+            break;
+        }
+        t.delete(&k)?;
+        meta.len -= 1;
+        trimmed += 1;
+    }
+    Ok(trimmed)
+}
+
+/// 返回[start, end]中的项，Direction::Reverse时从end开始
+fn get_entries<T: WrapDb>(
+    t: &T,
+    key: &[u8],
+    start: &StreamId,
+    end: &StreamId,
+    count: Option<LenType>,
+    direction: Direction,
+) -> Result<Vec<StreamEntry>, RrError> {
+    let mut re = Vec::new();
+    if start > end || count == Some(0) {
+        return Ok(re);
+    }
+    let prefix = make_field_key(KeyType::Stream, key, ENTRY_TAG);
+    let from = if matches!(direction, Direction::Forward) { start } else { end };
+    for item in t.iterator_from(&[prefix.as_slice(), &from.to_bytes()].concat(), direction) {
+        let (k, v) = item?;
+        let id = match k.strip_prefix(prefix.as_slice()) {
+            None => break,
+            Some(id) => StreamId::from_bytes(id)?,
+        };
+        if id < *start || id > *end {
+            break;
+        }
+        re.push(decode_entry(id, &v)?);
+        if count.is_some_and(|c| re.len() as LenType >= c) {
+            break;
+        }
+    }
+    Ok(re)
+}
+
+/// xreadgroup的 >，投递last之后的项
+fn deliver_new<T: WrapDb>(
+    t: &T,
+    key: &[u8],
+    group: &[u8],
+    consumer: &[u8],
+    last: &StreamId,
+    opts: &ReadGroupOptions,
+    now: i64,
+) -> Result<Vec<StreamEntry>, RrError> {
+    let start = match last.next() {
+        None => return Ok(Vec::new()),
+        Some(start) => start,
+    };
+    let entries = get_entries(t, key, &start, &StreamId::MAX, opts.count, Direction::Forward)?;
+    if let Some(last) = entries.last() {
+        t.put(&group_key(key, group), &last.id.to_bytes())?;
+    }
+    if !opts.no_ack {
+        for entry in &entries {
+            let pending = Pending {
+                delivery_time: now,
+                delivery_count: 1,
+                consumer: consumer.to_vec(),
+            };
+            t.put(&pending_key(key, group, &entry.id), &pending.encode())?;
+        }
+    }
+    Ok(entries)
+}
+
+/// xreadgroup指定id时，再次投递这个消费者id之后的pending项
+fn deliver_pending<T: WrapDb>(
+    t: &T,
+    key: &[u8],
+    group: &[u8],
+    consumer: &[u8],
+    id: &StreamId,
+    count: Option<LenType>,
+    now: i64,
+) -> Result<Vec<StreamEntry>, RrError> {
+    let mut re = Vec::new();
+    let start = match id.next() {
+        None => return Ok(re),
+        Some(start) => start,
+    };
+    if count == Some(0) {
+        return Ok(re);
+    }
+    let prefix = group_prefix(key, PENDING_TAG, group);
+    for item in t.iterator_from(&[prefix.as_slice(), &start.to_bytes()].concat(), Direction::Forward) {
+        let (k, v) = item?;
+        let id = match k.strip_prefix(prefix.as_slice()) {
+            None => break,
+            Some(id) => StreamId::from_bytes(id)?,
+        };
+        let mut pending = Pending::decode(&v)?;
+        if pending.consumer != consumer {
+            continue;
+        }
+        pending.delivery_time = now;
+        pending.delivery_count += 1;
+        t.put(&k, &pending.encode())?;
+        //已被xdel删除的项没有fields
+        let v = t.get(&entry_key(key, &id))?.unwrap_or_default();
+        re.push(decode_entry(id, &v)?);
+        if count.is_some_and(|c| re.len() as LenType >= c) {
+            break;
+        }
+    }
+    Ok(re)
+}
+
+fn no_group_error(key: &[u8], group: &[u8]) -> RrError {
+    RrError::message(format!(
+        "NOGROUP No such key '{}' or consumer group '{}'",
+        String::from_utf8_lossy(key),
+        String::from_utf8_lossy(group)
+    ))
+}
+
+/// 返回组最后投递的id，key或组不存在时返回NOGROUP错误
+fn get_group<T: WrapDb>(t: &T, key: &[u8], group: &[u8]) -> Result<StreamId, RrError> {
+    if !check_type(t, key, KeyType::Stream)? {
+        return Err(no_group_error(key, group));
+    }
+    let v = t.get(&group_key(key, group))?.ok_or_else(|| no_group_error(key, group))?;
+    StreamId::from_bytes(&v)
+}
+
+/// 与[get_group]一样，并锁定组，修改组之前调用
+fn lock_group<T: WrapDb>(t: &T, key: &[u8], group: &[u8]) -> Result<StreamId, RrError> {
+    if !check_type(t, key, KeyType::Stream)? {
+        return Err(no_group_error(key, group));
+    }
+    let v = t.get_for_update(&group_key(key, group))?.ok_or_else(|| no_group_error(key, group))?;
+    StreamId::from_bytes(&v)
+}
+
+/// 创建消费者或更新它最后活动的时间
+fn touch_consumer<T: WrapDb>(t: &T, key: &[u8], group: &[u8], consumer: &[u8], now: i64) -> Result<(), RrError> {
+    t.put(&consumer_key(key, group, consumer), &now.to_le_bytes())
+}
+
+fn entry_key(key: &[u8], id: &StreamId) -> Vec<u8> {
+    let mut k = make_field_key(KeyType::Stream, key, ENTRY_TAG);
+    k.extend_from_slice(&id.to_bytes());
+    k
+}
+
+fn group_key(key: &[u8], group: &[u8]) -> Vec<u8> {
+    let mut k = make_field_key(KeyType::Stream, key, GROUP_TAG);
+    k.extend_from_slice(group);
+    k
+}
+
+fn group_prefix(key: &[u8], tag: &[u8], group: &[u8]) -> Vec<u8> {
+    let mut k = make_field_key(KeyType::Stream, key, tag);
+    k.extend_from_slice(&(group.len() as u32).to_be_bytes());
+    k.extend_from_slice(group);
+    k
+}
+
+fn consumer_key(key: &[u8], group: &[u8], consumer: &[u8]) -> Vec<u8> {
+    let mut k = group_prefix(key, CONSUMER_TAG, group);
+    k.extend_from_slice(consumer);
+    k
+}
+
+fn pending_key(key: &[u8], group: &[u8], id: &StreamId) -> Vec<u8> {
+    let mut k = group_prefix(key, PENDING_TAG, group);
+    k.extend_from_slice(&id.to_bytes());
+    k
+}
+
+/// 每一个field与value: 长度（u32 LE） + bytes
+fn encode_fields(fields: &[(&[u8], &[u8])]) -> Vec<u8> {
+    let mut v = Vec::new();
+    for (field, value) in fields {
+        for bytes in [field, value] {
+            v.extend_from_slice(&(bytes.len() as u32).to_le_bytes());
+            v.extend_from_slice(bytes);
+        }
+    }
+    v
+}
+
+fn decode_entry(id: StreamId, mut v: &[u8]) -> Result<StreamEntry, RrError> {
+    let mut entry = StreamEntry { id, fields: Vec::new() };
+    while !v.is_empty() {
+        entry.fields.push((take_bytes(&mut v)?, take_bytes(&mut v)?));
+    }
+    Ok(entry)
+}
+
+fn take_bytes(v: &mut &[u8]) -> Result<Vec<u8>, RrError> {
+    let error = || RrError::corruption(&[], "fields of stream entry");
+    let len = u32::from_le_bytes(v.get(..4).ok_or_else(error)?.try_into()?) as usize;
+    let bytes = v.get(4..4 + len).ok_or_else(error)?.to_vec();
+    *v = &v[4 + len..];
+    Ok(bytes)
+}
@@ -0,0 +1,95 @@
+use rocksdb::{
+    ColumnFamily, DBAccess, Error, IteratorMode, OptimisticTransactionDB, ReadOptions, Transaction, TransactionDB, WriteBatchWithTransaction,
+    DEFAULT_COLUMN_FAMILY_NAME,
+};
+
+use crate::WrapIterator;
+
+/// [crate::RedisRocksdb]可以使用的带事务的数据库，悲观事务的[TransactionDB]与乐观事务的[OptimisticTransactionDB]
+/// 只包含不在事务中时用到的方法，cf为None时为default
+/// 乐观事务在提交时才检查冲突，冲突时返回[crate::RrError::TransactionConflict]，参见[crate::RedisRocksdb::in_transaction]
+pub trait TransactionalDb: DBAccess + Send + Sync + Sized + 'static {
+    fn transaction(&self) -> Transaction<'_, Self>;
+    fn cf_handle(&self, name: &str) -> Option<&ColumnFamily>;
+    fn get_in(&self, cf: Option<&ColumnFamily>, key: &[u8]) -> Result<Option<Vec<u8>>, Error>;
+    fn put_in(&self, cf: Option<&ColumnFamily>, key: &[u8], value: &[u8]) -> Result<(), Error>;
+    fn delete_in(&self, cf: Option<&ColumnFamily>, key: &[u8]) -> Result<(), Error>;
+    fn prefix_iterator_in<'a>(&'a self, cf: Option<&ColumnFamily>, prefix: &[u8]) -> WrapIterator<'a>;
+    fn iterator_in<'a>(&'a self, cf: Option<&ColumnFamily>, opts: ReadOptions, mode: IteratorMode) -> WrapIterator<'a>;
+    fn write(&self, batch: WriteBatchWithTransaction<true>) -> Result<(), Error>;
+    /// 使用DeleteRange删除[from, to)的key，不支持时返回false，由调用者遍历删除
+    /// [TransactionDB]不支持DeleteRange；[OptimisticTransactionDB]只能按column family删除，打开时没有default的handle时也不支持
+    fn delete_range_in(&self, _cf: Option<&ColumnFamily>, _from: &[u8], _to: &[u8]) -> Result<bool, Error> {
+        Ok(false)
+    }
+}
+
+/// 两种数据库的方法名字与参数都相同，只是没有共同的trait
+macro_rules! impl_transactional_db {
+    ($db:ty $(, $extra:item)*) => {
+        impl TransactionalDb for $db {
+            fn transaction(&self) -> Transaction<'_, Self> {
+                <$db>::transaction(self)
+            }
+
+            fn cf_handle(&self, name: &str) -> Option<&ColumnFamily> {
+                <$db>::cf_handle(self, name)
+            }
+
+            fn get_in(&self, cf: Option<&ColumnFamily>, key: &[u8]) -> Result<Option<Vec<u8>>, Error> {
+                match cf {
+                    Some(cf) => self.get_cf(cf, key),
+                    None => self.get(key),
+                }
+            }
+
+            fn put_in(&self, cf: Option<&ColumnFamily>, key: &[u8], value: &[u8]) -> Result<(), Error> {
+                match cf {
+                    Some(cf) => self.put_cf(cf, key, value),
+                    None => self.put(key, value),
+                }
+            }
+
+            fn delete_in(&self, cf: Option<&ColumnFamily>, key: &[u8]) -> Result<(), Error> {
+                match cf {
+                    Some(cf) => self.delete_cf(cf, key),
+                    None => self.delete(key),
+                }
+            }
+
+            fn prefix_iterator_in<'a>(&'a self, cf: Option<&ColumnFamily>, prefix: &[u8]) -> WrapIterator<'a> {
+                match cf {
+                    Some(cf) => Box::new(self.prefix_iterator_cf(cf, prefix)),
+                    None => Box::new(self.prefix_iterator(prefix)),
+                }
+            }
+
+            fn iterator_in<'a>(&'a self, cf: Option<&ColumnFamily>, opts: ReadOptions, mode: IteratorMode) -> WrapIterator<'a> {
+                match cf {
+                    Some(cf) => Box::new(self.iterator_cf_opt(cf, opts, mode)),
+                    None => Box::new(self.iterator_opt(mode, opts)),
+                }
+            }
+
+            fn write(&self, batch: WriteBatchWithTransaction<true>) -> Result<(), Error> {
+                <$db>::write(self, batch)
+            }
+
+            $($extra)*
+        }
+    };
+}
+
+impl_transactional_db!(TransactionDB);
+impl_transactional_db!(
+    OptimisticTransactionDB,
+    fn delete_range_in(&self, cf: Option<&ColumnFamily>, from: &[u8], to: &[u8]) -> Result<bool, Error> {
+        match cf.or_else(|| self.cf_handle(DEFAULT_COLUMN_FAMILY_NAME)) {
+            Some(cf) => {
+                self.delete_range_cf(cf, from, to)?;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+);
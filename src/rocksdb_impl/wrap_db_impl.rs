@@ -1,72 +1,214 @@
-use rocksdb::{DBIteratorWithThreadMode, Transaction, TransactionDB};
+use rocksdb::{ColumnFamily, Direction, IteratorMode, OptimisticTransactionDB, ReadOptions, Transaction, TransactionDB};
+
+use crate::{
+    rocksdb_impl::{
+        pub_sub_impl::ExpiredNotify,
+        shared::{namespace_key, namespace_prefix, TAG_NAMESPACE},
+    },
+    ColumnFamilies, RrError, TransactionalDb, WrapDb, WrapIterator,
+};
+
+/// 字段不是pub的，使用[WrapTransactionDB::new]，或者[crate::RedisRocksdb::wrap_db]，它会带上数据库的column family与namespace
+/// 以前的 `WrapTransactionDB { db }` 要改为 `WrapTransactionDB::new(db)`，参见README的Upgrade
+pub struct WrapTransactionDB<'a, D = TransactionDB> {
+    pub(crate) db: &'a D,
+    pub(crate) cfs: ColumnFamilies<'a>,
+    /// 0号之外的namespace，key加上namespace的前缀后存放，参见[crate::RedisRocksdb::select]
+    pub(crate) namespace: u32,
+    /// 惰性删除过期key时的通知，只有[crate::RedisRocksdb::wrap_db]设置
+    pub(crate) expired: Option<ExpiredNotify<'a>>,
+}
 
-use crate::{RrError, WrapDb};
+/// 乐观事务的数据库，参见[crate::RedisRocksdbBuilder::build_optimistic]
+pub type WrapOptimisticTransactionDB<'a> = WrapTransactionDB<'a, OptimisticTransactionDB>;
 
-pub struct WrapTransactionDB<'a> {
-    pub db: &'a TransactionDB,
+impl<'a, D: TransactionalDb> WrapTransactionDB<'a, D> {
+    /// 所有的数据都在default与0号namespace中，使用column family或namespace时用[crate::RedisRocksdb::wrap_db]
+    pub fn new(db: &'a D) -> Self {
+        WrapTransactionDB {
+            db,
+            cfs: ColumnFamilies::default(),
+            namespace: 0,
+            expired: None,
+        }
+    }
 }
 
-impl<'a> WrapDb for WrapTransactionDB<'a> {
-    type Db = TransactionDB;
+impl<'a, D: TransactionalDb> WrapDb for WrapTransactionDB<'a, D> {
+    type Db = D;
 
     fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>, RrError> {
-        Ok(self.db.get(key)?)
+        Ok(self.db.get_in(self.column_family(key), &namespace_key(self.namespace, key))?)
+    }
+
+    fn get_for_update(&self, key: &[u8]) -> Result<Option<Vec<u8>>, RrError> {
+        self.get(key)
     }
 
     fn put(&self, key: &[u8], value: &[u8]) -> Result<(), RrError> {
-        Ok(self.db.put(key, value)?)
+        Ok(self.db.put_in(self.column_family(key), &namespace_key(self.namespace, key), value)?)
     }
 
     fn delete(&self, key: &[u8]) -> Result<(), RrError> {
-        Ok(self.db.delete(key)?)
+        Ok(self.db.delete_in(self.column_family(key), &namespace_key(self.namespace, key))?)
     }
 
     /// 由于[rocksdb::TransactionDB]没有[rocksdb::DB::key_may_exist]方法，所以只能取一次key value
     fn exist(&self, key: &[u8]) -> Result<bool, RrError> {
-        Ok(self.db.get(key)?.is_some())
+        Ok(self.get(key)?.is_some())
     }
 
     fn get_db(&self) -> &Self::Db {
         self.db
     }
 
-    fn prefix_iterator<'b: 'c, 'c>(&'b self, prefix: &[u8]) -> DBIteratorWithThreadMode<'c, Self::Db> {
-        self.db.prefix_iterator(prefix)
+    fn prefix_iterator<'b: 'c, 'c>(&'b self, prefix: &[u8]) -> WrapIterator<'c> {
+        let it = self.db.prefix_iterator_in(self.column_family(prefix), &namespace_key(self.namespace, prefix));
+        in_namespace(it, self.namespace)
+    }
+
+    fn column_family(&self, key: &[u8]) -> Option<&ColumnFamily> {
+        self.cfs.get(key)
+    }
+
+    fn namespace(&self) -> u32 {
+        self.namespace
     }
+
+    fn iterator_from<'b: 'c, 'c>(&'b self, from: &[u8], direction: Direction) -> WrapIterator<'c> {
+        let physical = namespace_key(self.namespace, from);
+        let mode = IteratorMode::From(&physical, direction);
+        let it = self.db.iterator_in(self.column_family(from), total_order_read(), mode);
+        in_namespace(it, self.namespace)
+    }
+
+    fn expired(&self, key: &[u8]) {
+        if let Some(notify) = &self.expired {
+            notify.send(key);
+        }
+    }
+}
+
+/// 与[WrapTransactionDB]一样，使用[WrapTransaction::new]或者[crate::RedisRocksdb::wrap_transaction]
+pub struct WrapTransaction<'a, D = TransactionDB> {
+    pub(crate) db: &'a Transaction<'a, D>,
+    pub(crate) cfs: ColumnFamilies<'a>,
+    pub(crate) namespace: u32,
+    /// 惰性删除过期key时的通知，[crate::RedisRocksdb::in_transaction]与[crate::RedisTransaction::wrap]在提交之后发送
+    pub(crate) expired: Option<ExpiredNotify<'a>>,
 }
 
-pub struct WrapTransaction<'a> {
-    pub db: &'a Transaction<'a, TransactionDB>,
+/// 乐观事务，提交时才检查冲突，get_for_update不加锁，只让提交时检查这个key，参见[crate::RedisRocksdb::in_transaction]
+pub type WrapOptimisticTransaction<'a> = WrapTransaction<'a, OptimisticTransactionDB>;
+
+impl<'a, D> WrapTransaction<'a, D> {
+    /// 所有的数据都在default与0号namespace中，使用column family或namespace时用[crate::RedisRocksdb::wrap_transaction]
+    pub fn new(db: &'a Transaction<'a, D>) -> Self {
+        WrapTransaction {
+            db,
+            cfs: ColumnFamilies::default(),
+            namespace: 0,
+            expired: None,
+        }
+    }
 }
 
-impl<'a> WrapDb for WrapTransaction<'a> {
-    type Db = Transaction<'a, TransactionDB>;
+impl<'a, D> WrapDb for WrapTransaction<'a, D> {
+    type Db = Transaction<'a, D>;
 
     fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>, RrError> {
-        Ok(self.db.get(key)?)
+        match self.column_family(key) {
+            Some(cf) => Ok(self.db.get_cf(cf, namespace_key(self.namespace, key))?),
+            None => Ok(self.db.get(namespace_key(self.namespace, key))?),
+        }
+    }
+
+    fn get_for_update(&self, key: &[u8]) -> Result<Option<Vec<u8>>, RrError> {
+        match self.column_family(key) {
+            Some(cf) => Ok(self.db.get_for_update_cf(cf, namespace_key(self.namespace, key), true)?),
+            None => Ok(self.db.get_for_update(namespace_key(self.namespace, key), true)?),
+        }
     }
 
     fn put(&self, key: &[u8], value: &[u8]) -> Result<(), RrError> {
-        Ok(self.db.put(key, value)?)
+        match self.column_family(key) {
+            Some(cf) => Ok(self.db.put_cf(cf, namespace_key(self.namespace, key), value)?),
+            None => Ok(self.db.put(namespace_key(self.namespace, key), value)?),
+        }
     }
 
     fn delete(&self, key: &[u8]) -> Result<(), RrError> {
-        Ok(self.db.delete(key)?)
+        match self.column_family(key) {
+            Some(cf) => Ok(self.db.delete_cf(cf, namespace_key(self.namespace, key))?),
+            None => Ok(self.db.delete(namespace_key(self.namespace, key))?),
+        }
     }
 
     /// 由于[rocksdb::TransactionDB]没有[rocksdb::DB::key_may_exist]方法，所以只能取一次key value
     fn exist(&self, key: &[u8]) -> Result<bool, RrError> {
-        Ok(self.db.get(key)?.is_some())
+        Ok(self.get(key)?.is_some())
     }
     fn get_db(&self) -> &Self::Db {
         self.db
     }
 
-    fn prefix_iterator<'b: 'c, 'c>(&'b self, prefix: &[u8]) -> DBIteratorWithThreadMode<'c, Self::Db> {
-        self.db.prefix_iterator(prefix)
+    fn prefix_iterator<'b: 'c, 'c>(&'b self, prefix: &[u8]) -> WrapIterator<'c> {
+        let physical = namespace_key(self.namespace, prefix);
+        let it = match self.column_family(prefix) {
+            Some(cf) => self.db.prefix_iterator_cf(cf, physical),
+            None => self.db.prefix_iterator(physical),
+        };
+        in_namespace(it, self.namespace)
+    }
+
+    fn column_family(&self, key: &[u8]) -> Option<&ColumnFamily> {
+        self.cfs.get(key)
+    }
+
+    fn namespace(&self) -> u32 {
+        self.namespace
+    }
+
+    fn iterator_from<'b: 'c, 'c>(&'b self, from: &[u8], direction: Direction) -> WrapIterator<'c> {
+        let physical = namespace_key(self.namespace, from);
+        let it = match self.column_family(from) {
+            Some(cf) => self.db.iterator_cf_opt(cf, total_order_read(), IteratorMode::From(&physical, direction)),
+            None => self.db.iterator_opt(IteratorMode::From(&physical, direction), total_order_read()),
+        };
+        in_namespace(it, self.namespace)
+    }
+
+    fn expired(&self, key: &[u8]) {
+        if let Some(notify) = &self.expired {
+            notify.send(key);
+        }
     }
 }
 
+/// 遍历到其它的namespace时停止，并去掉key中namespace的前缀
+fn in_namespace<'a, I>(it: I, namespace: u32) -> WrapIterator<'a>
+where
+    I: Iterator<Item = Result<(Box<[u8]>, Box<[u8]>), rocksdb::Error>> + 'a,
+{
+    if namespace == 0 {
+        return Box::new(it.take_while(|item| !matches!(item, Ok((k, _)) if k.first() == Some(&TAG_NAMESPACE))));
+    }
+    let prefix = namespace_prefix(namespace);
+    Box::new(it.map_while(move |item| match item {
+        Err(e) => Some(Err(e)),
+        Ok((k, v)) => k.strip_prefix(&prefix[..]).map(|k| Ok((k.into(), v))),
+    }))
+}
+
+/// 设置了prefix extractor时（参见[crate::RedisRocksdbBuilder]），iterator_from也要能越过prefix
+pub(crate) fn total_order_read() -> ReadOptions {
+    let mut opts = ReadOptions::default();
+    opts.set_total_order_seek(true);
+    opts
+}
+
+/// 没有事务的[rocksdb::DB]，所有的数据都在default column family与0号namespace中
+/// 不支持[crate::ColumnFamilies]的布局，不能用于[crate::ColumnFamilies::open]打开的数据库
 pub struct WrapRocksDb<'a> {
     pub db: &'a rocksdb::DB,
 }
@@ -78,6 +220,10 @@ impl<'a> WrapDb for WrapRocksDb<'a> {
         Ok(self.db.get(key)?)
     }
 
+    fn get_for_update(&self, key: &[u8]) -> Result<Option<Vec<u8>>, RrError> {
+        Ok(self.db.get(key)?)
+    }
+
     fn put(&self, key: &[u8], value: &[u8]) -> Result<(), RrError> {
         Ok(self.db.put(key, value)?)
     }
@@ -94,7 +240,11 @@ impl<'a> WrapDb for WrapRocksDb<'a> {
         self.db
     }
 
-    fn prefix_iterator<'b: 'c, 'c>(&'b self, prefix: &[u8]) -> DBIteratorWithThreadMode<'c, Self::Db> {
-        self.db.prefix_iterator(prefix)
+    fn prefix_iterator<'b: 'c, 'c>(&'b self, prefix: &[u8]) -> WrapIterator<'c> {
+        Box::new(self.db.prefix_iterator(prefix))
+    }
+
+    fn iterator_from<'b: 'c, 'c>(&'b self, from: &[u8], direction: Direction) -> WrapIterator<'c> {
+        Box::new(self.db.iterator_opt(IteratorMode::From(from, direction), total_order_read()))
     }
 }
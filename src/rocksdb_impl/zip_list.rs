@@ -1,8 +1,6 @@
 use core::ptr;
 
-use rocksdb::{Transaction, TransactionDB};
-
-use crate::{read_int, write_int, EndianScalar, LenType, RrError, BYTES_LEN_TYPE};
+use crate::{read_int, write_int, EndianScalar, LenType, RrError, WrapDb, BYTES_LEN_TYPE};
 
 /// Sample
 /// ```rust
@@ -76,10 +74,6 @@ impl<'a> ZipListNode<'a> {
         self.2
     }
 
-    fn bytes_of_value(&self) -> usize {
-        self.2 - ZipListNode::SIZE_NODE_TYPE * 2
-    }
-
     fn offset(&self) -> usize {
         self.1
     }
@@ -120,12 +114,8 @@ impl ZipList {
         ZipList(Vec::from([0; ZipList::LEN_INIT]))
     }
 
-    pub(crate) fn get(tr: &Transaction<TransactionDB>, key: &[u8]) -> Result<Option<ZipList>, RrError> {
-        let v = tr.get(key)?;
-        match v {
-            None => Ok(None),
-            Some(v) => Ok(Some(ZipList::from(v.to_vec()))),
-        }
+    pub(crate) fn get<T: WrapDb>(t: &T, key: &[u8]) -> Result<Option<ZipList>, RrError> {
+        Ok(t.get(key)?.map(ZipList::from))
     }
 
     pub fn len(&self) -> LenType {
@@ -280,36 +270,12 @@ impl ZipList {
             // }
             // let node = node.expect("");
             let old_value = node.value().to_vec();
-            let old_bytes_value = node.bytes_of_value();
             let old_bytes_node = node.bytes_of_node();
 
-            let mut p = unsafe { self.0.as_mut_ptr().add(offset) };
-            //这里一定要使用isize,因为可能为负数
-            let diff: isize = value.len() as isize - (old_bytes_value as isize);
-            if diff == 0 {
-                //这种情况下 不需要移动任何数据，因为大小是一样的
-            } else if diff > 0 {
-                unsafe {
-                    self.0.reserve(diff as usize);
-                    self.0.set_len(self.0.len() + diff as usize);
-                    //重新计算p的位置，当vec大小变化后，内存可能会变化
-                    p = self.0.as_mut_ptr().add(offset);
-                    let count = self.0.len() - diff as usize - offset - old_bytes_node;
-                    if count > 0 {
-                        ptr::copy(p.add(old_bytes_node), p.offset(old_bytes_node as isize + diff), count);
-                    }
-                }
-            } else if diff < 0 {
-                unsafe {
-                    ptr::copy(p.add(old_bytes_node), p.offset(old_bytes_node as isize - diff.abs()), self.0.len() - offset);
-                }
-                self.0.truncate(self.0.len() - diff.unsigned_abs());
-                unsafe {
-                    p = self.0.as_mut_ptr().add(offset);
-                }
-            }
-
-            ZipListNode::write_value(value, p);
+            //大小可能不一样，所以替换整个node
+            let mut new_node = vec![0u8; ZipListNode::count_bytes(value)];
+            ZipListNode::write_value(value, new_node.as_mut_ptr());
+            self.0.splice(offset..offset + old_bytes_node, new_node);
             Some(old_value)
         } else {
             None
@@ -443,40 +409,6 @@ impl ZipList {
         result
     }
 
-    pub fn count_index(len: i32, index: i32) -> i32 {
-        let result_index = {
-            if index < 0 {
-                let mut index_ = len + index;
-                if index_ < 0 {
-                    index_ = 0;
-                }
-                index_
-            } else if index >= len {
-                len - 1
-            } else {
-                index
-            }
-        };
-        result_index
-    }
-
-    /// 返回值 (start_in_index, stop_in_index)
-    pub fn count_in_index(len: LenType, offset: usize, start_index: usize, stop_index: usize) -> Option<(usize, usize)> {
-        let len = len as usize;
-        let mut stop_in_index: usize;
-
-        if start_index >= len + offset || stop_index < offset {
-            return None;
-        }
-
-        let start_in_index = start_index - offset;
-        stop_in_index = start_in_index + (stop_index - start_index);
-        if stop_in_index >= len {
-            stop_in_index = len - 1;
-        }
-        Some((start_in_index, stop_in_index))
-    }
-
     fn insert_offset(&mut self, offset: usize, value: &[u8]) {
         let old_bytes = self.0.len();
         let add_bytes = ZipListNode::count_bytes(value);
@@ -526,7 +458,7 @@ impl<'a> ZipListIter<'a> {
 
     fn next_offset(&self) -> Option<usize> {
         if self.start_cur < self.zip_list.len() {
-            let len_value = ZipListNode::read_bytes_of_value(self.zip_list);
+            let len_value = ZipListNode::read_bytes_of_value(&self.zip_list[self.start_cur..]);
             let mut cur = self.start_cur;
             cur += len_value + ZipListNode::SIZE_NODE_TYPE * 2;
             Some(cur + ZipList::OFFSET_VALUE)
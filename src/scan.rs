@@ -0,0 +1,166 @@
+use crate::{KeyType, LenType, RrError};
+
+/// 可以恢复的游标，对应redis scan返回的cursor
+/// 内容是上一批最后遍历到的元素（key、field或member），下一次从它之后开始，所以遍历过程中增加或删除元素不会使游标失效
+/// 空的游标表示开始，返回空的游标表示遍历完成，与redis的游标0一样
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ScanCursor(Vec<u8>);
+
+/// 非空游标的第一个byte，这样空的元素也可以作为游标
+const CURSOR_TAG: u8 = b'>';
+
+impl ScanCursor {
+    pub fn start() -> Self {
+        ScanCursor(Vec::new())
+    }
+
+    /// 从element之后开始的游标
+    pub(crate) fn after(element: &[u8]) -> Self {
+        let mut data = Vec::with_capacity(element.len() + 1);
+        data.push(CURSOR_TAG);
+        data.extend_from_slice(element);
+        ScanCursor(data)
+    }
+
+    /// true: 开始的游标或遍历已经完成
+    pub fn is_finished(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// 上一批最后遍历到的元素，开始时返回None
+    pub(crate) fn last(&self) -> Option<&[u8]> {
+        self.0.split_first().map(|(_, element)| element)
+    }
+
+    /// 用于传输给客户端，使用[ScanCursor::try_from]还原
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl TryFrom<&[u8]> for ScanCursor {
+    type Error = RrError;
+
+    /// 接受redis的 "0" 作为开始的游标
+    fn try_from(data: &[u8]) -> Result<Self, Self::Error> {
+        match data.first() {
+            None => Ok(ScanCursor::start()),
+            Some(&CURSOR_TAG) => Ok(ScanCursor(data.to_vec())),
+            Some(_) if data == b"0" => Ok(ScanCursor::start()),
+            Some(_) => Err(RrError::message("invalid cursor".to_owned())),
+        }
+    }
+}
+
+/// scan返回的一批元素，cursor为下一批的游标
+#[derive(Clone, Debug, Default)]
+pub struct ScanBatch<E> {
+    pub cursor: ScanCursor,
+    pub items: Vec<E>,
+}
+
+/// 对应redis scan的 MATCH、COUNT与TYPE
+#[derive(Clone, Debug)]
+pub struct ScanOptions {
+    /// glob格式，支持 * ? [abc] [^a-z] 与 \ 转义，None表示所有
+    pub pattern: Option<Vec<u8>>,
+    /// 每一批最多遍历的元素个数，与redis一样是遍历的个数，不是返回的个数，过滤后返回的可能更少，甚至为空
+    pub count: LenType,
+    /// 只对[crate::KeySpace::scan]有效
+    pub key_type: Option<KeyType>,
+}
+
+impl Default for ScanOptions {
+    fn default() -> Self {
+        ScanOptions {
+            pattern: None,
+            count: 10,
+            key_type: None,
+        }
+    }
+}
+
+impl ScanOptions {
+    pub fn is_match(&self, element: &[u8]) -> bool {
+        match &self.pattern {
+            None => true,
+            Some(p) => glob_match(p, element),
+        }
+    }
+}
+
+/// 与redis的stringmatchlen一样的glob匹配
+pub fn glob_match(pattern: &[u8], s: &[u8]) -> bool {
+    let (mut p, mut i) = (0, 0);
+    while p < pattern.len() {
+        match pattern[p] {
+            b'*' => {
+                while p + 1 < pattern.len() && pattern[p + 1] == b'*' {
+                    p += 1;
+                }
+                if p + 1 == pattern.len() {
+                    return true;
+                }
+                return (i..=s.len()).any(|start| glob_match(&pattern[p + 1..], &s[start..]));
+            }
+            b'?' => {
+                if i >= s.len() {
+                    return false;
+                }
+                i += 1;
+            }
+            b'[' => {
+                if i >= s.len() {
+                    return false;
+                }
+                let (matched, end) = match_class(pattern, p + 1, s[i]);
+                if !matched {
+                    return false;
+                }
+                p = end;
+                i += 1;
+            }
+            c => {
+                let c = if c == b'\\' && p + 1 < pattern.len() {
+                    p += 1;
+                    pattern[p]
+                } else {
+                    c
+                };
+                if i >= s.len() || s[i] != c {
+                    return false;
+                }
+                i += 1;
+            }
+        }
+        p += 1;
+    }
+    i == s.len()
+}
+
+/// 匹配 [...]，p是 '[' 之后的位置，返回是否匹配与 ']' 的位置，没有 ']' 时到pattern的结尾
+fn match_class(pattern: &[u8], mut p: usize, c: u8) -> (bool, usize) {
+    let not = p < pattern.len() && (pattern[p] == b'^' || pattern[p] == b'!');
+    if not {
+        p += 1;
+    }
+    let mut matched = false;
+    while p < pattern.len() && pattern[p] != b']' {
+        if pattern[p] == b'\\' && p + 1 < pattern.len() {
+            p += 1;
+            matched |= pattern[p] == c;
+        } else if p + 2 < pattern.len() && pattern[p + 1] == b'-' && pattern[p + 2] != b']' {
+            let (start, end) = if pattern[p] <= pattern[p + 2] {
+                (pattern[p], pattern[p + 2])
+            } else {
+                (pattern[p + 2], pattern[p])
+            };
+            matched |= start <= c && c <= end;
+            p += 2;
+        } else {
+            matched |= pattern[p] == c;
+        }
+        p += 1;
+    }
+    (matched != not, p.min(pattern.len() - 1))
+}
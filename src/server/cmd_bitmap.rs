@@ -0,0 +1,210 @@
+use crate::{
+    resp::Frame,
+    server::{
+        command::{eq_ignore_case, integer, parse_i64, syntax_error, Command},
+        Connection,
+    },
+    BitFieldOp, BitFieldType, BitOp, BitOverflow, BitRange, BitUnit, Bitmap, RedisRocksdb, RedisTransaction, RrError,
+};
+
+/// 位图使用[crate::BitmapImpl]
+pub(crate) const COMMANDS: &[Command] = &[
+    Command {
+        name: "setbit",
+        arity: 4,
+        handler: setbit,
+    },
+    Command {
+        name: "getbit",
+        arity: 3,
+        handler: getbit,
+    },
+    Command {
+        name: "bitcount",
+        arity: -2,
+        handler: bitcount,
+    },
+    Command {
+        name: "bitpos",
+        arity: -3,
+        handler: bitpos,
+    },
+    Command {
+        name: "bitop",
+        arity: -4,
+        handler: bitop,
+    },
+    Command {
+        name: "bitfield",
+        arity: -2,
+        handler: bitfield,
+    },
+    Command {
+        name: "bitfield_ro",
+        arity: -2,
+        handler: bitfield_ro,
+    },
+];
+
+fn offset_error() -> RrError {
+    RrError::message("bit offset is not an integer or out of range".to_owned())
+}
+
+fn parse_offset(arg: &[u8]) -> Result<u64, RrError> {
+    u64::try_from(parse_i64(arg).map_err(|_| offset_error())?).map_err(|_| offset_error())
+}
+
+/// 只接受0与1
+fn parse_bit(arg: &[u8], error: &str) -> Result<bool, RrError> {
+    match arg {
+        b"0" => Ok(false),
+        b"1" => Ok(true),
+        _ => Err(RrError::message(error.to_owned())),
+    }
+}
+
+/// [start end [BYTE | BIT]]，bitpos可以没有end
+fn parse_range(args: &[&[u8]], end_required: bool) -> Result<Option<BitRange>, RrError> {
+    let (start, end, unit) = match args {
+        [] => return Ok(None),
+        [start] if !end_required => (start, None, None),
+        [start, end] => (start, Some(end), None),
+        [start, end, unit] => (start, Some(end), Some(unit)),
+        _ => return Err(syntax_error()),
+    };
+    let unit = match unit {
+        None => BitUnit::Byte,
+        Some(u) if eq_ignore_case(u, "byte") => BitUnit::Byte,
+        Some(u) if eq_ignore_case(u, "bit") => BitUnit::Bit,
+        Some(_) => return Err(syntax_error()),
+    };
+    Ok(Some(BitRange {
+        start: parse_i64(start)?,
+        end: end.map(|e| parse_i64(e)).transpose()?,
+        unit,
+    }))
+}
+
+fn setbit(_: &mut Connection, tx: &RedisTransaction<'_>, args: &[&[u8]]) -> Result<Frame<'static>, RrError> {
+    let offset = parse_offset(args[1])?;
+    let value = parse_bit(args[2], "bit is not an integer or out of range")?;
+    let old = RedisRocksdb::bitmap().set_bit(&tx.wrap(), args[0], offset, value)?;
+    Ok(integer(old))
+}
+
+fn getbit(_: &mut Connection, tx: &RedisTransaction<'_>, args: &[&[u8]]) -> Result<Frame<'static>, RrError> {
+    let bit = RedisRocksdb::bitmap().get_bit(&tx.wrap(), args[0], parse_offset(args[1])?)?;
+    Ok(integer(bit))
+}
+
+/// BITCOUNT key [start end [BYTE | BIT]]
+fn bitcount(_: &mut Connection, tx: &RedisTransaction<'_>, args: &[&[u8]]) -> Result<Frame<'static>, RrError> {
+    let range = parse_range(&args[1..], true)?;
+    let count = RedisRocksdb::bitmap().bit_count(&tx.wrap(), args[0], range.as_ref())?;
+    Ok(integer(count as i64))
+}
+
+/// BITPOS key bit [start [end [BYTE | BIT]]]
+fn bitpos(_: &mut Connection, tx: &RedisTransaction<'_>, args: &[&[u8]]) -> Result<Frame<'static>, RrError> {
+    let bit = parse_bit(args[1], "The bit argument must be 1 or 0.")?;
+    let range = parse_range(&args[2..], false)?;
+    Ok(integer(RedisRocksdb::bitmap().bit_pos(&tx.wrap(), args[0], bit, range.as_ref())?))
+}
+
+/// BITOP AND | OR | XOR | NOT destkey key [key ...]
+fn bitop(_: &mut Connection, tx: &RedisTransaction<'_>, args: &[&[u8]]) -> Result<Frame<'static>, RrError> {
+    let op = if eq_ignore_case(args[0], "and") {
+        BitOp::And
+    } else if eq_ignore_case(args[0], "or") {
+        BitOp::Or
+    } else if eq_ignore_case(args[0], "xor") {
+        BitOp::Xor
+    } else if eq_ignore_case(args[0], "not") {
+        BitOp::Not
+    } else {
+        return Err(syntax_error());
+    };
+    let len = RedisRocksdb::bitmap().bit_op(&tx.wrap(), op, args[1], &args[2..])?;
+    Ok(integer(len as i64))
+}
+
+/// i1到i64，u1到u63
+fn parse_field_type(arg: &[u8]) -> Result<BitFieldType, RrError> {
+    let invalid = || RrError::message("Invalid bitfield type. Use something like i16 u8. Note that u64 is not supported but i64 is.".to_owned());
+    let (signed, bits) = match arg.split_first() {
+        Some((b'i' | b'I', bits)) => (true, bits),
+        Some((b'u' | b'U', bits)) => (false, bits),
+        _ => return Err(invalid()),
+    };
+    let bits = std::str::from_utf8(bits).ok().and_then(|s| s.parse::<u8>().ok()).ok_or_else(invalid)?;
+    if bits == 0 || bits > if signed { 64 } else { 63 } {
+        return Err(invalid());
+    }
+    Ok(BitFieldType { signed, bits })
+}
+
+/// 与redis一样，"#N"表示第N个字段，即N乘以类型的bits
+fn parse_field_offset(arg: &[u8], ty: BitFieldType) -> Result<u64, RrError> {
+    match arg.strip_prefix(b"#") {
+        None => parse_offset(arg),
+        Some(n) => parse_offset(n)?.checked_mul(ty.bits as u64).ok_or_else(offset_error),
+    }
+}
+
+fn parse_field_ops(args: &[&[u8]]) -> Result<Vec<BitFieldOp>, RrError> {
+    let mut ops = Vec::new();
+    let mut i = 0;
+    while i < args.len() {
+        let name = args[i];
+        let value_arg = |n: usize| args.get(i + n).copied().ok_or_else(syntax_error);
+        if eq_ignore_case(name, "overflow") {
+            let o = value_arg(1)?;
+            let overflow = if eq_ignore_case(o, "wrap") {
+                BitOverflow::Wrap
+            } else if eq_ignore_case(o, "sat") {
+                BitOverflow::Sat
+            } else if eq_ignore_case(o, "fail") {
+                BitOverflow::Fail
+            } else {
+                return Err(RrError::message("Invalid OVERFLOW type specified".to_owned()));
+            };
+            ops.push(BitFieldOp::Overflow(overflow));
+            i += 2;
+            continue;
+        }
+        let ty = parse_field_type(value_arg(1)?)?;
+        let offset = parse_field_offset(value_arg(2)?, ty)?;
+        if eq_ignore_case(name, "get") {
+            ops.push(BitFieldOp::Get(ty, offset));
+            i += 3;
+        } else if eq_ignore_case(name, "set") {
+            ops.push(BitFieldOp::Set(ty, offset, parse_i64(value_arg(3)?)?));
+            i += 4;
+        } else if eq_ignore_case(name, "incrby") {
+            ops.push(BitFieldOp::IncrBy(ty, offset, parse_i64(value_arg(3)?)?));
+            i += 4;
+        } else {
+            return Err(syntax_error());
+        }
+    }
+    Ok(ops)
+}
+
+fn field_reply(values: Vec<Option<i64>>) -> Frame<'static> {
+    Frame::Array(values.into_iter().map(|v| v.map_or(Frame::Null, integer)).collect())
+}
+
+/// BITFIELD key [GET type offset | [OVERFLOW WRAP | SAT | FAIL] SET type offset value | INCRBY type offset increment ...]
+fn bitfield(_: &mut Connection, tx: &RedisTransaction<'_>, args: &[&[u8]]) -> Result<Frame<'static>, RrError> {
+    let ops = parse_field_ops(&args[1..])?;
+    Ok(field_reply(RedisRocksdb::bitmap().bit_field(&tx.wrap(), args[0], &ops)?))
+}
+
+/// BITFIELD_RO key [GET type offset ...]
+fn bitfield_ro(_: &mut Connection, tx: &RedisTransaction<'_>, args: &[&[u8]]) -> Result<Frame<'static>, RrError> {
+    let ops = parse_field_ops(&args[1..])?;
+    if !ops.iter().all(|op| matches!(op, BitFieldOp::Get(..))) {
+        return Err(RrError::message("BITFIELD_RO only supports the GET subcommand".to_owned()));
+    }
+    Ok(field_reply(RedisRocksdb::bitmap().bit_field(&tx.wrap(), args[0], &ops)?))
+}
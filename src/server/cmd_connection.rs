@@ -0,0 +1,184 @@
+use std::sync::Arc;
+
+use crate::{
+    resp::{Frame, RespVersion},
+    server::{
+        command::{bulk, commands, eq_ignore_case, integer, parse_db_index, parse_i64, syntax_error, wrong_args, Command},
+        Connection,
+    },
+    NotifyEvents, RedisTransaction, RrError,
+};
+
+pub(crate) const COMMANDS: &[Command] = &[
+    Command {
+        name: "ping",
+        arity: -1,
+        handler: ping,
+    },
+    Command {
+        name: "echo",
+        arity: 2,
+        handler: echo,
+    },
+    Command {
+        name: "quit",
+        arity: -1,
+        handler: quit,
+    },
+    Command {
+        name: "hello",
+        arity: -1,
+        handler: hello,
+    },
+    Command {
+        name: "select",
+        arity: 2,
+        handler: select,
+    },
+    Command {
+        name: "command",
+        arity: -1,
+        handler: command,
+    },
+    Command {
+        name: "client",
+        arity: -2,
+        handler: client,
+    },
+    Command {
+        name: "info",
+        arity: -1,
+        handler: info,
+    },
+    Command {
+        name: "config",
+        arity: -2,
+        handler: config,
+    },
+];
+
+/// 订阅模式（RESP2）中与redis一样回复 ["pong", message]
+fn ping(conn: &mut Connection, _: &RedisTransaction<'_>, args: &[&[u8]]) -> Result<Frame<'static>, RrError> {
+    if conn.subscribed() && conn.version == RespVersion::Resp2 {
+        return match args {
+            [] => Ok(Frame::Array(vec![bulk(b"pong"), bulk(b"")])),
+            [message] => Ok(Frame::Array(vec![bulk(b"pong"), bulk(message)])),
+            _ => Err(wrong_args("ping")),
+        };
+    }
+    match args {
+        [] => Ok(Frame::simple(&b"PONG"[..])),
+        [message] => Ok(bulk(message)),
+        _ => Err(wrong_args("ping")),
+    }
+}
+
+fn echo(_: &mut Connection, _: &RedisTransaction<'_>, args: &[&[u8]]) -> Result<Frame<'static>, RrError> {
+    Ok(bulk(args[0]))
+}
+
+fn quit(conn: &mut Connection, _: &RedisTransaction<'_>, _: &[&[u8]]) -> Result<Frame<'static>, RrError> {
+    conn.quit = true;
+    Ok(Frame::ok())
+}
+
+/// HELLO [protover [AUTH username password] [SETNAME clientname]]，不支持AUTH
+fn hello(conn: &mut Connection, _: &RedisTransaction<'_>, args: &[&[u8]]) -> Result<Frame<'static>, RrError> {
+    let mut version = conn.version;
+    if let Some(v) = args.first() {
+        version = match parse_i64(v) {
+            Ok(2) => RespVersion::Resp2,
+            Ok(3) => RespVersion::Resp3,
+            _ => return Err(RrError::message("NOPROTO unsupported protocol version".to_owned())),
+        };
+    }
+    let mut i = 1;
+    while i < args.len() {
+        if eq_ignore_case(args[i], "setname") && i + 1 < args.len() {
+            conn.name = Some(args[i + 1].to_vec());
+            i += 2;
+        } else {
+            return Err(syntax_error());
+        }
+    }
+    conn.version = version;
+    let proto = if version == RespVersion::Resp3 { 3 } else { 2 };
+    Ok(Frame::Map(vec![
+        (bulk(b"server"), bulk(b"redis_rocksdb")),
+        (bulk(b"version"), bulk(env!("CARGO_PKG_VERSION").as_bytes())),
+        (bulk(b"proto"), integer(proto)),
+        (bulk(b"id"), integer(conn.id as i64)),
+        (bulk(b"mode"), bulk(b"standalone")),
+        (bulk(b"role"), bulk(b"master")),
+        (bulk(b"modules"), Frame::Array(Vec::new())),
+    ]))
+}
+
+/// EXEC中的命令使用EXEC开始时的数据库的事务，所以不能在MULTI中切换数据库
+fn select(conn: &mut Connection, _: &RedisTransaction<'_>, args: &[&[u8]]) -> Result<Frame<'static>, RrError> {
+    let index = parse_db_index(args[0])?;
+    if conn.in_exec {
+        return Err(RrError::message("SELECT is not allowed in MULTI".to_owned()));
+    }
+    conn.db = Arc::new(conn.server.db.select(index));
+    Ok(Frame::ok())
+}
+
+/// redis-cli启动时会调用 COMMAND DOCS，返回空的结果就可以
+fn command(_: &mut Connection, _: &RedisTransaction<'_>, args: &[&[u8]]) -> Result<Frame<'static>, RrError> {
+    match args.first() {
+        Some(sub) if eq_ignore_case(sub, "count") => Ok(integer(commands().len() as i64)),
+        Some(sub) if eq_ignore_case(sub, "docs") => Ok(Frame::Map(Vec::new())),
+        _ => Ok(Frame::Array(Vec::new())),
+    }
+}
+
+fn client(conn: &mut Connection, _: &RedisTransaction<'_>, args: &[&[u8]]) -> Result<Frame<'static>, RrError> {
+    let sub = args[0];
+    if eq_ignore_case(sub, "id") {
+        Ok(integer(conn.id as i64))
+    } else if eq_ignore_case(sub, "setname") && args.len() == 2 {
+        conn.name = Some(args[1].to_vec());
+        Ok(Frame::ok())
+    } else if eq_ignore_case(sub, "getname") {
+        Ok(Frame::from(conn.name.clone()))
+    } else if eq_ignore_case(sub, "setinfo") && args.len() == 3 {
+        Ok(Frame::ok())
+    } else {
+        Err(RrError::message(format!("unknown subcommand '{}'", String::from_utf8_lossy(sub))))
+    }
+}
+
+/// 只返回客户端需要的基本信息
+fn info(_: &mut Connection, _: &RedisTransaction<'_>, _: &[&[u8]]) -> Result<Frame<'static>, RrError> {
+    let info = format!(
+        "# Server\r\nredis_version:7.0.0\r\nredis_rocksdb_version:{}\r\nredis_mode:standalone\r\n\r\n# Replication\r\nrole:master\r\n",
+        env!("CARGO_PKG_VERSION")
+    );
+    Ok(Frame::from(info.into_bytes()))
+}
+
+/// CONFIG GET | SET notify-keyspace-events，其它的配置GET时返回空，SET时返回错误
+fn config(conn: &mut Connection, _: &RedisTransaction<'_>, args: &[&[u8]]) -> Result<Frame<'static>, RrError> {
+    const NOTIFY: &str = "notify-keyspace-events";
+    match args {
+        [sub, name] if eq_ignore_case(sub, "get") => {
+            if !eq_ignore_case(name, NOTIFY) {
+                return Ok(Frame::Map(Vec::new()));
+            }
+            let events = conn.db().notify_events().to_string();
+            Ok(Frame::Map(vec![(bulk(NOTIFY.as_bytes()), bulk(events.as_bytes()))]))
+        }
+        [sub, name, value] if eq_ignore_case(sub, "set") => {
+            if !eq_ignore_case(name, NOTIFY) {
+                return Err(RrError::message(format!(
+                    "Unknown option or number of arguments for CONFIG SET - '{}'",
+                    String::from_utf8_lossy(name)
+                )));
+            }
+            conn.db().set_notify_events(NotifyEvents::parse(value)?);
+            Ok(Frame::ok())
+        }
+        _ => Err(RrError::message(format!("unknown subcommand '{}'", String::from_utf8_lossy(args[0])))),
+    }
+}
@@ -0,0 +1,213 @@
+use crate::{
+    resp::Frame,
+    server::{
+        command::{eq_ignore_case, integer, parse_f64, parse_i64, syntax_error, wrong_args, Command},
+        Connection,
+    },
+    Geo, GeoCoord, GeoFrom, GeoOrder, GeoSearch, GeoShape, GeoUnit, LenType, RedisRocksdb, RedisTransaction, RrError, ZAddOptions, ZExist,
+};
+
+/// geo使用[crate::GeoImpl]，key的类型为zset
+pub(crate) const COMMANDS: &[Command] = &[
+    Command {
+        name: "geoadd",
+        arity: -5,
+        handler: geoadd,
+    },
+    Command {
+        name: "geopos",
+        arity: -2,
+        handler: geopos,
+    },
+    Command {
+        name: "geodist",
+        arity: -4,
+        handler: geodist,
+    },
+    Command {
+        name: "geohash",
+        arity: -2,
+        handler: geohash,
+    },
+    Command {
+        name: "geosearch",
+        arity: -7,
+        handler: geosearch,
+    },
+];
+
+fn parse_unit(arg: &[u8]) -> Result<GeoUnit, RrError> {
+    if eq_ignore_case(arg, "m") {
+        Ok(GeoUnit::M)
+    } else if eq_ignore_case(arg, "km") {
+        Ok(GeoUnit::Km)
+    } else if eq_ignore_case(arg, "mi") {
+        Ok(GeoUnit::Mi)
+    } else if eq_ignore_case(arg, "ft") {
+        Ok(GeoUnit::Ft)
+    } else {
+        Err(RrError::message("unsupported unit provided. please use M, KM, FT, MI".to_owned()))
+    }
+}
+
+/// 与redis一样，距离保留4位小数
+fn dist_frame(meters: f64, unit: GeoUnit) -> Frame<'static> {
+    Frame::from(format!("{:.4}", meters / unit.meters()).into_bytes())
+}
+
+fn coord_frame(coord: &GeoCoord) -> Frame<'static> {
+    Frame::Array(vec![Frame::Double(coord.longitude), Frame::Double(coord.latitude)])
+}
+
+/// GEOADD key [NX | XX] [CH] longitude latitude member [longitude latitude member ...]
+fn geoadd(_: &mut Connection, tx: &RedisTransaction<'_>, args: &[&[u8]]) -> Result<Frame<'static>, RrError> {
+    let mut opts = ZAddOptions::default();
+    let mut i = 1;
+    while i < args.len() {
+        if eq_ignore_case(args[i], "nx") {
+            opts.exist = Some(ZExist::Nx);
+        } else if eq_ignore_case(args[i], "xx") {
+            opts.exist = Some(ZExist::Xx);
+        } else if eq_ignore_case(args[i], "ch") {
+            opts.ch = true;
+        } else {
+            break;
+        }
+        i += 1;
+    }
+    let items = &args[i..];
+    if items.is_empty() || !items.len().is_multiple_of(3) {
+        return Err(wrong_args("geoadd"));
+    }
+    let mut members = Vec::with_capacity(items.len() / 3);
+    for item in items.chunks(3) {
+        members.push((GeoCoord::new(parse_f64(item[0])?, parse_f64(item[1])?), item[2]));
+    }
+    let added = RedisRocksdb::geo().add(&tx.wrap(), args[0], &members, &opts)?;
+    Ok(integer(added as i64))
+}
+
+/// GEOPOS key [member ...]
+fn geopos(_: &mut Connection, tx: &RedisTransaction<'_>, args: &[&[u8]]) -> Result<Frame<'static>, RrError> {
+    let coords = RedisRocksdb::geo().pos(&tx.wrap(), args[0], &args[1..])?;
+    Ok(Frame::Array(coords.iter().map(|c| c.as_ref().map_or(Frame::Null, coord_frame)).collect()))
+}
+
+/// GEODIST key member1 member2 [M | KM | FT | MI]
+fn geodist(_: &mut Connection, tx: &RedisTransaction<'_>, args: &[&[u8]]) -> Result<Frame<'static>, RrError> {
+    let unit = match args {
+        [_, _, _] => GeoUnit::M,
+        [_, _, _, unit] => parse_unit(unit)?,
+        _ => return Err(syntax_error()),
+    };
+    let dist = RedisRocksdb::geo().dist(&tx.wrap(), args[0], args[1], args[2])?;
+    Ok(dist.map_or(Frame::Null, |d| dist_frame(d, unit)))
+}
+
+/// GEOHASH key [member ...]
+fn geohash(_: &mut Connection, tx: &RedisTransaction<'_>, args: &[&[u8]]) -> Result<Frame<'static>, RrError> {
+    let hashes = RedisRocksdb::geo().hash(&tx.wrap(), args[0], &args[1..])?;
+    Ok(Frame::Array(hashes.into_iter().map(|h| Frame::from(h.map(String::into_bytes))).collect()))
+}
+
+/// GEOSEARCH key FROMMEMBER member | FROMLONLAT longitude latitude BYRADIUS radius unit | BYBOX width height unit
+/// [ASC | DESC] [COUNT count [ANY]] [WITHCOORD] [WITHDIST] [WITHHASH]
+fn geosearch(_: &mut Connection, tx: &RedisTransaction<'_>, args: &[&[u8]]) -> Result<Frame<'static>, RrError> {
+    let mut from = None;
+    let mut shape = None;
+    let mut unit = GeoUnit::M;
+    let mut order = None;
+    let mut count = None;
+    let mut any = false;
+    let (mut with_coord, mut with_dist, mut with_hash) = (false, false, false);
+    let mut i = 1;
+    while i < args.len() {
+        let name = args[i];
+        let value = |n: usize| args.get(i + n).copied().ok_or_else(syntax_error);
+        if eq_ignore_case(name, "frommember") && from.is_none() {
+            from = Some(GeoFrom::Member(value(1)?.to_vec()));
+            i += 2;
+        } else if eq_ignore_case(name, "fromlonlat") && from.is_none() {
+            from = Some(GeoFrom::Coord(GeoCoord::new(parse_f64(value(1)?)?, parse_f64(value(2)?)?)));
+            i += 3;
+        } else if eq_ignore_case(name, "byradius") && shape.is_none() {
+            let radius = parse_f64(value(1)?)?;
+            if radius < 0.0 {
+                return Err(RrError::message("radius cannot be negative".to_owned()));
+            }
+            unit = parse_unit(value(2)?)?;
+            shape = Some(GeoShape::Radius(radius * unit.meters()));
+            i += 3;
+        } else if eq_ignore_case(name, "bybox") && shape.is_none() {
+            let (width, height) = (parse_f64(value(1)?)?, parse_f64(value(2)?)?);
+            if width < 0.0 || height < 0.0 {
+                return Err(RrError::message("height or width cannot be negative".to_owned()));
+            }
+            unit = parse_unit(value(3)?)?;
+            shape = Some(GeoShape::Box {
+                width: width * unit.meters(),
+                height: height * unit.meters(),
+            });
+            i += 4;
+        } else if eq_ignore_case(name, "asc") {
+            order = Some(GeoOrder::Asc);
+            i += 1;
+        } else if eq_ignore_case(name, "desc") {
+            order = Some(GeoOrder::Desc);
+            i += 1;
+        } else if eq_ignore_case(name, "count") {
+            let n = parse_i64(value(1)?)?;
+            if n <= 0 {
+                return Err(RrError::message("COUNT must be > 0".to_owned()));
+            }
+            count = Some(n as LenType);
+            i += 2;
+            if args.get(i).is_some_and(|a| eq_ignore_case(a, "any")) {
+                any = true;
+                i += 1;
+            }
+        } else if eq_ignore_case(name, "withcoord") {
+            with_coord = true;
+            i += 1;
+        } else if eq_ignore_case(name, "withdist") {
+            with_dist = true;
+            i += 1;
+        } else if eq_ignore_case(name, "withhash") {
+            with_hash = true;
+            i += 1;
+        } else {
+            return Err(syntax_error());
+        }
+    }
+    let from = from.ok_or_else(|| RrError::message("exactly one of FROMMEMBER or FROMLONLAT can be specified for GEOSEARCH".to_owned()))?;
+    let shape = shape.ok_or_else(|| RrError::message("exactly one of BYRADIUS and BYBOX can be specified for GEOSEARCH".to_owned()))?;
+    let opts = GeoSearch {
+        from,
+        shape,
+        order,
+        count,
+        any,
+    };
+    let matches = RedisRocksdb::geo().search(&tx.wrap(), args[0], &opts)?;
+
+    let with_any = with_coord || with_dist || with_hash;
+    let items = matches.into_iter().map(|m| {
+        let member = Frame::from(m.member);
+        if !with_any {
+            return member;
+        }
+        //与redis一样的顺序：成员、距离、hash、坐标
+        let mut item = vec![member];
+        if with_dist {
+            item.push(dist_frame(m.dist, unit));
+        }
+        if with_hash {
+            item.push(integer(m.hash as i64));
+        }
+        if with_coord {
+            item.push(coord_frame(&m.coord));
+        }
+        Frame::Array(item)
+    });
+    Ok(Frame::Array(items.collect()))
+}
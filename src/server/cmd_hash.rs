@@ -0,0 +1,174 @@
+use crate::{
+    resp::Frame,
+    server::{
+        command::{integer, parse_scan_options, scan_reply, wrong_args, Command},
+        Connection,
+    },
+    NotifyEvents, Object, RedisRocksdb, RedisTransaction, RrError,
+};
+
+/// hash使用[crate::ObjectImp]
+pub(crate) const COMMANDS: &[Command] = &[
+    Command {
+        name: "hset",
+        arity: -4,
+        handler: hset,
+    },
+    Command {
+        name: "hmset",
+        arity: -4,
+        handler: hmset,
+    },
+    Command {
+        name: "hsetnx",
+        arity: 4,
+        handler: hsetnx,
+    },
+    Command {
+        name: "hget",
+        arity: 3,
+        handler: hget,
+    },
+    Command {
+        name: "hmget",
+        arity: -3,
+        handler: hmget,
+    },
+    Command {
+        name: "hdel",
+        arity: -3,
+        handler: hdel,
+    },
+    Command {
+        name: "hexists",
+        arity: 3,
+        handler: hexists,
+    },
+    Command {
+        name: "hlen",
+        arity: 2,
+        handler: hlen,
+    },
+    Command {
+        name: "hgetall",
+        arity: 2,
+        handler: hgetall,
+    },
+    Command {
+        name: "hkeys",
+        arity: 2,
+        handler: hkeys,
+    },
+    Command {
+        name: "hvals",
+        arity: 2,
+        handler: hvals,
+    },
+    Command {
+        name: "hscan",
+        arity: -3,
+        handler: hscan,
+    },
+];
+
+/// 返回新增加字段的数量
+fn hset(_: &mut Connection, tx: &RedisTransaction<'_>, args: &[&[u8]]) -> Result<Frame<'static>, RrError> {
+    if args.len() % 2 != 1 {
+        return Err(wrong_args("hset"));
+    }
+    let t = tx.wrap();
+    let object = RedisRocksdb::object();
+    let mut added = 0;
+    for fv in args[1..].chunks(2) {
+        if !object.exists(&t, args[0], fv[0])? {
+            added += 1;
+        }
+        object.set(&t, args[0], fv[0], fv[1])?;
+    }
+    tx.notify(NotifyEvents::HASH, "hset", args[0]);
+    Ok(integer(added))
+}
+
+fn hmset(conn: &mut Connection, tx: &RedisTransaction<'_>, args: &[&[u8]]) -> Result<Frame<'static>, RrError> {
+    if args.len() % 2 != 1 {
+        return Err(wrong_args("hmset"));
+    }
+    hset(conn, tx, args)?;
+    Ok(Frame::ok())
+}
+
+fn hsetnx(_: &mut Connection, tx: &RedisTransaction<'_>, args: &[&[u8]]) -> Result<Frame<'static>, RrError> {
+    let set = RedisRocksdb::object().set_not_exist(&tx.wrap(), args[0], args[1], args[2])?;
+    if set > 0 {
+        tx.notify(NotifyEvents::HASH, "hset", args[0]);
+    }
+    Ok(integer(set))
+}
+
+fn hget(_: &mut Connection, tx: &RedisTransaction<'_>, args: &[&[u8]]) -> Result<Frame<'static>, RrError> {
+    let v = RedisRocksdb::object().get(&tx.wrap(), args[0], args[1])?;
+    Ok(Frame::from(v))
+}
+
+fn hmget(_: &mut Connection, tx: &RedisTransaction<'_>, args: &[&[u8]]) -> Result<Frame<'static>, RrError> {
+    let values = RedisRocksdb::object().mget(&tx.wrap(), args[0], &args[1..])?;
+    Ok(Frame::Array(values.into_iter().map(Frame::from).collect()))
+}
+
+/// 返回被删除的字段数，不存在的字段不计算在内
+fn hdel(_: &mut Connection, tx: &RedisTransaction<'_>, args: &[&[u8]]) -> Result<Frame<'static>, RrError> {
+    let t = tx.wrap();
+    let object = RedisRocksdb::object();
+    let mut deleted = 0;
+    for field in &args[1..] {
+        if object.exists(&t, args[0], field)? {
+            object.del(&t, args[0], field)?;
+            deleted += 1;
+        }
+    }
+    if deleted > 0 {
+        tx.notify(NotifyEvents::HASH, "hdel", args[0]);
+    }
+    Ok(integer(deleted))
+}
+
+fn hexists(_: &mut Connection, tx: &RedisTransaction<'_>, args: &[&[u8]]) -> Result<Frame<'static>, RrError> {
+    let exists = RedisRocksdb::object().exists(&tx.wrap(), args[0], args[1])?;
+    Ok(integer(exists as i64))
+}
+
+fn hlen(_: &mut Connection, tx: &RedisTransaction<'_>, args: &[&[u8]]) -> Result<Frame<'static>, RrError> {
+    let len = RedisRocksdb::object().len(&tx.wrap(), args[0])?;
+    Ok(integer(len.unwrap_or(0) as i64))
+}
+
+/// RESP3时返回Map，RESP2时为 [field, value, ...]
+fn hgetall(_: &mut Connection, tx: &RedisTransaction<'_>, args: &[&[u8]]) -> Result<Frame<'static>, RrError> {
+    let all = RedisRocksdb::object().get_all(&tx.wrap(), args[0])?;
+    Ok(Frame::Map(
+        all.unwrap_or_default().into_iter().map(|(f, v)| (Frame::from(f), Frame::from(v))).collect(),
+    ))
+}
+
+fn hkeys(_: &mut Connection, tx: &RedisTransaction<'_>, args: &[&[u8]]) -> Result<Frame<'static>, RrError> {
+    let keys = RedisRocksdb::object().keys(&tx.wrap(), args[0])?;
+    Ok(Frame::from(keys.unwrap_or_default()))
+}
+
+fn hvals(_: &mut Connection, tx: &RedisTransaction<'_>, args: &[&[u8]]) -> Result<Frame<'static>, RrError> {
+    let values = RedisRocksdb::object().vals(&tx.wrap(), args[0])?;
+    Ok(Frame::from(values))
+}
+
+/// HSCAN key cursor [MATCH pattern] [COUNT count]
+fn hscan(conn: &mut Connection, tx: &RedisTransaction<'_>, args: &[&[u8]]) -> Result<Frame<'static>, RrError> {
+    let cursor = conn.server.cursors.get(args[1])?;
+    let opts = parse_scan_options(&args[2..], false)?;
+    let batch = RedisRocksdb::object().scan(&tx.wrap(), args[0], &cursor, &opts)?;
+    let mut items = Vec::with_capacity(batch.items.len() * 2);
+    for (f, v) in batch.items {
+        items.push(Frame::from(f));
+        items.push(Frame::from(v));
+    }
+    Ok(scan_reply(conn, batch.cursor, items))
+}
@@ -0,0 +1,45 @@
+use crate::{
+    resp::Frame,
+    server::{
+        command::{integer, Command},
+        Connection,
+    },
+    HyperLogLog, RedisRocksdb, RedisTransaction, RrError,
+};
+
+/// HyperLogLog使用[crate::HyperLogLogImpl]
+pub(crate) const COMMANDS: &[Command] = &[
+    Command {
+        name: "pfadd",
+        arity: -2,
+        handler: pfadd,
+    },
+    Command {
+        name: "pfcount",
+        arity: -2,
+        handler: pfcount,
+    },
+    Command {
+        name: "pfmerge",
+        arity: -2,
+        handler: pfmerge,
+    },
+];
+
+/// PFADD key [element ...]
+fn pfadd(_: &mut Connection, tx: &RedisTransaction<'_>, args: &[&[u8]]) -> Result<Frame<'static>, RrError> {
+    let changed = RedisRocksdb::hyper_log_log().pf_add(&tx.wrap(), args[0], &args[1..])?;
+    Ok(integer(changed))
+}
+
+/// PFCOUNT key [key ...]
+fn pfcount(_: &mut Connection, tx: &RedisTransaction<'_>, args: &[&[u8]]) -> Result<Frame<'static>, RrError> {
+    let count = RedisRocksdb::hyper_log_log().pf_count(&tx.wrap(), args)?;
+    Ok(integer(count as i64))
+}
+
+/// PFMERGE destkey [sourcekey ...]
+fn pfmerge(_: &mut Connection, tx: &RedisTransaction<'_>, args: &[&[u8]]) -> Result<Frame<'static>, RrError> {
+    RedisRocksdb::hyper_log_log().pf_merge(&tx.wrap(), args[0], &args[1..])?;
+    Ok(Frame::ok())
+}
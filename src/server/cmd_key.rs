@@ -0,0 +1,246 @@
+use crate::{
+    resp::Frame,
+    server::{
+        command::{eq_ignore_case, integer, parse_db_index, parse_i64, parse_scan_options, scan_reply, syntax_error, Command},
+        Connection,
+    },
+    KeyExpire, KeySpace, NotifyEvents, RedisRocksdb, RedisTransaction, RrError,
+};
+
+pub(crate) const COMMANDS: &[Command] = &[
+    Command {
+        name: "del",
+        arity: -2,
+        handler: del,
+    },
+    Command {
+        name: "unlink",
+        arity: -2,
+        handler: del,
+    },
+    Command {
+        name: "exists",
+        arity: -2,
+        handler: exists,
+    },
+    Command {
+        name: "type",
+        arity: 2,
+        handler: key_type,
+    },
+    Command {
+        name: "rename",
+        arity: 3,
+        handler: rename,
+    },
+    Command {
+        name: "renamenx",
+        arity: 3,
+        handler: rename_nx,
+    },
+    Command {
+        name: "copy",
+        arity: -3,
+        handler: copy,
+    },
+    Command {
+        name: "expire",
+        arity: 3,
+        handler: expire,
+    },
+    Command {
+        name: "pexpire",
+        arity: 3,
+        handler: pexpire,
+    },
+    Command {
+        name: "expireat",
+        arity: 3,
+        handler: expire_at,
+    },
+    Command {
+        name: "pexpireat",
+        arity: 3,
+        handler: pexpire_at,
+    },
+    Command {
+        name: "ttl",
+        arity: 2,
+        handler: ttl,
+    },
+    Command {
+        name: "pttl",
+        arity: 2,
+        handler: pttl,
+    },
+    Command {
+        name: "persist",
+        arity: 2,
+        handler: persist,
+    },
+    Command {
+        name: "scan",
+        arity: -2,
+        handler: scan,
+    },
+    Command {
+        name: "dbsize",
+        arity: 1,
+        handler: db_size,
+    },
+    Command {
+        name: "flushdb",
+        arity: -1,
+        handler: flush_db,
+    },
+    Command {
+        name: "flushall",
+        arity: -1,
+        handler: flush_all,
+    },
+    Command {
+        name: "swapdb",
+        arity: 3,
+        handler: swap_db,
+    },
+];
+
+fn del(_: &mut Connection, tx: &RedisTransaction<'_>, args: &[&[u8]]) -> Result<Frame<'static>, RrError> {
+    let t = tx.wrap();
+    let key_space = RedisRocksdb::key_space();
+    let mut count = 0;
+    //逐个删除，以便对删除了的key发送del通知
+    for key in args {
+        if key_space.del(&t, key)? {
+            tx.notify(NotifyEvents::GENERIC, "del", key);
+            count += 1;
+        }
+    }
+    Ok(integer(count))
+}
+
+fn exists(_: &mut Connection, tx: &RedisTransaction<'_>, args: &[&[u8]]) -> Result<Frame<'static>, RrError> {
+    let count = RedisRocksdb::key_space().exists(&tx.wrap(), args)?;
+    Ok(integer(count as i64))
+}
+
+fn key_type(_: &mut Connection, tx: &RedisTransaction<'_>, args: &[&[u8]]) -> Result<Frame<'static>, RrError> {
+    let key_type = RedisRocksdb::key_space().key_type(&tx.wrap(), args[0])?;
+    Ok(Frame::simple(key_type.map_or("none", |k| k.name()).as_bytes()))
+}
+
+fn rename(_: &mut Connection, tx: &RedisTransaction<'_>, args: &[&[u8]]) -> Result<Frame<'static>, RrError> {
+    let t = tx.wrap();
+    let key_space = RedisRocksdb::key_space();
+    if key_space.key_type(&t, args[0])?.is_none() {
+        return Err(RrError::not_find("key"));
+    }
+    key_space.rename(&t, args[0], args[1])?;
+    tx.notify(NotifyEvents::GENERIC, "rename_from", args[0]);
+    tx.notify(NotifyEvents::GENERIC, "rename_to", args[1]);
+    Ok(Frame::ok())
+}
+
+fn rename_nx(_: &mut Connection, tx: &RedisTransaction<'_>, args: &[&[u8]]) -> Result<Frame<'static>, RrError> {
+    let t = tx.wrap();
+    let key_space = RedisRocksdb::key_space();
+    if key_space.key_type(&t, args[0])?.is_none() {
+        return Err(RrError::not_find("key"));
+    }
+    let renamed = key_space.rename_nx(&t, args[0], args[1])?;
+    if renamed {
+        tx.notify(NotifyEvents::GENERIC, "rename_from", args[0]);
+        tx.notify(NotifyEvents::GENERIC, "rename_to", args[1]);
+    }
+    Ok(integer(renamed as i64))
+}
+
+/// COPY source destination [REPLACE]，不支持DB
+fn copy(_: &mut Connection, tx: &RedisTransaction<'_>, args: &[&[u8]]) -> Result<Frame<'static>, RrError> {
+    let replace = match &args[2..] {
+        [] => false,
+        [r] if eq_ignore_case(r, "replace") => true,
+        _ => return Err(syntax_error()),
+    };
+    let copied = RedisRocksdb::key_space().copy(&tx.wrap(), args[0], args[1], replace)?;
+    if copied {
+        tx.notify(NotifyEvents::GENERIC, "copy_to", args[1]);
+    }
+    Ok(integer(copied as i64))
+}
+
+fn expire(_: &mut Connection, tx: &RedisTransaction<'_>, args: &[&[u8]]) -> Result<Frame<'static>, RrError> {
+    Ok(integer(tx.expire(&args[0], parse_i64(args[1])?)? as i64))
+}
+
+fn pexpire(_: &mut Connection, tx: &RedisTransaction<'_>, args: &[&[u8]]) -> Result<Frame<'static>, RrError> {
+    Ok(integer(tx.pexpire(&args[0], parse_i64(args[1])?)? as i64))
+}
+
+fn expire_at(_: &mut Connection, tx: &RedisTransaction<'_>, args: &[&[u8]]) -> Result<Frame<'static>, RrError> {
+    Ok(integer(tx.expire_at(&args[0], parse_i64(args[1])?)? as i64))
+}
+
+fn pexpire_at(_: &mut Connection, tx: &RedisTransaction<'_>, args: &[&[u8]]) -> Result<Frame<'static>, RrError> {
+    Ok(integer(tx.pexpire_at(&args[0], parse_i64(args[1])?)? as i64))
+}
+
+fn ttl(_: &mut Connection, tx: &RedisTransaction<'_>, args: &[&[u8]]) -> Result<Frame<'static>, RrError> {
+    Ok(integer(tx.ttl(&args[0])?))
+}
+
+fn pttl(_: &mut Connection, tx: &RedisTransaction<'_>, args: &[&[u8]]) -> Result<Frame<'static>, RrError> {
+    Ok(integer(tx.pttl(&args[0])?))
+}
+
+fn persist(_: &mut Connection, tx: &RedisTransaction<'_>, args: &[&[u8]]) -> Result<Frame<'static>, RrError> {
+    Ok(integer(tx.persist(&args[0])? as i64))
+}
+
+/// SCAN cursor [MATCH pattern] [COUNT count] [TYPE type]
+fn scan(conn: &mut Connection, tx: &RedisTransaction<'_>, args: &[&[u8]]) -> Result<Frame<'static>, RrError> {
+    let cursor = conn.server.cursors.get(args[0])?;
+    let opts = parse_scan_options(&args[1..], true)?;
+    let batch = RedisRocksdb::key_space().scan(&tx.wrap(), &cursor, &opts)?;
+    Ok(scan_reply(conn, batch.cursor, batch.items.into_iter().map(Frame::from).collect()))
+}
+
+fn db_size(conn: &mut Connection, _: &RedisTransaction<'_>, _: &[&[u8]]) -> Result<Frame<'static>, RrError> {
+    Ok(integer(conn.db().db_size()? as i64))
+}
+
+/// FLUSHDB [ASYNC|SYNC]，都是同步删除
+fn flush_db(conn: &mut Connection, _: &RedisTransaction<'_>, args: &[&[u8]]) -> Result<Frame<'static>, RrError> {
+    check_flush(conn, args, "FLUSHDB")?;
+    conn.db().flush_db()?;
+    Ok(Frame::ok())
+}
+
+/// FLUSHALL [ASYNC|SYNC]，都是同步删除
+fn flush_all(conn: &mut Connection, _: &RedisTransaction<'_>, args: &[&[u8]]) -> Result<Frame<'static>, RrError> {
+    check_flush(conn, args, "FLUSHALL")?;
+    conn.db().flush_all()?;
+    Ok(Frame::ok())
+}
+
+fn swap_db(conn: &mut Connection, _: &RedisTransaction<'_>, args: &[&[u8]]) -> Result<Frame<'static>, RrError> {
+    let (a, b) = (parse_db_index(args[0])?, parse_db_index(args[1])?);
+    if conn.in_exec {
+        return Err(RrError::message("SWAPDB is not allowed in MULTI".to_owned()));
+    }
+    conn.db().swap_db(a, b)?;
+    Ok(Frame::ok())
+}
+
+/// 删除不在EXEC的事务中，会与事务锁定的key冲突，所以不能在MULTI中使用
+fn check_flush(conn: &Connection, args: &[&[u8]], name: &str) -> Result<(), RrError> {
+    match args {
+        [] => {}
+        [mode] if eq_ignore_case(mode, "async") || eq_ignore_case(mode, "sync") => {}
+        _ => return Err(syntax_error()),
+    }
+    if conn.in_exec {
+        return Err(RrError::message(format!("{} is not allowed in MULTI", name)));
+    }
+    Ok(())
+}
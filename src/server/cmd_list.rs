@@ -0,0 +1,270 @@
+use crate::{
+    resp::Frame,
+    server::{
+        command::{eq_ignore_case, integer, parse_i32, parse_i64, parse_timeout, syntax_error, Command},
+        Connection,
+    },
+    RedisList, RedisTransaction, RrError,
+};
+
+pub(crate) const COMMANDS: &[Command] = &[
+    Command {
+        name: "lpush",
+        arity: -3,
+        handler: lpush,
+    },
+    Command {
+        name: "rpush",
+        arity: -3,
+        handler: rpush,
+    },
+    Command {
+        name: "lpushx",
+        arity: -3,
+        handler: lpushx,
+    },
+    Command {
+        name: "rpushx",
+        arity: -3,
+        handler: rpushx,
+    },
+    Command {
+        name: "lpop",
+        arity: -2,
+        handler: lpop,
+    },
+    Command {
+        name: "rpop",
+        arity: -2,
+        handler: rpop,
+    },
+    Command {
+        name: "llen",
+        arity: 2,
+        handler: llen,
+    },
+    Command {
+        name: "lrange",
+        arity: 4,
+        handler: lrange,
+    },
+    Command {
+        name: "lindex",
+        arity: 3,
+        handler: lindex,
+    },
+    Command {
+        name: "linsert",
+        arity: 5,
+        handler: linsert,
+    },
+    Command {
+        name: "lrem",
+        arity: 4,
+        handler: lrem,
+    },
+    Command {
+        name: "lset",
+        arity: 4,
+        handler: lset,
+    },
+    Command {
+        name: "ltrim",
+        arity: 4,
+        handler: ltrim,
+    },
+    Command {
+        name: "rpoplpush",
+        arity: 3,
+        handler: rpoplpush,
+    },
+    Command {
+        name: "blpop",
+        arity: -3,
+        handler: blpop,
+    },
+    Command {
+        name: "brpop",
+        arity: -3,
+        handler: brpop,
+    },
+    Command {
+        name: "brpoplpush",
+        arity: 4,
+        handler: brpoplpush,
+    },
+];
+
+/// 多个值在同一个事务中逐个push
+fn lpush(_: &mut Connection, tx: &RedisTransaction<'_>, args: &[&[u8]]) -> Result<Frame<'static>, RrError> {
+    let mut len = 0;
+    for v in &args[1..] {
+        len = tx.list_push_front(&args[0], v)?;
+    }
+    Ok(integer(len))
+}
+
+fn rpush(_: &mut Connection, tx: &RedisTransaction<'_>, args: &[&[u8]]) -> Result<Frame<'static>, RrError> {
+    let mut len = 0;
+    for v in &args[1..] {
+        len = tx.list_push_back(&args[0], v)?;
+    }
+    Ok(integer(len))
+}
+
+fn lpushx(_: &mut Connection, tx: &RedisTransaction<'_>, args: &[&[u8]]) -> Result<Frame<'static>, RrError> {
+    let mut len = 0;
+    for v in &args[1..] {
+        len = tx.list_push_front_exists(&args[0], v)?;
+        if len <= 0 {
+            break;
+        }
+    }
+    Ok(integer(len.max(0)))
+}
+
+fn rpushx(_: &mut Connection, tx: &RedisTransaction<'_>, args: &[&[u8]]) -> Result<Frame<'static>, RrError> {
+    let mut len = 0;
+    for v in &args[1..] {
+        len = tx.list_push_back_exists(&args[0], v)?;
+        if len <= 0 {
+            break;
+        }
+    }
+    Ok(integer(len.max(0)))
+}
+
+fn lpop(_: &mut Connection, tx: &RedisTransaction<'_>, args: &[&[u8]]) -> Result<Frame<'static>, RrError> {
+    pop(tx, args, |tx| tx.list_pop_front(&args[0]))
+}
+
+fn rpop(_: &mut Connection, tx: &RedisTransaction<'_>, args: &[&[u8]]) -> Result<Frame<'static>, RrError> {
+    pop(tx, args, |tx| tx.list_pop_back(&args[0]))
+}
+
+/// LPOP key [count]，有count时返回数组，key不存在时返回Null
+fn pop<F>(tx: &RedisTransaction<'_>, args: &[&[u8]], f: F) -> Result<Frame<'static>, RrError>
+where
+    F: Fn(&RedisTransaction<'_>) -> Result<Option<Vec<u8>>, RrError>,
+{
+    let count = match &args[1..] {
+        [] => return Ok(Frame::from(f(tx)?)),
+        [count] => match parse_i64(count)? {
+            c if c >= 0 => c,
+            _ => return Err(RrError::message("value is out of range, must be positive".to_owned())),
+        },
+        _ => return Err(syntax_error()),
+    };
+    if tx.list_len(&args[0])? < 0 {
+        return Ok(Frame::Null);
+    }
+    let mut values = Vec::new();
+    for _ in 0..count {
+        match f(tx)? {
+            None => break,
+            Some(v) => values.push(Frame::from(v)),
+        }
+    }
+    Ok(Frame::Array(values))
+}
+
+fn llen(_: &mut Connection, tx: &RedisTransaction<'_>, args: &[&[u8]]) -> Result<Frame<'static>, RrError> {
+    Ok(integer(tx.list_len(&args[0])?.max(0)))
+}
+
+fn lrange(_: &mut Connection, tx: &RedisTransaction<'_>, args: &[&[u8]]) -> Result<Frame<'static>, RrError> {
+    let values = tx.list_range(&args[0], parse_i32(args[1])?, parse_i32(args[2])?)?;
+    Ok(Frame::from(values))
+}
+
+/// 把负数的下标转换为正数，超出范围时返回None
+fn normalize_index(len: i32, index: i32) -> Option<i32> {
+    let index = if index < 0 { len + index } else { index };
+    (0..len).contains(&index).then_some(index)
+}
+
+fn lindex(_: &mut Connection, tx: &RedisTransaction<'_>, args: &[&[u8]]) -> Result<Frame<'static>, RrError> {
+    let len = tx.list_len(&args[0])?;
+    match normalize_index(len, parse_i32(args[1])?) {
+        None => Ok(Frame::Null),
+        Some(index) => Ok(Frame::from(tx.list_index(&args[0], index)?)),
+    }
+}
+
+/// LINSERT key BEFORE|AFTER pivot element
+fn linsert(_: &mut Connection, tx: &RedisTransaction<'_>, args: &[&[u8]]) -> Result<Frame<'static>, RrError> {
+    let len = if eq_ignore_case(args[1], "before") {
+        tx.list_insert_before(&args[0], &args[2], &args[3])?
+    } else if eq_ignore_case(args[1], "after") {
+        tx.list_insert_after(&args[0], &args[2], &args[3])?
+    } else {
+        return Err(syntax_error());
+    };
+    Ok(integer(len))
+}
+
+fn lrem(_: &mut Connection, tx: &RedisTransaction<'_>, args: &[&[u8]]) -> Result<Frame<'static>, RrError> {
+    let count = tx.list_rem(&args[0], parse_i32(args[1])?, &args[2])?;
+    Ok(integer(count as i64))
+}
+
+fn lset(_: &mut Connection, tx: &RedisTransaction<'_>, args: &[&[u8]]) -> Result<Frame<'static>, RrError> {
+    let len = tx.list_len(&args[0])?;
+    if len < 0 {
+        return Err(RrError::not_find("key"));
+    }
+    let index = parse_i32(args[1])?;
+    let index = normalize_index(len, index).ok_or_else(|| RrError::index_out_of_range(index))?;
+    tx.list_set(&args[0], index, &args[2])?;
+    Ok(Frame::ok())
+}
+
+fn ltrim(_: &mut Connection, tx: &RedisTransaction<'_>, args: &[&[u8]]) -> Result<Frame<'static>, RrError> {
+    tx.list_trim(&args[0], parse_i32(args[1])?, parse_i32(args[2])?)?;
+    Ok(Frame::ok())
+}
+
+fn rpoplpush(_: &mut Connection, tx: &RedisTransaction<'_>, args: &[&[u8]]) -> Result<Frame<'static>, RrError> {
+    Ok(Frame::from(tx.list_replace_back(&args[0], &args[1])?))
+}
+
+/// BLPOP key [key ...] timeout
+fn blpop(conn: &mut Connection, tx: &RedisTransaction<'_>, args: &[&[u8]]) -> Result<Frame<'static>, RrError> {
+    block_pop(conn, tx, args, true)
+}
+
+fn brpop(conn: &mut Connection, tx: &RedisTransaction<'_>, args: &[&[u8]]) -> Result<Frame<'static>, RrError> {
+    block_pop(conn, tx, args, false)
+}
+
+/// 返回第一个有数据的key与pop的值，阻塞时不使用命令的事务，在EXEC中与redis一样不阻塞
+fn block_pop(conn: &mut Connection, tx: &RedisTransaction<'_>, args: &[&[u8]], front: bool) -> Result<Frame<'static>, RrError> {
+    let (keys, timeout) = args.split_at(args.len() - 1);
+    let timeout = parse_timeout(timeout[0])?;
+    let re = if conn.in_exec {
+        let mut re = None;
+        for key in keys {
+            let value = if front { tx.list_pop_front(key)? } else { tx.list_pop_back(key)? };
+            if let Some(v) = value {
+                re = Some((key.to_vec(), v));
+                break;
+            }
+        }
+        re
+    } else {
+        let db = conn.db();
+        db.list_block(keys, timeout, |key| if front { db.list_pop_front(&key) } else { db.list_pop_back(&key) })?
+    };
+    Ok(re.map_or(Frame::Null, |(k, v)| Frame::Array(vec![Frame::from(k), Frame::from(v)])))
+}
+
+fn brpoplpush(conn: &mut Connection, tx: &RedisTransaction<'_>, args: &[&[u8]]) -> Result<Frame<'static>, RrError> {
+    let timeout = parse_timeout(args[2])?;
+    let value = if conn.in_exec {
+        tx.list_replace_back(&args[0], &args[1])?
+    } else {
+        let db = conn.db();
+        db.list_block(&args[..1], timeout, |key| db.list_replace_back(&key, &args[1]))?.map(|(_, v)| v)
+    };
+    Ok(Frame::from(value))
+}
@@ -0,0 +1,109 @@
+use crate::{
+    resp::Frame,
+    server::{
+        command::{bulk, integer, Command},
+        Connection,
+    },
+    PubSubMessage, RedisTransaction, RrError, Subscriber,
+};
+
+/// 订阅后，连接在等待命令的同时把收到的消息推送给客户端，参见[Connection::subscriber]
+pub(crate) const COMMANDS: &[Command] = &[
+    Command {
+        name: "publish",
+        arity: 3,
+        handler: publish,
+    },
+    Command {
+        name: "subscribe",
+        arity: -2,
+        handler: subscribe,
+    },
+    Command {
+        name: "psubscribe",
+        arity: -2,
+        handler: psubscribe,
+    },
+    Command {
+        name: "unsubscribe",
+        arity: -1,
+        handler: unsubscribe,
+    },
+    Command {
+        name: "punsubscribe",
+        arity: -1,
+        handler: punsubscribe,
+    },
+];
+
+/// 订阅模式（RESP2）中可以使用的命令，与redis一样
+pub(crate) fn allowed_in_subscribe(name: &str) -> bool {
+    matches!(name, "subscribe" | "psubscribe" | "unsubscribe" | "punsubscribe" | "ping" | "quit")
+}
+
+/// 收到的消息：["message", channel, payload] 或 ["pmessage", pattern, channel, payload]
+pub(crate) fn message_frame(message: PubSubMessage) -> Frame<'static> {
+    let mut items = match message.pattern {
+        None => vec![bulk(b"message")],
+        Some(pattern) => vec![bulk(b"pmessage"), Frame::from(pattern)],
+    };
+    items.push(Frame::from(message.channel));
+    items.push(Frame::from(message.payload));
+    Frame::Push(items)
+}
+
+/// 与redis一样，每一个channel都有一个回复：[kind, channel, 当时订阅的总数]，最后一个作为命令的回复，其它的先推送
+/// channels为空时（没有可以取消的订阅）回复 [kind, nil, 订阅的总数]
+fn each_channel(conn: &mut Connection, kind: &'static [u8], channels: Vec<Vec<u8>>, f: fn(&mut Subscriber, &[u8])) -> Frame<'static> {
+    let subscriber = conn.subscriber.get_or_insert_with(|| conn.server.db.subscriber());
+    let mut replies: Vec<Frame<'static>> = channels
+        .into_iter()
+        .map(|channel| {
+            f(subscriber, &channel);
+            Frame::Push(vec![bulk(kind), Frame::from(channel), integer(subscriber.count() as i64)])
+        })
+        .collect();
+    let last = replies
+        .pop()
+        .unwrap_or_else(|| Frame::Push(vec![bulk(kind), Frame::Null, integer(subscriber.count() as i64)]));
+    conn.pushed.extend(replies);
+    last
+}
+
+fn to_vec(args: &[&[u8]]) -> Vec<Vec<u8>> {
+    args.iter().map(|a| a.to_vec()).collect()
+}
+
+fn publish(conn: &mut Connection, _: &RedisTransaction<'_>, args: &[&[u8]]) -> Result<Frame<'static>, RrError> {
+    Ok(integer(conn.db().publish(args[0], args[1]) as i64))
+}
+
+fn subscribe(conn: &mut Connection, _: &RedisTransaction<'_>, args: &[&[u8]]) -> Result<Frame<'static>, RrError> {
+    Ok(each_channel(conn, b"subscribe", to_vec(args), |s, c| s.subscribe(&[c])))
+}
+
+fn psubscribe(conn: &mut Connection, _: &RedisTransaction<'_>, args: &[&[u8]]) -> Result<Frame<'static>, RrError> {
+    Ok(each_channel(conn, b"psubscribe", to_vec(args), |s, p| s.psubscribe(&[p])))
+}
+
+/// 没有参数时取消所有的channel
+fn unsubscribe(conn: &mut Connection, _: &RedisTransaction<'_>, args: &[&[u8]]) -> Result<Frame<'static>, RrError> {
+    let channels = match (args, &conn.subscriber) {
+        ([], Some(s)) => s.channels(),
+        _ => to_vec(args),
+    };
+    Ok(each_channel(conn, b"unsubscribe", channels, |s, c| {
+        s.unsubscribe(&[c]);
+    }))
+}
+
+/// 没有参数时取消所有的pattern
+fn punsubscribe(conn: &mut Connection, _: &RedisTransaction<'_>, args: &[&[u8]]) -> Result<Frame<'static>, RrError> {
+    let patterns = match (args, &conn.subscriber) {
+        ([], Some(s)) => s.patterns(),
+        _ => to_vec(args),
+    };
+    Ok(each_channel(conn, b"punsubscribe", patterns, |s, p| {
+        s.punsubscribe(&[p]);
+    }))
+}
@@ -0,0 +1,195 @@
+use crate::{
+    resp::Frame,
+    server::{
+        command::{integer, parse_i64, parse_scan_options, scan_reply, syntax_error, Command},
+        Connection,
+    },
+    LenType, RedisRocksdb, RedisTransaction, RrError, Set,
+};
+
+/// set使用[crate::SetImpl]
+pub(crate) const COMMANDS: &[Command] = &[
+    Command {
+        name: "sadd",
+        arity: -3,
+        handler: sadd,
+    },
+    Command {
+        name: "srem",
+        arity: -3,
+        handler: srem,
+    },
+    Command {
+        name: "sismember",
+        arity: 3,
+        handler: sismember,
+    },
+    Command {
+        name: "smismember",
+        arity: -3,
+        handler: smismember,
+    },
+    Command {
+        name: "scard",
+        arity: 2,
+        handler: scard,
+    },
+    Command {
+        name: "smembers",
+        arity: 2,
+        handler: smembers,
+    },
+    Command {
+        name: "spop",
+        arity: -2,
+        handler: spop,
+    },
+    Command {
+        name: "srandmember",
+        arity: -2,
+        handler: srandmember,
+    },
+    Command {
+        name: "smove",
+        arity: 4,
+        handler: smove,
+    },
+    Command {
+        name: "sinter",
+        arity: -2,
+        handler: sinter,
+    },
+    Command {
+        name: "sunion",
+        arity: -2,
+        handler: sunion,
+    },
+    Command {
+        name: "sdiff",
+        arity: -2,
+        handler: sdiff,
+    },
+    Command {
+        name: "sinterstore",
+        arity: -3,
+        handler: sinterstore,
+    },
+    Command {
+        name: "sunionstore",
+        arity: -3,
+        handler: sunionstore,
+    },
+    Command {
+        name: "sdiffstore",
+        arity: -3,
+        handler: sdiffstore,
+    },
+    Command {
+        name: "sscan",
+        arity: -3,
+        handler: sscan,
+    },
+];
+
+/// RESP3时返回Set，RESP2时为数组
+fn members_reply(members: Vec<Vec<u8>>) -> Frame<'static> {
+    Frame::Set(members.into_iter().map(Frame::from).collect())
+}
+
+fn sadd(_: &mut Connection, tx: &RedisTransaction<'_>, args: &[&[u8]]) -> Result<Frame<'static>, RrError> {
+    let added = RedisRocksdb::set().add(&tx.wrap(), args[0], &args[1..])?;
+    Ok(integer(added as i64))
+}
+
+fn srem(_: &mut Connection, tx: &RedisTransaction<'_>, args: &[&[u8]]) -> Result<Frame<'static>, RrError> {
+    let removed = RedisRocksdb::set().rem(&tx.wrap(), args[0], &args[1..])?;
+    Ok(integer(removed as i64))
+}
+
+fn sismember(_: &mut Connection, tx: &RedisTransaction<'_>, args: &[&[u8]]) -> Result<Frame<'static>, RrError> {
+    let is_member = RedisRocksdb::set().is_member(&tx.wrap(), args[0], args[1])?;
+    Ok(integer(is_member as i64))
+}
+
+fn smismember(_: &mut Connection, tx: &RedisTransaction<'_>, args: &[&[u8]]) -> Result<Frame<'static>, RrError> {
+    let re = RedisRocksdb::set().mis_member(&tx.wrap(), args[0], &args[1..])?;
+    Ok(Frame::Array(re.into_iter().map(|b| integer(b as i64)).collect()))
+}
+
+fn scard(_: &mut Connection, tx: &RedisTransaction<'_>, args: &[&[u8]]) -> Result<Frame<'static>, RrError> {
+    let len = RedisRocksdb::set().len(&tx.wrap(), args[0])?;
+    Ok(integer(len as i64))
+}
+
+fn smembers(_: &mut Connection, tx: &RedisTransaction<'_>, args: &[&[u8]]) -> Result<Frame<'static>, RrError> {
+    Ok(members_reply(RedisRocksdb::set().members(&tx.wrap(), args[0])?))
+}
+
+/// SPOP key [count]，没有count时返回一个成员
+fn spop(_: &mut Connection, tx: &RedisTransaction<'_>, args: &[&[u8]]) -> Result<Frame<'static>, RrError> {
+    let set = RedisRocksdb::set();
+    match args {
+        [key] => Ok(Frame::from(set.pop(&tx.wrap(), key, 1)?.pop())),
+        [key, count] => {
+            let count = parse_i64(count)?;
+            if count < 0 {
+                return Err(RrError::message("value is out of range, must be positive".to_owned()));
+            }
+            Ok(members_reply(set.pop(&tx.wrap(), key, count as LenType)?))
+        }
+        _ => Err(syntax_error()),
+    }
+}
+
+/// SRANDMEMBER key [count]，没有count时返回一个成员，count为负数时成员可能重复，所以返回数组
+fn srandmember(_: &mut Connection, tx: &RedisTransaction<'_>, args: &[&[u8]]) -> Result<Frame<'static>, RrError> {
+    let set = RedisRocksdb::set();
+    match args {
+        [key] => Ok(Frame::from(set.rand_member(&tx.wrap(), key, 1)?.pop())),
+        [key, count] => {
+            let members = set.rand_member(&tx.wrap(), key, parse_i64(count)?)?;
+            Ok(Frame::Array(members.into_iter().map(Frame::from).collect()))
+        }
+        _ => Err(syntax_error()),
+    }
+}
+
+fn smove(_: &mut Connection, tx: &RedisTransaction<'_>, args: &[&[u8]]) -> Result<Frame<'static>, RrError> {
+    let moved = RedisRocksdb::set().move_member(&tx.wrap(), args[0], args[1], args[2])?;
+    Ok(integer(moved as i64))
+}
+
+fn sinter(_: &mut Connection, tx: &RedisTransaction<'_>, args: &[&[u8]]) -> Result<Frame<'static>, RrError> {
+    Ok(members_reply(RedisRocksdb::set().inter(&tx.wrap(), args)?))
+}
+
+fn sunion(_: &mut Connection, tx: &RedisTransaction<'_>, args: &[&[u8]]) -> Result<Frame<'static>, RrError> {
+    Ok(members_reply(RedisRocksdb::set().union(&tx.wrap(), args)?))
+}
+
+fn sdiff(_: &mut Connection, tx: &RedisTransaction<'_>, args: &[&[u8]]) -> Result<Frame<'static>, RrError> {
+    Ok(members_reply(RedisRocksdb::set().diff(&tx.wrap(), args)?))
+}
+
+fn sinterstore(_: &mut Connection, tx: &RedisTransaction<'_>, args: &[&[u8]]) -> Result<Frame<'static>, RrError> {
+    let len = RedisRocksdb::set().inter_store(&tx.wrap(), args[0], &args[1..])?;
+    Ok(integer(len as i64))
+}
+
+fn sunionstore(_: &mut Connection, tx: &RedisTransaction<'_>, args: &[&[u8]]) -> Result<Frame<'static>, RrError> {
+    let len = RedisRocksdb::set().union_store(&tx.wrap(), args[0], &args[1..])?;
+    Ok(integer(len as i64))
+}
+
+fn sdiffstore(_: &mut Connection, tx: &RedisTransaction<'_>, args: &[&[u8]]) -> Result<Frame<'static>, RrError> {
+    let len = RedisRocksdb::set().diff_store(&tx.wrap(), args[0], &args[1..])?;
+    Ok(integer(len as i64))
+}
+
+/// SSCAN key cursor [MATCH pattern] [COUNT count]
+fn sscan(conn: &mut Connection, tx: &RedisTransaction<'_>, args: &[&[u8]]) -> Result<Frame<'static>, RrError> {
+    let cursor = conn.server.cursors.get(args[1])?;
+    let opts = parse_scan_options(&args[2..], false)?;
+    let batch = RedisRocksdb::set().scan(&tx.wrap(), args[0], &cursor, &opts)?;
+    Ok(scan_reply(conn, batch.cursor, batch.items.into_iter().map(Frame::from).collect()))
+}
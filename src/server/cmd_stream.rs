@@ -0,0 +1,575 @@
+use crate::{
+    resp::{Frame, RespVersion},
+    server::{
+        command::{eq_ignore_case, integer, parse_i64, syntax_error, wrong_args, Command},
+        Connection,
+    },
+    AutoClaimOptions, ClaimOptions, LenType, PendingFilter, ReadGroupOptions, RedisRocksdb, RedisTransaction, RrError, Stream, StreamAddOptions, StreamEntry,
+    StreamId, StreamRead, StreamTrim, XAddId,
+};
+
+/// stream使用[crate::StreamImpl]，阻塞的xread/xreadgroup使用[RedisRocksdb::stream_block]
+pub(crate) const COMMANDS: &[Command] = &[
+    Command {
+        name: "xadd",
+        arity: -5,
+        handler: xadd,
+    },
+    Command {
+        name: "xlen",
+        arity: 2,
+        handler: xlen,
+    },
+    Command {
+        name: "xrange",
+        arity: -4,
+        handler: xrange,
+    },
+    Command {
+        name: "xrevrange",
+        arity: -4,
+        handler: xrevrange,
+    },
+    Command {
+        name: "xtrim",
+        arity: -4,
+        handler: xtrim,
+    },
+    Command {
+        name: "xdel",
+        arity: -3,
+        handler: xdel,
+    },
+    Command {
+        name: "xread",
+        arity: -4,
+        handler: xread,
+    },
+    Command {
+        name: "xgroup",
+        arity: -2,
+        handler: xgroup,
+    },
+    Command {
+        name: "xreadgroup",
+        arity: -7,
+        handler: xreadgroup,
+    },
+    Command {
+        name: "xack",
+        arity: -4,
+        handler: xack,
+    },
+    Command {
+        name: "xpending",
+        arity: -3,
+        handler: xpending,
+    },
+    Command {
+        name: "xclaim",
+        arity: -6,
+        handler: xclaim,
+    },
+    Command {
+        name: "xautoclaim",
+        arity: -6,
+        handler: xautoclaim,
+    },
+];
+
+fn invalid_id() -> RrError {
+    RrError::message("Invalid stream ID specified as stream command argument".to_owned())
+}
+
+fn parse_u64(arg: &str) -> Result<u64, RrError> {
+    arg.parse().map_err(|_| invalid_id())
+}
+
+/// ms-seq，只有ms时seq使用missing_seq
+fn parse_id(arg: &[u8], missing_seq: u64) -> Result<StreamId, RrError> {
+    let s = std::str::from_utf8(arg).map_err(|_| invalid_id())?;
+    match s.split_once('-') {
+        None => Ok(StreamId::new(parse_u64(s)?, missing_seq)),
+        Some((ms, seq)) => Ok(StreamId::new(parse_u64(ms)?, parse_u64(seq)?)),
+    }
+}
+
+/// xrange的开始：- 、id 或 (id，返回None表示范围为空
+fn parse_start(arg: &[u8]) -> Result<Option<StreamId>, RrError> {
+    match arg {
+        b"-" => Ok(Some(StreamId::MIN)),
+        b"+" => Ok(Some(StreamId::MAX)),
+        _ => match arg.strip_prefix(b"(") {
+            Some(id) => Ok(parse_id(id, 0)?.next()),
+            None => parse_id(arg, 0).map(Some),
+        },
+    }
+}
+
+/// xrange的结束：+ 、id 或 (id，只有ms时包含这一毫秒所有的id
+fn parse_end(arg: &[u8]) -> Result<Option<StreamId>, RrError> {
+    match arg {
+        b"-" => Ok(Some(StreamId::MIN)),
+        b"+" => Ok(Some(StreamId::MAX)),
+        _ => match arg.strip_prefix(b"(") {
+            Some(id) => Ok(parse_id(id, u64::MAX)?.prev()),
+            None => parse_id(arg, u64::MAX).map(Some),
+        },
+    }
+}
+
+fn parse_count(arg: &[u8]) -> Result<LenType, RrError> {
+    Ok(parse_i64(arg)?.max(0) as LenType)
+}
+
+/// MAXLEN | MINID [= | ~] threshold [LIMIT count]，从args[*i]开始，结束时i指向下一个参数
+/// 与redis不同，~ 也是精确的删除，所以LIMIT被忽略
+fn parse_trim(args: &[&[u8]], i: &mut usize) -> Result<StreamTrim, RrError> {
+    let kind = args[*i];
+    *i += 1;
+    if matches!(args.get(*i), Some(&b"=") | Some(&b"~")) {
+        *i += 1;
+    }
+    let threshold = *args.get(*i).ok_or_else(syntax_error)?;
+    *i += 1;
+    let trim = if eq_ignore_case(kind, "maxlen") {
+        let max = parse_i64(threshold)?;
+        if max < 0 {
+            return Err(RrError::message("The MAXLEN argument must be >= 0.".to_owned()));
+        }
+        StreamTrim::MaxLen(max as LenType)
+    } else {
+        StreamTrim::MinId(parse_id(threshold, 0)?)
+    };
+    if args.get(*i).is_some_and(|a| eq_ignore_case(a, "limit")) {
+        parse_i64(args.get(*i + 1).ok_or_else(syntax_error)?)?;
+        *i += 2;
+    }
+    Ok(trim)
+}
+
+fn is_trim(arg: &[u8]) -> bool {
+    eq_ignore_case(arg, "maxlen") || eq_ignore_case(arg, "minid")
+}
+
+fn id_frame(id: &StreamId) -> Frame<'static> {
+    Frame::from(id.to_string().into_bytes())
+}
+
+/// [id, [field, value, ...]]，已被删除的项为 [id, nil]
+fn entry_frame(entry: StreamEntry) -> Frame<'static> {
+    let fields = if entry.fields.is_empty() {
+        Frame::Null
+    } else {
+        Frame::Array(entry.fields.into_iter().flat_map(|(f, v)| [Frame::from(f), Frame::from(v)]).collect())
+    };
+    Frame::Array(vec![id_frame(&entry.id), fields])
+}
+
+fn entries_frame(entries: Vec<StreamEntry>) -> Frame<'static> {
+    Frame::Array(entries.into_iter().map(entry_frame).collect())
+}
+
+/// 与redis一样，RESP3时为map，RESP2时为 [[key, entries], ...]，没有数据时为nil
+fn read_reply(conn: &Connection, re: Option<StreamRead>) -> Frame<'static> {
+    let re = match re {
+        Some(re) if !re.is_empty() => re,
+        _ => return Frame::Null,
+    };
+    let pairs = re.into_iter().map(|(key, entries)| (Frame::from(key), entries_frame(entries)));
+    if conn.version == RespVersion::Resp3 {
+        Frame::Map(pairs.collect())
+    } else {
+        Frame::Array(pairs.map(|(k, v)| Frame::Array(vec![k, v])).collect())
+    }
+}
+
+/// XADD key [NOMKSTREAM] [MAXLEN | MINID [= | ~] threshold [LIMIT count]] * | id field value [field value ...]
+fn xadd(_: &mut Connection, tx: &RedisTransaction<'_>, args: &[&[u8]]) -> Result<Frame<'static>, RrError> {
+    let mut opts = StreamAddOptions::default();
+    let mut i = 1;
+    while i < args.len() {
+        if eq_ignore_case(args[i], "nomkstream") {
+            opts.no_mk_stream = true;
+            i += 1;
+        } else if is_trim(args[i]) {
+            opts.trim = Some(parse_trim(args, &mut i)?);
+        } else {
+            break;
+        }
+    }
+    let id = match args.get(i).copied().ok_or_else(syntax_error)? {
+        b"*" => XAddId::Auto,
+        arg => match arg.strip_suffix(b"-*") {
+            Some(ms) => XAddId::AutoSeq(parse_u64(std::str::from_utf8(ms).map_err(|_| invalid_id())?)?),
+            None => XAddId::Explicit(parse_id(arg, 0)?),
+        },
+    };
+    let pairs = &args[i + 1..];
+    if pairs.is_empty() || !pairs.len().is_multiple_of(2) {
+        return Err(wrong_args("xadd"));
+    }
+    let fields: Vec<(&[u8], &[u8])> = pairs.chunks(2).map(|p| (p[0], p[1])).collect();
+    let id = tx.stream_add(args[0], &id, &fields, &opts)?;
+    Ok(id.map_or(Frame::Null, |id| id_frame(&id)))
+}
+
+fn xlen(_: &mut Connection, tx: &RedisTransaction<'_>, args: &[&[u8]]) -> Result<Frame<'static>, RrError> {
+    let len = RedisRocksdb::stream().len(&tx.wrap(), args[0])?;
+    Ok(integer(len as i64))
+}
+
+/// [COUNT count]
+fn parse_range_count(args: &[&[u8]]) -> Result<Option<LenType>, RrError> {
+    match args {
+        [] => Ok(None),
+        [name, count] if eq_ignore_case(name, "count") => Ok(Some(parse_count(count)?)),
+        _ => Err(syntax_error()),
+    }
+}
+
+/// XRANGE key start end [COUNT count]
+fn xrange(_: &mut Connection, tx: &RedisTransaction<'_>, args: &[&[u8]]) -> Result<Frame<'static>, RrError> {
+    let count = parse_range_count(&args[3..])?;
+    let (start, end) = match (parse_start(args[1])?, parse_end(args[2])?) {
+        (Some(start), Some(end)) => (start, end),
+        _ => return Ok(Frame::Array(Vec::new())),
+    };
+    Ok(entries_frame(RedisRocksdb::stream().range(&tx.wrap(), args[0], &start, &end, count)?))
+}
+
+/// XREVRANGE key end start [COUNT count]
+fn xrevrange(_: &mut Connection, tx: &RedisTransaction<'_>, args: &[&[u8]]) -> Result<Frame<'static>, RrError> {
+    let count = parse_range_count(&args[3..])?;
+    let (end, start) = match (parse_end(args[1])?, parse_start(args[2])?) {
+        (Some(end), Some(start)) => (end, start),
+        _ => return Ok(Frame::Array(Vec::new())),
+    };
+    Ok(entries_frame(RedisRocksdb::stream().rev_range(&tx.wrap(), args[0], &end, &start, count)?))
+}
+
+/// XTRIM key MAXLEN | MINID [= | ~] threshold [LIMIT count]
+fn xtrim(_: &mut Connection, tx: &RedisTransaction<'_>, args: &[&[u8]]) -> Result<Frame<'static>, RrError> {
+    if !is_trim(args[1]) {
+        return Err(syntax_error());
+    }
+    let mut i = 1;
+    let trim = parse_trim(args, &mut i)?;
+    if i != args.len() {
+        return Err(syntax_error());
+    }
+    let trimmed = RedisRocksdb::stream().trim(&tx.wrap(), args[0], &trim)?;
+    Ok(integer(trimmed as i64))
+}
+
+fn xdel(_: &mut Connection, tx: &RedisTransaction<'_>, args: &[&[u8]]) -> Result<Frame<'static>, RrError> {
+    let ids = args[1..].iter().map(|a| parse_id(a, 0)).collect::<Result<Vec<_>, _>>()?;
+    let deleted = RedisRocksdb::stream().del(&tx.wrap(), args[0], &ids)?;
+    Ok(integer(deleted as i64))
+}
+
+/// xread与xreadgroup共用的选项
+#[derive(Default)]
+struct ReadArgs<'a> {
+    count: Option<LenType>,
+    /// 毫秒，0表示一直等待
+    block: Option<i64>,
+    no_ack: bool,
+    keys: Vec<&'a [u8]>,
+    ids: Vec<&'a [u8]>,
+}
+
+/// [COUNT count] [BLOCK milliseconds] [NOACK] STREAMS key [key ...] id [id ...]
+fn parse_read_args<'a>(args: &[&'a [u8]], group: bool) -> Result<ReadArgs<'a>, RrError> {
+    let mut re = ReadArgs::default();
+    let mut i = 0;
+    while i < args.len() {
+        let name = args[i];
+        if eq_ignore_case(name, "streams") {
+            let rest = &args[i + 1..];
+            if rest.is_empty() || !rest.len().is_multiple_of(2) {
+                return Err(RrError::message(
+                    "Unbalanced 'xread' list of streams: for each stream key an ID or '$' must be specified.".to_owned(),
+                ));
+            }
+            let (keys, ids) = rest.split_at(rest.len() / 2);
+            re.keys = keys.to_vec();
+            re.ids = ids.to_vec();
+            return Ok(re);
+        } else if eq_ignore_case(name, "count") {
+            //与redis一样，0表示没有限制
+            re.count = Some(parse_count(args.get(i + 1).ok_or_else(syntax_error)?)?).filter(|c| *c > 0);
+            i += 2;
+        } else if eq_ignore_case(name, "block") {
+            let block = parse_i64(args.get(i + 1).ok_or_else(syntax_error)?)?;
+            if block < 0 {
+                return Err(RrError::message("timeout is negative".to_owned()));
+            }
+            re.block = Some(block);
+            i += 2;
+        } else if group && eq_ignore_case(name, "noack") {
+            re.no_ack = true;
+            i += 1;
+        } else {
+            return Err(syntax_error());
+        }
+    }
+    Err(syntax_error())
+}
+
+/// XREAD [COUNT count] [BLOCK milliseconds] STREAMS key [key ...] id [id ...]
+/// $ 在命令开始时转换为stream最后的id，所以阻塞时只返回之后新增的项
+fn xread(conn: &mut Connection, tx: &RedisTransaction<'_>, args: &[&[u8]]) -> Result<Frame<'static>, RrError> {
+    let read = parse_read_args(args, false)?;
+    let stream = RedisRocksdb::stream();
+    let mut keys = Vec::with_capacity(read.keys.len());
+    for (key, id) in read.keys.iter().zip(read.ids.iter()) {
+        let id = match *id {
+            b"$" => stream.last_id(&tx.wrap(), key)?.unwrap_or(StreamId::MIN),
+            id => parse_id(id, 0)?,
+        };
+        keys.push((*key, id));
+    }
+    let re = match read.block {
+        Some(block) if !conn.in_exec => conn.db().stream_block(&read.keys, block, |t| {
+            let re = stream.read(t, &keys, read.count)?;
+            Ok(Some(re).filter(|r| !r.is_empty()))
+        })?,
+        _ => Some(stream.read(&tx.wrap(), &keys, read.count)?),
+    };
+    Ok(read_reply(conn, re))
+}
+
+/// XREADGROUP GROUP group consumer [COUNT count] [BLOCK milliseconds] [NOACK] STREAMS key [key ...] id [id ...]
+/// 与redis一样，只有所有的id都是 > 时才阻塞
+fn xreadgroup(conn: &mut Connection, tx: &RedisTransaction<'_>, args: &[&[u8]]) -> Result<Frame<'static>, RrError> {
+    if !eq_ignore_case(args[0], "group") {
+        return Err(syntax_error());
+    }
+    let (group, consumer) = (args[1], args[2]);
+    let read = parse_read_args(&args[3..], true)?;
+    let mut keys = Vec::with_capacity(read.keys.len());
+    for (key, id) in read.keys.iter().zip(read.ids.iter()) {
+        let id = match *id {
+            b">" => None,
+            id => Some(parse_id(id, 0)?),
+        };
+        keys.push((*key, id));
+    }
+    let opts = ReadGroupOptions {
+        count: read.count,
+        no_ack: read.no_ack,
+    };
+    let stream = RedisRocksdb::stream();
+    let re = match read.block {
+        Some(block) if !conn.in_exec && keys.iter().all(|(_, id)| id.is_none()) => conn.db().stream_block(&read.keys, block, |t| {
+            let re = stream.read_group(t, group, consumer, &keys, &opts)?;
+            Ok(Some(re).filter(|r| !r.is_empty()))
+        })?,
+        _ => Some(stream.read_group(&tx.wrap(), group, consumer, &keys, &opts)?),
+    };
+    Ok(read_reply(conn, re))
+}
+
+/// XGROUP CREATE key group id | $ [MKSTREAM] [ENTRIESREAD n] | SETID key group id | $ [ENTRIESREAD n] |
+/// DESTROY key group | CREATECONSUMER key group consumer | DELCONSUMER key group consumer
+fn xgroup(_: &mut Connection, tx: &RedisTransaction<'_>, args: &[&[u8]]) -> Result<Frame<'static>, RrError> {
+    let stream = RedisRocksdb::stream();
+    let t = tx.wrap();
+    let sub = args[0];
+    let args = &args[1..];
+    //$ 为stream最后的id，stream不存在时为0-0
+    let parse_group_id = |key: &[u8], id: &[u8]| -> Result<StreamId, RrError> {
+        match id {
+            b"$" => Ok(stream.last_id(&t, key)?.unwrap_or(StreamId::MIN)),
+            id => parse_id(id, 0),
+        }
+    };
+    //ENTRIESREAD用于redis的lag，这里没有实现，只检查参数
+    let check_entries_read = |args: &[&[u8]]| -> Result<(), RrError> {
+        match args {
+            [] => Ok(()),
+            [name, n] if eq_ignore_case(name, "entriesread") => parse_i64(n).map(|_| ()),
+            _ => Err(syntax_error()),
+        }
+    };
+    if eq_ignore_case(sub, "create") && args.len() >= 3 {
+        let mut rest = &args[3..];
+        let mk_stream = rest.first().is_some_and(|a| eq_ignore_case(a, "mkstream"));
+        if mk_stream {
+            rest = &rest[1..];
+        }
+        check_entries_read(rest)?;
+        let id = parse_group_id(args[0], args[2])?;
+        stream.group_create(&t, args[0], args[1], &id, mk_stream)?;
+        Ok(Frame::ok())
+    } else if eq_ignore_case(sub, "setid") && args.len() >= 3 {
+        check_entries_read(&args[3..])?;
+        let id = parse_group_id(args[0], args[2])?;
+        stream.group_set_id(&t, args[0], args[1], &id)?;
+        Ok(Frame::ok())
+    } else if eq_ignore_case(sub, "destroy") && args.len() == 2 {
+        Ok(integer(stream.group_destroy(&t, args[0], args[1])?))
+    } else if eq_ignore_case(sub, "createconsumer") && args.len() == 3 {
+        Ok(integer(stream.group_create_consumer(&t, args[0], args[1], args[2])?))
+    } else if eq_ignore_case(sub, "delconsumer") && args.len() == 3 {
+        Ok(integer(stream.group_del_consumer(&t, args[0], args[1], args[2])? as i64))
+    } else {
+        Err(RrError::message(format!(
+            "unknown subcommand or wrong number of arguments for '{}'. Try XGROUP HELP.",
+            String::from_utf8_lossy(sub)
+        )))
+    }
+}
+
+fn xack(_: &mut Connection, tx: &RedisTransaction<'_>, args: &[&[u8]]) -> Result<Frame<'static>, RrError> {
+    let ids = args[2..].iter().map(|a| parse_id(a, 0)).collect::<Result<Vec<_>, _>>()?;
+    let acked = RedisRocksdb::stream().ack(&tx.wrap(), args[0], args[1], &ids)?;
+    Ok(integer(acked as i64))
+}
+
+/// XPENDING key group [[IDLE min-idle-time] start end count [consumer]]
+fn xpending(_: &mut Connection, tx: &RedisTransaction<'_>, args: &[&[u8]]) -> Result<Frame<'static>, RrError> {
+    let stream = RedisRocksdb::stream();
+    let (key, group) = (args[0], args[1]);
+    let mut rest = &args[2..];
+    if rest.is_empty() {
+        let summary = stream.pending(&tx.wrap(), key, group)?;
+        if summary.count == 0 {
+            return Ok(Frame::Array(vec![integer(0), Frame::Null, Frame::Null, Frame::Null]));
+        }
+        let consumers = summary
+            .consumers
+            .into_iter()
+            .map(|(c, n)| Frame::Array(vec![Frame::from(c), Frame::from(n.to_string().into_bytes())]))
+            .collect();
+        return Ok(Frame::Array(vec![
+            integer(summary.count as i64),
+            Frame::from(summary.min.map(|id| id.to_string().into_bytes())),
+            Frame::from(summary.max.map(|id| id.to_string().into_bytes())),
+            Frame::Array(consumers),
+        ]));
+    }
+    let mut min_idle = None;
+    if rest.len() >= 2 && eq_ignore_case(rest[0], "idle") {
+        min_idle = Some(parse_i64(rest[1])?);
+        rest = &rest[2..];
+    }
+    let (start, end, count, consumer) = match rest {
+        [start, end, count] => (start, end, count, None),
+        [start, end, count, consumer] => (start, end, count, Some(consumer.to_vec())),
+        _ => return Err(syntax_error()),
+    };
+    let (start, end) = match (parse_start(start)?, parse_end(end)?) {
+        (Some(start), Some(end)) => (start, end),
+        _ => return Ok(Frame::Array(Vec::new())),
+    };
+    let filter = PendingFilter {
+        start,
+        end,
+        count: parse_count(count)?,
+        consumer,
+        min_idle,
+    };
+    let entries = stream.pending_range(&tx.wrap(), key, group, &filter)?;
+    Ok(Frame::Array(
+        entries
+            .into_iter()
+            .map(|p| {
+                Frame::Array(vec![
+                    id_frame(&p.id),
+                    Frame::from(p.consumer),
+                    integer(p.idle),
+                    integer(p.delivery_count as i64),
+                ])
+            })
+            .collect(),
+    ))
+}
+
+fn parse_min_idle(arg: &[u8]) -> Result<i64, RrError> {
+    Ok(parse_i64(arg)?.max(0))
+}
+
+/// XCLAIM key group consumer min-idle-time id [id ...] [IDLE ms] [TIME unix-time-milliseconds] [RETRYCOUNT count] [FORCE] [JUSTID] [LASTID lastid]
+fn xclaim(_: &mut Connection, tx: &RedisTransaction<'_>, args: &[&[u8]]) -> Result<Frame<'static>, RrError> {
+    let mut opts = ClaimOptions {
+        min_idle: parse_min_idle(args[3])?,
+        ..Default::default()
+    };
+    let mut ids = Vec::new();
+    let mut i = 4;
+    while i < args.len() {
+        match parse_id(args[i], 0) {
+            Ok(id) => ids.push(id),
+            Err(_) => break,
+        }
+        i += 1;
+    }
+    while i < args.len() {
+        let name = args[i];
+        let value = || args.get(i + 1).copied().ok_or_else(syntax_error);
+        if eq_ignore_case(name, "force") {
+            opts.force = true;
+        } else if eq_ignore_case(name, "justid") {
+            opts.just_id = true;
+        } else if eq_ignore_case(name, "idle") {
+            opts.idle = Some(parse_i64(value()?)?);
+            i += 1;
+        } else if eq_ignore_case(name, "time") {
+            opts.time = Some(parse_i64(value()?)?);
+            i += 1;
+        } else if eq_ignore_case(name, "retrycount") {
+            opts.retry_count = Some(parse_count(value()?)?);
+            i += 1;
+        } else if eq_ignore_case(name, "lastid") {
+            //用于复制，这里只检查参数
+            parse_id(value()?, 0)?;
+            i += 1;
+        } else {
+            return Err(RrError::message(format!("Unrecognized XCLAIM option '{}'", String::from_utf8_lossy(name))));
+        }
+        i += 1;
+    }
+    let entries = RedisRocksdb::stream().claim(&tx.wrap(), args[0], args[1], args[2], &ids, &opts)?;
+    if opts.just_id {
+        return Ok(Frame::Array(entries.iter().map(|e| id_frame(&e.id)).collect()));
+    }
+    Ok(entries_frame(entries))
+}
+
+/// XAUTOCLAIM key group consumer min-idle-time start [COUNT count] [JUSTID]
+fn xautoclaim(_: &mut Connection, tx: &RedisTransaction<'_>, args: &[&[u8]]) -> Result<Frame<'static>, RrError> {
+    let mut opts = AutoClaimOptions {
+        min_idle: parse_min_idle(args[3])?,
+        ..Default::default()
+    };
+    let start = parse_start(args[4])?.ok_or_else(invalid_id)?;
+    let mut i = 5;
+    while i < args.len() {
+        if eq_ignore_case(args[i], "justid") {
+            opts.just_id = true;
+            i += 1;
+        } else if eq_ignore_case(args[i], "count") {
+            let count = parse_i64(args.get(i + 1).ok_or_else(syntax_error)?)?;
+            if count < 1 {
+                return Err(RrError::message("COUNT must be > 0".to_owned()));
+            }
+            opts.count = count as LenType;
+            i += 2;
+        } else {
+            return Err(syntax_error());
+        }
+    }
+    let claimed = RedisRocksdb::stream().auto_claim(&tx.wrap(), args[0], args[1], args[2], &start, &opts)?;
+    let entries = if opts.just_id {
+        Frame::Array(claimed.entries.iter().map(|e| id_frame(&e.id)).collect())
+    } else {
+        entries_frame(claimed.entries)
+    };
+    Ok(Frame::Array(vec![
+        id_frame(&claimed.next),
+        entries,
+        Frame::Array(claimed.deleted.iter().map(id_frame).collect()),
+    ]))
+}
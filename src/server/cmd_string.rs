@@ -0,0 +1,279 @@
+use crate::{
+    resp::Frame,
+    server::{
+        command::{bulk, eq_ignore_case, integer, parse_f64, parse_i64, syntax_error, wrong_args, Command},
+        Connection,
+    },
+    Expiration, KeyValue, LenType, RedisTransaction, RrError, SetCondition, SetOptions,
+};
+
+pub(crate) const COMMANDS: &[Command] = &[
+    Command {
+        name: "get",
+        arity: 2,
+        handler: get,
+    },
+    Command {
+        name: "set",
+        arity: -3,
+        handler: set,
+    },
+    Command {
+        name: "setnx",
+        arity: 3,
+        handler: set_nx,
+    },
+    Command {
+        name: "setex",
+        arity: 4,
+        handler: set_ex,
+    },
+    Command {
+        name: "psetex",
+        arity: 4,
+        handler: pset_ex,
+    },
+    Command {
+        name: "getset",
+        arity: 3,
+        handler: get_set,
+    },
+    Command {
+        name: "getdel",
+        arity: 2,
+        handler: get_del,
+    },
+    Command {
+        name: "getex",
+        arity: -2,
+        handler: get_ex,
+    },
+    Command {
+        name: "incr",
+        arity: 2,
+        handler: incr,
+    },
+    Command {
+        name: "incrby",
+        arity: 3,
+        handler: incr_by,
+    },
+    Command {
+        name: "decr",
+        arity: 2,
+        handler: decr,
+    },
+    Command {
+        name: "decrby",
+        arity: 3,
+        handler: decr_by,
+    },
+    Command {
+        name: "incrbyfloat",
+        arity: 3,
+        handler: incr_by_float,
+    },
+    Command {
+        name: "append",
+        arity: 3,
+        handler: append,
+    },
+    Command {
+        name: "strlen",
+        arity: 2,
+        handler: str_len,
+    },
+    Command {
+        name: "getrange",
+        arity: 4,
+        handler: get_range,
+    },
+    Command {
+        name: "setrange",
+        arity: 4,
+        handler: set_range,
+    },
+    Command {
+        name: "mget",
+        arity: -2,
+        handler: mget,
+    },
+    Command {
+        name: "mset",
+        arity: -3,
+        handler: mset,
+    },
+    Command {
+        name: "msetnx",
+        arity: -3,
+        handler: mset_nx,
+    },
+];
+
+fn get(_: &mut Connection, tx: &RedisTransaction<'_>, args: &[&[u8]]) -> Result<Frame<'static>, RrError> {
+    Ok(Frame::from(tx.get(&args[0])?))
+}
+
+/// SET key value [NX | XX] [GET] [EX seconds | PX milliseconds | EXAT unix-time-seconds | PXAT unix-time-milliseconds | KEEPTTL]
+fn set(_: &mut Connection, tx: &RedisTransaction<'_>, args: &[&[u8]]) -> Result<Frame<'static>, RrError> {
+    let mut options = SetOptions::default();
+    let mut i = 2;
+    while i < args.len() {
+        let arg = args[i];
+        if eq_ignore_case(arg, "nx") && options.condition.is_none() {
+            options.condition = Some(SetCondition::NotExists);
+        } else if eq_ignore_case(arg, "xx") && options.condition.is_none() {
+            options.condition = Some(SetCondition::Exists);
+        } else if eq_ignore_case(arg, "get") {
+            options.get = true;
+        } else if eq_ignore_case(arg, "keepttl") && options.expiration.is_none() {
+            options.expiration = Some(Expiration::KeepTtl);
+        } else if options.expiration.is_none() && i + 1 < args.len() {
+            options.expiration = Some(parse_expiration(arg, args[i + 1], "set")?.ok_or_else(syntax_error)?);
+            i += 1;
+        } else {
+            return Err(syntax_error());
+        }
+        i += 1;
+    }
+    let (written, old) = tx.put_with(&args[0], &args[1], &options)?;
+    if options.get {
+        Ok(Frame::from(old))
+    } else if written {
+        Ok(Frame::ok())
+    } else {
+        Ok(Frame::Null)
+    }
+}
+
+/// EX | PX | EXAT | PXAT 的过期时间，name不是它们时返回None
+/// 与redis一样，时间必须为正数
+fn parse_expiration(name: &[u8], value: &[u8], command: &str) -> Result<Option<Expiration>, RrError> {
+    //(毫秒的倍数, 是否为unix时间)
+    let (unit, absolute) = if eq_ignore_case(name, "ex") {
+        (1000, false)
+    } else if eq_ignore_case(name, "px") {
+        (1, false)
+    } else if eq_ignore_case(name, "exat") {
+        (1000, true)
+    } else if eq_ignore_case(name, "pxat") {
+        (1, true)
+    } else {
+        return Ok(None);
+    };
+    let invalid = || RrError::message(format!("invalid expire time in '{}' command", command));
+    let time = parse_i64(value)?;
+    if time <= 0 {
+        return Err(invalid());
+    }
+    let ms = time.checked_mul(unit).ok_or_else(invalid)?;
+    let expiration = if absolute { Some(Expiration::At(ms)) } else { Expiration::after(ms) };
+    expiration.map(Some).ok_or_else(invalid)
+}
+
+fn set_nx(_: &mut Connection, tx: &RedisTransaction<'_>, args: &[&[u8]]) -> Result<Frame<'static>, RrError> {
+    Ok(integer(tx.put_not_exist(&args[0], &args[1])? as i64))
+}
+
+fn set_ex(conn: &mut Connection, tx: &RedisTransaction<'_>, args: &[&[u8]]) -> Result<Frame<'static>, RrError> {
+    set_with_expiration(conn, tx, args, b"ex", "setex")
+}
+
+fn pset_ex(conn: &mut Connection, tx: &RedisTransaction<'_>, args: &[&[u8]]) -> Result<Frame<'static>, RrError> {
+    set_with_expiration(conn, tx, args, b"px", "psetex")
+}
+
+/// SETEX/PSETEX key time value
+fn set_with_expiration(_: &mut Connection, tx: &RedisTransaction<'_>, args: &[&[u8]], unit: &[u8], command: &str) -> Result<Frame<'static>, RrError> {
+    let options = SetOptions {
+        expiration: parse_expiration(unit, args[1], command)?,
+        ..Default::default()
+    };
+    tx.put_with(&args[0], &args[2], &options)?;
+    Ok(Frame::ok())
+}
+
+fn get_set(_: &mut Connection, tx: &RedisTransaction<'_>, args: &[&[u8]]) -> Result<Frame<'static>, RrError> {
+    Ok(Frame::from(tx.get_set(&args[0], &args[1])?))
+}
+
+fn get_del(_: &mut Connection, tx: &RedisTransaction<'_>, args: &[&[u8]]) -> Result<Frame<'static>, RrError> {
+    Ok(Frame::from(tx.get_del(&args[0])?))
+}
+
+/// GETEX key [EX seconds | PX milliseconds | EXAT unix-time-seconds | PXAT unix-time-milliseconds | PERSIST]
+fn get_ex(_: &mut Connection, tx: &RedisTransaction<'_>, args: &[&[u8]]) -> Result<Frame<'static>, RrError> {
+    let expiration = match &args[1..] {
+        [] => None,
+        [p] if eq_ignore_case(p, "persist") => Some(Expiration::Persist),
+        [name, value] => Some(parse_expiration(name, value, "getex")?.ok_or_else(syntax_error)?),
+        _ => return Err(syntax_error()),
+    };
+    Ok(Frame::from(tx.get_ex(&args[0], expiration)?))
+}
+
+fn incr(_: &mut Connection, tx: &RedisTransaction<'_>, args: &[&[u8]]) -> Result<Frame<'static>, RrError> {
+    Ok(integer(tx.incr_by(&args[0], 1)?))
+}
+
+fn incr_by(_: &mut Connection, tx: &RedisTransaction<'_>, args: &[&[u8]]) -> Result<Frame<'static>, RrError> {
+    Ok(integer(tx.incr_by(&args[0], parse_i64(args[1])?)?))
+}
+
+fn decr(_: &mut Connection, tx: &RedisTransaction<'_>, args: &[&[u8]]) -> Result<Frame<'static>, RrError> {
+    Ok(integer(tx.decr_by(&args[0], 1)?))
+}
+
+fn decr_by(_: &mut Connection, tx: &RedisTransaction<'_>, args: &[&[u8]]) -> Result<Frame<'static>, RrError> {
+    Ok(integer(tx.decr_by(&args[0], parse_i64(args[1])?)?))
+}
+
+/// 与redis一样，回复为bulk string
+fn incr_by_float(_: &mut Connection, tx: &RedisTransaction<'_>, args: &[&[u8]]) -> Result<Frame<'static>, RrError> {
+    let v = tx.incr_by_float(&args[0], parse_f64(args[1])?)?;
+    Ok(bulk(v.to_string().as_bytes()))
+}
+
+fn append(_: &mut Connection, tx: &RedisTransaction<'_>, args: &[&[u8]]) -> Result<Frame<'static>, RrError> {
+    Ok(integer(tx.append(&args[0], &args[1])? as i64))
+}
+
+fn str_len(_: &mut Connection, tx: &RedisTransaction<'_>, args: &[&[u8]]) -> Result<Frame<'static>, RrError> {
+    Ok(integer(tx.str_len(&args[0])? as i64))
+}
+
+fn get_range(_: &mut Connection, tx: &RedisTransaction<'_>, args: &[&[u8]]) -> Result<Frame<'static>, RrError> {
+    let v = tx.get_range(&args[0], parse_i64(args[1])?, parse_i64(args[2])?)?;
+    Ok(Frame::from(v))
+}
+
+fn set_range(_: &mut Connection, tx: &RedisTransaction<'_>, args: &[&[u8]]) -> Result<Frame<'static>, RrError> {
+    let offset = parse_i64(args[1])?;
+    if offset < 0 {
+        return Err(RrError::message("offset is out of range".to_owned()));
+    }
+    Ok(integer(tx.set_range(&args[0], offset as LenType, &args[2])? as i64))
+}
+
+fn mget(_: &mut Connection, tx: &RedisTransaction<'_>, args: &[&[u8]]) -> Result<Frame<'static>, RrError> {
+    Ok(Frame::Array(tx.mget(args)?.into_iter().map(Frame::from).collect()))
+}
+
+type Pairs<'a> = Vec<(&'a [u8], &'a [u8])>;
+
+/// key value 成对的参数
+fn pairs<'a>(args: &[&'a [u8]], command: &str) -> Result<Pairs<'a>, RrError> {
+    if !args.len().is_multiple_of(2) {
+        return Err(wrong_args(command));
+    }
+    Ok(args.chunks(2).map(|c| (c[0], c[1])).collect())
+}
+
+fn mset(_: &mut Connection, tx: &RedisTransaction<'_>, args: &[&[u8]]) -> Result<Frame<'static>, RrError> {
+    tx.mset(&pairs(args, "mset")?)?;
+    Ok(Frame::ok())
+}
+
+fn mset_nx(_: &mut Connection, tx: &RedisTransaction<'_>, args: &[&[u8]]) -> Result<Frame<'static>, RrError> {
+    Ok(integer(tx.mset_not_exist(&pairs(args, "msetnx")?)? as i64))
+}
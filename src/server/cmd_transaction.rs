@@ -0,0 +1,94 @@
+use std::mem;
+
+use crate::{
+    resp::Frame,
+    server::{command::Command, Connection, Multi},
+    RedisTransaction, RrError,
+};
+
+pub(crate) const COMMANDS: &[Command] = &[
+    Command {
+        name: "multi",
+        arity: 1,
+        handler: multi,
+    },
+    Command {
+        name: "exec",
+        arity: 1,
+        handler: exec,
+    },
+    Command {
+        name: "discard",
+        arity: 1,
+        handler: discard,
+    },
+    Command {
+        name: "watch",
+        arity: -2,
+        handler: watch,
+    },
+    Command {
+        name: "unwatch",
+        arity: 1,
+        handler: unwatch,
+    },
+];
+
+/// 在MULTI中不排队，直接执行的命令
+pub(crate) fn is_control(command: &Command) -> bool {
+    matches!(command.name, "multi" | "exec" | "discard" | "watch" | "unwatch" | "quit")
+}
+
+fn multi(conn: &mut Connection, _: &RedisTransaction<'_>, _: &[&[u8]]) -> Result<Frame<'static>, RrError> {
+    if conn.multi.is_some() {
+        return Err(RrError::message("ERR MULTI calls can not be nested".to_owned()));
+    }
+    conn.multi = Some(Multi::default());
+    Ok(Frame::ok())
+}
+
+/// 排队的命令在同一个事务中执行，每一个命令是原子的，与redis一样一个命令出错时其它的命令仍然执行
+/// watch的key在检查时被锁定，所以检查之后直到提交都不会被其它的连接修改
+fn exec(conn: &mut Connection, tx: &RedisTransaction<'_>, _: &[&[u8]]) -> Result<Frame<'static>, RrError> {
+    let multi = conn.multi.take().ok_or_else(|| RrError::message("ERR EXEC without MULTI".to_owned()))?;
+    let watched = mem::take(&mut conn.watched);
+    if multi.aborted {
+        return Err(RrError::message("EXECABORT Transaction discarded because of previous errors.".to_owned()));
+    }
+    if !watched.check(tx)? {
+        return Ok(Frame::Null);
+    }
+    conn.in_exec = true;
+    let mut replies = Vec::with_capacity(multi.commands.len());
+    for (command, args) in &multi.commands {
+        let args: Vec<&[u8]> = args.iter().map(|a| a.as_slice()).collect();
+        let re = tx.atomic(|tx| (command.handler)(conn, tx, &args));
+        replies.push(re.unwrap_or_else(|e| Frame::from(&e)));
+    }
+    conn.in_exec = false;
+    Ok(Frame::Array(replies))
+}
+
+fn discard(conn: &mut Connection, _: &RedisTransaction<'_>, _: &[&[u8]]) -> Result<Frame<'static>, RrError> {
+    if conn.multi.take().is_none() {
+        return Err(RrError::message("ERR DISCARD without MULTI".to_owned()));
+    }
+    conn.watched.clear();
+    Ok(Frame::ok())
+}
+
+fn watch(conn: &mut Connection, _: &RedisTransaction<'_>, args: &[&[u8]]) -> Result<Frame<'static>, RrError> {
+    if conn.multi.is_some() {
+        return Err(RrError::message("ERR WATCH inside MULTI is not allowed".to_owned()));
+    }
+    let db = conn.db.clone();
+    for key in args {
+        conn.watched.watch(&db, key)?;
+    }
+    Ok(Frame::ok())
+}
+
+fn unwatch(conn: &mut Connection, _: &RedisTransaction<'_>, _: &[&[u8]]) -> Result<Frame<'static>, RrError> {
+    conn.watched.clear();
+    Ok(Frame::ok())
+}
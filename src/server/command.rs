@@ -0,0 +1,162 @@
+use std::{
+    collections::HashMap,
+    sync::OnceLock,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    resp::Frame,
+    server::{
+        cmd_bitmap, cmd_connection, cmd_geo, cmd_hash, cmd_hyper_log_log, cmd_key, cmd_list, cmd_pub_sub, cmd_set, cmd_stream, cmd_string, cmd_transaction,
+        Connection, Server,
+    },
+    KeyType, RedisTransaction, RrError, ScanCursor, ScanOptions,
+};
+
+/// 命令的实现，args不包含命令名
+/// 命令在tx中执行，返回Ok后提交，在EXEC中则与其它的命令一起提交
+pub(crate) type Handler = fn(&mut Connection, &RedisTransaction<'_>, &[&[u8]]) -> Result<Frame<'static>, RrError>;
+
+pub(crate) struct Command {
+    /// 小写的命令名
+    pub name: &'static str,
+    /// 与redis一样，包含命令名的参数个数，负数表示最少的个数
+    pub arity: i32,
+    pub handler: Handler,
+}
+
+impl Command {
+    pub(crate) fn check_arity(&self, args_len: usize) -> bool {
+        let len = args_len as i32 + 1;
+        if self.arity >= 0 {
+            len == self.arity
+        } else {
+            len >= -self.arity
+        }
+    }
+}
+
+/// 所有的命令，key为小写的命令名
+pub(crate) fn commands() -> &'static HashMap<&'static str, &'static Command> {
+    static COMMANDS: OnceLock<HashMap<&'static str, &'static Command>> = OnceLock::new();
+    COMMANDS.get_or_init(|| {
+        [
+            cmd_connection::COMMANDS,
+            cmd_transaction::COMMANDS,
+            cmd_key::COMMANDS,
+            cmd_string::COMMANDS,
+            cmd_bitmap::COMMANDS,
+            cmd_hyper_log_log::COMMANDS,
+            cmd_list::COMMANDS,
+            cmd_hash::COMMANDS,
+            cmd_set::COMMANDS,
+            cmd_geo::COMMANDS,
+            cmd_stream::COMMANDS,
+            cmd_pub_sub::COMMANDS,
+        ]
+        .iter()
+        .flat_map(|c| c.iter())
+        .map(|c| (c.name, c))
+        .collect()
+    })
+}
+
+pub(crate) fn find_command(name: &[u8]) -> Option<&'static Command> {
+    let name = std::str::from_utf8(name).ok()?.to_ascii_lowercase();
+    commands().get(name.as_str()).copied()
+}
+
+pub(crate) fn syntax_error() -> RrError {
+    RrError::message("syntax error".to_owned())
+}
+
+pub(crate) fn wrong_args(name: &str) -> RrError {
+    RrError::message(format!("wrong number of arguments for '{}' command", name))
+}
+
+pub(crate) fn parse_i64(arg: &[u8]) -> Result<i64, RrError> {
+    std::str::from_utf8(arg)
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .ok_or_else(|| RrError::message("value is not an integer or out of range".to_owned()))
+}
+
+pub(crate) fn parse_i32(arg: &[u8]) -> Result<i32, RrError> {
+    i32::try_from(parse_i64(arg)?).map_err(|_| RrError::message("value is not an integer or out of range".to_owned()))
+}
+
+/// SELECT与SWAPDB的数据库编号
+pub(crate) fn parse_db_index(arg: &[u8]) -> Result<u32, RrError> {
+    match u32::try_from(parse_i64(arg)?) {
+        Ok(index) if index < Server::DATABASES => Ok(index),
+        _ => Err(RrError::message("DB index is out of range".to_owned())),
+    }
+}
+
+pub(crate) fn parse_f64(arg: &[u8]) -> Result<f64, RrError> {
+    std::str::from_utf8(arg)
+        .ok()
+        .and_then(|s| s.parse::<f64>().ok())
+        .filter(|f| !f.is_nan())
+        .ok_or_else(|| RrError::message("value is not a valid float".to_owned()))
+}
+
+/// 阻塞命令的timeout，与redis一样可以是小数的秒，返回向上取整的毫秒
+pub(crate) fn parse_timeout(arg: &[u8]) -> Result<i64, RrError> {
+    let timeout = std::str::from_utf8(arg)
+        .ok()
+        .and_then(|s| s.parse::<f64>().ok())
+        .filter(|f| f.is_finite())
+        .ok_or_else(|| RrError::message("timeout is not a float or out of range".to_owned()))?;
+    if timeout < 0.0 {
+        return Err(RrError::message("timeout is negative".to_owned()));
+    }
+    //与redis一样，加上当前的时间后不能超出i64的毫秒
+    let millis = (timeout * 1000.0).ceil();
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_millis() as i64);
+    if millis >= (i64::MAX - now) as f64 {
+        return Err(RrError::message("timeout is out of range".to_owned()));
+    }
+    Ok(millis as i64)
+}
+
+pub(crate) fn eq_ignore_case(arg: &[u8], name: &str) -> bool {
+    arg.eq_ignore_ascii_case(name.as_bytes())
+}
+
+pub(crate) fn bulk(v: &[u8]) -> Frame<'static> {
+    Frame::from(v.to_vec())
+}
+
+pub(crate) fn integer<I: Into<i64>>(i: I) -> Frame<'static> {
+    Frame::Integer(i.into())
+}
+
+/// 解析 [MATCH pattern] [COUNT count] [TYPE type]，with_type为false时不支持TYPE
+pub(crate) fn parse_scan_options(args: &[&[u8]], with_type: bool) -> Result<ScanOptions, RrError> {
+    let mut opts = ScanOptions::default();
+    let mut i = 0;
+    while i < args.len() {
+        let value = *args.get(i + 1).ok_or_else(syntax_error)?;
+        if eq_ignore_case(args[i], "match") {
+            opts.pattern = Some(value.to_vec());
+        } else if eq_ignore_case(args[i], "count") {
+            match parse_i64(value)? {
+                count if count > 0 => opts.count = count as u64,
+                _ => return Err(syntax_error()),
+            }
+        } else if with_type && eq_ignore_case(args[i], "type") {
+            //与redis 7一样，不认识的类型返回错误
+            opts.key_type = Some(KeyType::from_name(value).ok_or_else(|| RrError::message("unknown type name".to_owned()))?);
+        } else {
+            return Err(syntax_error());
+        }
+        i += 2;
+    }
+    Ok(opts)
+}
+
+/// scan的回复: [cursor, [items]]
+pub(crate) fn scan_reply(conn: &Connection, cursor: ScanCursor, items: Vec<Frame<'static>>) -> Frame<'static> {
+    Frame::Array(vec![Frame::from(conn.server.cursors.save(cursor)), Frame::Array(items)])
+}
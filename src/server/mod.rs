@@ -0,0 +1,328 @@
+use std::{
+    borrow::Cow,
+    collections::{HashMap, VecDeque},
+    io::{self, Read, Write},
+    net::{TcpListener, TcpStream},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+use crate::{
+    resp::{Frame, RespReader, RespVersion},
+    server::command::{find_command, wrong_args, Command},
+    RedisRocksdb, RrError, ScanCursor, Subscriber, WatchedKeys,
+};
+
+mod cmd_bitmap;
+mod cmd_connection;
+mod cmd_geo;
+mod cmd_hash;
+mod cmd_hyper_log_log;
+mod cmd_key;
+mod cmd_list;
+mod cmd_pub_sub;
+mod cmd_set;
+mod cmd_stream;
+mod cmd_string;
+mod cmd_transaction;
+mod command;
+
+/// 兼容redis的tcp服务，每一个连接使用一个线程，所以blpop等阻塞的命令只阻塞自己的连接
+/// 支持的命令见 cmd_*.rs 中的COMMANDS，与redis的默认配置一样有[Server::DATABASES]个数据库
+pub struct Server {
+    pub(crate) db: Arc<RedisRocksdb>,
+    pub(crate) cursors: ScanCursors,
+    next_client_id: AtomicU64,
+}
+
+impl Server {
+    pub const DATABASES: u32 = 16;
+
+    pub fn new(db: Arc<RedisRocksdb>) -> Self {
+        Server {
+            db,
+            cursors: ScanCursors::default(),
+            next_client_id: AtomicU64::new(1),
+        }
+    }
+
+    /// 接受连接，一直运行到listener出错
+    pub fn serve(self: &Arc<Self>, listener: TcpListener) -> io::Result<()> {
+        for stream in listener.incoming() {
+            let stream = stream?;
+            let server = self.clone();
+            thread::spawn(move || {
+                if let Err(e) = Connection::new(server).run(stream) {
+                    log::debug!("connection closed: {}", e);
+                }
+            });
+        }
+        Ok(())
+    }
+}
+
+/// 一个客户端连接的状态
+pub(crate) struct Connection {
+    pub(crate) server: Arc<Server>,
+    pub(crate) id: u64,
+    /// SELECT选择的数据库，共享server.db的数据库与等待者
+    pub(crate) db: Arc<RedisRocksdb>,
+    pub(crate) version: RespVersion,
+    pub(crate) name: Option<Vec<u8>>,
+    /// QUIT之后，回复完关闭连接
+    pub(crate) quit: bool,
+    /// MULTI之后排队的命令，为None时不在MULTI中
+    pub(crate) multi: Option<Multi>,
+    /// WATCH的key，EXEC或DISCARD之后清除
+    pub(crate) watched: WatchedKeys,
+    /// 正在执行EXEC，这时阻塞的命令不阻塞
+    pub(crate) in_exec: bool,
+    /// SUBSCRIBE/PSUBSCRIBE之后，等待命令时每隔[Connection::POLL]推送收到的消息
+    pub(crate) subscriber: Option<Subscriber>,
+    /// 在命令的回复之前要写回的数据，如subscribe多个channel时前面的回复
+    pub(crate) pushed: Vec<Frame<'static>>,
+}
+
+/// MULTI与EXEC之间排队的命令
+#[derive(Default)]
+pub(crate) struct Multi {
+    /// 命令与它的参数（不包含命令名）
+    pub(crate) commands: Vec<(&'static Command, Vec<Vec<u8>>)>,
+    /// 排队时有命令出错，与redis一样EXEC时放弃整个事务
+    pub(crate) aborted: bool,
+}
+
+impl Connection {
+    fn new(server: Arc<Server>) -> Self {
+        let id = server.next_client_id.fetch_add(1, Ordering::Relaxed);
+        Connection {
+            db: server.db.clone(),
+            server,
+            id,
+            version: RespVersion::Resp2,
+            name: None,
+            quit: false,
+            multi: None,
+            watched: WatchedKeys::new(),
+            in_exec: false,
+            subscriber: None,
+            pushed: Vec::new(),
+        }
+    }
+
+    /// 订阅模式中读取命令的超时，超时后推送收到的消息
+    const POLL: Duration = Duration::from_millis(20);
+
+    pub(crate) fn db(&self) -> &RedisRocksdb {
+        &self.db
+    }
+
+    /// 有订阅的channel或pattern
+    pub(crate) fn subscribed(&self) -> bool {
+        self.subscriber.as_ref().is_some_and(|s| s.count() > 0)
+    }
+
+    /// 读取命令并回复，支持pipeline：一次读取到的所有命令执行完后，一起写回
+    fn run(mut self, mut stream: TcpStream) -> io::Result<()> {
+        let mut reader = RespReader::new();
+        let mut buf = vec![0u8; 16 * 1024];
+        let mut out = Vec::new();
+        let mut polling = false;
+        loop {
+            let n = match stream.read(&mut buf) {
+                Ok(n) => n,
+                Err(e) if polling && matches!(e.kind(), io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut) => {
+                    self.encode_messages(&mut out);
+                    if !out.is_empty() {
+                        stream.write_all(&out)?;
+                        out.clear();
+                    }
+                    continue;
+                }
+                Err(e) => return Err(e),
+            };
+            if n == 0 {
+                return Ok(());
+            }
+            reader.feed(&buf[..n]);
+            loop {
+                let len = match next_request(&reader) {
+                    Ok(None) => break,
+                    Ok(Some((args, len))) => {
+                        if !args.is_empty() {
+                            let reply = self.execute(&args);
+                            for frame in self.pushed.drain(..) {
+                                frame.encode(self.version, &mut out);
+                            }
+                            reply.encode(self.version, &mut out);
+                        }
+                        len
+                    }
+                    Err(e) => {
+                        //协议错误时与redis一样，回复后关闭连接
+                        Frame::from(&e).encode(self.version, &mut out);
+                        stream.write_all(&out)?;
+                        return Ok(());
+                    }
+                };
+                reader.consume(len);
+                if self.quit {
+                    stream.write_all(&out)?;
+                    return Ok(());
+                }
+            }
+            if !self.subscribed() {
+                self.subscriber = None;
+            }
+            self.encode_messages(&mut out);
+            if !out.is_empty() {
+                stream.write_all(&out)?;
+                out.clear();
+            }
+            if polling != self.subscriber.is_some() {
+                polling = self.subscriber.is_some();
+                stream.set_read_timeout(if polling { Some(Connection::POLL) } else { None })?;
+            }
+        }
+    }
+
+    /// 推送订阅收到的消息
+    fn encode_messages(&self, out: &mut Vec<u8>) {
+        if let Some(subscriber) = &self.subscriber {
+            while let Some(message) = subscriber.try_recv() {
+                cmd_pub_sub::message_frame(message).encode(self.version, out);
+            }
+        }
+    }
+
+    pub(crate) fn execute(&mut self, args: &[&[u8]]) -> Frame<'static> {
+        let command = match find_command(args[0]) {
+            None => {
+                self.abort_multi();
+                let name = String::from_utf8_lossy(args[0]);
+                return Frame::from(&RrError::message(format!("unknown command '{}'", name)));
+            }
+            Some(c) => c,
+        };
+        if !command.check_arity(args.len() - 1) {
+            self.abort_multi();
+            return Frame::from(&wrong_args(command.name));
+        }
+        if self.subscribed() && self.version == RespVersion::Resp2 && !cmd_pub_sub::allowed_in_subscribe(command.name) {
+            return Frame::from(&RrError::message(format!(
+                "Can't execute '{}': only (P)SUBSCRIBE / (P)UNSUBSCRIBE / PING / QUIT are allowed in this context",
+                command.name
+            )));
+        }
+        if let Some(multi) = &mut self.multi {
+            if !cmd_transaction::is_control(command) {
+                multi.commands.push((command, args[1..].iter().map(|a| a.to_vec()).collect()));
+                return Frame::simple(&b"QUEUED"[..]);
+            }
+        }
+        let db = self.db.clone();
+        let tx = db.transaction();
+        let re = (command.handler)(self, &tx, &args[1..]).and_then(|frame| {
+            tx.exec()?;
+            Ok(frame)
+        });
+        match re {
+            Ok(frame) => frame,
+            Err(e) => Frame::from(&e),
+        }
+    }
+
+    fn abort_multi(&mut self) {
+        if let Some(multi) = &mut self.multi {
+            multi.aborted = true;
+        }
+    }
+}
+
+/// inline命令的最大长度，与redis一样
+const MAX_INLINE_LEN: usize = 64 * 1024;
+
+/// 命令的参数（借用buffer）与使用的bytes数
+type Request<'a> = (Vec<&'a [u8]>, usize);
+
+/// 支持RESP的数组与telnet的inline命令
+fn next_request(reader: &RespReader) -> Result<Option<Request<'_>>, RrError> {
+    let data = reader.data();
+    match data.first() {
+        None => Ok(None),
+        Some(b'*') => {
+            let (frame, len) = match reader.peek()? {
+                None => return Ok(None),
+                Some(r) => r,
+            };
+            let items = match frame {
+                Frame::Array(items) => items,
+                Frame::Null => Vec::new(),
+                _ => return Err(RrError::message("Protocol error: expected array".to_owned())),
+            };
+            let mut args = Vec::with_capacity(items.len());
+            for item in items {
+                match item {
+                    Frame::Bulk(Cow::Borrowed(arg)) => args.push(arg),
+                    _ => return Err(RrError::message("Protocol error: expected bulk string".to_owned())),
+                }
+            }
+            Ok(Some((args, len)))
+        }
+        Some(_) => match data.iter().position(|b| *b == b'\n') {
+            None if data.len() > MAX_INLINE_LEN => Err(RrError::message("Protocol error: too big inline request".to_owned())),
+            None => Ok(None),
+            Some(end) => {
+                let line = data[..end].strip_suffix(b"\r").unwrap_or(&data[..end]);
+                let args = line.split(|b| b.is_ascii_whitespace()).filter(|a| !a.is_empty()).collect();
+                Ok(Some((args, end + 1)))
+            }
+        },
+    }
+}
+
+/// redis的客户端把scan的游标当作整数，所以在服务端保存[ScanCursor]，返回它的编号
+/// 只保存最近的MAX个游标，更早的游标失效
+#[derive(Default)]
+pub(crate) struct ScanCursors {
+    cursors: Mutex<(u64, HashMap<u64, ScanCursor>, VecDeque<u64>)>,
+}
+
+impl ScanCursors {
+    const MAX: usize = 64 * 1024;
+
+    /// 遍历完成时返回 "0"
+    pub(crate) fn save(&self, cursor: ScanCursor) -> Vec<u8> {
+        if cursor.is_finished() {
+            return b"0".to_vec();
+        }
+        let mut guard = self.cursors.lock().unwrap_or_else(|e| e.into_inner());
+        let (next_id, cursors, order) = &mut *guard;
+        *next_id += 1;
+        let id = *next_id;
+        cursors.insert(id, cursor);
+        order.push_back(id);
+        if order.len() > ScanCursors::MAX {
+            if let Some(old) = order.pop_front() {
+                cursors.remove(&old);
+            }
+        }
+        id.to_string().into_bytes()
+    }
+
+    pub(crate) fn get(&self, id: &[u8]) -> Result<ScanCursor, RrError> {
+        if id == b"0" {
+            return Ok(ScanCursor::start());
+        }
+        let guard = self.cursors.lock().unwrap_or_else(|e| e.into_inner());
+        std::str::from_utf8(id)
+            .ok()
+            .and_then(|s| s.parse::<u64>().ok())
+            .and_then(|id| guard.1.get(&id).cloned())
+            .ok_or_else(|| RrError::message("invalid cursor".to_owned()))
+    }
+}
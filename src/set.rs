@@ -0,0 +1,55 @@
+use crate::{LenType, RrError, ScanBatch, ScanCursor, ScanOptions};
+
+/// 对应redis的set，成员按bytes排序存放，所以sinter/sunion/sdiff可以同时遍历多个集合，不用读入所有的成员
+/// 不存在的key当作空集合
+pub trait Set<T> {
+    /// 返回新增加成员的数量
+    /// 对应redis的sadd
+    fn add(&self, t: &T, key: &[u8], members: &[&[u8]]) -> Result<LenType, RrError>;
+    /// 返回被删除成员的数量，不存在的成员不计算在内
+    /// 对应redis的srem
+    fn rem(&self, t: &T, key: &[u8], members: &[&[u8]]) -> Result<LenType, RrError>;
+    /// 对应redis的sismember
+    fn is_member(&self, t: &T, key: &[u8], member: &[u8]) -> Result<bool, RrError>;
+    /// 返回值与请求的顺序一样
+    /// 对应redis的smismember
+    fn mis_member(&self, t: &T, key: &[u8], members: &[&[u8]]) -> Result<Vec<bool>, RrError>;
+    /// 成员的数量是单独存放的，不用遍历
+    /// 对应redis的scard
+    fn len(&self, t: &T, key: &[u8]) -> Result<LenType, RrError>;
+    /// 对应redis的smembers
+    fn members(&self, t: &T, key: &[u8]) -> Result<Vec<Vec<u8>>, RrError>;
+    /// 随机删除并返回count个成员
+    /// 对应redis的spop
+    fn pop(&self, t: &T, key: &[u8], count: LenType) -> Result<Vec<Vec<u8>>, RrError>;
+    /// 与redis一样，count为正数时返回不重复的成员，为负数时可能重复，个数为count的绝对值
+    /// count的绝对值超过2^24时返回错误
+    /// 对应redis的srandmember
+    fn rand_member(&self, t: &T, key: &[u8], count: i64) -> Result<Vec<Vec<u8>>, RrError>;
+    /// 把成员从src移动到dst，返回false表示src中没有这个成员
+    /// 对应redis的smove
+    fn move_member(&self, t: &T, src: &[u8], dst: &[u8], member: &[u8]) -> Result<bool, RrError>;
+    /// 对应redis的sinter
+    fn inter(&self, t: &T, keys: &[&[u8]]) -> Result<Vec<Vec<u8>>, RrError>;
+    /// 对应redis的sunion
+    fn union(&self, t: &T, keys: &[&[u8]]) -> Result<Vec<Vec<u8>>, RrError>;
+    /// 第一个集合中有，其它集合中都没有的成员
+    /// 对应redis的sdiff
+    fn diff(&self, t: &T, keys: &[&[u8]]) -> Result<Vec<Vec<u8>>, RrError>;
+    /// 结果存入dst（覆盖原来的key），返回结果的数量，结果为空时删除dst
+    /// 对应redis的sinterstore
+    fn inter_store(&self, t: &T, dst: &[u8], keys: &[&[u8]]) -> Result<LenType, RrError>;
+    /// 参见[Set::inter_store]
+    /// 对应redis的sunionstore
+    fn union_store(&self, t: &T, dst: &[u8], keys: &[&[u8]]) -> Result<LenType, RrError>;
+    /// 参见[Set::inter_store]
+    /// 对应redis的sdiffstore
+    fn diff_store(&self, t: &T, dst: &[u8], keys: &[&[u8]]) -> Result<LenType, RrError>;
+
+    /// 从cursor之后开始遍历成员，返回下一批的游标，按成员的bytes排序
+    /// 对应redis的sscan
+    fn scan(&self, t: &T, key: &[u8], cursor: &ScanCursor, opts: &ScanOptions) -> Result<ScanBatch<Vec<u8>>, RrError>;
+
+    /// 删除指定的key，及所有成员
+    fn del_key(&self, t: &T, key: &[u8]) -> Result<(), RrError>;
+}
@@ -1,10 +1,130 @@
-use crate::RrError;
+use crate::{LenType, RrError, ScanBatch, ScanCursor, ScanOptions};
 
-/// 可以存储大量的数据，在遍历数据时，性能不如redis hash
-pub trait SortedSet {
-    fn add(&mut self, key: &[u8], score: i64, v: &[u8]) -> Result<i64, RrError>;
+/// zadd 的 NX | XX
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ZExist {
+    /// 只添加新成员，不更新已存在的成员
+    Nx,
+    /// 只更新已存在的成员，不添加新成员
+    Xx,
+}
+
+/// zadd 的 GT | LT，只影响已存在的成员，新成员总是会被添加
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ZCompare {
+    /// 新的score大于原来的score时才更新
+    Gt,
+    /// 新的score小于原来的score时才更新
+    Lt,
+}
+
+/// zadd 的选项
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ZAddOptions {
+    pub exist: Option<ZExist>,
+    pub compare: Option<ZCompare>,
+    /// CH: 返回值为新增加与score被修改的成员数量，默认只返回新增加的数量
+    pub ch: bool,
+}
+
+/// score的范围，对应redis中的 1.5, (1.5, -inf, +inf
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ScoreBound {
+    Inclusive(f64),
+    Exclusive(f64),
+}
+
+impl ScoreBound {
+    /// -inf
+    pub fn min() -> Self {
+        ScoreBound::Inclusive(f64::NEG_INFINITY)
+    }
+    /// +inf
+    pub fn max() -> Self {
+        ScoreBound::Inclusive(f64::INFINITY)
+    }
+    pub fn value(&self) -> f64 {
+        match self {
+            ScoreBound::Inclusive(v) | ScoreBound::Exclusive(v) => *v,
+        }
+    }
+}
+
+/// 成员的范围，对应redis中的 [a, (a, -, +
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LexBound {
+    Inclusive(Vec<u8>),
+    Exclusive(Vec<u8>),
+    /// -
+    Min,
+    /// +
+    Max,
+}
+
+/// 对应redis的zset，成员按score排序，score相同时按成员的bytes排序
+/// 返回成员与score时，使用 (member, score)
+/// limit 为 (offset, count), 对应redis中的 LIMIT offset count
+pub trait SortedSet<T> {
+    /// 返回新增加成员的数量，如果 opts.ch 为true，返回新增加与被修改成员的数量
+    /// 对应redis的zadd
+    fn add(&self, t: &T, key: &[u8], members: &[(f64, &[u8])], opts: &ZAddOptions) -> Result<LenType, RrError>;
+    /// 返回新的score，如果因为选项没有执行，返回None
+    /// 对应redis的zadd INCR
+    fn add_incr(&self, t: &T, key: &[u8], increment: f64, member: &[u8], opts: &ZAddOptions) -> Result<Option<f64>, RrError>;
+    /// 返回新的score，成员不存在时，当作score为0
+    /// 对应redis的zincrby
+    fn incr_by(&self, t: &T, key: &[u8], increment: f64, member: &[u8]) -> Result<f64, RrError>;
+    /// 对应redis的zscore
+    fn score(&self, t: &T, key: &[u8], member: &[u8]) -> Result<Option<f64>, RrError>;
+    /// 按score从小到大的排名，从0开始
+    /// 对应redis的zrank
+    fn rank(&self, t: &T, key: &[u8], member: &[u8]) -> Result<Option<LenType>, RrError>;
+    /// 按score从大到小的排名，从0开始
+    /// 对应redis的zrevrank
+    fn rev_rank(&self, t: &T, key: &[u8], member: &[u8]) -> Result<Option<LenType>, RrError>;
+    /// 按下标返回，包含stop，负数表示从尾部开始，rev为true时从大到小
+    /// 对应redis的zrange start stop [REV]
+    fn range(&self, t: &T, key: &[u8], start: i64, stop: i64, rev: bool) -> Result<Vec<(Vec<u8>, f64)>, RrError>;
+    /// rev为true时从大到小返回，与redis不同的是min与max的位置不会因为rev而交换
+    /// 对应redis的zrange min max BYSCORE [REV] [LIMIT offset count]
+    fn range_by_score(
+        &self,
+        t: &T,
+        key: &[u8],
+        min: &ScoreBound,
+        max: &ScoreBound,
+        rev: bool,
+        limit: Option<(LenType, LenType)>,
+    ) -> Result<Vec<(Vec<u8>, f64)>, RrError>;
+    /// 只有当所有成员的score相同时，结果才是确定的，与redis一样
+    /// 对应redis的zrange min max BYLEX [REV] [LIMIT offset count]
+    fn range_by_lex(&self, t: &T, key: &[u8], min: &LexBound, max: &LexBound, rev: bool, limit: Option<(LenType, LenType)>) -> Result<Vec<Vec<u8>>, RrError>;
+    /// 对应redis的zcount
+    fn count(&self, t: &T, key: &[u8], min: &ScoreBound, max: &ScoreBound) -> Result<LenType, RrError>;
+    /// 返回被删除成员的数量，不存在的成员不计算在内
+    /// 对应redis的zrem
+    fn rem(&self, t: &T, key: &[u8], members: &[&[u8]]) -> Result<LenType, RrError>;
+    /// 对应redis的zremrangebyrank
+    fn rem_range_by_rank(&self, t: &T, key: &[u8], start: i64, stop: i64) -> Result<LenType, RrError>;
+    /// 对应redis的zremrangebyscore
+    fn rem_range_by_score(&self, t: &T, key: &[u8], min: &ScoreBound, max: &ScoreBound) -> Result<LenType, RrError>;
+    /// 对应redis的zremrangebylex
+    fn rem_range_by_lex(&self, t: &T, key: &[u8], min: &LexBound, max: &LexBound) -> Result<LenType, RrError>;
+    /// 删除并返回score最小的count个成员
+    /// 对应redis的zpopmin
+    fn pop_min(&self, t: &T, key: &[u8], count: LenType) -> Result<Vec<(Vec<u8>, f64)>, RrError>;
+    /// 删除并返回score最大的count个成员
+    /// 对应redis的zpopmax
+    fn pop_max(&self, t: &T, key: &[u8], count: LenType) -> Result<Vec<(Vec<u8>, f64)>, RrError>;
 
     /// 返回集合的数量
     /// 对应redis的zcard
-    fn len(&mut self, key: &[u8]) -> Result<Option<i64>, RrError>;
+    fn len(&self, t: &T, key: &[u8]) -> Result<Option<LenType>, RrError>;
+
+    /// 从cursor之后开始遍历成员与score，返回下一批的游标，按成员的bytes排序
+    /// 对应redis的zscan
+    fn scan(&self, t: &T, key: &[u8], cursor: &ScanCursor, opts: &ScanOptions) -> Result<ScanBatch<(Vec<u8>, f64)>, RrError>;
+
+    /// 删除指定的key，及所有成员
+    fn del_key(&self, t: &T, key: &[u8]) -> Result<(), RrError>;
 }
@@ -0,0 +1,246 @@
+use std::fmt::{Display, Formatter};
+
+use crate::{LenType, RrError};
+
+/// stream的id，对应redis中的 ms-seq
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct StreamId {
+    pub ms: u64,
+    pub seq: u64,
+}
+
+impl StreamId {
+    /// 0-0，对应redis中的 -
+    pub const MIN: StreamId = StreamId { ms: 0, seq: 0 };
+    /// 对应redis中的 +
+    pub const MAX: StreamId = StreamId { ms: u64::MAX, seq: u64::MAX };
+
+    pub fn new(ms: u64, seq: u64) -> Self {
+        StreamId { ms, seq }
+    }
+
+    /// 下一个id，MAX时返回None，用于 (id 这样不包含id的范围
+    pub fn next(&self) -> Option<StreamId> {
+        match (self.seq.checked_add(1), self.ms.checked_add(1)) {
+            (Some(seq), _) => Some(StreamId::new(self.ms, seq)),
+            (None, Some(ms)) => Some(StreamId::new(ms, 0)),
+            (None, None) => None,
+        }
+    }
+
+    /// 上一个id，MIN时返回None
+    pub fn prev(&self) -> Option<StreamId> {
+        match (self.seq.checked_sub(1), self.ms.checked_sub(1)) {
+            (Some(seq), _) => Some(StreamId::new(self.ms, seq)),
+            (None, Some(ms)) => Some(StreamId::new(ms, u64::MAX)),
+            (None, None) => None,
+        }
+    }
+
+    /// big endian，这样key的顺序就是id的顺序
+    pub fn to_bytes(&self) -> [u8; 16] {
+        let mut bytes = [0u8; 16];
+        bytes[..8].copy_from_slice(&self.ms.to_be_bytes());
+        bytes[8..].copy_from_slice(&self.seq.to_be_bytes());
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, RrError> {
+        let bytes = <[u8; 16]>::try_from(bytes)?;
+        let (ms, seq) = bytes.split_at(8);
+        Ok(StreamId::new(u64::from_be_bytes(ms.try_into()?), u64::from_be_bytes(seq.try_into()?)))
+    }
+}
+
+impl Display for StreamId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}-{}", self.ms, self.seq)
+    }
+}
+
+/// xadd的id
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum XAddId {
+    /// *: 使用当前的时间，与最后的id同一毫秒或时间回退时增加seq
+    Auto,
+    /// ms-*: 指定ms，自动产生seq
+    AutoSeq(u64),
+    Explicit(StreamId),
+}
+
+/// xadd与xtrim的 MAXLEN | MINID，与redis不同，~ 也是精确的删除
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StreamTrim {
+    /// 只保留最新的n个
+    MaxLen(LenType),
+    /// 删除id小于它的
+    MinId(StreamId),
+}
+
+/// xadd 的选项
+#[derive(Clone, Copy, Debug, Default)]
+pub struct StreamAddOptions {
+    /// NOMKSTREAM: key不存在时不创建，返回None
+    pub no_mk_stream: bool,
+    pub trim: Option<StreamTrim>,
+}
+
+/// stream中的一项，fields按xadd时的顺序
+/// 在xreadgroup读取pending的项时，已被xdel删除的项fields为空（xadd至少有一个字段，所以不会混淆）
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StreamEntry {
+    pub id: StreamId,
+    pub fields: Vec<(Vec<u8>, Vec<u8>)>,
+}
+
+/// xread与xreadgroup的结果，每一个key与它的项
+pub type StreamRead = Vec<(Vec<u8>, Vec<StreamEntry>)>;
+
+/// xreadgroup 的选项
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ReadGroupOptions {
+    pub count: Option<LenType>,
+    /// NOACK: 投递的项不加入pending entries list
+    pub no_ack: bool,
+}
+
+/// pending entries list中的一项，对应xpending的扩展格式
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PendingEntry {
+    pub id: StreamId,
+    pub consumer: Vec<u8>,
+    /// 距离上一次投递的毫秒数
+    pub idle: i64,
+    pub delivery_count: u64,
+}
+
+/// 对应xpending的简单格式
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PendingSummary {
+    pub count: LenType,
+    pub min: Option<StreamId>,
+    pub max: Option<StreamId>,
+    /// 有pending项的消费者与它的个数，按名字排序
+    pub consumers: Vec<(Vec<u8>, LenType)>,
+}
+
+/// xpending 的范围与过滤条件
+#[derive(Clone, Debug)]
+pub struct PendingFilter {
+    pub start: StreamId,
+    pub end: StreamId,
+    pub count: LenType,
+    pub consumer: Option<Vec<u8>>,
+    /// IDLE: 只返回空闲时间不小于它的（毫秒）
+    pub min_idle: Option<i64>,
+}
+
+/// xclaim 的选项
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ClaimOptions {
+    /// 只认领空闲时间不小于它的（毫秒）
+    pub min_idle: i64,
+    /// IDLE: 认领后的空闲时间，与time都为None时为0
+    pub idle: Option<i64>,
+    /// TIME: 认领后上一次投递的unix时间（毫秒）
+    pub time: Option<i64>,
+    /// RETRYCOUNT: 投递的次数，None时加1（just_id时不变）
+    pub retry_count: Option<u64>,
+    /// FORCE: 不在pending entries list中的id也认领，只要stream中存在
+    pub force: bool,
+    /// JUSTID: 不增加投递的次数，返回的项没有fields
+    pub just_id: bool,
+}
+
+/// xautoclaim 的选项
+#[derive(Clone, Copy, Debug)]
+pub struct AutoClaimOptions {
+    pub min_idle: i64,
+    /// 最多检查的pending项，默认100
+    pub count: LenType,
+    pub just_id: bool,
+}
+
+impl Default for AutoClaimOptions {
+    fn default() -> Self {
+        AutoClaimOptions {
+            min_idle: 0,
+            count: 100,
+            just_id: false,
+        }
+    }
+}
+
+/// xautoclaim的结果
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct AutoClaimed {
+    /// 下一次调用的开始id，0-0表示已遍历完
+    pub next: StreamId,
+    pub entries: Vec<StreamEntry>,
+    /// 已经不在stream中的id，它们从pending entries list中删除
+    pub deleted: Vec<StreamId>,
+}
+
+/// 对应redis的stream，每一项使用 key + id(big endian) 存放，所以xrange/xrevrange是rocksdb的范围遍历
+/// 消费组的pending entries list也按id排序存放
+///
+/// 阻塞的xread/xreadgroup见[crate::RedisRocksdb::stream_block]
+pub trait Stream<T> {
+    /// 返回新增项的id，no_mk_stream且key不存在时返回None
+    /// 对应redis的xadd
+    fn add(&self, t: &T, key: &[u8], id: &XAddId, fields: &[(&[u8], &[u8])], opts: &StreamAddOptions) -> Result<Option<StreamId>, RrError>;
+    /// 对应redis的xlen
+    fn len(&self, t: &T, key: &[u8]) -> Result<LenType, RrError>;
+    /// 最后的id（包括已经删除的），key不存在时返回None，用于xread的 $
+    fn last_id(&self, t: &T, key: &[u8]) -> Result<Option<StreamId>, RrError>;
+    /// start与end都包含在内，count为None时返回所有的
+    /// 对应redis的xrange
+    fn range(&self, t: &T, key: &[u8], start: &StreamId, end: &StreamId, count: Option<LenType>) -> Result<Vec<StreamEntry>, RrError>;
+    /// 从end开始倒序返回
+    /// 对应redis的xrevrange
+    fn rev_range(&self, t: &T, key: &[u8], end: &StreamId, start: &StreamId, count: Option<LenType>) -> Result<Vec<StreamEntry>, RrError>;
+    /// 返回删除的个数
+    /// 对应redis的xtrim
+    fn trim(&self, t: &T, key: &[u8], trim: &StreamTrim) -> Result<LenType, RrError>;
+    /// 返回删除的个数，不存在的id不计算在内
+    /// 对应redis的xdel
+    fn del(&self, t: &T, key: &[u8], ids: &[StreamId]) -> Result<LenType, RrError>;
+    /// 返回每一个key中id大于指定id的项，没有新项的key不返回
+    /// 对应redis的xread（不阻塞）
+    fn read(&self, t: &T, keys: &[(&[u8], StreamId)], count: Option<LenType>) -> Result<StreamRead, RrError>;
+
+    /// 从id之后开始投递，key不存在且mk_stream为false时返回错误，组已存在时返回BUSYGROUP错误
+    /// 对应redis的xgroup create
+    fn group_create(&self, t: &T, key: &[u8], group: &[u8], id: &StreamId, mk_stream: bool) -> Result<(), RrError>;
+    /// 对应redis的xgroup setid
+    fn group_set_id(&self, t: &T, key: &[u8], group: &[u8], id: &StreamId) -> Result<(), RrError>;
+    /// 删除组及它的消费者与pending entries list，返回false表示组不存在
+    /// 对应redis的xgroup destroy
+    fn group_destroy(&self, t: &T, key: &[u8], group: &[u8]) -> Result<bool, RrError>;
+    /// 返回false表示消费者已存在
+    /// 对应redis的xgroup createconsumer
+    fn group_create_consumer(&self, t: &T, key: &[u8], group: &[u8], consumer: &[u8]) -> Result<bool, RrError>;
+    /// 返回消费者被删除的pending项的个数
+    /// 对应redis的xgroup delconsumer
+    fn group_del_consumer(&self, t: &T, key: &[u8], group: &[u8], consumer: &[u8]) -> Result<LenType, RrError>;
+    /// id为None时（redis的 >）投递新的项，并加入pending entries list（no_ack时不加入），没有新项的key不返回
+    /// id为Some时返回这个消费者id之后的pending项，并增加投递的次数，没有pending项的key也返回
+    /// 对应redis的xreadgroup（不阻塞）
+    fn read_group(&self, t: &T, group: &[u8], consumer: &[u8], keys: &[(&[u8], Option<StreamId>)], opts: &ReadGroupOptions) -> Result<StreamRead, RrError>;
+    /// 从pending entries list中删除，返回删除的个数
+    /// 对应redis的xack
+    fn ack(&self, t: &T, key: &[u8], group: &[u8], ids: &[StreamId]) -> Result<LenType, RrError>;
+    /// 对应redis的xpending的简单格式
+    fn pending(&self, t: &T, key: &[u8], group: &[u8]) -> Result<PendingSummary, RrError>;
+    /// 对应redis的xpending的扩展格式
+    fn pending_range(&self, t: &T, key: &[u8], group: &[u8], filter: &PendingFilter) -> Result<Vec<PendingEntry>, RrError>;
+    /// 把pending项转给consumer，返回认领的项，已经不在stream中的项从pending entries list中删除
+    /// 对应redis的xclaim
+    fn claim(&self, t: &T, key: &[u8], group: &[u8], consumer: &[u8], ids: &[StreamId], opts: &ClaimOptions) -> Result<Vec<StreamEntry>, RrError>;
+    /// 从start开始认领空闲的pending项
+    /// 对应redis的xautoclaim
+    fn auto_claim(&self, t: &T, key: &[u8], group: &[u8], consumer: &[u8], start: &StreamId, opts: &AutoClaimOptions) -> Result<AutoClaimed, RrError>;
+
+    /// 删除指定的key，及所有的项与消费组
+    fn del_key(&self, t: &T, key: &[u8]) -> Result<(), RrError>;
+}
@@ -1,16 +1,48 @@
-use rocksdb::{DBAccess, DBIteratorWithThreadMode};
+use rocksdb::{ColumnFamily, DBAccess, Direction};
 
 use crate::RrError;
 
+/// [WrapDb]遍历的结果，与rocksdb的迭代器一样，key中没有namespace的前缀
+pub type WrapIterator<'a> = Box<dyn Iterator<Item = Result<(Box<[u8]>, Box<[u8]>), rocksdb::Error>> + 'a>;
+
 /// 是对db的抽象，减少在事务与不带事务时，重复的代码，如果想要更好的性能，那么可以不使用这层实现
 pub trait WrapDb {
     type Db: DBAccess;
     fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>, RrError>;
+    /// 在事务中读取并锁定key，防止并发修改时丢失更新，不是事务时与get一样
+    fn get_for_update(&self, key: &[u8]) -> Result<Option<Vec<u8>>, RrError>;
     fn put(&self, key: &[u8], value: &[u8]) -> Result<(), RrError>;
     fn delete(&self, key: &[u8]) -> Result<(), RrError>;
     /// 判断key是否存在， true存在，false不存在
     fn exist(&self, key: &[u8]) -> Result<bool, RrError>;
     /// 为了区分方法与字段，增加get
     fn get_db(&self) -> &Self::Db;
-    fn prefix_iterator<'b: 'c, 'c>(&'b self, prefix: &[u8]) -> DBIteratorWithThreadMode<'c, Self::Db>;
+    /// 只遍历namespace中的key，参见[WrapDb::namespace]
+    fn prefix_iterator<'b: 'c, 'c>(&'b self, prefix: &[u8]) -> WrapIterator<'c>;
+    /// key所在的column family，None时为default，参见[crate::ColumnFamilies]
+    /// 上面的方法都按key选择column family，prefix_iterator与iterator_from只遍历prefix或from所在的column family
+    fn column_family(&self, _key: &[u8]) -> Option<&ColumnFamily> {
+        None
+    }
+    /// 读写的key所在的namespace，不是0时存放到数据库中的key有namespace的前缀，参见[crate::RedisRocksdb::select]
+    fn namespace(&self) -> u32 {
+        0
+    }
+    /// 从from开始遍历，Direction::Reverse时从小于等于from的key开始，注意不会在prefix处停止，但会在namespace处停止
+    /// 默认使用prefix_iterator，只在数据库没有prefix extractor时正确，Reverse时要从头遍历到from，本crate的WrapDb都有自己的实现
+    fn iterator_from<'b: 'c, 'c>(&'b self, from: &[u8], direction: Direction) -> WrapIterator<'c> {
+        match direction {
+            Direction::Forward => self.prefix_iterator(from),
+            Direction::Reverse => {
+                let mut items: Vec<_> = self
+                    .prefix_iterator(&[])
+                    .take_while(|item| item.as_ref().map_or(true, |(k, _)| k.as_ref() <= from))
+                    .collect();
+                items.reverse();
+                Box::new(items.into_iter())
+            }
+        }
+    }
+    /// 访问时删除了已过期的key，[crate::RedisRocksdb]创建的WrapDb在删除生效后发送expired通知
+    fn expired(&self, _key: &[u8]) {}
 }
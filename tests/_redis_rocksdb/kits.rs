@@ -1,6 +1,7 @@
 use std::{fs, path};
 
-use rocksdb::TransactionDB;
+use redis_rocksdb::{ColumnFamilies, ExpireIndex};
+use rocksdb::{Options, TransactionDB, TransactionDBOptions};
 
 pub fn open_transaction_db(file: &str, name: &str) -> TransactionDB {
     let file_name = format!("temp/{}/{}.db", file, name);
@@ -19,3 +20,22 @@ pub fn open_rocks_db(file: &str, name: &str) -> rocksdb::DB {
     }
     rocksdb::DB::open_default(db_path).expect("")
 }
+
+/// 使用[ColumnFamilies]新建的数据库
+pub fn open_column_families_db(file: &str, name: &str) -> TransactionDB {
+    let file_name = format!("temp/{}/{}.db", file, name);
+    let db_path = path::Path::new(&file_name);
+    if !db_path.exists() {
+        fs::create_dir_all(db_path).expect("");
+    }
+    let mut opts = Options::default();
+    opts.create_if_missing(true);
+    ColumnFamilies::open(opts, &TransactionDBOptions::default(), db_path, &ExpireIndex::new()).expect("")
+}
+
+/// 用于[redis_rocksdb::RedisRocksdbBuilder]的路径
+pub fn db_path(file: &str, name: &str) -> String {
+    let file_name = format!("temp/{}/{}.db", file, name);
+    fs::create_dir_all(&file_name).expect("");
+    file_name
+}
@@ -1,5 +1,25 @@
 mod kits;
 
+mod test_bitmap_impl;
+mod test_bptree_impl;
+mod test_column_families;
+mod test_expire_impl;
+mod test_geo_impl;
 mod test_heap;
+mod test_hyper_log_log_impl;
+mod test_key_space_impl;
+mod test_key_value_impl;
 mod test_list_impl;
+mod test_namespace_impl;
 mod test_object_impl;
+mod test_optimistic;
+mod test_pub_sub_impl;
+mod test_redis_rocksdb_builder;
+mod test_redis_transaction;
+mod test_resp;
+mod test_scan_impl;
+mod test_server;
+mod test_set_impl;
+mod test_sorted_set_impl;
+mod test_stack_impl;
+mod test_stream_impl;
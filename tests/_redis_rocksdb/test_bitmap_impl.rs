@@ -0,0 +1,152 @@
+use function_name::named;
+use redis_rocksdb::{
+    BitFieldOp, BitFieldType, BitOp, BitOverflow, BitRange, BitUnit, Bitmap, KeySpace, KeyType, KeyValue, RedisList, RedisRocksdb, WrapTransactionDB,
+};
+
+use crate::_redis_rocksdb::kits::open_transaction_db;
+
+#[named]
+#[test]
+fn test_bitmap() {
+    let redis_db = RedisRocksdb::new(open_transaction_db(file!(), function_name!()));
+    let wrap_db = WrapTransactionDB::new(redis_db.get_db());
+    let bitmap = RedisRocksdb::bitmap();
+    //先清除数据，以便测试可以反复运行
+    let keys: [&[u8]; 3] = [b"b", b"s", b"l"];
+    RedisRocksdb::key_space().dels(&wrap_db, &keys).expect("");
+    let key = b"b".as_slice();
+
+    assert!(!bitmap.get_bit(&wrap_db, key, 7).expect(""));
+    assert!(!bitmap.set_bit(&wrap_db, key, 7, true).expect(""));
+    assert!(bitmap.set_bit(&wrap_db, key, 7, true).expect(""));
+    assert!(bitmap.get_bit(&wrap_db, key, 7).expect(""));
+    assert!(!bitmap.get_bit(&wrap_db, key, 1000).expect(""));
+    assert_eq!(Some(b"\x01".to_vec()), redis_db.get(&key.to_vec()).expect(""));
+    assert_eq!(Some(KeyType::Bitmap), RedisRocksdb::key_space().key_type(&wrap_db, key).expect(""));
+    assert_eq!("string", KeyType::Bitmap.name());
+
+    //跨过多个块
+    bitmap.set_bit(&wrap_db, key, 100_000, true).expect("");
+    assert_eq!(12501, redis_db.str_len(&key.to_vec()).expect(""));
+    assert_eq!(2, bitmap.bit_count(&wrap_db, key, None).expect(""));
+    assert_eq!(7, bitmap.bit_pos(&wrap_db, key, true, None).expect(""));
+    let range = BitRange {
+        start: 8,
+        end: None,
+        unit: BitUnit::Bit,
+    };
+    assert_eq!(100_000, bitmap.bit_pos(&wrap_db, key, true, Some(&range)).expect(""));
+    assert!(bitmap.set_bit(&wrap_db, key, 1 << 32, true).is_err());
+
+    //普通的字符串，第一次按位修改时转换为块，字符串的方法修改时转换回来
+    let s = b"s".to_vec();
+    redis_db.put(&s, &b"foobar".to_vec()).expect("");
+    assert_eq!(26, bitmap.bit_count(&wrap_db, &s, None).expect(""));
+    let range = BitRange {
+        start: 1,
+        end: Some(1),
+        unit: BitUnit::Byte,
+    };
+    assert_eq!(6, bitmap.bit_count(&wrap_db, &s, Some(&range)).expect(""));
+    let range = BitRange {
+        start: 5,
+        end: Some(30),
+        unit: BitUnit::Bit,
+    };
+    assert_eq!(17, bitmap.bit_count(&wrap_db, &s, Some(&range)).expect(""));
+    assert!(!bitmap.set_bit(&wrap_db, &s, 6, true).expect(""));
+    assert_eq!(Some(b"goobar".to_vec()), redis_db.get(&s).expect(""));
+    assert_eq!(7, redis_db.append(&s, &b"!".to_vec()).expect(""));
+    assert_eq!(Some(b"goobar!".to_vec()), redis_db.get(&s).expect(""));
+    assert_eq!(Some(KeyType::String), RedisRocksdb::key_space().key_type(&wrap_db, &s).expect(""));
+
+    //查找0，没有指定end时返回字符串之后的位置
+    redis_db.put(&s, &b"\xff\xff".to_vec()).expect("");
+    assert_eq!(16, bitmap.bit_pos(&wrap_db, &s, false, None).expect(""));
+    let range = BitRange {
+        start: 0,
+        end: Some(-1),
+        unit: BitUnit::Byte,
+    };
+    assert_eq!(-1, bitmap.bit_pos(&wrap_db, &s, false, Some(&range)).expect(""));
+    assert_eq!(0, bitmap.bit_pos(&wrap_db, b"none", false, None).expect(""));
+    assert_eq!(-1, bitmap.bit_pos(&wrap_db, b"none", true, None).expect(""));
+
+    //类型不对
+    let list_key = b"l".to_vec();
+    redis_db.list_push_back(&list_key, &b"a".to_vec()).expect("");
+    assert!(bitmap.set_bit(&wrap_db, &list_key, 1, true).is_err());
+    assert!(bitmap.bit_count(&wrap_db, &list_key, None).is_err());
+}
+
+#[named]
+#[test]
+fn test_bitmap_op() {
+    let redis_db = RedisRocksdb::new(open_transaction_db(file!(), function_name!()));
+    let wrap_db = WrapTransactionDB::new(redis_db.get_db());
+    let bitmap = RedisRocksdb::bitmap();
+    //先清除数据，以便测试可以反复运行
+    let keys: [&[u8]; 3] = [b"a", b"b", b"d"];
+    RedisRocksdb::key_space().dels(&wrap_db, &keys).expect("");
+    let (a, b, dst) = (keys[0], keys[1], keys[2]);
+
+    redis_db.put(&a.to_vec(), &b"foobar".to_vec()).expect("");
+    redis_db.put(&b.to_vec(), &b"abcdef".to_vec()).expect("");
+    assert_eq!(6, bitmap.bit_op(&wrap_db, BitOp::And, dst, &[a, b]).expect(""));
+    assert_eq!(Some(b"`bc`ab".to_vec()), redis_db.get(&dst.to_vec()).expect(""));
+    assert_eq!(6, bitmap.bit_op(&wrap_db, BitOp::Or, dst, &[a, b]).expect(""));
+    assert_eq!(Some(b"goofev".to_vec()), redis_db.get(&dst.to_vec()).expect(""));
+    //短的字符串用0补齐，dst也可以参与运算
+    assert_eq!(6, bitmap.bit_op(&wrap_db, BitOp::Xor, dst, &[dst, b"none"]).expect(""));
+    assert_eq!(Some(b"goofev".to_vec()), redis_db.get(&dst.to_vec()).expect(""));
+    assert_eq!(6, bitmap.bit_op(&wrap_db, BitOp::Not, dst, &[dst]).expect(""));
+    assert_eq!(Some(b"\x98\x90\x90\x99\x9a\x89".to_vec()), redis_db.get(&dst.to_vec()).expect(""));
+    assert!(bitmap.bit_op(&wrap_db, BitOp::Not, dst, &[a, b]).is_err());
+    //结果为空时删除dst
+    assert_eq!(0, bitmap.bit_op(&wrap_db, BitOp::And, dst, &[b"none"]).expect(""));
+    assert_eq!(None, redis_db.get(&dst.to_vec()).expect(""));
+}
+
+#[named]
+#[test]
+fn test_bitmap_field() {
+    let redis_db = RedisRocksdb::new(open_transaction_db(file!(), function_name!()));
+    let wrap_db = WrapTransactionDB::new(redis_db.get_db());
+    let bitmap = RedisRocksdb::bitmap();
+    //先清除数据，以便测试可以反复运行
+    let keys: [&[u8]; 1] = [b"f"];
+    RedisRocksdb::key_space().dels(&wrap_db, &keys).expect("");
+    let key = keys[0];
+    let i8 = BitFieldType { signed: true, bits: 8 };
+    let u2 = BitFieldType { signed: false, bits: 2 };
+
+    //只读取时不创建key
+    assert_eq!(vec![Some(0)], bitmap.bit_field(&wrap_db, key, &[BitFieldOp::Get(i8, 0)]).expect(""));
+    assert_eq!(None, redis_db.get(&key.to_vec()).expect(""));
+
+    let ops = [
+        BitFieldOp::Set(i8, 0, -1),
+        BitFieldOp::Get(BitFieldType { signed: false, bits: 8 }, 0),
+        BitFieldOp::IncrBy(i8, 0, 10),
+        BitFieldOp::IncrBy(u2, 100, 1),
+    ];
+    assert_eq!(vec![Some(0), Some(255), Some(9), Some(1)], bitmap.bit_field(&wrap_db, key, &ops).expect(""));
+    assert_eq!(13, redis_db.str_len(&key.to_vec()).expect(""));
+
+    //溢出
+    let ops = [
+        BitFieldOp::IncrBy(u2, 100, 4),
+        BitFieldOp::Overflow(BitOverflow::Sat),
+        BitFieldOp::IncrBy(u2, 100, 4),
+        BitFieldOp::IncrBy(i8, 0, 200),
+        BitFieldOp::Overflow(BitOverflow::Fail),
+        BitFieldOp::IncrBy(i8, 0, 1),
+        BitFieldOp::Set(u2, 100, 4),
+        BitFieldOp::Get(i8, 0),
+    ];
+    let re = bitmap.bit_field(&wrap_db, key, &ops).expect("");
+    assert_eq!(vec![Some(1), Some(3), Some(127), None, None, Some(127)], re);
+
+    let invalid = BitFieldType { signed: false, bits: 64 };
+    assert!(bitmap.bit_field(&wrap_db, key, &[BitFieldOp::Get(invalid, 0)]).is_err());
+}
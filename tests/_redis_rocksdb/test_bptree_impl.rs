@@ -0,0 +1,150 @@
+use std::ops::Bound;
+
+use function_name::named;
+use redis_rocksdb::{BPTree, BPTreeImpl, RedisRocksdb, WrapDb, WrapRocksDb, WrapTransaction, WrapTransactionDB};
+
+use crate::_redis_rocksdb::kits::{open_rocks_db, open_transaction_db};
+
+#[named]
+#[test]
+fn test_bptree() {
+    {
+        let redis_db = RedisRocksdb::new(open_transaction_db(file!(), function_name!()));
+        let wrap_db = WrapTransactionDB::new(redis_db.get_db());
+        tt_bptree(&wrap_db, RedisRocksdb::bp_tree());
+        //度数小时，更容易测试分裂与合并
+        tt_bptree(&wrap_db, BPTreeImpl::new(2));
+
+        let trans = redis_db.get_db().transaction();
+        let wrap_trans = WrapTransaction::new(&trans);
+        tt_bptree(&wrap_trans, BPTreeImpl::new(3));
+        let _ = trans.commit();
+    }
+    {
+        let rocks_db = open_rocks_db(file!(), function_name!());
+        let wrap_rocks_db = WrapRocksDb { db: &rocks_db };
+        tt_bptree(&wrap_rocks_db, BPTreeImpl::new(2));
+    }
+}
+
+fn field(i: u32) -> Vec<u8> {
+    format!("field_{:05}", i).into_bytes()
+}
+
+fn tt_bptree<T: WrapDb>(wrap_db: &T, tree: impl BPTree<T>) {
+    let key = vec![0 as u8, 1, 2];
+    let _ = tree.del_key(wrap_db, &key); //删除所有内容，以便多次测试
+    {
+        //测试没有数据的情况
+        assert_eq!(None, tree.len(wrap_db, &key).expect(""));
+        assert_eq!(None, tree.get_first(wrap_db, &key).expect(""));
+        assert_eq!(None, tree.del_last(wrap_db, &key).expect(""));
+        assert_eq!(None, tree.del(wrap_db, &key, &field(1)).expect(""));
+        tree.set_exist(wrap_db, &key, &field(1), b"v").expect("");
+        assert_eq!(None, tree.get(wrap_db, &key, &field(1)).expect(""));
+    }
+
+    let count = 500u32;
+    {
+        //乱序插入
+        for i in 0..count {
+            let f = field((i * 7919) % count);
+            tree.set(wrap_db, &key, &f, &f).expect("");
+        }
+        assert_eq!(Some(count as u64), tree.len(wrap_db, &key).expect(""));
+        tree.set(wrap_db, &key, &field(3), b"new").expect("");
+        tree.set_not_exist(wrap_db, &key, &field(4), b"new").expect("");
+        tree.set_exist(wrap_db, &key, &field(5), b"new").expect("");
+        assert_eq!(Some(count as u64), tree.len(wrap_db, &key).expect(""));
+        assert_eq!(Some(b"new".to_vec()), tree.get(wrap_db, &key, &field(3)).expect(""));
+        assert_eq!(Some(field(4)), tree.get(wrap_db, &key, &field(4)).expect(""));
+        assert_eq!(Some(b"new".to_vec()), tree.get(wrap_db, &key, &field(5)).expect(""));
+
+        assert_eq!(Some((field(0), field(0))), tree.get_first(wrap_db, &key).expect(""));
+        assert_eq!(Some((field(count - 1), field(count - 1))), tree.get_last(wrap_db, &key).expect(""));
+    }
+    {
+        //删除所有的偶数
+        for i in (0..count).filter(|i| i % 2 == 0) {
+            let f = field(i);
+            assert!(tree.del(wrap_db, &key, &f).expect("").is_some());
+            assert_eq!(None, tree.del(wrap_db, &key, &f).expect(""));
+        }
+        assert_eq!(Some((count / 2) as u64), tree.len(wrap_db, &key).expect(""));
+        assert_eq!(Some((field(1), field(1))), tree.get_first(wrap_db, &key).expect(""));
+
+        //从两头取出，一定是有序的
+        let mut expected: Vec<u32> = (0..count).filter(|i| i % 2 == 1).collect();
+        while !expected.is_empty() {
+            let first = expected.remove(0);
+            let (f, _) = tree.del_first(wrap_db, &key).expect("").expect("");
+            assert_eq!(field(first), f);
+            if let Some(last) = expected.pop() {
+                let (f, _) = tree.del_last(wrap_db, &key).expect("").expect("");
+                assert_eq!(field(last), f);
+            }
+        }
+        assert_eq!(None, tree.len(wrap_db, &key).expect(""));
+        assert_eq!(None, tree.get_first(wrap_db, &key).expect(""));
+    }
+    {
+        tree.set(wrap_db, &key, &field(1), b"v").expect("");
+        tree.del_key(wrap_db, &key).expect("");
+        assert_eq!(None, tree.len(wrap_db, &key).expect(""));
+        assert_eq!(None, tree.get(wrap_db, &key, &field(1)).expect(""));
+    }
+}
+
+#[named]
+#[test]
+fn test_bptree_range() {
+    let redis_db = RedisRocksdb::new(open_transaction_db(file!(), function_name!()));
+    let wrap_db = WrapTransactionDB::new(redis_db.get_db());
+    let tree = BPTreeImpl::new(2);
+    let key = function_name!().as_bytes();
+    let _ = tree.del_key(&wrap_db, key); //删除所有内容，以便多次测试
+    assert!(tree.range(&wrap_db, key, Bound::Unbounded, Bound::Unbounded, false, None).expect("").is_empty());
+
+    let count = 300u32;
+    for i in 0..count {
+        let f = field((i * 7919) % count);
+        tree.set(&wrap_db, key, &f, &f).expect("");
+    }
+    let fields = |re: Vec<(Vec<u8>, Vec<u8>)>| re.into_iter().map(|(f, _)| f).collect::<Vec<_>>();
+
+    let re = tree
+        .range(&wrap_db, key, Bound::Included(&field(10)), Bound::Excluded(&field(14)), false, None)
+        .expect("");
+    assert_eq!((10..14).map(field).collect::<Vec<_>>(), fields(re));
+    let re = tree
+        .range(&wrap_db, key, Bound::Excluded(&field(10)), Bound::Included(&field(14)), true, None)
+        .expect("");
+    assert_eq!((11..=14).rev().map(field).collect::<Vec<_>>(), fields(re));
+    let re = tree.range(&wrap_db, key, Bound::Unbounded, Bound::Unbounded, true, Some(3)).expect("");
+    assert_eq!(vec![field(299), field(298), field(297)], fields(re));
+
+    //分页读取所有的字段
+    let mut all = Vec::new();
+    let mut cursor: Option<Vec<u8>> = None;
+    loop {
+        let start = match &cursor {
+            None => Bound::Unbounded,
+            Some(c) => Bound::Excluded(c.as_slice()),
+        };
+        let page = fields(tree.range(&wrap_db, key, start, Bound::Unbounded, false, Some(7)).expect(""));
+        if page.is_empty() {
+            break;
+        }
+        cursor = page.last().cloned();
+        all.extend(page);
+    }
+    assert_eq!((0..count).map(field).collect::<Vec<_>>(), all);
+
+    //删除后，leaf之间的链接仍然正确
+    for i in (0..count).filter(|i| i % 3 != 0) {
+        tree.del(&wrap_db, key, &field(i)).expect("");
+    }
+    let it = tree.iter(&wrap_db, key, Bound::Unbounded, Bound::Unbounded, true).expect("");
+    let re: Vec<Vec<u8>> = it.map(|kv| kv.expect("").0).collect();
+    assert_eq!((0..count).rev().filter(|i| i % 3 == 0).map(field).collect::<Vec<_>>(), re);
+}
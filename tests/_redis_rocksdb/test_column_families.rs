@@ -0,0 +1,58 @@
+use function_name::named;
+use redis_rocksdb::{
+    rocksdb::{IteratorMode, Options, TransactionDBOptions},
+    ColumnFamilies, ExpireIndex, Heap, KeySpace, KeyValue, Object, RedisList, RedisRocksdb,
+};
+
+use crate::_redis_rocksdb::kits::{open_column_families_db, open_transaction_db};
+
+fn count_cf(redis_db: &RedisRocksdb, name: &str) -> usize {
+    let db = redis_db.get_db();
+    db.iterator_cf(db.cf_handle(name).expect(""), IteratorMode::Start).count()
+}
+
+#[named]
+#[test]
+fn test_column_families() {
+    let redis_db = RedisRocksdb::new(open_column_families_db(file!(), function_name!()));
+    let wrap_db = redis_db.wrap_db();
+    //先清除数据，以便测试可以反复运行
+    let keys: [&[u8]; 4] = [b"s", b"l", b"h", b"heap"];
+    RedisRocksdb::key_space().dels(&wrap_db, &keys).expect("");
+    for name in ColumnFamilies::NAMES {
+        assert_eq!(0, count_cf(&redis_db, name));
+    }
+
+    redis_db.put(&b"s".to_vec(), &b"v".to_vec()).expect("");
+    redis_db.list_push_back(&b"l".to_vec(), &b"a".to_vec()).expect("");
+    RedisRocksdb::object().set(&wrap_db, b"h", b"f", b"v").expect("");
+    RedisRocksdb::max_heap().push(&wrap_db, b"heap", b"f", b"v").expect("");
+    assert_eq!(1, count_cf(&redis_db, ColumnFamilies::STRING));
+    assert!(count_cf(&redis_db, ColumnFamilies::LIST) > 0);
+    assert!(count_cf(&redis_db, ColumnFamilies::HASH) > 0);
+    assert!(count_cf(&redis_db, ColumnFamilies::HEAP) > 0);
+
+    //通过api读取与遍历都按key选择column family
+    assert_eq!(Some(b"v".to_vec()), redis_db.get(&b"s".to_vec()).expect(""));
+    assert_eq!(vec![b"a".to_vec()], redis_db.list_range(&b"l".to_vec(), 0, -1).expect(""));
+    assert_eq!(Some(b"v".to_vec()), RedisRocksdb::object().get(&wrap_db, b"h", b"f").expect(""));
+    assert_eq!(Some(vec![b"f".to_vec()]), RedisRocksdb::object().keys(&wrap_db, b"h").expect(""));
+    assert_eq!(Some(1), RedisRocksdb::max_heap().len(&wrap_db, b"heap").expect(""));
+
+    assert_eq!(4, RedisRocksdb::key_space().dels(&wrap_db, &keys).expect(""));
+    for name in ColumnFamilies::NAMES {
+        assert_eq!(0, count_cf(&redis_db, name));
+    }
+}
+
+#[named]
+#[test]
+fn test_column_families_existing_db() {
+    let path = format!("temp/{}/{}.db", file!(), function_name!());
+    //没有使用column family的数据库不能使用column family打开
+    drop(open_transaction_db(file!(), function_name!()));
+    let mut opts = Options::default();
+    opts.create_if_missing(true);
+    assert!(ColumnFamilies::open(opts, &TransactionDBOptions::default(), &path, &ExpireIndex::new()).is_err());
+    drop(open_transaction_db(file!(), function_name!()));
+}
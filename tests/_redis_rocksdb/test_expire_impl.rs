@@ -0,0 +1,132 @@
+use std::{sync::Arc, thread, time::Duration};
+
+use function_name::named;
+use redis_rocksdb::{
+    rocksdb::{Direction, IteratorMode, TransactionDB},
+    ExpireSweeper, KeyExpire, KeyValue, Object, RedisList, RedisRocksdb, SortedSet, Stack, WrapTransactionDB, ZAddOptions,
+};
+
+use crate::_redis_rocksdb::kits::open_transaction_db;
+
+/// 保留key格式的版本，不然再次打开时会被当作旧格式的数据库
+fn clear_db(db: &TransactionDB) {
+    let keys: Vec<Box<[u8]>> = db.iterator(IteratorMode::Start).map(|kv| kv.expect("").0).collect();
+    for k in keys {
+        if k.as_ref() != b"\0layout_version" {
+            db.delete(k).expect("");
+        }
+    }
+}
+
+/// 以0开始的是数据库自己的记录，如key格式的版本
+fn count_db(db: &TransactionDB) -> usize {
+    db.iterator(IteratorMode::From(&[1], Direction::Forward)).count()
+}
+
+/// 写入各种类型的key，返回所有的key
+fn put_all_types(redis_db: &RedisRocksdb) -> Vec<Vec<u8>> {
+    let wrap_db = WrapTransactionDB::new(redis_db.get_db());
+    let list_key = b"list".to_vec();
+    for i in 0..600 {
+        redis_db.list_push_back(&list_key, &format!("value {}", i).into_bytes()).expect("");
+    }
+    let stack_key = b"stack".to_vec();
+    for i in 0..10u8 {
+        redis_db.push(&stack_key, &vec![i]).expect("");
+    }
+    let object_key = b"object".to_vec();
+    let object = RedisRocksdb::object();
+    object.set(&wrap_db, &object_key, b"f1", b"v1").expect("");
+    object.set(&wrap_db, &object_key, b"f2", b"v2").expect("");
+    let zset_key = b"zset".to_vec();
+    RedisRocksdb::sorted_set()
+        .add(&wrap_db, &zset_key, &[(1.0, b"a".as_ref()), (2.0, b"b".as_ref())], &ZAddOptions::default())
+        .expect("");
+    vec![list_key, stack_key, object_key, zset_key]
+}
+
+#[named]
+#[test]
+fn test_expire_ttl() {
+    let redis_db = RedisRocksdb::new(open_transaction_db(file!(), function_name!()));
+    clear_db(redis_db.get_db()); //先清除数据，以便测试可以反复运行
+    let key = function_name!().as_bytes().to_vec();
+
+    assert!(!redis_db.expire(&key, 10).expect(""));
+    assert_eq!(-2, redis_db.ttl(&key).expect(""));
+    assert!(!redis_db.persist(&key).expect(""));
+
+    redis_db.put(&key, &b"value".to_vec()).expect("");
+    assert_eq!(-1, redis_db.ttl(&key).expect(""));
+    assert!(redis_db.expire(&key, 100).expect(""));
+    assert_eq!(100, redis_db.ttl(&key).expect(""));
+    let pttl = redis_db.pttl(&key).expect("");
+    assert!(pttl > 99_000 && pttl <= 100_000);
+
+    assert!(redis_db.persist(&key).expect(""));
+    assert!(!redis_db.persist(&key).expect(""));
+    assert_eq!(-1, redis_db.pttl(&key).expect(""));
+
+    //与redis的set一样，put会清除过期时间
+    assert!(redis_db.pexpire(&key, 100_000).expect(""));
+    redis_db.put(&key, &b"new".to_vec()).expect("");
+    assert_eq!(-1, redis_db.ttl(&key).expect(""));
+
+    assert!(redis_db.pexpire(&key, 1).expect(""));
+    thread::sleep(Duration::from_millis(10));
+    assert_eq!(None, redis_db.get(&key).expect(""));
+    assert_eq!(-2, redis_db.ttl(&key).expect(""));
+
+    //过去的时间，直接删除
+    redis_db.put(&key, &b"value".to_vec()).expect("");
+    assert!(redis_db.expire_at(&key, 1).expect(""));
+    assert_eq!(None, redis_db.get(&key).expect(""));
+    assert_eq!(0, count_db(redis_db.get_db()));
+}
+
+#[named]
+#[test]
+fn test_expire_lazy() {
+    let redis_db = RedisRocksdb::new(open_transaction_db(file!(), function_name!()));
+    clear_db(redis_db.get_db()); //先清除数据，以便测试可以反复运行
+    let keys = put_all_types(&redis_db);
+    for key in &keys {
+        assert!(redis_db.pexpire(key, 1).expect(""));
+    }
+    thread::sleep(Duration::from_millis(10));
+
+    let wrap_db = WrapTransactionDB::new(redis_db.get_db());
+    assert_eq!(-1, redis_db.list_len(&keys[0]).expect(""));
+    assert_eq!(-1, redis_db.len(&keys[1]).expect(""));
+    assert_eq!(None, RedisRocksdb::object().get(&wrap_db, &keys[2], b"f1").expect(""));
+    assert_eq!(None, RedisRocksdb::sorted_set().len(&wrap_db, &keys[3]).expect(""));
+    //所有的子key与过期时间都已删除
+    assert_eq!(0, count_db(redis_db.get_db()));
+
+    //过期后新建的key，没有过期时间
+    RedisRocksdb::object().set(&wrap_db, &keys[2], b"f1", b"v1").expect("");
+    assert_eq!(-1, redis_db.ttl(&keys[2]).expect(""));
+}
+
+#[named]
+#[test]
+fn test_expire_sweeper() {
+    let redis_db = Arc::new(RedisRocksdb::new(open_transaction_db(file!(), function_name!())));
+    clear_db(redis_db.get_db()); //先清除数据，以便测试可以反复运行
+    let keys = put_all_types(&redis_db);
+    for key in &keys[1..] {
+        assert!(redis_db.pexpire(key, 1).expect(""));
+    }
+    assert!(redis_db.expire(&keys[0], 100).expect(""));
+    thread::sleep(Duration::from_millis(10));
+    assert_eq!(3, redis_db.sweep_expired(100).expect(""));
+    assert_eq!(0, redis_db.sweep_expired(100).expect(""));
+    assert_eq!(600, redis_db.list_len(&keys[0]).expect(""));
+
+    let sweeper = ExpireSweeper::start(&redis_db, Duration::from_millis(5));
+    assert!(redis_db.pexpire(&keys[0], 1).expect(""));
+    thread::sleep(Duration::from_millis(200));
+    //没有访问key，由后台线程删除
+    assert_eq!(0, count_db(redis_db.get_db()));
+    drop(sweeper);
+}
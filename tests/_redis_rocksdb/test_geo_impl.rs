@@ -0,0 +1,153 @@
+use function_name::named;
+use redis_rocksdb::{
+    Geo, GeoCoord, GeoFrom, GeoOrder, GeoSearch, GeoShape, KeySpace, KeyType, RedisList, RedisRocksdb, SortedSet, WrapTransactionDB, ZAddOptions, ZExist,
+};
+
+use crate::_redis_rocksdb::kits::open_transaction_db;
+
+fn search(from: GeoFrom, shape: GeoShape) -> GeoSearch {
+    GeoSearch {
+        from,
+        shape,
+        order: Some(GeoOrder::Asc),
+        count: None,
+        any: false,
+    }
+}
+
+#[named]
+#[test]
+fn test_geo() {
+    let redis_db = RedisRocksdb::new(open_transaction_db(file!(), function_name!()));
+    let wrap_db = WrapTransactionDB::new(redis_db.get_db());
+    let geo = RedisRocksdb::geo();
+    //先清除数据，以便测试可以反复运行
+    let keys: [&[u8]; 2] = [b"g", b"l"];
+    RedisRocksdb::key_space().dels(&wrap_db, &keys).expect("");
+    let key = keys[0];
+    let opts = ZAddOptions::default();
+
+    let palermo = GeoCoord::new(13.361389, 38.115556);
+    let catania = GeoCoord::new(15.087269, 37.502669);
+    assert_eq!(2, geo.add(&wrap_db, key, &[(palermo, b"Palermo"), (catania, b"Catania")], &opts).expect(""));
+    assert_eq!(Some(KeyType::SortedSet), RedisRocksdb::key_space().key_type(&wrap_db, key).expect(""));
+    //与redis一样的score
+    assert_eq!(Some(3479099956230698.0), RedisRocksdb::sorted_set().score(&wrap_db, key, b"Palermo").expect(""));
+    let nx = ZAddOptions {
+        exist: Some(ZExist::Nx),
+        ..Default::default()
+    };
+    assert_eq!(0, geo.add(&wrap_db, key, &[(catania, b"Palermo")], &nx).expect(""));
+    assert!(geo.add(&wrap_db, key, &[(GeoCoord::new(181.0, 0.0), b"x")], &opts).is_err());
+    assert!(geo.add(&wrap_db, key, &[(GeoCoord::new(0.0, 86.0), b"x")], &opts).is_err());
+
+    //geopos使用格子的中心
+    let pos = geo.pos(&wrap_db, key, &[b"Palermo", b"none"]).expect("");
+    let p = pos[0].expect("");
+    assert!((p.longitude - palermo.longitude).abs() < 1e-5 && (p.latitude - palermo.latitude).abs() < 1e-5);
+    assert_eq!(None, pos[1]);
+
+    let dist = geo.dist(&wrap_db, key, b"Palermo", b"Catania").expect("").expect("");
+    assert_eq!("166274.1516", format!("{:.4}", dist));
+    assert_eq!(None, geo.dist(&wrap_db, key, b"Palermo", b"none").expect(""));
+
+    let hashes = geo.hash(&wrap_db, key, &[b"Palermo", b"Catania", b"none"]).expect("");
+    assert_eq!(vec![Some("sqc8b49rny0".to_owned()), Some("sqdtr74hyu0".to_owned()), None], hashes);
+
+    //search
+    let center = GeoFrom::Coord(GeoCoord::new(15.0, 37.0));
+    let re = geo.search(&wrap_db, key, &search(center.clone(), GeoShape::Radius(200_000.0))).expect("");
+    assert_eq!(
+        vec![b"Catania".to_vec(), b"Palermo".to_vec()],
+        re.iter().map(|m| m.member.clone()).collect::<Vec<_>>()
+    );
+    assert_eq!("56.4413", format!("{:.4}", re[0].dist / 1000.0));
+    assert_eq!("190.4424", format!("{:.4}", re[1].dist / 1000.0));
+    let re = geo.search(&wrap_db, key, &search(center.clone(), GeoShape::Radius(100_000.0))).expect("");
+    assert_eq!(1, re.len());
+    let shape = GeoShape::Box {
+        width: 400_000.0,
+        height: 400_000.0,
+    };
+    let re = geo
+        .search(
+            &wrap_db,
+            key,
+            &GeoSearch {
+                order: Some(GeoOrder::Desc),
+                ..search(center.clone(), shape)
+            },
+        )
+        .expect("");
+    assert_eq!(
+        vec![b"Palermo".to_vec(), b"Catania".to_vec()],
+        re.iter().map(|m| m.member.clone()).collect::<Vec<_>>()
+    );
+    let re = geo
+        .search(
+            &wrap_db,
+            key,
+            &GeoSearch {
+                count: Some(1),
+                ..search(center, shape)
+            },
+        )
+        .expect("");
+    assert_eq!(b"Catania".to_vec(), re[0].member);
+    assert_eq!(1, re.len());
+    let re = geo
+        .search(&wrap_db, key, &search(GeoFrom::Member(b"Palermo".to_vec()), GeoShape::Radius(1.0)))
+        .expect("");
+    assert_eq!(vec![b"Palermo".to_vec()], re.iter().map(|m| m.member.clone()).collect::<Vec<_>>());
+    assert!(geo
+        .search(&wrap_db, key, &search(GeoFrom::Member(b"none".to_vec()), GeoShape::Radius(1.0)))
+        .is_err());
+    //key不存在
+    assert!(geo
+        .search(&wrap_db, b"none", &search(GeoFrom::Member(b"none".to_vec()), GeoShape::Radius(1.0)))
+        .expect("")
+        .is_empty());
+
+    //其它的类型
+    redis_db.list_push_back(&b"l".to_vec(), &b"a".to_vec()).expect("");
+    assert!(geo.pos(&wrap_db, b"l", &[b"a"]).is_err());
+}
+
+#[named]
+#[test]
+fn test_geo_search_edges() {
+    let redis_db = RedisRocksdb::new(open_transaction_db(file!(), function_name!()));
+    let wrap_db = WrapTransactionDB::new(redis_db.get_db());
+    let geo = RedisRocksdb::geo();
+    //先清除数据，以便测试可以反复运行
+    let keys: [&[u8]; 1] = [b"g"];
+    RedisRocksdb::key_space().dels(&wrap_db, &keys).expect("");
+    let key = keys[0];
+
+    //180度经线的两侧
+    let members = [
+        (GeoCoord::new(179.99, 10.0), &b"east"[..]),
+        (GeoCoord::new(-179.99, 10.0), &b"west"[..]),
+        (GeoCoord::new(0.0, 10.0), &b"far"[..]),
+    ];
+    geo.add(&wrap_db, key, &members, &ZAddOptions::default()).expect("");
+    let re = geo
+        .search(&wrap_db, key, &search(GeoFrom::Coord(GeoCoord::new(180.0, 10.0)), GeoShape::Radius(10_000.0)))
+        .expect("");
+    let mut found = re.iter().map(|m| m.member.clone()).collect::<Vec<_>>();
+    found.sort();
+    assert_eq!(vec![b"east".to_vec(), b"west".to_vec()], found);
+
+    //很大的范围包含所有的成员
+    let re = geo
+        .search(&wrap_db, key, &search(GeoFrom::Coord(GeoCoord::new(0.0, 0.0)), GeoShape::Radius(30_000_000.0)))
+        .expect("");
+    assert_eq!(3, re.len());
+    //ANY时找到count个就停止
+    let any = GeoSearch {
+        count: Some(2),
+        any: true,
+        ..search(GeoFrom::Coord(GeoCoord::new(0.0, 0.0)), GeoShape::Radius(30_000_000.0))
+    };
+    assert_eq!(2, geo.search(&wrap_db, key, &any).expect("").len());
+}
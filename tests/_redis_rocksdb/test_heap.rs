@@ -4,7 +4,7 @@ use std::{
 };
 
 use function_name::named;
-use redis_rocksdb::{write_int, Heap, MaxHeap, RedisRocksdb, WrapDb, WrapRocksDb, WrapTransaction, WrapTransactionDB};
+use redis_rocksdb::{write_int, Heap, KeySpace, MaxHeap, RedisRocksdb, WrapDb, WrapRocksDb, WrapTransaction, WrapTransactionDB};
 
 use crate::_redis_rocksdb::kits::{open_rocks_db, open_transaction_db};
 
@@ -14,12 +14,12 @@ fn test_heap() {
     {
         let redis_db = RedisRocksdb::new(open_transaction_db(file!(), function_name!()));
 
-        let wrap_db = WrapTransactionDB { db: redis_db.get_db() };
+        let wrap_db = WrapTransactionDB::new(redis_db.get_db());
         tt_heap(&wrap_db, RedisRocksdb::max_heap());
         tt_heap(&wrap_db, RedisRocksdb::mix_heap());
 
         let trans = redis_db.get_db().transaction();
-        let wrap_trans = WrapTransaction { db: &trans };
+        let wrap_trans = WrapTransaction::new(&trans);
         tt_heap(&wrap_trans, RedisRocksdb::max_heap());
         tt_heap(&wrap_trans, RedisRocksdb::mix_heap());
         let _ = trans.rollback();
@@ -38,7 +38,8 @@ fn tt_heap<T: WrapDb>(db: &T, heap: impl Heap<T> + 'static) {
     let field = vec![6 as u8, 7, 8];
     let value = "data".to_owned();
 
-    let _ = heap.remove_key(db, &key);
+    //max heap与min heap使用同一个key，类型不一样，所以使用key space删除
+    let _ = RedisRocksdb::key_space().del(db, &key);
 
     {
         let re = heap.peek(db, &key);
@@ -62,7 +63,7 @@ fn tt_heap<T: WrapDb>(db: &T, heap: impl Heap<T> + 'static) {
         let re = heap.pop(db, &key);
         assert_eq!(None, re.expect(""));
         let re = heap.len(db, &key);
-        assert_eq!(Some(0), re.expect(""));
+        assert_eq!(None, re.expect(""));
     }
 
     {
@@ -111,7 +112,7 @@ mod sample {
     fn sample() {
         let trans_db = TransactionDB::open_default("db_name.db").expect("");
         let redis_db = RedisRocksdb::new(trans_db);
-        let wrap_db = WrapTransactionDB { db: redis_db.get_db() };
+        let wrap_db = WrapTransactionDB::new(redis_db.get_db());
 
         let max_heap = RedisRocksdb::max_heap();
         let key = vec![0 as u8, 1, 2];
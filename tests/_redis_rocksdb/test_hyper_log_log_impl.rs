@@ -0,0 +1,83 @@
+use function_name::named;
+use redis_rocksdb::{HyperLogLog, KeySpace, KeyType, KeyValue, RedisList, RedisRocksdb, WrapTransactionDB};
+
+use crate::_redis_rocksdb::kits::open_transaction_db;
+
+#[named]
+#[test]
+fn test_hyper_log_log() {
+    let redis_db = RedisRocksdb::new(open_transaction_db(file!(), function_name!()));
+    let wrap_db = WrapTransactionDB::new(redis_db.get_db());
+    let hll = RedisRocksdb::hyper_log_log();
+    //先清除数据，以便测试可以反复运行
+    let keys: [&[u8]; 3] = [b"h", b"s", b"l"];
+    RedisRocksdb::key_space().dels(&wrap_db, &keys).expect("");
+    let key = keys[0];
+
+    assert_eq!(0, hll.pf_count(&wrap_db, &[key]).expect(""));
+    //没有元素时也创建key
+    assert!(hll.pf_add(&wrap_db, key, &[]).expect(""));
+    assert!(!hll.pf_add(&wrap_db, key, &[]).expect(""));
+    assert_eq!(Some(KeyType::String), RedisRocksdb::key_space().key_type(&wrap_db, key).expect(""));
+    assert!(hll.pf_add(&wrap_db, key, &[b"a", b"b", b"c"]).expect(""));
+    assert!(!hll.pf_add(&wrap_db, key, &[b"a"]).expect(""));
+    assert_eq!(3, hll.pf_count(&wrap_db, &[key]).expect(""));
+
+    //与redis一样的头部，元素少时为sparse编码
+    let value = redis_db.get(&key.to_vec()).expect("").expect("");
+    assert_eq!(b"HYLL", &value[..4]);
+    assert_eq!(1, value[4]);
+
+    //已知基数的误差
+    let mut n = 3;
+    for expected in [100, 1000, 10000, 100000] {
+        let elements: Vec<Vec<u8>> = (n..expected).map(|i| format!("e{}", i).into_bytes()).collect();
+        let elements: Vec<&[u8]> = elements.iter().map(|e| e.as_slice()).collect();
+        hll.pf_add(&wrap_db, key, &elements).expect("");
+        n = expected;
+        let count = hll.pf_count(&wrap_db, &[key]).expect("") as f64;
+        assert!((count - n as f64).abs() / (n as f64) < 0.02, "{} {}", n, count);
+    }
+    //元素多时为dense编码，16个字节的头部与16384个6 bits的寄存器
+    let value = redis_db.get(&key.to_vec()).expect("").expect("");
+    assert_eq!(0, value[4]);
+    assert_eq!(16 + 12288, value.len());
+
+    //不是HyperLogLog的字符串或其它的类型
+    redis_db.put(&b"s".to_vec(), &b"HYLL".to_vec()).expect("");
+    assert!(hll.pf_add(&wrap_db, b"s", &[b"a"]).is_err());
+    assert!(hll.pf_count(&wrap_db, &[b"s"]).is_err());
+    redis_db.list_push_back(&b"l".to_vec(), &b"a".to_vec()).expect("");
+    assert!(hll.pf_count(&wrap_db, &[b"l"]).is_err());
+}
+
+#[named]
+#[test]
+fn test_hyper_log_log_merge() {
+    let redis_db = RedisRocksdb::new(open_transaction_db(file!(), function_name!()));
+    let wrap_db = WrapTransactionDB::new(redis_db.get_db());
+    let hll = RedisRocksdb::hyper_log_log();
+    //先清除数据，以便测试可以反复运行
+    let keys: [&[u8]; 3] = [b"a", b"b", b"d"];
+    RedisRocksdb::key_space().dels(&wrap_db, &keys).expect("");
+    let (a, b, dst) = (keys[0], keys[1], keys[2]);
+
+    //a为0..3000，b为2000..5000，并集为5000
+    let elements: Vec<Vec<u8>> = (0..5000).map(|i| format!("e{}", i).into_bytes()).collect();
+    let elements: Vec<&[u8]> = elements.iter().map(|e| e.as_slice()).collect();
+    hll.pf_add(&wrap_db, a, &elements[..3000]).expect("");
+    hll.pf_add(&wrap_db, b, &elements[2000..]).expect("");
+    let union = hll.pf_count(&wrap_db, &[a, b, b"none"]).expect("");
+    assert!((union as f64 - 5000.0).abs() / 5000.0 < 0.02, "{}", union);
+
+    hll.pf_merge(&wrap_db, dst, &[a, b]).expect("");
+    assert_eq!(union, hll.pf_count(&wrap_db, &[dst]).expect(""));
+    //dst也参与合并
+    hll.pf_merge(&wrap_db, dst, &[a]).expect("");
+    assert_eq!(union, hll.pf_count(&wrap_db, &[dst]).expect(""));
+    //没有来源时创建空的dst
+    RedisRocksdb::key_space().dels(&wrap_db, &[dst]).expect("");
+    hll.pf_merge(&wrap_db, dst, &[]).expect("");
+    assert_eq!(0, hll.pf_count(&wrap_db, &[dst]).expect(""));
+    assert!(redis_db.get(&dst.to_vec()).expect("").is_some());
+}
@@ -0,0 +1,338 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use function_name::named;
+use redis_rocksdb::{
+    rocksdb::{Direction, IteratorMode, TransactionDB},
+    BPTree, Heap, KeyExpire, KeySpace, KeyType, KeyValue, Object, RedisList, RedisRocksdb, ScoreBound, SortedSet, Stack, WrapDb, WrapTransactionDB,
+    ZAddOptions,
+};
+
+use crate::_redis_rocksdb::kits::open_transaction_db;
+
+/// 保留key格式的版本，不然再次打开时会被当作旧格式的数据库
+fn clear_db(db: &TransactionDB) {
+    let keys: Vec<Box<[u8]>> = db.iterator(IteratorMode::Start).map(|kv| kv.expect("").0).collect();
+    for k in keys {
+        if k.as_ref() != b"\0layout_version" {
+            db.delete(k).expect("");
+        }
+    }
+}
+
+#[named]
+#[test]
+fn test_key_space_type() {
+    let redis_db = RedisRocksdb::new(open_transaction_db(file!(), function_name!()));
+    clear_db(redis_db.get_db()); //先清除数据，以便测试可以反复运行
+    let key_space = RedisRocksdb::key_space();
+    let string_key = b"string".to_vec();
+    let list_key = b"list".to_vec();
+    let stack_key = b"stack".to_vec();
+    let object_key = b"object".to_vec();
+    let zset_key = b"zset".to_vec();
+
+    redis_db.put(&string_key, &b"value".to_vec()).expect("");
+    redis_db.list_push_back(&list_key, &b"v1".to_vec()).expect("");
+    redis_db.push(&stack_key, &b"v1".to_vec()).expect("");
+    let wrap_db = WrapTransactionDB::new(redis_db.get_db());
+    RedisRocksdb::object().set(&wrap_db, &object_key, b"f1", b"v1").expect("");
+    RedisRocksdb::sorted_set()
+        .add(&wrap_db, &zset_key, &[(1.0, b"a".as_ref())], &ZAddOptions::default())
+        .expect("");
+
+    assert_eq!(Some(KeyType::String), key_space.key_type(&wrap_db, &string_key).expect(""));
+    assert_eq!(Some(KeyType::List), key_space.key_type(&wrap_db, &list_key).expect(""));
+    assert_eq!(Some(KeyType::Stack), key_space.key_type(&wrap_db, &stack_key).expect(""));
+    assert_eq!(Some(KeyType::Hash), key_space.key_type(&wrap_db, &object_key).expect(""));
+    assert_eq!("zset", key_space.key_type(&wrap_db, &zset_key).expect("").expect("").name());
+    assert_eq!(None, key_space.key_type(&wrap_db, b"none").expect(""));
+    assert_eq!(3, key_space.exists(&wrap_db, &[&list_key, &list_key, &stack_key, b"none"]).expect(""));
+
+    //类型不一样时返回WRONGTYPE错误，数据不变
+    assert!(redis_db.list_push_back(&object_key, &b"v1".to_vec()).is_err());
+    assert!(redis_db.push(&list_key, &b"v1".to_vec()).is_err());
+    assert!(RedisRocksdb::object().get(&wrap_db, &zset_key, b"a").is_err());
+    assert_eq!(None, redis_db.get(&list_key).expect(""));
+    assert_eq!(1, redis_db.list_len(&list_key).expect(""));
+
+    //put覆盖其它类型的key
+    redis_db.put(&list_key, &b"value".to_vec()).expect("");
+    let wrap_db = WrapTransactionDB::new(redis_db.get_db());
+    assert_eq!(Some(KeyType::String), key_space.key_type(&wrap_db, &list_key).expect(""));
+
+    assert!(key_space.del(&wrap_db, &object_key).expect(""));
+    assert!(!key_space.del(&wrap_db, &object_key).expect(""));
+    assert_eq!(None, RedisRocksdb::object().get(&wrap_db, &object_key, b"f1").expect(""));
+    assert_eq!(3, key_space.unlink(&wrap_db, &[&string_key, &list_key, &stack_key, b"none"]).expect(""));
+    assert_eq!(-1, redis_db.len(&stack_key).expect(""));
+    assert!(key_space.del(&wrap_db, &zset_key).expect(""));
+    //所有的数据与类型都已删除，只剩下以0开始的数据库自己的记录
+    assert_eq!(0, redis_db.get_db().iterator(IteratorMode::From(&[1], Direction::Forward)).count());
+}
+
+#[named]
+#[test]
+fn test_key_space_empty_removed() {
+    let redis_db = RedisRocksdb::new(open_transaction_db(file!(), function_name!()));
+    clear_db(redis_db.get_db()); //先清除数据，以便测试可以反复运行
+    let key_space = RedisRocksdb::key_space();
+    let wrap_db = redis_db.wrap_db();
+    let removed = |key: &[u8]| {
+        assert_eq!(0, key_space.exists(&wrap_db, &[key]).expect(""));
+        assert_eq!(None, key_space.key_type(&wrap_db, key).expect(""));
+    };
+
+    //最后一个元素删除后，与redis一样key也不存在了
+    let list_key = b"list".to_vec();
+    redis_db.list_push_back(&list_key, &b"v1".to_vec()).expect("");
+    redis_db.list_push_back(&list_key, &b"v2".to_vec()).expect("");
+    redis_db.list_pop_front(&list_key).expect("");
+    redis_db.list_pop_back(&list_key).expect("");
+    assert_eq!(-1, redis_db.list_len(&list_key).expect(""));
+    removed(&list_key);
+
+    let object = RedisRocksdb::object();
+    object.set(&wrap_db, b"object", b"f1", b"v1").expect("");
+    object.set(&wrap_db, b"object", b"f2", b"v2").expect("");
+    object.del(&wrap_db, b"object", b"f1").expect("");
+    assert_eq!(Some(KeyType::Hash), key_space.key_type(&wrap_db, b"object").expect(""));
+    object.del(&wrap_db, b"object", b"f2").expect("");
+    removed(b"object");
+    object.set(&wrap_db, b"object", b"f1", b"v1").expect("");
+    assert_eq!(1, object.dels(&wrap_db, b"object", &[b"f1", b"none"]).expect(""));
+    removed(b"object");
+
+    let bit_object = RedisRocksdb::bit_object();
+    bit_object.set(&wrap_db, b"bit", b"f1", b"v1").expect("");
+    bit_object.del(&wrap_db, b"bit", b"f1").expect("");
+    removed(b"bit");
+    bit_object.set(&wrap_db, b"bit", b"f1", b"v1").expect("");
+    assert_eq!(1, bit_object.dels(&wrap_db, b"bit", &[b"f1"]).expect(""));
+    removed(b"bit");
+
+    let max_heap = RedisRocksdb::max_heap();
+    max_heap.push(&wrap_db, b"max", b"1", b"v1").expect("");
+    assert!(max_heap.pop(&wrap_db, b"max").expect("").is_some());
+    removed(b"max");
+    let min_heap = RedisRocksdb::mix_heap();
+    min_heap.push(&wrap_db, b"min", b"1", b"v1").expect("");
+    assert!(min_heap.pop(&wrap_db, b"min").expect("").is_some());
+    removed(b"min");
+
+    let bp_tree = RedisRocksdb::bp_tree();
+    bp_tree.set(&wrap_db, b"tree", b"f1", b"v1").expect("");
+    bp_tree.set(&wrap_db, b"tree", b"f2", b"v2").expect("");
+    bp_tree.del(&wrap_db, b"tree", b"f1").expect("");
+    bp_tree.del(&wrap_db, b"tree", b"f2").expect("");
+    assert_eq!(None, bp_tree.len(&wrap_db, b"tree").expect(""));
+    removed(b"tree");
+
+    let zset = RedisRocksdb::sorted_set();
+    let members = [(1.0, b"a".as_ref()), (2.0, b"b".as_ref())];
+    zset.add(&wrap_db, b"zset", &members, &ZAddOptions::default()).expect("");
+    assert_eq!(2, zset.rem(&wrap_db, b"zset", &[b"a", b"b"]).expect(""));
+    removed(b"zset");
+    zset.add(&wrap_db, b"zset", &members, &ZAddOptions::default()).expect("");
+    assert_eq!(2, zset.rem_range_by_rank(&wrap_db, b"zset", 0, -1).expect(""));
+    removed(b"zset");
+    zset.add(&wrap_db, b"zset", &members, &ZAddOptions::default()).expect("");
+    assert_eq!(2, zset.rem_range_by_score(&wrap_db, b"zset", &ScoreBound::min(), &ScoreBound::max()).expect(""));
+    removed(b"zset");
+    //没有加入成员时不留下key
+    zset.add(&wrap_db, b"zset", &[], &ZAddOptions::default()).expect("");
+    removed(b"zset");
+
+    //所有的数据与类型都已删除，只剩下以0开始的数据库自己的记录
+    assert_eq!(0, redis_db.get_db().iterator(IteratorMode::From(&[1], Direction::Forward)).count());
+}
+
+#[named]
+#[test]
+fn test_key_space_rename_copy() {
+    let redis_db = RedisRocksdb::new(open_transaction_db(file!(), function_name!()));
+    clear_db(redis_db.get_db()); //先清除数据，以便测试可以反复运行
+    let key_space = RedisRocksdb::key_space();
+    let wrap_db = WrapTransactionDB::new(redis_db.get_db());
+
+    //list有多个node
+    let list_key = b"list".to_vec();
+    let list_new = b"list_new".to_vec();
+    for i in 0..600 {
+        redis_db.list_push_back(&list_key, &format!("value {}", i).into_bytes()).expect("");
+    }
+    assert!(redis_db.expire(&list_key, 100).expect(""));
+    assert!(key_space.copy(&wrap_db, &list_key, &list_new, false).expect(""));
+    assert!(!key_space.copy(&wrap_db, &list_key, &list_new, false).expect(""));
+    assert_eq!(100, redis_db.ttl(&list_new).expect(""));
+    redis_db.list_push_back(&list_new, &b"new".to_vec()).expect("");
+    assert_eq!(600, redis_db.list_len(&list_key).expect(""));
+    assert_eq!(601, redis_db.list_len(&list_new).expect(""));
+    assert_eq!(b"value 599".to_vec(), redis_db.list_index(&list_new, 599).expect(""));
+    key_space.rename(&wrap_db, &list_new, &list_key).expect("");
+    assert_eq!(-1, redis_db.list_len(&list_new).expect(""));
+    assert_eq!(601, redis_db.list_len(&list_key).expect(""));
+
+    let stack_key = b"stack".to_vec();
+    let stack_new = b"stack_new".to_vec();
+    for i in 0..10u8 {
+        redis_db.push(&stack_key, &vec![i]).expect("");
+    }
+    assert!(key_space.rename_nx(&wrap_db, &stack_key, &stack_new).expect(""));
+    assert_eq!(-1, redis_db.len(&stack_key).expect(""));
+    assert_eq!(vec![9u8], redis_db.index(&stack_new, -1).expect(""));
+    assert!(!key_space.rename_nx(&wrap_db, &stack_new, &list_key).expect(""));
+    assert!(key_space.rename(&wrap_db, &stack_key, &stack_new).is_err());
+
+    let object_key = b"object".to_vec();
+    let object_new = b"object_new".to_vec();
+    let object = RedisRocksdb::object();
+    object.set(&wrap_db, &object_key, b"f1", b"v1").expect("");
+    object.set(&wrap_db, &object_key, b"f2", b"v2").expect("");
+    assert!(key_space.copy(&wrap_db, &object_key, &stack_new, true).expect(""));
+    assert_eq!(Some(KeyType::Hash), key_space.key_type(&wrap_db, &stack_new).expect(""));
+    key_space.rename(&wrap_db, &object_key, &object_new).expect("");
+    assert_eq!(Some(2), object.len(&wrap_db, &object_new).expect(""));
+    assert_eq!(None, object.len(&wrap_db, &object_key).expect(""));
+
+    let zset_key = b"zset".to_vec();
+    let zset_new = b"zset_new".to_vec();
+    let zset = RedisRocksdb::sorted_set();
+    zset.add(&wrap_db, &zset_key, &[(1.0, b"a".as_ref()), (2.0, b"b".as_ref())], &ZAddOptions::default())
+        .expect("");
+    assert!(key_space.copy(&wrap_db, &zset_key, &zset_new, false).expect(""));
+    assert_eq!(Some(2.0), zset.score(&wrap_db, &zset_new, b"b").expect(""));
+    assert_eq!(Some(1), zset.rank(&wrap_db, &zset_new, b"b").expect(""));
+    assert_eq!(Some(2.0), zset.score(&wrap_db, &zset_key, b"b").expect(""));
+}
+
+#[named]
+#[test]
+fn test_key_space_no_collision() {
+    let redis_db = RedisRocksdb::new(open_transaction_db(file!(), function_name!()));
+    clear_db(redis_db.get_db()); //先清除数据，以便测试可以反复运行
+    let key_space = RedisRocksdb::key_space();
+    let wrap_db = WrapTransactionDB::new(redis_db.get_db());
+    let object = RedisRocksdb::object();
+
+    //旧的格式中，两个都是 "a:__:__x"
+    object.set(&wrap_db, b"a", b":__x", b"v1").expect("");
+    object.set(&wrap_db, b"a:__", b"x", b"v2").expect("");
+    redis_db.put(&b"a:__y".to_vec(), &b"v3".to_vec()).expect("");
+    assert_eq!(Some(b"v1".to_vec()), object.get(&wrap_db, b"a", b":__x").expect(""));
+    assert_eq!(Some(b"v2".to_vec()), object.get(&wrap_db, b"a:__", b"x").expect(""));
+    assert_eq!(None, object.get(&wrap_db, b"a", b"x").expect(""));
+    assert_eq!(Some(1), object.len(&wrap_db, b"a").expect(""));
+
+    assert!(key_space.del(&wrap_db, b"a").expect(""));
+    assert_eq!(Some(b"v2".to_vec()), object.get(&wrap_db, b"a:__", b"x").expect(""));
+    assert_eq!(Some(b"v3".to_vec()), redis_db.get(&b"a:__y".to_vec()).expect(""));
+}
+
+/// 旧版本写入的数据库：没有key格式的版本
+fn open_old_db(file: &str, name: &str) -> TransactionDB {
+    let db = open_transaction_db(file, name);
+    clear_db(&db); //先清除数据，以便测试可以反复运行
+    db.delete(b"\0layout_version").expect("");
+    db
+}
+
+#[named]
+#[test]
+fn test_key_space_migrate() {
+    let db = open_old_db(file!(), function_name!());
+    assert_eq!(2, RedisRocksdb::key_layout_version(&db).expect(""));
+    assert_eq!(0, RedisRocksdb::migrate_key_layout(&db).expect(""));
+
+    //写入旧的格式：类型 + 版本，过期时间，string与stack直接存放在key中，其它类型存放在 key + ":__" + field 中
+    let wrap_db = WrapTransactionDB::new(&db);
+    let old_type = |key: &[u8], key_type: KeyType| {
+        let mut v = vec![key_type as u8];
+        v.extend_from_slice(&1u64.to_le_bytes());
+        wrap_db.put(&[b"\0__key_type:".as_ref(), key].concat(), &v).expect("");
+    };
+    old_type(b"s", KeyType::String);
+    wrap_db.put(b"s", b"v").expect("");
+    let deadline = SystemTime::now().duration_since(UNIX_EPOCH).expect("").as_millis() as i64 + 100_000;
+    wrap_db.put(b"\0__expire_key:s", &deadline.to_le_bytes()).expect("");
+    wrap_db
+        .put(&[b"\0__expire_time:".as_ref(), &(deadline as u64).to_be_bytes(), b"s"].concat(), &[])
+        .expect("");
+    old_type(b"h", KeyType::Hash);
+    wrap_db.put(b"h:__f1", b"v1").expect("");
+    //属于最长的key
+    old_type(b"h:__g", KeyType::Hash);
+    wrap_db.put(b"h:__g:__f2", b"v2").expect("");
+    old_type(b"k", KeyType::Stack);
+    wrap_db.put(b"k", &2i64.to_le_bytes()).expect("");
+    wrap_db.put(&[b"k_".as_ref(), &0i64.to_le_bytes()].concat(), b"a").expect("");
+    wrap_db.put(&[b"k_".as_ref(), &1i64.to_le_bytes()].concat(), b"b").expect("");
+
+    assert_eq!(1, RedisRocksdb::key_layout_version(&db).expect(""));
+    assert_eq!(4, RedisRocksdb::migrate_key_layout(&db).expect(""));
+    assert_eq!(2, RedisRocksdb::key_layout_version(&db).expect(""));
+    assert_eq!(0, RedisRocksdb::migrate_key_layout(&db).expect(""));
+
+    let redis_db = RedisRocksdb::try_new(db).expect("");
+    let wrap_db = WrapTransactionDB::new(redis_db.get_db());
+    assert_eq!(Some(b"v".to_vec()), redis_db.get(&b"s".to_vec()).expect(""));
+    assert_eq!(100, redis_db.ttl(&b"s".to_vec()).expect(""));
+    let object = RedisRocksdb::object();
+    assert_eq!(Some(vec![(b"f1".to_vec(), b"v1".to_vec())]), object.get_all(&wrap_db, b"h").expect(""));
+    assert_eq!(Some(b"v2".to_vec()), object.get(&wrap_db, b"h:__g", b"f2").expect(""));
+    assert_eq!(2, redis_db.len(&b"k".to_vec()).expect(""));
+    assert_eq!(b"b".to_vec(), redis_db.index(&b"k".to_vec(), -1).expect(""));
+    //没有旧格式的key了
+    let keys: Vec<Box<[u8]>> = redis_db.get_db().iterator(IteratorMode::Start).map(|kv| kv.expect("").0).collect();
+    assert!(keys
+        .iter()
+        .all(|k| !k.starts_with(b"\0__") && !k.starts_with(b"h") && !k.starts_with(b"k") && k.as_ref() != b"s"));
+}
+
+#[named]
+#[test]
+fn test_key_space_migrate_baseline() {
+    {
+        //新建的数据库写入当前的版本，之后写入数据也还是当前的版本
+        let db = open_old_db(file!(), function_name!());
+        let redis_db = RedisRocksdb::try_new(db).expect("");
+        redis_db.put(&b"s".to_vec(), &b"v".to_vec()).expect("");
+        assert_eq!(2, RedisRocksdb::key_layout_version(redis_db.get_db()).expect(""));
+    }
+    {
+        //最初的版本写入的数据：没有类型与版本记录，string直接存放在key中，hash存放在 key + ":__" + field 中
+        let db = open_old_db(file!(), function_name!());
+        let wrap_db = WrapTransactionDB::new(&db);
+        wrap_db.put(b"s", b"v").expect("");
+        wrap_db.put(b"h:__f1", b"v1").expect("");
+        wrap_db.put(b"h:__f2", b"v2").expect("");
+        assert_eq!(0, RedisRocksdb::key_layout_version(&db).expect(""));
+        //旧格式的数据库不能打开，不然读取不到旧的数据
+        assert!(RedisRocksdb::try_new(db).is_err());
+    }
+
+    let db = open_transaction_db(file!(), function_name!());
+    assert_eq!(0, RedisRocksdb::key_layout_version(&db).expect(""));
+    //不知道key的类型，不转换
+    assert!(RedisRocksdb::migrate_key_layout(&db).is_err());
+    assert_eq!(0, RedisRocksdb::key_layout_version(&db).expect(""));
+
+    //没有数据的key忽略
+    let key_types: [(&[u8], KeyType); 3] = [(b"s", KeyType::String), (b"h", KeyType::Hash), (b"none", KeyType::Hash)];
+    assert_eq!(2, RedisRocksdb::migrate_key_layout_with(&db, &key_types).expect(""));
+    assert_eq!(2, RedisRocksdb::key_layout_version(&db).expect(""));
+    assert_eq!(0, RedisRocksdb::migrate_key_layout(&db).expect(""));
+
+    let redis_db = RedisRocksdb::try_new(db).expect("");
+    let wrap_db = WrapTransactionDB::new(redis_db.get_db());
+    assert_eq!(Some(b"v".to_vec()), redis_db.get(&b"s".to_vec()).expect(""));
+    let object = RedisRocksdb::object();
+    assert_eq!(
+        Some(vec![(b"f1".to_vec(), b"v1".to_vec()), (b"f2".to_vec(), b"v2".to_vec())]),
+        object.get_all(&wrap_db, b"h").expect("")
+    );
+    assert_eq!(2, redis_db.db_size().expect(""));
+    assert_eq!(None, redis_db.get(&b"none".to_vec()).expect(""));
+    //没有旧格式的key了
+    let keys: Vec<Box<[u8]>> = redis_db.get_db().iterator(IteratorMode::Start).map(|kv| kv.expect("").0).collect();
+    assert!(keys.iter().all(|k| !k.starts_with(b"\0__") && !k.starts_with(b"h") && k.as_ref() != b"s"));
+}
@@ -0,0 +1,174 @@
+use std::{sync::Arc, thread};
+
+use function_name::named;
+use redis_rocksdb::{Expiration, KeyExpire, KeySpace, KeyValue, RedisList, RedisRocksdb, SetCondition, SetOptions, WrapTransactionDB};
+
+use crate::_redis_rocksdb::kits::open_transaction_db;
+
+fn clear_keys(redis_db: &RedisRocksdb, keys: &[&[u8]]) {
+    RedisRocksdb::key_space().dels(&WrapTransactionDB::new(redis_db.get_db()), keys).expect("");
+}
+
+#[named]
+#[test]
+fn test_key_value_set() {
+    let redis_db = RedisRocksdb::new(open_transaction_db(file!(), function_name!()));
+    //先清除数据，以便测试可以反复运行
+    clear_keys(&redis_db, &[b"s", b"l"]);
+    let key = b"s".to_vec();
+
+    let nx = SetOptions {
+        condition: Some(SetCondition::NotExists),
+        ..Default::default()
+    };
+    let xx = SetOptions {
+        condition: Some(SetCondition::Exists),
+        ..Default::default()
+    };
+    assert_eq!((false, None), redis_db.put_with(&key, &b"v".to_vec(), &xx).expect(""));
+    assert_eq!((true, None), redis_db.put_with(&key, &b"v1".to_vec(), &nx).expect(""));
+    assert_eq!((false, None), redis_db.put_with(&key, &b"v2".to_vec(), &nx).expect(""));
+    assert!(!redis_db.put_not_exist(&key, &b"v2".to_vec()).expect(""));
+    assert_eq!(Some(b"v1".to_vec()), redis_db.get(&key).expect(""));
+
+    //GET返回原来的值
+    let get = SetOptions {
+        get: true,
+        ..Default::default()
+    };
+    assert_eq!((true, Some(b"v1".to_vec())), redis_db.put_with(&key, &b"v2".to_vec(), &get).expect(""));
+    assert_eq!(Some(b"v2".to_vec()), redis_db.get_set(&key, &b"v3".to_vec()).expect(""));
+
+    //过期时间，KEEPTTL保留，没有指定时移除
+    let ex = SetOptions {
+        expiration: Expiration::after(100_000),
+        ..Default::default()
+    };
+    redis_db.put_with(&key, &b"v4".to_vec(), &ex).expect("");
+    assert!(redis_db.ttl(&key).expect("") > 0);
+    let keep = SetOptions {
+        expiration: Some(Expiration::KeepTtl),
+        ..Default::default()
+    };
+    redis_db.put_with(&key, &b"v5".to_vec(), &keep).expect("");
+    assert!(redis_db.ttl(&key).expect("") > 0);
+    redis_db.put(&key, &b"v6".to_vec()).expect("");
+    assert_eq!(-1, redis_db.ttl(&key).expect(""));
+
+    //getex与getdel
+    assert_eq!(Some(b"v6".to_vec()), redis_db.get_ex(&key, Expiration::after(100_000)).expect(""));
+    assert!(redis_db.ttl(&key).expect("") > 0);
+    assert_eq!(Some(b"v6".to_vec()), redis_db.get_ex(&key, Some(Expiration::Persist)).expect(""));
+    assert_eq!(-1, redis_db.ttl(&key).expect(""));
+    assert_eq!(Some(b"v6".to_vec()), redis_db.get_del(&key).expect(""));
+    assert_eq!(None, redis_db.get_del(&key).expect(""));
+
+    //其它类型的key
+    let list_key = b"l".to_vec();
+    redis_db.list_push_back(&list_key, &b"a".to_vec()).expect("");
+    assert!(redis_db.get_set(&list_key, &b"v".to_vec()).is_err());
+    assert!(redis_db.incr_by(&list_key, 1).is_err());
+    assert_eq!((true, None), redis_db.put_with(&list_key, &b"v".to_vec(), &xx).expect(""));
+    assert_eq!(Some(b"v".to_vec()), redis_db.get(&list_key).expect(""));
+}
+
+#[named]
+#[test]
+fn test_key_value_incr() {
+    let redis_db = RedisRocksdb::new(open_transaction_db(file!(), function_name!()));
+    //先清除数据，以便测试可以反复运行
+    clear_keys(&redis_db, &[b"n", b"f", b"t"]);
+    let key = b"n".to_vec();
+
+    assert_eq!(1, redis_db.incr_by(&key, 1).expect(""));
+    assert_eq!(11, redis_db.incr_by(&key, 10).expect(""));
+    assert_eq!(8, redis_db.decr_by(&key, 3).expect(""));
+    assert!(redis_db.decr_by(&key, i64::MIN).is_err());
+    redis_db.put(&key, &i64::MAX.to_string().into_bytes()).expect("");
+    assert!(redis_db.incr_by(&key, 1).is_err());
+    assert_eq!(Some(i64::MAX.to_string().into_bytes()), redis_db.get(&key).expect(""));
+    for v in ["a", "1.5", " 1", "+1", "01", ""] {
+        redis_db.put(&key, &v.as_bytes().to_vec()).expect("");
+        assert!(redis_db.incr_by(&key, 1).is_err(), "{}", v);
+    }
+
+    //incr保留过期时间
+    redis_db.put(&key, &b"1".to_vec()).expect("");
+    redis_db.expire(&key, 100).expect("");
+    assert_eq!(2, redis_db.incr_by(&key, 1).expect(""));
+    assert!(redis_db.ttl(&key).expect("") > 0);
+
+    let key = b"f".to_vec();
+    assert_eq!(10.5, redis_db.incr_by_float(&key, 10.5).expect(""));
+    assert_eq!(Some(b"10.5".to_vec()), redis_db.get(&key).expect(""));
+    assert_eq!(5.0, redis_db.incr_by_float(&key, -5.5).expect(""));
+    assert_eq!(Some(b"5".to_vec()), redis_db.get(&key).expect(""));
+    assert!(redis_db.incr_by_float(&key, f64::INFINITY).is_err());
+    assert_eq!(Some(b"5".to_vec()), redis_db.get(&key).expect(""));
+
+    //并发的incr不会丢失更新
+    let redis_db = Arc::new(redis_db);
+    let threads: Vec<_> = (0..4)
+        .map(|_| {
+            let redis_db = redis_db.clone();
+            thread::spawn(move || {
+                for _ in 0..100 {
+                    redis_db.incr_by(&b"t".to_vec(), 1).expect("");
+                }
+            })
+        })
+        .collect();
+    for t in threads {
+        t.join().expect("");
+    }
+    assert_eq!(Some(b"400".to_vec()), redis_db.get(&b"t".to_vec()).expect(""));
+}
+
+#[named]
+#[test]
+fn test_key_value_range() {
+    let redis_db = RedisRocksdb::new(open_transaction_db(file!(), function_name!()));
+    //先清除数据，以便测试可以反复运行
+    clear_keys(&redis_db, &[b"s", b"r"]);
+    let key = b"s".to_vec();
+
+    assert_eq!(5, redis_db.append(&key, &b"Hello".to_vec()).expect(""));
+    assert_eq!(11, redis_db.append(&key, &b" World".to_vec()).expect(""));
+    assert_eq!(11, redis_db.str_len(&key).expect(""));
+    assert_eq!(0, redis_db.str_len(&b"none".to_vec()).expect(""));
+    assert_eq!(b"Hello".to_vec(), redis_db.get_range(&key, 0, 4).expect(""));
+    assert_eq!(b"rld".to_vec(), redis_db.get_range(&key, -3, -1).expect(""));
+    assert_eq!(b"Hello World".to_vec(), redis_db.get_range(&key, 0, 100).expect(""));
+    assert!(redis_db.get_range(&key, 5, 3).expect("").is_empty());
+
+    assert_eq!(11, redis_db.set_range(&key, 6, &b"Redis".to_vec()).expect(""));
+    assert_eq!(Some(b"Hello Redis".to_vec()), redis_db.get(&key).expect(""));
+    let key = b"r".to_vec();
+    assert_eq!(0, redis_db.set_range(&key, 3, &b"".to_vec()).expect(""));
+    assert_eq!(None, redis_db.get(&key).expect(""));
+    assert_eq!(5, redis_db.set_range(&key, 3, &b"ab".to_vec()).expect(""));
+    assert_eq!(Some(b"\0\0\0ab".to_vec()), redis_db.get(&key).expect(""));
+    assert!(redis_db.set_range(&key, 512 * 1024 * 1024, &b"a".to_vec()).is_err());
+}
+
+#[named]
+#[test]
+fn test_key_value_mset() {
+    let redis_db = RedisRocksdb::new(open_transaction_db(file!(), function_name!()));
+    //先清除数据，以便测试可以反复运行
+    clear_keys(&redis_db, &[b"a", b"b", b"c", b"l"]);
+    redis_db.list_push_back(&b"l".to_vec(), &b"v".to_vec()).expect("");
+
+    let items: [(&[u8], &[u8]); 2] = [(b"a", b"1"), (b"b", b"2")];
+    redis_db.mset(&items).expect("");
+    let keys: [&[u8]; 4] = [b"a", b"b", b"c", b"l"];
+    assert_eq!(vec![Some(b"1".to_vec()), Some(b"2".to_vec()), None, None], redis_db.mget(&keys).expect(""));
+
+    //有一个key存在时都不写入
+    let items: [(&[u8], &[u8]); 2] = [(b"c", b"3"), (b"a", b"4")];
+    assert!(!redis_db.mset_not_exist(&items).expect(""));
+    assert_eq!(None, redis_db.get(&b"c".to_vec()).expect(""));
+    let items: [(&[u8], &[u8]); 1] = [(b"c", b"3")];
+    assert!(redis_db.mset_not_exist(&items).expect(""));
+    assert_eq!(Some(b"3".to_vec()), redis_db.get(&b"c".to_vec()).expect(""));
+}
@@ -1,13 +1,19 @@
+use std::{
+    sync::Arc,
+    thread,
+    time::{Duration, Instant},
+};
+
 use function_name::named;
-use redis_rocksdb::{RedisList, RedisRocksdb};
+use redis_rocksdb::{KeySpace, List, Object, RedisList, RedisRocksdb, RrError, WrapDb, WrapRocksDb, WrapTransaction, WrapTransactionDB};
 
-use crate::_redis_rocksdb::kits::open_transaction_db;
+use crate::_redis_rocksdb::kits::{open_rocks_db, open_transaction_db};
 
 #[named]
 #[test]
 fn test_list_lpush() {
     let db = open_transaction_db(file!(), function_name!());
-    let mut redis_db = RedisRocksdb::new(db);
+    let redis_db = RedisRocksdb::new(db);
     let key = function_name!().as_bytes();
     let value = vec![1, 23, 6];
     let _ = redis_db.list_clear(&key); //先清除数据，以便测试可以反复运行
@@ -25,13 +31,19 @@ fn test_list_lpush() {
 
     let get_value = redis_db.list_index(&key, 1).expect("");
     assert_eq!(value, get_value);
+
+    //错误的变体
+    let re = redis_db.list_index(&key, 2);
+    assert!(matches!(re, Err(RrError::IndexOutOfRange(2))));
+    let re = redis_db.list_set(&b"none".as_ref(), 0, &value);
+    assert!(matches!(re, Err(RrError::NotFound(_))));
 }
 
 #[named]
 #[test]
 fn test_list_rpush() {
     let db = open_transaction_db(file!(), function_name!());
-    let mut redis_db = RedisRocksdb::new(db);
+    let redis_db = RedisRocksdb::new(db);
     let key = function_name!().as_bytes();
     let value = vec![1, 23, 6];
     let _ = redis_db.list_clear(&key); //先清除数据，以便测试可以反复运行
@@ -55,7 +67,7 @@ fn test_list_rpush() {
 #[test]
 fn test_list_lr_pop() {
     let db = open_transaction_db(file!(), function_name!());
-    let mut redis_db = RedisRocksdb::new(db);
+    let redis_db = RedisRocksdb::new(db);
     let key = function_name!().as_bytes();
     let value = vec![1];
     let _ = redis_db.list_clear(&key); //先清除数据，以便测试可以反复运行
@@ -101,7 +113,7 @@ fn test_list_lr_pop() {
 #[test]
 fn test_list_insert_set_rem_range() {
     let db = open_transaction_db(file!(), function_name!());
-    let mut redis_db = RedisRocksdb::new(db);
+    let redis_db = RedisRocksdb::new(db);
     let key = function_name!().as_bytes();
     let value = vec![1, 23, 6];
     let _ = redis_db.list_clear(&key); //先清除数据，以便测试可以反复运行
@@ -236,3 +248,193 @@ fn test_list_insert_set_rem_range() {
         );
     }
 }
+
+#[named]
+#[test]
+fn test_list_blocking_pop() {
+    let db = open_transaction_db(file!(), function_name!());
+    let redis_db = Arc::new(RedisRocksdb::new(db));
+    let key = function_name!().as_bytes();
+    let dst_key = format!("{}_dst", function_name!()).into_bytes();
+    let value = vec![1, 23, 6];
+    let value2 = vec![2, 9];
+    let _ = redis_db.list_clear(&key); //先清除数据，以便测试可以反复运行
+    let _ = redis_db.list_clear(&dst_key);
+
+    //list中有数据时，不阻塞
+    let _ = redis_db.list_push_back(&key, &value);
+    let _ = redis_db.list_push_back(&key, &value2);
+    assert_eq!(Some(value.clone()), redis_db.list_blpop(&key, 1).expect(""));
+    assert_eq!(Some(value2.clone()), redis_db.list_brpop(&key, 1).expect(""));
+
+    //超时返回None
+    let start = Instant::now();
+    assert_eq!(None, redis_db.list_blpop(&key, 1).expect(""));
+    assert!(start.elapsed() >= Duration::from_secs(1));
+    assert!(redis_db.list_blpop(&key, -1).is_err());
+
+    //由push唤醒，timeout为0时一直等待
+    let handle = {
+        let redis_db = redis_db.clone();
+        let key = key.to_vec();
+        thread::spawn(move || redis_db.list_blpop(&key, 0).expect(""))
+    };
+    thread::sleep(Duration::from_millis(100));
+    let _ = redis_db.list_push_front(&key, &value);
+    assert_eq!(Some(value.clone()), handle.join().expect(""));
+    assert_eq!(-1, redis_db.list_len(&key).expect(""));
+
+    //timeout超出时间的范围时也一直等待，不会溢出
+    let handle = {
+        let redis_db = redis_db.clone();
+        let key = key.to_vec();
+        thread::spawn(move || redis_db.list_brpop(&key, i64::MAX).expect(""))
+    };
+    thread::sleep(Duration::from_millis(100));
+    let _ = redis_db.list_push_back(&key, &value);
+    assert_eq!(Some(value.clone()), handle.join().expect(""));
+
+    //brpoplpush
+    let handle = {
+        let redis_db = redis_db.clone();
+        let key = key.to_vec();
+        let dst_key = dst_key.clone();
+        thread::spawn(move || redis_db.list_brpoplpush(&key, &dst_key, 5).expect(""))
+    };
+    thread::sleep(Duration::from_millis(100));
+    let _ = redis_db.list_push_back(&key, &value2);
+    assert_eq!(Some(value2.clone()), handle.join().expect(""));
+    assert_eq!(-1, redis_db.list_len(&key).expect(""));
+    assert_eq!(vec![value2.clone()], redis_db.list_range(&dst_key, 0, -1).expect(""));
+
+    //同时等待多个key，返回第一个有数据的key，由第二个key的push唤醒
+    let pop = |k: &[u8]| redis_db.list_pop_front(&k);
+    assert_eq!(
+        Some((dst_key.clone(), value2.clone())),
+        redis_db.list_block(&[key, &dst_key], 100, pop).expect("")
+    );
+    let handle = {
+        let redis_db = redis_db.clone();
+        let keys = [key.to_vec(), dst_key.clone()];
+        thread::spawn(move || {
+            let keys: Vec<&[u8]> = keys.iter().map(|k| k.as_slice()).collect();
+            redis_db.list_block(&keys, 5000, |k| redis_db.list_pop_front(&k)).expect("")
+        })
+    };
+    thread::sleep(Duration::from_millis(100));
+    let _ = redis_db.list_push_back(&dst_key, &value);
+    assert_eq!(Some((dst_key.clone(), value.clone())), handle.join().expect(""));
+}
+
+#[named]
+#[test]
+fn test_list_blocking_pop_fair() {
+    let db = open_transaction_db(file!(), function_name!());
+    let redis_db = Arc::new(RedisRocksdb::new(db));
+    let key = function_name!().as_bytes();
+    let _ = redis_db.list_clear(&key); //先清除数据，以便测试可以反复运行
+
+    //先到的等待者先取得数据
+    let mut handles = Vec::new();
+    for i in 0..3u8 {
+        let redis_db = redis_db.clone();
+        let key = key.to_vec();
+        handles.push(thread::spawn(move || (i, redis_db.list_blpop(&key, 5).expect(""))));
+        thread::sleep(Duration::from_millis(100));
+    }
+    for i in 0..3u8 {
+        let _ = redis_db.list_push_back(&key, &vec![i]);
+    }
+    for h in handles {
+        let (i, v) = h.join().expect("");
+        assert_eq!(Some(vec![i]), v);
+    }
+}
+
+#[named]
+#[test]
+fn test_list_trim() {
+    let db = open_transaction_db(file!(), function_name!());
+    let redis_db = RedisRocksdb::new(db);
+    let key = function_name!().as_bytes();
+    let _ = redis_db.list_clear(&key); //先清除数据，以便测试可以反复运行
+
+    //多个node的list
+    for i in 0..500i32 {
+        redis_db.list_push_back(&key, &i.to_be_bytes().to_vec()).expect("");
+    }
+    assert_eq!(100, redis_db.list_trim(&key, 0, -101).expect(""));
+    assert_eq!(150, redis_db.list_trim(&key, 150, 1000).expect(""));
+    let values = redis_db.list_range(&key, 0, -1).expect("");
+    assert_eq!(250, values.len());
+    assert_eq!(150i32.to_be_bytes().to_vec(), values[0]);
+    assert_eq!(399i32.to_be_bytes().to_vec(), values[249]);
+    assert_eq!(160i32.to_be_bytes().to_vec(), redis_db.list_index(&key, 10).expect(""));
+
+    //区间为空时删除整个list
+    assert_eq!(250, redis_db.list_trim(&key, 10, 5).expect(""));
+    assert_eq!(-1, redis_db.list_len(&key).expect(""));
+    assert_eq!(0, redis_db.list_trim(&key, 0, -1).expect(""));
+}
+
+#[named]
+#[test]
+fn test_list_wrap_db() {
+    {
+        let redis_db = RedisRocksdb::new(open_transaction_db(file!(), function_name!()));
+        let wrap_db = WrapTransactionDB::new(redis_db.get_db());
+        tt_list(&wrap_db);
+
+        let trans = redis_db.get_db().transaction();
+        let wrap_trans = WrapTransaction::new(&trans);
+        tt_list(&wrap_trans);
+        let _ = trans.commit();
+
+        //list与hash的修改在同一个事务中提交
+        let key = b"list_and_hash".to_vec();
+        let _ = redis_db.list_clear(&key); //先清除数据，以便测试可以反复运行
+        let _ = RedisRocksdb::object().del_key(&wrap_db, b"hash");
+        let trans = redis_db.get_db().transaction();
+        let wrap_trans = WrapTransaction::new(&trans);
+        RedisRocksdb::list().push_back(&wrap_trans, &key, b"job").expect("");
+        RedisRocksdb::object().set(&wrap_trans, b"hash", b"job", b"queued").expect("");
+        assert_eq!(-1, redis_db.list_len(&key).expect(""));
+        trans.commit().expect("");
+        assert_eq!(vec![b"job".to_vec()], redis_db.list_range(&key, 0, -1).expect(""));
+        assert_eq!(Some(b"queued".to_vec()), RedisRocksdb::object().get(&wrap_db, b"hash", b"job").expect(""));
+    }
+    {
+        let rocks_db = open_rocks_db(file!(), function_name!());
+        let wrap_rocks_db = WrapRocksDb { db: &rocks_db };
+        tt_list(&wrap_rocks_db);
+    }
+}
+
+fn tt_list<T: WrapDb>(wrap_db: &T) {
+    let list = RedisRocksdb::list();
+    let key = b"list".to_vec();
+    //删除所有内容，以便多次测试
+    let _ = RedisRocksdb::key_space().del(wrap_db, &key);
+
+    assert_eq!(-1, list.len(wrap_db, &key).expect(""));
+    assert_eq!(None, list.pop_front(wrap_db, &key).expect(""));
+    assert_eq!(0, list.push_back_exists(wrap_db, &key, b"a").expect(""));
+    assert_eq!(1, list.push_back(wrap_db, &key, b"b").expect(""));
+    assert_eq!(2, list.push_front(wrap_db, &key, b"a").expect(""));
+    assert_eq!(3, list.push_back_exists(wrap_db, &key, b"d").expect(""));
+    assert_eq!(4, list.insert_after(wrap_db, &key, b"b", b"c").expect(""));
+    assert_eq!(-1, list.insert_before(wrap_db, &key, b"none", b"c").expect(""));
+    assert_eq!(b"c".to_vec(), list.index(wrap_db, &key, 2).expect(""));
+    assert_eq!(b"d".to_vec(), list.set(wrap_db, &key, 3, b"dd").expect(""));
+    let all: Vec<Vec<u8>> = vec![b"a".to_vec(), b"b".to_vec(), b"c".to_vec(), b"dd".to_vec()];
+    assert_eq!(all, list.range(wrap_db, &key, 0, -1).expect(""));
+    assert_eq!(all[1..3].to_vec(), list.range(wrap_db, &key, 1, -2).expect(""));
+
+    assert_eq!(Some(b"dd".to_vec()), list.replace_back(wrap_db, &key, &key).expect(""));
+    assert_eq!(Some(b"dd".to_vec()), list.pop_front(wrap_db, &key).expect(""));
+    assert_eq!(Some(b"c".to_vec()), list.pop_back(wrap_db, &key).expect(""));
+    assert_eq!(1, list.rem(wrap_db, &key, -1, b"a").expect(""));
+    assert_eq!(1, list.trim(wrap_db, &key, 1, 10).expect(""));
+    assert_eq!(0, list.clear(wrap_db, &key).expect(""));
+    assert_eq!(-1, list.len(wrap_db, &key).expect(""));
+}
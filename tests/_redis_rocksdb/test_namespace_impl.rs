@@ -0,0 +1,117 @@
+use function_name::named;
+use redis_rocksdb::{Heap, KeyExpire, KeyValue, Object, RedisList, RedisRocksdb, Stack};
+
+use crate::_redis_rocksdb::kits::open_transaction_db;
+
+#[named]
+#[test]
+fn test_namespace_isolation() {
+    let redis_db = RedisRocksdb::new(open_transaction_db(file!(), function_name!()));
+    //先清除数据，以便测试可以反复运行
+    redis_db.flush_all().expect("");
+    let db1 = redis_db.select(1);
+    assert_eq!(0, redis_db.namespace());
+    assert_eq!(1, db1.namespace());
+
+    let key = b"key".to_vec();
+    redis_db.put(&key, &b"v0".to_vec()).expect("");
+    db1.put(&key, &b"v1".to_vec()).expect("");
+    assert_eq!(Some(b"v0".to_vec()), redis_db.get(&key).expect(""));
+    assert_eq!(Some(b"v1".to_vec()), db1.get(&key).expect(""));
+
+    let list = b"list".to_vec();
+    db1.list_push_back(&list, &b"a".to_vec()).expect("");
+    assert_eq!(-1, redis_db.list_len(&list).expect(""));
+    assert_eq!(vec![b"a".to_vec()], db1.list_range(&list, 0, -1).expect(""));
+
+    let stack = b"stack".to_vec();
+    redis_db.push(&stack, &b"a".to_vec()).expect("");
+    assert_eq!(-1, Stack::len(&db1, &stack).expect(""));
+    assert_eq!(1, Stack::len(&redis_db, &stack).expect(""));
+
+    let object = RedisRocksdb::object();
+    object.set(&db1.wrap_db(), b"h", b"f", b"v1").expect("");
+    assert_eq!(None, object.get(&redis_db.wrap_db(), b"h", b"f").expect(""));
+    assert_eq!(Some(vec![b"f".to_vec()]), object.keys(&db1.wrap_db(), b"h").expect(""));
+
+    let heap = RedisRocksdb::max_heap();
+    heap.push(&redis_db.wrap_db(), b"heap", b"f", b"v").expect("");
+    assert_eq!(None, heap.len(&db1.wrap_db(), b"heap").expect(""));
+
+    //过期时间也在各自的数据库中
+    assert!(db1.expire(&key, 100).expect(""));
+    assert_eq!(-1, redis_db.ttl(&key).expect(""));
+
+    assert_eq!(4, redis_db.db_size().expect(""));
+    assert_eq!(3, db1.db_size().expect(""));
+    assert_eq!(0, redis_db.select(2).db_size().expect(""));
+}
+
+#[named]
+#[test]
+fn test_flush_db() {
+    let redis_db = RedisRocksdb::new(open_transaction_db(file!(), function_name!()));
+    //先清除数据，以便测试可以反复运行
+    redis_db.flush_all().expect("");
+    let db1 = redis_db.select(1);
+    let db2 = redis_db.select(2);
+    let key = b"key".to_vec();
+    for db in [&redis_db, &db1, &db2] {
+        db.put(&key, &b"v".to_vec()).expect("");
+        RedisRocksdb::object().set(&db.wrap_db(), b"h", b"f", b"v").expect("");
+    }
+
+    db1.flush_db().expect("");
+    assert_eq!(0, db1.db_size().expect(""));
+    assert_eq!(None, db1.get(&key).expect(""));
+    assert_eq!(2, redis_db.db_size().expect(""));
+    assert_eq!(2, db2.db_size().expect(""));
+
+    redis_db.flush_db().expect("");
+    assert_eq!(0, redis_db.db_size().expect(""));
+    assert_eq!(Some(b"v".to_vec()), db2.get(&key).expect(""));
+
+    db1.put(&key, &b"v".to_vec()).expect("");
+    redis_db.flush_all().expect("");
+    for db in [&redis_db, &db1, &db2] {
+        assert_eq!(0, db.db_size().expect(""));
+    }
+}
+
+#[named]
+#[test]
+fn test_swap_db() {
+    let redis_db = RedisRocksdb::new(open_transaction_db(file!(), function_name!()));
+    //先清除数据，以便测试可以反复运行
+    redis_db.flush_all().expect("");
+    let db1 = redis_db.select(1);
+    let key = b"key".to_vec();
+    redis_db.put(&key, &b"v0".to_vec()).expect("");
+    db1.put(&key, &b"v1".to_vec()).expect("");
+
+    redis_db.swap_db(0, 1).expect("");
+    assert_eq!(Some(b"v1".to_vec()), redis_db.get(&key).expect(""));
+    assert_eq!(Some(b"v0".to_vec()), db1.get(&key).expect(""));
+    redis_db.put(&key, &b"new".to_vec()).expect("");
+    db1.flush_db().expect("");
+    assert_eq!(Some(b"new".to_vec()), redis_db.get(&key).expect(""));
+
+    redis_db.swap_db(1, 0).expect("");
+    assert_eq!(0, redis_db.db_size().expect(""));
+    assert_eq!(Some(b"new".to_vec()), db1.get(&key).expect(""));
+}
+
+#[named]
+#[test]
+fn test_namespaces_corrupt() {
+    let db = open_transaction_db(file!(), function_name!());
+    db.put(b"\0namespaces", [1, 2, 3]).expect("");
+    //读取不了数据库的映射时不能打开，不然选择的数据库可能是其它数据库的数据
+    assert!(RedisRocksdb::try_new(db).is_err());
+
+    //恢复，以便测试可以反复运行
+    let db = open_transaction_db(file!(), function_name!());
+    db.delete(b"\0namespaces").expect("");
+    let redis_db = RedisRocksdb::try_new(db).expect("");
+    assert_eq!(1, redis_db.select(1).namespace());
+}
@@ -1,5 +1,5 @@
 use function_name::named;
-use redis_rocksdb::{Object, RedisRocksdb, WrapDb, WrapRocksDb, WrapTransaction, WrapTransactionDB};
+use redis_rocksdb::{KeySpace, Object, RedisRocksdb, WrapDb, WrapRocksDb, WrapTransaction, WrapTransactionDB};
 
 use crate::_redis_rocksdb::kits::{open_rocks_db, open_transaction_db};
 
@@ -8,12 +8,12 @@ use crate::_redis_rocksdb::kits::{open_rocks_db, open_transaction_db};
 fn test_object() {
     {
         let redis_db = RedisRocksdb::new(open_transaction_db(file!(), function_name!()));
-        let wrap_db = WrapTransactionDB { db: redis_db.get_db() };
+        let wrap_db = WrapTransactionDB::new(redis_db.get_db());
         tt_object(&wrap_db, RedisRocksdb::object());
         tt_object(&wrap_db, RedisRocksdb::bit_object());
 
         let trans = redis_db.get_db().transaction();
-        let wrap_trans = WrapTransaction { db: &trans };
+        let wrap_trans = WrapTransaction::new(&trans);
         tt_object(&wrap_trans, RedisRocksdb::object());
         tt_object(&wrap_trans, RedisRocksdb::bit_object());
         let _ = trans.commit();
@@ -30,7 +30,8 @@ fn tt_object<T: WrapDb>(wrap_db: &T, object: impl Object<T>) {
     let key = vec![0 as u8, 1, 2];
     let field = vec![6 as u8, 7, 8];
     let value = "data".to_owned();
-    let _ = object.del_key(&wrap_db, &key); //删除所有内容，以便多次测试
+    //删除所有内容，以便多次测试。object与bit object使用同一个key，类型不一样，所以使用key space删除
+    let _ = RedisRocksdb::key_space().del(wrap_db, &key);
     {
         //测试没有数据的情况
         let re = object.del(&wrap_db, &key, &field);
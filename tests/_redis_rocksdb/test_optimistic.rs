@@ -0,0 +1,132 @@
+use std::{cell::Cell, sync::Arc, thread};
+
+use function_name::named;
+use redis_rocksdb::{KeyExpire, KeyValue, Object, RedisList, RedisRocksdb, RedisRocksdbBuilder, RrError, WrapDb, WrapOptimisticTransaction};
+
+use crate::_redis_rocksdb::kits::db_path;
+
+#[named]
+#[test]
+fn test_optimistic() {
+    let redis_db = RedisRocksdbBuilder::new(db_path(file!(), function_name!())).build_optimistic().expect("");
+    //先清除数据，以便测试可以反复运行
+    redis_db.flush_all().expect("");
+
+    let key = b"s".to_vec();
+    redis_db.put(&key, &b"v".to_vec()).expect("");
+    assert!(redis_db.expire(&key, 100).expect(""));
+    assert_eq!(Some(b"v".to_vec()), redis_db.get(&key).expect(""));
+
+    let list = b"l".to_vec();
+    redis_db.list_push_back(&list, &b"a".to_vec()).expect("");
+    redis_db.list_push_front(&list, &b"b".to_vec()).expect("");
+    assert_eq!(vec![b"b".to_vec(), b"a".to_vec()], redis_db.list_range(&list, 0, -1).expect(""));
+
+    let object = RedisRocksdb::object();
+    redis_db.in_transaction(|t| object.set(t, b"h", b"f", b"v")).expect("");
+    assert_eq!(Some(b"v".to_vec()), object.get(&redis_db.wrap_db(), b"h", b"f").expect(""));
+
+    let tx = redis_db.transaction();
+    tx.list_push_back(&list, &b"c".to_vec()).expect("");
+    object.set(&tx.wrap(), b"h", b"f2", b"v2").expect("");
+    assert!(tx.exec().expect(""));
+    assert_eq!(3, redis_db.list_len(&list).expect(""));
+    assert_eq!(3, redis_db.db_size().expect(""));
+}
+
+#[named]
+#[test]
+fn test_optimistic_retry() {
+    let redis_db = RedisRocksdbBuilder::new(db_path(file!(), function_name!())).build_optimistic().expect("");
+    //先清除数据，以便测试可以反复运行
+    redis_db.flush_all().expect("");
+    let object = RedisRocksdb::object();
+    let wrap_db = redis_db.wrap_db();
+
+    //第一次执行时在事务之外修改读过的字段，提交时冲突，重试后成功
+    let calls = Cell::new(0);
+    let update = |t: &WrapOptimisticTransaction<'_>| {
+        calls.set(calls.get() + 1);
+        let v = t.get_for_update(b"counter")?.unwrap_or_default();
+        if calls.get() == 1 {
+            wrap_db.put(b"counter", b"other")?;
+        }
+        t.put(b"counter", &[v, b"+".to_vec()].concat())?;
+        object.set(t, b"h", b"f", b"v")
+    };
+    redis_db.in_transaction(update).expect("");
+    assert_eq!(2, calls.get());
+    assert_eq!(Some(b"other+".to_vec()), wrap_db.get(b"counter").expect(""));
+
+    //不重试时返回冲突
+    let mut no_retry = redis_db.select(0);
+    no_retry.set_max_retries(0);
+    calls.set(0);
+    assert!(matches!(no_retry.in_transaction(update), Err(RrError::TransactionConflict)));
+    assert_eq!(1, calls.get());
+}
+
+#[named]
+#[test]
+fn test_optimistic_concurrent_push() {
+    let redis_db = Arc::new(
+        RedisRocksdbBuilder::new(db_path(file!(), function_name!()))
+            .max_retries(100)
+            .build_optimistic()
+            .expect(""),
+    );
+    //先清除数据，以便测试可以反复运行
+    redis_db.flush_all().expect("");
+    let list = b"l".to_vec();
+    let handles: Vec<_> = (0..4)
+        .map(|i| {
+            let redis_db = redis_db.clone();
+            let list = list.clone();
+            thread::spawn(move || {
+                for j in 0..50 {
+                    redis_db.list_push_back(&list, &format!("{}-{}", i, j).into_bytes()).expect("");
+                }
+            })
+        })
+        .collect();
+    for handle in handles {
+        handle.join().expect("");
+    }
+    assert_eq!(200, redis_db.list_len(&list).expect(""));
+}
+
+#[named]
+#[test]
+fn test_optimistic_flush() {
+    let redis_db = RedisRocksdbBuilder::new(db_path(file!(), function_name!())).build_optimistic().expect("");
+    //先清除数据，以便测试可以反复运行
+    redis_db.flush_all().expect("");
+    let db1 = redis_db.select(1);
+    let db2 = redis_db.select(2);
+    let key = b"key".to_vec();
+    for db in [&redis_db, &db1, &db2] {
+        db.put(&key, &b"v".to_vec()).expect("");
+        assert!(db.expire(&key, 100).expect(""));
+        db.in_transaction(|t| RedisRocksdb::object().set(t, b"h", b"f", b"v")).expect("");
+    }
+
+    //使用DeleteRange删除
+    db1.flush_db().expect("");
+    assert_eq!(0, db1.db_size().expect(""));
+    assert_eq!(None, db1.get(&key).expect(""));
+    assert_eq!(2, redis_db.db_size().expect(""));
+    assert_eq!(2, db2.db_size().expect(""));
+
+    //重建的key没有原来的过期时间
+    db1.put(&key, &b"v".to_vec()).expect("");
+    assert_eq!(-1, db1.ttl(&key).expect(""));
+
+    redis_db.flush_db().expect("");
+    assert_eq!(0, redis_db.db_size().expect(""));
+    assert_eq!(Some(b"v".to_vec()), db2.get(&key).expect(""));
+
+    redis_db.flush_all().expect("");
+    for db in [&redis_db, &db1, &db2] {
+        assert_eq!(0, db.db_size().expect(""));
+    }
+}
@@ -0,0 +1,255 @@
+use std::{thread, time::Duration};
+
+use function_name::named;
+use redis_rocksdb::{KeyExpire, KeySpace, KeyValue, NotifyEvents, Object, PubSubMessage, RedisList, RedisRocksdb, Subscriber, WrapTransactionDB};
+
+use crate::_redis_rocksdb::kits::open_transaction_db;
+
+fn message(pattern: Option<&[u8]>, channel: &[u8], payload: &[u8]) -> PubSubMessage {
+    PubSubMessage {
+        pattern: pattern.map(|p| p.to_vec()),
+        channel: channel.to_vec(),
+        payload: payload.to_vec(),
+    }
+}
+
+/// 取出所有已收到的消息
+fn received(subscriber: &Subscriber) -> Vec<(Vec<u8>, Vec<u8>)> {
+    let mut re = Vec::new();
+    while let Some(m) = subscriber.try_recv() {
+        re.push((m.channel, m.payload));
+    }
+    re
+}
+
+fn event(channel: &str, payload: &str) -> (Vec<u8>, Vec<u8>) {
+    (channel.as_bytes().to_vec(), payload.as_bytes().to_vec())
+}
+
+#[test]
+fn test_notify_events() {
+    assert_eq!(NotifyEvents::default(), NotifyEvents::parse(b"").expect(""));
+    let events = NotifyEvents::parse(b"KEA").expect("");
+    assert!(events.contains(NotifyEvents::KEYSPACE | NotifyEvents::KEYEVENT | NotifyEvents::ALL));
+    assert_eq!("KEg$lhxp", events.to_string());
+    assert_eq!(events, NotifyEvents::parse(events.to_string().as_bytes()).expect(""));
+    assert!(NotifyEvents::parse(b"Kz").is_err());
+
+    //K与E至少要有一个，并且要有事件的类别
+    assert!(!NotifyEvents::parse(b"KE").expect("").is_enabled(NotifyEvents::STRING));
+    assert!(!NotifyEvents::parse(b"A").expect("").is_enabled(NotifyEvents::STRING));
+    let events = NotifyEvents::parse(b"El").expect("");
+    assert!(events.is_enabled(NotifyEvents::LIST));
+    assert!(!events.is_enabled(NotifyEvents::STRING));
+}
+
+#[named]
+#[test]
+fn test_pub_sub() {
+    let redis_db = RedisRocksdb::new(open_transaction_db(file!(), function_name!()));
+    assert_eq!(0, redis_db.publish(b"c", b"m"));
+
+    let mut s1 = redis_db.subscriber();
+    let mut s2 = redis_db.subscriber();
+    s1.subscribe(&[b"c", b"c", b"d"]);
+    s2.psubscribe(&[b"c*"]);
+    assert_eq!(2, s1.count());
+    assert_eq!(1, s2.count());
+
+    assert_eq!(2, redis_db.publish(b"c", b"m1"));
+    assert_eq!(Some(message(None, b"c", b"m1")), s1.try_recv());
+    assert_eq!(None, s1.try_recv());
+    assert_eq!(Some(message(Some(b"c*"), b"c", b"m1")), s2.try_recv());
+    assert_eq!(1, redis_db.publish(b"d", b"m2"));
+    assert_eq!(1, redis_db.publish(b"cd", b"m3"));
+    assert_eq!(Some(message(None, b"d", b"m2")), s1.try_recv());
+    assert_eq!(Some(message(Some(b"c*"), b"cd", b"m3")), s2.try_recv());
+
+    //channel与pattern都匹配时收到两次
+    s2.subscribe(&[b"cd"]);
+    assert_eq!(2, redis_db.publish(b"cd", b"m4"));
+    assert_eq!(2, received(&s2).len());
+
+    assert_eq!(vec![b"c".to_vec()], s1.unsubscribe(&[b"c", b"none"]));
+    assert_eq!(1, redis_db.publish(b"c", b"m5"));
+    assert_eq!(vec![b"d".to_vec()], s1.unsubscribe(&[]));
+    assert_eq!(0, s1.count());
+    assert_eq!(vec![b"c*".to_vec()], s2.punsubscribe(&[]));
+    assert_eq!(1, redis_db.publish(b"cd", b"m6"));
+
+    //drop时取消订阅
+    drop(s2);
+    assert_eq!(0, redis_db.publish(b"cd", b"m7"));
+
+    //在其它的线程中接收
+    s1.subscribe(&[b"t"]);
+    let handle = thread::spawn(move || s1.recv_timeout(Duration::from_secs(5)));
+    assert_eq!(1, redis_db.publish(b"t", b"m8"));
+    assert_eq!(Some(message(None, b"t", b"m8")), handle.join().expect(""));
+}
+
+#[named]
+#[test]
+fn test_keyspace_notify() {
+    let redis_db = RedisRocksdb::new(open_transaction_db(file!(), function_name!()));
+    let wrap_db = WrapTransactionDB::new(redis_db.get_db());
+    //先清除数据，以便测试可以反复运行
+    let keys: [&[u8]; 4] = [b"s", b"l", b"l2", b"hp"];
+    RedisRocksdb::key_space().dels(&wrap_db, &keys).expect("");
+    let (s, l, l2) = (keys[0], keys[1], keys[2]);
+    let (v, v2): (&[u8], &[u8]) = (b"v", b"v2");
+
+    let mut events = redis_db.subscriber();
+    events.psubscribe(&[b"__keyevent@0__:*"]);
+    let mut space = redis_db.subscriber();
+    space.subscribe(&[b"__keyspace@0__:s"]);
+
+    //默认不发送通知
+    redis_db.put(&s, &v).expect("");
+    assert!(received(&events).is_empty());
+
+    redis_db.set_notify_events(NotifyEvents::parse(b"KE$lg").expect(""));
+    redis_db.put(&s, &v).expect("");
+    redis_db.append(&s, &v2).expect("");
+    assert_eq!(vec![event("__keyevent@0__:set", "s"), event("__keyevent@0__:append", "s")], received(&events));
+    assert_eq!(vec![event("__keyspace@0__:s", "set"), event("__keyspace@0__:s", "append")], received(&space));
+
+    redis_db.list_push_back(&l, &v).expect("");
+    redis_db.list_replace_back(&l, &l2).expect("");
+    assert_eq!(None, redis_db.list_pop_front(&l).expect(""));
+    assert_eq!(
+        vec![
+            event("__keyevent@0__:rpush", "l"),
+            event("__keyevent@0__:rpop", "l"),
+            event("__keyevent@0__:lpush", "l2"),
+        ],
+        received(&events)
+    );
+
+    redis_db.expire(&s, 100).expect("");
+    redis_db.persist(&s).expect("");
+    redis_db.get_del(&s).expect("");
+    assert_eq!(
+        vec![
+            event("__keyevent@0__:expire", "s"),
+            event("__keyevent@0__:persist", "s"),
+            event("__keyevent@0__:del", "s"),
+        ],
+        received(&events)
+    );
+
+    //事务提交后才发送，放弃的事务不发送
+    let tx = redis_db.transaction();
+    tx.put(&s, &v).expect("");
+    tx.list_push_front(&l2, &v).expect("");
+    assert!(received(&events).is_empty());
+    assert!(tx.exec().expect(""));
+    assert_eq!(vec![event("__keyevent@0__:set", "s"), event("__keyevent@0__:lpush", "l2")], received(&events));
+    let tx = redis_db.transaction();
+    tx.put(&s, &v2).expect("");
+    tx.discard().expect("");
+    assert!(received(&events).is_empty());
+
+    //没有开启的类别不发送
+    let tx = redis_db.transaction();
+    tx.heap_push(&RedisRocksdb::max_heap(), b"hp", b"f", b"v").expect("");
+    assert!(tx.exec().expect(""));
+    assert!(received(&events).is_empty());
+    redis_db.set_notify_events(NotifyEvents::parse(b"Epx").expect(""));
+    let tx = redis_db.transaction();
+    assert!(tx.heap_pop(&RedisRocksdb::max_heap(), b"hp").expect("").is_some());
+    assert!(tx.exec().expect(""));
+    assert_eq!(vec![event("__keyevent@0__:heappop", "hp")], received(&events));
+
+    //过期删除
+    redis_db.pexpire(&s, 1).expect("");
+    thread::sleep(Duration::from_millis(10));
+    assert_eq!(None, redis_db.get(&s).expect(""));
+    assert_eq!(vec![event("__keyevent@0__:expired", "s")], received(&events));
+}
+
+#[named]
+#[test]
+fn test_subscriber_capacity() {
+    let redis_db = RedisRocksdb::new(open_transaction_db(file!(), function_name!()));
+    let mut s1 = redis_db.subscriber_with_capacity(2);
+    let mut s2 = redis_db.subscriber();
+    s1.subscribe(&[b"c"]);
+    s2.subscribe(&[b"c"]);
+
+    //队列满了时丢弃新的消息，不影响其它的订阅者
+    assert_eq!(2, redis_db.publish(b"c", b"m1"));
+    assert_eq!(2, redis_db.publish(b"c", b"m2"));
+    assert_eq!(1, redis_db.publish(b"c", b"m3"));
+    assert_eq!(vec![event("c", "m1"), event("c", "m2")], received(&s1));
+    assert_eq!(vec![event("c", "m1"), event("c", "m2"), event("c", "m3")], received(&s2));
+    assert_eq!(2, redis_db.publish(b"c", b"m4"));
+    assert_eq!(vec![event("c", "m4")], received(&s1));
+}
+
+#[named]
+#[test]
+fn test_hash_heap_expired_notify() {
+    let redis_db = RedisRocksdb::new(open_transaction_db(file!(), function_name!()));
+    let wrap_db = WrapTransactionDB::new(redis_db.get_db());
+    //先清除数据，以便测试可以反复运行
+    let keys: [&[u8]; 2] = [b"h", b"hp"];
+    RedisRocksdb::key_space().dels(&wrap_db, &keys).expect("");
+    let (h, hp) = (keys[0], keys[1]);
+    let object = RedisRocksdb::object();
+    let heap = RedisRocksdb::max_heap();
+
+    redis_db.set_notify_events(NotifyEvents::parse(b"Ehpx").expect(""));
+    let mut events = redis_db.subscriber();
+    events.psubscribe(&[b"__keyevent@0__:*"]);
+
+    redis_db.hash_set(&object, h, b"f", b"v").expect("");
+    assert_eq!(0, redis_db.hash_set_not_exist(&object, h, b"f", b"v").expect(""));
+    assert_eq!(1, redis_db.hash_set_not_exist(&object, h, b"f2", b"v").expect(""));
+    assert_eq!(0, redis_db.hash_del(&object, h, &[b"none"]).expect(""));
+    assert_eq!(2, redis_db.hash_del(&object, h, &[b"f", b"f2"]).expect(""));
+    assert_eq!(
+        vec![
+            event("__keyevent@0__:hset", "h"),
+            event("__keyevent@0__:hset", "h"),
+            event("__keyevent@0__:hdel", "h"),
+        ],
+        received(&events)
+    );
+
+    redis_db.heap_push(&heap, hp, b"f", b"v").expect("");
+    assert!(redis_db.heap_pop(&heap, hp).expect("").is_some());
+    assert_eq!(None, redis_db.heap_pop(&heap, hp).expect(""));
+    assert_eq!(
+        vec![event("__keyevent@0__:heappush", "hp"), event("__keyevent@0__:heappop", "hp")],
+        received(&events)
+    );
+
+    //访问时删除已过期的key：in_transaction提交之后发送
+    let expire_hash = || {
+        redis_db.hash_set(&object, h, b"f", b"v").expect("");
+        redis_db.pexpire(&h, 1).expect("");
+        thread::sleep(Duration::from_millis(10));
+        received(&events);
+    };
+    expire_hash();
+    assert_eq!(None, redis_db.in_transaction(|t| object.get(t, h, b"f")).expect(""));
+    assert_eq!(vec![event("__keyevent@0__:expired", "h")], received(&events));
+
+    //不在事务中时马上发送
+    expire_hash();
+    assert_eq!(None, object.get(&redis_db.wrap_db(), h, b"f").expect(""));
+    assert_eq!(vec![event("__keyevent@0__:expired", "h")], received(&events));
+
+    //RedisTransaction提交之后发送，放弃的事务不发送
+    expire_hash();
+    let tx = redis_db.transaction();
+    assert_eq!(None, object.get(&tx.wrap(), h, b"f").expect(""));
+    assert!(received(&events).is_empty());
+    tx.discard().expect("");
+    assert!(received(&events).is_empty());
+    let tx = redis_db.transaction();
+    assert_eq!(None, object.get(&tx.wrap(), h, b"f").expect(""));
+    assert!(tx.exec().expect(""));
+    assert_eq!(vec![event("__keyevent@0__:expired", "h")], received(&events));
+}
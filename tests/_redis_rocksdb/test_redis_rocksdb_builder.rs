@@ -0,0 +1,64 @@
+use function_name::named;
+use redis_rocksdb::{KeySpace, KeyValue, Object, RedisList, RedisRocksdb, RedisRocksdbBuilder, ScanCursor, ScanOptions, WrapRocksDb};
+
+use crate::_redis_rocksdb::kits::db_path;
+
+#[named]
+#[test]
+fn test_redis_rocksdb_builder() {
+    let redis_db = RedisRocksdbBuilder::new(db_path(file!(), function_name!()))
+        .block_cache_size(8 * 1024 * 1024)
+        .build()
+        .expect("");
+    //先清除数据，以便测试可以反复运行
+    redis_db.flush_all().expect("");
+
+    redis_db.put(&b"s".to_vec(), &b"v".to_vec()).expect("");
+    redis_db.list_push_back(&b"l".to_vec(), &b"a".to_vec()).expect("");
+    let object = RedisRocksdb::object();
+    let wrap_db = redis_db.wrap_db();
+    object.set(&wrap_db, b"h", b"f1", b"v1").expect("");
+    object.set(&wrap_db, b"h", b"f2", b"v2").expect("");
+    object.set(&wrap_db, b"h2", b"f", b"v").expect("");
+
+    //prefix extractor不能影响跨key的遍历与同一个key的字段的遍历
+    assert_eq!(Some(b"v".to_vec()), redis_db.get(&b"s".to_vec()).expect(""));
+    assert_eq!(vec![b"a".to_vec()], redis_db.list_range(&b"l".to_vec(), 0, -1).expect(""));
+    assert_eq!(Some(vec![b"f1".to_vec(), b"f2".to_vec()]), object.keys(&wrap_db, b"h").expect(""));
+    let batch = RedisRocksdb::key_space()
+        .scan(&wrap_db, &ScanCursor::start(), &ScanOptions::default())
+        .expect("");
+    assert_eq!(4, batch.items.len());
+    assert_eq!(4, redis_db.db_size().expect(""));
+}
+
+#[named]
+#[test]
+fn test_redis_rocksdb_builder_column_families() {
+    let path = db_path(file!(), function_name!());
+    let redis_db = RedisRocksdbBuilder::new(&path).column_families(true).build().expect("");
+    //先清除数据，以便测试可以反复运行
+    redis_db.flush_all().expect("");
+    let wrap_db = redis_db.wrap_db();
+    RedisRocksdb::object().set(&wrap_db, b"h", b"f", b"v").expect("");
+    assert_eq!(Some(vec![b"f".to_vec()]), RedisRocksdb::object().keys(&wrap_db, b"h").expect(""));
+    drop(redis_db);
+
+    //column family只能用于事务的数据库
+    assert!(RedisRocksdbBuilder::new(&path).column_families(true).open_db().is_err());
+    let redis_db = RedisRocksdbBuilder::new(&path).column_families(true).build().expect("");
+    assert_eq!(Some(b"v".to_vec()), RedisRocksdb::object().get(&redis_db.wrap_db(), b"h", b"f").expect(""));
+}
+
+#[named]
+#[test]
+fn test_redis_rocksdb_builder_open_db() {
+    let rocks_db = RedisRocksdbBuilder::new(db_path(file!(), function_name!())).open_db().expect("");
+    let wrap_db = WrapRocksDb { db: &rocks_db };
+    let object = RedisRocksdb::object();
+    //先清除数据，以便测试可以反复运行
+    object.del(&wrap_db, b"h", b"f").expect("");
+
+    object.set(&wrap_db, b"h", b"f", b"v").expect("");
+    assert_eq!(Some(b"v".to_vec()), object.get(&wrap_db, b"h", b"f").expect(""));
+}
@@ -0,0 +1,203 @@
+use function_name::named;
+use redis_rocksdb::{
+    BPTree, KeyExpire, KeySpace, KeyValue, Object, RedisList, RedisRocksdb, ScoreBound, Set, SortedSet, WatchedKeys, WrapTransactionDB, ZAddOptions,
+};
+
+use crate::_redis_rocksdb::kits::open_transaction_db;
+
+fn clear_keys(redis_db: &RedisRocksdb, keys: &[&[u8]]) {
+    RedisRocksdb::key_space().dels(&WrapTransactionDB::new(redis_db.get_db()), keys).expect("");
+}
+
+#[named]
+#[test]
+fn test_redis_transaction_exec() {
+    let redis_db = RedisRocksdb::new(open_transaction_db(file!(), function_name!()));
+    //先清除数据，以便测试可以反复运行
+    clear_keys(&redis_db, &[b"s", b"l", b"h"]);
+    let wrap_db = WrapTransactionDB::new(redis_db.get_db());
+
+    let tx = redis_db.transaction();
+    tx.put(&b"s".to_vec(), &b"v".to_vec()).expect("");
+    assert_eq!(1, tx.list_push_back(&b"l".to_vec(), &b"a".to_vec()).expect(""));
+    RedisRocksdb::object().set(&tx.wrap(), b"h", b"f", b"v").expect("");
+    assert!(tx.expire(&b"s".to_vec(), 100).expect(""));
+    //事务中可以读取到自己的修改，其它的读取不到
+    assert_eq!(Some(b"v".to_vec()), tx.get(&b"s".to_vec()).expect(""));
+    assert_eq!(None, redis_db.get(&b"s".to_vec()).expect(""));
+    assert_eq!(-1, redis_db.list_len(&b"l".to_vec()).expect(""));
+    assert!(tx.exec().expect(""));
+
+    assert_eq!(Some(b"v".to_vec()), redis_db.get(&b"s".to_vec()).expect(""));
+    assert_eq!(vec![b"a".to_vec()], redis_db.list_range(&b"l".to_vec(), 0, -1).expect(""));
+    assert_eq!(Some(b"v".to_vec()), RedisRocksdb::object().get(&wrap_db, b"h", b"f").expect(""));
+    assert!(redis_db.ttl(&b"s".to_vec()).expect("") > 0);
+
+    //出错的命令不留下部分的修改，其它的命令不受影响
+    let tx = redis_db.transaction();
+    assert!(tx
+        .atomic(|tx| {
+            tx.list_push_back(&b"l".to_vec(), &b"b".to_vec())?;
+            tx.list_push_back(&b"h".to_vec(), &b"b".to_vec())
+        })
+        .is_err());
+    tx.list_push_front(&b"l".to_vec(), &b"z".to_vec()).expect("");
+    assert!(tx.exec().expect(""));
+    assert_eq!(vec![b"z".to_vec(), b"a".to_vec()], redis_db.list_range(&b"l".to_vec(), 0, -1).expect(""));
+}
+
+#[named]
+#[test]
+fn test_redis_transaction_discard() {
+    let redis_db = RedisRocksdb::new(open_transaction_db(file!(), function_name!()));
+    //先清除数据，以便测试可以反复运行
+    clear_keys(&redis_db, &[b"s", b"l"]);
+
+    let tx = redis_db.transaction();
+    tx.put(&b"s".to_vec(), &b"v".to_vec()).expect("");
+    tx.list_push_back(&b"l".to_vec(), &b"a".to_vec()).expect("");
+    tx.discard().expect("");
+    assert_eq!(None, redis_db.get(&b"s".to_vec()).expect(""));
+    assert_eq!(-1, redis_db.list_len(&b"l".to_vec()).expect(""));
+
+    //drop与discard一样
+    {
+        let tx = redis_db.transaction();
+        tx.put(&b"s".to_vec(), &b"v".to_vec()).expect("");
+    }
+    assert_eq!(None, redis_db.get(&b"s".to_vec()).expect(""));
+}
+
+#[named]
+#[test]
+fn test_redis_transaction_watch() {
+    let redis_db = RedisRocksdb::new(open_transaction_db(file!(), function_name!()));
+    //先清除数据，以便测试可以反复运行
+    clear_keys(&redis_db, &[b"w", b"s"]);
+    redis_db.put(&b"w".to_vec(), &b"1".to_vec()).expect("");
+
+    //watch的key没有修改
+    let mut tx = redis_db.transaction();
+    tx.watch(b"w").expect("");
+    tx.put(&b"s".to_vec(), &b"1".to_vec()).expect("");
+    assert!(tx.exec().expect(""));
+    assert_eq!(Some(b"1".to_vec()), redis_db.get(&b"s".to_vec()).expect(""));
+
+    //watch之后其它的写入修改了key，写入相同的值也一样
+    let mut tx = redis_db.transaction();
+    tx.watch(b"w").expect("");
+    redis_db.put(&b"w".to_vec(), &b"1".to_vec()).expect("");
+    tx.put(&b"s".to_vec(), &b"2".to_vec()).expect("");
+    assert!(!tx.exec().expect(""));
+    assert_eq!(Some(b"1".to_vec()), redis_db.get(&b"s".to_vec()).expect(""));
+
+    //watch不存在的key，之后key被创建
+    let mut watched = WatchedKeys::new();
+    watched.watch(&redis_db, b"none").expect("");
+    redis_db.list_push_back(&b"none".to_vec(), &b"a".to_vec()).expect("");
+    let tx = redis_db.transaction();
+    assert!(!watched.check(&tx).expect(""));
+    RedisRocksdb::key_space().del(&tx.wrap(), b"none").expect("");
+    assert!(tx.exec().expect(""));
+
+    //过期时间的修改也是修改
+    let mut tx = redis_db.transaction();
+    tx.watch(b"w").expect("");
+    redis_db.expire(&b"w".to_vec(), 100).expect("");
+    assert!(!tx.exec().expect(""));
+
+    //事务自己修改watch的key不算作修改
+    let mut tx = redis_db.transaction();
+    tx.watch(b"w").expect("");
+    assert_eq!(Some(b"1".to_vec()), tx.get(&b"w".to_vec()).expect(""));
+    tx.put(&b"w".to_vec(), &b"2".to_vec()).expect("");
+    assert!(tx.watch(b"s").is_err());
+    assert!(tx.exec().expect(""));
+    assert_eq!(Some(b"2".to_vec()), redis_db.get(&b"w".to_vec()).expect(""));
+
+    //没有读取时也一样
+    let mut tx = redis_db.transaction();
+    tx.watch(b"w").expect("");
+    tx.put(&b"w".to_vec(), &b"3".to_vec()).expect("");
+    assert!(tx.exec().expect(""));
+    assert_eq!(Some(b"3".to_vec()), redis_db.get(&b"w".to_vec()).expect(""));
+}
+
+#[named]
+#[test]
+fn test_redis_transaction_watch_no_op() {
+    let redis_db = RedisRocksdb::new(open_transaction_db(file!(), function_name!()));
+    //先清除数据，以便测试可以反复运行
+    clear_keys(&redis_db, &[b"h", b"bh", b"bt", b"st", b"z", b"s"]);
+    let object = RedisRocksdb::object();
+    let bit_object = RedisRocksdb::bit_object();
+    let bp_tree = RedisRocksdb::bp_tree();
+    let set = RedisRocksdb::set();
+    let sorted_set = RedisRocksdb::sorted_set();
+    redis_db.in_transaction(|t| object.set(t, b"h", b"f", b"v")).expect("");
+    redis_db.in_transaction(|t| bit_object.set(t, b"bh", b"f", b"v")).expect("");
+    redis_db.in_transaction(|t| bp_tree.set(t, b"bt", b"f", b"v")).expect("");
+    redis_db.in_transaction(|t| set.add(t, b"st", &[b"m"])).expect("");
+    redis_db
+        .in_transaction(|t| sorted_set.add(t, b"z", &[(1.0, b"m".as_ref())], &ZAddOptions::default()))
+        .expect("");
+
+    //没有写入的命令不算作修改
+    let keys: [&[u8]; 5] = [b"h", b"bh", b"bt", b"st", b"z"];
+    let mut tx = redis_db.transaction();
+    for key in keys {
+        tx.watch(key).expect("");
+    }
+    redis_db
+        .in_transaction(|t| {
+            assert_eq!(0, object.set_not_exist(t, b"h", b"f", b"v2")?);
+            assert_eq!(0, object.set_exist(t, b"h", b"none", b"v2")?);
+            object.del(t, b"h", b"none")?;
+            assert_eq!(0, bit_object.set_not_exist(t, b"bh", b"f", b"v2")?);
+            assert_eq!(0, bit_object.set_exist(t, b"bh", b"none", b"v2")?);
+            bit_object.del(t, b"bh", b"none")?;
+            bp_tree.set_not_exist(t, b"bt", b"f", b"v2")?;
+            bp_tree.set_exist(t, b"bt", b"none", b"v2")?;
+            assert_eq!(None, bp_tree.del(t, b"bt", b"none")?);
+
+            assert_eq!(0, set.add(t, b"st", &[b"m"])?);
+            assert_eq!(0, set.rem(t, b"st", &[b"none"])?);
+            assert_eq!(0, sorted_set.add(t, b"z", &[(1.0, b"m".as_ref())], &ZAddOptions::default())?);
+            assert_eq!(0, sorted_set.rem(t, b"z", &[b"none"])?);
+            assert_eq!(0, sorted_set.rem_range_by_rank(t, b"z", 5, 10)?);
+            assert_eq!(0, sorted_set.rem_range_by_score(t, b"z", &ScoreBound::Inclusive(5.0), &ScoreBound::max())?);
+            assert!(sorted_set.pop_min(t, b"z", 0)?.is_empty());
+            assert!(sorted_set.pop_max(t, b"z", 0)?.is_empty());
+            Ok(())
+        })
+        .expect("");
+    //不存在的key也一样
+    redis_db
+        .in_transaction(|t| {
+            assert!(set.pop(t, b"none", 1)?.is_empty());
+            assert_eq!(None, bp_tree.del_first(t, b"none")?);
+            assert_eq!(None, bp_tree.del_last(t, b"none")?);
+            Ok(())
+        })
+        .expect("");
+    tx.put(&b"s".to_vec(), &b"1".to_vec()).expect("");
+    assert!(tx.exec().expect(""));
+    assert_eq!(Some(b"v".to_vec()), object.get(&redis_db.wrap_db(), b"h", b"f").expect(""));
+
+    //写入了的是修改
+    for (write, key) in keys.into_iter().enumerate() {
+        let mut tx = redis_db.transaction();
+        tx.watch(key).expect("");
+        redis_db
+            .in_transaction(|t| match write {
+                0 => object.set_exist(t, b"h", b"f", b"v2").map(|_| ()),
+                1 => bit_object.set_not_exist(t, b"bh", b"f2", b"v2").map(|_| ()),
+                2 => bp_tree.set_exist(t, b"bt", b"f", b"v2"),
+                3 => set.add(t, b"st", &[b"m2"]).map(|_| ()),
+                _ => sorted_set.rem(t, b"z", &[b"m"]).map(|_| ()),
+            })
+            .expect("");
+        tx.put(&b"s".to_vec(), &b"2".to_vec()).expect("");
+        assert!(!tx.exec().expect(""));
+    }
+}
@@ -0,0 +1,199 @@
+use std::borrow::Cow;
+
+use redis_rocksdb::resp::{decode, Frame, RespReader, RespVersion};
+
+fn roundtrip(frame: &Frame<'_>, version: RespVersion) -> Frame<'static> {
+    let data = frame.to_bytes(version);
+    let (decoded, len) = decode(&data).expect("").expect("");
+    assert_eq!(data.len(), len);
+    decoded.into_owned()
+}
+
+#[test]
+fn test_resp_decode() {
+    let data = b"*3\r\n$3\r\nSET\r\n$3\r\nkey\r\n$5\r\nva\r\nl\r\n";
+    let (frame, len) = decode(data).expect("").expect("");
+    assert_eq!(data.len(), len);
+    assert_eq!(
+        Frame::Array(vec![Frame::bulk(&b"SET"[..]), Frame::bulk(&b"key"[..]), Frame::bulk(&b"va\r\nl"[..])]),
+        frame
+    );
+    //零拷贝，借用输入的buffer
+    if let Frame::Array(v) = &frame {
+        assert!(matches!(&v[0], Frame::Bulk(Cow::Borrowed(_))));
+    }
+
+    //每一个前缀都是不完整的数据
+    for i in 0..data.len() {
+        assert!(decode(&data[..i]).expect("").is_none());
+    }
+
+    let cases: Vec<(&[u8], Frame)> = vec![
+        (b"+OK\r\n", Frame::ok()),
+        (b"-ERR unknown\r\n", Frame::error(&b"ERR unknown"[..])),
+        (b":-100\r\n", Frame::Integer(-100)),
+        (b"$-1\r\n", Frame::Null),
+        (b"*-1\r\n", Frame::Null),
+        (b"_\r\n", Frame::Null),
+        (b"$0\r\n\r\n", Frame::bulk(&b""[..])),
+        (b",1.5\r\n", Frame::Double(1.5)),
+        (b",-inf\r\n", Frame::Double(f64::NEG_INFINITY)),
+        (b"#t\r\n", Frame::Boolean(true)),
+        (
+            b"(3492890328409238509324850943850943825024385\r\n",
+            Frame::BigNumber(Cow::Borrowed(b"3492890328409238509324850943850943825024385")),
+        ),
+        (b"!21\r\nSYNTAX invalid syntax\r\n", Frame::BulkError(Cow::Borrowed(b"SYNTAX invalid syntax"))),
+        (
+            b"=15\r\ntxt:Some string\r\n",
+            Frame::Verbatim(Cow::Borrowed(b"txt"), Cow::Borrowed(b"Some string")),
+        ),
+        (b"%1\r\n+key\r\n:1\r\n", Frame::Map(vec![(Frame::simple(&b"key"[..]), Frame::Integer(1))])),
+        (b"~2\r\n:1\r\n:2\r\n", Frame::Set(vec![Frame::Integer(1), Frame::Integer(2)])),
+        (b">2\r\n+message\r\n$-1\r\n", Frame::Push(vec![Frame::simple(&b"message"[..]), Frame::Null])),
+        (
+            b"*2\r\n*1\r\n:1\r\n%0\r\n",
+            Frame::Array(vec![Frame::Array(vec![Frame::Integer(1)]), Frame::Map(vec![])]),
+        ),
+    ];
+    for (data, expected) in cases {
+        let (frame, len) = decode(data).expect("").expect("");
+        assert_eq!(data.len(), len);
+        assert_eq!(expected, frame);
+    }
+
+    for data in [
+        &b"?\r\n"[..],
+        b":abc\r\n",
+        b"$2\r\nabc\r\n",
+        b"$-2\r\n",
+        b"#x\r\n",
+        b"~-1\r\n",
+        b"(12a\r\n",
+        b"=3\r\ntxt\r\n",
+    ] {
+        assert!(decode(data).is_err());
+    }
+    //嵌套太深
+    assert!(decode(&b"*1\r\n".repeat(200)).is_err());
+}
+
+#[test]
+fn test_resp_encode() {
+    let frame = Frame::Map(vec![
+        (Frame::simple(&b"server"[..]), Frame::bulk(b"redis_rocksdb".to_vec())),
+        (Frame::simple(&b"proto"[..]), Frame::Integer(3)),
+        (Frame::simple(&b"modules"[..]), Frame::Set(vec![])),
+        (Frame::simple(&b"score"[..]), Frame::Double(-2.25)),
+        (Frame::simple(&b"ok"[..]), Frame::Boolean(true)),
+        (Frame::simple(&b"none"[..]), Frame::Null),
+    ]);
+    assert_eq!(frame, roundtrip(&frame, RespVersion::Resp3));
+
+    //RESP2中转换为相近的类型
+    let data = frame.to_bytes(RespVersion::Resp2);
+    assert!(data.starts_with(b"*12\r\n"));
+    let resp2 = roundtrip(&frame, RespVersion::Resp2);
+    if let Frame::Array(v) = resp2 {
+        assert_eq!(Frame::Array(vec![]), v[5]);
+        assert_eq!(Frame::bulk(&b"-2.25"[..]), v[7]);
+        assert_eq!(Frame::Integer(1), v[9]);
+        assert_eq!(Frame::Null, v[11]);
+    } else {
+        panic!("expected array");
+    }
+
+    assert_eq!(b"$-1\r\n".to_vec(), Frame::Null.to_bytes(RespVersion::Resp2));
+    assert_eq!(b"_\r\n".to_vec(), Frame::Null.to_bytes(RespVersion::Resp3));
+    assert_eq!(b",inf\r\n".to_vec(), Frame::Double(f64::INFINITY).to_bytes(RespVersion::Resp3));
+    assert_eq!(
+        b"-SYNTAX bad\r\n".to_vec(),
+        Frame::BulkError(Cow::Borrowed(b"SYNTAX bad")).to_bytes(RespVersion::Resp2)
+    );
+    //simple string中有CRLF时使用bulk
+    assert_eq!(b"$4\r\na\r\nb\r\n".to_vec(), Frame::simple(&b"a\r\nb"[..]).to_bytes(RespVersion::Resp2));
+    assert_eq!(Frame::from(Some(b"v".to_vec())), Frame::bulk(&b"v"[..]));
+    assert_eq!(Frame::from(None::<Vec<u8>>), Frame::Null);
+}
+
+#[test]
+fn test_resp_reader() {
+    let mut data = Vec::new();
+    for i in 0..100 {
+        let frame = Frame::Array(vec![Frame::bulk(&b"PUSH"[..]), Frame::bulk(format!("value {}", i).into_bytes())]);
+        frame.encode(RespVersion::Resp2, &mut data);
+    }
+    //模拟每次读取3个byte
+    let mut reader = RespReader::new();
+    let mut frames = Vec::new();
+    for chunk in data.chunks(3) {
+        reader.feed(chunk);
+        while let Some(frame) = reader.next_frame().expect("") {
+            frames.push(frame);
+        }
+    }
+    assert_eq!(100, frames.len());
+    assert_eq!(0, reader.pending());
+    assert_eq!(
+        Some(b"value 99".as_ref()),
+        match &frames[99] {
+            Frame::Array(v) => v[1].as_bytes(),
+            _ => None,
+        }
+    );
+
+    reader.feed(b"+PONG\r\n:1");
+    let (frame, len) = reader.peek().expect("").expect("");
+    assert_eq!(Frame::simple(&b"PONG"[..]), frame);
+    reader.consume(len);
+    assert!(reader.next_frame().expect("").is_none());
+    reader.feed(b"2\r\n");
+    assert_eq!(Some(Frame::Integer(12)), reader.next_frame().expect(""));
+
+    //大的bulk与嵌套的类型分多次读取，一次读取中有多个frame
+    let big = vec![b'x'; 100_000];
+    let frames = vec![
+        Frame::Array(vec![
+            Frame::bulk(big.clone()),
+            Frame::Array(vec![Frame::Integer(1), Frame::Null]),
+            Frame::Array(vec![]),
+        ]),
+        Frame::Map(vec![(Frame::simple(&b"k"[..]), Frame::Set(vec![Frame::Double(1.5), Frame::Boolean(true)]))]),
+        Frame::Null,
+        Frame::bulk(&b"a\r\nb"[..]),
+    ];
+    let mut data = Vec::new();
+    for frame in &frames {
+        frame.encode(RespVersion::Resp3, &mut data);
+    }
+    let mut decoded = Vec::new();
+    for chunk in data.chunks(1000) {
+        reader.feed(chunk);
+        while let Some(frame) = reader.next_frame().expect("") {
+            decoded.push(frame);
+        }
+    }
+    assert_eq!(frames, decoded);
+    assert_eq!(0, reader.pending());
+
+    //错误的数据
+    reader.feed(b"*2\r\n$1\r\na\r\n?x\r\n");
+    assert!(reader.next_frame().is_err());
+    let mut reader = RespReader::new();
+    reader.feed(b"$abc\r\n");
+    assert!(reader.peek().is_err());
+}
+
+#[test]
+fn test_resp_error() {
+    let e = Frame::from(&redis_rocksdb::RrError::WrongType);
+    assert_eq!(Frame::error(&b"WRONGTYPE Operation against a key holding the wrong kind of value"[..]), e);
+    let e = Frame::from(&redis_rocksdb::RrError::Message("key".to_owned()));
+    assert_eq!(Frame::error(&b"ERR key"[..]), e);
+    assert!(e.is_error());
+    assert_eq!("RrError: ERR key", e.to_error().expect("").to_string());
+    let e = Frame::from(&redis_rocksdb::RrError::NotFound("key".to_owned()));
+    assert_eq!(Frame::error(&b"ERR no such key"[..]), e);
+    let e = Frame::from(&redis_rocksdb::RrError::IndexOutOfRange(3));
+    assert_eq!(Frame::error(&b"ERR index out of range"[..]), e);
+}
@@ -0,0 +1,175 @@
+use function_name::named;
+use redis_rocksdb::{
+    glob_match,
+    rocksdb::{IteratorMode, TransactionDB},
+    KeyExpire, KeySpace, KeyType, Object, RedisList, RedisRocksdb, ScanCursor, ScanOptions, SortedSet, WrapTransactionDB, ZAddOptions,
+};
+
+use crate::_redis_rocksdb::kits::open_transaction_db;
+
+/// 保留key格式的版本，不然再次打开时会被当作旧格式的数据库
+fn clear_db(db: &TransactionDB) {
+    let keys: Vec<Box<[u8]>> = db.iterator(IteratorMode::Start).map(|kv| kv.expect("").0).collect();
+    for k in keys {
+        if k.as_ref() != b"\0layout_version" {
+            db.delete(k).expect("");
+        }
+    }
+}
+
+#[test]
+fn test_glob_match() {
+    assert!(glob_match(b"*", b""));
+    assert!(glob_match(b"h?llo", b"hello"));
+    assert!(!glob_match(b"h?llo", b"hllo"));
+    assert!(glob_match(b"h*llo", b"heeeello"));
+    assert!(glob_match(b"h[ae]llo", b"hallo"));
+    assert!(!glob_match(b"h[^e]llo", b"hello"));
+    assert!(glob_match(b"h[a-b]llo", b"hbllo"));
+    assert!(glob_match(b"h\\*llo", b"h*llo"));
+    assert!(!glob_match(b"h\\*llo", b"hello"));
+    assert!(glob_match(b"user:*:name", b"user:100:name"));
+    assert!(!glob_match(b"user:*:name", b"user:100:age"));
+}
+
+#[named]
+#[test]
+fn test_scan_key_space() {
+    let redis_db = RedisRocksdb::new(open_transaction_db(file!(), function_name!()));
+    clear_db(redis_db.get_db()); //先清除数据，以便测试可以反复运行
+    let wrap_db = WrapTransactionDB::new(redis_db.get_db());
+    let object = RedisRocksdb::object();
+    for i in 0..25 {
+        object.set(&wrap_db, format!("user:{:02}", i).as_bytes(), b"name", b"v").expect("");
+    }
+    redis_db.list_push_back(&b"user:list".to_vec(), &b"v".to_vec()).expect("");
+    redis_db.list_push_back(&b"other".to_vec(), &b"v".to_vec()).expect("");
+    //没有过期的key正常返回
+    assert!(redis_db.expire_at(&b"user:list".to_vec(), i64::MAX / 1000).expect(""));
+
+    let key_space = RedisRocksdb::key_space();
+    let opts = ScanOptions {
+        pattern: Some(b"user:*".to_vec()),
+        count: 10,
+        key_type: None,
+    };
+    let mut cursor = ScanCursor::start();
+    let mut keys = Vec::new();
+    let mut batches = 0;
+    loop {
+        let batch = key_space.scan(&wrap_db, &cursor, &opts).expect("");
+        assert!(batch.items.len() <= 10);
+        keys.extend(batch.items);
+        batches += 1;
+        //游标可以传输后再还原
+        cursor = ScanCursor::try_from(batch.cursor.as_bytes()).expect("");
+        if cursor.is_finished() {
+            break;
+        }
+    }
+    assert!(batches >= 3);
+    assert_eq!(26, keys.len());
+    assert_eq!(b"user:00".to_vec(), keys[0]);
+    assert_eq!(b"user:list".to_vec(), keys[25]);
+
+    let opts = ScanOptions {
+        pattern: None,
+        count: 100,
+        key_type: Some(KeyType::List),
+    };
+    let batch = key_space.scan(&wrap_db, &ScanCursor::start(), &opts).expect("");
+    assert!(batch.cursor.is_finished());
+    assert_eq!(vec![b"other".to_vec(), b"user:list".to_vec()], batch.items);
+
+    //已过期的key不返回
+    assert!(redis_db.pexpire(&b"other".to_vec(), -1).expect(""));
+    let batch = key_space.scan(&wrap_db, &ScanCursor::start(), &opts).expect("");
+    assert_eq!(vec![b"user:list".to_vec()], batch.items);
+}
+
+#[named]
+#[test]
+fn test_scan_object() {
+    let redis_db = RedisRocksdb::new(open_transaction_db(file!(), function_name!()));
+    clear_db(redis_db.get_db()); //先清除数据，以便测试可以反复运行
+    let wrap_db = WrapTransactionDB::new(redis_db.get_db());
+    tt_scan_object(&wrap_db, RedisRocksdb::object(), b"object");
+    tt_scan_object(&wrap_db, RedisRocksdb::bit_object(), b"bit_object");
+}
+
+fn tt_scan_object<'a, T: Object<WrapTransactionDB<'a>>>(wrap_db: &WrapTransactionDB<'a>, object: T, key: &[u8]) {
+    let opts = ScanOptions {
+        pattern: None,
+        count: 7,
+        key_type: None,
+    };
+    let batch = object.scan(wrap_db, key, &ScanCursor::start(), &opts).expect("");
+    assert!(batch.cursor.is_finished());
+    assert!(batch.items.is_empty());
+
+    for i in 0..50 {
+        object
+            .set(wrap_db, key, format!("f{:02}", i).as_bytes(), format!("v{}", i).as_bytes())
+            .expect("");
+    }
+    let mut cursor = ScanCursor::start();
+    let mut fields = Vec::new();
+    loop {
+        let batch = object.scan(wrap_db, key, &cursor, &opts).expect("");
+        assert!(batch.items.len() <= 7);
+        //遍历时删除已经返回的字段，不影响游标
+        if let Some((f, _)) = batch.items.first() {
+            object.del(wrap_db, key, f).expect("");
+        }
+        fields.extend(batch.items);
+        cursor = batch.cursor;
+        if cursor.is_finished() {
+            break;
+        }
+    }
+    assert_eq!(50, fields.len());
+    assert_eq!((b"f07".to_vec(), b"v7".to_vec()), fields[7]);
+
+    let opts = ScanOptions {
+        pattern: Some(b"f1?".to_vec()),
+        count: 1000,
+        key_type: None,
+    };
+    let batch = object.scan(wrap_db, key, &ScanCursor::start(), &opts).expect("");
+    assert!(batch.cursor.is_finished());
+    assert_eq!(10, batch.items.len());
+}
+
+#[named]
+#[test]
+fn test_scan_sorted_set() {
+    let redis_db = RedisRocksdb::new(open_transaction_db(file!(), function_name!()));
+    clear_db(redis_db.get_db()); //先清除数据，以便测试可以反复运行
+    let wrap_db = WrapTransactionDB::new(redis_db.get_db());
+    let key = function_name!().as_bytes();
+    let zset = RedisRocksdb::sorted_set();
+    let members: Vec<(f64, Vec<u8>)> = (0..30).map(|i| (-(i as f64), format!("m{:02}", i).into_bytes())).collect();
+    let members: Vec<(f64, &[u8])> = members.iter().map(|(s, m)| (*s, m.as_slice())).collect();
+    zset.add(&wrap_db, key, &members, &ZAddOptions::default()).expect("");
+
+    let opts = ScanOptions {
+        pattern: Some(b"m*5".to_vec()),
+        count: 4,
+        key_type: None,
+    };
+    let mut cursor = ScanCursor::start();
+    let mut re = Vec::new();
+    loop {
+        let batch = zset.scan(&wrap_db, key, &cursor, &opts).expect("");
+        re.extend(batch.items);
+        cursor = batch.cursor;
+        if cursor.is_finished() {
+            break;
+        }
+    }
+    //按成员排序，不是按score
+    assert_eq!(vec![(b"m05".to_vec(), -5.0), (b"m15".to_vec(), -15.0), (b"m25".to_vec(), -25.0)], re);
+
+    //类型不一样
+    assert!(RedisRocksdb::object().scan(&wrap_db, key, &ScanCursor::start(), &opts).is_err());
+}
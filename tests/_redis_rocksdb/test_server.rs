@@ -0,0 +1,435 @@
+use std::{
+    io::{Read, Write},
+    net::{TcpListener, TcpStream},
+    sync::Arc,
+    thread,
+    time::{Duration, Instant},
+};
+
+use function_name::named;
+use redis_rocksdb::{
+    resp::{Frame, RespReader, RespVersion},
+    server::Server,
+    KeySpace, RedisRocksdb, WrapTransactionDB,
+};
+
+use crate::_redis_rocksdb::kits::open_transaction_db;
+
+struct Client {
+    stream: TcpStream,
+    reader: RespReader,
+}
+
+impl Client {
+    fn connect(server: &Arc<Server>) -> Client {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("");
+        let addr = listener.local_addr().expect("");
+        let server = server.clone();
+        thread::spawn(move || server.serve(listener));
+        Client {
+            stream: TcpStream::connect(addr).expect(""),
+            reader: RespReader::new(),
+        }
+    }
+
+    fn send_raw(&mut self, data: &[u8]) {
+        self.stream.write_all(data).expect("");
+    }
+
+    fn send(&mut self, args: &[&str]) {
+        let frame = Frame::Array(args.iter().map(|a| Frame::bulk(a.as_bytes())).collect());
+        self.send_raw(&frame.to_bytes(RespVersion::Resp2));
+    }
+
+    fn read(&mut self) -> Frame<'static> {
+        let mut buf = [0u8; 1024];
+        loop {
+            if let Some(frame) = self.reader.next_frame().expect("") {
+                return frame;
+            }
+            let n = self.stream.read(&mut buf).expect("");
+            assert!(n > 0);
+            self.reader.feed(&buf[..n]);
+        }
+    }
+
+    fn call(&mut self, args: &[&str]) -> Frame<'static> {
+        self.send(args);
+        self.read()
+    }
+}
+
+fn bulks(items: &[&str]) -> Frame<'static> {
+    Frame::Array(items.iter().map(|a| Frame::from(a.as_bytes().to_vec())).collect())
+}
+
+#[named]
+#[test]
+fn test_server() {
+    let db = Arc::new(RedisRocksdb::new(open_transaction_db(file!(), function_name!())));
+    //先清除数据，以便测试可以反复运行
+    let keys: [&[u8]; 17] = [
+        b"s", b"l", b"h", b"l2", b"t", b"w", b"n", b"s2", b"st", b"st2", b"bm", b"bm2", b"hll", b"hll2", b"x", b"x2", b"geo",
+    ];
+    RedisRocksdb::key_space().dels(&WrapTransactionDB::new(db.get_db()), &keys).expect("");
+
+    let server = Arc::new(Server::new(db));
+    let mut client = Client::connect(&server);
+
+    assert_eq!(Frame::simple(&b"PONG"[..]), client.call(&["PING"]));
+    assert_eq!(Frame::ok(), client.call(&["SET", "s", "v"]));
+    assert_eq!(Frame::bulk(&b"v"[..]), client.call(&["get", "s"]));
+    assert_eq!(Frame::Null, client.call(&["GET", "none"]));
+    assert!(client.call(&["GET"]).is_error());
+    assert!(client.call(&["NOCOMMAND"]).is_error());
+
+    //string
+    assert_eq!(Frame::Null, client.call(&["SET", "s", "v1", "NX"]));
+    assert_eq!(Frame::bulk(&b"v"[..]), client.call(&["SET", "s", "v1", "XX", "GET", "EX", "100"]));
+    assert!(matches!(client.call(&["TTL", "s"]), Frame::Integer(t) if t > 0));
+    assert!(client.call(&["SET", "s", "v", "EX", "0"]).is_error());
+    assert!(client.call(&["SET", "s", "v", "NX", "XX"]).is_error());
+    assert_eq!(Frame::ok(), client.call(&["SET", "s", "v"]));
+    assert_eq!(Frame::Integer(-1), client.call(&["TTL", "s"]));
+    assert_eq!(Frame::Integer(0), client.call(&["SETNX", "s", "x"]));
+    assert_eq!(Frame::Integer(1), client.call(&["INCR", "n"]));
+    assert_eq!(Frame::Integer(-9), client.call(&["DECRBY", "n", "10"]));
+    assert!(client.call(&["INCR", "s"]).is_error());
+    assert!(client.call(&["DECRBY", "n", "-9223372036854775808"]).is_error());
+    assert_eq!(Frame::bulk(&b"-8.5"[..]), client.call(&["INCRBYFLOAT", "n", "0.5"]));
+    assert_eq!(Frame::Integer(3), client.call(&["APPEND", "s2", "abc"]));
+    assert_eq!(Frame::Integer(3), client.call(&["STRLEN", "s2"]));
+    assert_eq!(Frame::bulk(&b"bc"[..]), client.call(&["GETRANGE", "s2", "1", "-1"]));
+    assert_eq!(Frame::Integer(5), client.call(&["SETRANGE", "s2", "3", "de"]));
+    assert_eq!(Frame::ok(), client.call(&["MSET", "s", "v", "s2", "abcde"]));
+    assert!(client.call(&["MSET", "s", "v", "s2"]).is_error());
+    assert_eq!(Frame::Integer(0), client.call(&["MSETNX", "s", "v", "none", "v"]));
+    assert_eq!(
+        Frame::Array(vec![Frame::bulk(&b"v"[..]), Frame::bulk(&b"abcde"[..]), Frame::Null]),
+        client.call(&["MGET", "s", "s2", "none"])
+    );
+    assert_eq!(Frame::bulk(&b"abcde"[..]), client.call(&["GETEX", "s2", "PX", "100000"]));
+    assert!(matches!(client.call(&["PTTL", "s2"]), Frame::Integer(t) if t > 0));
+    assert_eq!(Frame::bulk(&b"abcde"[..]), client.call(&["GETDEL", "s2"]));
+    assert_eq!(Frame::Null, client.call(&["GET", "s2"]));
+
+    //bitmap
+    assert_eq!(Frame::Integer(0), client.call(&["SETBIT", "bm", "7", "1"]));
+    assert_eq!(Frame::Integer(1), client.call(&["GETBIT", "bm", "7"]));
+    assert!(client.call(&["SETBIT", "bm", "-1", "1"]).is_error());
+    assert!(client.call(&["SETBIT", "bm", "1", "2"]).is_error());
+    assert_eq!(Frame::bulk(&b"\x01"[..]), client.call(&["GET", "bm"]));
+    assert_eq!(Frame::simple(&b"string"[..]), client.call(&["TYPE", "bm"]));
+    assert_eq!(Frame::ok(), client.call(&["SET", "bm2", "foobar"]));
+    assert_eq!(Frame::Integer(26), client.call(&["BITCOUNT", "bm2"]));
+    assert_eq!(Frame::Integer(17), client.call(&["BITCOUNT", "bm2", "5", "30", "BIT"]));
+    assert!(client.call(&["BITCOUNT", "bm2", "1"]).is_error());
+    assert_eq!(Frame::Integer(1), client.call(&["BITPOS", "bm2", "1"]));
+    assert_eq!(Frame::Integer(2), client.call(&["BITPOS", "bm2", "1", "2", "-1", "BIT"]));
+    assert_eq!(Frame::Integer(6), client.call(&["BITOP", "OR", "bm", "bm", "bm2"]));
+    assert_eq!(Frame::bulk(&b"goobar"[..]), client.call(&["GET", "bm"]));
+    assert_eq!(
+        Frame::Array(vec![Frame::Integer(103), Frame::Integer(111), Frame::Null]),
+        client.call(&["BITFIELD", "bm", "GET", "u8", "0", "SET", "i8", "#1", "-1", "OVERFLOW", "FAIL", "INCRBY", "u8", "#1", "1"])
+    );
+    assert_eq!(Frame::Array(vec![Frame::Integer(255)]), client.call(&["BITFIELD_RO", "bm", "GET", "u8", "8"]));
+    assert!(client.call(&["BITFIELD_RO", "bm", "SET", "u8", "8", "1"]).is_error());
+    assert!(client.call(&["BITFIELD", "bm", "GET", "u64", "0"]).is_error());
+
+    //hyper log log
+    assert_eq!(Frame::Integer(1), client.call(&["PFADD", "hll", "a", "b", "c"]));
+    assert_eq!(Frame::Integer(0), client.call(&["PFADD", "hll", "a"]));
+    assert_eq!(Frame::Integer(3), client.call(&["PFCOUNT", "hll"]));
+    assert_eq!(Frame::Integer(1), client.call(&["PFADD", "hll2", "c", "d"]));
+    assert_eq!(Frame::Integer(4), client.call(&["PFCOUNT", "hll", "hll2"]));
+    assert_eq!(Frame::ok(), client.call(&["PFMERGE", "hll2", "hll"]));
+    assert_eq!(Frame::Integer(4), client.call(&["PFCOUNT", "hll2"]));
+    assert_eq!(Frame::simple(&b"string"[..]), client.call(&["TYPE", "hll"]));
+    assert!(client.call(&["PFADD", "bm2", "a"]).is_error());
+
+    //list
+    assert_eq!(Frame::Integer(3), client.call(&["RPUSH", "l", "a", "b", "c"]));
+    assert_eq!(Frame::Integer(4), client.call(&["LPUSH", "l", "z"]));
+    assert_eq!(bulks(&["z", "a", "b", "c"]), client.call(&["LRANGE", "l", "0", "-1"]));
+    assert_eq!(Frame::bulk(&b"c"[..]), client.call(&["LINDEX", "l", "-1"]));
+    assert_eq!(Frame::Null, client.call(&["LINDEX", "l", "10"]));
+    assert_eq!(Frame::Integer(5), client.call(&["LINSERT", "l", "BEFORE", "b", "x"]));
+    assert_eq!(Frame::ok(), client.call(&["LSET", "l", "0", "y"]));
+    assert!(client.call(&["LSET", "l", "10", "y"]).is_error());
+    assert_eq!(Frame::Integer(1), client.call(&["LREM", "l", "0", "x"]));
+    assert_eq!(Frame::bulk(&b"y"[..]), client.call(&["LPOP", "l"]));
+    assert_eq!(bulks(&["c", "b"]), client.call(&["RPOP", "l", "2"]));
+    assert_eq!(Frame::Integer(1), client.call(&["LLEN", "l"]));
+    assert_eq!(Frame::Integer(0), client.call(&["LLEN", "l2"]));
+    assert_eq!(Frame::Integer(3), client.call(&["RPUSH", "l2", "x", "y", "z"]));
+    assert_eq!(Frame::ok(), client.call(&["LTRIM", "l2", "1", "-1"]));
+    assert_eq!(bulks(&["y", "z"]), client.call(&["LRANGE", "l2", "0", "-1"]));
+    assert_eq!(bulks(&["l", "a"]), client.call(&["BLPOP", "l", "0.1"]));
+    //小数的秒精确到毫秒
+    let start = Instant::now();
+    assert_eq!(Frame::Null, client.call(&["BLPOP", "l", "0.1"]));
+    assert!(start.elapsed() < Duration::from_millis(900));
+    assert!(client.call(&["BLPOP", "l", "1e300"]).is_error());
+    //多个key时返回第一个有数据的key
+    assert_eq!(bulks(&["l2", "z"]), client.call(&["BRPOP", "none", "l", "l2", "0"]));
+    assert_eq!(bulks(&["l2", "y"]), client.call(&["BLPOP", "l", "l2", "0.1"]));
+    assert_eq!(Frame::Null, client.call(&["BLPOP", "l", "l2", "0.1"]));
+    assert!(client.call(&["BRPOPLPUSH", "l", "l2", "1e17"]).is_error());
+
+    //hash
+    assert_eq!(Frame::Integer(2), client.call(&["HSET", "h", "f1", "v1", "f2", "v2"]));
+    assert_eq!(Frame::Integer(0), client.call(&["HSET", "h", "f1", "v"]));
+    assert_eq!(Frame::bulk(&b"v"[..]), client.call(&["HGET", "h", "f1"]));
+    assert_eq!(Frame::Integer(0), client.call(&["HSETNX", "h", "f1", "v1"]));
+    assert_eq!(Frame::Integer(2), client.call(&["HLEN", "h"]));
+    assert_eq!(Frame::Integer(1), client.call(&["HEXISTS", "h", "f2"]));
+    assert_eq!(
+        Frame::Array(vec![Frame::bulk(&b"v"[..]), Frame::Null]),
+        client.call(&["HMGET", "h", "f1", "f3"])
+    );
+    assert_eq!(bulks(&["f1", "v", "f2", "v2"]), client.call(&["HGETALL", "h"]));
+    assert_eq!(bulks(&["f1", "f2"]), client.call(&["HKEYS", "h"]));
+    assert_eq!(Frame::Integer(1), client.call(&["HDEL", "h", "f2", "f3"]));
+
+    //set
+    assert_eq!(Frame::Integer(3), client.call(&["SADD", "st", "c", "a", "b", "a"]));
+    assert_eq!(Frame::Integer(3), client.call(&["SCARD", "st"]));
+    assert_eq!(Frame::Integer(1), client.call(&["SISMEMBER", "st", "a"]));
+    assert_eq!(
+        Frame::Array(vec![Frame::Integer(1), Frame::Integer(0)]),
+        client.call(&["SMISMEMBER", "st", "b", "x"])
+    );
+    assert_eq!(Frame::Integer(2), client.call(&["SADD", "st2", "b", "d"]));
+    assert_eq!(bulks(&["b"]), client.call(&["SINTER", "st", "st2"]));
+    assert_eq!(bulks(&["a", "c"]), client.call(&["SDIFF", "st", "st2"]));
+    assert_eq!(Frame::Integer(4), client.call(&["SUNIONSTORE", "st2", "st", "st2"]));
+    assert_eq!(Frame::Integer(1), client.call(&["SREM", "st2", "d", "x"]));
+    assert_eq!(Frame::Integer(1), client.call(&["SMOVE", "st2", "st", "c"]));
+    assert_eq!(bulks(&["a", "b"]), client.call(&["SMEMBERS", "st2"]));
+    assert!(client.call(&["SPOP", "st", "-1"]).is_error());
+    assert!(client.call(&["SADD", "h", "a"]).as_bytes().expect("").starts_with(b"WRONGTYPE"));
+
+    //stream
+    assert_eq!(Frame::bulk(&b"1-0"[..]), client.call(&["XADD", "x", "1", "f", "v"]));
+    assert_eq!(Frame::bulk(&b"1-1"[..]), client.call(&["XADD", "x", "1-*", "f", "v2"]));
+    assert!(client.call(&["XADD", "x", "1-1", "f", "v"]).is_error());
+    assert!(client.call(&["XADD", "x", "1-0", "f"]).is_error());
+    assert_eq!(Frame::Null, client.call(&["XADD", "x2", "NOMKSTREAM", "*", "f", "v"]));
+    assert_eq!(Frame::Integer(2), client.call(&["XLEN", "x"]));
+    let entry = |id: &str, v: &str| Frame::Array(vec![Frame::from(id.as_bytes().to_vec()), bulks(&["f", v])]);
+    assert_eq!(Frame::Array(vec![entry("1-1", "v2")]), client.call(&["XRANGE", "x", "(1-0", "+"]));
+    assert_eq!(Frame::Array(vec![entry("1-1", "v2")]), client.call(&["XREVRANGE", "x", "+", "-", "COUNT", "1"]));
+    assert_eq!(
+        Frame::Array(vec![Frame::Array(vec![Frame::bulk(&b"x"[..]), Frame::Array(vec![entry("1-1", "v2")])])]),
+        client.call(&["XREAD", "STREAMS", "x", "1-0"])
+    );
+    assert_eq!(Frame::Null, client.call(&["XREAD", "BLOCK", "100", "STREAMS", "x", "$"]));
+    assert_eq!(Frame::ok(), client.call(&["XGROUP", "CREATE", "x", "g", "0"]));
+    assert!(client
+        .call(&["XGROUP", "CREATE", "x", "g", "0"])
+        .as_bytes()
+        .expect("")
+        .starts_with(b"BUSYGROUP"));
+    assert_eq!(
+        Frame::Array(vec![Frame::Array(vec![Frame::bulk(&b"x"[..]), Frame::Array(vec![entry("1-0", "v")])])]),
+        client.call(&["XREADGROUP", "GROUP", "g", "c", "COUNT", "1", "STREAMS", "x", ">"])
+    );
+    let pending = client.call(&["XPENDING", "x", "g"]);
+    assert_eq!(
+        Frame::Array(vec![
+            Frame::Integer(1),
+            Frame::bulk(&b"1-0"[..]),
+            Frame::bulk(&b"1-0"[..]),
+            Frame::Array(vec![bulks(&["c", "1"])])
+        ]),
+        pending
+    );
+    assert_eq!(bulks(&["1-0"]), client.call(&["XCLAIM", "x", "g", "c2", "0", "1-0", "JUSTID"]));
+    assert_eq!(Frame::Integer(1), client.call(&["XACK", "x", "g", "1-0"]));
+    assert_eq!(Frame::Integer(1), client.call(&["XDEL", "x", "1-0"]));
+    assert_eq!(Frame::Integer(1), client.call(&["XTRIM", "x", "MAXLEN", "~", "0"]));
+    assert_eq!(Frame::simple(&b"stream"[..]), client.call(&["TYPE", "x"]));
+
+    //geo
+    assert_eq!(
+        Frame::Integer(2),
+        client.call(&["GEOADD", "geo", "13.361389", "38.115556", "Palermo", "15.087269", "37.502669", "Catania"])
+    );
+    assert!(client.call(&["GEOADD", "geo", "200", "0", "x"]).is_error());
+    assert_eq!(Frame::bulk(&b"166.2742"[..]), client.call(&["GEODIST", "geo", "Palermo", "Catania", "km"]));
+    assert_eq!(Frame::Null, client.call(&["GEODIST", "geo", "Palermo", "none"]));
+    assert_eq!(
+        Frame::Array(vec![Frame::bulk(&b"sqc8b49rny0"[..]), Frame::Null]),
+        client.call(&["GEOHASH", "geo", "Palermo", "none"])
+    );
+    assert!(matches!(&client.call(&["GEOPOS", "geo", "Palermo", "none"]), Frame::Array(v) if v.len() == 2 && v[1] == Frame::Null));
+    assert_eq!(
+        bulks(&["Catania", "Palermo"]),
+        client.call(&["GEOSEARCH", "geo", "FROMLONLAT", "15", "37", "BYRADIUS", "200", "km", "ASC"])
+    );
+    assert_eq!(
+        Frame::Array(vec![Frame::Array(vec![Frame::bulk(&b"Catania"[..]), Frame::bulk(&b"56.4413"[..])])]),
+        client.call(&[
+            "GEOSEARCH",
+            "geo",
+            "FROMLONLAT",
+            "15",
+            "37",
+            "BYBOX",
+            "400",
+            "400",
+            "km",
+            "COUNT",
+            "1",
+            "WITHDIST"
+        ])
+    );
+    assert!(client.call(&["GEOSEARCH", "geo", "BYRADIUS", "200", "km"]).is_error());
+    assert_eq!(Frame::simple(&b"zset"[..]), client.call(&["TYPE", "geo"]));
+
+    //类型不对
+    let e = client.call(&["LPUSH", "h", "a"]);
+    assert!(e.as_bytes().expect("").starts_with(b"WRONGTYPE"));
+
+    //key
+    assert_eq!(Frame::simple(&b"hash"[..]), client.call(&["TYPE", "h"]));
+    assert_eq!(Frame::Integer(2), client.call(&["EXISTS", "h", "s", "none"]));
+    let scan = client.call(&["SCAN", "0", "MATCH", "[hs]", "COUNT", "100"]);
+    assert_eq!(Frame::Array(vec![Frame::bulk(&b"0"[..]), bulks(&["h", "s"])]), scan);
+
+    //事务
+    assert_eq!(Frame::ok(), client.call(&["MULTI"]));
+    assert!(client.call(&["MULTI"]).is_error());
+    assert_eq!(Frame::simple(&b"QUEUED"[..]), client.call(&["SET", "t", "1"]));
+    assert_eq!(Frame::simple(&b"QUEUED"[..]), client.call(&["LPUSH", "t", "a"]));
+    assert_eq!(Frame::simple(&b"QUEUED"[..]), client.call(&["GET", "t"]));
+    let replies = match client.call(&["EXEC"]) {
+        Frame::Array(replies) => replies,
+        f => panic!("{:?}", f),
+    };
+    assert_eq!(3, replies.len());
+    assert_eq!(Frame::ok(), replies[0]);
+    assert!(replies[1].is_error());
+    assert_eq!(Frame::bulk(&b"1"[..]), replies[2]);
+    assert!(client.call(&["EXEC"]).is_error());
+
+    assert_eq!(Frame::ok(), client.call(&["MULTI"]));
+    assert_eq!(Frame::simple(&b"QUEUED"[..]), client.call(&["SET", "t", "2"]));
+    assert_eq!(Frame::ok(), client.call(&["DISCARD"]));
+    assert_eq!(Frame::bulk(&b"1"[..]), client.call(&["GET", "t"]));
+
+    //排队时出错，EXEC时放弃整个事务
+    assert_eq!(Frame::ok(), client.call(&["MULTI"]));
+    assert_eq!(Frame::simple(&b"QUEUED"[..]), client.call(&["SET", "t", "3"]));
+    assert!(client.call(&["GET"]).is_error());
+    let e = client.call(&["EXEC"]);
+    assert!(e.as_bytes().expect("").starts_with(b"EXECABORT"));
+    assert_eq!(Frame::bulk(&b"1"[..]), client.call(&["GET", "t"]));
+
+    //watch的key被其它的连接修改
+    let mut other = Client::connect(&server);
+    assert_eq!(Frame::ok(), client.call(&["WATCH", "w"]));
+    assert_eq!(Frame::ok(), other.call(&["SET", "w", "1"]));
+    assert_eq!(Frame::ok(), client.call(&["MULTI"]));
+    assert_eq!(Frame::simple(&b"QUEUED"[..]), client.call(&["SET", "t", "4"]));
+    assert_eq!(Frame::Null, client.call(&["EXEC"]));
+    assert_eq!(Frame::bulk(&b"1"[..]), client.call(&["GET", "t"]));
+    //EXEC之后不再watch
+    assert_eq!(Frame::ok(), client.call(&["MULTI"]));
+    assert_eq!(Frame::simple(&b"QUEUED"[..]), client.call(&["SET", "t", "4"]));
+    assert_eq!(Frame::Array(vec![Frame::ok()]), client.call(&["EXEC"]));
+
+    //pipeline与inline命令
+    client.send_raw(b"PING\r\n*2\r\n$4\r\nECHO\r\n$2\r\nhi\r\nGET s\r\n");
+    assert_eq!(Frame::simple(&b"PONG"[..]), client.read());
+    assert_eq!(Frame::bulk(&b"hi"[..]), client.read());
+    assert_eq!(Frame::bulk(&b"v"[..]), client.read());
+
+    //RESP3
+    let hello = client.call(&["HELLO", "3"]);
+    assert!(matches!(hello, Frame::Map(_)));
+    assert!(matches!(client.call(&["HGETALL", "h"]), Frame::Map(m) if m.len() == 1));
+
+    assert_eq!(Frame::ok(), client.call(&["QUIT"]));
+}
+
+#[named]
+#[test]
+fn test_server_pub_sub() {
+    let db = Arc::new(RedisRocksdb::new(open_transaction_db(file!(), function_name!())));
+    //先清除数据，以便测试可以反复运行
+    let keys: [&[u8]; 1] = [b"h"];
+    RedisRocksdb::key_space().dels(&WrapTransactionDB::new(db.get_db()), &keys).expect("");
+
+    let server = Arc::new(Server::new(db));
+    let mut sub = Client::connect(&server);
+    let mut client = Client::connect(&server);
+    let pushed = |items: &[&str], count: i64| {
+        let mut items: Vec<Frame<'static>> = items.iter().map(|a| Frame::from(a.as_bytes().to_vec())).collect();
+        items.push(Frame::Integer(count));
+        Frame::Array(items)
+    };
+
+    assert_eq!(pushed(&["subscribe", "c1"], 1), sub.call(&["SUBSCRIBE", "c1", "c2"]));
+    assert_eq!(pushed(&["subscribe", "c2"], 2), sub.read());
+    assert_eq!(pushed(&["psubscribe", "__keyevent@0__:*"], 3), sub.call(&["PSUBSCRIBE", "__keyevent@0__:*"]));
+    //订阅模式中只能使用订阅相关的命令
+    assert!(sub.call(&["GET", "h"]).is_error());
+    assert_eq!(bulks(&["pong", ""]), sub.call(&["PING"]));
+
+    assert_eq!(Frame::Integer(1), client.call(&["PUBLISH", "c1", "hi"]));
+    assert_eq!(bulks(&["message", "c1", "hi"]), sub.read());
+
+    assert_eq!(Frame::ok(), client.call(&["CONFIG", "SET", "notify-keyspace-events", "Eh"]));
+    assert_eq!(
+        bulks(&["notify-keyspace-events", "Eh"]),
+        client.call(&["CONFIG", "GET", "notify-keyspace-events"])
+    );
+    assert!(client.call(&["CONFIG", "SET", "notify-keyspace-events", "?"]).is_error());
+    assert_eq!(Frame::Integer(1), client.call(&["HSET", "h", "f", "v"]));
+    assert_eq!(bulks(&["pmessage", "__keyevent@0__:*", "__keyevent@0__:hset", "h"]), sub.read());
+    assert_eq!(Frame::ok(), client.call(&["CONFIG", "SET", "notify-keyspace-events", ""]));
+
+    assert_eq!(pushed(&["unsubscribe", "c1"], 2), sub.call(&["UNSUBSCRIBE"]));
+    assert_eq!(pushed(&["unsubscribe", "c2"], 1), sub.read());
+    assert_eq!(pushed(&["punsubscribe", "__keyevent@0__:*"], 0), sub.call(&["PUNSUBSCRIBE"]));
+    assert_eq!(Frame::Integer(0), client.call(&["PUBLISH", "c1", "hi"]));
+    //取消所有的订阅后，可以使用其它的命令
+    assert!(matches!(sub.call(&["HGETALL", "h"]), Frame::Array(a) if a.len() == 2));
+}
+
+#[named]
+#[test]
+fn test_server_select() {
+    let db = Arc::new(RedisRocksdb::new(open_transaction_db(file!(), function_name!())));
+    //先清除数据，以便测试可以反复运行
+    db.flush_all().expect("");
+
+    let server = Arc::new(Server::new(db));
+    let mut client = Client::connect(&server);
+    let mut other = Client::connect(&server);
+
+    assert_eq!(Frame::ok(), client.call(&["SET", "k", "v0"]));
+    assert_eq!(Frame::ok(), client.call(&["SELECT", "1"]));
+    assert_eq!(Frame::Null, client.call(&["GET", "k"]));
+    assert_eq!(Frame::ok(), client.call(&["SET", "k", "v1"]));
+    assert_eq!(Frame::Integer(1), client.call(&["DBSIZE"]));
+    //其它的连接仍然在0号数据库
+    assert_eq!(Frame::bulk(&b"v0"[..]), other.call(&["GET", "k"]));
+    assert!(client.call(&["SELECT", "16"]).is_error());
+
+    assert_eq!(Frame::ok(), other.call(&["SWAPDB", "0", "1"]));
+    assert_eq!(Frame::bulk(&b"v1"[..]), other.call(&["GET", "k"]));
+    assert_eq!(Frame::bulk(&b"v0"[..]), client.call(&["GET", "k"]));
+
+    assert_eq!(Frame::ok(), client.call(&["MULTI"]));
+    assert_eq!(Frame::simple(&b"QUEUED"[..]), client.call(&["FLUSHDB"]));
+    assert!(matches!(client.call(&["EXEC"]), Frame::Array(a) if a[0].is_error()));
+
+    assert_eq!(Frame::ok(), client.call(&["FLUSHDB", "SYNC"]));
+    assert_eq!(Frame::Integer(0), client.call(&["DBSIZE"]));
+    assert_eq!(Frame::Integer(1), other.call(&["DBSIZE"]));
+    assert_eq!(Frame::ok(), other.call(&["FLUSHALL"]));
+    assert_eq!(Frame::Integer(0), other.call(&["DBSIZE"]));
+    assert_eq!(Frame::ok(), other.call(&["SWAPDB", "0", "1"]));
+}
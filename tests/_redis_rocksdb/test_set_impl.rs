@@ -0,0 +1,141 @@
+use function_name::named;
+use redis_rocksdb::{KeySpace, KeyType, RedisList, RedisRocksdb, ScanCursor, ScanOptions, Set, WrapTransactionDB};
+
+use crate::_redis_rocksdb::kits::open_transaction_db;
+
+fn members(items: &[&str]) -> Vec<Vec<u8>> {
+    items.iter().map(|m| m.as_bytes().to_vec()).collect()
+}
+
+#[named]
+#[test]
+fn test_set() {
+    let redis_db = RedisRocksdb::new(open_transaction_db(file!(), function_name!()));
+    let wrap_db = WrapTransactionDB::new(redis_db.get_db());
+    let set = RedisRocksdb::set();
+    //先清除数据，以便测试可以反复运行
+    let keys: [&[u8]; 3] = [b"s", b"l", b"d"];
+    RedisRocksdb::key_space().dels(&wrap_db, &keys).expect("");
+    let key = b"s".as_slice();
+
+    //没有数据的情况
+    assert_eq!(0, set.len(&wrap_db, key).expect(""));
+    assert!(set.members(&wrap_db, key).expect("").is_empty());
+    assert!(!set.is_member(&wrap_db, key, b"a").expect(""));
+    assert!(set.pop(&wrap_db, key, 1).expect("").is_empty());
+    assert!(set.rand_member(&wrap_db, key, -3).expect("").is_empty());
+    assert_eq!(0, set.rem(&wrap_db, key, &[b"a"]).expect(""));
+
+    assert_eq!(3, set.add(&wrap_db, key, &[b"c", b"a", b"b", b"a"]).expect(""));
+    assert_eq!(1, set.add(&wrap_db, key, &[b"a", b"d"]).expect(""));
+    assert_eq!(4, set.len(&wrap_db, key).expect(""));
+    assert_eq!(members(&["a", "b", "c", "d"]), set.members(&wrap_db, key).expect(""));
+    assert!(set.is_member(&wrap_db, key, b"b").expect(""));
+    assert_eq!(vec![true, false], set.mis_member(&wrap_db, key, &[b"a", b"x"]).expect(""));
+    assert_eq!(1, set.rem(&wrap_db, key, &[b"d", b"x"]).expect(""));
+    assert_eq!(3, set.len(&wrap_db, key).expect(""));
+
+    //随机成员
+    let all = members(&["a", "b", "c"]);
+    let re = set.rand_member(&wrap_db, key, 2).expect("");
+    assert_eq!(2, re.len());
+    assert!(re[0] != re[1] && re.iter().all(|m| all.contains(m)));
+    assert_eq!(3, set.rand_member(&wrap_db, key, 10).expect("").len());
+    let re = set.rand_member(&wrap_db, key, -10).expect("");
+    assert_eq!(10, re.len());
+    assert!(re.iter().all(|m| all.contains(m)));
+    assert!(set.rand_member(&wrap_db, key, i64::MIN).is_err());
+    assert_eq!(3, set.len(&wrap_db, key).expect(""));
+
+    let popped = set.pop(&wrap_db, key, 2).expect("");
+    assert_eq!(2, popped.len());
+    assert_eq!(1, set.len(&wrap_db, key).expect(""));
+    let mut rest = set.members(&wrap_db, key).expect("");
+    rest.extend(popped);
+    rest.sort();
+    assert_eq!(all, rest);
+
+    //最后一个成员删除后，与redis一样key也不存在了
+    let key_space = RedisRocksdb::key_space();
+    let removed = |key: &[u8]| {
+        assert_eq!(0, key_space.exists(&wrap_db, &[key]).expect(""));
+        assert_eq!(None, key_space.key_type(&wrap_db, key).expect(""));
+    };
+    assert_eq!(1, set.pop(&wrap_db, key, 5).expect("").len());
+    removed(key);
+    set.add(&wrap_db, key, &[b"a"]).expect("");
+    assert_eq!(1, set.rem(&wrap_db, key, &[b"a"]).expect(""));
+    removed(key);
+    set.add(&wrap_db, key, &[b"a"]).expect("");
+    let dst = b"d".as_slice();
+    assert!(set.move_member(&wrap_db, key, dst, b"a").expect(""));
+    removed(key);
+    assert_eq!(Some(KeyType::Set), key_space.key_type(&wrap_db, dst).expect(""));
+    assert!(set.move_member(&wrap_db, dst, key, b"a").expect(""));
+    removed(dst);
+    assert_eq!(0, set.add(&wrap_db, dst, &[]).expect(""));
+    removed(dst);
+
+    //类型不对
+    let list_key = b"l".to_vec();
+    redis_db.list_push_back(&list_key, &b"a".to_vec()).expect("");
+    assert!(set.add(&wrap_db, &list_key, &[b"a"]).is_err());
+    assert!(set.len(&wrap_db, &list_key).is_err());
+    assert!(set.inter(&wrap_db, &[key, &list_key]).is_err());
+}
+
+#[named]
+#[test]
+fn test_set_operations() {
+    let redis_db = RedisRocksdb::new(open_transaction_db(file!(), function_name!()));
+    let wrap_db = WrapTransactionDB::new(redis_db.get_db());
+    let set = RedisRocksdb::set();
+    //先清除数据，以便测试可以反复运行
+    let keys: [&[u8]; 4] = [b"s1", b"s2", b"s3", b"d"];
+    RedisRocksdb::key_space().dels(&wrap_db, &keys).expect("");
+    let (s1, s2, s3, dst) = (keys[0], keys[1], keys[2], keys[3]);
+
+    set.add(&wrap_db, s1, &[b"a", b"b", b"c", b"d"]).expect("");
+    set.add(&wrap_db, s2, &[b"c"]).expect("");
+    set.add(&wrap_db, s3, &[b"a", b"c", b"e"]).expect("");
+
+    assert_eq!(members(&["c"]), set.inter(&wrap_db, &[s1, s2, s3]).expect(""));
+    assert!(set.inter(&wrap_db, &[s1, b"none"]).expect("").is_empty());
+    assert_eq!(members(&["a", "b", "c", "d", "e"]), set.union(&wrap_db, &[s1, s2, s3, b"none"]).expect(""));
+    assert_eq!(members(&["b", "d"]), set.diff(&wrap_db, &[s1, s2, s3]).expect(""));
+    assert!(set.diff(&wrap_db, &[b"none", s1]).expect("").is_empty());
+
+    assert_eq!(2, set.inter_store(&wrap_db, dst, &[s1, s3]).expect(""));
+    assert_eq!(members(&["a", "c"]), set.members(&wrap_db, dst).expect(""));
+    //dst也可以参与运算
+    assert_eq!(1, set.diff_store(&wrap_db, dst, &[s3, s2, dst, b"none"]).expect(""));
+    assert_eq!(members(&["e"]), set.members(&wrap_db, dst).expect(""));
+    assert_eq!(5, set.union_store(&wrap_db, dst, &[dst, s1]).expect(""));
+    assert_eq!(5, set.len(&wrap_db, dst).expect(""));
+    //结果为空时删除dst
+    assert_eq!(0, set.inter_store(&wrap_db, dst, &[s2, b"none"]).expect(""));
+    assert_eq!(0, RedisRocksdb::key_space().exists(&wrap_db, &[dst]).expect(""));
+
+    //smove
+    assert!(set.move_member(&wrap_db, s1, s2, b"a").expect(""));
+    assert!(!set.move_member(&wrap_db, s1, s2, b"a").expect(""));
+    assert_eq!(3, set.len(&wrap_db, s1).expect(""));
+    assert_eq!(members(&["a", "c"]), set.members(&wrap_db, s2).expect(""));
+
+    //sscan
+    let opts = ScanOptions {
+        count: 2,
+        ..Default::default()
+    };
+    let mut cursor = ScanCursor::start();
+    let mut scanned = Vec::new();
+    loop {
+        let batch = set.scan(&wrap_db, s1, &cursor, &opts).expect("");
+        scanned.extend(batch.items);
+        cursor = batch.cursor;
+        if cursor.is_finished() {
+            break;
+        }
+    }
+    assert_eq!(members(&["b", "c", "d"]), scanned);
+}
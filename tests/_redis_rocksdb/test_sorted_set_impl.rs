@@ -0,0 +1,161 @@
+use function_name::named;
+use redis_rocksdb::{LexBound, RedisRocksdb, ScoreBound, SortedSet, WrapDb, WrapRocksDb, WrapTransaction, WrapTransactionDB, ZAddOptions, ZCompare, ZExist};
+
+use crate::_redis_rocksdb::kits::{open_rocks_db, open_transaction_db};
+
+#[named]
+#[test]
+fn test_sorted_set() {
+    {
+        let redis_db = RedisRocksdb::new(open_transaction_db(file!(), function_name!()));
+        let wrap_db = WrapTransactionDB::new(redis_db.get_db());
+        tt_sorted_set(&wrap_db, RedisRocksdb::sorted_set());
+
+        let trans = redis_db.get_db().transaction();
+        let wrap_trans = WrapTransaction::new(&trans);
+        tt_sorted_set(&wrap_trans, RedisRocksdb::sorted_set());
+        let _ = trans.commit();
+    }
+    {
+        let rocks_db = open_rocks_db(file!(), function_name!());
+        let wrap_rocks_db = WrapRocksDb { db: &rocks_db };
+        tt_sorted_set(&wrap_rocks_db, RedisRocksdb::sorted_set());
+    }
+}
+
+fn tt_sorted_set<T: WrapDb>(wrap_db: &T, zset: impl SortedSet<T>) {
+    let key = vec![0 as u8, 1, 2];
+    let _ = zset.del_key(wrap_db, &key); //删除所有内容，以便多次测试
+    let opts = ZAddOptions::default();
+    {
+        //测试没有数据的情况
+        assert_eq!(None, zset.len(wrap_db, &key).expect(""));
+        assert_eq!(None, zset.score(wrap_db, &key, b"a").expect(""));
+        assert_eq!(None, zset.rank(wrap_db, &key, b"a").expect(""));
+        assert!(zset.range(wrap_db, &key, 0, -1, false).expect("").is_empty());
+        assert!(zset.pop_min(wrap_db, &key, 1).expect("").is_empty());
+        assert_eq!(0, zset.rem(wrap_db, &key, &[b"a".as_slice()]).expect(""));
+    }
+    {
+        let members: Vec<(f64, &[u8])> = vec![(1.0, b"a"), (-2.5, b"b"), (3.0, b"c"), (1.0, b"d")];
+        assert_eq!(4, zset.add(wrap_db, &key, &members, &opts).expect(""));
+        assert_eq!(Some(4), zset.len(wrap_db, &key).expect(""));
+        assert_eq!(Some(-2.5), zset.score(wrap_db, &key, b"b").expect(""));
+
+        let all = zset.range(wrap_db, &key, 0, -1, false).expect("");
+        let expected: Vec<(Vec<u8>, f64)> = vec![(b"b".to_vec(), -2.5), (b"a".to_vec(), 1.0), (b"d".to_vec(), 1.0), (b"c".to_vec(), 3.0)];
+        assert_eq!(expected, all);
+        let rev = zset.range(wrap_db, &key, 0, 1, true).expect("");
+        assert_eq!(vec![(b"c".to_vec(), 3.0), (b"d".to_vec(), 1.0)], rev);
+
+        assert_eq!(Some(0), zset.rank(wrap_db, &key, b"b").expect(""));
+        assert_eq!(Some(2), zset.rank(wrap_db, &key, b"d").expect(""));
+        assert_eq!(Some(0), zset.rev_rank(wrap_db, &key, b"c").expect(""));
+    }
+    {
+        //NX XX GT LT CH
+        let nx = ZAddOptions {
+            exist: Some(ZExist::Nx),
+            ..Default::default()
+        };
+        assert_eq!(0, zset.add(wrap_db, &key, &[(10.0, b"a".as_slice())], &nx).expect(""));
+        assert_eq!(Some(1.0), zset.score(wrap_db, &key, b"a").expect(""));
+
+        let xx = ZAddOptions {
+            exist: Some(ZExist::Xx),
+            ..Default::default()
+        };
+        assert_eq!(0, zset.add(wrap_db, &key, &[(10.0, b"x".as_slice())], &xx).expect(""));
+        assert_eq!(None, zset.score(wrap_db, &key, b"x").expect(""));
+
+        let gt_ch = ZAddOptions {
+            compare: Some(ZCompare::Gt),
+            ch: true,
+            ..Default::default()
+        };
+        assert_eq!(0, zset.add(wrap_db, &key, &[(0.5, b"a".as_slice())], &gt_ch).expect(""));
+        assert_eq!(1, zset.add(wrap_db, &key, &[(2.0, b"a".as_slice())], &gt_ch).expect(""));
+        assert_eq!(Some(2.0), zset.score(wrap_db, &key, b"a").expect(""));
+
+        let lt = ZAddOptions {
+            compare: Some(ZCompare::Lt),
+            ..Default::default()
+        };
+        assert_eq!(None, zset.add_incr(wrap_db, &key, 1.0, b"a", &lt).expect(""));
+        assert_eq!(Some(1.5), zset.add_incr(wrap_db, &key, -0.5, b"a", &lt).expect(""));
+
+        let nx_gt = ZAddOptions {
+            exist: Some(ZExist::Nx),
+            compare: Some(ZCompare::Gt),
+            ch: false,
+        };
+        assert!(zset.add(wrap_db, &key, &[(1.0, b"a".as_slice())], &nx_gt).is_err());
+
+        assert_eq!(4.0, zset.incr_by(wrap_db, &key, 2.5, b"a").expect(""));
+        assert_eq!(5.0, zset.incr_by(wrap_db, &key, 5.0, b"e").expect(""));
+        assert_eq!(Some(5), zset.len(wrap_db, &key).expect(""));
+        // b:-2.5 d:1 c:3 a:4 e:5
+    }
+    {
+        //by score
+        let re = zset
+            .range_by_score(wrap_db, &key, &ScoreBound::Inclusive(1.0), &ScoreBound::Exclusive(4.0), false, None)
+            .expect("");
+        assert_eq!(vec![(b"d".to_vec(), 1.0), (b"c".to_vec(), 3.0)], re);
+        let re = zset
+            .range_by_score(wrap_db, &key, &ScoreBound::min(), &ScoreBound::max(), true, Some((1, 2)))
+            .expect("");
+        assert_eq!(vec![(b"a".to_vec(), 4.0), (b"c".to_vec(), 3.0)], re);
+        let re = zset
+            .range_by_score(wrap_db, &key, &ScoreBound::Exclusive(3.0), &ScoreBound::Inclusive(4.0), true, None)
+            .expect("");
+        assert_eq!(vec![(b"a".to_vec(), 4.0)], re);
+        assert_eq!(5, zset.count(wrap_db, &key, &ScoreBound::min(), &ScoreBound::max()).expect(""));
+        assert_eq!(
+            2,
+            zset.count(wrap_db, &key, &ScoreBound::Exclusive(1.0), &ScoreBound::Inclusive(4.0)).expect("")
+        );
+    }
+    {
+        let re = zset.pop_min(wrap_db, &key, 1).expect("");
+        assert_eq!(vec![(b"b".to_vec(), -2.5)], re);
+        let re = zset.pop_max(wrap_db, &key, 2).expect("");
+        assert_eq!(vec![(b"e".to_vec(), 5.0), (b"a".to_vec(), 4.0)], re);
+        assert_eq!(Some(2), zset.len(wrap_db, &key).expect(""));
+
+        assert_eq!(1, zset.rem(wrap_db, &key, &[b"c".as_slice(), b"x".as_slice()]).expect(""));
+        assert_eq!(1, zset.rem_range_by_rank(wrap_db, &key, 0, -1).expect(""));
+        assert_eq!(None, zset.len(wrap_db, &key).expect(""));
+    }
+    {
+        //by lex, score都相同
+        let members: Vec<(f64, &[u8])> = vec![(0.0, b"a"), (0.0, b"b"), (0.0, b"c"), (0.0, b"d")];
+        assert_eq!(4, zset.add(wrap_db, &key, &members, &opts).expect(""));
+        let re = zset
+            .range_by_lex(
+                wrap_db,
+                &key,
+                &LexBound::Exclusive(b"a".to_vec()),
+                &LexBound::Inclusive(b"c".to_vec()),
+                false,
+                None,
+            )
+            .expect("");
+        assert_eq!(vec![b"b".to_vec(), b"c".to_vec()], re);
+        let re = zset.range_by_lex(wrap_db, &key, &LexBound::Min, &LexBound::Max, true, Some((0, 3))).expect("");
+        assert_eq!(vec![b"d".to_vec(), b"c".to_vec(), b"b".to_vec()], re);
+
+        assert_eq!(
+            2,
+            zset.rem_range_by_lex(wrap_db, &key, &LexBound::Min, &LexBound::Exclusive(b"c".to_vec()))
+                .expect("")
+        );
+        assert_eq!(
+            2,
+            zset.rem_range_by_score(wrap_db, &key, &ScoreBound::min(), &ScoreBound::Inclusive(0.0))
+                .expect("")
+        );
+        assert_eq!(None, zset.len(wrap_db, &key).expect(""));
+    }
+    let _ = zset.del_key(wrap_db, &key);
+}
@@ -0,0 +1,85 @@
+use function_name::named;
+use redis_rocksdb::{RedisRocksdb, Stack};
+
+use crate::_redis_rocksdb::kits::open_transaction_db;
+
+#[named]
+#[test]
+fn test_stack_push_pop() {
+    let db = open_transaction_db(file!(), function_name!());
+    let redis_db = RedisRocksdb::new(db);
+    let key = function_name!().as_bytes();
+    let _ = redis_db.clear(&key); //先清除数据，以便测试可以反复运行
+    assert_eq!(-1, redis_db.len(&key).expect(""));
+    assert_eq!(-1, redis_db.push_exists(&key, &vec![1u8]).expect(""));
+    assert_eq!(None, redis_db.pop(&key).expect(""));
+
+    let value = vec![1, 23, 6];
+    assert_eq!(1, redis_db.push(&key, &value).expect(""));
+    let value2 = vec![2, 9];
+    assert_eq!(2, redis_db.push_exists(&key, &value2).expect(""));
+    assert_eq!(2, redis_db.len(&key).expect(""));
+    assert_eq!(value, redis_db.index(&key, 0).expect(""));
+    assert_eq!(value2, redis_db.index(&key, -1).expect(""));
+    assert!(redis_db.index(&key, 2).is_err());
+    assert!(redis_db.index(&key, -3).is_err());
+
+    assert_eq!(Some(value2), redis_db.pop(&key).expect(""));
+    assert_eq!(Some(value), redis_db.pop(&key).expect(""));
+    assert_eq!(None, redis_db.pop(&key).expect(""));
+    assert_eq!(0, redis_db.len(&key).expect(""));
+
+    assert_eq!(0, redis_db.clear(&key).expect(""));
+    assert_eq!(-1, redis_db.clear(&key).expect(""));
+}
+
+#[named]
+#[test]
+fn test_stack_pushs_pops() {
+    let db = open_transaction_db(file!(), function_name!());
+    let redis_db = RedisRocksdb::new(db);
+    let key = function_name!().as_bytes();
+    let _ = redis_db.clear(&key); //先清除数据，以便测试可以反复运行
+    let values = vec![vec![1u8], vec![2u8], vec![3u8]];
+    let refs: Vec<&Vec<u8>> = values.iter().collect();
+    assert_eq!(3, redis_db.pushs(&key, &refs).expect(""));
+    assert_eq!(values, redis_db.range(&key, 0, -1).expect(""));
+    assert_eq!(values, redis_db.range(&key, -100, 100).expect(""));
+    assert_eq!(vec![vec![2u8]], redis_db.range(&key, 1, 1).expect(""));
+    assert!(redis_db.range(&key, 2, 1).expect("").is_empty());
+    assert!(redis_db.range(&key, 3, 10).expect("").is_empty());
+
+    assert_eq!(vec![3u8], redis_db.set(&key, -1, &vec![4u8]).expect(""));
+    assert_eq!(vec![4u8], redis_db.index(&key, 2).expect(""));
+    assert!(redis_db.set(&key, 3, &vec![5u8]).is_err());
+
+    assert_eq!(vec![vec![4u8], vec![2u8]], redis_db.pops(&key, 2).expect(""));
+    assert_eq!(vec![vec![1u8]], redis_db.pops(&key, 10).expect(""));
+    assert!(redis_db.pops(&key, 1).expect("").is_empty());
+    assert_eq!(0, redis_db.len(&key).expect(""));
+}
+
+#[named]
+#[test]
+fn test_stack_poplpush() {
+    let db = open_transaction_db(file!(), function_name!());
+    let redis_db = RedisRocksdb::new(db);
+    let key = function_name!().as_bytes();
+    let dstkey = format!("{}_dst", function_name!());
+    let dstkey = dstkey.as_bytes();
+    let _ = redis_db.clear(&key); //先清除数据，以便测试可以反复运行
+    let _ = redis_db.clear(&dstkey);
+
+    assert_eq!(None, redis_db.poplpush(&key, &dstkey).expect(""));
+    redis_db.push(&key, &vec![1u8]).expect("");
+    redis_db.push(&key, &vec![2u8]).expect("");
+    redis_db.push(&dstkey, &vec![9u8]).expect("");
+
+    assert_eq!(Some(vec![2u8]), redis_db.poplpush(&key, &dstkey).expect(""));
+    assert_eq!(1, redis_db.len(&key).expect(""));
+    assert_eq!(vec![vec![9u8], vec![2u8]], redis_db.range(&dstkey, 0, -1).expect(""));
+
+    //源与目标相同时，元素的顺序不变
+    assert_eq!(Some(vec![1u8]), redis_db.poplpush(&key, &key).expect(""));
+    assert_eq!(vec![vec![1u8]], redis_db.range(&key, 0, -1).expect(""));
+}
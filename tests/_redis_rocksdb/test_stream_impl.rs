@@ -0,0 +1,252 @@
+use std::{sync::Arc, thread, time::Duration};
+
+use function_name::named;
+use redis_rocksdb::{
+    AutoClaimOptions, ClaimOptions, KeySpace, KeyType, KeyValue, PendingFilter, ReadGroupOptions, RedisRocksdb, Stream, StreamAddOptions, StreamId, StreamTrim,
+    WrapTransactionDB, XAddId,
+};
+
+use crate::_redis_rocksdb::kits::open_transaction_db;
+
+fn id(ms: u64, seq: u64) -> StreamId {
+    StreamId::new(ms, seq)
+}
+
+#[named]
+#[test]
+fn test_stream() {
+    let redis_db = RedisRocksdb::new(open_transaction_db(file!(), function_name!()));
+    let wrap_db = WrapTransactionDB::new(redis_db.get_db());
+    let stream = RedisRocksdb::stream();
+    //先清除数据，以便测试可以反复运行
+    let keys: [&[u8]; 2] = [b"x", b"y"];
+    RedisRocksdb::key_space().dels(&wrap_db, &keys).expect("");
+    let key = keys[0];
+    let opts = StreamAddOptions::default();
+    let fields: [(&[u8], &[u8]); 2] = [(b"f1", b"v1"), (b"f2", b"v2")];
+
+    assert_eq!(0, stream.len(&wrap_db, key).expect(""));
+    assert_eq!(None, stream.last_id(&wrap_db, key).expect(""));
+    //NOMKSTREAM时不创建key
+    let no_mk = StreamAddOptions {
+        no_mk_stream: true,
+        trim: None,
+    };
+    assert_eq!(None, stream.add(&wrap_db, key, &XAddId::Auto, &fields, &no_mk).expect(""));
+    assert_eq!(None, RedisRocksdb::key_space().key_type(&wrap_db, key).expect(""));
+
+    assert_eq!(
+        Some(id(1, 0)),
+        stream.add(&wrap_db, key, &XAddId::Explicit(id(1, 0)), &fields, &opts).expect("")
+    );
+    assert_eq!(Some(id(1, 1)), stream.add(&wrap_db, key, &XAddId::AutoSeq(1), &fields[..1], &opts).expect(""));
+    assert_eq!(Some(id(3, 0)), stream.add(&wrap_db, key, &XAddId::AutoSeq(3), &fields, &opts).expect(""));
+    //id必须大于最后的id，0-0也不可以
+    assert!(stream.add(&wrap_db, key, &XAddId::Explicit(id(3, 0)), &fields, &opts).is_err());
+    assert!(stream.add(&wrap_db, key, &XAddId::AutoSeq(2), &fields, &opts).is_err());
+    assert!(stream.add(&wrap_db, b"y", &XAddId::Explicit(StreamId::MIN), &fields, &opts).is_err());
+    let auto = stream.add(&wrap_db, key, &XAddId::Auto, &fields, &opts).expect("").expect("");
+    assert!(auto > id(3, 0));
+    assert_eq!(4, stream.len(&wrap_db, key).expect(""));
+    assert_eq!(Some(auto), stream.last_id(&wrap_db, key).expect(""));
+    assert_eq!(Some(KeyType::Stream), RedisRocksdb::key_space().key_type(&wrap_db, key).expect(""));
+
+    //range
+    let all = stream.range(&wrap_db, key, &StreamId::MIN, &StreamId::MAX, None).expect("");
+    assert_eq!(vec![id(1, 0), id(1, 1), id(3, 0), auto], all.iter().map(|e| e.id).collect::<Vec<_>>());
+    assert_eq!(vec![(b"f1".to_vec(), b"v1".to_vec())], all[1].fields);
+    assert_eq!(2, all[0].fields.len());
+    let entries = stream.range(&wrap_db, key, &id(1, 1), &id(3, u64::MAX), None).expect("");
+    assert_eq!(vec![id(1, 1), id(3, 0)], entries.iter().map(|e| e.id).collect::<Vec<_>>());
+    let entries = stream.rev_range(&wrap_db, key, &StreamId::MAX, &StreamId::MIN, Some(2)).expect("");
+    assert_eq!(vec![auto, id(3, 0)], entries.iter().map(|e| e.id).collect::<Vec<_>>());
+    assert!(stream.range(&wrap_db, key, &id(5, 0), &id(4, 0), None).expect("").is_empty());
+
+    //del，删除后last_id不变
+    assert_eq!(1, stream.del(&wrap_db, key, &[id(1, 1), id(2, 0)]).expect(""));
+    assert_eq!(3, stream.len(&wrap_db, key).expect(""));
+    assert_eq!(Some(auto), stream.last_id(&wrap_db, key).expect(""));
+
+    //trim
+    assert_eq!(1, stream.trim(&wrap_db, key, &StreamTrim::MinId(id(2, 0))).expect(""));
+    assert_eq!(1, stream.trim(&wrap_db, key, &StreamTrim::MaxLen(1)).expect(""));
+    let entries = stream.range(&wrap_db, key, &StreamId::MIN, &StreamId::MAX, None).expect("");
+    assert_eq!(vec![auto], entries.iter().map(|e| e.id).collect::<Vec<_>>());
+    let max_len = StreamAddOptions {
+        no_mk_stream: false,
+        trim: Some(StreamTrim::MaxLen(1)),
+    };
+    let last = stream.add(&wrap_db, key, &XAddId::Auto, &fields, &max_len).expect("").expect("");
+    assert_eq!(1, stream.len(&wrap_db, key).expect(""));
+
+    //read
+    let re = stream.read(&wrap_db, &[(key, StreamId::MIN), (b"y", StreamId::MIN)], None).expect("");
+    assert_eq!(1, re.len());
+    assert_eq!(key, re[0].0.as_slice());
+    assert_eq!(last, re[0].1[0].id);
+    assert!(stream.read(&wrap_db, &[(key, last)], None).expect("").is_empty());
+
+    RedisRocksdb::key_space().dels(&wrap_db, &[key]).expect("");
+    assert_eq!(0, stream.len(&wrap_db, key).expect(""));
+    //其它的类型
+    redis_db.put(&b"y".to_vec(), &b"v".to_vec()).expect("");
+    assert!(stream.len(&wrap_db, b"y").is_err());
+    assert!(stream.add(&wrap_db, b"y", &XAddId::Auto, &fields, &opts).is_err());
+}
+
+#[named]
+#[test]
+fn test_stream_group() {
+    let redis_db = RedisRocksdb::new(open_transaction_db(file!(), function_name!()));
+    let wrap_db = WrapTransactionDB::new(redis_db.get_db());
+    let stream = RedisRocksdb::stream();
+    //先清除数据，以便测试可以反复运行
+    let keys: [&[u8]; 2] = [b"x", b"none"];
+    RedisRocksdb::key_space().dels(&wrap_db, &keys).expect("");
+    let key = keys[0];
+    let (group, c1, c2): (&[u8], &[u8], &[u8]) = (b"g", b"c1", b"c2");
+    let fields: [(&[u8], &[u8]); 1] = [(b"f", b"v")];
+    let read_opts = ReadGroupOptions::default();
+
+    //key不存在
+    assert!(stream.group_create(&wrap_db, b"none", group, &StreamId::MIN, false).is_err());
+    stream.group_create(&wrap_db, key, group, &StreamId::MIN, true).expect("");
+    assert!(stream.group_create(&wrap_db, key, group, &StreamId::MIN, false).is_err());
+    for i in 1..=4 {
+        stream
+            .add(&wrap_db, key, &XAddId::Explicit(id(i, 0)), &fields, &StreamAddOptions::default())
+            .expect("");
+    }
+
+    //> 投递新的项
+    let opts = ReadGroupOptions { count: Some(2), no_ack: false };
+    let re = stream.read_group(&wrap_db, group, c1, &[(key, None)], &opts).expect("");
+    assert_eq!(vec![id(1, 0), id(2, 0)], re[0].1.iter().map(|e| e.id).collect::<Vec<_>>());
+    let re = stream.read_group(&wrap_db, group, c2, &[(key, None)], &read_opts).expect("");
+    assert_eq!(vec![id(3, 0), id(4, 0)], re[0].1.iter().map(|e| e.id).collect::<Vec<_>>());
+    assert!(stream.read_group(&wrap_db, group, c2, &[(key, None)], &read_opts).expect("").is_empty());
+    assert!(stream.read_group(&wrap_db, b"nogroup", c1, &[(key, None)], &read_opts).is_err());
+    //历史的pending项
+    let re = stream.read_group(&wrap_db, group, c1, &[(key, Some(StreamId::MIN))], &read_opts).expect("");
+    assert_eq!(vec![id(1, 0), id(2, 0)], re[0].1.iter().map(|e| e.id).collect::<Vec<_>>());
+
+    //pending
+    let summary = stream.pending(&wrap_db, key, group).expect("");
+    assert_eq!(4, summary.count);
+    assert_eq!(Some(id(1, 0)), summary.min);
+    assert_eq!(Some(id(4, 0)), summary.max);
+    assert_eq!(vec![(c1.to_vec(), 2), (c2.to_vec(), 2)], summary.consumers);
+    let filter = PendingFilter {
+        start: StreamId::MIN,
+        end: StreamId::MAX,
+        count: 10,
+        consumer: Some(c1.to_vec()),
+        min_idle: None,
+    };
+    let pending = stream.pending_range(&wrap_db, key, group, &filter).expect("");
+    assert_eq!(vec![id(1, 0), id(2, 0)], pending.iter().map(|p| p.id).collect::<Vec<_>>());
+    //读了两次
+    assert_eq!(2, pending[0].delivery_count);
+
+    //ack
+    assert_eq!(1, stream.ack(&wrap_db, key, group, &[id(1, 0), id(9, 0)]).expect(""));
+    assert_eq!(0, stream.ack(&wrap_db, key, group, &[id(1, 0)]).expect(""));
+    assert_eq!(3, stream.pending(&wrap_db, key, group).expect("").count);
+
+    //claim，idle还不够时不认领
+    let claim = ClaimOptions {
+        min_idle: 1000 * 1000,
+        ..Default::default()
+    };
+    assert!(stream.claim(&wrap_db, key, group, c1, &[id(3, 0)], &claim).expect("").is_empty());
+    let claimed = stream.claim(&wrap_db, key, group, c1, &[id(3, 0)], &ClaimOptions::default()).expect("");
+    assert_eq!(vec![id(3, 0)], claimed.iter().map(|e| e.id).collect::<Vec<_>>());
+    let filter = PendingFilter { consumer: None, ..filter };
+    let pending = stream.pending_range(&wrap_db, key, group, &filter).expect("");
+    assert_eq!(c1, pending[1].consumer.as_slice());
+    //被删除的项在认领时从pending entries list中删除
+    stream.del(&wrap_db, key, &[id(4, 0)]).expect("");
+    let claimed = stream
+        .auto_claim(&wrap_db, key, group, c2, &StreamId::MIN, &AutoClaimOptions::default())
+        .expect("");
+    assert_eq!(StreamId::MIN, claimed.next);
+    assert_eq!(vec![id(2, 0), id(3, 0)], claimed.entries.iter().map(|e| e.id).collect::<Vec<_>>());
+    assert_eq!(vec![id(4, 0)], claimed.deleted);
+    assert_eq!(2, stream.pending(&wrap_db, key, group).expect("").count);
+
+    //消费者
+    assert!(stream.group_create_consumer(&wrap_db, key, group, b"c3").expect(""));
+    assert!(!stream.group_create_consumer(&wrap_db, key, group, b"c3").expect(""));
+    assert_eq!(2, stream.group_del_consumer(&wrap_db, key, group, c2).expect(""));
+    assert_eq!(0, stream.pending(&wrap_db, key, group).expect("").count);
+
+    //setid之后重新投递
+    stream.group_set_id(&wrap_db, key, group, &StreamId::MIN).expect("");
+    let re = stream
+        .read_group(&wrap_db, group, c1, &[(key, None)], &ReadGroupOptions { count: None, no_ack: true })
+        .expect("");
+    assert_eq!(3, re[0].1.len());
+    assert_eq!(0, stream.pending(&wrap_db, key, group).expect("").count);
+
+    assert!(stream.group_destroy(&wrap_db, key, group).expect(""));
+    assert!(!stream.group_destroy(&wrap_db, key, group).expect(""));
+}
+
+#[named]
+#[test]
+fn test_stream_block() {
+    let redis_db = Arc::new(RedisRocksdb::new(open_transaction_db(file!(), function_name!())));
+    let wrap_db = WrapTransactionDB::new(redis_db.get_db());
+    let stream = RedisRocksdb::stream();
+    //先清除数据，以便测试可以反复运行
+    let keys: [&[u8]; 1] = [b"x"];
+    RedisRocksdb::key_space().dels(&wrap_db, &keys).expect("");
+    let key = keys[0];
+
+    //超时
+    let re = redis_db
+        .stream_block(&[key], 50, |t| {
+            Ok(Some(stream.read(t, &[(key, StreamId::MIN)], None)?).filter(|r| !r.is_empty()))
+        })
+        .expect("");
+    assert_eq!(None, re);
+
+    let handle = {
+        let redis_db = redis_db.clone();
+        thread::spawn(move || {
+            redis_db
+                .stream_block(&[b"x"], 0, |t| {
+                    let re = RedisRocksdb::stream().read(t, &[(b"x", StreamId::MIN)], None)?;
+                    Ok(Some(re).filter(|r| !r.is_empty()))
+                })
+                .expect("")
+        })
+    };
+    thread::sleep(Duration::from_millis(100));
+    let tx = redis_db.transaction();
+    let added = tx.stream_add(key, &XAddId::Auto, &[(b"f", b"v")], &StreamAddOptions::default()).expect("");
+    assert!(tx.exec().expect(""));
+    let re = handle.join().expect("").expect("");
+    assert_eq!(added, Some(re[0].1[0].id));
+
+    //不在事务中的stream_add也唤醒等待者，timeout超出时间的范围时一直等待
+    let last = added.expect("");
+    let handle = {
+        let redis_db = redis_db.clone();
+        thread::spawn(move || {
+            redis_db
+                .stream_block(&[b"x"], i64::MAX, |t| {
+                    let re = RedisRocksdb::stream().read(t, &[(b"x", last)], None)?;
+                    Ok(Some(re).filter(|r| !r.is_empty()))
+                })
+                .expect("")
+        })
+    };
+    thread::sleep(Duration::from_millis(100));
+    let added = redis_db
+        .stream_add(key, &XAddId::Auto, &[(b"f", b"v2")], &StreamAddOptions::default())
+        .expect("");
+    let re = handle.join().expect("").expect("");
+    assert_eq!(added, Some(re[0].1[0].id));
+    assert_eq!(2, stream.len(&wrap_db, key).expect(""));
+}